@@ -0,0 +1,57 @@
+//! Parse and serialize throughput
+//!
+//! Run with `cargo bench`; compare against a saved baseline with
+//! `cargo bench -- --save-baseline main` and `cargo bench -- --baseline main`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+/// A `table.users` block with `rows` rows mixing every value type
+fn table(rows: usize) -> String {
+    let mut text =
+        String::from("table.users\nid:int name:string email active:bool score:float team\n");
+    for i in 0..rows {
+        text.push_str(&format!(
+            "{} \"User {}\" user{}@example.com {} {}.5 :team:{}\n",
+            i,
+            i,
+            i,
+            i % 2 == 0,
+            i % 100,
+            i % 7
+        ));
+    }
+    text
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for rows in [100, 10_000] {
+        let text = table(rows);
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        group.bench_function(format!("table/{}", rows), |b| {
+            b.iter(|| ison_rs::parse(black_box(&text)).unwrap())
+        });
+    }
+
+    let isonl = ison_rs::ison_to_isonl(&table(10_000)).unwrap();
+    group.throughput(Throughput::Bytes(isonl.len() as u64));
+    group.bench_function("isonl/10000", |b| {
+        b.iter(|| ison_rs::parse_isonl(black_box(&isonl)).unwrap())
+    });
+    group.finish();
+}
+
+fn bench_serialize(c: &mut Criterion) {
+    let doc = ison_rs::parse(&table(10_000)).unwrap();
+    let mut group = c.benchmark_group("serialize");
+    group.bench_function("dumps/10000", |b| {
+        b.iter(|| ison_rs::dumps(black_box(&doc), false))
+    });
+    group.bench_function("dumps_aligned/10000", |b| {
+        b.iter(|| ison_rs::dumps(black_box(&doc), true))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse, bench_serialize);
+criterion_main!(benches);
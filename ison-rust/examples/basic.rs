@@ -1,6 +1,6 @@
 //! Basic example of using the ISON parser
 
-use ison_rs::{parse, dumps, dumps_isonl, Block, Document, FieldInfo, Value};
+use ison_rs::{parse, dumps, dumps_isonl, Block, Document, FieldInfo, Row, Value};
 
 fn main() {
     println!("=== ISON Parser for Rust ===\n");
@@ -90,16 +90,16 @@ id type_ref namespace_ref simple_ref
         FieldInfo::with_type("price", "float"),
     ];
 
-    let mut row1 = std::collections::HashMap::new();
-    row1.insert("id".to_string(), Value::Int(1));
-    row1.insert("name".to_string(), Value::String("Widget".to_string()));
-    row1.insert("price".to_string(), Value::Float(29.99));
+    let mut row1 = Row::new();
+    row1.insert("id".into(), Value::Int(1));
+    row1.insert("name".into(), Value::String("Widget".to_string()));
+    row1.insert("price".into(), Value::Float(29.99));
     block.rows.push(row1);
 
-    let mut row2 = std::collections::HashMap::new();
-    row2.insert("id".to_string(), Value::Int(2));
-    row2.insert("name".to_string(), Value::String("Gadget".to_string()));
-    row2.insert("price".to_string(), Value::Float(49.99));
+    let mut row2 = Row::new();
+    row2.insert("id".into(), Value::Int(2));
+    row2.insert("name".into(), Value::String("Gadget".to_string()));
+    row2.insert("price".into(), Value::Float(49.99));
     block.rows.push(row2);
 
     new_doc.blocks.push(block);
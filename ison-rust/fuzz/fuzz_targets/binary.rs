@@ -0,0 +1,10 @@
+//! ISONB, MessagePack, and CBOR decoders
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = ison_rs::from_isonb(data);
+    let _ = ison_rs::Document::from_msgpack(data);
+    let _ = ison_rs::Document::from_cbor(data);
+});
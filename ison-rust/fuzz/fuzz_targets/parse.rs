@@ -0,0 +1,15 @@
+//! ISON and ISONL text, plus selectors and re-serialization of whatever parses
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|text: &str| {
+    if let Ok(doc) = ison_rs::parse(text) {
+        let _ = ison_rs::dumps(&doc, true);
+        let _ = ison_rs::dumps_isonl(&doc);
+        let _ = doc.to_markdown();
+        // Reuse the first line as a selector so path parsing is covered too
+        let _ = doc.select(text.lines().next().unwrap_or(""));
+    }
+    let _ = ison_rs::parse_isonl(text);
+});
@@ -0,0 +1,9 @@
+//! CSV and markdown table import
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|text: &str| {
+    let _ = ison_rs::from_csv(text, "t");
+    let _ = ison_rs::from_markdown_table(text, "t");
+});
@@ -0,0 +1,109 @@
+#ifndef ISON_H
+#define ISON_H
+
+/* Generated by cbindgen from src/ffi.rs; do not edit by hand. */
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+// Type tag of an `IsonValue`
+typedef enum IsonValueType {
+  ISON_VALUE_TYPE_NULL = 0,
+  ISON_VALUE_TYPE_BOOL = 1,
+  ISON_VALUE_TYPE_INT = 2,
+  ISON_VALUE_TYPE_FLOAT = 3,
+  ISON_VALUE_TYPE_STRING = 4,
+  ISON_VALUE_TYPE_REFERENCE = 5,
+  // Exact decimal (`rust_decimal` builds); read it with `ison_value_to_string`
+  ISON_VALUE_TYPE_DECIMAL = 6,
+  // Binary data; read it with `ison_value_bytes`
+  ISON_VALUE_TYPE_BYTES = 7,
+} IsonValueType;
+
+// Opaque handle to a block borrowed from a document
+typedef struct IsonBlock IsonBlock;
+
+// Opaque handle to a parsed document
+typedef struct IsonDocument IsonDocument;
+
+// Opaque handle to a value borrowed from a document
+typedef struct IsonValue IsonValue;
+
+// Parse NUL-terminated UTF-8 ISON text.
+//
+// Returns null on failure and, if `error_out` is not null, stores an owned
+// error message there.
+struct IsonDocument *ison_parse(const char *text, char **error_out);
+
+// Serialize a document to ISON text
+char *ison_dumps(const struct IsonDocument *doc, bool align_columns);
+
+// Release a document from `ison_parse`; null is ignored
+void ison_free_document(struct IsonDocument *doc);
+
+// Release a string returned by this library; null is ignored
+void ison_free_string(char *s);
+
+// Library version, e.g. `"1.0.1"` (static, do not free)
+const char *ison_version(void);
+
+size_t ison_document_block_count(const struct IsonDocument *doc);
+
+// Block by position, or null
+const struct IsonBlock *ison_document_block(const struct IsonDocument *doc, size_t index);
+
+// Block by name, or null
+const struct IsonBlock *ison_document_get_block(const struct IsonDocument *doc, const char *name);
+
+char *ison_block_kind(const struct IsonBlock *block);
+
+char *ison_block_name(const struct IsonBlock *block);
+
+size_t ison_block_field_count(const struct IsonBlock *block);
+
+// Field name by position, or null
+char *ison_block_field_name(const struct IsonBlock *block, size_t index);
+
+// Number of data rows
+size_t ison_block_row_count(const struct IsonBlock *block);
+
+// Number of summary rows (after `---`)
+size_t ison_block_summary_row_count(const struct IsonBlock *block);
+
+// Value of `field` in data row `row`, or null when either is missing
+const struct IsonValue *ison_block_value(const struct IsonBlock *block,
+                                         size_t row,
+                                         const char *field);
+
+// Value of `field` in summary row `row`, or null when either is missing
+const struct IsonValue *ison_block_summary_value(const struct IsonBlock *block,
+                                                 size_t row,
+                                                 const char *field);
+
+// Type of a value; null pointers report `ISON_VALUE_TYPE_NULL`
+enum IsonValueType ison_value_type(const struct IsonValue *value);
+
+// Boolean value, false for other types
+bool ison_value_as_bool(const struct IsonValue *value);
+
+// Integer value, 0 for other types
+int64_t ison_value_as_int(const struct IsonValue *value);
+
+// Float value (integers widen), NaN for other types
+double ison_value_as_float(const struct IsonValue *value);
+
+// String contents for strings, ISON text (e.g. `:user:1`, `42`, `null`) otherwise
+char *ison_value_to_string(const struct IsonValue *value);
+
+// Reference id, or null for other types
+char *ison_value_reference_id(const struct IsonValue *value);
+
+// Reference type or relationship (`user` in `:user:1`), or null
+char *ison_value_reference_type(const struct IsonValue *value);
+
+// Contents of a bytes value, borrowed from its document, with the length
+// stored in `len` (if non-null); null and a length of 0 for other types
+const uint8_t *ison_value_bytes(const struct IsonValue *value, size_t *len);
+
+#endif  /* ISON_H */
@@ -0,0 +1,199 @@
+//! Column aggregation and grouping for blocks
+//!
+//! Computes per-column statistics and builds the summary rows that ISON
+//! places after the `---` separator.
+
+use crate::prelude::*;
+use crate::{Block, Row, Value};
+
+/// Aggregate function applied to a numeric column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    Sum,
+    Min,
+    Max,
+    Mean,
+    Count,
+}
+
+/// Statistics for one numeric column
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnStats {
+    pub field: String,
+    /// Number of numeric (non-null) values
+    pub count: usize,
+    pub sum: f64,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    /// True when every value is an integer
+    pub integer: bool,
+}
+
+impl ColumnStats {
+    fn from_values<'a>(field: &str, values: impl Iterator<Item = &'a Value>) -> Option<Self> {
+        let mut stats = ColumnStats {
+            field: field.to_string(),
+            count: 0,
+            sum: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            mean: 0.0,
+            integer: true,
+        };
+        for value in values {
+            let n = match value {
+                Value::Int(i) => *i as f64,
+                Value::Float(f) => {
+                    stats.integer = false;
+                    *f
+                }
+                #[cfg(feature = "rust_decimal")]
+                Value::Decimal(_) => {
+                    stats.integer = false;
+                    value.as_float()?
+                }
+                Value::Null => continue,
+                _ => return None,
+            };
+            stats.count += 1;
+            stats.sum += n;
+            stats.min = stats.min.min(n);
+            stats.max = stats.max.max(n);
+        }
+        if stats.count == 0 {
+            return None;
+        }
+        stats.mean = stats.sum / stats.count as f64;
+        Some(stats)
+    }
+
+    /// Value of an aggregate, kept as `Int` for integer columns (except `Mean`)
+    pub fn value(&self, aggregate: Aggregate) -> Value {
+        let n = match aggregate {
+            Aggregate::Sum => self.sum,
+            Aggregate::Min => self.min,
+            Aggregate::Max => self.max,
+            Aggregate::Mean => return Value::Float(self.mean),
+            Aggregate::Count => return Value::Int(self.count as i64),
+        };
+        if self.integer {
+            Value::Int(n as i64)
+        } else {
+            Value::Float(n)
+        }
+    }
+}
+
+impl Block {
+    /// Statistics for every numeric column, in field order.
+    ///
+    /// A column is numeric when all its non-null values are `Int`, `Float`, or `Decimal`
+    /// and at least one is present.
+    pub fn aggregate(&self) -> Vec<ColumnStats> {
+        self.fields
+            .iter()
+            .filter_map(|f| self.column_stats(f))
+            .collect()
+    }
+
+    /// Statistics for a single column, if it is numeric
+    pub fn column_stats(&self, field: &str) -> Option<ColumnStats> {
+        ColumnStats::from_values(field, self.rows.iter().filter_map(|r| r.get(field)))
+    }
+
+    /// Summary row with `aggregate` applied to each numeric column
+    pub fn summary_row(&self, aggregate: Aggregate) -> Row {
+        self.aggregate()
+            .into_iter()
+            .map(|s| {
+                let value = s.value(aggregate);
+                (s.field.into(), value)
+            })
+            .collect()
+    }
+
+    /// Split rows into sub-blocks by the value of `field`, in order of first appearance
+    pub fn group_by(&self, field: &str) -> Vec<(Value, Block)> {
+        let mut groups: Vec<(Value, Block)> = Vec::new();
+        for row in &self.rows {
+            let key = row.get(field).cloned().unwrap_or(Value::Null);
+            let idx = match groups.iter().position(|(k, _)| *k == key) {
+                Some(idx) => idx,
+                None => {
+                    let mut block = Block::new(self.kind.clone(), self.name.clone());
+                    block.fields = self.fields.clone();
+                    block.field_info = self.field_info.clone();
+                    groups.push((key, block));
+                    groups.len() - 1
+                }
+            };
+            groups[idx].1.rows.push(row.clone());
+        }
+        groups
+    }
+
+    /// Copy of the block with one summary row per group of `field`.
+    ///
+    /// Each summary row holds the group key plus `aggregate` over the
+    /// group's numeric columns; existing summary rows are replaced.
+    pub fn group_summary(&self, field: &str, aggregate: Aggregate) -> Block {
+        let mut block = self.clone();
+        block.summary_rows = self
+            .group_by(field)
+            .into_iter()
+            .map(|(key, group)| {
+                let mut row = group.summary_row(aggregate);
+                row.insert(field.into(), key);
+                row
+            })
+            .collect();
+        block
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    const SALES: &str = "table.sales\namount price region\n10 1.5 north\n5 2.0 south\n7 ~ north";
+
+    #[test]
+    fn test_aggregate_columns() {
+        let doc = parse(SALES).unwrap();
+        let stats = doc["sales"].aggregate();
+        let fields: Vec<&str> = stats.iter().map(|s| s.field.as_str()).collect();
+        assert_eq!(fields, vec!["amount", "price"]);
+        assert_eq!(stats[0].sum, 22.0);
+        assert_eq!(stats[0].value(Aggregate::Max), Value::Int(10));
+        assert_eq!(stats[1].count, 2);
+        assert_eq!(stats[1].mean, 1.75);
+    }
+
+    #[test]
+    fn test_group_summary() {
+        let doc = parse(SALES).unwrap();
+        let groups = doc["sales"].group_by("region");
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].1.len(), 2);
+
+        let summarized = doc["sales"].group_summary("region", Aggregate::Sum);
+        assert_eq!(summarized.summary_rows.len(), 2);
+        assert_eq!(
+            summarized.summary_rows[0].get("amount"),
+            Some(&Value::Int(17))
+        );
+        assert_eq!(
+            summarized.summary_rows[1].get("region"),
+            Some(&Value::String("south".into()))
+        );
+
+        let text = dumps(
+            &Document {
+                blocks: vec![summarized],
+            },
+            false,
+        );
+        assert!(text.contains("---\n17 1.5 north"));
+    }
+}
@@ -0,0 +1,191 @@
+//! Appending rows to ISONL files
+//!
+//! ISONL repeats a block's fields on every line, or declares them once in a
+//! `#schema:` line, and readers take each block's fields from its first
+//! definition. [`IsonlAppender`] reads those definitions from an existing
+//! file and writes new rows against them, so an ISONL file can serve as an
+//! append-only log.
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::prelude::*;
+use crate::{isonl_prefix, isonl_schema, Block, ISONError, IsonlReader, Result, Serializer};
+
+/// Appends rows to an ISONL file, reusing the field definitions already in it.
+///
+/// Each call to [`append`](IsonlAppender::append) is one batch: its lines go
+/// out in a single write to a file opened in append mode, followed by an
+/// fsync. Several appenders can share a file as long as each batch fits in
+/// one write and only one of them defines any new block.
+///
+/// ```rust,no_run
+/// use ison_rs::{ison, IsonlAppender};
+///
+/// let mut log = IsonlAppender::open("events.isonl")?;
+/// log.append(&ison! { table.events [id: int, kind] [1, "login"] [2, "logout"] })?;
+/// # Ok::<(), ison_rs::ISONError>(())
+/// ```
+pub struct IsonlAppender {
+    file: File,
+    blocks: IsonlReader,
+    serializer: Serializer,
+}
+
+impl IsonlAppender {
+    /// Open `path` for appending, creating it if needed, and read the block
+    /// definitions it already holds.
+    ///
+    /// Fails if the existing content is not valid ISONL. A last line without
+    /// a newline is completed before anything is appended.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+
+        let mut blocks = IsonlReader::default();
+        for (line_num, line) in BufReader::new(&file).lines().enumerate() {
+            blocks.read_line(&line?, line_num + 1)?;
+        }
+
+        if file.seek(SeekFrom::End(0))? > 0 {
+            file.seek(SeekFrom::End(-1))?;
+            let mut last = [0u8];
+            file.read_exact(&mut last)?;
+            if last[0] != b'\n' {
+                file.write_all(b"\n")?;
+            }
+        }
+
+        Ok(Self {
+            file,
+            blocks,
+            serializer: Serializer::new(false),
+        })
+    }
+
+    /// Fields of a block defined in the file or by an earlier append
+    pub fn fields(&self, kind: &str, name: &str) -> Option<&[String]> {
+        let idx = self.blocks.find(kind, name)?;
+        Some(&self.blocks.blocks[idx].block.fields)
+    }
+
+    /// Append the rows of `block` as one batch and fsync.
+    ///
+    /// Rows of a block the file already defines are written with its fields,
+    /// in its order, so they read back as part of that block; a field the
+    /// definition lacks is an error. Other blocks are defined by `block`'s
+    /// own fields.
+    ///
+    /// In a file that starts with a `#!ison/` version line (as written by
+    /// [`dumps_isonl_with_schema`](crate::dumps_isonl_with_schema)), new
+    /// blocks get a `#schema:` line and rows leave out their fields.
+    pub fn append(&mut self, block: &Block) -> Result<()> {
+        let mut batch = String::new();
+        let idx = match self.blocks.find(&block.kind, &block.name) {
+            Some(idx) => {
+                let defined = &self.blocks.blocks[idx].block.fields;
+                if let Some(extra) = block.fields.iter().find(|f| !defined.contains(f)) {
+                    return Err(ISONError {
+                        message: format!(
+                            "Field '{}' is not defined for {}.{}",
+                            extra, block.kind, block.name
+                        ),
+                        line: None,
+                    });
+                }
+                idx
+            }
+            None => {
+                if self.blocks.versioned {
+                    batch.push_str(&isonl_schema(block));
+                    batch.push('\n');
+                }
+                self.blocks.define(block)
+            }
+        };
+
+        let header = &self.blocks.blocks[idx].block;
+        let prefix = if self.blocks.versioned {
+            format!("{}.{}||", header.kind, header.name)
+        } else {
+            isonl_prefix(header)
+        };
+        for row in &block.rows {
+            batch.push_str(&prefix);
+            batch.push_str(&self.serializer.serialize_values(row, &header.fields));
+            batch.push('\n');
+        }
+        self.file.write_all(batch.as_bytes())?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, parse_isonl};
+
+    #[test]
+    fn test_append() {
+        let path = std::env::temp_dir().join(format!("ison-append-{}.isonl", std::process::id()));
+        std::fs::write(&path, "table.users|id name:string|1 Alice").unwrap();
+
+        let mut log = IsonlAppender::open(&path).unwrap();
+        assert_eq!(log.fields("table", "users").unwrap(), ["id", "name"]);
+        assert!(log.fields("table", "orders").is_none());
+
+        // Known block: the file's field order and annotations win
+        log.append(&parse("table.users\nname id\nBob 2").unwrap()["users"])
+            .unwrap();
+        log.append(&parse("table.orders\nid user\n9 :2").unwrap()["orders"])
+            .unwrap();
+        let err = log
+            .append(&parse("table.users\nid email\n3 c@x.com").unwrap()["users"])
+            .unwrap_err();
+        assert_eq!(err.message, "Field 'email' is not defined for table.users");
+        drop(log);
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            text,
+            "table.users|id name:string|1 Alice\n\
+             table.users|id name:string|2 Bob\n\
+             table.orders|id user|9 :2\n"
+        );
+        let doc = parse_isonl(&text).unwrap();
+        assert_eq!(doc["users"].len(), 2);
+
+        // Reopening picks up the blocks appended before
+        let log = IsonlAppender::open(&path).unwrap();
+        assert_eq!(log.fields("table", "orders").unwrap(), ["id", "user"]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_append_with_schema() {
+        let path = std::env::temp_dir().join(format!("ison-schema-{}.isonl", std::process::id()));
+        let doc = parse("table.users\nid name\n1 Alice").unwrap();
+        std::fs::write(&path, crate::dumps_isonl_with_schema(&doc)).unwrap();
+
+        let mut log = IsonlAppender::open(&path).unwrap();
+        log.append(&parse("table.users\nid name\n2 Bob").unwrap()["users"])
+            .unwrap();
+        log.append(&parse("table.tags\ntag\nx").unwrap()["tags"])
+            .unwrap();
+        drop(log);
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            text,
+            "#!ison/1.0\n#schema: table.users|id name\ntable.users||1 Alice\n\
+             table.users||2 Bob\n#schema: table.tags|tag\ntable.tags||x\n"
+        );
+        assert_eq!(parse_isonl(&text).unwrap()["users"].len(), 2);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
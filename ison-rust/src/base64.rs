@@ -0,0 +1,93 @@
+//! Standard base64 (RFC 4648, `+/` alphabet) for [`Value::Bytes`](crate::Value::Bytes) cells
+
+use crate::prelude::*;
+
+/// Token prefix marking a base64 bytes value, e.g. `b64:SGVsbG8=`
+pub const BYTES_PREFIX: &str = "b64:";
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encode with `=` padding
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = (u32::from(chunk[0]) << 16)
+            | (u32::from(*chunk.get(1).unwrap_or(&0)) << 8)
+            | u32::from(*chunk.get(2).unwrap_or(&0));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i)) as usize & 0x3f] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Decode, with or without padding; `None` for anything that is not base64
+pub(crate) fn decode(text: &str) -> Option<Vec<u8>> {
+    let text = text.as_bytes();
+    let unpadded = match text {
+        [rest @ .., b'=', b'='] | [rest @ .., b'='] if text.len().is_multiple_of(4) => rest,
+        _ => text,
+    };
+    if unpadded.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(unpadded.len() * 3 / 4);
+    for chunk in unpadded.chunks(4) {
+        let mut n = 0u32;
+        for (i, &b) in chunk.iter().enumerate() {
+            n |= u32::from(sextet(b)?) << (18 - 6 * i);
+        }
+        let [_, a, b, c] = n.to_be_bytes();
+        out.extend_from_slice(&[a, b, c][..chunk.len() - 1]);
+    }
+    Some(out)
+}
+
+fn sextet(b: u8) -> Option<u8> {
+    Some(match b {
+        b'A'..=b'Z' => b - b'A',
+        b'a'..=b'z' => b - b'a' + 26,
+        b'0'..=b'9' => b - b'0' + 52,
+        b'+' => 62,
+        b'/' => 63,
+        _ => return None,
+    })
+}
+
+/// Serde `serialize_with` for bytes: the ISON token text, e.g. `"b64:SGk="`
+#[cfg(feature = "serde")]
+pub(crate) fn serialize<S: serde::Serializer>(
+    bytes: &[u8],
+    serializer: S,
+) -> core::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("{}{}", BYTES_PREFIX, encode(bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        for (raw, encoded) in [
+            (&b""[..], ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+            (&[0xfb, 0xff][..], "+/8="),
+        ] {
+            assert_eq!(encode(raw), encoded);
+            assert_eq!(decode(encoded).as_deref(), Some(raw));
+        }
+        assert_eq!(decode("Zm8").as_deref(), Some(&b"fo"[..]));
+        assert!(decode("Zm9vY").is_none());
+        assert!(decode("Zm8*").is_none());
+        assert!(decode("Zg=").is_none());
+    }
+}
@@ -0,0 +1,227 @@
+//! `ison` command-line tool
+//!
+//! Build with `cargo install ison-rs --features cli`. Every command reads the
+//! named file, or stdin when the file is omitted or `-`, and writes to stdout.
+
+use std::fs;
+use std::io::{self, Read};
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: ison <command> [options] [file]
+
+Commands:
+  fmt [--align] [--delimiter <d>]        Reformat ISON; --infer-types annotates
+      [--infer-types]                    untyped fields from their values
+  to-json [--pretty]                     Convert ISON to JSON
+  from-json                              Convert JSON to ISON
+  from-csv --name <block>                Convert CSV to an ISON table
+  to-isonl [--schema]                    Convert ISON to ISONL; --schema declares
+                                         fields once in #schema: lines
+  from-isonl                             Convert ISONL to ISON
+  validate --schema <schema.ison>        Check against annotated field types
+  query [--locations] <selector>         Print values matching a selector,
+                                         e.g. \"users[*].email\"
+
+Options:
+  -h, --help                             Show this help
+  -V, --version                          Show version";
+
+/// Failure with its exit code: 1 for bad input or failed checks, 2 for usage errors
+struct Failure(u8, String);
+
+impl From<ison_rs::ISONError> for Failure {
+    fn from(e: ison_rs::ISONError) -> Self {
+        Failure(1, e.to_string())
+    }
+}
+
+fn usage(message: impl Into<String>) -> Failure {
+    Failure(2, format!("{}\n\n{}", message.into(), USAGE))
+}
+
+/// Parsed flags and positional arguments for one command
+struct Args {
+    flags: Vec<String>,
+    options: Vec<(String, String)>,
+    positional: Vec<String>,
+}
+
+impl Args {
+    /// Split `args`, where `valued` lists options that take a value
+    fn parse(args: &[String], valued: &[&str], switches: &[&str]) -> Result<Self, Failure> {
+        let mut parsed = Args {
+            flags: Vec::new(),
+            options: Vec::new(),
+            positional: Vec::new(),
+        };
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if valued.contains(&arg.as_str()) {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| usage(format!("{} requires a value", arg)))?;
+                parsed.options.push((arg.clone(), value.clone()));
+            } else if switches.contains(&arg.as_str()) {
+                parsed.flags.push(arg.clone());
+            } else if arg.starts_with("--") {
+                return Err(usage(format!("Unknown option: {}", arg)));
+            } else {
+                parsed.positional.push(arg.clone());
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|f| f == name)
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn required(&self, name: &str) -> Result<&str, Failure> {
+        self.option(name)
+            .ok_or_else(|| usage(format!("{} is required", name)))
+    }
+
+    /// Contents of the single optional input file
+    fn input(&self) -> Result<String, Failure> {
+        if let Some(extra) = self.positional.get(1) {
+            return Err(usage(format!("Unexpected argument: {}", extra)));
+        }
+        read_input(self.positional.first().map(String::as_str))
+    }
+}
+
+fn read_input(path: Option<&str>) -> Result<String, Failure> {
+    match path {
+        None | Some("-") => {
+            let mut text = String::new();
+            io::stdin()
+                .read_to_string(&mut text)
+                .map_err(|e| Failure(1, format!("Failed to read stdin: {}", e)))?;
+            Ok(text)
+        }
+        Some(path) => fs::read_to_string(path)
+            .map_err(|e| Failure(1, format!("Failed to read {}: {}", path, e))),
+    }
+}
+
+fn run(argv: &[String]) -> Result<String, Failure> {
+    let Some((command, rest)) = argv.split_first() else {
+        return Err(usage("Missing command"));
+    };
+
+    match command.as_str() {
+        "-h" | "--help" | "help" => Ok(USAGE.to_string()),
+        "-V" | "--version" => Ok(format!("ison {}", ison_rs::VERSION)),
+        "fmt" => {
+            let args = Args::parse(rest, &["--delimiter"], &["--align", "--infer-types"])?;
+            let doc = ison_rs::parse(&args.input()?)?;
+            let options = ison_rs::DumpOptions {
+                align_columns: args.flag("--align"),
+                delimiter: args.option("--delimiter").unwrap_or(" ").to_string(),
+                infer_types: args.flag("--infer-types"),
+                ..Default::default()
+            };
+            Ok(ison_rs::dumps_with_options(&doc, &options)?)
+        }
+        "to-json" => {
+            let args = Args::parse(rest, &[], &["--pretty"])?;
+            Ok(ison_rs::ison_to_json(
+                &args.input()?,
+                args.flag("--pretty"),
+            )?)
+        }
+        "from-json" => {
+            let args = Args::parse(rest, &[], &[])?;
+            Ok(ison_rs::json_to_ison(&args.input()?)?)
+        }
+        "from-csv" => {
+            let args = Args::parse(rest, &["--name"], &[])?;
+            let name = args.required("--name")?.to_string();
+            let block = ison_rs::from_csv(&args.input()?, &name)?;
+            Ok(ison_rs::dumps(
+                &ison_rs::Document {
+                    blocks: vec![block],
+                },
+                false,
+            ))
+        }
+        "to-isonl" => {
+            let args = Args::parse(rest, &[], &["--schema"])?;
+            let input = args.input()?;
+            if args.flag("--schema") {
+                Ok(ison_rs::dumps_isonl_with_schema(&ison_rs::parse(&input)?))
+            } else {
+                Ok(ison_rs::ison_to_isonl(&input)?)
+            }
+        }
+        "from-isonl" => {
+            let args = Args::parse(rest, &[], &[])?;
+            Ok(ison_rs::isonl_to_ison(&args.input()?)?)
+        }
+        "validate" => {
+            let args = Args::parse(rest, &["--schema"], &[])?;
+            let schema = ison_rs::parse(&read_input(Some(args.required("--schema")?))?)?;
+            let doc = ison_rs::parse(&args.input()?)?;
+            let violations = ison_rs::check_schema(&doc, &schema);
+            if violations.is_empty() {
+                Ok("valid".to_string())
+            } else {
+                let lines: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+                Err(Failure(1, lines.join("\n")))
+            }
+        }
+        "query" => {
+            let args = Args::parse(rest, &[], &["--locations"])?;
+            let selector = args
+                .positional
+                .first()
+                .ok_or_else(|| usage("query requires a selector"))?
+                .clone();
+            let file = args.positional.get(1).map(String::as_str);
+            if args.positional.len() > 2 {
+                return Err(usage(format!(
+                    "Unexpected argument: {}",
+                    args.positional[2]
+                )));
+            }
+            let doc = ison_rs::parse(&read_input(file)?)?;
+            let lines: Vec<String> = doc
+                .select(&selector)?
+                .iter()
+                .map(|hit| {
+                    if args.flag("--locations") {
+                        format!("{}[{}].{}\t{}", hit.block, hit.row, hit.field, hit.value)
+                    } else {
+                        hit.value.to_string()
+                    }
+                })
+                .collect();
+            Ok(lines.join("\n"))
+        }
+        other => Err(usage(format!("Unknown command: {}", other))),
+    }
+}
+
+fn main() -> ExitCode {
+    let argv: Vec<String> = std::env::args().skip(1).collect();
+    match run(&argv) {
+        Ok(output) => {
+            if !output.is_empty() {
+                println!("{}", output.trim_end_matches('\n'));
+            }
+            ExitCode::SUCCESS
+        }
+        Err(Failure(code, message)) => {
+            eprintln!("{}", message);
+            ExitCode::from(code)
+        }
+    }
+}
@@ -0,0 +1,221 @@
+//! Lightweight schema checks driven by type annotations
+//!
+//! A schema is itself an ISON document whose blocks declare the expected
+//! fields and their types, without rows:
+//!
+//! ```text
+//! table.users
+//! id:int name:string email:string? manager:ref
+//! ```
+//!
+//! Recognized types are `int`, `float` (integers accepted), `number`,
+//! `string`, `bool`, `ref`, and `any`; null is only accepted with a trailing
+//! `?`. Fields without a type, or with another annotation such as `computed`,
+//! accept any value. For full validation with constraints use `isonantic-rs`.
+
+use core::fmt;
+
+use crate::prelude::*;
+use crate::{Block, Document, Value};
+
+/// A place where a document does not match its schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SchemaViolation {
+    pub block: String,
+    /// Data row index, for value errors
+    pub row: Option<usize>,
+    pub field: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.block)?;
+        if let Some(row) = self.row {
+            write!(f, "[{}]", row)?;
+        }
+        if let Some(field) = &self.field {
+            write!(f, ".{}", field)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Whether a non-null `value` satisfies the annotation (without its `?` suffix).
+///
+/// `None` for annotations this module does not check.
+fn matches_type(annotation: &str, value: &Value) -> Option<bool> {
+    Some(match annotation {
+        "int" => value.is_int(),
+        "float" | "number" => value.as_float().is_some(),
+        #[cfg(feature = "rust_decimal")]
+        "decimal" => value.as_decimal().is_some(),
+        "string" => value.is_string(),
+        "bool" => value.is_bool(),
+        "ref" => value.is_reference(),
+        "bytes" => value.is_bytes(),
+        "any" => true,
+        _ => return None,
+    })
+}
+
+/// Check `doc` against the blocks and field annotations declared in `schema`.
+///
+/// Every schema block must exist in `doc` (matched by name) with every
+/// declared field, and each data row value must match the field's type.
+/// Blocks and fields not mentioned in the schema are ignored.
+pub fn check_schema(doc: &Document, schema: &Document) -> Vec<SchemaViolation> {
+    let mut violations = Vec::new();
+    for expected in &schema.blocks {
+        let violation =
+            |row: Option<usize>, field: Option<&str>, message: String| SchemaViolation {
+                block: expected.name.clone(),
+                row,
+                field: field.map(str::to_string),
+                message,
+            };
+
+        let Some(block) = doc.get(&expected.name) else {
+            violations.push(violation(None, None, "missing block".to_string()));
+            continue;
+        };
+        if block.kind != expected.kind {
+            violations.push(violation(
+                None,
+                None,
+                format!("expected kind '{}', found '{}'", expected.kind, block.kind),
+            ));
+        }
+
+        for info in &expected.field_info {
+            if !block.fields.contains(&info.name) {
+                violations.push(violation(
+                    None,
+                    Some(&info.name),
+                    "missing field".to_string(),
+                ));
+                continue;
+            }
+            let Some(annotation) = info.field_type.as_deref() else {
+                continue;
+            };
+            let (annotation, nullable) = match annotation.strip_suffix('?') {
+                Some(base) => (base, true),
+                None => (annotation, false),
+            };
+            for (i, row) in block.rows.iter().enumerate() {
+                let value = row.get(info.name.as_str()).unwrap_or(&Value::Null);
+                let ok = match matches_type(annotation, value) {
+                    None => true,
+                    Some(_) if value.is_null() => nullable,
+                    Some(ok) => ok,
+                };
+                if !ok {
+                    violations.push(violation(
+                        Some(i),
+                        Some(&info.name),
+                        format!("expected {}, found {}", annotation, value.type_name()),
+                    ));
+                }
+            }
+        }
+    }
+    violations
+}
+
+impl Block {
+    /// The annotation every non-null data cell of `field` satisfies, with `?`
+    /// if some are null: `int`, `float` (for a mix with ints), `string`, and
+    /// so on.
+    ///
+    /// `None` when the field has no non-null cells or they mix other types.
+    pub fn infer_field_type(&self, field: &str) -> Option<String> {
+        let mut inferred: Option<&'static str> = None;
+        let mut nullable = false;
+        for row in &self.rows {
+            let value = row.get(field).unwrap_or(&Value::Null);
+            if value.is_null() {
+                nullable = true;
+                continue;
+            }
+            let name = value.type_name();
+            inferred = Some(match inferred {
+                None => name,
+                Some(seen) if seen == name => seen,
+                Some("int") if matches!(name, "float" | "decimal") => name,
+                Some(seen @ ("float" | "decimal")) if name == "int" => seen,
+                Some(_) => return None,
+            });
+        }
+        inferred.map(|name| {
+            if nullable {
+                format!("{}?", name)
+            } else {
+                name.to_string()
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_check_schema() {
+        let schema = parse(
+            "table.users\nid:int name:string email:string? boss:ref score:float\n\n\
+             table.orders\nid:int",
+        )
+        .unwrap();
+        let doc =
+            parse("table.users\nid name email boss score\n1 Alice ~ :2 3\n\"x\" Bob b@x.com 7 ~")
+                .unwrap();
+
+        let messages: Vec<String> = check_schema(&doc, &schema)
+            .iter()
+            .map(|v| v.to_string())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "users[1].id: expected int, found string",
+                "users[1].boss: expected ref, found int",
+                "users[1].score: expected float, found null",
+                "orders: missing block",
+            ]
+        );
+
+        assert!(check_schema(&doc, &parse("table.users\nid name").unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_infer_field_type() {
+        let doc = parse(
+            "table.t\nid score name boss mixed empty\n1 2 Alice :1 x ~\n2 2.5 ~ :1 3 ~\n---\n3 total x x x x",
+        )
+        .unwrap();
+        let block = &doc["t"];
+        let inferred: Vec<Option<String>> = block
+            .fields
+            .iter()
+            .map(|f| block.infer_field_type(f))
+            .collect();
+        assert_eq!(
+            inferred,
+            [
+                Some("int".into()),
+                Some("float".into()),
+                Some("string?".into()),
+                Some("ref".into()),
+                None,
+                None
+            ]
+        );
+        // Inferred annotations pass the schema check
+        let schema = parse("table.t\nid:int score:float name:string? boss:ref").unwrap();
+        assert!(check_schema(&doc, &schema).is_empty());
+    }
+}
@@ -0,0 +1,218 @@
+//! Splitting documents into context-sized chunks
+//!
+//! Used for map-reduce style processing, where each chunk must stand on its
+//! own: every chunk is a valid [`Document`] whose blocks repeat their header
+//! and fields line.
+
+use crate::prelude::*;
+use crate::{Block, Document, Serializer};
+
+/// Limits for [`Document::chunk`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChunkOptions {
+    /// Maximum data rows per chunk, counting overlap rows
+    pub max_rows: Option<usize>,
+    /// Maximum size of each chunk in compact form (`dumps(chunk, false)`)
+    pub max_bytes: Option<usize>,
+    /// Rows repeated from the end of the previous chunk when a block is split
+    pub overlap: usize,
+    /// Include every block's header in every chunk, even without rows
+    pub keep_headers: bool,
+}
+
+/// Chunk under construction; slots follow the source document's block order
+struct Pending {
+    blocks: Vec<Option<Block>>,
+    rows: usize,
+    bytes: usize,
+    /// Rows not repeated from the previous chunk
+    fresh: usize,
+}
+
+impl Document {
+    /// Split the document into chunks within `options` limits.
+    ///
+    /// Rows stay in document order and chunks fill across block boundaries.
+    /// Summary rows travel with the last data row of their block. A row that
+    /// alone exceeds `max_bytes` still gets a chunk of its own.
+    pub fn chunk(&self, options: ChunkOptions) -> Vec<Document> {
+        let serializer = Serializer::new(false);
+        let overlap = match options.max_rows {
+            Some(max) => options.overlap.min(max.saturating_sub(1)),
+            None => options.overlap,
+        };
+
+        let skeletons: Vec<Block> = self
+            .blocks
+            .iter()
+            .map(|b| {
+                let mut skeleton = b.clone();
+                skeleton.rows.clear();
+                skeleton.summary_rows.clear();
+                skeleton
+            })
+            .collect();
+        let header_bytes: Vec<usize> = skeletons
+            .iter()
+            .map(|b| serializer.serialize_block(b).len())
+            .collect();
+        let summary_bytes: Vec<usize> = self
+            .blocks
+            .iter()
+            .zip(&header_bytes)
+            .map(|(b, header)| {
+                if b.summary_rows.is_empty() {
+                    return 0;
+                }
+                let mut summary = b.clone();
+                summary.rows.clear();
+                serializer.serialize_block(&summary).len() - header
+            })
+            .collect();
+
+        let new_chunk = || {
+            let mut pending = Pending {
+                blocks: vec![None; self.blocks.len()],
+                rows: 0,
+                bytes: 0,
+                fresh: 0,
+            };
+            if options.keep_headers {
+                for (i, skeleton) in skeletons.iter().enumerate() {
+                    pending.blocks[i] = Some(skeleton.clone());
+                }
+                pending.bytes =
+                    header_bytes.iter().sum::<usize>() + 2 * self.blocks.len().saturating_sub(1);
+            }
+            pending
+        };
+        // Bytes needed to make block `i` present in the chunk
+        let open_cost = |pending: &Pending, i: usize| {
+            if pending.blocks[i].is_some() {
+                0
+            } else if pending.blocks.iter().any(Option::is_some) {
+                header_bytes[i] + 2
+            } else {
+                header_bytes[i]
+            }
+        };
+        let fits = |pending: &Pending, bytes: usize| {
+            pending.fresh == 0
+                || (options.max_rows.is_none_or(|max| pending.rows < max)
+                    && options
+                        .max_bytes
+                        .is_none_or(|max| pending.bytes + bytes <= max))
+        };
+
+        let mut chunks = Vec::new();
+        let mut pending = new_chunk();
+        for (b, block) in self.blocks.iter().enumerate() {
+            let row_bytes: Vec<usize> = (0..block.rows.len())
+                .map(|i| serializer.serialize_row(block, false, i, &[]).len() + 1)
+                .collect();
+
+            if block.rows.is_empty() {
+                pending.bytes += open_cost(&pending, b) + summary_bytes[b];
+                let slot = pending.blocks[b].get_or_insert_with(|| skeletons[b].clone());
+                slot.summary_rows = block.summary_rows.clone();
+                continue;
+            }
+
+            let mut i = 0;
+            while i < block.rows.len() {
+                let last = i + 1 == block.rows.len();
+                let cost =
+                    open_cost(&pending, b) + row_bytes[i] + if last { summary_bytes[b] } else { 0 };
+
+                if !fits(&pending, cost) {
+                    chunks.push(finish(pending));
+                    pending = new_chunk();
+                    if i > 0 {
+                        pending.bytes += open_cost(&pending, b);
+                        let slot = pending.blocks[b].get_or_insert_with(|| skeletons[b].clone());
+                        let start = i.saturating_sub(overlap);
+                        for (row, bytes) in block.rows[start..i].iter().zip(&row_bytes[start..i]) {
+                            slot.rows.push(row.clone());
+                            pending.rows += 1;
+                            pending.bytes += bytes;
+                        }
+                    }
+                    continue;
+                }
+
+                pending.bytes += cost;
+                pending.rows += 1;
+                pending.fresh += 1;
+                let slot = pending.blocks[b].get_or_insert_with(|| skeletons[b].clone());
+                slot.rows.push(block.rows[i].clone());
+                if last {
+                    slot.summary_rows = block.summary_rows.clone();
+                }
+                i += 1;
+            }
+        }
+        if pending.fresh > 0 || chunks.is_empty() {
+            chunks.push(finish(pending));
+        }
+        chunks
+    }
+}
+
+fn finish(pending: Pending) -> Document {
+    Document {
+        blocks: pending.blocks.into_iter().flatten().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    const DOC: &str =
+        "table.users\nid name\n1 Alice\n2 Bob\n3 Carol\n4 Dave\n5 Eve\n---\n5 total\n\n\
+                       table.tags\nid label\n1 a\n2 b";
+
+    fn ids(doc: &Document, block: &str) -> Vec<i64> {
+        doc.get(block)
+            .map(|b| b.rows.iter().map(|r| r["id"].as_int().unwrap()).collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_chunk_by_rows_with_overlap() {
+        let doc = parse(DOC).unwrap();
+        let chunks = doc.chunk(ChunkOptions {
+            max_rows: Some(3),
+            overlap: 1,
+            ..Default::default()
+        });
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(ids(&chunks[0], "users"), vec![1, 2, 3]);
+        assert_eq!(ids(&chunks[1], "users"), vec![3, 4, 5]);
+        assert_eq!(chunks[1]["users"].summary_rows.len(), 1);
+        assert!(!chunks[1].has("tags"));
+        assert_eq!(ids(&chunks[2], "tags"), vec![1, 2]);
+        assert!(!chunks[2].has("users"));
+        for chunk in &chunks {
+            assert!(parse(&dumps(chunk, false)).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_chunk_by_bytes_with_headers() {
+        let doc = parse(DOC).unwrap();
+        let options = ChunkOptions {
+            max_bytes: Some(60),
+            keep_headers: true,
+            ..Default::default()
+        };
+        let chunks = doc.chunk(options);
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert_eq!(chunk.len(), 2);
+            assert!(dumps(chunk, false).len() <= 60);
+        }
+        let all: Vec<i64> = chunks.iter().flat_map(|c| ids(c, "users")).collect();
+        assert_eq!(all, vec![1, 2, 3, 4, 5]);
+    }
+}
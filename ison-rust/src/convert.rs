@@ -0,0 +1,283 @@
+//! Conversions between Rust values and [`Value`], and the [`ison!`](crate::ison) macro
+
+use crate::prelude::*;
+use crate::{ISONError, Reference, Value};
+
+impl Value {
+    /// Name of the variant as used in type annotations: `null`, `bool`, `int`,
+    /// `float`, `string`, `ref`, `bytes`, or `decimal`
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Bool(_) => "bool",
+            Value::Int(_) => "int",
+            Value::Float(_) => "float",
+            Value::String(_) => "string",
+            Value::Reference(_) => "ref",
+            Value::Bytes(_) => "bytes",
+            #[cfg(feature = "rust_decimal")]
+            Value::Decimal(_) => "decimal",
+        }
+    }
+}
+
+macro_rules! from_int {
+    ($($t:ty),*) => {$(
+        impl From<$t> for Value {
+            fn from(v: $t) -> Self {
+                Value::Int(i64::from(v))
+            }
+        }
+    )*};
+}
+
+from_int!(i8, i16, i32, i64, u8, u16, u32);
+
+impl From<f32> for Value {
+    fn from(v: f32) -> Self {
+        Value::Float(f64::from(v))
+    }
+}
+
+impl From<f64> for Value {
+    fn from(v: f64) -> Self {
+        Value::Float(v)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(v: bool) -> Self {
+        Value::Bool(v)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(v: &str) -> Self {
+        Value::String(v.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(v: String) -> Self {
+        Value::String(v)
+    }
+}
+
+impl From<Reference> for Value {
+    fn from(v: Reference) -> Self {
+        Value::Reference(v)
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(v: Vec<u8>) -> Self {
+        Value::Bytes(v)
+    }
+}
+
+impl From<&[u8]> for Value {
+    fn from(v: &[u8]) -> Self {
+        Value::Bytes(v.to_vec())
+    }
+}
+
+#[cfg(feature = "rust_decimal")]
+impl From<crate::Decimal> for Value {
+    fn from(v: crate::Decimal) -> Self {
+        Value::Decimal(v)
+    }
+}
+
+/// `None` becomes [`Value::Null`]
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(v: Option<T>) -> Self {
+        v.map_or(Value::Null, Into::into)
+    }
+}
+
+fn mismatch(expected: &str, found: &Value) -> ISONError {
+    ISONError {
+        message: format!("Expected {}, found {}", expected, found.type_name()),
+        line: None,
+    }
+}
+
+impl TryFrom<Value> for i64 {
+    type Error = ISONError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        value.as_int().ok_or_else(|| mismatch("int", &value))
+    }
+}
+
+/// Integers widen to floats, as in [`Value::as_float`]
+impl TryFrom<Value> for f64 {
+    type Error = ISONError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        value.as_float().ok_or_else(|| mismatch("float", &value))
+    }
+}
+
+impl TryFrom<Value> for bool {
+    type Error = ISONError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        value.as_bool().ok_or_else(|| mismatch("bool", &value))
+    }
+}
+
+impl TryFrom<Value> for String {
+    type Error = ISONError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::String(s) => Ok(s),
+            other => Err(mismatch("string", &other)),
+        }
+    }
+}
+
+impl TryFrom<Value> for Reference {
+    type Error = ISONError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Reference(r) => Ok(r),
+            other => Err(mismatch("ref", &other)),
+        }
+    }
+}
+
+impl TryFrom<Value> for Vec<u8> {
+    type Error = ISONError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Bytes(b) => Ok(b),
+            other => Err(mismatch("bytes", &other)),
+        }
+    }
+}
+
+/// Integers widen, as in [`Value::as_decimal`]
+#[cfg(feature = "rust_decimal")]
+impl TryFrom<Value> for crate::Decimal {
+    type Error = ISONError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        value
+            .as_decimal()
+            .ok_or_else(|| mismatch("decimal", &value))
+    }
+}
+
+/// Build a [`Row`](crate::Row) or [`Block`](crate::Block) from literals.
+///
+/// Values go through `Value::from`, so plain Rust literals, `Option`s,
+/// [`Reference`]s, and `Value::Null` all work.
+///
+/// ```rust
+/// use ison_rs::{ison, Reference, Value};
+///
+/// let row = ison!({ id: 1, name: "Alice", email: None::<&str> });
+/// assert_eq!(row["name"], Value::from("Alice"));
+///
+/// let users = ison! {
+///     table.users
+///     [id: int, name, manager]
+///     [1, "Alice", Value::Null]
+///     [2, "Bob", Reference::new("1")]
+/// };
+/// assert_eq!(users.get_field_type("id"), Some("int"));
+/// assert_eq!(users.len(), 2);
+/// ```
+///
+/// # Panics
+///
+/// The block form panics if a row has more values than the header has fields.
+#[macro_export]
+macro_rules! ison {
+    ({ $($key:ident : $value:expr),* $(,)? }) => {{
+        #[allow(unused_mut)]
+        let mut row = $crate::Row::new();
+        $( row.insert(stringify!($key).into(), $crate::Value::from($value)); )*
+        row
+    }};
+    (
+        $kind:ident . $name:ident
+        [ $($field:ident $(: $field_type:ident)?),* $(,)? ]
+        $( [ $($value:expr),* $(,)? ] )*
+    ) => {{
+        let mut block = $crate::Block::new(stringify!($kind), stringify!($name));
+        $(
+            block.fields.push(stringify!($field).into());
+            block.field_info.push($crate::FieldInfo {
+                name: stringify!($field).into(),
+                field_type: None $(.or(Some(stringify!($field_type).into())))?,
+                is_computed: false $(|| stringify!($field_type) == "computed")?,
+            });
+        )*
+        $(
+            block
+                .push_row_values(&[$($crate::Value::from($value)),*])
+                .expect("ison! row has more values than fields");
+        )*
+        block
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dumps, Document};
+
+    #[test]
+    fn test_from_and_try_from() {
+        assert_eq!(Value::from(7u8), Value::Int(7));
+        assert_eq!(Value::from(1.5f32), Value::Float(1.5));
+        assert_eq!(Value::from(Some("x")), Value::String("x".into()));
+        assert_eq!(Value::from(None::<i64>), Value::Null);
+        assert_eq!(Value::from(&b"hi"[..]), Value::Bytes(b"hi".to_vec()));
+
+        assert_eq!(i64::try_from(Value::Int(3)).unwrap(), 3);
+        assert_eq!(f64::try_from(Value::Int(3)).unwrap(), 3.0);
+        assert!(bool::try_from(Value::Bool(true)).unwrap());
+        assert_eq!(Vec::<u8>::try_from(Value::Bytes(vec![1])).unwrap(), [1]);
+        assert_eq!(
+            String::try_from(Value::Int(3)).unwrap_err().message,
+            "Expected string, found int"
+        );
+        assert_eq!(
+            Reference::try_from(Value::from(Reference::new("9")))
+                .unwrap()
+                .id,
+            "9"
+        );
+    }
+
+    #[test]
+    fn test_ison_macro() {
+        let row = ison!({ id: 1, name: "Alice", score: 9.5 });
+        assert_eq!(row.len(), 3);
+        assert_eq!(row["score"], Value::Float(9.5));
+        assert!(ison!({}).is_empty());
+
+        let block = ison! {
+            table.users
+            [id: int, name, total: computed]
+            [1, "Alice", 3]
+            [2, Some("Bob")]
+        };
+        assert_eq!(block.get_computed_fields(), vec!["total"]);
+        assert_eq!(
+            dumps(
+                &Document {
+                    blocks: vec![block]
+                },
+                false
+            ),
+            "table.users\nid:int name total:computed\n1 Alice 3\n2 Bob null"
+        );
+    }
+}
@@ -0,0 +1,161 @@
+//! CSV import
+//!
+//! Reads RFC 4180 CSV (comma separated, `"` quoting with `""` escapes, CRLF or
+//! LF line endings) into a block. The first record names the fields.
+
+use crate::prelude::*;
+use crate::{parse_token, Block, FieldInfo, ISONError, Result, Row, Value};
+
+/// One CSV cell; quoted cells are always strings
+struct Cell {
+    text: String,
+    quoted: bool,
+}
+
+fn csv_error(line: usize, message: impl Into<String>) -> ISONError {
+    ISONError {
+        message: message.into(),
+        line: Some(line),
+    }
+}
+
+/// Split CSV text into records, tracking the line each record starts on
+fn records(text: &str) -> Result<Vec<(usize, Vec<Cell>)>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut cell = Cell {
+        text: String::new(),
+        quoted: false,
+    };
+    let mut line = 1;
+    let mut start_line = 1;
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    cell.text.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                '\n' => {
+                    line += 1;
+                    cell.text.push(c);
+                }
+                _ => cell.text.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if cell.text.is_empty() && !cell.quoted => {
+                in_quotes = true;
+                cell.quoted = true;
+            }
+            ',' => record.push(core::mem::replace(
+                &mut cell,
+                Cell {
+                    text: String::new(),
+                    quoted: false,
+                },
+            )),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(core::mem::replace(
+                    &mut cell,
+                    Cell {
+                        text: String::new(),
+                        quoted: false,
+                    },
+                ));
+                records.push((start_line, core::mem::take(&mut record)));
+                line += 1;
+                start_line = line;
+            }
+            _ => cell.text.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(csv_error(start_line, "Unterminated quoted CSV field"));
+    }
+    if !cell.text.is_empty() || cell.quoted || !record.is_empty() {
+        record.push(cell);
+        records.push((start_line, record));
+    }
+
+    // Blank lines carry no data
+    records.retain(|(_, r)| !(r.len() == 1 && r[0].text.is_empty() && !r[0].quoted));
+    Ok(records)
+}
+
+/// Parse CSV text into a `table.<name>` block.
+///
+/// Unquoted cells use ISON value syntax (`42` is an integer, `true` a bool,
+/// `:1` a reference) and empty ones become null; quoted cells stay strings.
+pub fn from_csv(text: &str, name: &str) -> Result<Block> {
+    let mut records = records(text)?.into_iter();
+    let (_, header) = records
+        .next()
+        .ok_or_else(|| csv_error(1, "CSV input has no header row"))?;
+
+    let mut block = Block::new("table", name);
+    for cell in header {
+        let field = cell.text.trim().to_string();
+        block.fields.push(field.clone());
+        block.field_info.push(FieldInfo::new(field));
+    }
+
+    let keys = block.field_keys();
+    for (line, record) in records {
+        if record.len() != block.fields.len() {
+            return Err(csv_error(
+                line,
+                format!(
+                    "Expected {} CSV fields, found {}",
+                    block.fields.len(),
+                    record.len()
+                ),
+            ));
+        }
+        let mut row = Row::new();
+        for (key, cell) in keys.iter().zip(record) {
+            let value = if cell.quoted {
+                Value::String(cell.text)
+            } else if cell.text.trim().is_empty() {
+                Value::Null
+            } else {
+                parse_token(cell.text.trim()).map_err(|e| csv_error(line, e.message))?
+            };
+            row.insert(key.clone(), value);
+        }
+        block.rows.push(row);
+    }
+    Ok(block)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_csv() {
+        let csv = "id,name,note,manager\r\n1,Alice,\"says \"\"hi\"\", twice\",:2\n2,Bob,,\n\n3,\"42\",\"multi\nline\",true\n";
+        let block = from_csv(csv, "people").unwrap();
+        assert_eq!(block.fields, vec!["id", "name", "note", "manager"]);
+        assert_eq!(block.len(), 3);
+        assert_eq!(block[0]["note"], Value::String("says \"hi\", twice".into()));
+        assert!(block[0]["manager"].is_reference());
+        assert!(block[1]["note"].is_null());
+        assert_eq!(block[2]["name"], Value::String("42".into()));
+        assert_eq!(block[2]["note"], Value::String("multi\nline".into()));
+        assert_eq!(block[2]["manager"], Value::Bool(true));
+    }
+
+    #[test]
+    fn test_from_csv_errors() {
+        assert!(from_csv("", "t").is_err());
+        assert_eq!(from_csv("a,b\n1,2\n3\n", "t").unwrap_err().line, Some(3));
+        assert!(from_csv("a\n\"open", "t").is_err());
+    }
+}
@@ -0,0 +1,349 @@
+//! Row, column, and block mutation
+//!
+//! Rows and columns created here share the block's field-name keys (see
+//! [`Block::field_keys`]), like parsed rows do. Column operations keep
+//! `fields`, `field_info`, data rows, and summary rows in step.
+
+use alloc::collections::BTreeSet;
+
+use crate::prelude::*;
+use crate::{
+    intern, Block, Document, FieldInfo, FieldName, ISONError, Result, Row, Serializer, Value,
+};
+
+/// Which of a set of duplicate rows [`Block::dedup_by`] keeps
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Keep {
+    /// The earliest, in its position
+    #[default]
+    First,
+    /// The latest, in its position
+    Last,
+}
+
+impl Block {
+    /// Append a data row given values in field order.
+    ///
+    /// Fewer values than fields leaves the trailing fields absent, as the
+    /// parser does for short rows; more is an error.
+    pub fn push_row_values(&mut self, values: &[Value]) -> Result<()> {
+        if values.len() > self.fields.len() {
+            return Err(ISONError {
+                message: format!(
+                    "Block '{}' has {} fields, got {} values",
+                    self.name,
+                    self.fields.len(),
+                    values.len()
+                ),
+                line: None,
+            });
+        }
+        let keys = self.row_keys();
+        let row: Row = keys.into_iter().zip(values.iter().cloned()).collect();
+        self.rows.push(row);
+        Ok(())
+    }
+
+    /// Apply `update` to every data row matching `predicate`, returning how many matched
+    pub fn update_where<P, U>(&mut self, mut predicate: P, mut update: U) -> usize
+    where
+        P: FnMut(&Row) -> bool,
+        U: FnMut(&mut Row),
+    {
+        let mut count = 0;
+        for row in self.rows.iter_mut().filter(|row| predicate(row)) {
+            update(row);
+            count += 1;
+        }
+        count
+    }
+
+    /// Remove every data row matching `predicate`, returning how many were removed
+    pub fn delete_where<P>(&mut self, mut predicate: P) -> usize
+    where
+        P: FnMut(&Row) -> bool,
+    {
+        let before = self.rows.len();
+        self.rows.retain(|row| !predicate(row));
+        before - self.rows.len()
+    }
+
+    /// Remove data rows whose `keys` fields repeat an earlier (or, with
+    /// [`Keep::Last`], a later) row's, returning how many were removed.
+    ///
+    /// Empty `keys` compares whole rows. Absent cells count as null.
+    pub fn dedup_by(&mut self, keys: &[&str], keep: Keep) -> Result<usize> {
+        let fields: Vec<&str> = if keys.is_empty() {
+            self.fields.iter().map(String::as_str).collect()
+        } else {
+            for key in keys {
+                self.field_position(key)?;
+            }
+            keys.to_vec()
+        };
+
+        let serializer = Serializer::new(false);
+        let row_key = |row: &Row| -> Vec<String> {
+            fields
+                .iter()
+                .map(|f| serializer.serialize_value(row.get(*f).unwrap_or(&Value::Null)))
+                .collect()
+        };
+        let mut seen = BTreeSet::new();
+        let mut kept = vec![false; self.rows.len()];
+        let order: Vec<usize> = match keep {
+            Keep::First => (0..self.rows.len()).collect(),
+            Keep::Last => (0..self.rows.len()).rev().collect(),
+        };
+        for idx in order {
+            kept[idx] = seen.insert(row_key(&self.rows[idx]));
+        }
+
+        let before = self.rows.len();
+        let mut kept = kept.into_iter();
+        self.rows.retain(|_| kept.next().unwrap_or(true));
+        Ok(before - self.rows.len())
+    }
+
+    /// Add a column, setting `default` in every data row.
+    ///
+    /// An empty `field_type` adds the field without an annotation; a null
+    /// default leaves the new cells absent.
+    pub fn add_field(&mut self, name: &str, field_type: &str, default: Value) -> Result<()> {
+        if self.fields.iter().any(|f| f == name) {
+            return Err(self.column_error(format!("Field '{}' already exists", name)));
+        }
+        self.fields.push(name.to_string());
+        self.field_info.push(if field_type.is_empty() {
+            FieldInfo::new(name)
+        } else {
+            FieldInfo::with_type(name, field_type)
+        });
+        if !default.is_null() {
+            let key = FieldName::from(name);
+            for row in &mut self.rows {
+                row.insert(key.clone(), default.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Rename a column in the header and every row
+    pub fn rename_field(&mut self, from: &str, to: &str) -> Result<()> {
+        let idx = self.field_position(from)?;
+        if from != to && self.fields.iter().any(|f| f == to) {
+            return Err(self.column_error(format!("Field '{}' already exists", to)));
+        }
+        self.fields[idx] = to.to_string();
+        if let Some(info) = self.field_info.iter_mut().find(|fi| fi.name == from) {
+            info.name = to.to_string();
+        }
+        let key = FieldName::from(to);
+        for row in self.rows.iter_mut().chain(self.summary_rows.iter_mut()) {
+            if let Some(value) = row.remove(from) {
+                row.insert(key.clone(), value);
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove a column from the header and every row
+    pub fn drop_field(&mut self, name: &str) -> Result<()> {
+        let idx = self.field_position(name)?;
+        self.fields.remove(idx);
+        self.field_info.retain(|fi| fi.name != name);
+        for row in self.rows.iter_mut().chain(self.summary_rows.iter_mut()) {
+            row.remove(name);
+        }
+        Ok(())
+    }
+
+    /// Put the columns in this order; `order` must name every field exactly once
+    pub fn reorder_fields(&mut self, order: &[&str]) -> Result<()> {
+        let is_permutation = order.len() == self.fields.len()
+            && order
+                .iter()
+                .all(|name| self.fields.iter().any(|f| f == name))
+            && order
+                .iter()
+                .enumerate()
+                .all(|(i, name)| !order[..i].contains(name));
+        if !is_permutation {
+            return Err(self.column_error(format!(
+                "Field order must list each of {} exactly once",
+                self.fields.join(", ")
+            )));
+        }
+        self.fields = order.iter().map(|f| f.to_string()).collect();
+        let mut info = core::mem::take(&mut self.field_info);
+        for name in order {
+            if let Some(idx) = info.iter().position(|fi| fi.name == *name) {
+                self.field_info.push(info.remove(idx));
+            }
+        }
+        self.field_info.append(&mut info);
+        Ok(())
+    }
+
+    fn field_position(&self, name: &str) -> Result<usize> {
+        self.fields
+            .iter()
+            .position(|f| f == name)
+            .ok_or_else(|| self.column_error(format!("Unknown field '{}'", name)))
+    }
+
+    fn column_error(&self, message: String) -> ISONError {
+        ISONError {
+            message: format!("Block '{}': {}", self.name, message),
+            line: None,
+        }
+    }
+
+    /// Keys for a new row, reusing those of an existing row when possible
+    pub(crate) fn row_keys(&self) -> Vec<FieldName> {
+        match self.rows.first() {
+            Some(row) => {
+                let existing: Vec<_> = row.keys().cloned().collect();
+                self.fields.iter().map(|f| intern(&existing, f)).collect()
+            }
+            None => self.field_keys(),
+        }
+    }
+}
+
+impl Document {
+    /// [`Block::dedup_by`] for each `(block name, keys)` pair, returning the
+    /// rows removed per pair.
+    ///
+    /// Every block with that name is deduplicated on its own; a name with no
+    /// block removes nothing.
+    pub fn dedup_all(
+        &mut self,
+        keys_per_block: &[(&str, &[&str])],
+        keep: Keep,
+    ) -> Result<Vec<(String, usize)>> {
+        let mut removed = Vec::new();
+        for (name, keys) in keys_per_block {
+            let mut count = 0;
+            for block in self.blocks.iter_mut().filter(|b| b.name == *name) {
+                count += block.dedup_by(keys, keep)?;
+            }
+            removed.push((name.to_string(), count));
+        }
+        Ok(removed)
+    }
+
+    /// Remove and return the first block with this name
+    pub fn remove_block(&mut self, name: &str) -> Option<Block> {
+        let idx = self.blocks.iter().position(|b| b.name == name)?;
+        Some(self.blocks.remove(idx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dumps, parse};
+    use alloc::sync::Arc;
+
+    #[test]
+    fn test_row_mutation() {
+        let mut doc = parse("table.users\nid name active\n1 Alice true\n2 Bob false").unwrap();
+        let users = doc.get_mut("users").unwrap();
+
+        users
+            .push_row_values(&[Value::Int(3), Value::String("Carol".into())])
+            .unwrap();
+        assert_eq!(users[2]["name"], Value::String("Carol".into()));
+        assert!(!users[2].contains_key("active"));
+        let key = |row: &Row| row.get_key_value("id").unwrap().0.clone();
+        assert!(Arc::ptr_eq(&key(&users[0]), &key(&users[2])));
+        assert!(users.push_row_values(&vec![Value::Null; 4]).is_err());
+
+        let updated = users.update_where(
+            |row| row.get("active") != Some(&Value::Bool(true)),
+            |row| {
+                row.insert("active".into(), Value::Bool(true));
+            },
+        );
+        assert_eq!(updated, 2);
+        assert!(users.rows.iter().all(|r| r["active"] == Value::Bool(true)));
+
+        assert_eq!(users.delete_where(|row| row["id"] == Value::Int(2)), 1);
+        assert_eq!(users.len(), 2);
+
+        assert_eq!(doc.remove_block("users").unwrap().len(), 2);
+        assert!(doc.remove_block("users").is_none());
+        assert!(doc.is_empty());
+    }
+
+    #[test]
+    fn test_dedup() {
+        let text = "table.users\nid name\n1 Alice\n2 Bob\n1 \"Alice B\"\n2 Bob\n3 ~\n\n\
+                    table.tags\ntag\nx\ny\nx";
+        let mut doc = parse(text).unwrap();
+        let users = doc.get_mut("users").unwrap();
+        assert_eq!(users.dedup_by(&["id"], Keep::Last).unwrap(), 2);
+        let names: Vec<_> = users.rows.iter().map(|r| r["name"].to_string()).collect();
+        assert_eq!(names, ["Alice B", "Bob", "null"]);
+        assert!(users.dedup_by(&["email"], Keep::First).is_err());
+
+        let mut doc = parse(text).unwrap();
+        let removed = doc
+            .dedup_all(
+                &[("users", &[]), ("tags", &["tag"]), ("missing", &["id"])],
+                Keep::First,
+            )
+            .unwrap();
+        assert_eq!(
+            removed,
+            [
+                ("users".to_string(), 1),
+                ("tags".to_string(), 1),
+                ("missing".to_string(), 0)
+            ]
+        );
+        assert_eq!(doc["users"][2]["name"], Value::String("Alice B".into()));
+        assert_eq!(doc["tags"].len(), 2);
+    }
+
+    #[test]
+    fn test_column_operations() {
+        let mut doc = parse("table.items\nid name:string\n1 Pen\n2 Ink\n---\n~ total").unwrap();
+        let items = doc.get_mut("items").unwrap();
+
+        items
+            .add_field("price", "float", Value::Float(0.0))
+            .unwrap();
+        assert_eq!(items.get_field_type("price"), Some("float"));
+        assert_eq!(items[1]["price"], Value::Float(0.0));
+        assert!(items.add_field("id", "", Value::Null).is_err());
+
+        items.rename_field("name", "title").unwrap();
+        assert_eq!(items.get_field_type("title"), Some("string"));
+        assert_eq!(items[0]["title"], Value::String("Pen".into()));
+        assert_eq!(
+            items.summary_rows[0]["title"],
+            Value::String("total".into())
+        );
+        assert!(items.rename_field("title", "id").is_err());
+        assert!(items.rename_field("missing", "x").is_err());
+
+        items.reorder_fields(&["price", "title", "id"]).unwrap();
+        assert_eq!(items.fields, vec!["price", "title", "id"]);
+        let names: Vec<&str> = items.field_info.iter().map(|fi| fi.name.as_str()).collect();
+        assert_eq!(names, vec!["price", "title", "id"]);
+        assert!(items.reorder_fields(&["price", "price", "id"]).is_err());
+        assert!(items.reorder_fields(&["price", "id"]).is_err());
+
+        items.drop_field("title").unwrap();
+        assert_eq!(items.fields, vec!["price", "id"]);
+        assert!(!items[0].contains_key("title"));
+        assert!(!items.summary_rows[0].contains_key("title"));
+
+        assert_eq!(
+            dumps(&doc, false),
+            "table.items\nprice:float id\n0.0 1\n0.0 2\n---\nnull null"
+        );
+    }
+}
@@ -0,0 +1,187 @@
+//! Embedding columns
+//!
+//! Vector stores export embeddings as strings of bracketed floats, one per
+//! row, as the RudraDB plugin does:
+//!
+//! ```text
+//! table.vectors
+//! id embedding
+//! doc1 "[0.1, 0.25, -1]"
+//! doc2 "[0.3, 0.5, 0.75]"
+//! ```
+//!
+//! [`parse_embedding`] and [`format_embedding`] convert single cells. With
+//! the `ndarray` feature, [`Block::embeddings`] reads a whole column into an
+//! `Array2<f32>`, one row per data row, and [`Block::set_embeddings`] writes
+//! one back.
+
+use crate::prelude::*;
+#[cfg(feature = "ndarray")]
+use crate::{intern, Block, ISONError, Result, Value};
+
+/// The floats of an embedding cell such as `[0.1, 0.25, -1]`, or `None` if
+/// it is not a bracketed list of numbers.
+///
+/// Placeholders like `[768d vector]`, written in place of long vectors,
+/// are not embeddings.
+pub fn parse_embedding(text: &str) -> Option<Vec<f32>> {
+    let inner = text.trim().strip_prefix('[')?.strip_suffix(']')?;
+    inner
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse().ok())
+        .collect()
+}
+
+/// An embedding as a cell, `[0.1, 0.25, -1]`, with every float written so
+/// it parses back to the same value
+pub fn format_embedding(values: &[f32]) -> String {
+    let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+    format!("[{}]", values.join(", "))
+}
+
+#[cfg(feature = "ndarray")]
+impl Block {
+    /// The `field` column as a matrix with one row per data row, and the
+    /// `id_field` cell of each row alongside (`Value::Null` where a row has
+    /// none).
+    ///
+    /// Every row needs an embedding, all of the same length.
+    ///
+    /// ```rust
+    /// let doc = ison_rs::parse(
+    ///     "table.vectors\nid embedding\ndoc1 \"[1, 2]\"\ndoc2 \"[3, 4.5]\"",
+    /// )?;
+    /// let (ids, matrix) = doc["vectors"].embeddings("id", "embedding")?;
+    /// assert_eq!(ids[1].as_str(), Some("doc2"));
+    /// assert_eq!(matrix.dim(), (2, 2));
+    /// assert_eq!(matrix[[1, 1]], 4.5);
+    /// # Ok::<(), ison_rs::ISONError>(())
+    /// ```
+    pub fn embeddings(
+        &self,
+        id_field: &str,
+        field: &str,
+    ) -> Result<(Vec<Value>, ndarray::Array2<f32>)> {
+        let mut ids = Vec::with_capacity(self.rows.len());
+        let mut data = Vec::new();
+        let mut dim = None;
+        for (index, row) in self.rows.iter().enumerate() {
+            let values = match row.get(field) {
+                Some(Value::String(s)) => parse_embedding(s),
+                _ => None,
+            }
+            .ok_or_else(|| {
+                embedding_error(format!(
+                    "Row {} of block '{}' has no embedding in '{}'",
+                    index, self.name, field
+                ))
+            })?;
+            let expected = *dim.get_or_insert(values.len());
+            if values.len() != expected {
+                return Err(embedding_error(format!(
+                    "Row {} of block '{}' has a {}-dimensional embedding, expected {}",
+                    index,
+                    self.name,
+                    values.len(),
+                    expected
+                )));
+            }
+            ids.push(row.get(id_field).cloned().unwrap_or(Value::Null));
+            data.extend(values);
+        }
+        let matrix = ndarray::Array2::from_shape_vec((self.rows.len(), dim.unwrap_or(0)), data)
+            .expect("rows were checked to have the same length");
+        Ok((ids, matrix))
+    }
+
+    /// Store each row of `matrix` in the `field` cell of the matching data
+    /// row, adding the field if the block lacks it.
+    ///
+    /// `matrix` needs one row per data row.
+    pub fn set_embeddings(&mut self, field: &str, matrix: ndarray::ArrayView2<f32>) -> Result<()> {
+        if matrix.nrows() != self.rows.len() {
+            return Err(embedding_error(format!(
+                "{} embeddings for {} rows of block '{}'",
+                matrix.nrows(),
+                self.rows.len(),
+                self.name
+            )));
+        }
+        if !self.fields.iter().any(|f| f == field) {
+            self.fields.push(field.to_string());
+            self.field_info.push(crate::FieldInfo::new(field));
+        }
+        let key = intern(&self.field_keys(), field);
+        for (row, values) in self.rows.iter_mut().zip(matrix.rows()) {
+            let values: Vec<f32> = values.iter().copied().collect();
+            row.insert(key.clone(), Value::String(format_embedding(&values)));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "ndarray")]
+fn embedding_error(message: String) -> ISONError {
+    ISONError {
+        message,
+        line: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format_embedding() {
+        assert_eq!(
+            parse_embedding("[0.1, 0.25, -1]"),
+            Some(vec![0.1, 0.25, -1.0])
+        );
+        assert_eq!(parse_embedding(" [1,2 3] "), Some(vec![1.0, 2.0, 3.0]));
+        assert_eq!(parse_embedding("[]"), Some(vec![]));
+        assert_eq!(parse_embedding("[768d vector]"), None);
+        assert_eq!(parse_embedding("0.1, 0.2"), None);
+
+        let values = [0.1f32, 1.0 / 3.0, -2.5e-8];
+        assert_eq!(parse_embedding(&format_embedding(&values)).unwrap(), values);
+        assert_eq!(format_embedding(&[1.0, 0.5]), "[1, 0.5]");
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn test_block_embeddings() {
+        use crate::{dumps, parse};
+
+        let mut doc = parse("table.vectors\nid embedding\n1 \"[1, 2]\"\n2 \"[3, 4]\"").unwrap();
+        let (ids, matrix) = doc["vectors"].embeddings("id", "embedding").unwrap();
+        assert_eq!(ids, [Value::Int(1), Value::Int(2)]);
+        assert_eq!(matrix, ndarray::array![[1.0, 2.0], [3.0, 4.0]]);
+
+        let scaled = &matrix * 0.5;
+        let block = doc.get_mut("vectors").unwrap();
+        block.set_embeddings("half", scaled.view()).unwrap();
+        assert_eq!(block.embeddings("id", "half").unwrap().1, scaled);
+        assert!(dumps(&doc, false).contains("1 \"[1, 2]\" \"[0.5, 1]\""));
+
+        let block = doc.get_mut("vectors").unwrap();
+        let err = block
+            .set_embeddings("half", matrix.slice(ndarray::s![..1, ..]))
+            .unwrap_err();
+        assert_eq!(err.message, "1 embeddings for 2 rows of block 'vectors'");
+
+        let doc = parse("table.vectors\nid embedding\n1 \"[1, 2]\"\n2 \"[3]\"\n3 \"[2d vector]\"")
+            .unwrap();
+        let err = doc["vectors"].embeddings("id", "embedding").unwrap_err();
+        assert_eq!(
+            err.message,
+            "Row 1 of block 'vectors' has a 1-dimensional embedding, expected 2"
+        );
+        let err = doc["vectors"].embeddings("id", "vector").unwrap_err();
+        assert_eq!(
+            err.message,
+            "Row 0 of block 'vectors' has no embedding in 'vector'"
+        );
+    }
+}
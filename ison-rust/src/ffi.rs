@@ -0,0 +1,417 @@
+//! C API for embedding the parser in other languages
+//!
+//! Enabled by the `ffi` feature; the header is `include/ison.h`, generated
+//! with `cbindgen --config cbindgen.toml --output include/ison.h`.
+//!
+//! ## Ownership
+//!
+//! - `IsonDocument` pointers come from [`ison_parse`] and are released with
+//!   [`ison_free_document`].
+//! - `IsonBlock` and `IsonValue` pointers borrow from their document and are
+//!   valid until it is freed.
+//! - Every `char *` returned by a function is owned by the caller and must be
+//!   released with [`ison_free_string`]; `const char *` results are static.
+//!
+//! Functions never panic across the boundary: null or out-of-range arguments
+//! yield null pointers, zero counts, or `ISON_VALUE_TYPE_NULL`.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+use crate::{Block, Document, Value};
+
+/// Opaque handle to a parsed document
+pub struct IsonDocument {
+    _private: [u8; 0],
+}
+
+/// Opaque handle to a block borrowed from a document
+pub struct IsonBlock {
+    _private: [u8; 0],
+}
+
+/// Opaque handle to a value borrowed from a document
+pub struct IsonValue {
+    _private: [u8; 0],
+}
+
+/// Type tag of an `IsonValue`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IsonValueType {
+    Null = 0,
+    Bool = 1,
+    Int = 2,
+    Float = 3,
+    String = 4,
+    Reference = 5,
+    /// Exact decimal (`rust_decimal` builds); read it with `ison_value_to_string`
+    Decimal = 6,
+    /// Binary data; read it with `ison_value_bytes`
+    Bytes = 7,
+}
+
+// Handles are the Rust objects themselves behind opaque pointer types
+
+fn block_ptr(block: &Block) -> *const IsonBlock {
+    (block as *const Block).cast()
+}
+
+fn value_ptr(value: &Value) -> *const IsonValue {
+    (value as *const Value).cast()
+}
+
+unsafe fn doc_ref<'a>(doc: *const IsonDocument) -> Option<&'a Document> {
+    doc.cast::<Document>().as_ref()
+}
+
+unsafe fn block_ref<'a>(block: *const IsonBlock) -> Option<&'a Block> {
+    block.cast::<Block>().as_ref()
+}
+
+unsafe fn value_ref<'a>(value: *const IsonValue) -> Option<&'a Value> {
+    value.cast::<Value>().as_ref()
+}
+
+/// Owned C string, or null if `s` contains an interior NUL
+fn c_string(s: &str) -> *mut c_char {
+    CString::new(s).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+unsafe fn str_arg<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    CStr::from_ptr(s).to_str().ok()
+}
+
+unsafe fn set_error(error_out: *mut *mut c_char, message: &str) {
+    if !error_out.is_null() {
+        *error_out = c_string(message);
+    }
+}
+
+/// Parse NUL-terminated UTF-8 ISON text.
+///
+/// Returns null on failure and, if `error_out` is not null, stores an owned
+/// error message there.
+#[no_mangle]
+pub unsafe extern "C" fn ison_parse(
+    text: *const c_char,
+    error_out: *mut *mut c_char,
+) -> *mut IsonDocument {
+    let Some(text) = str_arg(text) else {
+        set_error(error_out, "text must be non-null UTF-8");
+        return ptr::null_mut();
+    };
+    match crate::parse(text) {
+        Ok(doc) => Box::into_raw(Box::new(doc)).cast(),
+        Err(e) => {
+            set_error(error_out, &e.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Serialize a document to ISON text
+#[no_mangle]
+pub unsafe extern "C" fn ison_dumps(doc: *const IsonDocument, align_columns: bool) -> *mut c_char {
+    match doc_ref(doc) {
+        Some(doc) => c_string(&crate::dumps(doc, align_columns)),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Release a document from `ison_parse`; null is ignored
+#[no_mangle]
+pub unsafe extern "C" fn ison_free_document(doc: *mut IsonDocument) {
+    if !doc.is_null() {
+        drop(Box::from_raw(doc.cast::<Document>()));
+    }
+}
+
+/// Release a string returned by this library; null is ignored
+#[no_mangle]
+pub unsafe extern "C" fn ison_free_string(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Library version, e.g. `"1.0.1"` (static, do not free)
+#[no_mangle]
+pub extern "C" fn ison_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
+
+// ----------------------------------------------------------------------------
+// Blocks
+// ----------------------------------------------------------------------------
+
+#[no_mangle]
+pub unsafe extern "C" fn ison_document_block_count(doc: *const IsonDocument) -> usize {
+    doc_ref(doc).map_or(0, |d| d.blocks.len())
+}
+
+/// Block by position, or null
+#[no_mangle]
+pub unsafe extern "C" fn ison_document_block(
+    doc: *const IsonDocument,
+    index: usize,
+) -> *const IsonBlock {
+    doc_ref(doc)
+        .and_then(|d| d.blocks.get(index))
+        .map_or(ptr::null(), block_ptr)
+}
+
+/// Block by name, or null
+#[no_mangle]
+pub unsafe extern "C" fn ison_document_get_block(
+    doc: *const IsonDocument,
+    name: *const c_char,
+) -> *const IsonBlock {
+    match (doc_ref(doc), str_arg(name)) {
+        (Some(doc), Some(name)) => doc.get(name).map_or(ptr::null(), block_ptr),
+        _ => ptr::null(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ison_block_kind(block: *const IsonBlock) -> *mut c_char {
+    block_ref(block).map_or(ptr::null_mut(), |b| c_string(&b.kind))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ison_block_name(block: *const IsonBlock) -> *mut c_char {
+    block_ref(block).map_or(ptr::null_mut(), |b| c_string(&b.name))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ison_block_field_count(block: *const IsonBlock) -> usize {
+    block_ref(block).map_or(0, |b| b.fields.len())
+}
+
+/// Field name by position, or null
+#[no_mangle]
+pub unsafe extern "C" fn ison_block_field_name(
+    block: *const IsonBlock,
+    index: usize,
+) -> *mut c_char {
+    block_ref(block)
+        .and_then(|b| b.fields.get(index))
+        .map_or(ptr::null_mut(), |f| c_string(f))
+}
+
+/// Number of data rows
+#[no_mangle]
+pub unsafe extern "C" fn ison_block_row_count(block: *const IsonBlock) -> usize {
+    block_ref(block).map_or(0, |b| b.rows.len())
+}
+
+/// Number of summary rows (after `---`)
+#[no_mangle]
+pub unsafe extern "C" fn ison_block_summary_row_count(block: *const IsonBlock) -> usize {
+    block_ref(block).map_or(0, |b| b.summary_rows.len())
+}
+
+/// Value of `field` in data row `row`, or null when either is missing
+#[no_mangle]
+pub unsafe extern "C" fn ison_block_value(
+    block: *const IsonBlock,
+    row: usize,
+    field: *const c_char,
+) -> *const IsonValue {
+    match (block_ref(block), str_arg(field)) {
+        (Some(block), Some(field)) => block
+            .rows
+            .get(row)
+            .and_then(|r| r.get(field))
+            .map_or(ptr::null(), value_ptr),
+        _ => ptr::null(),
+    }
+}
+
+/// Value of `field` in summary row `row`, or null when either is missing
+#[no_mangle]
+pub unsafe extern "C" fn ison_block_summary_value(
+    block: *const IsonBlock,
+    row: usize,
+    field: *const c_char,
+) -> *const IsonValue {
+    match (block_ref(block), str_arg(field)) {
+        (Some(block), Some(field)) => block
+            .summary_rows
+            .get(row)
+            .and_then(|r| r.get(field))
+            .map_or(ptr::null(), value_ptr),
+        _ => ptr::null(),
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Values
+// ----------------------------------------------------------------------------
+
+/// Type of a value; null pointers report `ISON_VALUE_TYPE_NULL`
+#[no_mangle]
+pub unsafe extern "C" fn ison_value_type(value: *const IsonValue) -> IsonValueType {
+    match value_ref(value) {
+        None | Some(Value::Null) => IsonValueType::Null,
+        Some(Value::Bool(_)) => IsonValueType::Bool,
+        Some(Value::Int(_)) => IsonValueType::Int,
+        Some(Value::Float(_)) => IsonValueType::Float,
+        Some(Value::String(_)) => IsonValueType::String,
+        Some(Value::Reference(_)) => IsonValueType::Reference,
+        Some(Value::Bytes(_)) => IsonValueType::Bytes,
+        #[cfg(feature = "rust_decimal")]
+        Some(Value::Decimal(_)) => IsonValueType::Decimal,
+    }
+}
+
+/// Boolean value, false for other types
+#[no_mangle]
+pub unsafe extern "C" fn ison_value_as_bool(value: *const IsonValue) -> bool {
+    value_ref(value).and_then(|v| v.as_bool()).unwrap_or(false)
+}
+
+/// Integer value, 0 for other types
+#[no_mangle]
+pub unsafe extern "C" fn ison_value_as_int(value: *const IsonValue) -> i64 {
+    value_ref(value).and_then(|v| v.as_int()).unwrap_or(0)
+}
+
+/// Float value (integers widen), NaN for other types
+#[no_mangle]
+pub unsafe extern "C" fn ison_value_as_float(value: *const IsonValue) -> f64 {
+    value_ref(value)
+        .and_then(|v| v.as_float())
+        .unwrap_or(f64::NAN)
+}
+
+/// String contents for strings, ISON text (e.g. `:user:1`, `42`, `null`) otherwise
+#[no_mangle]
+pub unsafe extern "C" fn ison_value_to_string(value: *const IsonValue) -> *mut c_char {
+    match value_ref(value) {
+        Some(Value::String(s)) => c_string(s),
+        Some(Value::Reference(r)) => c_string(&r.to_ison()),
+        Some(other) => c_string(&other.to_string()),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Reference id, or null for other types
+#[no_mangle]
+pub unsafe extern "C" fn ison_value_reference_id(value: *const IsonValue) -> *mut c_char {
+    value_ref(value)
+        .and_then(|v| v.as_reference())
+        .map_or(ptr::null_mut(), |r| c_string(&r.id))
+}
+
+/// Reference type or relationship (`user` in `:user:1`), or null
+#[no_mangle]
+pub unsafe extern "C" fn ison_value_reference_type(value: *const IsonValue) -> *mut c_char {
+    value_ref(value)
+        .and_then(|v| v.as_reference())
+        .and_then(|r| r.ref_type.as_deref())
+        .map_or(ptr::null_mut(), c_string)
+}
+
+/// Contents of a bytes value, borrowed from its document, with the length
+/// stored in `len` (if non-null); null and a length of 0 for other types
+#[no_mangle]
+pub unsafe extern "C" fn ison_value_bytes(value: *const IsonValue, len: *mut usize) -> *const u8 {
+    let bytes = value_ref(value).and_then(|v| v.as_bytes());
+    if !len.is_null() {
+        *len = bytes.map_or(0, <[u8]>::len);
+    }
+    bytes.map_or(ptr::null(), <[u8]>::as_ptr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn take(s: *mut c_char) -> String {
+        assert!(!s.is_null());
+        let owned = CStr::from_ptr(s).to_str().unwrap().to_string();
+        ison_free_string(s);
+        owned
+    }
+
+    #[test]
+    fn test_ffi_roundtrip() {
+        unsafe {
+            let text = CString::new("table.users\nid name boss\n1 Alice :user:2\n2 Bob ~").unwrap();
+            let doc = ison_parse(text.as_ptr(), ptr::null_mut());
+            assert!(!doc.is_null());
+            assert_eq!(ison_document_block_count(doc), 1);
+
+            let name = CString::new("users").unwrap();
+            let block = ison_document_get_block(doc, name.as_ptr());
+            assert_eq!(block, ison_document_block(doc, 0));
+            assert_eq!(take(ison_block_name(block)), "users");
+            assert_eq!(ison_block_field_count(block), 3);
+            assert_eq!(take(ison_block_field_name(block, 1)), "name");
+            assert_eq!(ison_block_row_count(block), 2);
+
+            let id = CString::new("id").unwrap();
+            let boss = CString::new("boss").unwrap();
+            let v = ison_block_value(block, 0, id.as_ptr());
+            assert_eq!(ison_value_type(v), IsonValueType::Int);
+            assert_eq!(ison_value_as_int(v), 1);
+            let r = ison_block_value(block, 0, boss.as_ptr());
+            assert_eq!(ison_value_type(r), IsonValueType::Reference);
+            assert_eq!(take(ison_value_reference_id(r)), "2");
+            assert_eq!(take(ison_value_reference_type(r)), "user");
+            assert_eq!(take(ison_value_to_string(r)), ":user:2");
+            let n = ison_block_value(block, 1, boss.as_ptr());
+            assert_eq!(ison_value_type(n), IsonValueType::Null);
+            let mut len = 9;
+            assert!(ison_value_bytes(n, &mut len).is_null());
+            assert_eq!(len, 0);
+            assert!(ison_block_value(block, 9, id.as_ptr()).is_null());
+
+            assert!(take(ison_dumps(doc, false)).starts_with("table.users\nid name boss"));
+            ison_free_document(doc);
+        }
+    }
+
+    #[test]
+    fn test_ffi_bytes() {
+        unsafe {
+            let text = CString::new("table.files\nhash\nb64:3q2+7w==").unwrap();
+            let doc = ison_parse(text.as_ptr(), ptr::null_mut());
+            let hash = CString::new("hash").unwrap();
+            let v = ison_block_value(ison_document_block(doc, 0), 0, hash.as_ptr());
+            assert_eq!(ison_value_type(v), IsonValueType::Bytes);
+            let mut len = 0;
+            let data = ison_value_bytes(v, &mut len);
+            assert_eq!(
+                std::slice::from_raw_parts(data, len),
+                [0xde, 0xad, 0xbe, 0xef]
+            );
+            ison_free_document(doc);
+        }
+    }
+
+    #[test]
+    fn test_ffi_errors() {
+        unsafe {
+            let mut error = ptr::null_mut();
+            let text = CString::new("no header here").unwrap();
+            assert!(ison_parse(text.as_ptr(), &mut error).is_null());
+            assert!(take(error).contains("Invalid block header"));
+
+            assert!(ison_parse(ptr::null(), ptr::null_mut()).is_null());
+            assert_eq!(ison_document_block_count(ptr::null()), 0);
+            assert_eq!(ison_value_type(ptr::null()), IsonValueType::Null);
+            ison_free_document(ptr::null_mut());
+            assert_eq!(
+                CStr::from_ptr(ison_version()).to_str().unwrap(),
+                crate::VERSION
+            );
+        }
+    }
+}
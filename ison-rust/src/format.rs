@@ -0,0 +1,370 @@
+//! Canonical formatting of ISON text
+//!
+//! [`format_ison`] re-lays out a document without re-serializing it: every
+//! header, field, and value keeps its spelling, and comments stay where
+//! they were. Only whitespace changes, which makes it safe to run from a
+//! pre-commit hook. Use [`dumps_with_options`](crate::dumps_with_options)
+//! instead to normalize values as well.
+
+use crate::incremental::TextEdit;
+use crate::prelude::*;
+use crate::{dumps, lex, parse, ISONError, Result, TokenKind};
+
+/// Layout options for [`format_ison`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatStyle {
+    /// Pad the cells of each column, field names included, to a common width
+    pub align_columns: bool,
+    /// Spaces between columns, at least 1
+    pub column_padding: usize,
+    /// Empty lines between blocks
+    pub blank_lines: usize,
+    /// Keep `#` comments; when false they are removed
+    pub keep_comments: bool,
+}
+
+impl Default for FormatStyle {
+    fn default() -> Self {
+        Self {
+            align_columns: false,
+            column_padding: 1,
+            blank_lines: 1,
+            keep_comments: true,
+        }
+    }
+}
+
+/// A line of a block's body
+enum Line {
+    Comment(String),
+    Cells {
+        cells: Vec<String>,
+        comment: Option<String>,
+    },
+    Separator,
+}
+
+/// A block and the comments directly before it
+#[derive(Default)]
+struct Section {
+    leading: Vec<String>,
+    header: String,
+    lines: Vec<Line>,
+}
+
+/// `text[range]`, or an error if a token range does not fit the text
+fn slice(text: &str, range: core::ops::Range<usize>) -> Result<&str> {
+    text.get(range.clone()).ok_or_else(|| ISONError {
+        message: format!("Token range {:?} is not within the text", range),
+        line: None,
+    })
+}
+
+/// Split `text` into blocks from its tokens; see [`lex`]
+fn sections(text: &str) -> Result<(Vec<Section>, Vec<String>)> {
+    let mut sections: Vec<Section> = Vec::new();
+    // Comments after a blank line, waiting for the next header
+    let mut loose: Vec<String> = Vec::new();
+    let mut in_block = false;
+    let mut prev_end = 0;
+    let mut tokens = lex(text).peekable();
+
+    while let Some(token) = tokens.next() {
+        let gap = slice(text, prev_end..token.span.start)?;
+        let new_line = prev_end == 0 || gap.contains('\n');
+        if gap.matches('\n').count() > 1 {
+            in_block = false;
+        }
+        prev_end = token.span.end;
+        let token_text = slice(text, token.span.clone())?.to_string();
+
+        match token.kind {
+            TokenKind::Header => {
+                sections.push(Section {
+                    leading: core::mem::take(&mut loose),
+                    header: token_text,
+                    lines: Vec::new(),
+                });
+                in_block = true;
+            }
+            TokenKind::Comment if new_line => match sections.last_mut() {
+                Some(section) if in_block => section.lines.push(Line::Comment(token_text)),
+                _ => loose.push(token_text),
+            },
+            TokenKind::Comment => {
+                if let Some(Line::Cells { comment, .. }) =
+                    sections.last_mut().and_then(|s| s.lines.last_mut())
+                {
+                    *comment = Some(token_text);
+                }
+            }
+            TokenKind::SummarySeparator => {
+                if let Some(section) = sections.last_mut() {
+                    section.lines.push(Line::Separator);
+                }
+            }
+            _ => {
+                let Some(section) = sections.last_mut() else {
+                    continue;
+                };
+                let mut cell = token_text;
+                // A type annotation joins its field name across the colon
+                if token.kind == TokenKind::Field {
+                    if let Some(annotation) = tokens.next_if(|t| {
+                        t.kind == TokenKind::TypeAnnotation
+                            && text.get(token.span.end..t.span.start) == Some(":")
+                    }) {
+                        cell = slice(text, token.span.start..annotation.span.end)?.to_string();
+                        prev_end = annotation.span.end;
+                    }
+                }
+                match section.lines.last_mut() {
+                    Some(Line::Cells {
+                        cells,
+                        comment: None,
+                    }) if !new_line => cells.push(cell),
+                    _ => section.lines.push(Line::Cells {
+                        cells: vec![cell],
+                        comment: None,
+                    }),
+                }
+            }
+        }
+    }
+    Ok((sections, loose))
+}
+
+fn width(cell: &str) -> Option<usize> {
+    (!cell.contains('\n')).then(|| cell.chars().count())
+}
+
+/// Reformat `text` according to `style`.
+///
+/// Fails, like [`parse`], if `text` is not valid ISON, and also in the rare
+/// case that the new layout would parse differently. Lines end in `\n`, and
+/// the result ends with a newline if `text` did.
+///
+/// ```rust
+/// use ison_rs::{format_ison, FormatStyle};
+///
+/// let text = "# people\ntable.users\nid   name\n1 Alice   # admin\n22    Bob\n\n\n\ntable.empty\nx";
+/// let style = FormatStyle { align_columns: true, ..Default::default() };
+/// assert_eq!(
+///     format_ison(text, &style)?,
+///     "# people\ntable.users\nid name\n1  Alice # admin\n22 Bob\n\ntable.empty\nx"
+/// );
+/// # Ok::<(), ison_rs::ISONError>(())
+/// ```
+pub fn format_ison(text: &str, style: &FormatStyle) -> Result<String> {
+    let original = dumps(&parse(text)?, false);
+    let (sections, trailing) = sections(text)?;
+    let gap = " ".repeat(style.column_padding.max(1));
+    let mut out: Vec<String> = Vec::new();
+    let keep = |comments: &[String]| -> Vec<String> {
+        if style.keep_comments {
+            comments.to_vec()
+        } else {
+            Vec::new()
+        }
+    };
+
+    for (i, section) in sections.iter().enumerate() {
+        if i > 0 {
+            out.extend(core::iter::repeat_n(String::new(), style.blank_lines));
+        }
+        out.extend(keep(&section.leading));
+        out.push(section.header.clone());
+
+        let mut widths: Vec<usize> = Vec::new();
+        if style.align_columns {
+            for line in &section.lines {
+                if let Line::Cells { cells, .. } = line {
+                    if widths.len() < cells.len() {
+                        widths.resize(cells.len(), 0);
+                    }
+                    for (w, cell) in widths.iter_mut().zip(cells) {
+                        *w = (*w).max(width(cell).unwrap_or(0));
+                    }
+                }
+            }
+        }
+
+        for line in &section.lines {
+            match line {
+                Line::Comment(comment) if style.keep_comments => out.push(comment.clone()),
+                Line::Comment(_) => {}
+                Line::Separator => out.push("---".to_string()),
+                Line::Cells { cells, comment } => {
+                    let mut text = String::new();
+                    for (j, cell) in cells.iter().enumerate() {
+                        if j > 0 {
+                            text.push_str(&gap);
+                        }
+                        text.push_str(cell);
+                        let last = j + 1 == cells.len();
+                        if let (false, Some(w), Some(&target)) = (last, width(cell), widths.get(j))
+                        {
+                            text.extend(core::iter::repeat_n(' ', target.saturating_sub(w)));
+                        }
+                    }
+                    if let (true, Some(comment)) = (style.keep_comments, comment) {
+                        text.push_str(&gap);
+                        text.push_str(comment);
+                    }
+                    out.push(text);
+                }
+            }
+        }
+    }
+
+    let trailing = keep(&trailing);
+    if !trailing.is_empty() {
+        if !sections.is_empty() {
+            out.extend(core::iter::repeat_n(String::new(), style.blank_lines));
+        }
+        out.extend(trailing);
+    }
+
+    let mut formatted = out.join("\n");
+    if text.ends_with('\n') && !formatted.is_empty() {
+        formatted.push('\n');
+    }
+
+    // Whitespace the parser does not skip, or `blank_lines: 0` before a
+    // header that only a blank line marks, can make a layout mean something
+    // else; never hand that back
+    if parse(&formatted).map(|doc| dumps(&doc, false)).ok() != Some(original) {
+        return Err(ISONError {
+            message: "Formatting would change the document; format it by hand".to_string(),
+            line: None,
+        });
+    }
+    Ok(formatted)
+}
+
+/// [`format_ison`] as edits to `text`: none if it is already formatted,
+/// otherwise one replacing the span that differs
+pub fn format_edits(text: &str, style: &FormatStyle) -> Result<Vec<TextEdit>> {
+    let formatted = format_ison(text, style)?;
+    if formatted == text {
+        return Ok(Vec::new());
+    }
+
+    let mut start = text
+        .bytes()
+        .zip(formatted.bytes())
+        .take_while(|(a, b)| a == b)
+        .count();
+    while !text.is_char_boundary(start) || !formatted.is_char_boundary(start) {
+        start -= 1;
+    }
+    let max_suffix = text.len().min(formatted.len()) - start;
+    let mut suffix = text
+        .bytes()
+        .rev()
+        .zip(formatted.bytes().rev())
+        .take(max_suffix)
+        .take_while(|(a, b)| a == b)
+        .count();
+    while !text.is_char_boundary(text.len() - suffix)
+        || !formatted.is_char_boundary(formatted.len() - suffix)
+    {
+        suffix -= 1;
+    }
+
+    Ok(vec![TextEdit::new(
+        start..text.len() - suffix,
+        &formatted[start..formatted.len() - suffix],
+    )])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "# Inventory export\n\n\
+        table.items   \nid   name:string    price # header comment\n\
+        # first row\n1 \"Pen, blue\"   2.50\n200 Ink  10\n---\ntotal ~    12.5 # sum\n\n\n\
+        # settings\nobject.config\nkey    value\nmode fast\nretries:int   3\n\n\
+        table.notes\nid text\n1 \"\"\"a\n  b\"\"\"\n\n# end\n";
+
+    #[test]
+    fn test_format_ison() {
+        let aligned = FormatStyle {
+            align_columns: true,
+            ..Default::default()
+        };
+        let formatted = format_ison(TEXT, &aligned).unwrap();
+        assert_eq!(
+            formatted,
+            "# Inventory export\n\
+             table.items\n\
+             id    name:string price # header comment\n\
+             # first row\n\
+             1     \"Pen, blue\" 2.50\n\
+             200   Ink         10\n\
+             ---\n\
+             total ~           12.5 # sum\n\
+             \n\
+             # settings\n\
+             object.config\n\
+             key         value\n\
+             mode        fast\n\
+             retries:int 3\n\
+             \n\
+             table.notes\n\
+             id text\n\
+             1  \"\"\"a\n  b\"\"\"\n\
+             \n\
+             # end\n"
+        );
+        assert_eq!(
+            dumps(&parse(&formatted).unwrap(), false),
+            dumps(&parse(TEXT).unwrap(), false)
+        );
+        assert_eq!(format_ison(&formatted, &aligned).unwrap(), formatted);
+
+        let compact = FormatStyle {
+            keep_comments: false,
+            blank_lines: 2,
+            column_padding: 2,
+            ..Default::default()
+        };
+        let formatted = format_ison(TEXT, &compact).unwrap();
+        assert!(!formatted.contains('#'));
+        assert!(formatted.starts_with("table.items\nid  name:string  price\n"));
+        assert!(formatted.contains("total  ~  12.5\n\n\nobject.config\n"));
+
+        assert!(format_ison("table.t\nx\n:a:b:c", &aligned).is_err());
+        // Only the blank line ends the first block here
+        let glued = FormatStyle {
+            blank_lines: 0,
+            ..Default::default()
+        };
+        assert!(format_ison("table.a\nx\n1\n\n:.b\ny", &glued).is_err());
+
+        // Quoted and non-ASCII field names keep their annotations
+        let text = "table.t\n\"é:int\"   b\n1 2";
+        assert_eq!(
+            format_ison(text, &FormatStyle::default()).unwrap(),
+            "table.t\n\"é:int\" b\n1 2"
+        );
+        // Used to panic slicing inside `é`
+        assert_eq!(
+            format_ison("a.e\n\"é:", &FormatStyle::default()).unwrap(),
+            "a.e\n\"é:"
+        );
+    }
+
+    #[test]
+    fn test_format_edits() {
+        let style = FormatStyle::default();
+        let text = "table.t\nx   y\n1   é\n";
+        let edits = format_edits(text, &style).unwrap();
+        assert_eq!(edits.len(), 1);
+        let mut doc = parse(text).unwrap();
+        let applied = doc.apply_edit(text, &edits[0]).unwrap().text;
+        assert_eq!(applied, format_ison(text, &style).unwrap());
+        assert!(format_edits(&applied, &style).unwrap().is_empty());
+    }
+}
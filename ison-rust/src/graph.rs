@@ -0,0 +1,335 @@
+//! Graphs built from relationship references
+//!
+//! Every row with an `id` is a node. A relationship reference in one of its
+//! cells (`:MANAGES:2`, any reference whose type is UPPERCASE) is a directed
+//! edge from that row's id to the referenced id, labelled with the type.
+//!
+//! ```text
+//! table.people
+//! id name reports_to
+//! 1 Alice ~
+//! 2 Bob :REPORTS_TO:1
+//! ```
+
+use alloc::collections::{BTreeMap, VecDeque};
+
+use crate::prelude::*;
+use crate::{Document, Value};
+
+/// A directed, labelled edge between two node ids
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    /// Reference type, e.g. `MANAGES`
+    pub relationship: String,
+    /// Block and field the reference was found in
+    pub block: String,
+    pub field: String,
+}
+
+/// Nodes and relationship edges of a document; see [`Document::to_graph`]
+#[derive(Debug, Clone, Default)]
+pub struct Graph {
+    nodes: Vec<String>,
+    index: BTreeMap<String, usize>,
+    edges: Vec<Edge>,
+    /// Outgoing edge indexes per node
+    outgoing: Vec<Vec<usize>>,
+}
+
+impl Graph {
+    /// Node ids, rows first (in document order) and then any ids that are
+    /// only referenced
+    pub fn nodes(&self) -> &[String] {
+        &self.nodes
+    }
+
+    /// Edges in document order
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    /// Whether `id` is a node
+    pub fn contains(&self, id: &str) -> bool {
+        self.index.contains_key(id)
+    }
+
+    /// Distinct targets of the edges leaving `id`
+    pub fn neighbors(&self, id: &str) -> Vec<&str> {
+        let mut out: Vec<&str> = Vec::new();
+        if let Some(&node) = self.index.get(id) {
+            for &edge in &self.outgoing[node] {
+                let to = self.edges[edge].to.as_str();
+                if !out.contains(&to) {
+                    out.push(to);
+                }
+            }
+        }
+        out
+    }
+
+    /// Whether a chain of edges leads from `from` to `to`; a node reaches itself
+    pub fn path_exists(&self, from: &str, to: &str) -> bool {
+        let (Some(&start), Some(&goal)) = (self.index.get(from), self.index.get(to)) else {
+            return false;
+        };
+        let mut seen = vec![false; self.nodes.len()];
+        let mut queue = VecDeque::from([start]);
+        seen[start] = true;
+        while let Some(node) = queue.pop_front() {
+            if node == goal {
+                return true;
+            }
+            for &edge in &self.outgoing[node] {
+                let next = self.index[&self.edges[edge].to];
+                if !seen[next] {
+                    seen[next] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+        false
+    }
+
+    /// Graphviz DOT, with the relationship as each edge's label
+    pub fn to_dot(&self) -> String {
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+        let mut out = String::from("digraph ison {\n");
+        for node in &self.nodes {
+            out.push_str(&format!("  {};\n", quote(node)));
+        }
+        for edge in &self.edges {
+            out.push_str(&format!(
+                "  {} -> {} [label={}];\n",
+                quote(&edge.from),
+                quote(&edge.to),
+                quote(&edge.relationship)
+            ));
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// GraphML, with the relationship as a `relationship` edge attribute
+    pub fn to_graphml(&self) -> String {
+        let mut out = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n  \
+             <key id=\"relationship\" for=\"edge\" attr.name=\"relationship\" attr.type=\"string\"/>\n  \
+             <graph id=\"ison\" edgedefault=\"directed\">\n",
+        );
+        for node in &self.nodes {
+            out.push_str(&format!("    <node id=\"{}\"/>\n", xml_escape(node)));
+        }
+        for edge in &self.edges {
+            out.push_str(&format!(
+                "    <edge source=\"{}\" target=\"{}\"><data key=\"relationship\">{}</data></edge>\n",
+                xml_escape(&edge.from),
+                xml_escape(&edge.to),
+                xml_escape(&edge.relationship)
+            ));
+        }
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+
+    fn node(&mut self, id: &str) -> usize {
+        if let Some(&node) = self.index.get(id) {
+            return node;
+        }
+        self.nodes.push(id.to_string());
+        self.outgoing.push(Vec::new());
+        self.index.insert(id.to_string(), self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Node id of a row's `id` cell
+fn id_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Reference(r) => r.id.clone(),
+        other => other.to_string(),
+    }
+}
+
+impl Document {
+    /// Graph of every row with an `id` and the relationship references in its cells
+    pub fn to_graph(&self) -> Graph {
+        let mut graph = Graph::default();
+        let mut pending = Vec::new();
+        for block in &self.blocks {
+            for row in &block.rows {
+                let Some(id) = row.get("id").map(id_text) else {
+                    continue;
+                };
+                let from = graph.node(&id);
+                for field in &block.fields {
+                    if let Some(Value::Reference(r)) = row.get(field.as_str()) {
+                        if r.is_relationship() {
+                            pending.push((from, r, block, field));
+                        }
+                    }
+                }
+            }
+        }
+        // Edges after nodes, so ids that only appear as targets come last
+        for (from, r, block, field) in pending {
+            let to = graph.node(&r.id);
+            graph.outgoing[from].push(graph.edges.len());
+            graph.edges.push(Edge {
+                from: graph.nodes[from].clone(),
+                to: graph.nodes[to].clone(),
+                relationship: r.ref_type.clone().unwrap_or_default(),
+                block: block.name.clone(),
+                field: field.clone(),
+            });
+        }
+        graph
+    }
+}
+
+/// Node weight of [`Document::to_petgraph`]: a row, by position
+#[cfg(feature = "petgraph")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowRef {
+    pub block: String,
+    /// Index into the block's data rows
+    pub row: usize,
+    /// The row's `id` cell, if it has one
+    pub id: Option<Value>,
+}
+
+/// Edge weight of [`Document::to_petgraph`]: the reference that links two rows
+#[cfg(feature = "petgraph")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelationshipEdge {
+    /// Field of the source row holding the reference
+    pub field: String,
+    pub reference: crate::Reference,
+}
+
+#[cfg(feature = "petgraph")]
+impl RelationshipEdge {
+    /// Relationship type, e.g. `MANAGES`, for UPPERCASE reference types
+    pub fn relationship(&self) -> Option<&str> {
+        self.reference.relationship_type()
+    }
+}
+
+#[cfg(feature = "petgraph")]
+impl Document {
+    /// Every data row as a node, and an edge for every reference cell that
+    /// [resolves](Document::resolve) to a row.
+    ///
+    /// Unlike [`to_graph`](Document::to_graph), rows need no `id` and any
+    /// reference counts, not only relationships; references to missing rows
+    /// are skipped.
+    pub fn to_petgraph(&self) -> petgraph::graph::DiGraph<RowRef, RelationshipEdge> {
+        let mut graph = petgraph::graph::DiGraph::new();
+        let mut nodes = BTreeMap::new();
+        for block in &self.blocks {
+            for (index, row) in block.rows.iter().enumerate() {
+                let node = graph.add_node(RowRef {
+                    block: block.name.clone(),
+                    row: index,
+                    id: row.get("id").cloned(),
+                });
+                nodes.insert(row as *const crate::Row, node);
+            }
+        }
+        for block in &self.blocks {
+            for row in &block.rows {
+                let from = nodes[&(row as *const crate::Row)];
+                for field in &block.fields {
+                    let Some(Value::Reference(r)) = row.get(field.as_str()) else {
+                        continue;
+                    };
+                    if let Some((_, target)) = self.resolve(r) {
+                        let to = nodes[&(target as *const crate::Row)];
+                        graph.add_edge(
+                            from,
+                            to,
+                            RelationshipEdge {
+                                field: field.clone(),
+                                reference: r.clone(),
+                            },
+                        );
+                    }
+                }
+            }
+        }
+        graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    #[test]
+    fn test_graph() {
+        let doc = parse(
+            "table.people\nid name boss mentor\n1 Alice ~ ~\n2 Bob :REPORTS_TO:1 :user:1\n\
+             3 \"Carol & Co\" :REPORTS_TO:2 :MENTORED_BY:9",
+        )
+        .unwrap();
+        let graph = doc.to_graph();
+
+        assert_eq!(graph.nodes(), ["1", "2", "3", "9"]);
+        assert_eq!(graph.edges().len(), 3);
+        assert_eq!(graph.edges()[2].field, "mentor");
+        assert_eq!(graph.neighbors("3"), vec!["2", "9"]);
+        assert!(graph.neighbors("1").is_empty());
+        assert!(graph.path_exists("3", "1"));
+        assert!(!graph.path_exists("1", "3"));
+        assert!(!graph.path_exists("1", "missing"));
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph ison {\n  \"1\";\n"));
+        assert!(dot.contains("  \"2\" -> \"1\" [label=\"REPORTS_TO\"];\n"));
+
+        let graphml = graph.to_graphml();
+        assert!(graphml.contains(
+            "<edge source=\"3\" target=\"9\"><data key=\"relationship\">MENTORED_BY</data></edge>"
+        ));
+        assert!(graphml.ends_with("</graph>\n</graphml>\n"));
+    }
+
+    #[cfg(feature = "petgraph")]
+    #[test]
+    fn test_petgraph() {
+        use crate::Value;
+        use petgraph::algo::dijkstra;
+
+        let doc = parse(
+            "table.teams\nid name\n10 Core\n\n\
+             table.people\nid name boss team\n1 Alice ~ :10\n2 Bob :REPORTS_TO:1 :10\n\
+             3 Carol :REPORTS_TO:2 :MEMBER_OF:99",
+        )
+        .unwrap();
+        let graph = doc.to_petgraph();
+
+        assert_eq!(graph.node_count(), 4);
+        assert_eq!(graph.edge_count(), 4);
+        let carol = graph.node_indices().next_back().unwrap();
+        assert_eq!(graph[carol].block, "people");
+        assert_eq!(graph[carol].id, Some(Value::Int(3)));
+
+        let boss = graph.edges(carol).next().unwrap().weight();
+        assert_eq!(boss.field, "boss");
+        assert_eq!(boss.relationship(), Some("REPORTS_TO"));
+
+        let distances = dijkstra(&graph, carol, None, |_| 1);
+        let team = graph.node_indices().next().unwrap();
+        assert_eq!(distances[&team], 2);
+    }
+}
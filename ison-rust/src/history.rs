@@ -0,0 +1,330 @@
+//! Document history
+//!
+//! A [`VersionedDocument`] records a version for every edit, with a patch
+//! listing the blocks it added, removed, or modified. Versions are
+//! [`SharedDocument`]s, so each one stores only the blocks its edit
+//! touched and shares the rest with its parent. [`undo`](VersionedDocument::undo)
+//! and [`checkout`](VersionedDocument::checkout) move between versions
+//! without discarding any, and [`audit_block`](VersionedDocument::audit_block)
+//! writes the whole history as an ISON table.
+
+use alloc::sync::Arc;
+
+use crate::prelude::*;
+use crate::{Block, Document, ISONError, Result, SharedDocument, Value};
+
+/// How an edit changed one block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl ChangeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Modified => "modified",
+        }
+    }
+}
+
+/// One block's entry in a [`Version`]'s patch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockPatch {
+    /// The block's `kind.name`
+    pub block: String,
+    pub change: ChangeKind,
+    /// Data rows before the edit; 0 for an added block
+    pub rows_before: usize,
+    /// Data rows after the edit; 0 for a removed block
+    pub rows_after: usize,
+}
+
+/// A recorded state of a [`VersionedDocument`]
+#[derive(Debug, Clone)]
+pub struct Version {
+    /// Position in [`VersionedDocument::versions`]; the first is 0
+    pub number: usize,
+    /// The version this one was edited from, `None` for the first
+    pub parent: Option<usize>,
+    pub label: String,
+    /// Blocks that differ from the parent, in document order
+    pub patch: Vec<BlockPatch>,
+    doc: SharedDocument,
+}
+
+impl Version {
+    /// The document as of this version
+    pub fn document(&self) -> &SharedDocument {
+        &self.doc
+    }
+}
+
+/// A document that keeps every version it has been through
+///
+/// ```rust
+/// use ison_rs::history::VersionedDocument;
+///
+/// let doc = ison_rs::parse("table.users\nid\n1\n2")?;
+/// let mut history = VersionedDocument::new(doc);
+/// history.edit("drop first user", |doc| {
+///     doc.get_mut("users").unwrap().rows.remove(0);
+/// });
+/// assert_eq!(history.current()["users"].len(), 1);
+///
+/// history.undo()?;
+/// assert_eq!(history.current()["users"].len(), 2);
+/// history.checkout(1)?;
+/// assert_eq!(history.current()["users"].len(), 1);
+/// # Ok::<(), ison_rs::ISONError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct VersionedDocument {
+    versions: Vec<Version>,
+    current: usize,
+}
+
+impl VersionedDocument {
+    /// Start a history at version 0, labelled `initial`
+    pub fn new(doc: impl Into<SharedDocument>) -> Self {
+        let doc = doc.into();
+        let patch = diff(&SharedDocument::new(), &doc);
+        Self {
+            versions: vec![Version {
+                number: 0,
+                parent: None,
+                label: "initial".to_string(),
+                patch,
+                doc,
+            }],
+            current: 0,
+        }
+    }
+
+    /// The checked-out document
+    pub fn current(&self) -> &SharedDocument {
+        &self.versions[self.current].doc
+    }
+
+    /// Number of the checked-out version
+    pub fn version(&self) -> usize {
+        self.current
+    }
+
+    /// Every version, oldest first
+    pub fn versions(&self) -> &[Version] {
+        &self.versions
+    }
+
+    /// Edit the checked-out document, recording a new version labelled
+    /// `label` if anything changed.
+    ///
+    /// The new version's parent is the one checked out, so editing after
+    /// [`undo`](Self::undo) starts a branch and keeps the undone versions.
+    pub fn edit<R>(&mut self, label: &str, edit: impl FnOnce(&mut SharedDocument) -> R) -> R {
+        let mut doc = self.current().clone();
+        let result = edit(&mut doc);
+        let patch = diff(self.current(), &doc);
+        if !patch.is_empty() {
+            let number = self.versions.len();
+            self.versions.push(Version {
+                number,
+                parent: Some(self.current),
+                label: label.to_string(),
+                patch,
+                doc,
+            });
+            self.current = number;
+        }
+        result
+    }
+
+    /// Check out the parent of the current version
+    pub fn undo(&mut self) -> Result<()> {
+        match self.versions[self.current].parent {
+            Some(parent) => {
+                self.current = parent;
+                Ok(())
+            }
+            None => Err(ISONError {
+                message: "Nothing to undo".to_string(),
+                line: None,
+            }),
+        }
+    }
+
+    /// Check out `version`
+    pub fn checkout(&mut self, version: usize) -> Result<()> {
+        if version >= self.versions.len() {
+            return Err(ISONError {
+                message: format!(
+                    "No version {}; the latest is {}",
+                    version,
+                    self.versions.len() - 1
+                ),
+                line: None,
+            });
+        }
+        self.current = version;
+        Ok(())
+    }
+
+    /// The history as a `table.history` block, one row per block patch:
+    /// `version parent label block change rows_before rows_after`
+    pub fn audit_block(&self) -> Block {
+        let mut block = Block::new("table", "history");
+        for field in [
+            "version:int",
+            "parent:int",
+            "label:string",
+            "block:string",
+            "change:string",
+            "rows_before:int",
+            "rows_after:int",
+        ] {
+            block.add_field_def(field);
+        }
+        let keys = block.field_keys();
+        for version in &self.versions {
+            for patch in &version.patch {
+                let values = [
+                    Value::Int(version.number as i64),
+                    version.parent.map_or(Value::Null, |p| Value::Int(p as i64)),
+                    Value::String(version.label.clone()),
+                    Value::String(patch.block.clone()),
+                    Value::String(patch.change.as_str().to_string()),
+                    Value::Int(patch.rows_before as i64),
+                    Value::Int(patch.rows_after as i64),
+                ];
+                block.rows.push(keys.iter().cloned().zip(values).collect());
+            }
+        }
+        block
+    }
+
+    /// The checked-out document, as a plain [`Document`]
+    pub fn to_document(&self) -> Document {
+        self.current().clone().into_document()
+    }
+}
+
+/// Whether two blocks hold the same header and rows
+fn same_block(a: &Block, b: &Block) -> bool {
+    a.kind == b.kind
+        && a.name == b.name
+        && a.fields == b.fields
+        && a.field_info.len() == b.field_info.len()
+        && a.field_info
+            .iter()
+            .zip(&b.field_info)
+            .all(|(x, y)| x.field_type == y.field_type)
+        && a.rows == b.rows
+        && a.summary_rows == b.summary_rows
+}
+
+/// Blocks of `after` that differ from `before`, matched by kind and name
+/// in order
+fn diff(before: &SharedDocument, after: &SharedDocument) -> Vec<BlockPatch> {
+    let mut unmatched: Vec<&Arc<Block>> = before.blocks().iter().collect();
+    let mut patch = Vec::new();
+    let qualified = |b: &Block| format!("{}.{}", b.kind, b.name);
+
+    for block in after.blocks() {
+        let found = unmatched
+            .iter()
+            .position(|old| old.kind == block.kind && old.name == block.name);
+        match found.map(|i| unmatched.remove(i)) {
+            Some(old) if Arc::ptr_eq(old, block) || same_block(old, block) => {}
+            Some(old) => patch.push(BlockPatch {
+                block: qualified(block),
+                change: ChangeKind::Modified,
+                rows_before: old.len(),
+                rows_after: block.len(),
+            }),
+            None => patch.push(BlockPatch {
+                block: qualified(block),
+                change: ChangeKind::Added,
+                rows_before: 0,
+                rows_after: block.len(),
+            }),
+        }
+    }
+    for old in unmatched {
+        patch.push(BlockPatch {
+            block: qualified(old),
+            change: ChangeKind::Removed,
+            rows_before: old.len(),
+            rows_after: 0,
+        });
+    }
+    patch
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dumps, parse};
+
+    #[test]
+    fn test_history() {
+        let doc = parse("table.users\nid name\n1 Alice\n2 Bob\n\ntable.orders\nid\n9").unwrap();
+        let original = dumps(&doc, false);
+        let mut history = VersionedDocument::new(doc);
+
+        // No change, no version
+        history.edit("touch", |doc| {
+            doc.get_mut("users");
+        });
+        assert_eq!(history.versions().len(), 1);
+
+        history.edit("rename", |doc| {
+            let users = doc.get_mut("users").unwrap();
+            users.rows[0].insert("name".into(), Value::String("Alicia".into()));
+            users.rows.pop();
+        });
+        history.edit("restructure", |doc| {
+            doc.remove("orders");
+            doc.push(Block::new("object", "config"));
+        });
+        assert_eq!(history.version(), 2);
+        assert!(history.versions()[2]
+            .document()
+            .shares("users", history.versions()[1].document()));
+
+        history.undo().unwrap();
+        history.undo().unwrap();
+        assert_eq!(dumps(&history.to_document(), false), original);
+        assert_eq!(history.undo().unwrap_err().message, "Nothing to undo");
+
+        // Editing an old version branches from it
+        history.edit("branch", |doc| {
+            doc.remove("users");
+        });
+        assert_eq!(history.versions()[3].parent, Some(0));
+        history.checkout(2).unwrap();
+        assert!(history.current().get("config").is_some());
+        assert!(history.checkout(9).is_err());
+
+        let audit = history.audit_block();
+        let text = dumps(
+            &Document {
+                blocks: vec![audit],
+            },
+            false,
+        );
+        assert_eq!(
+            text,
+            "table.history\n\
+             version:int parent:int label:string block:string change:string rows_before:int rows_after:int\n\
+             0 null initial \"table.users\" added 0 2\n\
+             0 null initial \"table.orders\" added 0 1\n\
+             1 0 rename \"table.users\" modified 2 1\n\
+             2 1 restructure \"object.config\" added 0 0\n\
+             2 1 restructure \"table.orders\" removed 1 0\n\
+             3 0 branch \"table.users\" removed 2 0"
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//! Incremental re-parsing for editors
+//!
+//! [`Document::apply_edit`] takes the text a document was parsed from and
+//! one edit to it, and re-parses only the blocks the edit touches. Block
+//! boundaries are found again with the header-only scan of
+//! [`parse_lazy`], which is cheap next to parsing rows; every block whose
+//! text the edit did not change keeps its already parsed rows.
+
+use core::ops::Range;
+
+use crate::prelude::*;
+use crate::{parse_lazy, Document, ISONError, LazyBlock, Result};
+
+/// Replace `range` (byte offsets into the old text) with `text`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+impl TextEdit {
+    pub fn new(range: Range<usize>, text: impl Into<String>) -> Self {
+        Self {
+            range,
+            text: text.into(),
+        }
+    }
+}
+
+/// Outcome of [`Document::apply_edit`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reparse {
+    /// The edited text, which the document now matches
+    pub text: String,
+    /// Indices of the blocks that were parsed again, in the updated
+    /// document; all other blocks were kept as they were
+    pub blocks: Range<usize>,
+}
+
+/// Byte range of a lazy block within the text it was scanned from
+fn span(text: &str, block: &LazyBlock) -> Range<usize> {
+    let start = block.text().as_ptr() as usize - text.as_ptr() as usize;
+    start..start + block.text().len()
+}
+
+impl Document {
+    /// Apply `edit` to `old_text`, the text this document was
+    /// [parsed](crate::parse) from, and update the document to match the
+    /// result without re-parsing blocks the edit left alone.
+    ///
+    /// The updated document is the one `parse` would give for the new text.
+    /// Errors are the ones it would report, except that, as with
+    /// [`parse_lazy`], a malformed header is reported ahead of row errors in
+    /// earlier blocks. On error the document is unchanged. If the document
+    /// does not have the blocks of `old_text`, every block is parsed again.
+    ///
+    /// ```rust
+    /// use ison_rs::incremental::TextEdit;
+    ///
+    /// let old = "table.users\nid name\n1 Alice\n\ntable.orders\nid total\n7 9.5";
+    /// let mut doc = ison_rs::parse(old)?;
+    /// let at = old.find("Alice").unwrap();
+    /// let reparse = doc.apply_edit(old, &TextEdit::new(at..at + 5, "Alicia"))?;
+    /// assert_eq!(reparse.blocks, 0..1);
+    /// assert_eq!(doc["users"][0]["name"].as_str(), Some("Alicia"));
+    /// # Ok::<(), ison_rs::ISONError>(())
+    /// ```
+    pub fn apply_edit(&mut self, old_text: &str, edit: &TextEdit) -> Result<Reparse> {
+        let Range { start, end } = edit.range;
+        if start > end
+            || end > old_text.len()
+            || !old_text.is_char_boundary(start)
+            || !old_text.is_char_boundary(end)
+        {
+            return Err(ISONError {
+                message: format!(
+                    "Edit range {}..{} is not within the text ({} bytes)",
+                    start,
+                    end,
+                    old_text.len()
+                ),
+                line: None,
+            });
+        }
+        let mut text = String::with_capacity(old_text.len() - (end - start) + edit.text.len());
+        text.push_str(&old_text[..start]);
+        text.push_str(&edit.text);
+        text.push_str(&old_text[end..]);
+
+        let new_blocks = parse_lazy(&text)?;
+        let new_spans: Vec<Range<usize>> =
+            new_blocks.blocks().iter().map(|b| span(&text, b)).collect();
+        let old_spans: Vec<Range<usize>> = match parse_lazy(old_text) {
+            Ok(old) if self.matches(old.blocks()) => {
+                old.blocks().iter().map(|b| span(old_text, b)).collect()
+            }
+            _ => Vec::new(),
+        };
+
+        // Blocks wholly before the edit keep their offsets; blocks wholly
+        // after it move by the change in length
+        let shift = |r: &Range<usize>| {
+            (r.start + edit.text.len() - (end - start))..(r.end + edit.text.len() - (end - start))
+        };
+        let max = old_spans.len().min(new_spans.len());
+        let prefix = (0..max)
+            .take_while(|&i| old_spans[i].end <= start && old_spans[i] == new_spans[i])
+            .count();
+        let suffix = (0..max - prefix)
+            .take_while(|&i| {
+                let old = &old_spans[old_spans.len() - 1 - i];
+                old.start >= end && shift(old) == new_spans[new_spans.len() - 1 - i]
+            })
+            .count();
+
+        let changed = prefix..new_spans.len() - suffix;
+        let mut parsed = Vec::with_capacity(changed.len());
+        for lazy in &new_blocks.blocks()[changed.clone()] {
+            parsed.push(lazy.block()?.clone());
+        }
+
+        let old_len = if old_spans.is_empty() {
+            self.blocks.len()
+        } else {
+            old_spans.len()
+        };
+        let replaced = if old_spans.is_empty() {
+            0..old_len
+        } else {
+            prefix..old_len - suffix
+        };
+        self.blocks.splice(replaced, parsed);
+        Ok(Reparse {
+            text,
+            blocks: changed,
+        })
+    }
+
+    /// Whether this document's blocks are the blocks of `lazy`, in order
+    fn matches(&self, lazy: &[LazyBlock]) -> bool {
+        self.blocks.len() == lazy.len()
+            && self
+                .blocks
+                .iter()
+                .zip(lazy)
+                .all(|(b, l)| b.kind == l.kind() && b.name == l.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dumps, parse};
+
+    /// Apply an edit both ways and compare with a full parse
+    fn check(old: &str, range: Range<usize>, replacement: &str) -> Range<usize> {
+        let mut doc = parse(old).unwrap();
+        let reparse = doc
+            .apply_edit(old, &TextEdit::new(range, replacement))
+            .unwrap();
+        let full = parse(&reparse.text).unwrap();
+        assert_eq!(
+            dumps(&doc, false),
+            dumps(&full, false),
+            "{:?}",
+            reparse.text
+        );
+        reparse.blocks
+    }
+
+    #[test]
+    fn test_apply_edit() {
+        let old = "table.a\nx\n1\n\ntable.b\ny\n2\n\ntable.c\nz\n3";
+        let at = |s: &str| old.find(s).unwrap();
+
+        // Inside one block
+        assert_eq!(check(old, at("2")..at("2") + 1, "20"), 1..2);
+        // Appending a row to a block
+        assert_eq!(check(old, at("2") + 1..at("2") + 1, "\n4"), 1..2);
+        // Removing the blank line merges b into a
+        assert_eq!(check(old, at("\n\ntable.b")..at("table.b"), "\n"), 0..1);
+        // A new block in the gap
+        assert_eq!(
+            check(old, at("table.c")..at("table.c"), "table.n\nw\n9\n\n"),
+            2..3
+        );
+        // Everything
+        assert_eq!(check(old, 0..old.len(), "object.o\nk\nv"), 0..1);
+        assert_eq!(check(old, 0..old.len(), ""), 0..0);
+
+        // Headers inside multi-line strings are not block boundaries
+        let old = "table.a\nx\n1\n\ntable.b\ny\n\"\"\"\n\ntable.fake\n\"\"\"\n\ntable.c\nz\n3";
+        let at = |s: &str| old.find(s).unwrap();
+        assert_eq!(check(old, at("fake")..at("fake") + 4, "real"), 1..2);
+        assert_eq!(check(old, at("1")..at("1") + 1, "one"), 0..1);
+    }
+
+    #[test]
+    fn test_apply_edit_errors() {
+        let old = "table.a\nx:int\n1\n\ntable.b\ny\n2";
+        let mut doc = parse(old).unwrap();
+        let before = doc.clone();
+
+        let at = old.find("1").unwrap();
+        let err = doc
+            .apply_edit(old, &TextEdit::new(at..at + 1, ":bad:ref:x"))
+            .unwrap_err();
+        let full = parse(&old.replacen('1', ":bad:ref:x", 1)).unwrap_err();
+        assert_eq!((err.message, err.line), (full.message, full.line));
+        assert!(doc
+            .apply_edit(old, &TextEdit::new(4..old.len() + 1, ""))
+            .is_err());
+        assert_eq!(dumps(&doc, false), dumps(&before, false));
+
+        // A document that is not from old_text is parsed again in full
+        let reparse = doc
+            .apply_edit("table.q\nv\n0", &TextEdit::new(0..0, "# note\n"))
+            .unwrap();
+        assert_eq!(reparse.blocks, 0..1);
+        assert_eq!(dumps(&doc, false), "table.q\nv\n0");
+    }
+}
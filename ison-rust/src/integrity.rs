@@ -0,0 +1,230 @@
+//! Integrity footer in a `meta.checksum` block
+//!
+//! ```text
+//! meta.checksum
+//! kind name rows:int hash:string
+//! table users 2 fnv1a64:f2460fb03a817521
+//! ```
+//!
+//! One row per block gives its row count and a hash of its field names and
+//! data rows. [`Document::add_checksum`] (or
+//! [`DumpOptions::checksum`](crate::DumpOptions::checksum)) writes the block
+//! last, so a document cut off in transit is missing its footer or the rows it
+//! counts, and [`Document::verify_integrity`] reports which.
+//!
+//! Values are hashed in their normalized form and summary rows are left out,
+//! so the checksum survives reformatting and conversion to ISONL and back.
+//! The hash is FNV-1a: it catches truncation and accidental corruption, not
+//! deliberate tampering by someone who can recompute it.
+
+use crate::prelude::*;
+use crate::{Block, Document, ISONError, Result, Serializer, Value};
+
+/// Kind of the checksum block
+pub const CHECKSUM_KIND: &str = "meta";
+/// Name of the checksum block
+pub const CHECKSUM_NAME: &str = "checksum";
+
+const HASH_PREFIX: &str = "fnv1a64:";
+
+fn is_checksum(block: &Block) -> bool {
+    block.kind == CHECKSUM_KIND && block.name == CHECKSUM_NAME
+}
+
+pub(crate) const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// Fold `bytes` into a 64-bit FNV-1a hash
+pub(crate) fn fnv1a64(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Hash of a block's field names and data rows
+fn block_hash(block: &Block) -> String {
+    let serializer = Serializer::new(false);
+    let mut hash = FNV_OFFSET;
+    let mut feed = |text: &str| hash = fnv1a64(fnv1a64(hash, text.as_bytes()), b"\n");
+    feed(&block.fields.join(" "));
+    for row in &block.rows {
+        feed(&serializer.serialize_values(row, &block.fields));
+    }
+    format!("{}{:016x}", HASH_PREFIX, hash)
+}
+
+fn integrity_error(message: String) -> ISONError {
+    ISONError {
+        message,
+        line: None,
+    }
+}
+
+impl Document {
+    /// Replace any `meta.checksum` block with one for the current blocks,
+    /// added at the end
+    pub fn add_checksum(&mut self) {
+        self.blocks.retain(|b| !is_checksum(b));
+
+        let mut checksum = Block::new(CHECKSUM_KIND, CHECKSUM_NAME);
+        for def in ["kind", "name", "rows:int", "hash:string"] {
+            checksum.add_field_def(def);
+        }
+        let keys = checksum.field_keys();
+        for block in &self.blocks {
+            let values = [
+                Value::String(block.kind.clone()),
+                Value::String(block.name.clone()),
+                Value::Int(block.rows.len() as i64),
+                Value::String(block_hash(block)),
+            ];
+            checksum
+                .rows
+                .push(keys.iter().cloned().zip(values).collect());
+        }
+        self.blocks.push(checksum);
+    }
+
+    /// Check the blocks against the `meta.checksum` block.
+    ///
+    /// Fails if there is no checksum block, if a block it lists is missing or
+    /// differs in row count or content, or if a block is not listed. Repeated
+    /// blocks are matched to rows of the same kind and name in order.
+    pub fn verify_integrity(&self) -> Result<()> {
+        let checksum = self
+            .blocks
+            .iter()
+            .find(|b| is_checksum(b))
+            .ok_or_else(|| integrity_error("Missing meta.checksum block".to_string()))?;
+
+        let text = |row: &crate::Row, field: &str| match row.get(field) {
+            Some(Value::String(s)) => s.clone(),
+            Some(other) => other.to_string(),
+            None => String::new(),
+        };
+        let mut expected: Vec<_> = checksum
+            .rows
+            .iter()
+            .map(|row| {
+                let rows = match row.get("rows") {
+                    Some(Value::Int(n)) => Some(*n),
+                    _ => None,
+                };
+                (
+                    text(row, "kind"),
+                    text(row, "name"),
+                    rows,
+                    text(row, "hash"),
+                )
+            })
+            .map(Some)
+            .collect();
+
+        for block in self.blocks.iter().filter(|b| !is_checksum(b)) {
+            let entry = expected
+                .iter_mut()
+                .find(|e| {
+                    e.as_ref()
+                        .is_some_and(|e| e.0 == block.kind && e.1 == block.name)
+                })
+                .and_then(Option::take)
+                .ok_or_else(|| {
+                    integrity_error(format!(
+                        "Block {}.{} is not covered by the checksum",
+                        block.kind, block.name
+                    ))
+                })?;
+            let (_, _, rows, hash) = entry;
+            if rows != Some(block.rows.len() as i64) {
+                return Err(integrity_error(format!(
+                    "Block {}.{} has {} rows, checksum expects {}",
+                    block.kind,
+                    block.name,
+                    block.rows.len(),
+                    rows.map_or_else(|| "?".to_string(), |n| n.to_string())
+                )));
+            }
+            if hash != block_hash(block) {
+                return Err(integrity_error(format!(
+                    "Block {}.{} does not match its checksum",
+                    block.kind, block.name
+                )));
+            }
+        }
+
+        if let Some((kind, name, _, _)) = expected.into_iter().flatten().next() {
+            return Err(integrity_error(format!(
+                "Block {}.{} is missing",
+                kind, name
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dumps, dumps_isonl, parse, parse_isonl};
+
+    #[test]
+    fn test_checksum() {
+        let mut doc = parse(
+            "table.users\nid name\n1 Alice\n2 \"Bob B\"\n---\ntotal 2\n\n\
+             object.config\nkey value\nmode fast",
+        )
+        .unwrap();
+        assert_eq!(
+            doc.verify_integrity().unwrap_err().message,
+            "Missing meta.checksum block"
+        );
+
+        doc.add_checksum();
+        doc.add_checksum();
+        assert_eq!(doc.len(), 3);
+        let text = dumps(&doc, false);
+        assert!(text.contains(
+            "\nmeta.checksum\nkind name rows:int hash:string\ntable users 2 fnv1a64:f2460fb03a817521\n"
+        ));
+        parse(&text).unwrap().verify_integrity().unwrap();
+        parse(&dumps(&doc, true))
+            .unwrap()
+            .verify_integrity()
+            .unwrap();
+        parse_isonl(&dumps_isonl(&doc))
+            .unwrap()
+            .verify_integrity()
+            .unwrap();
+
+        let options = crate::DumpOptions {
+            checksum: true,
+            ..Default::default()
+        };
+        let mut plain = doc.clone();
+        plain.blocks.pop();
+        assert_eq!(crate::dumps_with_options(&plain, &options).unwrap(), text);
+
+        let err = |text: &str| parse(text).unwrap().verify_integrity().unwrap_err().message;
+        // Cut after the first row of users: the footer is gone
+        let cut = &text[..text.find("1 Alice").unwrap() + 7];
+        assert_eq!(err(cut), "Missing meta.checksum block");
+        assert_eq!(
+            err(&text.replace("2 \"Bob B\"\n", "")),
+            "Block table.users has 1 rows, checksum expects 2"
+        );
+        assert_eq!(
+            err(&text.replace("Bob B", "Eve")),
+            "Block table.users does not match its checksum"
+        );
+        assert_eq!(
+            err(&text.replace("object.config", "object.settings")),
+            "Block object.settings is not covered by the checksum"
+        );
+        let (head, footer) = text.split_at(text.find("meta.checksum").unwrap());
+        let without_config = format!(
+            "{}\n\n{}",
+            &head[..head.find("\n\nobject").unwrap()],
+            footer
+        );
+        assert_eq!(err(&without_config), "Block object.config is missing");
+    }
+}
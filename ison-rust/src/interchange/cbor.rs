@@ -0,0 +1,76 @@
+use ciborium::value::Value as Cbor;
+
+use super::{document_node, error, node_document, Node};
+use crate::{Document, Result};
+
+/// CBOR tag used for references (private-use range, not IANA-registered)
+pub const CBOR_REFERENCE_TAG: u64 = 0x4953_4f4e;
+
+fn to_cbor(node: Node) -> Cbor {
+    match node {
+        Node::Null => Cbor::Null,
+        Node::Bool(b) => Cbor::Bool(b),
+        Node::Int(i) => Cbor::Integer(i.into()),
+        Node::Float(f) => Cbor::Float(f),
+        Node::Str(s) => Cbor::Text(s),
+        Node::Bytes(b) => Cbor::Bytes(b),
+        Node::Ref(text) => Cbor::Tag(CBOR_REFERENCE_TAG, Box::new(Cbor::Text(text))),
+        Node::Array(items) => Cbor::Array(items.into_iter().map(to_cbor).collect()),
+        Node::Map(entries) => Cbor::Map(
+            entries
+                .into_iter()
+                .map(|(k, v)| (Cbor::Text(k), to_cbor(v)))
+                .collect(),
+        ),
+    }
+}
+
+fn from_cbor(value: Cbor) -> Result<Node> {
+    Ok(match value {
+        Cbor::Null => Node::Null,
+        Cbor::Bool(b) => Node::Bool(b),
+        Cbor::Integer(i) => {
+            Node::Int(i64::try_from(i).map_err(|_| error("CBOR", "integer out of i64 range"))?)
+        }
+        Cbor::Float(f) => Node::Float(f),
+        Cbor::Text(s) => Node::Str(s),
+        Cbor::Tag(CBOR_REFERENCE_TAG, inner) => match *inner {
+            Cbor::Text(text) => Node::Ref(text),
+            _ => return Err(error("CBOR", "reference tag must wrap a text string")),
+        },
+        Cbor::Tag(tag, _) => return Err(error("CBOR", &format!("unknown tag {}", tag))),
+        Cbor::Bytes(b) => Node::Bytes(b),
+        Cbor::Array(items) => Node::Array(items.into_iter().map(from_cbor).collect::<Result<_>>()?),
+        Cbor::Map(entries) => Node::Map(
+            entries
+                .into_iter()
+                .map(|(k, v)| match k {
+                    Cbor::Text(k) => Ok((k, from_cbor(v)?)),
+                    _ => Err(error("CBOR", "map keys must be strings")),
+                })
+                .collect::<Result<_>>()?,
+        ),
+        _ => return Err(error("CBOR", "unsupported value")),
+    })
+}
+
+impl Document {
+    /// Encode as CBOR (see the [`interchange`](crate::interchange) mapping)
+    pub fn to_cbor(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        ciborium::ser::into_writer(&to_cbor(document_node(self)), &mut out)
+            .expect("writing to a Vec cannot fail");
+        out
+    }
+
+    /// Decode a document produced by [`Document::to_cbor`]
+    pub fn from_cbor(bytes: &[u8]) -> Result<Document> {
+        let mut reader = bytes;
+        let value: Cbor =
+            ciborium::de::from_reader(&mut reader).map_err(|e| error("CBOR", &e.to_string()))?;
+        if !reader.is_empty() {
+            return Err(error("CBOR", "trailing bytes"));
+        }
+        node_document("CBOR", &from_cbor(value)?)
+    }
+}
@@ -0,0 +1,275 @@
+//! MessagePack and CBOR encodings of [`Document`]
+//!
+//! Both encodings share one mapping, so a document reads the same in either:
+//!
+//! - the document is an array of blocks
+//! - a block is a map `{kind, name, fields, rows, summary_rows}`
+//! - `fields` is an array of maps `{name, type?, computed?}`
+//! - rows are maps from field name to value, in field order
+//! - null, bool, int, float, string and bytes values map to the native types
+//! - references are tagged values (MessagePack ext type [`MSGPACK_REFERENCE_EXT`],
+//!   CBOR tag [`CBOR_REFERENCE_TAG`]) wrapping their ISON text form, e.g. `:MANAGES:2`
+//! - decimals are strings, read back as decimals in `decimal` columns
+//!
+//! Enable with the `msgpack` and `cbor` features.
+
+#[cfg(feature = "cbor")]
+mod cbor;
+#[cfg(feature = "msgpack")]
+mod msgpack;
+
+#[cfg(feature = "cbor")]
+pub use cbor::CBOR_REFERENCE_TAG;
+#[cfg(feature = "msgpack")]
+pub use msgpack::MSGPACK_REFERENCE_EXT;
+
+use crate::{intern, parse_token, Block, Document, FieldInfo, ISONError, Result, Row, Value};
+
+/// Encoding-neutral tree both formats convert to and from
+enum Node {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    /// ISON text form of a reference
+    Ref(String),
+    Bytes(Vec<u8>),
+    Array(Vec<Node>),
+    Map(Vec<(String, Node)>),
+}
+
+fn error(format: &str, reason: &str) -> ISONError {
+    ISONError {
+        message: format!("Invalid {} document: {}", format, reason),
+        line: None,
+    }
+}
+
+fn str_node(s: &str) -> Node {
+    Node::Str(s.to_string())
+}
+
+fn value_node(value: &Value) -> Node {
+    match value {
+        Value::Null => Node::Null,
+        Value::Bool(b) => Node::Bool(*b),
+        Value::Int(i) => Node::Int(*i),
+        Value::Float(f) => Node::Float(*f),
+        Value::String(s) => str_node(s),
+        Value::Reference(r) => Node::Ref(r.to_ison()),
+        Value::Bytes(b) => Node::Bytes(b.clone()),
+        #[cfg(feature = "rust_decimal")]
+        Value::Decimal(d) => Node::Str(d.to_string()),
+    }
+}
+
+fn row_node(row: &Row, fields: &[String]) -> Node {
+    let mut extra: Vec<&str> = row
+        .keys()
+        .map(|k| &**k)
+        .filter(|k| !fields.iter().any(|f| f == k))
+        .collect();
+    extra.sort();
+    Node::Map(
+        fields
+            .iter()
+            .map(String::as_str)
+            .chain(extra)
+            .filter_map(|k| Some((k.to_string(), value_node(row.get(k)?))))
+            .collect(),
+    )
+}
+
+fn document_node(doc: &Document) -> Node {
+    Node::Array(
+        doc.blocks
+            .iter()
+            .map(|block| {
+                let fields = block
+                    .fields
+                    .iter()
+                    .enumerate()
+                    .map(|(i, name)| {
+                        let info = block.field_info.get(i).filter(|fi| &fi.name == name);
+                        let mut entries = vec![("name".to_string(), str_node(name))];
+                        if let Some(t) = info.and_then(|fi| fi.field_type.as_deref()) {
+                            entries.push(("type".to_string(), str_node(t)));
+                        }
+                        if info.is_some_and(|fi| fi.is_computed) {
+                            entries.push(("computed".to_string(), Node::Bool(true)));
+                        }
+                        Node::Map(entries)
+                    })
+                    .collect();
+                let rows = |rows: &[Row]| {
+                    Node::Array(rows.iter().map(|r| row_node(r, &block.fields)).collect())
+                };
+                Node::Map(vec![
+                    ("kind".to_string(), str_node(&block.kind)),
+                    ("name".to_string(), str_node(&block.name)),
+                    ("fields".to_string(), Node::Array(fields)),
+                    ("rows".to_string(), rows(&block.rows)),
+                    ("summary_rows".to_string(), rows(&block.summary_rows)),
+                ])
+            })
+            .collect(),
+    )
+}
+
+/// Field lookup on a decoded map
+fn entry<'a>(map: &'a [(String, Node)], key: &str) -> Option<&'a Node> {
+    map.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+}
+
+fn node_value(format: &str, node: &Node) -> Result<Value> {
+    Ok(match node {
+        Node::Null => Value::Null,
+        Node::Bool(b) => Value::Bool(*b),
+        Node::Int(i) => Value::Int(*i),
+        Node::Float(f) => Value::Float(*f),
+        Node::Str(s) => Value::String(s.clone()),
+        Node::Bytes(b) => Value::Bytes(b.clone()),
+        Node::Ref(text) => match parse_token(text) {
+            Ok(value @ Value::Reference(_)) => value,
+            _ => return Err(error(format, &format!("invalid reference '{}'", text))),
+        },
+        Node::Array(_) | Node::Map(_) => {
+            return Err(error(format, "nested arrays and maps are not ISON values"))
+        }
+    })
+}
+
+fn as_str(format: &str, node: Option<&Node>, what: &str) -> Result<String> {
+    match node {
+        Some(Node::Str(s)) => Ok(s.clone()),
+        _ => Err(error(format, &format!("{} must be a string", what))),
+    }
+}
+
+/// Array items; a missing entry reads as empty
+fn as_array<'a>(format: &str, node: Option<&'a Node>, what: &str) -> Result<&'a [Node]> {
+    match node {
+        Some(Node::Array(items)) => Ok(items),
+        None => Ok(&[]),
+        _ => Err(error(format, &format!("{} must be an array", what))),
+    }
+}
+
+fn as_map<'a>(format: &str, node: &'a Node, what: &str) -> Result<&'a [(String, Node)]> {
+    match node {
+        Node::Map(entries) => Ok(entries),
+        _ => Err(error(format, &format!("{} must be a map", what))),
+    }
+}
+
+fn node_document(format: &str, node: &Node) -> Result<Document> {
+    let Node::Array(blocks) = node else {
+        return Err(error(format, "document must be an array of blocks"));
+    };
+    let mut doc = Document::new();
+    for block_node in blocks {
+        let map = as_map(format, block_node, "block")?;
+        let mut block = Block::new(
+            as_str(format, entry(map, "kind"), "block kind")?,
+            as_str(format, entry(map, "name"), "block name")?,
+        );
+        for field in as_array(format, entry(map, "fields"), "fields")? {
+            let field = as_map(format, field, "field")?;
+            let name = as_str(format, entry(field, "name"), "field name")?;
+            let field_type = match entry(field, "type") {
+                None | Some(Node::Null) => None,
+                other => Some(as_str(format, other, "field type")?),
+            };
+            let is_computed = matches!(entry(field, "computed"), Some(Node::Bool(true)));
+            block.fields.push(name.clone());
+            block.field_info.push(FieldInfo {
+                name,
+                field_type,
+                is_computed,
+            });
+        }
+        let keys = block.field_keys();
+        #[cfg(feature = "rust_decimal")]
+        let cell_types = block.cell_types();
+        for (key, target) in [
+            ("rows", &mut block.rows),
+            ("summary_rows", &mut block.summary_rows),
+        ] {
+            for row in as_array(format, entry(map, key), key)? {
+                let mut decoded = Row::new();
+                for (field, value) in as_map(format, row, "row")? {
+                    let value = node_value(format, value)?;
+                    #[cfg(feature = "rust_decimal")]
+                    let value = match value {
+                        Value::String(s)
+                            if block
+                                .fields
+                                .iter()
+                                .zip(&cell_types)
+                                .any(|(f, &t)| t == crate::CellType::Decimal && f == field) =>
+                        {
+                            crate::parse_decimal(&s).map_or(Value::String(s), Value::Decimal)
+                        }
+                        other => other,
+                    };
+                    decoded.insert(intern(&keys, field), value);
+                }
+                target.push(decoded);
+            }
+        }
+        doc.blocks.push(block);
+    }
+    Ok(doc)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    fn sample() -> Document {
+        parse(
+            "table.users\nid:int name boss total:computed\n\
+             1 Alice :MANAGES:2 ~\n2 \"Bob Smith\" :user:1 2.5\n3 b64:AP8= ~ ~\n---\n2 ~ ~ ~",
+        )
+        .unwrap()
+    }
+
+    fn assert_same(a: &Document, b: &Document) {
+        let (a, b) = (&a.blocks[0], &b.blocks[0]);
+        assert_eq!((&a.kind, &a.name, &a.fields), (&b.kind, &b.name, &b.fields));
+        assert_eq!(a.get_field_type("id"), Some("int"));
+        assert_eq!(a.get_computed_fields(), vec!["total"]);
+        assert_eq!(a.rows, b.rows);
+        assert_eq!(a.summary_rows, b.summary_rows);
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn test_msgpack_roundtrip() {
+        let doc = sample();
+        let bytes = doc.to_msgpack();
+        assert_same(&Document::from_msgpack(&bytes).unwrap(), &doc);
+        assert!(Document::from_msgpack(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Document::from_msgpack(&[0x2a]).is_err());
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn test_cbor_roundtrip() {
+        let doc = sample();
+        let bytes = doc.to_cbor();
+        assert_same(&Document::from_cbor(&bytes).unwrap(), &doc);
+        assert!(Document::from_cbor(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Document::from_cbor(&[0x2a]).is_err());
+    }
+
+    #[cfg(all(feature = "msgpack", feature = "rust_decimal"))]
+    #[test]
+    fn test_decimal_roundtrip() {
+        let doc = parse("table.prices\nsku price:decimal\nA 19.90\nB \"19.90\"").unwrap();
+        let back = Document::from_msgpack(&doc.to_msgpack()).unwrap();
+        assert_eq!(back["prices"].rows, doc["prices"].rows);
+        assert_eq!(back["prices"][0]["price"].to_string(), "19.90");
+    }
+}
@@ -0,0 +1,88 @@
+use rmpv::Value as Mp;
+
+use super::{document_node, error, node_document, Node};
+use crate::{Document, Result};
+
+/// MessagePack extension type used for references
+pub const MSGPACK_REFERENCE_EXT: i8 = 1;
+
+fn to_mp(node: Node) -> Mp {
+    match node {
+        Node::Null => Mp::Nil,
+        Node::Bool(b) => Mp::Boolean(b),
+        Node::Int(i) => Mp::from(i),
+        Node::Float(f) => Mp::F64(f),
+        Node::Str(s) => Mp::from(s),
+        Node::Ref(text) => Mp::Ext(MSGPACK_REFERENCE_EXT, text.into_bytes()),
+        Node::Bytes(b) => Mp::Binary(b),
+        Node::Array(items) => Mp::Array(items.into_iter().map(to_mp).collect()),
+        Node::Map(entries) => Mp::Map(
+            entries
+                .into_iter()
+                .map(|(k, v)| (Mp::from(k), to_mp(v)))
+                .collect(),
+        ),
+    }
+}
+
+fn from_mp(value: Mp) -> Result<Node> {
+    Ok(match value {
+        Mp::Nil => Node::Null,
+        Mp::Boolean(b) => Node::Bool(b),
+        Mp::Integer(i) => Node::Int(
+            i.as_i64()
+                .ok_or_else(|| error("MessagePack", "integer out of i64 range"))?,
+        ),
+        Mp::F32(f) => Node::Float(f64::from(f)),
+        Mp::F64(f) => Node::Float(f),
+        Mp::String(s) => Node::Str(
+            s.into_str()
+                .ok_or_else(|| error("MessagePack", "invalid UTF-8 string"))?,
+        ),
+        Mp::Ext(MSGPACK_REFERENCE_EXT, bytes) => Node::Ref(
+            String::from_utf8(bytes).map_err(|_| error("MessagePack", "invalid reference"))?,
+        ),
+        Mp::Ext(ty, _) => {
+            return Err(error(
+                "MessagePack",
+                &format!("unknown extension type {}", ty),
+            ))
+        }
+        Mp::Binary(b) => Node::Bytes(b),
+        Mp::Array(items) => Node::Array(items.into_iter().map(from_mp).collect::<Result<_>>()?),
+        Mp::Map(entries) => Node::Map(
+            entries
+                .into_iter()
+                .map(|(k, v)| match k {
+                    Mp::String(s) => Ok((
+                        s.into_str()
+                            .ok_or_else(|| error("MessagePack", "invalid UTF-8 key"))?,
+                        from_mp(v)?,
+                    )),
+                    _ => Err(error("MessagePack", "map keys must be strings")),
+                })
+                .collect::<Result<_>>()?,
+        ),
+    })
+}
+
+impl Document {
+    /// Encode as MessagePack (see the [`interchange`](crate::interchange) mapping)
+    pub fn to_msgpack(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        rmpv::encode::write_value(&mut out, &to_mp(document_node(self)))
+            .expect("writing to a Vec cannot fail");
+        out
+    }
+
+    /// Decode a document produced by [`Document::to_msgpack`]
+    pub fn from_msgpack(bytes: &[u8]) -> Result<Document> {
+        let mut reader = bytes;
+        let value = rmpv::decode::read_value(&mut reader)
+            .map_err(|e| error("MessagePack", &e.to_string()))?;
+        if !reader.is_empty() {
+            return Err(error("MessagePack", "trailing bytes"));
+        }
+        node_document("MessagePack", &from_mp(value)?)
+    }
+}
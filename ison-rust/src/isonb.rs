@@ -0,0 +1,380 @@
+//! ISONB: binary ISON for caching and IPC
+//!
+//! A compact, length-prefixed encoding that round-trips every [`Document`]
+//! exactly, including references, field annotations and summary rows.
+//!
+//! ## Layout
+//!
+//! All integers are unsigned LEB128 varints unless noted; strings are a
+//! varint byte length followed by UTF-8.
+//!
+//! ```text
+//! document = "ISNB" version:u8 count block*
+//! block    = kind name count field* count row* count row*   (data rows, then summary rows)
+//! field    = name (0 | 1 type) computed:u8
+//! row      = count (key value)*
+//! key      = 0 name | index+1                               (index into the block's fields)
+//! value    = 0                                              null
+//!          | 1 | 2                                          false | true
+//!          | 3 zigzag-varint                                int
+//!          | 4 f64 little-endian                            float
+//!          | 5 string                                       string
+//!          | 6 id (0 | 1 ref_type)                          reference
+//!          | 7 16 bytes                                     decimal (`rust_decimal` builds, `Decimal::serialize`)
+//!          | 8 length byte*                                 bytes
+//! ```
+//!
+//! Row entries are written in field order, followed by any keys outside the
+//! field list in sorted order, so encoding is deterministic.
+
+use crate::prelude::*;
+use crate::{
+    intern, Block, Document, FieldInfo, FieldName, ISONError, Reference, Result, Row, Value,
+};
+
+/// Magic bytes at the start of every ISONB payload
+pub const ISONB_MAGIC: &[u8; 4] = b"ISNB";
+
+/// Current ISONB format version; decoders reject newer versions
+pub const ISONB_VERSION: u8 = 1;
+
+const TAG_NULL: u8 = 0;
+const TAG_FALSE: u8 = 1;
+const TAG_TRUE: u8 = 2;
+const TAG_INT: u8 = 3;
+const TAG_FLOAT: u8 = 4;
+const TAG_STRING: u8 = 5;
+const TAG_REFERENCE: u8 = 6;
+#[cfg(feature = "rust_decimal")]
+const TAG_DECIMAL: u8 = 7;
+const TAG_BYTES: u8 = 8;
+
+/// Encode a document as ISONB
+pub fn to_isonb(doc: &Document) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(ISONB_MAGIC);
+    out.push(ISONB_VERSION);
+    write_varint(&mut out, doc.blocks.len() as u64);
+    for block in &doc.blocks {
+        write_block(&mut out, block);
+    }
+    out
+}
+
+/// Decode an ISONB payload produced by [`to_isonb`]
+pub fn from_isonb(bytes: &[u8]) -> Result<Document> {
+    let mut reader = Reader { bytes, pos: 0 };
+    if reader.take(4)? != ISONB_MAGIC {
+        return Err(reader.error("missing ISNB magic"));
+    }
+    let version = reader.byte()?;
+    if version == 0 || version > ISONB_VERSION {
+        return Err(reader.error(&format!("unsupported version {}", version)));
+    }
+
+    let mut doc = Document::new();
+    for _ in 0..reader.count()? {
+        doc.blocks.push(reader.block()?);
+    }
+    if reader.pos != bytes.len() {
+        return Err(reader.error("trailing bytes"));
+    }
+    Ok(doc)
+}
+
+fn write_varint(out: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        out.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+fn write_str(out: &mut Vec<u8>, s: &str) {
+    write_varint(out, s.len() as u64);
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn write_opt_str(out: &mut Vec<u8>, s: Option<&str>) {
+    match s {
+        Some(s) => {
+            out.push(1);
+            write_str(out, s);
+        }
+        None => out.push(0),
+    }
+}
+
+fn write_block(out: &mut Vec<u8>, block: &Block) {
+    write_str(out, &block.kind);
+    write_str(out, &block.name);
+
+    // `fields` is authoritative for row keys; `field_info` may be absent on hand-built blocks
+    write_varint(out, block.fields.len() as u64);
+    for (i, name) in block.fields.iter().enumerate() {
+        let info = block.field_info.get(i).filter(|fi| &fi.name == name);
+        write_str(out, name);
+        write_opt_str(out, info.and_then(|fi| fi.field_type.as_deref()));
+        out.push(info.is_some_and(|fi| fi.is_computed) as u8);
+    }
+
+    for rows in [&block.rows, &block.summary_rows] {
+        write_varint(out, rows.len() as u64);
+        for row in rows {
+            write_row(out, row, &block.fields);
+        }
+    }
+}
+
+fn write_row(out: &mut Vec<u8>, row: &Row, fields: &[String]) {
+    let mut extra: Vec<&str> = row
+        .keys()
+        .map(|k| &**k)
+        .filter(|k| !fields.iter().any(|f| f == k))
+        .collect();
+    extra.sort();
+    let present = fields
+        .iter()
+        .filter(|f| row.contains_key(f.as_str()))
+        .count();
+
+    write_varint(out, (present + extra.len()) as u64);
+    for (i, field) in fields.iter().enumerate() {
+        if let Some(value) = row.get(field.as_str()) {
+            write_varint(out, i as u64 + 1);
+            write_value(out, value);
+        }
+    }
+    for key in extra {
+        write_varint(out, 0);
+        write_str(out, key);
+        write_value(out, &row[key]);
+    }
+}
+
+fn write_value(out: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Null => out.push(TAG_NULL),
+        Value::Bool(false) => out.push(TAG_FALSE),
+        Value::Bool(true) => out.push(TAG_TRUE),
+        Value::Int(i) => {
+            out.push(TAG_INT);
+            write_varint(out, ((i << 1) ^ (i >> 63)) as u64);
+        }
+        Value::Float(f) => {
+            out.push(TAG_FLOAT);
+            out.extend_from_slice(&f.to_le_bytes());
+        }
+        Value::String(s) => {
+            out.push(TAG_STRING);
+            write_str(out, s);
+        }
+        Value::Reference(r) => {
+            out.push(TAG_REFERENCE);
+            write_str(out, &r.id);
+            write_opt_str(out, r.ref_type.as_deref());
+        }
+        Value::Bytes(b) => {
+            out.push(TAG_BYTES);
+            write_varint(out, b.len() as u64);
+            out.extend_from_slice(b);
+        }
+        #[cfg(feature = "rust_decimal")]
+        Value::Decimal(d) => {
+            out.push(TAG_DECIMAL);
+            out.extend_from_slice(&d.serialize());
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn error(&self, reason: &str) -> ISONError {
+        ISONError {
+            message: format!("Invalid ISONB at byte {}: {}", self.pos, reason),
+            line: None,
+        }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| self.error("unexpected end of input"))?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> Result<u64> {
+        let mut n = 0u64;
+        for shift in (0..64).step_by(7) {
+            let b = self.byte()?;
+            n |= u64::from(b & 0x7f) << shift;
+            if b & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        Err(self.error("varint too long"))
+    }
+
+    /// Element count, bounded by the remaining input so corrupt data cannot force huge allocations
+    fn count(&mut self) -> Result<usize> {
+        let n = self.varint()?;
+        if n > (self.bytes.len() - self.pos) as u64 {
+            return Err(self.error("count exceeds remaining input"));
+        }
+        Ok(n as usize)
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.count()?;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| self.error("invalid UTF-8"))
+    }
+
+    fn opt_string(&mut self) -> Result<Option<String>> {
+        match self.byte()? {
+            0 => Ok(None),
+            1 => Ok(Some(self.string()?)),
+            _ => Err(self.error("invalid option tag")),
+        }
+    }
+
+    fn block(&mut self) -> Result<Block> {
+        let kind = self.string()?;
+        let name = self.string()?;
+        let mut block = Block::new(kind, name);
+
+        for _ in 0..self.count()? {
+            let name = self.string()?;
+            let field_type = self.opt_string()?;
+            let is_computed = match self.byte()? {
+                0 => false,
+                1 => true,
+                _ => return Err(self.error("invalid computed flag")),
+            };
+            block.fields.push(name.clone());
+            block.field_info.push(FieldInfo {
+                name,
+                field_type,
+                is_computed,
+            });
+        }
+
+        let keys = block.field_keys();
+        for _ in 0..self.count()? {
+            let row = self.row(&keys)?;
+            block.rows.push(row);
+        }
+        for _ in 0..self.count()? {
+            let row = self.row(&keys)?;
+            block.summary_rows.push(row);
+        }
+        Ok(block)
+    }
+
+    fn row(&mut self, keys: &[FieldName]) -> Result<Row> {
+        let mut row = Row::new();
+        for _ in 0..self.count()? {
+            let key = match self.varint()? {
+                0 => intern(keys, &self.string()?),
+                i => keys
+                    .get(i as usize - 1)
+                    .cloned()
+                    .ok_or_else(|| self.error("field index out of range"))?,
+            };
+            let value = self.value()?;
+            row.insert(key, value);
+        }
+        Ok(row)
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        Ok(match self.byte()? {
+            TAG_NULL => Value::Null,
+            TAG_FALSE => Value::Bool(false),
+            TAG_TRUE => Value::Bool(true),
+            TAG_INT => {
+                let z = self.varint()?;
+                Value::Int(((z >> 1) as i64) ^ -((z & 1) as i64))
+            }
+            TAG_FLOAT => {
+                let bytes: [u8; 8] = self.take(8)?.try_into().expect("8 bytes");
+                Value::Float(f64::from_le_bytes(bytes))
+            }
+            TAG_STRING => Value::String(self.string()?),
+            TAG_REFERENCE => {
+                let id = self.string()?;
+                Value::Reference(Reference {
+                    id,
+                    ref_type: self.opt_string()?,
+                })
+            }
+            TAG_BYTES => {
+                let len = self.varint()?;
+                Value::Bytes(self.take(len as usize)?.to_vec())
+            }
+            #[cfg(feature = "rust_decimal")]
+            TAG_DECIMAL => {
+                let bytes: [u8; 16] = self.take(16)?.try_into().expect("16 bytes");
+                Value::Decimal(crate::Decimal::deserialize(bytes))
+            }
+            tag => return Err(self.error(&format!("unknown value tag {}", tag))),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_isonb_roundtrip() {
+        let mut doc = parse(
+            "table.users\nid:int name score:float boss total:computed\n\
+             1 Alice 9.5 :MANAGES:2 ~\n-300 \"Bob Smith\" -0.25 :user:1 true\n---\n2 ~ ~ ~ false",
+        )
+        .unwrap();
+        doc.blocks[0].rows[0].insert("extra".into(), Value::Int(i64::MIN));
+
+        let bytes = to_isonb(&doc);
+        assert_eq!(&bytes[..4], ISONB_MAGIC);
+        assert_eq!(bytes[4], ISONB_VERSION);
+        assert_eq!(to_isonb(&doc), bytes);
+
+        let decoded = from_isonb(&bytes).unwrap();
+        let (a, b) = (&decoded.blocks[0], &doc.blocks[0]);
+        assert_eq!(a.fields, b.fields);
+        assert_eq!(a.get_field_type("total"), Some("computed"));
+        assert_eq!(a.get_computed_fields(), vec!["total"]);
+        assert_eq!(a.rows, b.rows);
+        assert_eq!(a.summary_rows, b.summary_rows);
+    }
+
+    #[test]
+    fn test_isonb_rejects_corrupt_input() {
+        let doc = parse("table.t\na\n1\n2").unwrap();
+        let bytes = to_isonb(&doc);
+        assert!(from_isonb(b"JSON").is_err());
+        assert!(from_isonb(&bytes[..bytes.len() - 1]).is_err());
+
+        let mut newer = bytes.clone();
+        newer[4] = ISONB_VERSION + 1;
+        assert!(from_isonb(&newer).is_err());
+
+        let mut trailing = bytes;
+        trailing.push(0);
+        assert!(from_isonb(&trailing).is_err());
+    }
+}
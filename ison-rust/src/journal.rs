@@ -0,0 +1,348 @@
+//! Write-ahead journals
+//!
+//! A [`Journal`] pairs an ISON snapshot with an ISONL file that records
+//! every change made since the snapshot, one line each. Lines name their
+//! block in the usual `kind.name` header and lead with two reserved fields,
+//! `_op` and `_row`:
+//!
+//! ```text
+//! #journal: 3
+//! table.users|_op _row id:int name|insert 2 3 Cy
+//! table.users|_op _row id:int name|update 0 1 Alicia
+//! table.users|_op _row|delete 1
+//! table.audit|_op when msg|define
+//! table.audit|_op|drop
+//! ```
+//!
+//! Opening the journal replays those lines onto the snapshot, and
+//! [`Journal::compact`] folds them into a new snapshot. Both files start
+//! with a `#journal:` generation line, which compaction bumps, so a crash
+//! between writing the snapshot and clearing the log never replays the
+//! same changes twice.
+
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::prelude::*;
+use crate::store::write_atomic;
+use crate::{
+    dumps, intern, isonl_prefix, parse, Block, Document, ISONError, IsonlReader, Result, Row,
+    Serializer, Value,
+};
+
+const GENERATION: &str = "#journal:";
+const OP: &str = "_op";
+const ROW: &str = "_row";
+
+/// One change to a document, as recorded by [`Journal::append`]
+///
+/// Row indices are positions in the block's data rows when the change is
+/// applied, so entries must be appended in the order they are made.
+#[derive(Debug, Clone, Copy)]
+pub enum JournalEntry<'a> {
+    /// Add the block's header, replacing any block of the same name; its
+    /// rows are not recorded
+    Define(&'a Block),
+    /// Remove the block
+    Drop(&'a Block),
+    /// Insert a data row at an index
+    Insert(&'a Block, usize, &'a Row),
+    /// Replace the data row at an index
+    Update(&'a Block, usize, &'a Row),
+    /// Remove the data row at an index
+    Delete(&'a Block, usize),
+}
+
+/// A snapshot and the changes made since; see the [module docs](self)
+///
+/// ```rust,no_run
+/// use ison_rs::journal::{Journal, JournalEntry};
+///
+/// let (mut journal, mut doc) = Journal::open("data.ison", "data.isonl")?;
+/// let users = doc.get_mut("users").unwrap();
+/// let row = users.rows[0].clone();
+/// journal.append(&[JournalEntry::Insert(users, users.len(), &row)])?;
+/// users.rows.push(row);
+///
+/// journal.compact(&doc)?;
+/// # Ok::<(), ison_rs::ISONError>(())
+/// ```
+pub struct Journal {
+    file: File,
+    path: PathBuf,
+    snapshot: PathBuf,
+    generation: u64,
+}
+
+impl Journal {
+    /// Load `snapshot` and replay the journal at `path` onto it, creating
+    /// either file as needed.
+    ///
+    /// A journal from before the snapshot's last compaction is cleared
+    /// rather than replayed. A last line without a newline, as left by a
+    /// crash mid-write, is dropped.
+    pub fn open(snapshot: impl AsRef<Path>, path: impl AsRef<Path>) -> Result<(Self, Document)> {
+        let (snapshot, path) = (snapshot.as_ref(), path.as_ref());
+        let (mut doc, snapshot_generation) = match std::fs::read_to_string(snapshot) {
+            Ok(text) => (parse(&text)?, generation(&text)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (Document::new(), 0),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+        let mut text = String::new();
+        file.read_to_string(&mut text)?;
+        let journal_generation = generation(&text)?;
+
+        if text.is_empty() || journal_generation < snapshot_generation {
+            write_atomic(path, &format!("{} {}\n", GENERATION, snapshot_generation))?;
+            file = OpenOptions::new().append(true).open(path)?;
+        } else if journal_generation > snapshot_generation {
+            return Err(ISONError {
+                message: format!(
+                    "Journal generation {} is newer than snapshot generation {}",
+                    journal_generation, snapshot_generation
+                ),
+                line: None,
+            });
+        } else {
+            let complete = text.rfind('\n').map_or(0, |end| end + 1);
+            for (line_num, line) in text[..complete].lines().enumerate() {
+                replay_line(&mut doc, line, line_num + 1)?;
+            }
+            if complete < text.len() {
+                file.set_len(complete as u64)?;
+                file.seek(SeekFrom::End(0))?;
+            }
+        }
+
+        let journal = Self {
+            file,
+            path: path.to_path_buf(),
+            snapshot: snapshot.to_path_buf(),
+            generation: snapshot_generation,
+        };
+        Ok((journal, doc))
+    }
+
+    /// Number of compactions so far
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Record `entries` as one batch: a single write, then an fsync
+    pub fn append(&mut self, entries: &[JournalEntry]) -> Result<()> {
+        let serializer = Serializer::new(false);
+        let mut batch = String::new();
+        for entry in entries {
+            encode(&serializer, entry, &mut batch);
+        }
+        self.file.write_all(batch.as_bytes())?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+
+    /// Write `doc` as the new snapshot and clear the journal.
+    ///
+    /// The snapshot is replaced first, each file through a temporary one,
+    /// so a crash at any point leaves a pair that opens to `doc` or to the
+    /// state before.
+    pub fn compact(&mut self, doc: &Document) -> Result<()> {
+        let next = self.generation + 1;
+        let marker = format!("{} {}\n", GENERATION, next);
+        write_atomic(&self.snapshot, &format!("{}{}", marker, dumps(doc, false)))?;
+        write_atomic(&self.path, &marker)?;
+        self.file = OpenOptions::new().append(true).open(&self.path)?;
+        self.generation = next;
+        Ok(())
+    }
+}
+
+impl core::fmt::Debug for Journal {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Journal")
+            .field("path", &self.path)
+            .field("snapshot", &self.snapshot)
+            .field("generation", &self.generation)
+            .finish()
+    }
+}
+
+/// Add the line for `entry` to `out`
+fn encode(serializer: &Serializer, entry: &JournalEntry, out: &mut String) {
+    let (block, op, index, row) = match *entry {
+        JournalEntry::Define(block) => (block, "define", None, None),
+        JournalEntry::Drop(block) => (block, "drop", None, None),
+        JournalEntry::Insert(block, i, row) => (block, "insert", Some(i), Some(row)),
+        JournalEntry::Update(block, i, row) => (block, "update", Some(i), Some(row)),
+        JournalEntry::Delete(block, i) => (block, "delete", Some(i), None),
+    };
+    let prefix = isonl_prefix(block);
+    let (header, fields) = prefix[..prefix.len() - 1].split_once('|').unwrap();
+
+    let mut names = vec![OP];
+    let mut values = op.to_string();
+    if let Some(i) = index {
+        names.push(ROW);
+        values.push_str(&format!(" {}", i));
+    }
+    if matches!(entry, JournalEntry::Define(_)) || row.is_some() {
+        names.extend(fields.split_whitespace());
+    }
+    if let Some(row) = row.filter(|_| !block.fields.is_empty()) {
+        values.push(' ');
+        values.push_str(&serializer.serialize_values(row, &block.fields));
+    }
+    out.push_str(&format!("{}|{}|{}\n", header, names.join(" "), values));
+}
+
+/// The generation in a leading `#journal:` line; 0 without one
+fn generation(text: &str) -> Result<u64> {
+    let Some(rest) = text.lines().next().and_then(|l| l.strip_prefix(GENERATION)) else {
+        return Ok(0);
+    };
+    rest.trim().parse().map_err(|_| ISONError {
+        message: format!("Invalid journal generation: {}", rest.trim()),
+        line: Some(1),
+    })
+}
+
+/// Apply the entry on `line` to `doc`
+fn replay_line(doc: &mut Document, line: &str, line_num: usize) -> Result<()> {
+    let invalid = |message: String| ISONError {
+        message,
+        line: Some(line_num),
+    };
+    let mut reader = IsonlReader::default();
+    let Some((idx, mut row)) = reader.read_line(line, line_num)? else {
+        return Ok(());
+    };
+    let header = &reader.blocks[idx].block;
+    let name = header.name.as_str();
+    let op = row.remove(OP);
+    let index = match row.remove(ROW) {
+        Some(Value::Int(i)) if i >= 0 => Some(i as usize),
+        None => None,
+        Some(other) => return Err(invalid(format!("Invalid journal row index: {}", other))),
+    };
+
+    let op = op.as_ref().and_then(Value::as_str).unwrap_or_default();
+    if op == "define" {
+        let mut block = Block::new(header.kind.clone(), name);
+        for info in header.field_info.iter().filter(|f| f.name != OP) {
+            block.fields.push(info.name.clone());
+            block.field_info.push(info.clone());
+        }
+        match doc.get_mut(name) {
+            Some(existing) => *existing = block,
+            None => doc.blocks.push(block),
+        }
+        return Ok(());
+    }
+    if op == "drop" {
+        return match doc.remove_block(name) {
+            Some(_) => Ok(()),
+            None => Err(invalid(format!("Block '{}' not found", name))),
+        };
+    }
+
+    let block = doc
+        .get_mut(name)
+        .ok_or_else(|| invalid(format!("Block '{}' not found", name)))?;
+    let len = block.rows.len();
+    let in_range = |limit: usize| {
+        index.filter(|&i| i < limit).ok_or_else(|| {
+            invalid(format!(
+                "Journal row index out of range for block '{}'",
+                name
+            ))
+        })
+    };
+    let keys = block.row_keys();
+    let row = || -> Row {
+        row.into_iter()
+            .map(|(k, v)| (intern(&keys, &k), v))
+            .collect()
+    };
+    match op {
+        "insert" => block.rows.insert(in_range(len + 1)?, row()),
+        "update" => block.rows[in_range(len)?] = row(),
+        "delete" => {
+            block.rows.remove(in_range(len)?);
+        }
+        _ => return Err(invalid(format!("Unknown journal operation: {}", op))),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Value;
+
+    #[test]
+    fn test_journal() {
+        let dir = std::env::temp_dir().join(format!("ison-journal-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (snapshot, log) = (dir.join("data.ison"), dir.join("data.isonl"));
+        std::fs::write(&snapshot, "table.users\nid:int name\n1 Alice\n2 Bob").unwrap();
+
+        let (mut journal, mut doc) = Journal::open(&snapshot, &log).unwrap();
+        let users = doc.get_mut("users").unwrap();
+        let mut row = users.rows[0].clone();
+        row.insert("id".into(), Value::Int(3));
+        journal
+            .append(&[JournalEntry::Insert(users, 2, &row)])
+            .unwrap();
+        users.rows.push(row);
+        users.rows[0].insert("name".into(), "Alicia".into());
+        journal
+            .append(&[
+                JournalEntry::Update(users, 0, &users.rows[0]),
+                JournalEntry::Delete(users, 1),
+            ])
+            .unwrap();
+        users.rows.remove(1);
+        let audit = Block::new("table", "audit");
+        journal
+            .append(&[JournalEntry::Define(&audit), JournalEntry::Drop(&audit)])
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&log).unwrap(),
+            "#journal: 0\n\
+             table.users|_op _row id:int name|insert 2 3 Alice\n\
+             table.users|_op _row id:int name|update 0 1 Alicia\n\
+             table.users|_op _row|delete 1\n\
+             table.audit|_op|define\n\
+             table.audit|_op|drop\n"
+        );
+        let expected = dumps(&doc, false);
+
+        // A torn last line is dropped on replay
+        let mut file = OpenOptions::new().append(true).open(&log).unwrap();
+        file.write_all(b"table.users|_op _row|del").unwrap();
+        let (mut journal, replayed) = Journal::open(&snapshot, &log).unwrap();
+        assert_eq!(dumps(&replayed, false), expected);
+
+        journal.compact(&replayed).unwrap();
+        assert_eq!(journal.generation(), 1);
+        assert_eq!(std::fs::read_to_string(&log).unwrap(), "#journal: 1\n");
+        // As if the log had not been cleared: its old entries are ignored
+        std::fs::write(&log, "#journal: 0\ntable.users|_op _row|delete 0\n").unwrap();
+        let (journal, reopened) = Journal::open(&snapshot, &log).unwrap();
+        assert_eq!(dumps(&reopened, false), expected);
+        assert_eq!(journal.generation(), 1);
+
+        std::fs::write(&log, "#journal: 1\ntable.users|_op _row|delete 7\n").unwrap();
+        let err = Journal::open(&snapshot, &log).unwrap_err();
+        assert_eq!(err.line, Some(2));
+        std::fs::write(&log, "#journal: 5\n").unwrap();
+        assert!(Journal::open(&snapshot, &log).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,304 @@
+//! JSON export with options
+//!
+//! [`Document::to_json`] writes each block as an array of row objects, with
+//! references as `{"id": .., "ref_type": ..}` objects. With
+//! [`JsonExportOptions::expand_references`], a reference that
+//! [resolves](Document::resolve) to a row is replaced by that row's object,
+//! so consumers do not have to resolve references themselves.
+//!
+//! Blocks and row keys come out sorted by name unless
+//! [`preserve_block_order`](JsonExportOptions::preserve_block_order) and
+//! [`preserve_field_order`](JsonExportOptions::preserve_field_order) keep
+//! them in document and header order, and
+//! [`metadata`](JsonExportOptions::metadata) wraps each block's rows with its
+//! kind and field types:
+//!
+//! ```text
+//! {"users": {"kind": "table",
+//!            "fields": [{"name": "id", "type": "int", "computed": false}],
+//!            "rows": [{"id": 1}]}}
+//! ```
+
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+use serde_json::Value as Json;
+
+use crate::prelude::*;
+use crate::{Block, Document, Row, Value};
+
+/// Options for [`Document::to_json_with_options`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JsonExportOptions {
+    /// Indent the output
+    pub pretty: bool,
+    /// Levels of references to replace with the rows they point at; 0
+    /// leaves every reference as it is. A reference back to a row that is
+    /// already being expanded (a cycle), or one that resolves to nothing,
+    /// is left as it is.
+    pub expand_references: usize,
+    /// Write blocks in document order instead of sorted by name
+    pub preserve_block_order: bool,
+    /// Write row keys in header order instead of sorted by name
+    pub preserve_field_order: bool,
+    /// Write each block as `{"kind", "fields", "rows"}` instead of a bare
+    /// array of rows, so kinds and field annotations survive
+    pub metadata: bool,
+}
+
+/// JSON tree whose objects keep their keys in insertion order, which
+/// `serde_json::Map` does not without its `preserve_order` feature
+enum Node {
+    Leaf(Json),
+    Array(Vec<Node>),
+    Object(Vec<(String, Node)>),
+}
+
+impl Node {
+    fn object(mut entries: Vec<(String, Node)>, sorted: bool) -> Node {
+        if sorted {
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+        Node::Object(entries)
+    }
+}
+
+impl Serialize for Node {
+    fn serialize<S: Serializer>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error> {
+        match self {
+            Node::Leaf(json) => json.serialize(serializer),
+            Node::Array(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+            Node::Object(entries) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+/// Rows being expanded, outermost first, for cycle detection
+type Path = Vec<*const Row>;
+
+impl Document {
+    /// JSON with the given options; see [`JsonExportOptions`]
+    ///
+    /// ```rust
+    /// use ison_rs::JsonExportOptions;
+    ///
+    /// let doc = ison_rs::parse("table.users\nid name\n1 Alice\n\ntable.orders\nid user\n7 :1")?;
+    /// let options = JsonExportOptions { expand_references: 1, ..Default::default() };
+    /// let json = doc.to_json_with_options(&options);
+    /// assert!(json.contains(r#""user":{"id":1,"name":"Alice"}"#));
+    /// # Ok::<(), ison_rs::ISONError>(())
+    /// ```
+    pub fn to_json_with_options(&self, options: &JsonExportOptions) -> String {
+        let mut blocks: Vec<(String, Node)> = Vec::new();
+        for block in &self.blocks {
+            let rows = Node::Array(
+                block
+                    .rows
+                    .iter()
+                    .map(|row| {
+                        self.row_json(
+                            block,
+                            row,
+                            options.expand_references,
+                            options,
+                            &mut Vec::new(),
+                        )
+                    })
+                    .collect(),
+            );
+            let node = if options.metadata {
+                block_metadata(block, rows)
+            } else {
+                rows
+            };
+            // Like a map insert, a later block replaces one of the same name
+            match blocks.iter_mut().find(|(name, _)| *name == block.name) {
+                Some(entry) => entry.1 = node,
+                None => blocks.push((block.name.clone(), node)),
+            }
+        }
+
+        let out = Node::object(blocks, !options.preserve_block_order);
+        if options.pretty {
+            serde_json::to_string_pretty(&out).unwrap_or_default()
+        } else {
+            serde_json::to_string(&out).unwrap_or_default()
+        }
+    }
+
+    fn row_json(
+        &self,
+        block: &Block,
+        row: &Row,
+        depth: usize,
+        options: &JsonExportOptions,
+        path: &mut Path,
+    ) -> Node {
+        path.push(row as *const Row);
+        let mut entries = Vec::new();
+        for field in &block.fields {
+            let Some(value) = row.get(field.as_str()) else {
+                continue;
+            };
+            entries.push((field.clone(), self.value_json(value, depth, options, path)));
+        }
+        // Cells set on a row without a header field, after the header's
+        let mut extra: Vec<_> = row
+            .iter()
+            .filter(|(k, _)| !block.fields.iter().any(|f| f.as_str() == &***k))
+            .collect();
+        extra.sort_by(|a, b| a.0.cmp(b.0));
+        for (field, value) in extra {
+            entries.push((
+                field.to_string(),
+                self.value_json(value, depth, options, path),
+            ));
+        }
+        path.pop();
+        Node::object(entries, !options.preserve_field_order)
+    }
+
+    fn value_json(
+        &self,
+        value: &Value,
+        depth: usize,
+        options: &JsonExportOptions,
+        path: &mut Path,
+    ) -> Node {
+        if let Value::Reference(reference) = value {
+            if depth > 0 {
+                if let Some((block, target)) = self.resolve(reference) {
+                    if !path.contains(&(target as *const Row)) {
+                        return self.row_json(block, target, depth - 1, options, path);
+                    }
+                }
+            }
+        }
+        Node::Leaf(serde_json::to_value(value).unwrap_or(Json::Null))
+    }
+}
+
+/// `{"kind", "fields", "rows"}` for [`JsonExportOptions::metadata`]
+fn block_metadata(block: &Block, rows: Node) -> Node {
+    let fields = block
+        .fields
+        .iter()
+        .map(|name| {
+            let computed = block.get_computed_fields().contains(&name.as_str());
+            Node::Object(vec![
+                ("name".to_string(), Node::Leaf(Json::from(name.as_str()))),
+                (
+                    "type".to_string(),
+                    Node::Leaf(block.get_field_type(name).into()),
+                ),
+                ("computed".to_string(), Node::Leaf(Json::Bool(computed))),
+            ])
+        })
+        .collect();
+    Node::Object(vec![
+        (
+            "kind".to_string(),
+            Node::Leaf(Json::from(block.kind.as_str())),
+        ),
+        ("fields".to_string(), Node::Array(fields)),
+        ("rows".to_string(), rows),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn json(doc: &Document, depth: usize) -> Json {
+        let options = JsonExportOptions {
+            expand_references: depth,
+            ..Default::default()
+        };
+        serde_json::from_str(&doc.to_json_with_options(&options)).unwrap()
+    }
+
+    #[test]
+    fn test_expand_references() {
+        let doc = parse(
+            "table.teams\nid name\n10 Core\n\n\
+             table.people\nid name boss team\n1 Alice :2 :team:10\n2 Bob :1 :team:10\n3 Carol ~ :99",
+        )
+        .unwrap();
+
+        // Without expansion, the same as to_json
+        let plain = json(&doc, 0);
+        assert_eq!(
+            plain,
+            serde_json::from_str::<Json>(&doc.to_json(false)).unwrap()
+        );
+        assert_eq!(plain["people"][0]["boss"]["id"], "2");
+
+        let expanded = json(&doc, 1);
+        let alice = &expanded["people"][0];
+        assert_eq!(alice["team"]["name"], "Core");
+        assert_eq!(alice["boss"]["name"], "Bob");
+        // One level only: Bob's boss stays a reference
+        assert_eq!(alice["boss"]["boss"]["id"], "1");
+        assert_eq!(expanded["people"][2]["team"]["id"], "99");
+
+        // Alice -> Bob -> Alice is a cycle, left as a reference
+        let deep = json(&doc, 5);
+        assert_eq!(deep["people"][0]["boss"]["team"]["name"], "Core");
+        assert_eq!(
+            deep["people"][0]["boss"]["boss"],
+            plain["people"][1]["boss"]
+        );
+    }
+
+    #[test]
+    fn test_export_ordering_and_metadata() {
+        let doc = parse(
+            "table.zebra
+z:int a
+1 x
+
+object.apple
+b
+~",
+        )
+        .unwrap();
+
+        let sorted = doc.to_json_with_options(&JsonExportOptions::default());
+        assert_eq!(
+            sorted,
+            r#"{"apple":[{"b":null}],"zebra":[{"a":"x","z":1}]}"#
+        );
+        assert_eq!(doc.to_json(false), sorted);
+
+        let options = JsonExportOptions {
+            preserve_block_order: true,
+            preserve_field_order: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            doc.to_json_with_options(&options),
+            r#"{"zebra":[{"z":1,"a":"x"}],"apple":[{"b":null}]}"#
+        );
+
+        let options = JsonExportOptions {
+            metadata: true,
+            ..options
+        };
+        let json: Json = serde_json::from_str(&doc.to_json_with_options(&options)).unwrap();
+        assert_eq!(json["apple"]["kind"], "object");
+        assert_eq!(json["zebra"]["fields"][0]["type"], "int");
+        assert_eq!(json["zebra"]["fields"][1]["type"], Json::Null);
+        assert_eq!(json["zebra"]["rows"][0]["a"], "x");
+    }
+}
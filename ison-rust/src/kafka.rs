@@ -0,0 +1,246 @@
+//! ISONL over Kafka
+//!
+//! Every ISONL line is a complete row, so it makes a natural Kafka record:
+//! [`records`] turns a document into one record per row, keyed by block
+//! name so a block's rows stay in one partition and in order. On the
+//! consuming side, [`RecordDecoder`] turns payloads back into rows one at a
+//! time, and [`DocumentAssembler`] collects them into a [`Document`].
+//!
+//! Records carry their block's fields on every line rather than in
+//! `#schema:` lines, since a consumer may start anywhere in a topic.
+//! [`produce`] and the `*_message` methods connect these to `rdkafka`.
+//!
+//! Enable with the `kafka` feature.
+
+use rdkafka::message::Message;
+use rdkafka::producer::{BaseRecord, ProducerContext, ThreadedProducer};
+
+use crate::prelude::*;
+use crate::{isonl_prefix, Block, Document, ISONError, IsonlReader, Result, Row, Serializer};
+
+/// One row as a Kafka record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IsonlRecord {
+    /// The block's name
+    pub key: String,
+    /// The row's ISONL line, without a newline
+    pub payload: String,
+}
+
+/// One record per data row of `doc`, in document order
+///
+/// ```rust
+/// use ison_rs::kafka::{records, DocumentAssembler};
+///
+/// let doc = ison_rs::parse("table.users\nid name\n1 Alice\n2 Bob")?;
+/// let records = records(&doc);
+/// assert_eq!(records[1].key, "users");
+/// assert_eq!(records[1].payload, "table.users|id name|2 Bob");
+///
+/// let mut assembler = DocumentAssembler::new();
+/// for record in &records {
+///     assembler.push(record.payload.as_bytes())?;
+/// }
+/// assert_eq!(assembler.document()["users"].len(), 2);
+/// # Ok::<(), ison_rs::ISONError>(())
+/// ```
+pub fn records(doc: &Document) -> Vec<IsonlRecord> {
+    let serializer = Serializer::new(false);
+    let mut records = Vec::new();
+    for block in &doc.blocks {
+        let prefix = isonl_prefix(block);
+        for row in &block.rows {
+            records.push(IsonlRecord {
+                key: block.name.clone(),
+                payload: format!(
+                    "{}{}",
+                    prefix,
+                    serializer.serialize_values(row, &block.fields)
+                ),
+            });
+        }
+    }
+    records
+}
+
+/// Send every row of `doc` to `topic`, one record each; returns the
+/// number of records queued.
+///
+/// Records are queued for delivery, not delivered: flush the producer to
+/// wait for them. Stops at the first record the producer refuses.
+pub fn produce<C: ProducerContext<DeliveryOpaque = ()> + 'static>(
+    producer: &ThreadedProducer<C>,
+    topic: &str,
+    doc: &Document,
+) -> Result<usize> {
+    let records = records(doc);
+    for record in &records {
+        producer
+            .send(
+                BaseRecord::to(topic)
+                    .key(&record.key)
+                    .payload(&record.payload),
+            )
+            .map_err(|(e, _)| ISONError {
+                message: format!("Kafka send failed: {}", e),
+                line: None,
+            })?;
+    }
+    Ok(records.len())
+}
+
+/// Decodes record payloads into rows, remembering each block's fields
+#[derive(Default)]
+pub struct RecordDecoder {
+    reader: IsonlReader,
+    records: usize,
+}
+
+impl RecordDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The row in `payload` and its block's header (without rows), or
+    /// `None` for an empty, comment, or `#schema:` record.
+    ///
+    /// Errors carry the number of the record, counting from 1, as their
+    /// line.
+    pub fn decode(&mut self, payload: &[u8]) -> Result<Option<(&Block, Row)>> {
+        self.records += 1;
+        let text = core::str::from_utf8(payload).map_err(|_| ISONError {
+            message: "Record payload is not valid UTF-8".to_string(),
+            line: Some(self.records),
+        })?;
+        let mut lines = text.lines().filter(|l| !l.trim().is_empty());
+        let (Some(line), None) = (lines.next(), lines.next()) else {
+            if text.trim().is_empty() {
+                return Ok(None);
+            }
+            return Err(ISONError {
+                message: "Record payload holds more than one ISONL line".to_string(),
+                line: Some(self.records),
+            });
+        };
+        Ok(self
+            .reader
+            .read_line(line, self.records)?
+            .map(|(idx, row)| (&self.reader.blocks[idx].block, row)))
+    }
+
+    /// [`decode`](Self::decode) the payload of a consumed message; a
+    /// message without one (a tombstone) is skipped
+    pub fn decode_message(&mut self, message: &impl Message) -> Result<Option<(&Block, Row)>> {
+        self.decode(message.payload().unwrap_or_default())
+    }
+}
+
+/// Collects decoded records into a document
+///
+/// Blocks appear in the order their first record arrived.
+#[derive(Default)]
+pub struct DocumentAssembler {
+    decoder: RecordDecoder,
+    doc: Document,
+}
+
+impl DocumentAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the row in `payload`
+    pub fn push(&mut self, payload: &[u8]) -> Result<()> {
+        let Some((header, row)) = self.decoder.decode(payload)? else {
+            return Ok(());
+        };
+        let block = match self
+            .doc
+            .blocks
+            .iter()
+            .position(|b| b.kind == header.kind && b.name == header.name)
+        {
+            Some(i) => &mut self.doc.blocks[i],
+            None => {
+                self.doc.blocks.push(header.clone());
+                self.doc.blocks.last_mut().unwrap()
+            }
+        };
+        block.rows.push(row);
+        Ok(())
+    }
+
+    /// [`push`](Self::push) the payload of a consumed message
+    pub fn push_message(&mut self, message: &impl Message) -> Result<()> {
+        self.push(message.payload().unwrap_or_default())
+    }
+
+    pub fn document(&self) -> &Document {
+        &self.doc
+    }
+
+    pub fn into_document(self) -> Document {
+        self.doc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rdkafka::message::{OwnedMessage, Timestamp};
+
+    use super::*;
+    use crate::{dumps, parse, Value};
+
+    fn message(payload: Option<&str>) -> OwnedMessage {
+        OwnedMessage::new(
+            payload.map(|p| p.as_bytes().to_vec()),
+            Some(b"users".to_vec()),
+            "events".to_string(),
+            Timestamp::NotAvailable,
+            0,
+            0,
+            None,
+        )
+    }
+
+    #[test]
+    fn test_records_roundtrip() {
+        let doc = parse(
+            "table.users\nid:int name\n1 Alice\n2 \"Bob Smith\"\n\n\
+             table.orders\nid user\n9 :user:1",
+        )
+        .unwrap();
+        let records = records(&doc);
+        let keys: Vec<_> = records.iter().map(|r| r.key.as_str()).collect();
+        assert_eq!(keys, ["users", "users", "orders"]);
+
+        // Interleaved, as from several partitions, plus a tombstone
+        let mut assembler = DocumentAssembler::new();
+        for i in [0, 2, 1] {
+            assembler
+                .push_message(&message(Some(&records[i].payload)))
+                .unwrap();
+        }
+        assembler.push_message(&message(None)).unwrap();
+        assert_eq!(dumps(&assembler.into_document(), false), dumps(&doc, false));
+    }
+
+    #[test]
+    fn test_record_decoder() {
+        let mut decoder = RecordDecoder::new();
+        let (block, row) = decoder
+            .decode(b"table.users|id:int name|1 Alice\n")
+            .unwrap()
+            .unwrap();
+        assert_eq!(block.get_field_type("id"), Some("int"));
+        assert_eq!(row["id"], Value::Int(1));
+        assert!(decoder.decode(b"# comment").unwrap().is_none());
+
+        let err = decoder
+            .decode(b"table.users|id|1\ntable.users|id|2")
+            .unwrap_err();
+        assert_eq!(err.line, Some(3));
+        assert!(decoder.decode(b"not isonl").is_err());
+        assert!(decoder.decode(&[0xff]).is_err());
+    }
+}
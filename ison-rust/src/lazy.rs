@@ -0,0 +1,189 @@
+//! Lazy parsing: blocks are found up front, rows parsed on first access
+//!
+//! [`parse_lazy`] reads only block headers, skipping over fields and rows the
+//! same way [`parse_blocks`](crate::parse_blocks) skips unwanted blocks. Each
+//! [`LazyBlock`] keeps its slice of the text and parses it the first time it
+//! is asked for, so a large document that is only partly read costs little
+//! more than finding its block boundaries.
+
+use core::cell::OnceCell;
+
+use crate::prelude::*;
+use crate::{Block, Document, ISONError, ParseOptions, Parser, Result};
+
+/// A document whose blocks are parsed on demand; see [`parse_lazy`]
+#[derive(Debug, Clone)]
+pub struct LazyDocument<'a> {
+    blocks: Vec<LazyBlock<'a>>,
+}
+
+/// One block of a [`LazyDocument`]: its header, and its rows once parsed
+#[derive(Debug, Clone)]
+pub struct LazyBlock<'a> {
+    kind: String,
+    name: String,
+    /// Text from the header through the last row
+    text: &'a str,
+    /// Line of the header
+    line: usize,
+    parsed: OnceCell<Block>,
+}
+
+/// Find the blocks of `text` without parsing their rows.
+///
+/// Only malformed headers are reported here; errors in a block's fields or
+/// rows come from the first [`LazyBlock::block`] call that parses it.
+///
+/// ```rust
+/// let doc = ison_rs::parse_lazy("table.users\nid name\n1 Alice\n\ntable.logs\nmsg\nstart")?;
+/// assert_eq!(doc.len(), 2);
+/// let users = doc.get("users")?.unwrap();
+/// assert_eq!(users[0].get("name").and_then(|v| v.as_str()), Some("Alice"));
+/// assert!(!doc.blocks()[1].is_parsed());
+/// # Ok::<(), ison_rs::ISONError>(())
+/// ```
+pub fn parse_lazy(text: &str) -> Result<LazyDocument<'_>> {
+    let mut parser = Parser::new(text);
+    let mut blocks = Vec::new();
+
+    parser.skip_whitespace_and_comments();
+    while parser.pos < parser.text.len() {
+        let start = parser.pos;
+        let line = parser.line;
+        if let Some(header) = parser.parse_header()? {
+            parser.skip_block();
+            blocks.push(LazyBlock {
+                kind: header.kind,
+                name: header.name,
+                text: &text[start..parser.pos],
+                line,
+                parsed: OnceCell::new(),
+            });
+        }
+        parser.skip_whitespace_and_comments();
+    }
+
+    Ok(LazyDocument { blocks })
+}
+
+impl<'a> LazyDocument<'a> {
+    /// All blocks in document order, parsed or not
+    pub fn blocks(&self) -> &[LazyBlock<'a>] {
+        &self.blocks
+    }
+
+    /// Number of blocks
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Whether the document has no blocks
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Check if block exists, without parsing it
+    pub fn has(&self, name: &str) -> bool {
+        self.blocks.iter().any(|b| b.name == name)
+    }
+
+    /// Get block by name, parsing it on first access.
+    ///
+    /// Like [`Document::get`], the first block with that name wins.
+    pub fn get(&self, name: &str) -> Result<Option<&Block>> {
+        self.blocks
+            .iter()
+            .find(|b| b.name == name)
+            .map(LazyBlock::block)
+            .transpose()
+    }
+
+    /// Parse every block, giving the same document as [`parse`](crate::parse)
+    pub fn to_document(&self) -> Result<Document> {
+        let options = ParseOptions::default();
+        let mut doc = Document::new();
+        for lazy in &self.blocks {
+            options.add_block(&mut doc, lazy.block()?.clone(), lazy.line)?;
+        }
+        Ok(doc)
+    }
+}
+
+impl LazyBlock<'_> {
+    /// Block kind from the header
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    /// Block name from the header
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Text from the header through the last row
+    pub(crate) fn text(&self) -> &str {
+        self.text
+    }
+
+    /// Whether the rows have been parsed yet
+    pub fn is_parsed(&self) -> bool {
+        self.parsed.get().is_some()
+    }
+
+    /// The parsed block, parsing it if this is the first call.
+    ///
+    /// A block that fails to parse is not cached, so every call returns the
+    /// error again, with lines numbered as in the whole text.
+    pub fn block(&self) -> Result<&Block> {
+        if let Some(block) = self.parsed.get() {
+            return Ok(block);
+        }
+        let mut doc = Parser::new(self.text).parse().map_err(|e| ISONError {
+            line: e.line.map(|line| line + self.line - 1),
+            ..e
+        })?;
+        let block = doc
+            .blocks
+            .pop()
+            .expect("lazy block text starts with a header");
+        Ok(self.parsed.get_or_init(|| block))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dumps, parse};
+
+    #[test]
+    fn test_parse_lazy() {
+        let text = "# inventory\ntable.users\nid name\n1 Alice\n2 \"\"\"\ntable.fake\n\n\"\"\"\n\n\
+                    object.config\nkey value\nmode fast\n\n\
+                    table.orders\nid user\n7 :user:1\n8 :bad:ref:x";
+        let doc = parse_lazy(text).unwrap();
+        let names: Vec<_> = doc.blocks().iter().map(LazyBlock::name).collect();
+        assert_eq!(names, ["users", "config", "orders"]);
+        assert!(doc.has("orders"));
+        assert!(doc.blocks().iter().all(|b| !b.is_parsed()));
+
+        let users = doc.get("users").unwrap().unwrap();
+        assert_eq!(users.len(), 2);
+        assert!(doc.blocks()[0].is_parsed());
+        assert!(!doc.blocks()[1].is_parsed());
+        assert!(doc.get("missing").unwrap().is_none());
+
+        // Row errors surface on access, with whole-document line numbers
+        let err = doc.get("orders").unwrap_err();
+        assert_eq!(err.line, parse(text).unwrap_err().line);
+
+        let text = text.replace(":bad:ref:x", "ok");
+        let doc = parse_lazy(&text).unwrap();
+        assert_eq!(
+            dumps(&doc.to_document().unwrap(), false),
+            dumps(&parse(&text).unwrap(), false)
+        );
+
+        assert!(parse_lazy("table.ok\nx\n1\n\nnot a header").is_err());
+        assert!(parse_lazy("").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,318 @@
+//! Token stream for syntax highlighting
+//!
+//! [`lex`] splits text into classified tokens with byte ranges, following
+//! the same block, field, and row structure as the parser. It never fails:
+//! text the parser would reject still gets tokens, with anything that
+//! cannot be read marked [`TokenKind::Invalid`], so an editor can highlight
+//! a document while it is being typed.
+
+use alloc::collections::VecDeque;
+use core::ops::Range;
+
+use crate::prelude::*;
+use crate::{ends_block, is_pairs_header, ParseOptions, Parser, Value};
+
+/// What a [`LexToken`] is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    /// A `kind.name` block header
+    Header,
+    /// A field name in a header row, or a key in a `key value` block
+    Field,
+    /// The type after a field name, without its `:`
+    TypeAnnotation,
+    /// `null` or `~`
+    Null,
+    /// `true` or `false`
+    Bool,
+    Int,
+    /// A float or decimal
+    Float,
+    /// A quoted or unquoted string, including `"""` strings
+    String,
+    Reference,
+    /// A `b64:` bytes value
+    Bytes,
+    /// A `#` comment, whole-line or trailing
+    Comment,
+    /// `---`, before summary rows
+    SummarySeparator,
+    /// A malformed header or a value that does not parse, such as `:a:b:c`
+    Invalid,
+}
+
+/// A classified token; see [`lex`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LexToken {
+    pub kind: TokenKind,
+    /// Byte range in the text, quotes included
+    pub span: Range<usize>,
+}
+
+/// Where the lexer is within a block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Header,
+    Fields,
+    Rows,
+    Pairs,
+}
+
+/// Iterator returned by [`lex`]
+pub struct Lexer<'a> {
+    parser: Parser<'a>,
+    state: State,
+    /// Kind of the current block, to spot `key value` blocks
+    kind: &'a str,
+    pending: VecDeque<LexToken>,
+}
+
+/// Classified tokens of `text`, in order.
+///
+/// ```rust
+/// use ison_rs::TokenKind::*;
+///
+/// let text = "table.users\nid:int name\n1 \"Alice\" # first\n---\ntotal :1";
+/// let kinds: Vec<_> = ison_rs::lex(text).map(|t| t.kind).collect();
+/// assert_eq!(
+///     kinds,
+///     [Header, Field, TypeAnnotation, Field, Int, String, Comment, SummarySeparator, String, Reference]
+/// );
+/// ```
+pub fn lex(text: &str) -> Lexer<'_> {
+    lex_with_options(text, &ParseOptions::default())
+}
+
+/// [`lex`], reading cells as [`parse_with_options`](crate::parse_with_options)
+/// does with `options`
+pub fn lex_with_options<'a>(text: &'a str, options: &ParseOptions) -> Lexer<'a> {
+    Lexer {
+        parser: Parser::with_options(text, options.clone()),
+        state: State::Header,
+        kind: "",
+        pending: VecDeque::new(),
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = LexToken;
+
+    fn next(&mut self) -> Option<LexToken> {
+        while self.pending.is_empty() {
+            let begin = self.parser.pos;
+            let line = self.parser.read_line()?;
+            let raw = &self.parser.text[begin..self.parser.pos];
+            self.lex_line(line, raw);
+        }
+        self.pending.pop_front()
+    }
+}
+
+impl<'a> Lexer<'a> {
+    fn push(&mut self, kind: TokenKind, start: usize, end: usize) {
+        self.pending.push_back(LexToken {
+            kind,
+            span: start..end,
+        });
+    }
+
+    /// Lex `line`, trimmed from `raw`
+    fn lex_line(&mut self, line: &'a str, raw: &str) {
+        let start = self.parser.offset(line);
+        if line.is_empty() {
+            // Blank lines end a block, but may come between header and
+            // fields; a line of other whitespace is an empty field list
+            let blank = raw
+                .bytes()
+                .all(|b| matches!(b, b' ' | b'\t' | b'\r' | b'\n'));
+            match self.state {
+                State::Fields if !blank => self.state = State::Rows,
+                State::Fields => {}
+                _ => self.state = State::Header,
+            }
+            return;
+        }
+        if line.starts_with('#') {
+            self.push(TokenKind::Comment, start, start + line.len());
+            return;
+        }
+
+        // As in the parser, a header line ends a block
+        if matches!(self.state, State::Rows | State::Pairs) && ends_block(line) {
+            self.state = State::Header;
+        }
+
+        match self.state {
+            State::Header => {
+                let kind = match line.find('.') {
+                    Some(dot)
+                        if !line[..dot].trim().is_empty() && !line[dot + 1..].trim().is_empty() =>
+                    {
+                        TokenKind::Header
+                    }
+                    _ => TokenKind::Invalid,
+                };
+                self.kind = line.split('.').next().unwrap_or("").trim();
+                self.push(kind, start, start + line.len());
+                self.state = State::Fields;
+            }
+            State::Fields => {
+                let tokens = self.tokens(line);
+                let pairs = self.parser.options.key_value_objects
+                    && is_pairs_header(self.kind, tokens.iter().map(|t| t.1.as_str()));
+                for (span, token) in &tokens {
+                    self.field(span.clone(), token);
+                }
+                self.comment(line);
+                self.state = if pairs { State::Pairs } else { State::Rows };
+            }
+            State::Rows | State::Pairs => {
+                let line = self.parser.continue_block_string(line);
+                if line.trim() == "---" {
+                    self.push(TokenKind::SummarySeparator, start, start + line.len());
+                    return;
+                }
+                let tokens = self.tokens(line);
+                for (i, (span, token)) in tokens.iter().enumerate() {
+                    if i == 0 && self.state == State::Pairs {
+                        self.field(span.clone(), token);
+                    } else {
+                        let kind = self.value_kind(span, token);
+                        self.push(kind, span.start, span.end);
+                    }
+                }
+                self.comment(line);
+            }
+        }
+    }
+
+    /// Tokens of `line` with their text ranges
+    fn tokens(&self, line: &str) -> Vec<(Range<usize>, String)> {
+        let start = self.parser.offset(line);
+        let mut tokens = Vec::new();
+        self.parser.tokenize_line_with(line, |range, token| {
+            tokens.push((start + range.start..start + range.end, token.into_owned()))
+        });
+        tokens
+    }
+
+    /// A field definition, split into name and type annotation at the
+    /// colon in the source text, which quotes and escapes shift from its
+    /// place in `token`
+    fn field(&mut self, span: Range<usize>, token: &str) {
+        let raw = &self.parser.text[span.clone()];
+        match token.find(':').and(raw.find(':')) {
+            Some(colon) => {
+                self.push(TokenKind::Field, span.start, span.start + colon);
+                self.push(TokenKind::TypeAnnotation, span.start + colon + 1, span.end);
+            }
+            None => self.push(TokenKind::Field, span.start, span.end),
+        }
+    }
+
+    /// A trailing `#` comment on `line`, if any
+    fn comment(&mut self, line: &str) {
+        let (comment, _) = Parser::scan_line(line);
+        if comment < line.len() {
+            let start = self.parser.offset(line);
+            self.push(TokenKind::Comment, start + comment, start + line.len());
+        }
+    }
+
+    fn value_kind(&self, span: &Range<usize>, token: &str) -> TokenKind {
+        if self.parser.text.as_bytes()[span.start] == b'"' {
+            return TokenKind::String;
+        }
+        match self.parser.parse_value(token) {
+            Ok(Value::Null) => TokenKind::Null,
+            Ok(Value::Bool(_)) => TokenKind::Bool,
+            Ok(Value::Int(_)) => TokenKind::Int,
+            Ok(Value::Float(_)) => TokenKind::Float,
+            #[cfg(feature = "rust_decimal")]
+            Ok(Value::Decimal(_)) => TokenKind::Float,
+            Ok(Value::String(_)) => TokenKind::String,
+            Ok(Value::Reference(_)) => TokenKind::Reference,
+            Ok(Value::Bytes(_)) => TokenKind::Bytes,
+            Err(_) => TokenKind::Invalid,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use TokenKind::*;
+
+    #[test]
+    fn test_lex() {
+        let text = "# header comment\ntable.docs\nid body:string   data:bytes\n\
+                    1 \"\"\"two\nlines\"\"\" b64:SGk=\n2.5 ~ true # note \"quoted\"\n\
+                    :a:b:c nan x\n\n\
+                    object.config\nkey value\nmode:string fast\napp.name 1\n\
+                    next.thing\noops";
+        let options = ParseOptions {
+            key_value_objects: true,
+            ..Default::default()
+        };
+        let tokens: Vec<_> = lex_with_options(text, &options)
+            .map(|t| (t.kind, &text[t.span]))
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                (Comment, "# header comment"),
+                (Header, "table.docs"),
+                (Field, "id"),
+                (Field, "body"),
+                (TypeAnnotation, "string"),
+                (Field, "data"),
+                (TypeAnnotation, "bytes"),
+                (Int, "1"),
+                (String, "\"\"\"two\nlines\"\"\""),
+                (Bytes, "b64:SGk="),
+                (Float, "2.5"),
+                (Null, "~"),
+                (Bool, "true"),
+                (Comment, "# note \"quoted\""),
+                (Invalid, ":a:b:c"),
+                (Float, "nan"),
+                (String, "x"),
+                (Header, "object.config"),
+                (Field, "key"),
+                (Field, "value"),
+                (Field, "mode"),
+                (TypeAnnotation, "string"),
+                (String, "fast"),
+                (Field, "app.name"),
+                (Int, "1"),
+                (Header, "next.thing"),
+                (Field, "oops"),
+            ]
+        );
+        let text = "table.t\n\"é:x\" \"q\\\"a:int\" ü\n1 2 3";
+        let tokens: Vec<_> = lex(text).map(|t| (t.kind, &text[t.span])).collect();
+        assert_eq!(
+            tokens,
+            [
+                (Header, "table.t"),
+                (Field, "\"é"),
+                (TypeAnnotation, "x\""),
+                (Field, "\"q\\\"a"),
+                (TypeAnnotation, "int\""),
+                (Field, "ü"),
+                (Int, "1"),
+                (Int, "2"),
+                (Int, "3"),
+            ]
+        );
+        // Without `key_value_objects`, `key value` names two columns
+        let kinds: Vec<_> = lex("object.o\nkey value\nmode fast")
+            .map(|t| t.kind)
+            .collect();
+        assert_eq!(kinds, [Header, Field, Field, String, String]);
+        let kinds: Vec<_> = lex("table.t\n\nx.y\n1").map(|t| t.kind).collect();
+        assert_eq!(kinds, [Header, Field, Int]);
+        assert_eq!(lex("no header").next().unwrap().kind, Invalid);
+        assert_eq!(lex("").count(), 0);
+    }
+}
@@ -0,0 +1,171 @@
+//! Original spellings of numbers, for lossless round trips
+//!
+//! The parser normally keeps only a number's value, so `0.50`, `1e6`, and
+//! `007` come back from [`dumps`](crate::dumps) as `0.5`, `1000000.0`, and
+//! `7`. With [`ParseOptions::lossless_numbers`](crate::ParseOptions::lossless_numbers)
+//! it also records, in [`Block::number_text`](crate::Block::number_text), the
+//! text of every numeric cell the serializer would write differently, and the
+//! serializer writes that text back as long as the cell still holds the value
+//! it spells.
+
+use alloc::collections::BTreeMap;
+
+use crate::prelude::*;
+use crate::{CellType, FieldName, Parser, Token, Value};
+
+/// Source text of numeric cells, by row and field
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NumberText {
+    rows: Vec<BTreeMap<FieldName, String>>,
+    summary_rows: Vec<BTreeMap<FieldName, String>>,
+}
+
+impl NumberText {
+    /// Recorded text of a data row's cell
+    pub fn get(&self, row: usize, field: &str) -> Option<&str> {
+        self.row(false, row)?.get(field).map(String::as_str)
+    }
+
+    /// Recorded text of a summary row's cell
+    pub fn get_summary(&self, row: usize, field: &str) -> Option<&str> {
+        self.row(true, row)?.get(field).map(String::as_str)
+    }
+
+    /// Whether nothing is recorded
+    pub fn is_empty(&self) -> bool {
+        self.rows
+            .iter()
+            .chain(&self.summary_rows)
+            .all(BTreeMap::is_empty)
+    }
+
+    /// Forget every recorded spelling, so the block serializes normalized
+    pub fn clear(&mut self) {
+        self.rows.clear();
+        self.summary_rows.clear();
+    }
+
+    pub(crate) fn row(&self, summary: bool, row: usize) -> Option<&BTreeMap<FieldName, String>> {
+        let rows = if summary {
+            &self.summary_rows
+        } else {
+            &self.rows
+        };
+        rows.get(row)
+    }
+
+    pub(crate) fn insert_row(
+        &mut self,
+        summary: bool,
+        row: usize,
+        text: BTreeMap<FieldName, String>,
+    ) {
+        if text.is_empty() {
+            return;
+        }
+        let rows = if summary {
+            &mut self.summary_rows
+        } else {
+            &mut self.rows
+        };
+        if rows.len() <= row {
+            rows.resize_with(row + 1, BTreeMap::new);
+        }
+        rows[row] = text;
+    }
+
+    /// Put data rows' spellings in the order the rows now have: row `i`
+    /// takes what row `order[i]` had
+    pub(crate) fn reorder(&mut self, order: &[usize]) {
+        if self.rows.is_empty() {
+            return;
+        }
+        let mut old = core::mem::take(&mut self.rows);
+        for (i, &from) in order.iter().enumerate() {
+            if let Some(text) = old.get_mut(from) {
+                self.insert_row(false, i, core::mem::take(text));
+            }
+        }
+    }
+
+    /// Take over `other`'s spellings for rows appended after `rows` data
+    /// rows and `summary_rows` summary rows
+    pub(crate) fn append(&mut self, other: NumberText, rows: usize, summary_rows: usize) {
+        for (i, text) in other.rows.into_iter().enumerate() {
+            self.insert_row(false, rows + i, text);
+        }
+        for (i, text) in other.summary_rows.into_iter().enumerate() {
+            self.insert_row(true, summary_rows + i, text);
+        }
+    }
+}
+
+/// Whether `text` reads back as `value` in a column of `cell_type`, so the
+/// serializer can write it instead of the normalized form
+pub(crate) fn spells(text: &str, value: &Value, cell_type: CellType) -> bool {
+    let token = Token {
+        text: text.into(),
+        quoted: false,
+    };
+    Parser::new("")
+        .parse_cell(&token, cell_type)
+        .is_ok_and(|parsed| parsed == *value)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{dumps, parse, parse_with_options, ParseOptions, Value};
+
+    fn lossless(text: &str) -> crate::Document {
+        let options = ParseOptions {
+            lossless_numbers: true,
+            ..Default::default()
+        };
+        parse_with_options(text, &options).unwrap()
+    }
+
+    #[test]
+    fn test_lossless_numbers() {
+        let text =
+            "table.prices\nid price qty note\n007 0.50 1e6 +1\n2 1.0 -0 x\n---\ntotal 1.500 ~ ~";
+        assert_eq!(
+            dumps(&parse(text).unwrap(), false),
+            "table.prices\nid price qty note\n7 0.5 1000000.0 1\n2 1.0 0 x\n---\ntotal 1.5 null null"
+        );
+
+        let mut doc = lossless(text);
+        let prices = &doc["prices"];
+        assert_eq!(prices.number_text.get(0, "id"), Some("007"));
+        assert_eq!(prices.number_text.get(1, "price"), None);
+        assert_eq!(prices.number_text.get_summary(0, "price"), Some("1.500"));
+        assert_eq!(
+            dumps(&doc, false),
+            "table.prices\nid price qty note\n007 0.50 1e6 +1\n2 1.0 -0 x\n---\ntotal 1.500 null null"
+        );
+        assert!(dumps(&doc, true).contains("\n007   0.50  1e6  +1\n"));
+
+        // Changed cells fall back to the normal form
+        let prices = &mut doc.blocks[0];
+        prices.rows[0].insert("price".into(), Value::Float(0.75));
+        prices.rows[0].insert("qty".into(), Value::Int(1_000_000));
+        assert!(dumps(&doc, false).contains("\n007 0.75 1000000 +1\n"));
+
+        doc.blocks[0].number_text.clear();
+        assert!(doc.blocks[0].number_text.is_empty());
+        assert!(dumps(&doc, false).contains("\n7 0.75 1000000 1\n"));
+    }
+
+    #[test]
+    fn test_lossless_merge() {
+        let doc = parse_with_options(
+            "table.t\nx\n1.10\n\ntable.t\nx\n02",
+            &ParseOptions {
+                lossless_numbers: true,
+                duplicate_blocks: crate::DuplicateBlockPolicy::MergeRows,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(dumps(&doc, false), "table.t\nx\n1.10\n02");
+    }
+}
@@ -25,17 +25,257 @@
 //! // Serialize back
 //! let output = dumps(&doc, true);
 //! ```
-
+//!
+//! ## Panics
+//!
+//! Decoding never panics on untrusted input: [`parse`], [`loads`],
+//! [`parse_isonl`], [`from_isonb`], [`from_csv`], [`from_markdown_table`],
+//! [`Document::select`], and the MessagePack/CBOR decoders return `Err` for
+//! anything malformed, whatever the bytes. The cargo-fuzz targets in `fuzz/`
+//! and `tests/no_panic.rs` check this. Indexing (`doc["users"]`, `block[0]`)
+//! panics on a missing entry, like the std collections; use `get` instead.
+//!
+//! ## `no_std`
+//!
+//! The parser, serializer, and the conversions that do not need I/O build
+//! with `no_std + alloc` when the default `std` feature is disabled. Rows are
+//! then `hashbrown` maps, and JSON, MessagePack/CBOR, the CLI, and the
+//! WASM/C bindings are unavailable because they depend on `std`.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use core::fmt;
+#[cfg(not(feature = "std"))]
+use hashbrown::HashMap;
+#[cfg(feature = "std")]
 use std::collections::HashMap;
-use std::fmt;
+
+/// `alloc` items that `std` builds get from the standard prelude
+mod prelude {
+    pub(crate) use alloc::format;
+    pub(crate) use alloc::string::{String, ToString};
+    pub(crate) use alloc::vec;
+    pub(crate) use alloc::vec::Vec;
+}
+use prelude::*;
 
 // Plugins module (feature-gated)
 pub mod plugins;
 
+pub mod aggregate;
+#[cfg(feature = "std")]
+pub mod append;
+mod base64;
+pub mod check;
+pub mod chunk;
+pub mod convert;
+pub mod csv;
+pub mod edit;
+pub mod embedding;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod format;
+pub mod graph;
+pub mod history;
+pub mod incremental;
+pub mod integrity;
+#[cfg(feature = "std")]
+pub mod journal;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+#[cfg(any(feature = "msgpack", feature = "cbor"))]
+pub mod interchange;
+pub mod isonb;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod lazy;
+pub mod lex;
+pub mod lexeme;
+pub mod llm;
+pub mod markdown;
+pub mod meta;
+pub mod merge;
+pub mod observe;
+pub mod options;
+pub mod profile;
+pub mod progress;
+#[cfg(feature = "prost")]
+pub mod proto;
+#[cfg(feature = "redact")]
+pub mod redact;
+pub mod repair;
+pub mod resolve;
+pub mod sample;
+pub mod secure;
+pub mod select;
+pub mod shared;
+pub mod sort;
+pub mod spans;
+#[cfg(feature = "std")]
+pub mod store;
+#[cfg(feature = "serde")]
+pub mod tagged;
+#[cfg(feature = "std")]
+pub mod stream;
+pub mod tokens;
+pub mod transaction;
+#[cfg(feature = "serde")]
+pub mod typed;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "web")]
+pub mod web;
+
+pub use aggregate::{Aggregate, ColumnStats};
+#[cfg(feature = "std")]
+pub use append::IsonlAppender;
+pub use base64::BYTES_PREFIX;
+pub use check::{check_schema, SchemaViolation};
+pub use chunk::ChunkOptions;
+pub use csv::from_csv;
+pub use edit::Keep;
+pub use format::{format_edits, format_ison, FormatStyle};
+pub use isonb::{from_isonb, to_isonb};
+#[cfg(feature = "serde")]
+pub use json::JsonExportOptions;
+pub use lazy::{parse_lazy, LazyBlock, LazyDocument};
+pub use lex::{lex, lex_with_options, LexToken, TokenKind};
+pub use lexeme::NumberText;
+pub use llm::{parse_from_llm_response, PromptStyle};
+pub use markdown::from_markdown_table;
+pub use meta::DocumentMeta;
+pub use options::{
+    Dialect, DumpOptions, DuplicateBlockPolicy, NonFinitePolicy, ParseOptions, RowFilter,
+};
+pub use progress::{CancellationToken, Progress, ProgressCallback};
+#[cfg(feature = "redact")]
+pub use redact::{Redaction, RedactionRules};
+pub use repair::{parse_repair, Repair, RepairKind, RepairReport};
+pub use sample::SampleStrategy;
+pub use secure::{dumps_encrypted, parse_decrypted, Cipher};
+pub use select::Selected;
+pub use shared::SharedDocument;
+pub use sort::{compare_values, NullOrder, SortOrder};
+pub use spans::{
+    diagnostics, diagnostics_with_options, parse_with_spans, parse_with_spans_and_options,
+    SpannedDocument,
+};
+#[cfg(feature = "std")]
+pub use stream::{
+    convert_ison_to_isonl, convert_ison_to_isonl_with_options, convert_isonl_to_ison,
+};
+pub use tokens::{dumps_within_budget, estimate_tokens, HeuristicTokenizer, TokenEstimate, Tokenizer};
+pub use transaction::{Transaction, TransactionError};
+
+#[cfg(feature = "rust_decimal")]
+pub use rust_decimal::Decimal;
+
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-pub const VERSION: &str = "1.0.1";
+/// Crate version, taken from `Cargo.toml` so it never drifts from the published package
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Version of the ISON specification implemented by this parser
+pub const SPEC_VERSION: &str = "1.0";
+
+// =============================================================================
+// Capabilities
+// =============================================================================
+
+/// Structured report of what this build of the crate supports.
+///
+/// Intended for tools that negotiate between several ISON implementations or
+/// plugin hosts and need to adapt at runtime instead of parsing `VERSION`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Capabilities {
+    /// Crate version (same as [`VERSION`])
+    pub version: &'static str,
+    /// Implemented spec version (same as [`SPEC_VERSION`])
+    pub spec_version: &'static str,
+    /// Cargo features compiled into this build
+    pub features: Vec<&'static str>,
+    /// Plugins available in this build
+    pub plugins: Vec<&'static str>,
+    /// ISONL line format support
+    pub isonl: bool,
+    /// ISONB binary encoding, with its format version
+    pub isonb: Option<u8>,
+    /// Summary rows after a `---` separator
+    pub summary_rows: bool,
+    /// Inline array values
+    pub arrays: bool,
+    /// Directive lines (e.g. `#!ison/1.0`)
+    pub directives: bool,
+}
+
+impl Capabilities {
+    /// Check whether a cargo feature is compiled in
+    pub fn has_feature(&self, name: &str) -> bool {
+        self.features.contains(&name)
+    }
+
+    /// Check whether a plugin is available
+    pub fn has_plugin(&self, name: &str) -> bool {
+        self.plugins.contains(&name)
+    }
+}
+
+/// Report the capabilities of this build
+///
+/// # Example
+///
+/// ```rust
+/// let caps = ison_rs::capabilities();
+/// assert_eq!(caps.version, ison_rs::VERSION);
+/// if caps.has_feature("serde") {
+///     // JSON conversion is available
+/// }
+/// ```
+pub fn capabilities() -> Capabilities {
+    let features = [
+        ("std", cfg!(feature = "std")),
+        ("serde", cfg!(feature = "serde")),
+        ("msgpack", cfg!(feature = "msgpack")),
+        ("cbor", cfg!(feature = "cbor")),
+        ("cli", cfg!(feature = "cli")),
+        ("wasm", cfg!(feature = "wasm")),
+        ("ffi", cfg!(feature = "ffi")),
+        ("rust_decimal", cfg!(feature = "rust_decimal")),
+        ("petgraph", cfg!(feature = "petgraph")),
+        ("aes-gcm", cfg!(feature = "aes-gcm")),
+        ("prost", cfg!(feature = "prost")),
+        ("redact", cfg!(feature = "redact")),
+        ("kafka", cfg!(feature = "kafka")),
+        ("ndarray", cfg!(feature = "ndarray")),
+        ("web", cfg!(feature = "web")),
+        ("axum", cfg!(feature = "axum")),
+        ("actix", cfg!(feature = "actix")),
+    ];
+    let plugins = [("rudradb", cfg!(feature = "rudradb"))];
+
+    let enabled = |list: &[(&'static str, bool)]| -> Vec<&'static str> {
+        list.iter().filter(|(_, on)| *on).map(|(name, _)| *name).collect()
+    };
+
+    Capabilities {
+        version: VERSION,
+        spec_version: SPEC_VERSION,
+        features: enabled(&features),
+        plugins: enabled(&plugins),
+        isonl: true,
+        isonb: Some(isonb::ISONB_VERSION),
+        summary_rows: true,
+        arrays: false,
+        directives: true,
+    }
+}
 
 // =============================================================================
 // Error Types
@@ -57,16 +297,27 @@ impl fmt::Display for ISONError {
     }
 }
 
+#[cfg(feature = "std")]
 impl std::error::Error for ISONError {}
 
-pub type Result<T> = std::result::Result<T, ISONError>;
+#[cfg(feature = "std")]
+impl From<std::io::Error> for ISONError {
+    fn from(e: std::io::Error) -> Self {
+        ISONError {
+            message: format!("I/O error: {}", e),
+            line: None,
+        }
+    }
+}
+
+pub type Result<T> = core::result::Result<T, ISONError>;
 
 // =============================================================================
 // Types
 // =============================================================================
 
 /// Reference to another record in the document
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Reference {
     pub id: String,
@@ -90,6 +341,12 @@ impl Reference {
         }
     }
 
+    /// Create a block-qualified reference: `:users.id:42`, or
+    /// `:lines.order_id,line:42,3` for a composite key
+    pub fn qualified(block: &str, fields: &[&str], key: &[&str]) -> Self {
+        Self::with_type(key.join(","), format!("{}.{}", block, fields.join(",")))
+    }
+
     /// Check if this is a relationship reference (UPPERCASE type)
     pub fn is_relationship(&self) -> bool {
         match &self.ref_type {
@@ -98,9 +355,9 @@ impl Reference {
         }
     }
 
-    /// Get namespace (for non-relationship references)
+    /// Get namespace (for references that are neither relationships nor block-qualified)
     pub fn get_namespace(&self) -> Option<&str> {
-        if self.is_relationship() {
+        if self.is_relationship() || self.target_block().is_some() {
             None
         } else {
             self.ref_type.as_deref()
@@ -116,6 +373,25 @@ impl Reference {
         }
     }
 
+    /// Block a qualified reference points into (`users` in `:users.id:42`)
+    pub fn target_block(&self) -> Option<&str> {
+        let (block, _) = self.ref_type.as_deref()?.split_once('.')?;
+        Some(block)
+    }
+
+    /// Key fields of a qualified reference (`["id"]` in `:users.id:42`), empty otherwise
+    pub fn target_fields(&self) -> Vec<&str> {
+        match self.ref_type.as_deref().and_then(|t| t.split_once('.')) {
+            Some((_, fields)) => fields.split(',').collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// The id split on `,` (`["42", "3"]` in `:lines.order_id,line:42,3`)
+    pub fn key_parts(&self) -> Vec<&str> {
+        self.id.split(',').collect()
+    }
+
     /// Convert to ISON string representation
     pub fn to_ison(&self) -> String {
         match &self.ref_type {
@@ -132,7 +408,12 @@ impl fmt::Display for Reference {
 }
 
 /// Value types in ISON
-#[derive(Debug, Clone, PartialEq)]
+///
+/// Equality is structural, with two exceptions for floats so that `Value`
+/// can be `Eq` and `Hash`: every NaN equals every other NaN, and `0.0`
+/// equals `-0.0`. Values of different variants are never equal, even
+/// `Int(1)` and `Float(1.0)`. The ordering is [`compare_values`](crate::compare_values).
+#[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "serde", serde(untagged))]
 pub enum Value {
@@ -142,6 +423,14 @@ pub enum Value {
     Float(f64),
     String(String),
     Reference(Reference),
+    /// Binary data, written as a `b64:` token; serializes as that token's text
+    #[cfg_attr(feature = "serde", serde(serialize_with = "base64::serialize", skip_deserializing))]
+    Bytes(Vec<u8>),
+    /// Exact decimal, from a `decimal` column or
+    /// [`ParseOptions::exact_decimals`]. Last so untagged deserialization
+    /// never prefers it; serializes as a string, e.g. `"19.99"`.
+    #[cfg(feature = "rust_decimal")]
+    Decimal(Decimal),
 }
 
 impl Value {
@@ -161,6 +450,16 @@ impl Value {
         matches!(self, Value::Float(_))
     }
 
+    /// Int, float, or decimal
+    pub fn is_number(&self) -> bool {
+        match self {
+            Value::Int(_) | Value::Float(_) => true,
+            #[cfg(feature = "rust_decimal")]
+            Value::Decimal(_) => true,
+            _ => false,
+        }
+    }
+
     pub fn is_string(&self) -> bool {
         matches!(self, Value::String(_))
     }
@@ -183,10 +482,21 @@ impl Value {
         }
     }
 
+    pub fn is_bytes(&self) -> bool {
+        matches!(self, Value::Bytes(_))
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    pub fn is_decimal(&self) -> bool {
+        matches!(self, Value::Decimal(_))
+    }
+
     pub fn as_float(&self) -> Option<f64> {
         match self {
             Value::Float(f) => Some(*f),
             Value::Int(i) => Some(*i as f64),
+            #[cfg(feature = "rust_decimal")]
+            Value::Decimal(d) => rust_decimal::prelude::ToPrimitive::to_f64(d),
             _ => None,
         }
     }
@@ -204,6 +514,23 @@ impl Value {
             _ => None,
         }
     }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Bytes(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Exact decimal value; integers widen
+    #[cfg(feature = "rust_decimal")]
+    pub fn as_decimal(&self) -> Option<Decimal> {
+        match self {
+            Value::Decimal(d) => Some(*d),
+            Value::Int(i) => Some(Decimal::from(*i)),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for Value {
@@ -215,12 +542,112 @@ impl fmt::Display for Value {
             Value::Float(fl) => write!(f, "{}", fl),
             Value::String(s) => write!(f, "{}", s),
             Value::Reference(r) => write!(f, "{}", r),
+            Value::Bytes(b) => write!(f, "{}{}", BYTES_PREFIX, base64::encode(b)),
+            #[cfg(feature = "rust_decimal")]
+            Value::Decimal(d) => write!(f, "{}", d),
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Null, Value::Null) => true,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Int(a), Value::Int(b)) => a == b,
+            (Value::Float(a), Value::Float(b)) => a == b || (a.is_nan() && b.is_nan()),
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Reference(a), Value::Reference(b)) => a == b,
+            (Value::Bytes(a), Value::Bytes(b)) => a == b,
+            #[cfg(feature = "rust_decimal")]
+            (Value::Decimal(a), Value::Decimal(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Value {}
+
+impl core::hash::Hash for Value {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        core::mem::discriminant(self).hash(state);
+        match self {
+            Value::Null => {}
+            Value::Bool(b) => b.hash(state),
+            Value::Int(i) => i.hash(state),
+            // One hash for all NaNs and for both zeros, matching `eq`
+            Value::Float(f) if f.is_nan() => f64::NAN.to_bits().hash(state),
+            Value::Float(f) if *f == 0.0 => 0u64.hash(state),
+            Value::Float(f) => f.to_bits().hash(state),
+            Value::String(s) => s.hash(state),
+            Value::Reference(r) => r.hash(state),
+            Value::Bytes(b) => b.hash(state),
+            #[cfg(feature = "rust_decimal")]
+            Value::Decimal(d) => d.hash(state),
+        }
+    }
+}
+
+/// Row key: a field name shared by every row of a block.
+///
+/// Parsers allocate each field name once per block (see [`Block::field_keys`])
+/// and clone the `Arc` into each row, so a million-row table holds one copy
+/// of `"id"` rather than a million.
+pub type FieldName = Arc<str>;
+
+/// Key for `name`, shared with `keys` when it is one of them
+pub(crate) fn intern(keys: &[FieldName], name: &str) -> FieldName {
+    keys.iter()
+        .find(|k| &***k == name)
+        .cloned()
+        .unwrap_or_else(|| name.into())
+}
+
+/// Whether `fields` marks a block of `kind` as `key value` pairs, under
+/// [`ParseOptions::key_value_objects`]
+pub(crate) fn is_pairs_header<'f>(kind: &str, fields: impl IntoIterator<Item = &'f str>) -> bool {
+    (kind == "object" || kind == "meta") && fields.into_iter().eq(["key", "value"])
+}
+
+/// Whether a trimmed line ends the block it is in: a blank line or a new
+/// header.
+///
+/// As in the Python and JavaScript parsers, only a line that is nothing but
+/// `kind.name`, each an identifier, is a header; rows such as `example.com 10`
+/// or `app.name demo` stay in the block.
+pub(crate) fn ends_block(line: &str) -> bool {
+    let ident = |s: &str| {
+        s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && s.chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    };
+    line.is_empty()
+        || line
+            .split_once('.')
+            .is_some_and(|(kind, name)| ident(kind) && ident(name))
+}
+
+/// Edit distance between `a` and `b`, counted in chars
+pub(crate) fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
         }
+        prev = cur;
     }
+    prev[b.len()]
 }
 
-/// A row of data (field name -> value mapping)
-pub type Row = HashMap<String, Value>;
+/// A row of data (field name -> value mapping).
+///
+/// A `std::collections::HashMap` with the `std` feature, otherwise a
+/// `hashbrown::HashMap`. Look values up with `&str` (`row.get("id")`,
+/// `row["id"]`); insert with a [`FieldName`] or `field.into()`.
+pub type Row = HashMap<FieldName, Value>;
 
 /// Field information including optional type annotation
 #[derive(Debug, Clone)]
@@ -249,6 +676,25 @@ impl FieldInfo {
             is_computed,
         }
     }
+
+    /// How the parser reads this column's cells, from the annotation (ignoring a `?` suffix)
+    pub(crate) fn cell_type(&self) -> CellType {
+        match self.field_type.as_deref().map(|t| t.trim_end_matches('?')) {
+            Some("decimal") => CellType::Decimal,
+            Some("bytes") => CellType::Bytes,
+            _ => CellType::Any,
+        }
+    }
+}
+
+/// Column annotations that change how cells parse
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CellType {
+    Any,
+    /// `decimal`: numbers stay exact (`rust_decimal` builds)
+    Decimal,
+    /// `bytes`: cells are base64 without the `b64:` prefix
+    Bytes,
 }
 
 /// A block of structured data
@@ -261,6 +707,10 @@ pub struct Block {
     pub field_info: Vec<FieldInfo>,
     pub rows: Vec<Row>,
     pub summary_rows: Vec<Row>,
+    /// Original spellings of numbers, recorded under
+    /// [`ParseOptions::lossless_numbers`] and written back by the serializer
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub number_text: NumberText,
 }
 
 impl Block {
@@ -272,6 +722,7 @@ impl Block {
             field_info: Vec::new(),
             rows: Vec::new(),
             summary_rows: Vec::new(),
+            number_text: NumberText::default(),
         }
     }
 
@@ -290,6 +741,38 @@ impl Block {
         self.rows.get(index)
     }
 
+    /// Row keys for `fields`, one shared allocation per field name
+    pub fn field_keys(&self) -> Vec<FieldName> {
+        self.fields.iter().map(|f| FieldName::from(f.as_str())).collect()
+    }
+
+    /// Add a field from its header definition, `name` or `name:type`
+    pub(crate) fn add_field_def(&mut self, def: &str) {
+        match def.split_once(':') {
+            Some((name, field_type)) => {
+                self.fields.push(name.to_string());
+                self.field_info.push(FieldInfo::with_type(name, field_type));
+            }
+            None => {
+                self.fields.push(def.to_string());
+                self.field_info.push(FieldInfo::new(def));
+            }
+        }
+    }
+
+    /// Cell type of each field, from its annotation
+    pub(crate) fn cell_types(&self) -> Vec<CellType> {
+        self.fields.iter().map(|f| self.cell_type(f)).collect()
+    }
+
+    /// Cell type of one field, from its annotation
+    pub(crate) fn cell_type(&self, field: &str) -> CellType {
+        self.field_info
+            .iter()
+            .find(|fi| fi.name == field)
+            .map_or(CellType::Any, FieldInfo::cell_type)
+    }
+
     /// Get field type annotation
     pub fn get_field_type(&self, field_name: &str) -> Option<&str> {
         self.field_info
@@ -308,7 +791,7 @@ impl Block {
     }
 }
 
-impl std::ops::Index<usize> for Block {
+impl core::ops::Index<usize> for Block {
     type Output = Row;
 
     fn index(&self, index: usize) -> &Self::Output {
@@ -338,6 +821,123 @@ impl Document {
         self.blocks.iter_mut().find(|b| b.name == name)
     }
 
+    /// Get block by name, or an error naming the closest block names.
+    ///
+    /// ```rust
+    /// let doc = ison_rs::parse("table.users\nid\n1\n\ntable.orders\nid\n2")?;
+    /// let err = doc.get_or_suggest("user").unwrap_err();
+    /// assert_eq!(err.message, "Block 'user' not found; did you mean 'users'?");
+    /// # Ok::<(), ison_rs::ISONError>(())
+    /// ```
+    pub fn get_or_suggest(&self, name: &str) -> Result<&Block> {
+        if let Some(block) = self.get(name) {
+            return Ok(block);
+        }
+        // Allow about one typo per three characters
+        let limit = (name.chars().count() / 3).max(1);
+        let mut close: Vec<(usize, &str)> = Vec::new();
+        for block in &self.blocks {
+            let distance = levenshtein(&name.to_lowercase(), &block.name.to_lowercase());
+            if distance <= limit && !close.iter().any(|(_, n)| *n == block.name) {
+                close.push((distance, &block.name));
+            }
+        }
+        close.sort_by_key(|(distance, _)| *distance);
+
+        let message = if close.is_empty() {
+            format!("Block '{}' not found; {}", name, self.block_names())
+        } else {
+            let names: Vec<String> = close.iter().map(|(_, n)| format!("'{}'", n)).collect();
+            format!(
+                "Block '{}' not found; did you mean {}?",
+                name,
+                names.join(" or ")
+            )
+        };
+        Err(ISONError {
+            message,
+            line: None,
+        })
+    }
+
+    /// The distinct block names, for error messages
+    fn block_names(&self) -> String {
+        let mut names: Vec<&str> = Vec::new();
+        for block in &self.blocks {
+            if !names.contains(&block.name.as_str()) {
+                names.push(&block.name);
+            }
+        }
+        if names.is_empty() {
+            "the document has no blocks".to_string()
+        } else {
+            format!("available blocks: {}", names.join(", "))
+        }
+    }
+
+    /// Get block by kind and name, e.g. `("object", "config")`
+    pub fn get_kind_name(&self, kind: &str, name: &str) -> Option<&Block> {
+        self.blocks.iter().find(|b| b.kind == kind && b.name == name)
+    }
+
+    /// Get mutable block by kind and name
+    pub fn get_kind_name_mut(&mut self, kind: &str, name: &str) -> Option<&mut Block> {
+        self.blocks
+            .iter_mut()
+            .find(|b| b.kind == kind && b.name == name)
+    }
+
+    /// Key-value map of the `object.<name>` block (its first data row)
+    pub fn get_object(&self, name: &str) -> Option<&Row> {
+        self.get_kind_name("object", name)?.rows.first()
+    }
+
+    /// Mutable key-value map of the `object.<name>` block
+    pub fn get_object_mut(&mut self, name: &str) -> Option<&mut Row> {
+        self.get_kind_name_mut("object", name)?.rows.first_mut()
+    }
+
+    /// Get block by its header form, e.g. `"table.users"`.
+    ///
+    /// Splits at the first `.` like the parser, so `"table.users.v2"` is
+    /// kind `table`, name `users.v2`.
+    pub fn get_qualified(&self, qualified: &str) -> Option<&Block> {
+        let (kind, name) = qualified.split_once('.')?;
+        self.get_kind_name(kind, name)
+    }
+
+    /// All blocks of one kind, in document order
+    pub fn get_by_kind(&self, kind: &str) -> Vec<&Block> {
+        self.blocks.iter().filter(|b| b.kind == kind).collect()
+    }
+
+    /// Blocks grouped by kind, kinds in order of first appearance
+    pub fn blocks_by_kind(&self) -> Vec<(&str, Vec<&Block>)> {
+        let mut groups: Vec<(&str, Vec<&Block>)> = Vec::new();
+        for block in &self.blocks {
+            match groups.iter_mut().find(|(kind, _)| *kind == block.kind) {
+                Some((_, blocks)) => blocks.push(block),
+                None => groups.push((&block.kind, vec![block])),
+            }
+        }
+        groups
+    }
+
+    /// A copy holding only the blocks named in `names`, in document order.
+    ///
+    /// [`parse_blocks`] gets the same result from text without parsing the
+    /// other blocks' rows.
+    pub fn subset(&self, names: &[&str]) -> Document {
+        Document {
+            blocks: self
+                .blocks
+                .iter()
+                .filter(|b| names.contains(&b.name.as_str()))
+                .cloned()
+                .collect(),
+        }
+    }
+
     /// Check if block exists
     pub fn has(&self, name: &str) -> bool {
         self.blocks.iter().any(|b| b.name == name)
@@ -356,25 +956,21 @@ impl Document {
     /// Convert to JSON string (requires serde feature)
     #[cfg(feature = "serde")]
     pub fn to_json(&self, pretty: bool) -> String {
-        let map: HashMap<&str, Vec<&Row>> = self
-            .blocks
-            .iter()
-            .map(|b| (b.name.as_str(), b.rows.iter().collect()))
-            .collect();
-
-        if pretty {
-            serde_json::to_string_pretty(&map).unwrap_or_default()
-        } else {
-            serde_json::to_string(&map).unwrap_or_default()
-        }
+        self.to_json_with_options(&json::JsonExportOptions {
+            pretty,
+            ..Default::default()
+        })
     }
 }
 
-impl std::ops::Index<&str> for Document {
+impl core::ops::Index<&str> for Document {
     type Output = Block;
 
     fn index(&self, name: &str) -> &Self::Output {
-        self.get(name).expect("Block not found")
+        match self.get(name) {
+            Some(block) => block,
+            None => panic!("Block '{}' not found; {}", name, self.block_names()),
+        }
     }
 }
 
@@ -382,18 +978,85 @@ impl std::ops::Index<&str> for Document {
 // Parser
 // =============================================================================
 
+/// Opens and closes a multi-line string cell
+const BLOCK_QUOTE: &str = "\"\"\"";
+
+/// A token of a line, without quotes or escapes
+pub(crate) struct Token<'l> {
+    pub(crate) text: Cow<'l, str>,
+    /// Written in quotes, so always a string
+    pub(crate) quoted: bool,
+}
+
+impl core::ops::Deref for Token<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.text
+    }
+}
+
+/// A line in the body of a block
+enum BlockLine<'a> {
+    Values(Vec<Token<'a>>),
+    /// `---`, which starts the summary rows
+    SummarySeparator,
+}
+
 struct Parser<'a> {
     text: &'a str,
     pos: usize,
     line: usize,
+    options: ParseOptions,
+    /// Names of the blocks to keep; others are skipped unparsed
+    only: Option<&'a [&'a str]>,
+    /// Source spans, collected for [`parse_with_spans`]
+    spans: Option<spans::SpanRecorder>,
+    monitor: progress::Monitor,
 }
 
 impl<'a> Parser<'a> {
     fn new(text: &'a str) -> Self {
+        Self::with_options(text, ParseOptions::default())
+    }
+
+    fn with_options(text: &'a str, options: ParseOptions) -> Self {
+        let monitor = progress::Monitor::new(options.progress.clone(), options.cancel.clone());
         Self {
             text,
             pos: 0,
             line: 1,
+            options,
+            only: None,
+            spans: None,
+            monitor,
+        }
+    }
+
+    /// Number of the line last read, for errors about it (`line` is the
+    /// next line's)
+    fn last_line(&self) -> usize {
+        (self.line - 1).max(1)
+    }
+
+    /// Byte offset of `s`, a slice of the text
+    fn offset(&self, s: &str) -> usize {
+        s.as_ptr() as usize - self.text.as_ptr() as usize
+    }
+
+    /// Note `line` as the one being parsed, with its tokens, when
+    /// collecting spans
+    fn record_line(&mut self, line: &str) {
+        if self.spans.is_none() {
+            return;
+        }
+        let start = self.offset(line);
+        let mut tokens = Vec::new();
+        self.tokenize_line_with(line, |range, _| {
+            tokens.push(start + range.start..start + range.end)
+        });
+        if let Some(spans) = &mut self.spans {
+            spans.line(start..start + line.len(), tokens);
         }
     }
 
@@ -403,16 +1066,21 @@ impl<'a> Parser<'a> {
         self.skip_whitespace_and_comments();
 
         while self.pos < self.text.len() {
+            let line = self.line;
+            self.monitor.check(Some(line))?;
             if let Some(block) = self.parse_block()? {
-                doc.blocks.push(block);
+                self.options.add_block(&mut doc, block, line)?;
             }
             self.skip_whitespace_and_comments();
         }
 
+        self.monitor.bytes = self.text.len();
+        self.monitor.finish();
         Ok(doc)
     }
 
-    fn parse_block(&mut self) -> Result<Option<Block>> {
+    /// Read a `kind.name` header line into an empty block
+    fn parse_header(&mut self) -> Result<Option<Block>> {
         let header_line = match self.read_line() {
             Some(line) => line,
             None => return Ok(None),
@@ -421,10 +1089,11 @@ impl<'a> Parser<'a> {
         if header_line.starts_with('#') || header_line.is_empty() {
             return Ok(None);
         }
+        self.record_line(header_line);
 
         let dot_index = header_line.find('.').ok_or_else(|| ISONError {
             message: format!("Invalid block header: {}", header_line),
-            line: Some(self.line),
+            line: Some(self.last_line()),
         })?;
 
         let kind = header_line[..dot_index].trim().to_string();
@@ -433,11 +1102,33 @@ impl<'a> Parser<'a> {
         if kind.is_empty() || name.is_empty() {
             return Err(ISONError {
                 message: format!("Invalid block header: {}", header_line),
-                line: Some(self.line),
+                line: Some(self.last_line()),
             });
         }
 
-        let mut block = Block::new(kind, name);
+        if self.spans.is_some() {
+            let kind_start = self.offset(header_line[..dot_index].trim());
+            let name_start = self.offset(header_line[dot_index + 1..].trim());
+            if let Some(spans) = &mut self.spans {
+                spans.header(
+                    kind_start..kind_start + kind.len(),
+                    name_start..name_start + name.len(),
+                );
+            }
+        }
+
+        Ok(Some(Block::new(kind, name)))
+    }
+
+    fn parse_block(&mut self) -> Result<Option<Block>> {
+        let Some(mut block) = self.parse_header()? else {
+            return Ok(None);
+        };
+
+        if self.only.is_some_and(|only| !only.contains(&block.name.as_str())) {
+            self.skip_block();
+            return Ok(None);
+        }
 
         // Parse field definitions
         self.skip_empty_lines();
@@ -446,32 +1137,173 @@ impl<'a> Parser<'a> {
             None => return Ok(Some(block)),
         };
 
-        let field_tokens = self.tokenize_line(&fields_line);
-        for token in field_tokens {
-            if let Some(colon_idx) = token.find(':') {
-                let field_name = token[..colon_idx].to_string();
-                let field_type = token[colon_idx + 1..].to_string();
-                block.fields.push(field_name.clone());
-                block.field_info.push(FieldInfo::with_type(field_name, field_type));
-            } else {
-                block.fields.push(token.clone());
-                block.field_info.push(FieldInfo::new(token));
+        self.record_line(fields_line);
+        self.check_line(fields_line)?;
+        let field_tokens = self.tokenize_line(fields_line);
+        if self.options.key_value_objects
+            && is_pairs_header(&block.kind, field_tokens.iter().map(|t| &**t))
+        {
+            return self.parse_pairs(block).map(Some);
+        }
+        if let Some(spans) = &mut self.spans {
+            spans.fields();
+        }
+        // Positions of the columns kept under `include_fields`
+        let mut columns = Vec::with_capacity(field_tokens.len());
+        for (column, token) in field_tokens.iter().enumerate() {
+            let def = self.options.field_def(token);
+            let name = def.split(':').next().unwrap_or("");
+            if self.options.includes(&block.name, name) {
+                block.add_field_def(&def);
+                columns.push(column);
             }
         }
 
         // Parse data rows
+        let keys = block.field_keys();
+        let cell_types = block.cell_types();
+        let canonical = self.options.lossless_numbers.then(|| Serializer::new(false));
         let mut in_summary = false;
-        while self.pos < self.text.len() {
-            let line = match self.peek_line() {
-                Some(line) => line,
-                None => break,
+        while let Some(line) = self.next_block_line()? {
+            let values = match line {
+                BlockLine::SummarySeparator => {
+                    in_summary = true;
+                    continue;
+                }
+                BlockLine::Values(values) => values,
+            };
+            self.monitor.bytes = self.pos;
+            self.monitor.row();
+            self.monitor.check(Some(self.line - 1))?;
+            if let Some(spans) = &mut self.spans {
+                spans.row(in_summary);
+            }
+
+            let mut row = Row::new();
+            let mut number_text = BTreeMap::new();
+            for ((key, &column), &cell_type) in keys.iter().zip(&columns).zip(&cell_types) {
+                let Some(token) = values.get(column) else {
+                    // Python fills missing trailing cells with null
+                    if self.options.dialect == Dialect::PythonCompat {
+                        row.insert(key.clone(), Value::Null);
+                        continue;
+                    }
+                    break;
+                };
+                let value = self.parse_cell(token, cell_type)?;
+                if let Some(serializer) = &canonical {
+                    if value.is_number() && serializer.serialize_value(&value) != **token {
+                        number_text.insert(key.clone(), token.to_string());
+                    }
+                }
+                row.insert(key.clone(), value);
+            }
+
+            if !in_summary && !self.options.keeps(&block.name, &row) {
+                continue;
+            }
+            let index = if in_summary { block.summary_rows.len() } else { block.rows.len() };
+            block.number_text.insert_row(in_summary, index, number_text);
+            if in_summary {
+                block.summary_rows.push(row);
+            } else {
+                block.rows.push(row);
+            }
+        }
+
+        Ok(Some(block))
+    }
+
+    /// Parse the body of an `object` or `meta` block written as `key value` pairs.
+    ///
+    /// Each `key[:type] value` line becomes a field of the block's single
+    /// row; pairs after `---` form a single summary row.
+    fn parse_pairs(&mut self, mut block: Block) -> Result<Block> {
+        let mut row = Row::new();
+        let mut summary = Row::new();
+        let mut in_summary = false;
+        while let Some(line) = self.next_block_line()? {
+            let values = match line {
+                BlockLine::SummarySeparator => {
+                    in_summary = true;
+                    continue;
+                }
+                BlockLine::Values(values) => values,
+            };
+            self.monitor.bytes = self.pos;
+            self.monitor.check(Some(self.line - 1))?;
+            let [key, value] = &values[..] else {
+                return Err(ISONError {
+                    message: format!("Expected a key and a value, found {} tokens", values.len()),
+                    line: Some(self.line - 1),
+                });
+            };
+
+            let (name, field_type) = match key.split_once(':') {
+                Some((name, field_type)) => (name, Some(field_type)),
+                None => (&**key, None),
+            };
+            let name = self.options.field_name(name);
+            if !self.options.includes(&block.name, name) {
+                continue;
+            }
+            let is_new = !block.fields.iter().any(|f| f == name);
+            if is_new {
+                block.fields.push(name.to_string());
+                block.field_info.push(match field_type {
+                    Some(field_type) => FieldInfo::with_type(name, field_type),
+                    None => FieldInfo::new(name),
+                });
+            }
+            if let Some(spans) = &mut self.spans {
+                let index = block.fields.iter().position(|f| f == name).unwrap_or(0);
+                spans.pair(index, is_new, in_summary);
+            }
+            let cell_type = block
+                .field_info
+                .iter()
+                .find(|fi| fi.name == name)
+                .map_or(CellType::Any, FieldInfo::cell_type);
+            let target = if in_summary { &mut summary } else { &mut row };
+            target.insert(name.into(), self.parse_cell(value, cell_type)?);
+        }
+
+        // Share keys between the data and summary rows
+        let keys = block.field_keys();
+        let rekey = |row: Row| -> Row {
+            row.into_iter()
+                .map(|(k, v)| (intern(&keys, &k), v))
+                .collect()
+        };
+        let row = rekey(row);
+        self.monitor.row();
+        if self.options.keeps(&block.name, &row) {
+            block.rows.push(row);
+        }
+        if let Some(spans) = &mut self.spans {
+            spans.object_row();
+        }
+        if !summary.is_empty() {
+            self.monitor.row();
+            block.summary_rows.push(rekey(summary));
+        }
+        Ok(block)
+    }
+
+    /// Consume the next line of the current block's body.
+    ///
+    /// `None` at a blank line, a new block header, or the end of input;
+    /// comment lines are skipped. Fails on a line the
+    /// [`Dialect`] rejects.
+    fn next_block_line(&mut self) -> Result<Option<BlockLine<'a>>> {
+        loop {
+            let Some(line) = self.peek_line() else {
+                return Ok(None);
             };
 
             // Empty line or new block = end of current block
-            if line.is_empty() || (line.chars().next().map(|c| c.is_alphabetic()).unwrap_or(false)
-                && line.contains('.'))
-            {
-                break;
+            if ends_block(line) {
+                return Ok(None);
             }
 
             self.read_line(); // consume the line
@@ -480,120 +1312,314 @@ impl<'a> Parser<'a> {
             if line.starts_with('#') {
                 continue;
             }
+            let line = self.continue_block_string(line);
+            self.record_line(line);
 
             // Summary separator
             if line.trim() == "---" {
-                in_summary = true;
-                continue;
+                return Ok(Some(BlockLine::SummarySeparator));
             }
 
-            let values = self.tokenize_line(&line);
+            self.check_line(line)?;
+            let values = self.tokenize_line(line);
             if values.is_empty() {
-                break;
+                return Ok(None);
             }
+            return Ok(Some(BlockLine::Values(values)));
+        }
+    }
 
-            let mut row = Row::new();
-            for (i, field) in block.fields.iter().enumerate() {
-                if i < values.len() {
-                    row.insert(field.clone(), self.parse_value(&values[i])?);
-                }
+    /// Consume the fields and rows of a block without tokenizing them
+    fn skip_block(&mut self) {
+        self.skip_empty_lines();
+        self.read_line();
+        // Same boundaries as `next_block_line`
+        while let Some(line) = self.peek_line() {
+            if ends_block(line) {
+                return;
             }
-
-            if in_summary {
-                block.summary_rows.push(row);
-            } else {
-                block.rows.push(row);
+            self.read_line();
+            if !line.starts_with('#') {
+                self.continue_block_string(line);
             }
         }
-
-        Ok(Some(block))
     }
 
-    fn tokenize_line(&self, line: &str) -> Vec<String> {
-        let mut tokens = Vec::new();
-        let mut chars: Vec<char> = line.chars().collect();
-        let mut i = 0;
-
-        // Remove inline comments
-        let mut in_quote = false;
-        let mut comment_start = None;
-        for (idx, &ch) in chars.iter().enumerate() {
-            if ch == '"' && (idx == 0 || chars[idx - 1] != '\\') {
-                in_quote = !in_quote;
-            } else if ch == '#' && !in_quote {
-                comment_start = Some(idx);
+    /// Extend `line` through the line that closes any `"""` string it opens.
+    ///
+    /// Without a closing `"""` the string runs to the end of the text.
+    fn continue_block_string(&mut self, line: &'a str) -> &'a str {
+        let start = line.as_ptr() as usize - self.text.as_ptr() as usize;
+        let mut line = line;
+        while Self::scan_line(line).1 {
+            let close = self.text[self.pos..].find(BLOCK_QUOTE);
+            let end = match close {
+                Some(close) => {
+                    let after = self.pos + close + BLOCK_QUOTE.len();
+                    self.text[after..].find('\n').map_or(self.text.len(), |n| after + n + 1)
+                }
+                None => self.text.len(),
+            };
+            self.line += self.text[self.pos..end].matches('\n').count();
+            self.pos = end;
+            line = self.text[start..end].trim_end();
+            if close.is_none() {
                 break;
             }
         }
-        if let Some(start) = comment_start {
-            chars.truncate(start);
-        }
+        line
+    }
+
+    /// Split a line into tokens, dropping any trailing `#` comment.
+    ///
+    /// Works on bytes: every delimiter is ASCII, so byte offsets always fall
+    /// on char boundaries. Tokens borrow from `line` unless a quoted string
+    /// contains escapes or carriage returns.
+    fn tokenize_line<'l>(&self, line: &'l str) -> Vec<Token<'l>> {
+        let mut tokens = Vec::new();
+        self.tokenize_line_with(line, |range, text| {
+            let quoted = line.as_bytes()[range.start] == b'"';
+            tokens.push(Token { text, quoted })
+        });
+        tokens
+    }
 
-        while i < chars.len() {
+    /// [`tokenize_line`](Self::tokenize_line), passing each token to `push`
+    /// with its byte range in `line`, quotes included
+    fn tokenize_line_with<'l>(
+        &self,
+        line: &'l str,
+        mut push: impl FnMut(core::ops::Range<usize>, Cow<'l, str>),
+    ) {
+        // Python only has whole-line comments
+        let line = match self.options.dialect {
+            Dialect::PythonCompat => line,
+            _ => &line[..Self::scan_line(line).0],
+        };
+        let bytes = line.as_bytes();
+        let mut i = 0;
+
+        while i < bytes.len() {
             // Skip whitespace
-            while i < chars.len() && (chars[i] == ' ' || chars[i] == '\t') {
+            while i < bytes.len() && (bytes[i] == b' ' || bytes[i] == b'\t') {
                 i += 1;
             }
 
-            if i >= chars.len() {
+            if i >= bytes.len() {
                 break;
             }
 
-            // Quoted string
-            if chars[i] == '"' {
-                let (token, new_pos) = self.parse_quoted_string(&chars, i);
-                tokens.push(token);
+            let start = i;
+            // Block string
+            if line[i..].starts_with(BLOCK_QUOTE) {
+                let (token, new_pos) = Self::parse_block_string(line, i);
+                i = new_pos;
+                push(start..i, token);
+            } else if bytes[i] == b'"' {
+                // Quoted string
+                let (token, new_pos) = self.parse_quoted_string(line, i);
                 i = new_pos;
+                push(start..i, token);
             } else {
                 // Unquoted token
-                let start = i;
-                while i < chars.len() && chars[i] != ' ' && chars[i] != '\t' {
+                while i < bytes.len() && bytes[i] != b' ' && bytes[i] != b'\t' {
                     i += 1;
                 }
-                tokens.push(chars[start..i].iter().collect());
+                push(start..i, Cow::Borrowed(&line[start..i]));
             }
         }
+    }
 
-        tokens
+    /// Offset of an inline `#` comment outside quotes (or the line length),
+    /// and whether the line ends inside an unclosed `"""` string
+    fn scan_line(line: &str) -> (usize, bool) {
+        let bytes = line.as_bytes();
+        let mut in_quote = false;
+        let mut idx = 0;
+        while idx < bytes.len() {
+            let b = bytes[idx];
+            let token_start = idx == 0 || bytes[idx - 1] == b' ' || bytes[idx - 1] == b'\t';
+            if !in_quote && token_start && line[idx..].starts_with(BLOCK_QUOTE) {
+                let body = idx + BLOCK_QUOTE.len();
+                match line[body..].find(BLOCK_QUOTE) {
+                    Some(close) => idx = body + close + BLOCK_QUOTE.len(),
+                    None => return (bytes.len(), true),
+                }
+                continue;
+            }
+            if b == b'"' && (idx == 0 || bytes[idx - 1] != b'\\') {
+                in_quote = !in_quote;
+            } else if b == b'#' && !in_quote {
+                return (idx, false);
+            }
+            idx += 1;
+        }
+        (bytes.len(), false)
     }
 
-    fn parse_quoted_string(&self, chars: &[char], start: usize) -> (String, usize) {
-        let mut result = String::new();
-        let mut i = start + 1; // skip opening quote
-
-        while i < chars.len() {
-            if chars[i] == '\\' {
-                if i + 1 < chars.len() {
-                    let next = chars[i + 1];
-                    match next {
-                        'n' => result.push('\n'),
-                        't' => result.push('\t'),
-                        'r' => result.push('\r'),
-                        '\\' => result.push('\\'),
-                        '"' => result.push('"'),
-                        _ => result.push(next),
+    /// Outside [`Dialect::V1`], fail on a quoted string `line` leaves open,
+    /// and under [`Dialect::Strict`] on an unquoted `#`, which only V1
+    /// reads as a comment
+    fn check_line(&self, line: &str) -> Result<()> {
+        if self.options.dialect == Dialect::V1 {
+            return Ok(());
+        }
+        let error = |message: String| ISONError {
+            message,
+            line: Some(self.last_line()),
+        };
+        let bytes = line.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] == b' ' || bytes[i] == b'\t' {
+                i += 1;
+            } else if line[i..].starts_with(BLOCK_QUOTE) {
+                let body = i + BLOCK_QUOTE.len();
+                i = line[body..]
+                    .find(BLOCK_QUOTE)
+                    .map_or(bytes.len(), |close| body + close + BLOCK_QUOTE.len());
+            } else if bytes[i] == b'"' {
+                i += 1;
+                loop {
+                    match bytes.get(i) {
+                        None => return Err(error("Unterminated quoted string".to_string())),
+                        Some(b'\\') if i + 1 == bytes.len() => {
+                            return Err(error("Unexpected end of line after backslash".to_string()))
+                        }
+                        Some(b'\\') => i += 2,
+                        Some(b'"') => break,
+                        Some(_) => i += 1,
                     }
-                    i += 2;
-                } else {
-                    result.push('\\');
-                    i += 1;
                 }
-            } else if chars[i] == '"' {
-                return (result, i + 1);
-            } else {
-                result.push(chars[i]);
                 i += 1;
+            } else {
+                let start = i;
+                while i < bytes.len() && bytes[i] != b' ' && bytes[i] != b'\t' {
+                    i += 1;
+                }
+                let token = &line[start..i];
+                if self.options.dialect == Dialect::Strict && token.contains('#') {
+                    return Err(error(format!("Unquoted '#': {}", token)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse the `"""` string opening at byte `start`, returning its raw
+    /// contents and the offset just past the closing quotes.
+    ///
+    /// A newline right after the opening quotes is dropped, and `\r\n`
+    /// becomes `\n`; nothing else is escaped.
+    fn parse_block_string(line: &str, start: usize) -> (Cow<'_, str>, usize) {
+        let body = start + BLOCK_QUOTE.len();
+        let (end, next) = match line[body..].find(BLOCK_QUOTE) {
+            Some(close) => (body + close, body + close + BLOCK_QUOTE.len()),
+            None => (line.len(), line.len()),
+        };
+        let raw = &line[body..end];
+        let raw = raw
+            .strip_prefix("\r\n")
+            .or_else(|| raw.strip_prefix('\n'))
+            .unwrap_or(raw);
+        let token = if raw.contains('\r') {
+            Cow::Owned(raw.replace("\r\n", "\n"))
+        } else {
+            Cow::Borrowed(raw)
+        };
+        (token, next)
+    }
+
+    /// Parse the quoted string opening at byte `start`, returning it and the
+    /// offset just past the closing quote
+    fn parse_quoted_string<'l>(&self, line: &'l str, start: usize) -> (Cow<'l, str>, usize) {
+        let bytes = line.as_bytes();
+        let body = start + 1; // skip opening quote
+
+        // Fast path: no escapes, borrow the contents
+        let mut i = body;
+        while i < bytes.len() && bytes[i] != b'"' && bytes[i] != b'\\' {
+            i += 1;
+        }
+        if i >= bytes.len() {
+            return (Cow::Borrowed(&line[body..]), i);
+        }
+        if bytes[i] == b'"' {
+            return (Cow::Borrowed(&line[body..i]), i + 1);
+        }
+
+        let mut result = String::from(&line[body..i]);
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\\' => match line[i + 1..].chars().next() {
+                    Some(next) => {
+                        result.push(match next {
+                            'n' => '\n',
+                            't' => '\t',
+                            'r' => '\r',
+                            other => other,
+                        });
+                        i += 1 + next.len_utf8();
+                    }
+                    None => {
+                        result.push('\\');
+                        i += 1;
+                    }
+                },
+                b'"' => return (Cow::Owned(result), i + 1),
+                _ => {
+                    // Copy up to the next quote or escape in one go
+                    let run = i;
+                    while i < bytes.len() && bytes[i] != b'"' && bytes[i] != b'\\' {
+                        i += 1;
+                    }
+                    result.push_str(&line[run..i]);
+                }
             }
         }
 
-        (result, i)
+        (Cow::Owned(result), i)
+    }
+
+    /// Parse a cell, honouring its column's [`CellType`]; other quoted
+    /// cells are strings whatever they hold
+    fn parse_cell(&self, cell: &Token, cell_type: CellType) -> Result<Value> {
+        let token = &*cell.text;
+        match cell_type {
+            #[cfg(feature = "rust_decimal")]
+            CellType::Decimal => {
+                if let Some(d) = parse_decimal(token) {
+                    return Ok(Value::Decimal(d));
+                }
+            }
+            CellType::Bytes if token != "null" && token != "~" => {
+                if let Some(bytes) = base64::decode(token) {
+                    return Ok(Value::Bytes(bytes));
+                }
+            }
+            _ => {}
+        }
+        if cell.quoted {
+            return Ok(Value::String(token.to_string()));
+        }
+        self.parse_value(token)
     }
 
     fn parse_value(&self, token: &str) -> Result<Value> {
         // Null
-        if token == "null" || token == "~" {
+        if token == "null" {
             return Ok(Value::Null);
         }
+        if token == "~" {
+            return match self.options.dialect {
+                Dialect::V1 => Ok(Value::Null),
+                Dialect::PythonCompat => Ok(Value::String(token.to_string())),
+                Dialect::Strict => Err(ISONError {
+                    message: "Ambiguous null: ~".to_string(),
+                    line: Some(self.last_line()),
+                }),
+            };
+        }
 
         // Boolean
         if token == "true" {
@@ -608,65 +1634,116 @@ impl<'a> Parser<'a> {
             return self.parse_reference(token);
         }
 
+        // Bytes, which Python does not have
+        let encoded = token.strip_prefix(BYTES_PREFIX);
+        if let Some(encoded) = encoded.filter(|_| self.options.dialect != Dialect::PythonCompat) {
+            return base64::decode(encoded).map(Value::Bytes).ok_or_else(|| ISONError {
+                message: format!("Invalid base64: {}", token),
+                line: Some(self.last_line()),
+            });
+        }
+
+        // Numbers Python reads as strings, such as `1e6`, `+5`, and `nan`
+        if self.options.dialect != Dialect::V1 && !is_plain_number(token) && reads_as_number(token)
+        {
+            if self.options.dialect == Dialect::Strict {
+                return Err(ISONError {
+                    message: format!("Ambiguous number: {}", token),
+                    line: Some(self.last_line()),
+                });
+            }
+            return Ok(Value::String(token.to_string()));
+        }
+
+        // Non-finite float tokens; Rust's own spellings (`NaN`, `infinity`) are strings
+        match token {
+            "nan" => return self.non_finite(f64::NAN, token),
+            "inf" | "+inf" => return self.non_finite(f64::INFINITY, token),
+            "-inf" => return self.non_finite(f64::NEG_INFINITY, token),
+            _ => {}
+        }
+
         // Integer
         if let Ok(i) = token.parse::<i64>() {
             return Ok(Value::Int(i));
         }
 
+        #[cfg(feature = "rust_decimal")]
+        if self.options.exact_decimals {
+            if let Some(d) = parse_decimal(token) {
+                return Ok(Value::Decimal(d));
+            }
+        }
+
         // Float
         if let Ok(f) = token.parse::<f64>() {
-            return Ok(Value::Float(f));
+            if f.is_finite() {
+                return Ok(Value::Float(f));
+            }
+            // Overflow such as `1e999`, as opposed to a word
+            if token.bytes().any(|b| b.is_ascii_digit()) {
+                return self.non_finite(f, token);
+            }
         }
 
         // String
         Ok(Value::String(token.to_string()))
     }
 
+    /// Apply the [`NonFinitePolicy`] to a NaN or infinite float
+    fn non_finite(&self, f: f64, token: &str) -> Result<Value> {
+        match self.options.non_finite {
+            NonFinitePolicy::Allow => Ok(Value::Float(f)),
+            NonFinitePolicy::Null => Ok(Value::Null),
+            NonFinitePolicy::Reject => Err(ISONError {
+                message: format!("Non-finite number: {}", token),
+                line: Some(self.last_line()),
+            }),
+        }
+    }
+
     fn parse_reference(&self, token: &str) -> Result<Value> {
         let content = &token[1..]; // skip ':'
+        if self.options.dialect == Dialect::PythonCompat {
+            // `:type:id`, where the id may hold colons; a lone `:` is text
+            return Ok(match content.split_once(':') {
+                _ if content.is_empty() => Value::String(token.to_string()),
+                Some((ref_type, id)) => Value::Reference(Reference::with_type(id, ref_type)),
+                None => Value::Reference(Reference::new(content)),
+            });
+        }
         let parts: Vec<&str> = content.split(':').collect();
 
         match parts.len() {
-            1 => Ok(Value::Reference(Reference::new(parts[0]))),
+            1 if !(content.is_empty() && self.options.dialect == Dialect::Strict) => {
+                Ok(Value::Reference(Reference::new(parts[0])))
+            }
             2 => Ok(Value::Reference(Reference::with_type(parts[1], parts[0]))),
             _ => Err(ISONError {
                 message: format!("Invalid reference: {}", token),
-                line: Some(self.line),
+                line: Some(self.last_line()),
             }),
         }
     }
 
-    fn read_line(&mut self) -> Option<String> {
-        if self.pos >= self.text.len() {
-            return None;
-        }
-
-        let start = self.pos;
-        while self.pos < self.text.len() && self.text.as_bytes()[self.pos] != b'\n' {
-            self.pos += 1;
-        }
-
-        let line = self.text[start..self.pos].trim().to_string();
-
-        if self.pos < self.text.len() {
-            self.pos += 1; // skip newline
-        }
+    fn read_line(&mut self) -> Option<&'a str> {
+        let (line, end) = self.next_line()?;
+        self.pos = end;
         self.line += 1;
-
         Some(line)
     }
 
-    fn peek_line(&self) -> Option<String> {
-        if self.pos >= self.text.len() {
-            return None;
-        }
-
-        let mut end = self.pos;
-        while end < self.text.len() && self.text.as_bytes()[end] != b'\n' {
-            end += 1;
-        }
+    fn peek_line(&self) -> Option<&'a str> {
+        self.next_line().map(|(line, _)| line)
+    }
 
-        Some(self.text[self.pos..end].trim().to_string())
+    /// The trimmed line at `pos` and the offset after its newline
+    fn next_line(&self) -> Option<(&'a str, usize)> {
+        let rest = self.text.get(self.pos..).filter(|rest| !rest.is_empty())?;
+        Some(match rest.as_bytes().iter().position(|&b| b == b'\n') {
+            Some(len) => (rest[..len].trim(), self.pos + len + 1),
+            None => (rest.trim(), self.text.len()),
+        })
     }
 
     fn skip_whitespace_and_comments(&mut self) {
@@ -713,21 +1790,58 @@ impl<'a> Parser<'a> {
 // =============================================================================
 
 struct Serializer {
-    align_columns: bool,
-    delimiter: String,
+    options: DumpOptions,
+    monitor: core::cell::RefCell<progress::Monitor>,
 }
 
 impl Serializer {
     fn new(align_columns: bool) -> Self {
-        Self { align_columns, delimiter: " ".to_string() }
+        Self::with_options(DumpOptions { align_columns, ..Default::default() })
     }
 
     fn with_delimiter(align_columns: bool, delimiter: &str) -> Self {
-        Self { align_columns, delimiter: delimiter.to_string() }
+        Self::with_options(DumpOptions {
+            align_columns,
+            delimiter: delimiter.to_string(),
+            ..Default::default()
+        })
+    }
+
+    fn with_options(options: DumpOptions) -> Self {
+        let monitor = progress::Monitor::new(options.progress.clone(), options.cancel.clone());
+        Self {
+            options,
+            monitor: core::cell::RefCell::new(monitor),
+        }
+    }
+
+    /// Count a written row; true once serialization has been cancelled
+    fn tick(&self, line: &str) -> bool {
+        let mut monitor = self.monitor.borrow_mut();
+        monitor.bytes += line.len() + 1;
+        monitor.row();
+        monitor.check(None).is_err()
+    }
+
+    /// Fail if cancelled, otherwise send the final progress report
+    fn finish(&self, text: &str) -> Result<()> {
+        let mut monitor = self.monitor.borrow_mut();
+        monitor.check(None)?;
+        monitor.bytes = text.len();
+        monitor.finish();
+        Ok(())
     }
 
     fn serialize(&self, doc: &Document) -> String {
-        let parts: Vec<String> = doc.blocks.iter().map(|b| self.serialize_block(b)).collect();
+        // Document metadata leads, wherever it sits in `blocks`
+        let is_meta = |b: &&Block| b.kind == meta::META_KIND && b.name == meta::META_NAME;
+        let parts: Vec<String> = doc
+            .blocks
+            .iter()
+            .filter(is_meta)
+            .chain(doc.blocks.iter().filter(|b| !is_meta(b)))
+            .map(|b| self.serialize_block(b))
+            .collect();
         parts.join("\n\n")
     }
 
@@ -742,32 +1856,42 @@ impl Serializer {
             .field_info
             .iter()
             .map(|fi| {
-                if let Some(ref ft) = fi.field_type {
+                let inferred = match fi.field_type {
+                    None if self.options.infer_types => block.infer_field_type(&fi.name),
+                    _ => None,
+                };
+                if let Some(ft) = fi.field_type.as_ref().or(inferred.as_ref()) {
                     format!("{}:{}", fi.name, ft)
                 } else {
                     fi.name.clone()
                 }
             })
             .collect();
-        lines.push(field_defs.join(&self.delimiter));
+        lines.push(field_defs.join(&self.options.delimiter));
 
         // Calculate column widths for alignment
-        let widths = if self.align_columns {
+        let widths = if self.options.align_columns {
             self.calculate_widths(block)
         } else {
             vec![]
         };
 
         // Data rows
-        for row in &block.rows {
-            lines.push(self.serialize_row(row, &block.fields, &widths));
+        for i in 0..block.rows.len() {
+            lines.push(self.serialize_row(block, false, i, &widths));
+            if self.tick(&lines[lines.len() - 1]) {
+                return lines.join("\n");
+            }
         }
 
         // Summary separator and rows
         if !block.summary_rows.is_empty() {
             lines.push("---".to_string());
-            for row in &block.summary_rows {
-                lines.push(self.serialize_row(row, &block.fields, &widths));
+            for i in 0..block.summary_rows.len() {
+                lines.push(self.serialize_row(block, true, i, &widths));
+                if self.tick(&lines[lines.len() - 1]) {
+                    break;
+                }
             }
         }
 
@@ -777,13 +1901,11 @@ impl Serializer {
     fn calculate_widths(&self, block: &Block) -> Vec<usize> {
         let mut widths: Vec<usize> = block.fields.iter().map(|f| f.len()).collect();
 
-        for row in block.rows.iter().chain(block.summary_rows.iter()) {
-            for (i, field) in block.fields.iter().enumerate() {
-                if let Some(value) = row.get(field) {
-                    let str_val = self.serialize_value(value);
-                    if i < widths.len() {
-                        widths[i] = widths[i].max(str_val.len());
-                    }
+        for summary in [false, true] {
+            let rows = if summary { &block.summary_rows } else { &block.rows };
+            for i in 0..rows.len() {
+                for (width, cell) in widths.iter_mut().zip(self.serialize_cells(block, summary, i)) {
+                    *width = (*width).max(cell.len());
                 }
             }
         }
@@ -791,14 +1913,31 @@ impl Serializer {
         widths
     }
 
-    fn serialize_row(&self, row: &Row, fields: &[String], widths: &[usize]) -> String {
-        let mut values = Vec::new();
+    /// Serialized cells of a data or summary row, keeping recorded number spellings
+    fn serialize_cells(&self, block: &Block, summary: bool, index: usize) -> Vec<String> {
+        let row = if summary { &block.summary_rows[index] } else { &block.rows[index] };
+        let number_text = block.number_text.row(summary, index);
+        block
+            .fields
+            .iter()
+            .map(|field| {
+                let value = row.get(field.as_str()).unwrap_or(&Value::Null);
+                match number_text.and_then(|t| t.get(field.as_str())) {
+                    Some(text) if lexeme::spells(text, value, block.cell_type(field)) => {
+                        text.clone()
+                    }
+                    _ => self.serialize_value(value),
+                }
+            })
+            .collect()
+    }
 
-        for (i, field) in fields.iter().enumerate() {
-            let value = row.get(field).cloned().unwrap_or(Value::Null);
-            let mut str_val = self.serialize_value(&value);
+    fn serialize_row(&self, block: &Block, summary: bool, index: usize, widths: &[usize]) -> String {
+        let fields = &block.fields;
+        let mut values = Vec::new();
 
-            if self.align_columns && !widths.is_empty() && i < fields.len() - 1 {
+        for (i, mut str_val) in self.serialize_cells(block, summary, index).into_iter().enumerate() {
+            if self.options.align_columns && !widths.is_empty() && i < fields.len() - 1 {
                 while str_val.len() < widths[i] {
                     str_val.push(' ');
                 }
@@ -806,31 +1945,73 @@ impl Serializer {
             values.push(str_val);
         }
 
-        values.join(&self.delimiter)
+        values.join(&self.options.delimiter)
+    }
+
+    /// A row's cells in field order, without alignment or recorded number spellings
+    fn serialize_values(&self, row: &Row, fields: &[String]) -> String {
+        let values: Vec<String> = fields
+            .iter()
+            .map(|f| self.serialize_value(row.get(f.as_str()).unwrap_or(&Value::Null)))
+            .collect();
+        values.join(&self.options.delimiter)
     }
 
-    fn serialize_value(&self, value: &Value) -> String {
+    pub(crate) fn serialize_value(&self, value: &Value) -> String {
         match value {
             Value::Null => "null".to_string(),
             Value::Bool(b) => if *b { "true" } else { "false" }.to_string(),
             Value::Int(i) => i.to_string(),
-            Value::Float(f) => f.to_string(),
+            Value::Float(f) if f.is_finite() => {
+                // Display never uses an exponent, which the Python and JS
+                // parsers do not read as a number; the decimal point keeps
+                // 1.0 from coming back as an integer
+                let text = f.to_string();
+                if text.contains('.') {
+                    text
+                } else {
+                    format!("{}.0", text)
+                }
+            }
+            Value::Float(f) => match self.options.non_finite {
+                NonFinitePolicy::Null => "null".to_string(),
+                _ if f.is_nan() => "nan".to_string(),
+                _ if *f > 0.0 => "inf".to_string(),
+                _ => "-inf".to_string(),
+            },
             Value::Reference(r) => r.to_ison(),
             Value::String(s) => self.serialize_string(s),
+            Value::Bytes(_) => value.to_string(),
+            // Display keeps the scale, so 19.90 stays 19.90
+            #[cfg(feature = "rust_decimal")]
+            Value::Decimal(d) => d.to_string(),
         }
     }
 
     fn serialize_string(&self, s: &str) -> String {
+        // Raw `"""` text cannot hold `"""`, a trailing quote, or a lone `\r`
+        if self.options.block_strings
+            && s.contains('\n')
+            && !s.contains(BLOCK_QUOTE)
+            && !s.ends_with('"')
+            && !s.contains('\r')
+        {
+            return format!("{}\n{}{}", BLOCK_QUOTE, s, BLOCK_QUOTE);
+        }
+
         let needs_quotes = s.contains(' ')
             || s.contains('\t')
             || s.contains('\n')
             || s.contains('"')
             || s.contains('\\')
             || s.contains('.')  // Avoid confusion with block headers (type.name)
+            || s.contains('#')
+            || s == "~"
             || s == "true"
             || s == "false"
             || s == "null"
             || s.starts_with(':')
+            || s.starts_with(BYTES_PREFIX)
             || s.parse::<f64>().is_ok();
 
         if !needs_quotes {
@@ -852,22 +2033,80 @@ impl Serializer {
 // ISONL Parser/Serializer
 // =============================================================================
 
-/// Parse ISONL format
-pub fn parse_isonl(text: &str) -> Result<Document> {
-    let mut doc = Document::new();
-    let mut block_map: HashMap<String, usize> = HashMap::new();
+/// Starts an ISONL version line, e.g. `#!ison/1.0`
+const ISONL_VERSION: &str = "#!ison/";
 
-    for (line_num, line) in text.lines().enumerate() {
+/// Starts an ISONL line declaring a block's fields once, e.g.
+/// `#schema: table.users|id name`, so its rows can leave the fields segment
+/// empty: `table.users||1 Alice`
+const ISONL_SCHEMA: &str = "#schema:";
+
+/// A block defined by an ISONL line, with its shared row keys
+pub(crate) struct IsonlBlock {
+    /// The block's header, without rows
+    pub(crate) block: Block,
+    keys: Vec<FieldName>,
+    cell_types: Vec<CellType>,
+}
+
+/// Reads ISONL one line at a time, remembering each block's first field list
+#[derive(Default)]
+pub(crate) struct IsonlReader {
+    pub(crate) blocks: Vec<IsonlBlock>,
+    index: HashMap<String, usize>,
+    /// Whether a `#!ison/` version line was seen
+    pub(crate) versioned: bool,
+}
+
+impl IsonlReader {
+    /// Index of the block with this kind and name, if defined
+    #[cfg(feature = "std")]
+    pub(crate) fn find(&self, kind: &str, name: &str) -> Option<usize> {
+        self.index.get(&format!("{}.{}", kind, name)).copied()
+    }
+
+    /// Add a block definition (its fields; rows are ignored), returning its index
+    pub(crate) fn define(&mut self, block: &Block) -> usize {
+        let mut header = Block::new(block.kind.clone(), block.name.clone());
+        header.fields = block.fields.clone();
+        header.field_info = block.field_info.clone();
+        self.index.insert(format!("{}.{}", block.kind, block.name), self.blocks.len());
+        self.blocks.push(IsonlBlock {
+            keys: header.field_keys(),
+            cell_types: header.cell_types(),
+            block: header,
+        });
+        self.blocks.len() - 1
+    }
+
+    /// The row on `line` (numbered `line_num`) and the index of its block in
+    /// `blocks`, or `None` for a blank, comment, or directive line
+    pub(crate) fn read_line(&mut self, line: &str, line_num: usize) -> Result<Option<(usize, Row)>> {
         let line = line.trim();
+        if let Some(version) = line.strip_prefix(ISONL_VERSION) {
+            // Same major version as ours
+            if version.split('.').next() != SPEC_VERSION.split('.').next() {
+                return Err(ISONError {
+                    message: format!("Unsupported ISONL version: {}", version),
+                    line: Some(line_num),
+                });
+            }
+            self.versioned = true;
+            return Ok(None);
+        }
+        if let Some(schema) = line.strip_prefix(ISONL_SCHEMA) {
+            self.read_schema(schema.trim(), line_num)?;
+            return Ok(None);
+        }
         if line.is_empty() || line.starts_with('#') {
-            continue;
+            return Ok(None);
         }
 
         let parts: Vec<&str> = line.split('|').collect();
         if parts.len() != 3 {
             return Err(ISONError {
                 message: format!("Invalid ISONL line: {}", line),
-                line: Some(line_num + 1),
+                line: Some(line_num),
             });
         }
 
@@ -877,86 +2116,146 @@ pub fn parse_isonl(text: &str) -> Result<Document> {
 
         let dot_index = header.find('.').ok_or_else(|| ISONError {
             message: format!("Invalid ISONL header: {}", header),
-            line: Some(line_num + 1),
+            line: Some(line_num),
         })?;
 
         let kind = &header[..dot_index];
         let name = &header[dot_index + 1..];
         let key = format!("{}.{}", kind, name);
 
-        let block_idx = if let Some(&idx) = block_map.get(&key) {
-            idx
-        } else {
-            let mut block = Block::new(kind, name);
-
-            // Parse fields
-            for f in fields_part.split_whitespace() {
-                if let Some(colon_idx) = f.find(':') {
-                    let field_name = f[..colon_idx].to_string();
-                    let field_type = f[colon_idx + 1..].to_string();
-                    block.fields.push(field_name.clone());
-                    block.field_info.push(FieldInfo::with_type(field_name, field_type));
-                } else {
-                    block.fields.push(f.to_string());
-                    block.field_info.push(FieldInfo::new(f));
+        let block_idx = match self.index.get(&key) {
+            Some(&idx) => idx,
+            None if fields_part.is_empty() => {
+                return Err(ISONError {
+                    message: format!("No schema for {}", key),
+                    line: Some(line_num),
+                })
+            }
+            None => {
+                let mut block = Block::new(kind, name);
+                for f in fields_part.split_whitespace() {
+                    block.add_field_def(f);
                 }
+                self.define(&block)
             }
-
-            let idx = doc.blocks.len();
-            block_map.insert(key, idx);
-            doc.blocks.push(block);
-            idx
         };
 
         // Parse values
+        let IsonlBlock { keys, cell_types, .. } = &self.blocks[block_idx];
         let parser = Parser::new("");
         let values = parser.tokenize_line(values_part);
         let mut row = Row::new();
+        for ((key, token), &cell_type) in keys.iter().zip(&values).zip(cell_types) {
+            row.insert(key.clone(), parser.parse_cell(token, cell_type)?);
+        }
+        Ok(Some((block_idx, row)))
+    }
+
+    /// Define the block in a `kind.name|fields` schema line
+    fn read_schema(&mut self, schema: &str, line_num: usize) -> Result<()> {
+        let invalid = || ISONError {
+            message: format!("Invalid ISONL schema: {}", schema),
+            line: Some(line_num),
+        };
+        let (header, fields) = schema.split_once('|').ok_or_else(invalid)?;
+        let header = header.trim();
+        let (kind, name) = header.split_once('.').ok_or_else(invalid)?;
+        let mut block = Block::new(kind, name);
+        for f in fields.split_whitespace() {
+            block.add_field_def(f);
+        }
 
-        let block = &doc.blocks[block_idx];
-        for (i, field) in block.fields.iter().enumerate() {
-            if i < values.len() {
-                row.insert(field.clone(), parser.parse_value(&values[i])?);
+        match self.index.get(header) {
+            None => {
+                self.define(&block);
+            }
+            Some(&idx) if isonl_prefix(&self.blocks[idx].block) != isonl_prefix(&block) => {
+                return Err(ISONError {
+                    message: format!("Conflicting schema for {}", header),
+                    line: Some(line_num),
+                });
             }
+            Some(_) => {}
         }
+        Ok(())
+    }
+}
+
+/// Parse ISONL format
+pub fn parse_isonl(text: &str) -> Result<Document> {
+    let mut doc = Document::new();
+    let mut reader = IsonlReader::default();
 
-        doc.blocks[block_idx].rows.push(row);
+    for (line_num, line) in text.lines().enumerate() {
+        let row = reader.read_line(line, line_num + 1)?;
+        // Blocks are numbered in order of definition, as in `doc`; a schema
+        // line defines one without rows
+        for defined in &reader.blocks[doc.blocks.len()..] {
+            doc.blocks.push(defined.block.clone());
+        }
+        if let Some((block_idx, row)) = row {
+            doc.blocks[block_idx].rows.push(row);
+        }
     }
 
     Ok(doc)
 }
 
+/// The `kind.name|fields|` prefix of a block's ISONL lines
+pub(crate) fn isonl_prefix(block: &Block) -> String {
+    let fields: Vec<String> = block
+        .field_info
+        .iter()
+        .map(|fi| {
+            if let Some(ref ft) = fi.field_type {
+                format!("{}:{}", fi.name, ft)
+            } else {
+                fi.name.clone()
+            }
+        })
+        .collect();
+    format!("{}.{}|{}|", block.kind, block.name, fields.join(" "))
+}
+
+/// The `#schema: kind.name|fields` line declaring a block's fields
+pub(crate) fn isonl_schema(block: &Block) -> String {
+    let prefix = isonl_prefix(block);
+    format!("{} {}", ISONL_SCHEMA, &prefix[..prefix.len() - 1])
+}
+
 /// Serialize to ISONL format
 pub fn dumps_isonl(doc: &Document) -> String {
     let serializer = Serializer::new(false);
     let mut lines = Vec::new();
 
     for block in &doc.blocks {
-        let header = format!("{}.{}", block.kind, block.name);
-        let fields: Vec<String> = block
-            .field_info
-            .iter()
-            .map(|fi| {
-                if let Some(ref ft) = fi.field_type {
-                    format!("{}:{}", fi.name, ft)
-                } else {
-                    fi.name.clone()
-                }
-            })
-            .collect();
-        let fields_str = fields.join(" ");
+        let prefix = isonl_prefix(block);
+        for row in &block.rows {
+            lines.push(format!("{}{}", prefix, serializer.serialize_values(row, &block.fields)));
+        }
+    }
+
+    lines.join("\n")
+}
 
+/// Serialize to ISONL that declares each block's fields once.
+///
+/// A `#!ison/1.0` line and one `#schema:` line per block come first, and rows
+/// leave their fields segment empty (`table.users||1 Alice`). Blocks without
+/// rows keep their schema line.
+pub fn dumps_isonl_with_schema(doc: &Document) -> String {
+    let serializer = Serializer::new(false);
+    let mut lines = vec![format!("{}{}", ISONL_VERSION, SPEC_VERSION)];
+    lines.extend(doc.blocks.iter().map(isonl_schema));
+
+    for block in &doc.blocks {
         for row in &block.rows {
-            let values: Vec<String> = block
-                .fields
-                .iter()
-                .map(|f| {
-                    row.get(f)
-                        .map(|v| serializer.serialize_value(v))
-                        .unwrap_or_else(|| "null".to_string())
-                })
-                .collect();
-            lines.push(format!("{}|{}|{}", header, fields_str, values.join(" ")));
+            lines.push(format!(
+                "{}.{}||{}",
+                block.kind,
+                block.name,
+                serializer.serialize_values(row, &block.fields)
+            ));
         }
     }
 
@@ -972,6 +2271,56 @@ pub fn parse(text: &str) -> Result<Document> {
     Parser::new(text).parse()
 }
 
+/// Parse ISON text with non-default [`ParseOptions`]
+pub fn parse_with_options(text: &str, options: &ParseOptions) -> Result<Document> {
+    Parser::with_options(text, options.clone()).parse()
+}
+
+/// Parse only the blocks named in `names`, skipping the rows of all others.
+///
+/// The result equals `parse(text)?.subset(names)`, except that errors inside
+/// skipped blocks go unnoticed.
+pub fn parse_blocks(text: &str, names: &[&str]) -> Result<Document> {
+    let mut parser = Parser::new(text);
+    parser.only = Some(names);
+    parser.parse()
+}
+
+/// Parse a single value token using ISON value syntax
+pub(crate) fn parse_token(token: &str) -> Result<Value> {
+    Parser::new("").parse_value(token)
+}
+
+/// An exact decimal from a plain or scientific numeric token
+#[cfg(feature = "rust_decimal")]
+pub(crate) fn parse_decimal(token: &str) -> Option<Decimal> {
+    // rust_decimal also accepts `1_000`, which ISON reads as a string
+    if !token.bytes().all(|b| b.is_ascii_digit() || b"+-.eE".contains(&b)) {
+        return None;
+    }
+    Decimal::from_str_exact(token)
+        .or_else(|_| Decimal::from_scientific(token))
+        .ok()
+}
+
+/// `-?digits` or `-?digits.digits`, the numbers every implementation reads
+fn is_plain_number(token: &str) -> bool {
+    let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let unsigned = token.strip_prefix('-').unwrap_or(token);
+    match unsigned.split_once('.') {
+        Some((int, frac)) => digits(int) && digits(frac),
+        None => digits(unsigned),
+    }
+}
+
+/// Whether [`Dialect::V1`] reads `token` as a number
+fn reads_as_number(token: &str) -> bool {
+    matches!(token, "nan" | "inf" | "+inf" | "-inf")
+        || token
+            .parse::<f64>()
+            .is_ok_and(|f| f.is_finite() || token.bytes().any(|b| b.is_ascii_digit()))
+}
+
 /// Parse an ISON string into a Document (alias for parse)
 pub fn loads(text: &str) -> Result<Document> {
     parse(text)
@@ -996,6 +2345,24 @@ pub fn dumps_with_delimiter(doc: &Document, align_columns: bool, delimiter: &str
     Serializer::with_delimiter(align_columns, delimiter).serialize(doc)
 }
 
+/// Serialize a Document with non-default [`DumpOptions`]
+///
+/// Fails only under [`NonFinitePolicy::Reject`], if `doc` holds a NaN or
+/// infinite float.
+pub fn dumps_with_options(doc: &Document, options: &DumpOptions) -> Result<String> {
+    options.check_finite(doc)?;
+    let serializer = Serializer::with_options(options.clone());
+    let text = if options.checksum {
+        let mut doc = doc.clone();
+        doc.add_checksum();
+        serializer.serialize(&doc)
+    } else {
+        serializer.serialize(doc)
+    };
+    serializer.finish(&text)?;
+    Ok(text)
+}
+
 /// Parse ISONL string (alias for parse_isonl)
 pub fn loads_isonl(text: &str) -> Result<Document> {
     parse_isonl(text)
@@ -1044,13 +2411,14 @@ pub fn json_to_ison(json_text: &str) -> Result<String> {
             .map(|f| FieldInfo { name: f.clone(), field_type: None, is_computed: false })
             .collect();
 
+        let keys: Vec<FieldName> = fields.iter().map(|f| FieldName::from(f.as_str())).collect();
         let mut rows = Vec::new();
         for item in arr {
             let item_obj = item.as_object()
                 .ok_or_else(|| ISONError { message: "Array items must be objects".to_string(), line: None })?;
 
             let mut row = Row::new();
-            for field in &fields {
+            for (field, key) in fields.iter().zip(&keys) {
                 if let Some(val) = item_obj.get(field) {
                     let value = match val {
                         serde_json::Value::Null => Value::Null,
@@ -1066,9 +2434,9 @@ pub fn json_to_ison(json_text: &str) -> Result<String> {
                         }
                         serde_json::Value::String(s) => {
                             // Check if it's a reference (starts with :)
-                            if s.starts_with(':') {
+                            if let Some(body) = s.strip_prefix(':') {
                                 // Parse reference: :id or :type:id
-                                let parts: Vec<&str> = s[1..].splitn(2, ':').collect();
+                                let parts: Vec<&str> = body.splitn(2, ':').collect();
                                 if parts.len() == 2 {
                                     Value::Reference(Reference::with_type(parts[1], parts[0]))
                                 } else {
@@ -1080,7 +2448,7 @@ pub fn json_to_ison(json_text: &str) -> Result<String> {
                         }
                         _ => Value::String(val.to_string()),
                     };
-                    row.insert(field.clone(), value);
+                    row.insert(key.clone(), value);
                 }
             }
             rows.push(row);
@@ -1093,6 +2461,7 @@ pub fn json_to_ison(json_text: &str) -> Result<String> {
             field_info,
             rows,
             summary_rows: vec![],
+            number_text: NumberText::default(),
         };
         doc.blocks.push(block);
     }
@@ -1153,6 +2522,30 @@ id user_id
         let ref3 = orders[2].get("user_id").unwrap().as_reference().unwrap();
         assert_eq!(ref3.id, "10");
         assert!(ref3.is_relationship());
+        assert_eq!(ref3.target_block(), None);
+        assert!(ref3.target_fields().is_empty());
+    }
+
+    #[test]
+    fn test_qualified_references() {
+        let doc = parse("table.lines\nref\n:users.id:42\n:lines.order_id,line:42,3").unwrap();
+        let lines = &doc["lines"];
+
+        let user = lines[0]["ref"].as_reference().unwrap();
+        assert_eq!(user.target_block(), Some("users"));
+        assert_eq!(user.target_fields(), vec!["id"]);
+        assert_eq!(user.key_parts(), vec!["42"]);
+        assert!(!user.is_relationship());
+        assert_eq!(user.get_namespace(), None);
+
+        let line = lines[1]["ref"].as_reference().unwrap();
+        assert_eq!(line.target_fields(), vec!["order_id", "line"]);
+        assert_eq!(line.key_parts(), vec!["42", "3"]);
+        assert_eq!(
+            *line,
+            Reference::qualified("lines", &["order_id", "line"], &["42", "3"])
+        );
+        assert_eq!(line.to_ison(), ":lines.order_id,line:42,3");
     }
 
     #[test]
@@ -1185,6 +2578,185 @@ id name email
         assert_eq!(doc2.get("users").unwrap().len(), 2);
     }
 
+    #[test]
+    fn test_tokenize_quotes_and_unicode() {
+        let doc = parse(
+            "table.notes\nid text tag\n1 \"caf\u{e9} # not a comment\" \u{65e5}\u{672c} # comment\n\
+             2 \"say \\\"hi\\\"\\n\u{e9}\\\u{e9}\" \"\"",
+        )
+        .unwrap();
+        let notes = &doc["notes"];
+        assert_eq!(notes[0]["text"], Value::String("caf\u{e9} # not a comment".into()));
+        assert_eq!(notes[0]["tag"], Value::String("\u{65e5}\u{672c}".into()));
+        assert_eq!(notes[1]["text"], Value::String("say \"hi\"\n\u{e9}\u{e9}".into()));
+        assert_eq!(notes[1]["tag"], Value::String(String::new()));
+    }
+
+    #[test]
+    fn test_quoted_cells() {
+        let doc = parse("table.t\nid a b c\n1 \"123\" \"true\" \"~\"\n2 123 true ~").unwrap();
+        let t = &doc["t"];
+        assert_eq!(t[0]["a"], Value::String("123".into()));
+        assert_eq!(t[0]["b"], Value::String("true".into()));
+        assert_eq!(t[0]["c"], Value::String("~".into()));
+        assert_eq!(t[1]["a"], Value::Int(123));
+        assert_eq!(t[1]["b"], Value::Bool(true));
+        assert!(t[1]["c"].is_null());
+        assert_eq!(parse(&dumps(&doc, false)).unwrap()["t"].rows, t.rows);
+    }
+
+    #[test]
+    fn test_error_lines() {
+        let line = |text: &str| parse(text).unwrap_err().line;
+        assert_eq!(line("# note\n\nnodot\nid"), Some(3));
+        assert_eq!(line("table.\nid"), Some(1));
+        assert_eq!(line("table.t\nid\n1\nb64:***"), Some(4));
+        assert_eq!(line("table.t\nid\n:a:b:c"), Some(3));
+        assert_eq!(line("table.t\nid\n1\n\ntable.u\nx\n:1:2:3\n4"), Some(7));
+    }
+
+    #[test]
+    fn test_field_names_shared_across_rows() {
+        let doc = parse("table.users\nid name\n1 Alice\n2 Bob\n3 Carol").unwrap();
+        let key = |row: &Row| row.get_key_value("name").unwrap().0.clone();
+        let users = &doc["users"];
+        assert!(Arc::ptr_eq(&key(&users[0]), &key(&users[2])));
+
+        let doc = parse_isonl("table.t|id|1\ntable.t|id|2").unwrap();
+        let id = |row: &Row| row.get_key_value("id").unwrap().0.clone();
+        assert!(Arc::ptr_eq(&id(&doc["t"][0]), &id(&doc["t"][1])));
+    }
+
+    #[test]
+    fn test_lookup_by_kind() {
+        let doc = parse(
+            "object.users\ncount\n2\n\ntable.users\nid\n1\n2\n\ntable.orders\nid\n9",
+        )
+        .unwrap();
+        assert_eq!(doc.get("users").unwrap().kind, "object");
+        assert_eq!(doc.get_kind_name("table", "users").unwrap().len(), 2);
+        assert_eq!(doc.get_qualified("table.users").unwrap().len(), 2);
+        assert!(doc.get_qualified("meta.users").is_none());
+        assert_eq!(doc.get_by_kind("table").len(), 2);
+
+        let kinds: Vec<(&str, usize)> = doc
+            .blocks_by_kind()
+            .iter()
+            .map(|(kind, blocks)| (*kind, blocks.len()))
+            .collect();
+        assert_eq!(kinds, vec![("object", 1), ("table", 2)]);
+    }
+
+    #[test]
+    fn test_get_or_suggest() {
+        let doc =
+            parse("table.users\nid\n1\n\ntable.user_roles\nid\n2\n\nobject.Config\nk\nv").unwrap();
+        assert_eq!(doc.get_or_suggest("users").unwrap().len(), 1);
+        assert_eq!(
+            doc.get_or_suggest("user").unwrap_err().message,
+            "Block 'user' not found; did you mean 'users'?"
+        );
+        assert_eq!(
+            doc.get_or_suggest("userroles").unwrap_err().message,
+            "Block 'userroles' not found; did you mean 'user_roles'?"
+        );
+        let items = parse("table.item\nid\n1\n\ntable.items\nid\n2").unwrap();
+        assert_eq!(
+            items.get_or_suggest("itemz").unwrap_err().message,
+            "Block 'itemz' not found; did you mean 'item' or 'items'?"
+        );
+        assert_eq!(
+            doc.get_or_suggest("confg").unwrap_err().message,
+            "Block 'confg' not found; did you mean 'Config'?"
+        );
+        assert_eq!(
+            doc.get_or_suggest("zzz").unwrap_err().message,
+            "Block 'zzz' not found; available blocks: users, user_roles, Config"
+        );
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "ab"), 2);
+        assert_eq!(levenshtein("é", "e"), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Block 'orders' not found; available blocks: users")]
+    fn test_index_missing_block() {
+        let doc = parse("table.users\nid\n1").unwrap();
+        let _ = &doc["orders"];
+    }
+
+    #[test]
+    fn test_object_pairs() {
+        let options = ParseOptions {
+            key_value_objects: true,
+            ..Default::default()
+        };
+        let doc = parse_with_options(
+            "object.config\nkey value\nhost db.local\ntimeout:int 30\nversion 1.5 # comment\n\
+             ---\nchecked true\n\nobject.app\nname version\nison 2",
+            &options,
+        )
+        .unwrap();
+        let block = doc.get_kind_name("object", "config").unwrap();
+        assert_eq!(block.fields, vec!["host", "timeout", "version", "checked"]);
+        assert_eq!(block.get_field_type("timeout"), Some("int"));
+
+        let config = doc.get_object("config").unwrap();
+        assert_eq!(config["host"], Value::String("db.local".into()));
+        assert_eq!(config["timeout"], Value::Int(30));
+        assert_eq!(config["version"], Value::Float(1.5));
+        assert_eq!(block.summary_rows[0]["checked"], Value::Bool(true));
+
+        // Tabular objects keep their single row
+        assert_eq!(doc.get_object("app").unwrap()["version"], Value::Int(2));
+        assert!(doc.get_object("users").is_none());
+
+        let err = parse_with_options("object.bad\nkey value\nhost\n", &options).unwrap_err();
+        assert_eq!(err.line, Some(3));
+
+        // Without the option, `key value` is an ordinary fields line
+        let doc = parse("object.settings\nkey value\ntheme dark\nlang en").unwrap();
+        assert_eq!(doc["settings"].fields, vec!["key", "value"]);
+        assert_eq!(doc["settings"].len(), 2);
+    }
+
+    #[test]
+    fn test_float_text() {
+        // The Python and JS parsers read `-?[0-9]+\.[0-9]+` as a float
+        let floats = [1.0, -0.5, 0.1, 1e-7, 2.5e-12, 1e20, -3.0e21, f64::MAX];
+        for f in floats {
+            let text = Serializer::new(false).serialize_value(&Value::Float(f));
+            let (whole, fraction) = text.trim_start_matches('-').split_once('.').unwrap();
+            assert!(
+                !whole.is_empty()
+                    && !fraction.is_empty()
+                    && (whole.to_string() + fraction).bytes().all(|b| b.is_ascii_digit()),
+                "{}",
+                text
+            );
+            assert_eq!(parse_token(&text).unwrap(), Value::Float(f));
+        }
+    }
+
+    #[test]
+    fn test_block_end() {
+        let text = "table.sites\nhost visits\nexample.com 10\nalice@x.com 3\n\
+                    object.app\nkey value\napp.name demo\nmeta.version 2\ntable.next\nid\n1";
+        let options = ParseOptions {
+            key_value_objects: true,
+            ..Default::default()
+        };
+        let doc = parse_with_options(text, &options).unwrap();
+        assert_eq!(doc["sites"].len(), 2);
+        assert_eq!(doc["sites"][0]["host"].as_str(), Some("example.com"));
+        let app = doc.get_object("app").unwrap();
+        assert_eq!(app["app.name"].as_str(), Some("demo"));
+        assert_eq!(app["meta.version"], Value::Int(2));
+        assert_eq!(doc["next"].len(), 1);
+
+        assert_eq!(parse_lazy(text).unwrap().blocks().len(), 3);
+    }
+
     #[test]
     fn test_isonl() {
         let isonl = "table.users|id name|1 Alice\ntable.users|id name|2 Bob";
@@ -1196,6 +2768,51 @@ id name email
         assert_eq!(users[0].get("name").unwrap().as_str(), Some("Alice"));
     }
 
+    #[test]
+    fn test_isonl_schema() {
+        let doc = parse("table.users\nid:int name\n1 Alice\n2 Bob\n\ntable.empty\nx").unwrap();
+        let isonl = dumps_isonl_with_schema(&doc);
+        assert_eq!(
+            isonl,
+            "#!ison/1.0\n#schema: table.users|id:int name\n#schema: table.empty|x\n\
+             table.users||1 Alice\ntable.users||2 Bob"
+        );
+        let back = parse_isonl(&isonl).unwrap();
+        assert_eq!(back["users"].rows, doc["users"].rows);
+        assert_eq!(back["empty"].fields, vec!["x"]);
+
+        // Full lines still work alongside schema lines
+        let mixed = parse_isonl("#schema: table.t|a b\ntable.t|a b|1 2\ntable.t||3 4").unwrap();
+        assert_eq!(mixed["t"].len(), 2);
+
+        let err = |text| parse_isonl(text).unwrap_err();
+        assert_eq!(err("#!ison/2.0").message, "Unsupported ISONL version: 2.0");
+        assert_eq!(err("table.t||1").message, "No schema for table.t");
+        assert_eq!(
+            err("#schema: table.t|a\n#schema: table.t|b").message,
+            "Conflicting schema for table.t"
+        );
+        assert_eq!(err("# log\n#schema: nodot|a").line, Some(2));
+    }
+
+    #[test]
+    fn test_parse_blocks() {
+        let text = "table.users\nid name\n1 Alice\n\n\
+                    table.notes\nid body\n1 \"\"\"\nnot.a header\n\ntable.x\n\"\"\"\n\
+                    # comment\n2 :bad:ref:extra\n\n\
+                    table.orders\nid user\n7 :user:1";
+        let full = parse(&text.replace(":bad:ref:extra", "ok")).unwrap();
+        let subset = full.subset(&["orders", "users"]);
+        assert_eq!(subset.blocks.len(), 2);
+        assert_eq!(subset.blocks[0].name, "users");
+
+        // The bad cell in the skipped block is never parsed
+        assert!(parse(text).is_err());
+        let doc = parse_blocks(text, &["orders", "users"]).unwrap();
+        assert_eq!(dumps(&doc, false), dumps(&subset, false));
+        assert!(parse_blocks(text, &[]).unwrap().blocks.is_empty());
+    }
+
     #[test]
     fn test_dumps_with_delimiter() {
         let ison = r#"table.users
@@ -1222,6 +2839,101 @@ id name email
     }
 
     #[test]
+    fn test_capabilities() {
+        let caps = capabilities();
+        assert_eq!(caps.version, VERSION);
+        assert_eq!(caps.spec_version, SPEC_VERSION);
+        assert!(caps.isonl);
+        // ISONL `#!ison/` version and `#schema:` directives
+        assert!(caps.directives);
+        assert_eq!(caps.has_feature("serde"), cfg!(feature = "serde"));
+        assert_eq!(caps.has_feature("cli"), cfg!(feature = "cli"));
+        assert!(!caps.has_plugin("nonexistent"));
+    }
+
+    #[test]
+    fn test_block_strings() {
+        let text = "table.chunks\nid text note\n\
+                    1 \"\"\"\nFirst line\n  indented # not a comment\n\"\"\" short # comment\n\
+                    2 \"\"\"one line\"\"\" x\n\n\
+                    table.next\nid\n1";
+        let doc = parse(text).unwrap();
+        let chunks = &doc["chunks"];
+        assert_eq!(
+            chunks[0]["text"].as_str(),
+            Some("First line\n  indented # not a comment\n")
+        );
+        assert_eq!(chunks[0]["note"].as_str(), Some("short"));
+        assert_eq!(chunks[1]["text"].as_str(), Some("one line"));
+        assert_eq!(doc.len(), 2);
+
+        let options = DumpOptions {
+            block_strings: true,
+            ..Default::default()
+        };
+        let out = dumps_with_options(&doc, &options).unwrap();
+        assert!(out.contains("1 \"\"\"\nFirst line\n  indented # not a comment\n\"\"\" short\n"));
+        assert_eq!(parse(&out).unwrap()["chunks"].rows, chunks.rows);
+        assert!(dumps(&doc, false).contains(r#""First line\n  indented"#));
+
+        // Unterminated strings run to the end of the text
+        let doc = parse("table.t\nx\n\"\"\"abc\r\ndef").unwrap();
+        assert_eq!(doc["t"][0]["x"].as_str(), Some("abc\ndef"));
+    }
+
+    #[test]
+    fn test_bytes_values() {
+        let text = "table.files\nname hash:bytes thumb\nreadme 3q2+7w== b64:SGk=\nnotes null b64:";
+        let doc = parse(text).unwrap();
+        let files = &doc["files"];
+        assert_eq!(files[0]["hash"].as_bytes(), Some(&[0xde, 0xad, 0xbe, 0xef][..]));
+        assert_eq!(files[0]["thumb"], Value::Bytes(b"Hi".to_vec()));
+        assert!(files[1]["hash"].is_null());
+        assert_eq!(files[1]["thumb"], Value::Bytes(Vec::new()));
+
+        // Always written with the prefix, so annotations are not needed to read it back
+        assert_eq!(
+            dumps(&doc, false),
+            "table.files\nname hash:bytes thumb\nreadme b64:3q2+7w== b64:SGk=\nnotes null b64:"
+        );
+        assert_eq!(parse_isonl(&dumps_isonl(&doc)).unwrap()["files"].rows, files.rows);
+        assert_eq!(from_isonb(&to_isonb(&doc)).unwrap()["files"].rows, files.rows);
+
+        let err = parse("table.t\nx\nb64:not*base64").unwrap_err();
+        assert_eq!(err.message, "Invalid base64: b64:not*base64");
+        #[cfg(feature = "serde")]
+        assert!(doc.to_json(false).contains(r#""thumb":"b64:SGk=""#));
+    }
+
+    #[test]
+    #[cfg(feature = "rust_decimal")]
+    fn test_decimal_values() {
+        let text = "table.prices\nsku price:decimal tax:decimal? rate\nA 19.99 0.10 0.1\nB 20 null 0.25";
+        let doc = parse(text).unwrap();
+        let prices = &doc["prices"];
+        assert_eq!(prices[0]["price"], Value::Decimal(Decimal::new(1999, 2)));
+        assert_eq!(prices[0]["tax"].to_string(), "0.10");
+        assert_eq!(prices[0]["rate"], Value::Float(0.1));
+        assert_eq!(prices[1]["price"].as_decimal(), Some(Decimal::from(20)));
+        assert!(prices[1]["tax"].is_null());
+
+        // Text, ISONL, and ISONB keep the exact digits
+        assert_eq!(dumps(&doc, false), text);
+        assert_eq!(parse_isonl(&dumps_isonl(&doc)).unwrap()["prices"].rows, prices.rows);
+        assert_eq!(from_isonb(&to_isonb(&doc)).unwrap()["prices"].rows, prices.rows);
+
+        let options = ParseOptions {
+            exact_decimals: true,
+            ..Default::default()
+        };
+        let exact = parse_with_options("table.t\nx\n0.1\n3\n1e-2", &options).unwrap();
+        assert_eq!(exact["t"][0]["x"], Value::Decimal(Decimal::new(1, 1)));
+        assert_eq!(exact["t"][1]["x"], Value::Int(3));
+        assert_eq!(exact["t"][2]["x"], Value::Decimal(Decimal::new(1, 2)));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
     fn test_json_to_ison() {
         let json = r#"{
             "users": [
@@ -1240,6 +2952,7 @@ id name email
     }
 
     #[test]
+    #[cfg(feature = "serde")]
     fn test_ison_to_json() {
         let ison = r#"table.users
 id name email
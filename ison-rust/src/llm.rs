@@ -0,0 +1,324 @@
+//! Passing documents to language models and reading them back
+//!
+//! [`Document::to_prompt_context`] writes each block as a labelled
+//! ```` ```ison ```` fence, optionally with a sentence saying what it holds.
+//! [`parse_from_llm_response`] goes the other way, pulling ISON out of a
+//! chatty reply:
+//!
+//! ````text
+//! Sure! Here are the users you asked for:
+//!
+//! ```ison
+//! table.users
+//! id name
+//! 1 Alice
+//! ```
+//!
+//! Let me know if you need anything else.
+//! ````
+//!
+//! Fences labelled `ison` or `isonl` are read when there are any. Otherwise
+//! blocks are found by shape, in unlabelled fences or in the prose itself:
+//! a `kind.name` line, a line of field names, and rows with one value per
+//! field, up to the first line that does not fit.
+
+use alloc::collections::BTreeMap;
+
+use crate::prelude::*;
+use crate::{parse, parse_isonl, Block, Document, ISONError, Parser, Result, Serializer};
+
+/// Layout of [`Document::to_prompt_context`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PromptStyle {
+    /// Pad columns to equal width
+    pub align_columns: bool,
+    /// Sentence placed under a block's label, by block name
+    pub descriptions: BTreeMap<String, String>,
+}
+
+impl PromptStyle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pad columns to equal width
+    pub fn aligned(mut self, align_columns: bool) -> Self {
+        self.align_columns = align_columns;
+        self
+    }
+
+    /// Describe block `name` under its label
+    pub fn describe(mut self, name: &str, description: &str) -> Self {
+        self.descriptions
+            .insert(name.to_string(), description.to_string());
+        self
+    }
+}
+
+impl Document {
+    /// The document as markdown for a prompt: each block under a
+    /// `### kind.name` label, then its description if `style` has one, then
+    /// the block in an `ison` fence.
+    ///
+    /// ```rust
+    /// use ison_rs::PromptStyle;
+    ///
+    /// let doc = ison_rs::parse("table.users\nid name\n1 Alice")?;
+    /// let style = PromptStyle::new().describe("users", "Registered accounts");
+    /// assert_eq!(
+    ///     doc.to_prompt_context(&style),
+    ///     "### table.users\nRegistered accounts\n```ison\ntable.users\nid name\n1 Alice\n```"
+    /// );
+    /// # Ok::<(), ison_rs::ISONError>(())
+    /// ```
+    pub fn to_prompt_context(&self, style: &PromptStyle) -> String {
+        let serializer = Serializer::new(style.align_columns);
+        let sections: Vec<String> = self
+            .blocks
+            .iter()
+            .map(|block| {
+                let mut section = format!("### {}.{}\n", block.kind, block.name);
+                if let Some(description) = style.descriptions.get(&block.name) {
+                    section.push_str(description.trim());
+                    section.push('\n');
+                }
+                format!(
+                    "{}```ison\n{}\n```",
+                    section,
+                    serializer.serialize_block(block)
+                )
+            })
+            .collect();
+        sections.join("\n\n")
+    }
+}
+
+/// A fenced code block of a response
+struct Fence<'a> {
+    language: &'a str,
+    /// Line of the response before the first content line
+    line: usize,
+    lines: Vec<&'a str>,
+}
+
+/// Fenced code blocks of `text`; a fence left open runs to the end
+fn fences(text: &str) -> Vec<Fence<'_>> {
+    let mut fences = Vec::new();
+    let mut open: Option<(char, usize, Fence)> = None;
+    for (index, line) in text.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let marker = trimmed.chars().next().filter(|c| *c == '`' || *c == '~');
+        let run = marker.map_or(0, |c| trimmed.chars().take_while(|x| *x == c).count());
+        match &mut open {
+            Some((c, len, _))
+                if marker == Some(*c) && run >= *len && trimmed[run..].trim().is_empty() =>
+            {
+                fences.push(open.take().unwrap().2);
+            }
+            Some((_, _, fence)) => fence.lines.push(line),
+            None if run >= 3 => {
+                let language = trimmed[run..].split_whitespace().next().unwrap_or("");
+                let fence = Fence {
+                    language,
+                    line: index + 1,
+                    lines: Vec::new(),
+                };
+                open = Some((marker.unwrap(), run, fence));
+            }
+            None => {}
+        }
+    }
+    fences.extend(open.map(|(_, _, fence)| fence));
+    fences
+}
+
+/// `kind.name`, with no spaces, a kind starting with a letter (so `3.14` is
+/// a value), and no trailing dot
+pub(crate) fn is_header(line: &str) -> bool {
+    let ident = |s: &str| {
+        !s.is_empty()
+            && s.chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+    };
+    match line.trim().split_once('.') {
+        Some((kind, name)) => {
+            ident(kind)
+                && kind.starts_with(char::is_alphabetic)
+                && ident(name)
+                && !kind.contains('.')
+                && !name.ends_with('.')
+        }
+        None => false,
+    }
+}
+
+/// Field names, `name` or `name:type`, as a fields line
+pub(crate) fn is_fields<T: core::ops::Deref<Target = str>>(tokens: &[T]) -> bool {
+    !tokens.is_empty()
+        && tokens.iter().all(|t| {
+            let (name, field_type) = t.split_once(':').unwrap_or((t, "x"));
+            !name.is_empty()
+                && !field_type.is_empty()
+                && t.chars().all(|c| c.is_alphanumeric() || "_-.:".contains(c))
+        })
+}
+
+/// The lines of `lines` that look like ISON blocks or ISONL records, with a
+/// blank line between blocks
+fn find_ison(lines: &[&str]) -> (String, String) {
+    let parser = Parser::new("");
+    let (mut ison, mut isonl) = (Vec::new(), Vec::new());
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i].trim();
+        let parts: Vec<&str> = line.splitn(3, '|').collect();
+        if parts.len() == 3 && is_header(parts[0]) {
+            isonl.push(line);
+            i += 1;
+            continue;
+        }
+        let fields = lines.get(i + 1).map(|l| parser.tokenize_line(l.trim()));
+        let Some(fields) = fields.filter(|f| is_header(line) && is_fields(f)) else {
+            i += 1;
+            continue;
+        };
+        ison.extend([line, lines[i + 1].trim()]);
+        i += 2;
+        while let Some(row) = lines.get(i).map(|l| l.trim()) {
+            if row != "---" && parser.tokenize_line(row).len() != fields.len() {
+                break;
+            }
+            ison.push(row);
+            i += 1;
+        }
+        ison.push("");
+    }
+    (ison.join("\n"), isonl.join("\n"))
+}
+
+/// Append `from`'s blocks to `doc`, adding rows to a block of the same kind,
+/// name, and fields instead of repeating it
+fn merge(doc: &mut Document, from: Document) {
+    for block in from.blocks {
+        let same = |b: &&mut Block| {
+            b.kind == block.kind && b.name == block.name && b.fields == block.fields
+        };
+        match doc.blocks.iter_mut().find(same) {
+            Some(existing) => {
+                existing.rows.extend(block.rows);
+                existing.summary_rows.extend(block.summary_rows);
+            }
+            None => doc.blocks.push(block),
+        }
+    }
+}
+
+/// The ISON in a model's reply (see the [module docs](self)).
+///
+/// Blocks found in several places are gathered into one document; a block
+/// continued in a later fence gets the later rows. Errors in `ison` fences
+/// are reported with the line in `text`, and a reply with no ISON at all is
+/// an error.
+pub fn parse_from_llm_response(text: &str) -> Result<Document> {
+    let fences = fences(text);
+    let labelled: Vec<&Fence> = fences
+        .iter()
+        .filter(|f| matches!(f.language, "ison" | "isonl"))
+        .collect();
+    let mut doc = Document::new();
+
+    if !labelled.is_empty() {
+        for fence in labelled {
+            let content = fence.lines.join("\n");
+            let parsed = match fence.language {
+                "isonl" => parse_isonl(&content),
+                _ => parse(&content),
+            }
+            .map_err(|e| ISONError {
+                line: e.line.map(|l| l + fence.line),
+                ..e
+            })?;
+            merge(&mut doc, parsed);
+        }
+        return Ok(doc);
+    }
+
+    let all: Vec<&str> = text.lines().collect();
+    let regions = if fences.is_empty() {
+        vec![all.as_slice()]
+    } else {
+        fences.iter().map(|f| f.lines.as_slice()).collect()
+    };
+    for lines in regions {
+        let (ison, isonl) = find_ison(lines);
+        merge(&mut doc, parse(&ison)?);
+        merge(&mut doc, parse_isonl(&isonl)?);
+    }
+    if doc.blocks.is_empty() {
+        return Err(ISONError {
+            message: "No ISON found in LLM response".to_string(),
+            line: None,
+        });
+    }
+    Ok(doc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dumps;
+
+    #[test]
+    fn test_prompt_context_roundtrip() {
+        let doc = parse(
+            "table.users\nid:int name\n1 Alice\n2 \"Bob Smith\"\n\n\
+             object.config\nkey value\ntheme dark",
+        )
+        .unwrap();
+        let style = PromptStyle::new()
+            .aligned(true)
+            .describe("users", "One row per account.");
+        let context = doc.to_prompt_context(&style);
+        assert!(context.starts_with("### table.users\nOne row per account.\n```ison\n"));
+        assert!(context.contains("\n\n### object.config\n```ison\nobject.config\n"));
+
+        let reply = format!("Here you go:\n\n{}\n\nAnything else?", context);
+        let back = parse_from_llm_response(&reply).unwrap();
+        assert_eq!(dumps(&back, false), dumps(&doc, false));
+    }
+
+    #[test]
+    fn test_parse_from_llm_response() {
+        // Labelled fences win over other fences, and a block continued in a
+        // second fence keeps its rows together
+        let reply = "Example:\n```\ntable.other\nx\n1\n```\n\
+                     ```ison\ntable.users\nid name\n1 Alice\n```\nand\n\
+                     ~~~isonl\ntable.users|id name|2 Bob\n~~~\n";
+        let doc = parse_from_llm_response(reply).unwrap();
+        assert_eq!(doc.blocks.len(), 1);
+        assert_eq!(doc["users"].len(), 2);
+
+        // Unlabelled fence, and an unterminated one
+        let doc = parse_from_llm_response("```\ntable.a\nx y\n1 2\n```").unwrap();
+        assert_eq!(doc["a"][0]["y"].as_int(), Some(2));
+        let doc = parse_from_llm_response("```ison\ntable.a\nx\n1\n2").unwrap();
+        assert_eq!(doc["a"].len(), 2);
+
+        // Blocks in prose end at the first line that does not fit
+        let reply = "Sure! The results (see e.g. example.com) are:\n\
+                     table.results\nid score:float\n1 0.5\n2 \"0.75\"\n---\n~ 1.25\n\
+                     That is all for the results.\n\
+                     I also logged table.events|id kind|7 click\n\
+                     table.events|id kind|8 view";
+        let doc = parse_from_llm_response(reply).unwrap();
+        assert_eq!(doc["results"].len(), 2);
+        assert_eq!(doc["results"].summary_rows.len(), 1);
+        assert_eq!(doc["events"].len(), 1);
+
+        let err = parse_from_llm_response("Hello!\n```ison\ntable.bad\nid:bytes\nb64:***\n```")
+            .unwrap_err();
+        assert_eq!(err.line, Some(5));
+        let err = parse_from_llm_response("I could not find any users.").unwrap_err();
+        assert_eq!(err.message, "No ISON found in LLM response");
+    }
+}
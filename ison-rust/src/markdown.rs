@@ -0,0 +1,206 @@
+//! GitHub-flavored markdown tables
+//!
+//! Pipes in cells are escaped as `\|` and line breaks become `<br>`. Null
+//! values render as empty cells. Summary rows follow the data rows with every
+//! non-empty cell in bold, which is how [`from_markdown_table`] recognizes them.
+
+use crate::prelude::*;
+use crate::{parse_token, Block, Document, ISONError, Result, Row, Value};
+
+fn escape_cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        other => other
+            .to_string()
+            .replace('|', "\\|")
+            .replace("\r\n", "<br>")
+            .replace('\n', "<br>"),
+    }
+}
+
+fn markdown_row(cells: Vec<String>) -> String {
+    format!("| {} |", cells.join(" | "))
+}
+
+impl Block {
+    /// Render the block as a markdown table
+    pub fn to_markdown(&self) -> String {
+        let mut lines = vec![
+            markdown_row(self.fields.iter().map(|f| f.replace('|', "\\|")).collect()),
+            markdown_row(vec!["---".to_string(); self.fields.len()]),
+        ];
+        let cells = |row: &Row, bold: bool| {
+            self.fields
+                .iter()
+                .map(|f| {
+                    let cell = row.get(f.as_str()).map(escape_cell).unwrap_or_default();
+                    if bold && !cell.is_empty() {
+                        format!("**{}**", cell)
+                    } else {
+                        cell
+                    }
+                })
+                .collect()
+        };
+        for row in &self.rows {
+            lines.push(markdown_row(cells(row, false)));
+        }
+        for row in &self.summary_rows {
+            lines.push(markdown_row(cells(row, true)));
+        }
+        lines.join("\n")
+    }
+}
+
+impl Document {
+    /// Render every block as a `### kind.name` heading followed by its table
+    pub fn to_markdown(&self) -> String {
+        self.blocks
+            .iter()
+            .map(|b| format!("### {}.{}\n\n{}", b.kind, b.name, b.to_markdown()))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+/// Split a table line on unescaped pipes, dropping the outer ones
+fn split_cells(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = if line.ends_with('|') && !line.ends_with("\\|") {
+        &line[..line.len() - 1]
+    } else {
+        line
+    };
+
+    let mut cells = Vec::new();
+    let mut current = String::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                current.push('|');
+                chars.next();
+            }
+            '|' => cells.push(core::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    cells.push(current);
+    cells
+        .into_iter()
+        .map(|c| c.trim().replace("<br>", "\n"))
+        .collect()
+}
+
+fn is_separator(cells: &[String]) -> bool {
+    cells.iter().all(|c| {
+        let c = c.trim_matches(':');
+        !c.is_empty() && c.chars().all(|ch| ch == '-')
+    })
+}
+
+fn bold_inner(cell: &str) -> Option<&str> {
+    cell.strip_prefix("**")?.strip_suffix("**")
+}
+
+/// Parse a markdown table into a `table.<name>` block.
+///
+/// Cells are read with ISON value syntax (`42` is an integer, `:1` a
+/// reference); empty cells become null. Lines outside the table are ignored.
+pub fn from_markdown_table(text: &str, name: &str) -> Result<Block> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| l.trim_start().starts_with('|'));
+    let error = |message: String, line: usize| ISONError {
+        message,
+        line: Some(line + 1),
+    };
+
+    let (header_line, header) = lines
+        .next()
+        .ok_or_else(|| error("No markdown table found".to_string(), 0))?;
+    let fields = split_cells(header);
+    match lines.next() {
+        Some((_, sep)) if is_separator(&split_cells(sep)) => {}
+        _ => {
+            return Err(error(
+                "Missing markdown table separator row".to_string(),
+                header_line + 1,
+            ))
+        }
+    }
+
+    let mut block = Block::new("table", name);
+    for field in &fields {
+        block.fields.push(field.clone());
+        block.field_info.push(crate::FieldInfo::new(field.clone()));
+    }
+    let keys = block.field_keys();
+
+    for (line_no, line) in lines {
+        let cells = split_cells(line);
+        if cells.len() != fields.len() {
+            return Err(error(
+                format!("Expected {} cells, found {}", fields.len(), cells.len()),
+                line_no,
+            ));
+        }
+        let summary = cells.iter().any(|c| !c.is_empty())
+            && cells
+                .iter()
+                .all(|c| c.is_empty() || bold_inner(c).is_some());
+
+        let mut row = Row::new();
+        for (key, cell) in keys.iter().zip(&cells) {
+            let cell = if summary {
+                bold_inner(cell).unwrap_or(cell)
+            } else {
+                cell
+            };
+            let value = if cell.is_empty() {
+                Value::Null
+            } else {
+                parse_token(cell).map_err(|e| error(e.message, line_no))?
+            };
+            row.insert(key.clone(), value);
+        }
+        if summary {
+            block.summary_rows.push(row);
+        } else {
+            block.rows.push(row);
+        }
+    }
+    Ok(block)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_to_markdown() {
+        let doc = parse("table.items\nid note owner\n1 \"a|b\" :7\n2 ~ :8\n---\n3 ~ ~").unwrap();
+        let md = doc.to_markdown();
+        assert_eq!(
+            md,
+            "### table.items\n\n\
+             | id | note | owner |\n| --- | --- | --- |\n\
+             | 1 | a\\|b | :7 |\n| 2 |  | :8 |\n| **3** |  |  |"
+        );
+    }
+
+    #[test]
+    fn test_from_markdown_roundtrip() {
+        let doc = parse("table.items\nid note owner\n1 \"a|b\" :7\n2 ~ :8\n---\n3 ~ ~").unwrap();
+        let block = from_markdown_table(&doc.to_markdown(), "items").unwrap();
+        assert_eq!(block.fields, doc["items"].fields);
+        assert_eq!(block.rows, doc["items"].rows);
+        assert_eq!(block.summary_rows, doc["items"].summary_rows);
+
+        assert!(from_markdown_table("no table here", "x").is_err());
+        assert!(from_markdown_table("| a | b |\n| --- | --- |\n| 1 |", "x").is_err());
+    }
+}
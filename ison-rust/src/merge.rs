@@ -0,0 +1,562 @@
+//! Three-way merging
+//!
+//! [`merge3`] combines two documents edited from a common base. Blocks are
+//! matched by kind and name, and rows by key: the `id` field by default,
+//! the fields given to [`merge3_by`], or the row's position when a block
+//! has no key. A change made on one side only is taken as is; a cell both
+//! sides changed differently is a conflict, resolved to ours and listed in
+//! a `table.conflicts` block at the end of the merged document. Deleting a
+//! row, block or column the other side changed is a conflict too, resolved
+//! by keeping the change.
+
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+
+use crate::prelude::*;
+use crate::{Block, Document, FieldInfo, Row, Value};
+
+/// Name of the block that lists conflicts
+pub const CONFLICTS_BLOCK: &str = "conflicts";
+
+/// A change both sides made differently
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    /// `kind.name` of the block
+    pub block: String,
+    /// The row's key values joined by `,`, its 1-based position in an
+    /// unkeyed block, or `None` for a whole block
+    pub key: Option<String>,
+    /// The cell's field, or `None` for a whole row or block
+    pub field: Option<String>,
+    pub base: Value,
+    pub ours: Value,
+    pub theirs: Value,
+}
+
+/// Outcome of [`merge3`]
+#[derive(Debug, Clone)]
+pub struct Merge {
+    /// The merged blocks, followed by `table.conflicts` if there are
+    /// conflicts
+    pub document: Document,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Merge {
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// Merge `ours` and `theirs`, both edited from `base`, keying rows on `id`
+///
+/// ```rust
+/// use ison_rs::merge::merge3;
+///
+/// let base = ison_rs::parse("table.users\nid name role\n1 Alice dev\n2 Bob dev")?;
+/// let ours = ison_rs::parse("table.users\nid name role\n1 Alicia dev\n2 Bob dev")?;
+/// let theirs = ison_rs::parse("table.users\nid name role\n1 Alice lead\n2 Bob dev\n3 Cy ops")?;
+///
+/// let merged = merge3(&base, &ours, &theirs);
+/// assert!(merged.is_clean());
+/// assert_eq!(
+///     ison_rs::dumps(&merged.document, false),
+///     "table.users\nid name role\n1 Alicia lead\n2 Bob dev\n3 Cy ops"
+/// );
+/// # Ok::<(), ison_rs::ISONError>(())
+/// ```
+pub fn merge3(base: &Document, ours: &Document, theirs: &Document) -> Merge {
+    merge3_by(base, ours, theirs, &[])
+}
+
+/// [`merge3`] with the key fields of some blocks given as `(kind.name,
+/// fields)` pairs; other blocks key on `id` if they have it
+pub fn merge3_by(
+    base: &Document,
+    ours: &Document,
+    theirs: &Document,
+    keys_per_block: &[(&str, &[&str])],
+) -> Merge {
+    let mut merger = Merger {
+        keys_per_block,
+        conflicts: Vec::new(),
+    };
+    let mut blocks = Vec::new();
+
+    // Blocks in our order, then blocks only they added
+    let mut names: Vec<(&str, &str)> = Vec::new();
+    for block in ours.blocks.iter().chain(&theirs.blocks) {
+        if !names.contains(&(&block.kind, &block.name)) {
+            names.push((&block.kind, &block.name));
+        }
+    }
+    for (kind, name) in names {
+        if let Some(block) = merger.block(
+            base.get_kind_name(kind, name),
+            ours.get_kind_name(kind, name),
+            theirs.get_kind_name(kind, name),
+        ) {
+            blocks.push(block);
+        }
+    }
+
+    let conflicts = merger.conflicts;
+    if !conflicts.is_empty() {
+        blocks.push(conflicts_block(&conflicts));
+    }
+    Merge {
+        document: Document { blocks },
+        conflicts,
+    }
+}
+
+/// The `table.conflicts` block for `conflicts`
+pub fn conflicts_block(conflicts: &[Conflict]) -> Block {
+    let mut block = Block::new("table", CONFLICTS_BLOCK);
+    for field in ["block", "key", "field", "base", "ours", "theirs"] {
+        block.add_field_def(field);
+    }
+    let keys = block.field_keys();
+    for conflict in conflicts {
+        let text = |s: &Option<String>| s.clone().map_or(Value::Null, Value::String);
+        let values = [
+            Value::String(conflict.block.clone()),
+            text(&conflict.key),
+            text(&conflict.field),
+            conflict.base.clone(),
+            conflict.ours.clone(),
+            conflict.theirs.clone(),
+        ];
+        block.rows.push(keys.iter().cloned().zip(values).collect());
+    }
+    block
+}
+
+/// Marker for a deleted or changed row or block in a [`Conflict`]
+fn marker(present: bool) -> Value {
+    Value::String(if present { "modified" } else { "deleted" }.to_string())
+}
+
+struct Merger<'k> {
+    keys_per_block: &'k [(&'k str, &'k [&'k str])],
+    conflicts: Vec<Conflict>,
+}
+
+impl Merger<'_> {
+    fn block(
+        &mut self,
+        base: Option<&Block>,
+        ours: Option<&Block>,
+        theirs: Option<&Block>,
+    ) -> Option<Block> {
+        match (base, ours, theirs) {
+            (_, Some(o), Some(t)) => Some(self.rows(base, o, t)),
+            (None, side, other) => side.or(other).cloned(),
+            // Deleted on one side: fine if the other left it alone
+            (Some(b), Some(side), None) | (Some(b), None, Some(side)) => {
+                if same(b, side) {
+                    return None;
+                }
+                self.conflicts.push(Conflict {
+                    block: qualified(side),
+                    key: None,
+                    field: None,
+                    base: Value::Null,
+                    ours: marker(ours.is_some()),
+                    theirs: marker(theirs.is_some()),
+                });
+                Some(side.clone())
+            }
+            (Some(_), None, None) => None,
+        }
+    }
+
+    /// Merge the rows of a block both sides have
+    fn rows(&mut self, base: Option<&Block>, ours: &Block, theirs: &Block) -> Block {
+        let empty = Block::new(ours.kind.clone(), ours.name.clone());
+        let base = base.unwrap_or(&empty);
+        let key_fields = self.key_fields(ours);
+        let base_rows = keyed(base, &key_fields);
+        let base_index: BTreeMap<&RowKey, &Row> = base_rows.iter().map(|(k, r)| (k, *r)).collect();
+
+        // A column one side dropped and the other changed is a conflict per
+        // changed cell, and is kept by giving it back its base values on
+        // the side that dropped it
+        let (mut ours, mut theirs) = (Cow::Borrowed(ours), Cow::Borrowed(theirs));
+        for field in &base.fields {
+            let dropped_by_ours = match (ours.fields.contains(field), theirs.fields.contains(field))
+            {
+                (true, false) => false,
+                (false, true) => true,
+                _ => continue,
+            };
+            let kept = if dropped_by_ours { &theirs } else { &ours };
+            let changed: Vec<(RowKey, Value, Value)> = keyed(kept, &key_fields)
+                .into_iter()
+                .filter_map(|(key, row)| {
+                    let cell = |r: Option<&Row>| {
+                        r.and_then(|r| r.get(field.as_str()))
+                            .cloned()
+                            .unwrap_or(Value::Null)
+                    };
+                    let (before, after) = (cell(base_index.get(&key).copied()), cell(Some(row)));
+                    (before != after).then_some((key, before, after))
+                })
+                .collect();
+            if changed.is_empty() {
+                continue;
+            }
+            for (key, before, after) in changed {
+                let (our_value, their_value) = if dropped_by_ours {
+                    (marker(false), after)
+                } else {
+                    (after, marker(false))
+                };
+                self.conflicts.push(Conflict {
+                    block: qualified(base),
+                    key: Some(display_key(&key)),
+                    field: Some(field.clone()),
+                    base: before,
+                    ours: our_value,
+                    theirs: their_value,
+                });
+            }
+            let side = if dropped_by_ours {
+                ours.to_mut()
+            } else {
+                theirs.to_mut()
+            };
+            restore_field(side, base, field, &key_fields, &base_index);
+        }
+        let (ours, theirs) = (&*ours, &*theirs);
+
+        // Fields either side has, less those one side dropped
+        let dropped = |f: &String| {
+            base.fields.contains(f) && (!ours.fields.contains(f) || !theirs.fields.contains(f))
+        };
+        let mut merged = Block::new(ours.kind.clone(), ours.name.clone());
+        for info in ours.field_info.iter().chain(&theirs.field_info) {
+            if !merged.fields.contains(&info.name) && !dropped(&info.name) {
+                merged.fields.push(info.name.clone());
+                merged.field_info.push(info.clone());
+            }
+        }
+
+        let our_rows = keyed(ours, &key_fields);
+        let their_rows = keyed(theirs, &key_fields);
+        let their_index: BTreeMap<&RowKey, &Row> =
+            their_rows.iter().map(|(k, r)| (k, *r)).collect();
+        let our_index: BTreeMap<&RowKey, &Row> = our_rows.iter().map(|(k, r)| (k, *r)).collect();
+
+        let mut order: Vec<&RowKey> = our_rows.iter().map(|(k, _)| k).collect();
+        // Rows only they have, whether added or deleted by us
+        order.extend(
+            their_rows
+                .iter()
+                .map(|(k, _)| k)
+                .filter(|k| !our_index.contains_key(k)),
+        );
+
+        let keys = merged.field_keys();
+        for key in order {
+            let (b, o, t) = (
+                base_index.get(key).copied(),
+                our_index.get(key).copied(),
+                their_index.get(key).copied(),
+            );
+            let row = self.row(&merged, key, b, o, t);
+            if let Some(row) = row {
+                merged.rows.push(
+                    keys.iter()
+                        .filter_map(|k| row.get(k).map(|v| (k.clone(), v.clone())))
+                        .collect(),
+                );
+            }
+        }
+
+        // Summary rows are merged as a whole
+        merged.summary_rows = if theirs.summary_rows == base.summary_rows {
+            ours.summary_rows.clone()
+        } else if ours.summary_rows == base.summary_rows {
+            theirs.summary_rows.clone()
+        } else {
+            if ours.summary_rows != theirs.summary_rows {
+                self.conflicts.push(Conflict {
+                    block: qualified(ours),
+                    key: Some("---".to_string()),
+                    field: None,
+                    base: Value::Null,
+                    ours: marker(true),
+                    theirs: marker(true),
+                });
+            }
+            ours.summary_rows.clone()
+        };
+        merged
+    }
+
+    /// Merge one row; `None` when it ends up deleted
+    fn row(
+        &mut self,
+        block: &Block,
+        key: &RowKey,
+        base: Option<&Row>,
+        ours: Option<&Row>,
+        theirs: Option<&Row>,
+    ) -> Option<Row> {
+        if ours == theirs || theirs == base {
+            return ours.cloned();
+        }
+        if ours == base {
+            return theirs.cloned();
+        }
+        let display_key = display_key(key);
+        let (Some(o), Some(t)) = (ours, theirs) else {
+            // Deleted on one side, changed on the other: keep the change
+            self.conflicts.push(Conflict {
+                block: qualified(block),
+                key: Some(display_key),
+                field: None,
+                base: Value::Null,
+                ours: marker(ours.is_some()),
+                theirs: marker(theirs.is_some()),
+            });
+            return ours.or(theirs).cloned();
+        };
+
+        let mut row = Row::new();
+        for field in &block.fields {
+            let cell = |r: Option<&Row>| r.and_then(|r| r.get(field.as_str())).cloned();
+            let (bv, ov, tv) = (cell(base), cell(Some(o)), cell(Some(t)));
+            let value = if ov == tv || tv == bv {
+                ov
+            } else if ov == bv {
+                tv
+            } else {
+                self.conflicts.push(Conflict {
+                    block: qualified(block),
+                    key: Some(display_key.clone()),
+                    field: Some(field.clone()),
+                    base: bv.unwrap_or(Value::Null),
+                    ours: ov.clone().unwrap_or(Value::Null),
+                    theirs: tv.unwrap_or(Value::Null),
+                });
+                ov
+            };
+            if let Some(value) = value {
+                row.insert(field.as_str().into(), value);
+            }
+        }
+        Some(row)
+    }
+
+    /// Key fields of `block`; empty to key rows by position
+    fn key_fields(&self, block: &Block) -> Vec<String> {
+        let name = qualified(block);
+        match self.keys_per_block.iter().find(|(n, _)| *n == name) {
+            Some((_, keys)) => keys.iter().map(|k| k.to_string()).collect(),
+            None if block.fields.iter().any(|f| f == "id") => vec!["id".to_string()],
+            None => Vec::new(),
+        }
+    }
+}
+
+/// A row's key values, or its 1-based position in an unkeyed block, and
+/// which of the rows with those values it is, counting from 1
+type RowKey = (Vec<Value>, usize);
+
+/// Rows of `block` with their keys
+fn keyed<'b>(block: &'b Block, key_fields: &[String]) -> Vec<(RowKey, &'b Row)> {
+    let mut counts: BTreeMap<Vec<Value>, usize> = BTreeMap::new();
+    block
+        .rows
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let values: Vec<Value> = if key_fields.is_empty() {
+                vec![Value::Int(i as i64 + 1)]
+            } else {
+                key_fields
+                    .iter()
+                    .map(|f| row.get(f.as_str()).cloned().unwrap_or(Value::Null))
+                    .collect()
+            };
+            let count = counts.entry(values.clone()).or_insert(0);
+            *count += 1;
+            ((values, *count), row)
+        })
+        .collect()
+}
+
+/// Add `field` back to `block`, which dropped it, with the values of the
+/// matching rows of `base`
+fn restore_field(
+    block: &mut Block,
+    base: &Block,
+    field: &str,
+    key_fields: &[String],
+    base_index: &BTreeMap<&RowKey, &Row>,
+) {
+    let info = base.field_info.iter().find(|fi| fi.name == field);
+    block.fields.push(field.to_string());
+    block
+        .field_info
+        .push(info.cloned().unwrap_or_else(|| FieldInfo::new(field)));
+    let keys: Vec<RowKey> = keyed(block, key_fields)
+        .into_iter()
+        .map(|(k, _)| k)
+        .collect();
+    for (key, row) in keys.iter().zip(&mut block.rows) {
+        if let Some(value) = base_index.get(key).and_then(|r| r.get(field)) {
+            row.insert(field.into(), value.clone());
+        }
+    }
+}
+
+/// The key values of a [`Conflict`], joined by `,`
+fn display_key((values, _): &RowKey) -> String {
+    let parts: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+    parts.join(",")
+}
+
+fn qualified(block: &Block) -> String {
+    format!("{}.{}", block.kind, block.name)
+}
+
+/// Whether two blocks hold the same fields and rows
+fn same(a: &Block, b: &Block) -> bool {
+    a.fields == b.fields && a.rows == b.rows && a.summary_rows == b.summary_rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dumps, parse};
+
+    #[test]
+    fn test_merge3() {
+        let base = parse(
+            "table.users\nid name role\n1 Alice dev\n2 Bob dev\n3 Cy ops\n\n\
+             table.notes\ntext\nhello\n\ntable.old\nx\n1",
+        )
+        .unwrap();
+        let ours = parse(
+            "table.users\nid name role\n1 Alicia dev\n3 Cy ops\n4 Di qa\n\n\
+             table.notes\ntext\nhey\nbye\n\ntable.old\nx\n2",
+        )
+        .unwrap();
+        let theirs = parse(
+            "table.users\nid name role email\n1 Alice lead a@x\n2 Bob dev b@x\n3 Cy sre c@x\n\n\
+             table.notes\ntext\nhi\n\ntable.new\ny\n1",
+        )
+        .unwrap();
+
+        let merged = merge3(&base, &ours, &theirs);
+        let text = dumps(&merged.document, false);
+        assert_eq!(
+            text,
+            "table.users\nid name role email\n1 Alicia lead a@x\n3 Cy sre c@x\n4 Di qa null\n\
+             2 Bob dev b@x\n\n\
+             table.notes\ntext\nhey\nbye\n\n\
+             table.old\nx\n2\n\n\
+             table.new\ny\n1\n\n\
+             table.conflicts\nblock key field base ours theirs\n\
+             \"table.users\" \"2\" null null deleted modified\n\
+             \"table.notes\" \"1\" text hello hey hi\n\
+             \"table.old\" null null null modified deleted"
+        );
+        assert_eq!(merged.conflicts.len(), 3);
+        assert_eq!(merged.conflicts[1].field.as_deref(), Some("text"));
+    }
+
+    #[test]
+    fn test_merge3_cells() {
+        let base = parse("table.t\nk v w\na 1 1\nb 1 1").unwrap();
+        let ours = parse("table.t\nk v w\na 2 1\nb 1 1").unwrap();
+        let theirs = parse("table.t\nk v w\na 3 5\nb 1 1").unwrap();
+        let keys: &[(&str, &[&str])] = &[("table.t", &["k"])];
+
+        let merged = merge3_by(&base, &ours, &theirs, keys);
+        assert_eq!(merged.conflicts.len(), 1);
+        let conflict = &merged.conflicts[0];
+        assert_eq!(conflict.key.as_deref(), Some("a"));
+        assert_eq!(
+            (&conflict.base, &conflict.ours, &conflict.theirs),
+            (&Value::Int(1), &Value::Int(2), &Value::Int(3))
+        );
+        assert!(dumps(&merged.document, false).starts_with("table.t\nk v w\na 2 5\nb 1 1\n"));
+
+        // Identical edits are not conflicts
+        assert!(merge3_by(&base, &ours, &ours, keys).is_clean());
+        assert!(merge3(&base, &base, &base).is_clean());
+
+        // Keys are given per kind.name: object.t keys rows by position, so
+        // our deleting row a looks like deleting the row they changed
+        let base = parse("table.t\nk v\na 1\nb 1\n\nobject.t\nk v\na 1\nb 1").unwrap();
+        let ours = parse("table.t\nk v\nb 1\n\nobject.t\nk v\nb 1").unwrap();
+        let theirs = parse("table.t\nk v\na 1\nb 2\n\nobject.t\nk v\na 1\nb 2").unwrap();
+        let merged = merge3_by(&base, &ours, &theirs, keys);
+        assert_eq!(merged.conflicts.len(), 1);
+        assert_eq!(merged.conflicts[0].block, "object.t");
+        assert!(dumps(&merged.document, false).starts_with("table.t\nk v\nb 2\n\n"));
+    }
+
+    #[test]
+    fn test_merge3_dropped_column() {
+        let base = parse("table.t\nid v note\n1 1 a\n2 1 b").unwrap();
+        let ours = parse("table.t\nid v\n1 2\n2 1").unwrap();
+
+        // Dropping a column the other side left alone is taken as is
+        let merged = merge3(&base, &ours, &base);
+        assert!(merged.is_clean());
+        assert_eq!(dumps(&merged.document, false), "table.t\nid v\n1 2\n2 1");
+
+        // If they changed it, the column stays, with a conflict per change
+        let theirs = parse("table.t\nid v note\n1 1 a\n2 1 c").unwrap();
+        let merged = merge3(&base, &ours, &theirs);
+        assert_eq!(
+            merged.conflicts,
+            [Conflict {
+                block: "table.t".into(),
+                key: Some("2".into()),
+                field: Some("note".into()),
+                base: "b".into(),
+                ours: "deleted".into(),
+                theirs: "c".into(),
+            }]
+        );
+        assert!(dumps(&merged.document, false).starts_with("table.t\nid v note\n1 2 a\n2 1 c\n"));
+
+        // And the same the other way round
+        let merged = merge3(&base, &theirs, &ours);
+        assert_eq!(merged.conflicts[0].ours, Value::from("c"));
+        assert_eq!(merged.conflicts[0].theirs, Value::from("deleted"));
+        assert!(dumps(&merged.document, false).starts_with("table.t\nid v note\n1 2 a\n2 1 c\n"));
+    }
+
+    #[test]
+    fn test_merge3_key_values() {
+        let base = parse("table.t\nid v\n\"a#b\" 1").unwrap();
+        let ours = parse("table.t\nid v\n\"a#b\" 2").unwrap();
+        let theirs = parse("table.t\nid v\n\"a#b\" 3").unwrap();
+        let merged = merge3(&base, &ours, &theirs);
+        assert_eq!(merged.conflicts[0].key.as_deref(), Some("a#b"));
+
+        // 1 and "1" are different keys: they delete one, we edit the other
+        let base = parse("table.t\nid v\n1 x\n\"1\" y").unwrap();
+        let ours = parse("table.t\nid v\n1 x\n\"1\" z").unwrap();
+        let theirs = parse("table.t\nid v\n\"1\" y").unwrap();
+        let merged = merge3(&base, &ours, &theirs);
+        assert!(merged.is_clean());
+        assert_eq!(dumps(&merged.document, false), "table.t\nid v\n\"1\" z");
+
+        // So are ("x,y", "z") and ("x", "y,z")
+        let keys: &[(&str, &[&str])] = &[("table.t", &["a", "b"])];
+        let base = parse("table.t\na b v\n\"x,y\" z 1\nx \"y,z\" 2").unwrap();
+        let ours = parse("table.t\na b v\n\"x,y\" z 1\nx \"y,z\" 3").unwrap();
+        let theirs = parse("table.t\na b v\nx \"y,z\" 2").unwrap();
+        let merged = merge3_by(&base, &ours, &theirs, keys);
+        assert!(merged.is_clean());
+        assert_eq!(merged.document["t"].rows, ours["t"].rows[1..]);
+    }
+}
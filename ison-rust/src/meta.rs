@@ -0,0 +1,174 @@
+//! Document-level metadata in a `meta.document` block
+//!
+//! ```text
+//! meta.document
+//! version generator created_at schema
+//! "1.0" "gpt-4o via ingest-v2" 2025-01-15T10:30:00Z "https://example.com/orders.ison"
+//! ```
+//!
+//! Under [`ParseOptions::key_value_objects`](crate::ParseOptions::key_value_objects)
+//! it may also be written as `key value` pairs, one per line.
+//!
+//! The serializer always writes this block first, wherever it sits in
+//! [`Document::blocks`].
+
+use crate::prelude::*;
+use crate::{Block, Document, FieldInfo, Row, Value};
+
+/// Kind of the metadata block
+pub const META_KIND: &str = "meta";
+/// Name of the metadata block
+pub const META_NAME: &str = "document";
+
+/// Typed view of the `meta.document` block
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DocumentMeta {
+    /// Format or schema version of the document (`version`)
+    pub version: Option<String>,
+    /// Tool or model that produced the document (`generator`)
+    pub generator: Option<String>,
+    /// Creation timestamp, conventionally RFC 3339 (`created_at`)
+    pub created_at: Option<String>,
+    /// Schema the document claims to follow, as a URL, path, or reference (`schema`)
+    pub schema: Option<String>,
+    /// Any other keys, in block order
+    pub extra: Vec<(String, Value)>,
+}
+
+const KNOWN: [&str; 4] = ["version", "generator", "created_at", "schema"];
+
+/// Text of a metadata value; floats keep their decimal point so `1.0` stays `"1.0"`
+fn text(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        Value::Float(f) => Some(format!("{:?}", f)),
+        other => Some(other.to_string()),
+    }
+}
+
+impl DocumentMeta {
+    fn from_block(block: &Block) -> Self {
+        let empty = Row::new();
+        let row = block.rows.first().unwrap_or(&empty);
+        let get = |key: &str| row.get(key).and_then(text);
+        DocumentMeta {
+            version: get("version"),
+            generator: get("generator"),
+            created_at: get("created_at"),
+            schema: get("schema"),
+            extra: block
+                .fields
+                .iter()
+                .filter(|f| !KNOWN.contains(&f.as_str()))
+                .filter_map(|f| Some((f.clone(), row.get(f.as_str())?.clone())))
+                .collect(),
+        }
+    }
+
+    fn to_block(&self) -> Block {
+        let mut block = Block::new(META_KIND, META_NAME);
+        let known = [
+            &self.version,
+            &self.generator,
+            &self.created_at,
+            &self.schema,
+        ];
+        let mut values: Vec<(&str, Value)> = KNOWN
+            .iter()
+            .zip(known)
+            .filter_map(|(key, value)| Some((*key, Value::String(value.clone()?))))
+            .collect();
+        values.extend(self.extra.iter().map(|(k, v)| (k.as_str(), v.clone())));
+
+        let mut row = Row::new();
+        for (key, value) in values {
+            block.fields.push(key.to_string());
+            block.field_info.push(FieldInfo::new(key));
+            row.insert(key.into(), value);
+        }
+        block.rows.push(row);
+        block
+    }
+}
+
+impl Document {
+    /// The `meta.document` block, if present
+    pub fn meta_block(&self) -> Option<&Block> {
+        self.get_kind_name(META_KIND, META_NAME)
+    }
+
+    /// Metadata from the `meta.document` block, if present
+    pub fn meta(&self) -> Option<DocumentMeta> {
+        self.meta_block().map(DocumentMeta::from_block)
+    }
+
+    /// Replace the `meta.document` block, adding it at the front if missing
+    pub fn set_meta(&mut self, meta: &DocumentMeta) {
+        let block = meta.to_block();
+        match self
+            .blocks
+            .iter()
+            .position(|b| b.kind == META_KIND && b.name == META_NAME)
+        {
+            Some(idx) => self.blocks[idx] = block,
+            None => self.blocks.insert(0, block),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dumps, parse, parse_with_options, ParseOptions};
+
+    #[test]
+    fn test_meta_accessors() {
+        let doc = parse(
+            "table.users\nid\n1\n\n\
+             meta.document\nversion generator created_at run_id\n\
+             1.0 \"ingest v2\" 2025-01-15T10:30:00Z 42",
+        )
+        .unwrap();
+        let meta = doc.meta().unwrap();
+        assert_eq!(meta.version.as_deref(), Some("1.0"));
+        assert_eq!(meta.generator.as_deref(), Some("ingest v2"));
+        assert_eq!(meta.created_at.as_deref(), Some("2025-01-15T10:30:00Z"));
+        assert_eq!(meta.schema, None);
+        assert_eq!(meta.extra, vec![("run_id".to_string(), Value::Int(42))]);
+
+        // Emitted first, and round-trips
+        let text = dumps(&doc, false);
+        assert!(text.starts_with("meta.document\n"));
+        assert_eq!(parse(&text).unwrap().meta().unwrap(), meta);
+
+        let options = ParseOptions {
+            key_value_objects: true,
+            ..Default::default()
+        };
+        let pairs = parse_with_options(
+            "meta.document\nkey value\nversion 1.0\ngenerator \"ingest v2\"\n\
+             created_at 2025-01-15T10:30:00Z\nrun_id 42",
+            &options,
+        )
+        .unwrap();
+        assert_eq!(pairs.meta().unwrap(), meta);
+    }
+
+    #[test]
+    fn test_set_meta() {
+        let mut doc = parse("table.users\nid\n1").unwrap();
+        assert!(doc.meta().is_none());
+
+        let meta = DocumentMeta {
+            version: Some("2.0".into()),
+            schema: Some("orders.ison".into()),
+            ..Default::default()
+        };
+        doc.set_meta(&meta);
+        doc.set_meta(&meta);
+        assert_eq!(doc.len(), 2);
+        assert_eq!(doc.blocks[0].fields, vec!["version", "schema"]);
+        assert_eq!(parse(&dumps(&doc, false)).unwrap().meta().unwrap(), meta);
+    }
+}
@@ -0,0 +1,313 @@
+//! Change notifications
+//!
+//! An [`ObservedDocument`] wraps a [`Document`] and routes edits through
+//! methods that tell subscribers what changed, so caches, indexes, and
+//! views built from the document can be updated one event at a time
+//! instead of rebuilt. Reads go through [`ObservedDocument::document`];
+//! there is no mutable access that would bypass the subscribers.
+
+use alloc::boxed::Box;
+use core::fmt;
+
+use crate::prelude::*;
+use crate::{intern, Block, Document, ISONError, Result, Row};
+
+/// One change to an [`ObservedDocument`]
+///
+/// Row indices are positions in the block's data rows at the time of the
+/// change, so applying events in order to a copy keeps it in step.
+#[derive(Debug, Clone, Copy)]
+pub enum ChangeEvent<'a> {
+    RowInserted {
+        block: &'a str,
+        index: usize,
+        row: &'a Row,
+    },
+    RowUpdated {
+        block: &'a str,
+        index: usize,
+        before: &'a Row,
+        after: &'a Row,
+    },
+    RowDeleted {
+        block: &'a str,
+        index: usize,
+        row: &'a Row,
+    },
+    BlockAdded {
+        block: &'a Block,
+    },
+    BlockRemoved {
+        block: &'a Block,
+    },
+}
+
+/// Handle for [`ObservedDocument::unsubscribe`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Subscription(usize);
+
+type Listener = Box<dyn FnMut(&ChangeEvent) + Send>;
+
+/// A document that reports its changes; see the [module docs](self)
+///
+/// ```rust
+/// use ison_rs::observe::{ChangeEvent, ObservedDocument};
+/// use std::sync::{Arc, Mutex};
+///
+/// let mut doc = ObservedDocument::new(ison_rs::parse("table.users\nid\n1\n2")?);
+/// let deleted = Arc::new(Mutex::new(Vec::new()));
+/// let log = deleted.clone();
+/// doc.on_change(move |event| {
+///     if let ChangeEvent::RowDeleted { index, .. } = event {
+///         log.lock().unwrap().push(*index);
+///     }
+/// });
+/// doc.delete_where("users", |_| true)?;
+/// assert_eq!(*deleted.lock().unwrap(), [0, 0]);
+/// # Ok::<(), ison_rs::ISONError>(())
+/// ```
+#[derive(Default)]
+pub struct ObservedDocument {
+    doc: Document,
+    listeners: Vec<(Subscription, Listener)>,
+    next_id: usize,
+}
+
+impl fmt::Debug for ObservedDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ObservedDocument")
+            .field("doc", &self.doc)
+            .field("listeners", &self.listeners.len())
+            .finish()
+    }
+}
+
+impl ObservedDocument {
+    pub fn new(doc: Document) -> Self {
+        Self {
+            doc,
+            ..Default::default()
+        }
+    }
+
+    pub fn document(&self) -> &Document {
+        &self.doc
+    }
+
+    /// The document, without its subscribers
+    pub fn into_document(self) -> Document {
+        self.doc
+    }
+
+    /// Call `listener` for every later change
+    pub fn on_change(
+        &mut self,
+        listener: impl FnMut(&ChangeEvent) + Send + 'static,
+    ) -> Subscription {
+        let id = Subscription(self.next_id);
+        self.next_id += 1;
+        self.listeners.push((id, Box::new(listener)));
+        id
+    }
+
+    /// Stop calling a listener; false if it was already removed
+    pub fn unsubscribe(&mut self, subscription: Subscription) -> bool {
+        let before = self.listeners.len();
+        self.listeners.retain(|(id, _)| *id != subscription);
+        self.listeners.len() < before
+    }
+
+    fn emit(listeners: &mut [(Subscription, Listener)], event: ChangeEvent) {
+        for (_, listener) in listeners {
+            listener(&event);
+        }
+    }
+
+    /// Append a data row to block `name`
+    pub fn insert_row(&mut self, name: &str, row: Row) -> Result<()> {
+        let block = block_mut(&mut self.doc, name)?;
+        let keys = block.row_keys();
+        block.rows.push(
+            row.into_iter()
+                .map(|(k, v)| (intern(&keys, &k), v))
+                .collect(),
+        );
+        let index = block.rows.len() - 1;
+        let event = ChangeEvent::RowInserted {
+            block: &block.name,
+            index,
+            row: &block.rows[index],
+        };
+        Self::emit(&mut self.listeners, event);
+        Ok(())
+    }
+
+    /// [`Block::update_where`] on block `name`, reporting each row whose
+    /// cells changed
+    pub fn update_where(
+        &mut self,
+        name: &str,
+        mut predicate: impl FnMut(&Row) -> bool,
+        mut update: impl FnMut(&mut Row),
+    ) -> Result<usize> {
+        let listeners = &mut self.listeners;
+        let block = block_mut(&mut self.doc, name)?;
+        let mut count = 0;
+        for (index, row) in block.rows.iter_mut().enumerate() {
+            if !predicate(row) {
+                continue;
+            }
+            count += 1;
+            let before = row.clone();
+            update(row);
+            if *row != before {
+                let event = ChangeEvent::RowUpdated {
+                    block: &block.name,
+                    index,
+                    before: &before,
+                    after: row,
+                };
+                Self::emit(listeners, event);
+            }
+        }
+        Ok(count)
+    }
+
+    /// [`Block::delete_where`] on block `name`
+    pub fn delete_where(
+        &mut self,
+        name: &str,
+        mut predicate: impl FnMut(&Row) -> bool,
+    ) -> Result<usize> {
+        let listeners = &mut self.listeners;
+        let block = block_mut(&mut self.doc, name)?;
+        let rows = core::mem::take(&mut block.rows);
+        let before = rows.len();
+        for row in rows {
+            if predicate(&row) {
+                let event = ChangeEvent::RowDeleted {
+                    block: &block.name,
+                    index: block.rows.len(),
+                    row: &row,
+                };
+                Self::emit(listeners, event);
+            } else {
+                block.rows.push(row);
+            }
+        }
+        Ok(before - block.rows.len())
+    }
+
+    /// Append a block
+    pub fn add_block(&mut self, block: Block) {
+        self.doc.blocks.push(block);
+        let event = ChangeEvent::BlockAdded {
+            block: &self.doc.blocks[self.doc.blocks.len() - 1],
+        };
+        Self::emit(&mut self.listeners, event);
+    }
+
+    /// Remove and return the first block named `name`
+    pub fn remove_block(&mut self, name: &str) -> Option<Block> {
+        let block = self.doc.remove_block(name)?;
+        Self::emit(
+            &mut self.listeners,
+            ChangeEvent::BlockRemoved { block: &block },
+        );
+        Some(block)
+    }
+}
+
+fn block_mut<'d>(doc: &'d mut Document, name: &str) -> Result<&'d mut Block> {
+    doc.get_mut(name).ok_or_else(|| ISONError {
+        message: format!("Block '{}' not found", name),
+        line: None,
+    })
+}
+
+impl From<Document> for ObservedDocument {
+    fn from(doc: Document) -> Self {
+        Self::new(doc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::{parse, Value};
+
+    #[test]
+    fn test_observed_document() {
+        let mut doc =
+            ObservedDocument::new(parse("table.users\nid name\n1 Alice\n2 Bob\n3 Cy").unwrap());
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let log = events.clone();
+        let subscription = doc.on_change(move |event| {
+            let entry = match event {
+                ChangeEvent::RowInserted { block, index, row } => {
+                    format!("insert {}[{}] {}", block, index, row["name"])
+                }
+                ChangeEvent::RowUpdated {
+                    block,
+                    index,
+                    before,
+                    after,
+                } => format!(
+                    "update {}[{}] {} -> {}",
+                    block, index, before["name"], after["name"]
+                ),
+                ChangeEvent::RowDeleted { block, index, row } => {
+                    format!("delete {}[{}] {}", block, index, row["name"])
+                }
+                ChangeEvent::BlockAdded { block } => format!("add {}", block.name),
+                ChangeEvent::BlockRemoved { block } => format!("remove {}", block.name),
+            };
+            log.lock().unwrap().push(entry);
+        });
+
+        let row: Row = [("id".into(), Value::Int(4)), ("name".into(), "Di".into())]
+            .into_iter()
+            .collect();
+        doc.insert_row("users", row).unwrap();
+        let updated = doc
+            .update_where(
+                "users",
+                |r| r["id"].as_int() < Some(3),
+                |r| {
+                    if r["id"] == Value::Int(2) {
+                        r.insert("name".into(), "Robert".into());
+                    }
+                },
+            )
+            .unwrap();
+        assert_eq!(updated, 2);
+        assert_eq!(
+            doc.delete_where("users", |r| r["id"] != Value::Int(3))
+                .unwrap(),
+            3
+        );
+        doc.add_block(Block::new("table", "audit"));
+        doc.remove_block("audit");
+        assert!(doc.delete_where("missing", |_| true).is_err());
+
+        assert!(doc.unsubscribe(subscription));
+        assert!(!doc.unsubscribe(subscription));
+        doc.remove_block("users");
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                "insert users[3] Di",
+                "update users[1] Bob -> Robert",
+                "delete users[0] Alice",
+                "delete users[0] Robert",
+                "delete users[1] Di",
+                "add audit",
+                "remove audit",
+            ]
+        );
+        assert!(doc.into_document().is_empty());
+    }
+}
@@ -0,0 +1,634 @@
+//! Parser and serializer configuration
+//!
+//! [`parse`](crate::parse) uses [`ParseOptions::default`], which accepts
+//! everything the format allows. Pass options to
+//! [`parse_with_options`](crate::parse_with_options) to tighten that.
+//! [`DumpOptions`] does the same for
+//! [`dumps_with_options`](crate::dumps_with_options).
+
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use core::fmt;
+
+use crate::prelude::*;
+use crate::progress::{CancellationToken, Progress, ProgressCallback};
+use crate::{intern, Block, Document, ISONError, Result, Row, Value};
+
+/// What to do when a document repeats a block's kind and name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateBlockPolicy {
+    /// Keep every block; [`Document::get`] returns the first
+    #[default]
+    KeepAll,
+    /// Fail with the line of the repeated header
+    Error,
+    /// Ignore later blocks with the same kind and name
+    KeepFirst,
+    /// Let each repeat replace the earlier block, keeping its position
+    KeepLast,
+    /// Append the repeat's rows to the first block, adding any new fields
+    MergeRows,
+}
+
+/// What to do with the non-finite floats `nan`, `inf`, and `-inf`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NonFinitePolicy {
+    /// Read and write them as float tokens
+    #[default]
+    Allow,
+    /// Fail on them
+    Reject,
+    /// Replace them with null
+    Null,
+}
+
+/// Which implementation's reading of the format to follow where ISON
+/// parsers disagree
+///
+/// The dialects differ only in the places listed; block boundaries, field
+/// definitions, and `"""` strings are read alike.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dialect {
+    /// This crate's reading: `#` starts a comment anywhere outside quotes,
+    /// `~` is null, anything Rust parses as a number is one (`1e6`, `+5`,
+    /// `.5`, `nan`), and an unclosed quote runs to the end of the line
+    #[default]
+    V1,
+    /// The Python `ison-py` parser's reading: only whole lines are comments,
+    /// `null` is the only null, numbers are `-?digits` or
+    /// `-?digits.digits`, `:a:b:c` refers to `b:c` of type `a`, missing
+    /// trailing cells are null, and an unclosed quote is an error
+    PythonCompat,
+    /// Fail on everything the other dialects read differently: an unquoted
+    /// `#` after the start of a line, `~`, other number spellings, an empty
+    /// reference, and an unclosed quote
+    Strict,
+}
+
+/// Predicate deciding which rows a parse keeps; see [`ParseOptions::row_filter`]
+pub type RowFilter = Arc<dyn Fn(&str, &Row) -> bool + Send + Sync>;
+
+/// Options for [`parse_with_options`](crate::parse_with_options)
+#[derive(Clone, Default)]
+pub struct ParseOptions {
+    /// Reading of comments, quoting, nulls, and numbers
+    pub dialect: Dialect,
+    /// Handling of repeated `kind.name` blocks
+    pub duplicate_blocks: DuplicateBlockPolicy,
+    /// Handling of `nan`, `inf`, `-inf`, and numbers too large for `f64`
+    pub non_finite: NonFinitePolicy,
+    /// Read every non-integer number as an exact [`Value::Decimal`](crate::Value::Decimal),
+    /// not just those in `decimal` columns
+    #[cfg(feature = "rust_decimal")]
+    pub exact_decimals: bool,
+    /// Record how numbers were written (`0.50`, `1e6`, `007`) in
+    /// [`Block::number_text`], so serializing writes them back unchanged
+    pub lossless_numbers: bool,
+    /// Read an `object` or `meta` block whose fields line is `key value` as
+    /// one `key value` pair per line. Off by default, as a tabular block may
+    /// name its columns `key` and `value`.
+    pub key_value_objects: bool,
+    /// Field renames applied as headers are read, e.g. `"e-mail" -> "email"`,
+    /// so rows and field info only ever see the new names. Keys of `key
+    /// value` blocks are renamed too; type annotations are kept.
+    pub field_aliases: BTreeMap<String, String>,
+    /// Per block name, the only fields to keep; the cells of other columns
+    /// are never parsed. Names are matched after
+    /// [`field_aliases`](Self::field_aliases), and blocks not listed keep
+    /// every field.
+    pub include_fields: BTreeMap<String, Vec<String>>,
+    /// Called with the block name and each parsed data row; rows it rejects
+    /// are dropped before they are stored. Summary rows are always kept.
+    pub row_filter: Option<RowFilter>,
+    /// Told how far parsing has got; see [`progress`](crate::progress)
+    pub progress: Option<ProgressCallback>,
+    /// Stops parsing with an error once cancelled
+    pub cancel: Option<CancellationToken>,
+}
+
+impl fmt::Debug for ParseOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct("ParseOptions");
+        s.field("dialect", &self.dialect)
+            .field("duplicate_blocks", &self.duplicate_blocks)
+            .field("non_finite", &self.non_finite);
+        #[cfg(feature = "rust_decimal")]
+        s.field("exact_decimals", &self.exact_decimals);
+        s.field("lossless_numbers", &self.lossless_numbers)
+            .field("key_value_objects", &self.key_value_objects)
+            .field("field_aliases", &self.field_aliases)
+            .field("include_fields", &self.include_fields)
+            .field("row_filter", &self.row_filter.as_ref().map(|_| ".."))
+            .field("progress", &self.progress.as_ref().map(|_| ".."))
+            .field("cancel", &self.cancel)
+            .finish()
+    }
+}
+
+/// Options for [`dumps_with_options`](crate::dumps_with_options)
+#[derive(Clone)]
+pub struct DumpOptions {
+    /// Pad columns to a common width
+    pub align_columns: bool,
+    /// Column separator; the parser only splits on spaces and tabs
+    pub delimiter: String,
+    /// Write strings containing newlines as `"""` block strings, one source
+    /// line per line of text, instead of escaping each newline as `\n`
+    pub block_strings: bool,
+    /// Handling of NaN and infinite floats
+    pub non_finite: NonFinitePolicy,
+    /// Annotate fields that have no type with the one their cells share
+    /// (`id:int name:string?`), see [`Block::infer_field_type`]
+    pub infer_types: bool,
+    /// Append a `meta.checksum` block for the document's blocks, see
+    /// [`Document::add_checksum`]
+    pub checksum: bool,
+    /// Told how far serialization has got; see [`progress`](crate::progress)
+    pub progress: Option<ProgressCallback>,
+    /// Stops serialization with an error once cancelled
+    pub cancel: Option<CancellationToken>,
+}
+
+impl fmt::Debug for DumpOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DumpOptions")
+            .field("align_columns", &self.align_columns)
+            .field("delimiter", &self.delimiter)
+            .field("block_strings", &self.block_strings)
+            .field("non_finite", &self.non_finite)
+            .field("infer_types", &self.infer_types)
+            .field("checksum", &self.checksum)
+            .field("progress", &self.progress.as_ref().map(|_| ".."))
+            .field("cancel", &self.cancel)
+            .finish()
+    }
+}
+
+impl Default for DumpOptions {
+    fn default() -> Self {
+        Self {
+            align_columns: false,
+            delimiter: " ".to_string(),
+            block_strings: false,
+            non_finite: NonFinitePolicy::Allow,
+            infer_types: false,
+            checksum: false,
+            progress: None,
+            cancel: None,
+        }
+    }
+}
+
+impl DumpOptions {
+    /// Set [`progress`](Self::progress)
+    pub fn progress(mut self, progress: impl Fn(Progress) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Arc::new(progress));
+        self
+    }
+
+    /// Under [`NonFinitePolicy::Reject`], fail on the first NaN or infinite float in `doc`
+    pub(crate) fn check_finite(&self, doc: &Document) -> Result<()> {
+        if self.non_finite != NonFinitePolicy::Reject {
+            return Ok(());
+        }
+        for block in &doc.blocks {
+            for row in block.rows.iter().chain(&block.summary_rows) {
+                for (field, value) in row {
+                    if matches!(value, Value::Float(f) if !f.is_finite()) {
+                        return Err(ISONError {
+                            message: format!(
+                                "Non-finite float {} in {}.{}",
+                                value, block.name, field
+                            ),
+                            line: None,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ParseOptions {
+    /// Set [`dialect`](Self::dialect)
+    ///
+    /// ```rust
+    /// use ison_rs::{parse_with_options, Dialect, ParseOptions, Value};
+    ///
+    /// let text = "table.colors\nname hex\nred #ff0000\nnone ~";
+    /// let doc = parse_with_options(text, &ParseOptions::default().dialect(Dialect::PythonCompat))?;
+    /// assert_eq!(doc["colors"][0]["hex"].as_str(), Some("#ff0000"));
+    /// assert_eq!(doc["colors"][1]["hex"].as_str(), Some("~"));
+    ///
+    /// let err = parse_with_options(text, &ParseOptions::default().dialect(Dialect::Strict));
+    /// assert_eq!(err.unwrap_err().line, Some(3));
+    /// # Ok::<(), ison_rs::ISONError>(())
+    /// ```
+    pub fn dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// Set [`row_filter`](Self::row_filter)
+    ///
+    /// ```rust
+    /// use ison_rs::{parse_with_options, ParseOptions};
+    ///
+    /// let options = ParseOptions::default()
+    ///     .row_filter(|block, row| block != "logs" || row["level"].as_str() == Some("error"));
+    /// let doc = parse_with_options("table.logs\nlevel msg\ninfo up\nerror down", &options)?;
+    /// assert_eq!(doc["logs"].len(), 1);
+    /// # Ok::<(), ison_rs::ISONError>(())
+    /// ```
+    pub fn row_filter(
+        mut self,
+        filter: impl Fn(&str, &Row) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.row_filter = Some(Arc::new(filter));
+        self
+    }
+
+    /// Set [`progress`](Self::progress)
+    pub fn progress(mut self, progress: impl Fn(Progress) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Arc::new(progress));
+        self
+    }
+
+    /// Whether [`row_filter`](Self::row_filter) keeps `row` of block `block`
+    pub(crate) fn keeps(&self, block: &str, row: &Row) -> bool {
+        self.row_filter
+            .as_ref()
+            .is_none_or(|filter| filter(block, row))
+    }
+
+    /// `name` under [`field_aliases`](Self::field_aliases)
+    pub(crate) fn field_name<'n>(&'n self, name: &'n str) -> &'n str {
+        self.field_aliases.get(name).map_or(name, String::as_str)
+    }
+
+    /// Whether field `name` of block `block` is parsed, under
+    /// [`include_fields`](Self::include_fields)
+    pub(crate) fn includes(&self, block: &str, name: &str) -> bool {
+        self.include_fields
+            .get(block)
+            .is_none_or(|fields| fields.iter().any(|f| f == name))
+    }
+
+    /// A `name[:type]` field definition with its name aliased
+    pub(crate) fn field_def<'n>(&self, def: &'n str) -> Cow<'n, str> {
+        if self.field_aliases.is_empty() {
+            return Cow::Borrowed(def);
+        }
+        let (name, field_type) = match def.split_once(':') {
+            Some((name, field_type)) => (name, Some(field_type)),
+            None => (def, None),
+        };
+        match (self.field_aliases.get(name), field_type) {
+            (None, _) => Cow::Borrowed(def),
+            (Some(alias), None) => Cow::Owned(alias.clone()),
+            (Some(alias), Some(field_type)) => Cow::Owned(format!("{}:{}", alias, field_type)),
+        }
+    }
+
+    /// Add a parsed block to `doc` according to the duplicate policy
+    pub(crate) fn add_block(&self, doc: &mut Document, block: Block, line: usize) -> Result<()> {
+        let existing = doc
+            .blocks
+            .iter()
+            .position(|b| b.kind == block.kind && b.name == block.name);
+        let Some(idx) = existing else {
+            doc.blocks.push(block);
+            return Ok(());
+        };
+
+        match self.duplicate_blocks {
+            DuplicateBlockPolicy::KeepAll => doc.blocks.push(block),
+            DuplicateBlockPolicy::Error => {
+                return Err(ISONError {
+                    message: format!("Duplicate block: {}.{}", block.kind, block.name),
+                    line: Some(line),
+                })
+            }
+            DuplicateBlockPolicy::KeepFirst => {}
+            DuplicateBlockPolicy::KeepLast => doc.blocks[idx] = block,
+            DuplicateBlockPolicy::MergeRows => merge_rows(&mut doc.blocks[idx], block),
+        }
+        Ok(())
+    }
+}
+
+fn merge_rows(target: &mut Block, source: Block) {
+    for info in source.field_info {
+        if !target.fields.contains(&info.name) {
+            target.fields.push(info.name.clone());
+            target.field_info.push(info);
+        }
+    }
+    // Re-key rows onto the target's shared field names
+    let keys = target.field_keys();
+    let rekey = |row: Row| -> Row {
+        row.into_iter()
+            .map(|(k, v)| (intern(&keys, &k), v))
+            .collect()
+    };
+    target.number_text.append(
+        source.number_text,
+        target.rows.len(),
+        target.summary_rows.len(),
+    );
+    target.rows.extend(source.rows.into_iter().map(rekey));
+    target
+        .summary_rows
+        .extend(source.summary_rows.into_iter().map(rekey));
+}
+
+impl Document {
+    /// All blocks with this name, in document order
+    pub fn get_all(&self, name: &str) -> Vec<&Block> {
+        self.blocks.iter().filter(|b| b.name == name).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dumps, dumps_with_options, parse, parse_with_options, Reference};
+
+    const DOC: &str = "table.users\nid name\n1 Alice\n\n\
+                       table.orders\nid\n9\n\n\
+                       table.users\nid email\n2 b@x.com";
+
+    fn with(policy: DuplicateBlockPolicy) -> Result<Document> {
+        parse_with_options(
+            DOC,
+            &ParseOptions {
+                duplicate_blocks: policy,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_duplicate_policies() {
+        let doc = parse(DOC).unwrap();
+        assert_eq!(doc.len(), 3);
+        assert_eq!(doc.get_all("users").len(), 2);
+
+        assert_eq!(with(DuplicateBlockPolicy::Error).unwrap_err().line, Some(9));
+
+        let first = with(DuplicateBlockPolicy::KeepFirst).unwrap();
+        assert_eq!(first.len(), 2);
+        assert_eq!(first["users"].fields, vec!["id", "name"]);
+
+        let last = with(DuplicateBlockPolicy::KeepLast).unwrap();
+        assert_eq!(last.blocks[0].fields, vec!["id", "email"]);
+        assert_eq!(last.blocks[1].name, "orders");
+
+        let merged = with(DuplicateBlockPolicy::MergeRows).unwrap();
+        let users = &merged["users"];
+        assert_eq!(users.fields, vec!["id", "name", "email"]);
+        assert_eq!(users.len(), 2);
+        assert_eq!(users[1]["id"], Value::Int(2));
+        assert!(!users[1].contains_key("name"));
+    }
+
+    #[test]
+    fn test_non_finite_policies() {
+        let text = "table.t\nx\nnan\ninf\n-inf\n1e999\nNaN\nInfinity";
+        let doc = parse(text).unwrap();
+        let t = &doc["t"];
+        assert!(t[0]["x"].as_float().unwrap().is_nan());
+        assert_eq!(t[1]["x"], Value::Float(f64::INFINITY));
+        assert_eq!(t[2]["x"], Value::Float(f64::NEG_INFINITY));
+        assert_eq!(t[3]["x"], Value::Float(f64::INFINITY));
+        assert_eq!(t[4]["x"], Value::String("NaN".into()));
+        assert_eq!(t[5]["x"], Value::String("Infinity".into()));
+        assert!(dumps(&doc, false).starts_with("table.t\nx\nnan\ninf\n-inf\ninf\n"));
+
+        let options = |non_finite| ParseOptions {
+            non_finite,
+            ..Default::default()
+        };
+        let nulls = parse_with_options(text, &options(NonFinitePolicy::Null)).unwrap();
+        assert!(nulls["t"].rows[..4].iter().all(|r| r["x"].is_null()));
+        let err = parse_with_options(text, &options(NonFinitePolicy::Reject)).unwrap_err();
+        assert_eq!(err.message, "Non-finite number: nan");
+
+        let dump = |non_finite| {
+            dumps_with_options(
+                &doc,
+                &DumpOptions {
+                    non_finite,
+                    ..Default::default()
+                },
+            )
+        };
+        assert!(dump(NonFinitePolicy::Null)
+            .unwrap()
+            .starts_with("table.t\nx\nnull\nnull\nnull\nnull\n"));
+        assert_eq!(
+            dump(NonFinitePolicy::Reject).unwrap_err().message,
+            "Non-finite float NaN in t.x"
+        );
+    }
+
+    #[test]
+    fn test_field_aliases() {
+        let options = ParseOptions {
+            field_aliases: [
+                ("e-mail", "email"),
+                ("user id", "user_id"),
+                ("Host", "host"),
+            ]
+            .into_iter()
+            .map(|(from, to)| (from.to_string(), to.to_string()))
+            .collect(),
+            key_value_objects: true,
+            ..Default::default()
+        };
+        let doc = parse_with_options(
+            "table.users\n\"user id\" e-mail:string name\n1 a@x.com Alice\n\n\
+             object.config\nkey value\nHost:string db\nport 5432",
+            &options,
+        )
+        .unwrap();
+        let users = &doc["users"];
+        assert_eq!(users.fields, vec!["user_id", "email", "name"]);
+        assert_eq!(users.get_field_type("email"), Some("string"));
+        assert_eq!(users[0]["email"], Value::String("a@x.com".into()));
+        let config = doc.get_object("config").unwrap();
+        assert_eq!(config["host"], Value::String("db".into()));
+        assert_eq!(doc["config"].get_field_type("host"), Some("string"));
+
+        let plain = parse("table.t\ne-mail\nx").unwrap();
+        assert_eq!(plain["t"].fields, vec!["e-mail"]);
+    }
+
+    #[test]
+    fn test_include_fields() {
+        let mut options = ParseOptions {
+            key_value_objects: true,
+            ..Default::default()
+        };
+        options
+            .include_fields
+            .insert("users".into(), vec!["email".into(), "id".into()]);
+        options
+            .include_fields
+            .insert("config".into(), vec!["port".into()]);
+        options
+            .field_aliases
+            .insert("e-mail".into(), "email".into());
+        // Skipped cells are not parsed, so a bad reference there is no error
+        let doc = parse_with_options(
+            "table.users\nid name e-mail:string score\n1 Alice a@x.com :a:b:c\n2 Bob\n\n\
+             table.orders\nid total\n9 2.5\n\n\
+             object.config\nkey value\nhost db\nport 5432",
+            &options,
+        )
+        .unwrap();
+        let users = &doc["users"];
+        assert_eq!(users.fields, vec!["id", "email"]);
+        assert_eq!(users.get_field_type("email"), Some("string"));
+        assert_eq!(users[0].len(), 2);
+        assert_eq!(users[0]["email"], Value::String("a@x.com".into()));
+        assert_eq!(users[1].len(), 1);
+        assert_eq!(doc["orders"].fields, vec!["id", "total"]);
+        assert_eq!(doc["config"].fields, vec!["port"]);
+        assert_eq!(doc.get_object("config").unwrap()["port"], Value::Int(5432));
+
+        options.include_fields.insert("t".into(), Vec::new());
+        let empty = parse_with_options("table.t\nx y\n1 2", &options).unwrap();
+        assert!(empty["t"].fields.is_empty());
+        assert!(empty["t"][0].is_empty());
+    }
+
+    #[test]
+    fn test_row_filter() {
+        let options = ParseOptions {
+            lossless_numbers: true,
+            ..Default::default()
+        }
+        .row_filter(|block, row| match block {
+            "orders" => row["total"].as_float().is_some_and(|t| t >= 10.0),
+            "config" => false,
+            _ => true,
+        });
+        let doc = parse_with_options(
+            "table.orders\nid total\n1 5.0\n2 12.50\n3 7\n4 10.0\n---\nsum 34.5\n\n\
+             table.users\nid\n1\n\nobject.config\nkey value\nmode fast",
+            &options,
+        )
+        .unwrap();
+        let orders = &doc["orders"];
+        assert_eq!(orders.len(), 2);
+        assert_eq!(orders[0]["id"], Value::Int(2));
+        assert_eq!(orders.number_text.get(0, "total"), Some("12.50"));
+        assert_eq!(orders.number_text.get(1, "total"), None);
+        assert_eq!(orders.summary_rows.len(), 1);
+        assert_eq!(doc["users"].len(), 1);
+        assert!(doc.get_object("config").is_none());
+        assert!(format!("{:?}", options).contains("row_filter: Some"));
+    }
+
+    #[test]
+    fn test_dialects() {
+        let text = "table.t\nid note value ref\n1 \"a # b\" 1e6 :a:b:c\n\
+                    2 #tag ~ :\n3 x +5 :user:7 # trailing\n4 y nan\n5 z b64:AAE=";
+        let with = |dialect| parse_with_options(text, &ParseOptions::default().dialect(dialect));
+
+        let err = with(Dialect::V1).unwrap_err();
+        assert_eq!(
+            (err.message.as_str(), err.line),
+            ("Invalid reference: :a:b:c", Some(3))
+        );
+        let v1 =
+            parse_with_options(&text.replace(":a:b:c", ":a:b"), &ParseOptions::default()).unwrap();
+        let t = &v1["t"];
+        assert_eq!(t[0]["value"], Value::Float(1e6));
+        assert!(!t[1].contains_key("note"));
+        assert_eq!(t[2]["value"], Value::Int(5));
+        assert_eq!(
+            t[2]["ref"],
+            Value::Reference(Reference::with_type("7", "user"))
+        );
+        assert!(t[3]["value"].as_float().unwrap().is_nan());
+        assert!(t[4]["value"].is_bytes());
+
+        let python = with(Dialect::PythonCompat).unwrap();
+        let t = &python["t"];
+        assert_eq!(t[0]["note"], Value::String("a # b".into()));
+        assert_eq!(t[0]["value"], Value::String("1e6".into()));
+        assert_eq!(
+            t[0]["ref"],
+            Value::Reference(Reference::with_type("b:c", "a"))
+        );
+        assert_eq!(t[1]["note"], Value::String("#tag".into()));
+        assert_eq!(t[1]["value"], Value::String("~".into()));
+        assert_eq!(t[1]["ref"], Value::String(":".into()));
+        assert_eq!(t[2]["value"], Value::String("+5".into()));
+        assert_eq!(t[3]["value"], Value::String("nan".into()));
+        assert_eq!(t[3]["ref"], Value::Null);
+        assert_eq!(t[4]["value"], Value::String("b64:AAE=".into()));
+
+        let strict = |text: &str| {
+            let err = parse_with_options(text, &ParseOptions::default().dialect(Dialect::Strict))
+                .unwrap_err();
+            (err.message, err.line)
+        };
+        let rows = |rows: &str| format!("table.t\nid value\n{}", rows);
+        assert_eq!(
+            strict(&rows("1 1e6")),
+            ("Ambiguous number: 1e6".into(), Some(3))
+        );
+        assert_eq!(
+            strict(&rows("1 x\n2 ~")),
+            ("Ambiguous null: ~".into(), Some(4))
+        );
+        assert_eq!(
+            strict(&rows("1 x # note")),
+            ("Unquoted '#': #".into(), Some(3))
+        );
+        assert_eq!(
+            strict(&rows("1 :")),
+            ("Invalid reference: :".into(), Some(3))
+        );
+        for dialect in [Dialect::PythonCompat, Dialect::Strict] {
+            let options = ParseOptions::default().dialect(dialect);
+            let err = parse_with_options(&rows("1 \"open"), &options).unwrap_err();
+            assert_eq!(err.message, "Unterminated quoted string");
+            let err = parse_with_options(&rows("1 \"open\\"), &options).unwrap_err();
+            assert_eq!(err.message, "Unexpected end of line after backslash");
+        }
+
+        // What every dialect reads alike, including this crate's own output
+        let portable = "table.t\nid value note\n1 -2.5 \"# x\"\n2 null \"~\"\n3 :user:7 \"1e6\"";
+        let doc = parse(portable).unwrap();
+        assert_eq!(dumps(&doc, false), portable);
+        for dialect in [Dialect::PythonCompat, Dialect::Strict] {
+            let options = ParseOptions::default().dialect(dialect);
+            assert_eq!(
+                parse_with_options(portable, &options).unwrap()["t"].rows,
+                doc["t"].rows
+            );
+        }
+    }
+
+    #[test]
+    fn test_infer_types() {
+        let doc = parse(
+            "table.users\nid name score:computed photo\n1 Alice 2 b64:AAE=\n2 ~ 3.5 b64:/w==",
+        )
+        .unwrap();
+        let options = DumpOptions {
+            infer_types: true,
+            ..Default::default()
+        };
+        let text = dumps_with_options(&doc, &options).unwrap();
+        assert!(text.starts_with("table.users\nid:int name:string? score:computed photo:bytes\n"));
+        assert_eq!(parse(&text).unwrap()["users"].rows, doc["users"].rows);
+        assert!(dumps(&doc, false).starts_with("table.users\nid name score:computed photo\n"));
+    }
+}
@@ -1,6 +1,7 @@
 //! # ISON Plugins
 //!
-//! Export data from databases and vector stores to ISON format.
+//! Export data from databases and vector stores to ISON format, and import
+//! it back.
 //!
 //! ## Available Plugins
 //!
@@ -15,8 +16,12 @@
 //! let ison = exporter.export_all()?;
 //! ```
 
-#[cfg(feature = "rudradb")]
+// Built for tests against `rudradb_mock` until the crate is published
+#[cfg(any(feature = "rudradb", all(test, feature = "serde")))]
 mod rudradb_plugin;
 
+#[cfg(all(test, feature = "serde", not(feature = "rudradb")))]
+mod rudradb_mock;
+
 #[cfg(feature = "rudradb")]
 pub use rudradb_plugin::*;
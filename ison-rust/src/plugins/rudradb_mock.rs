@@ -0,0 +1,215 @@
+//! In-memory stand-in for the parts of the `rudradb` API the plugin uses,
+//! so its tests run until the crate is published
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use nalgebra::DVector;
+
+pub type Metadata = HashMap<String, serde_json::Value>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelationshipType(String);
+
+impl RelationshipType {
+    pub fn semantic() -> Self {
+        Self("semantic".to_string())
+    }
+
+    pub fn hierarchical() -> Self {
+        Self("hierarchical".to_string())
+    }
+}
+
+impl fmt::Display for RelationshipType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for RelationshipType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "semantic" | "hierarchical" | "temporal" | "causal" | "associative" => {
+                Ok(Self(s.to_string()))
+            }
+            _ => Err(format!("no relationship type '{}'", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Vector {
+    pub id: String,
+    pub embedding: DVector<f32>,
+    pub metadata: Metadata,
+}
+
+#[derive(Debug, Clone)]
+pub struct Relationship {
+    pub source_id: String,
+    pub target_id: String,
+    pub relationship_type: RelationshipType,
+    pub strength: f32,
+}
+
+// The mock ranks by similarity only, without following relationships
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default)]
+pub struct SearchParams {
+    pub top_k: Option<usize>,
+    pub include_relationships: Option<bool>,
+    pub max_hops: Option<usize>,
+    pub similarity_threshold: Option<f32>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ResultSource {
+    Similarity,
+}
+
+#[derive(Debug, Clone)]
+pub struct VectorSearchResult {
+    pub vector: Vector,
+    pub combined_score: f32,
+    pub source: ResultSource,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchResult {
+    pub results: Vec<VectorSearchResult>,
+}
+
+// Stored vectors are never normalized by the mock
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default)]
+pub struct RudraDBConfig {
+    auto_normalize: bool,
+}
+
+impl RudraDBConfig {
+    pub fn set_auto_normalize(mut self, auto_normalize: bool) -> Self {
+        self.auto_normalize = auto_normalize;
+        self
+    }
+}
+
+#[derive(Default)]
+struct Store {
+    /// In insertion order
+    vectors: Vec<Vector>,
+    relationships: Vec<Relationship>,
+}
+
+#[derive(Default)]
+pub struct RudraDB {
+    store: Mutex<Store>,
+}
+
+impl RudraDB {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_config(_config: RudraDBConfig) -> Self {
+        Self::default()
+    }
+
+    fn store(&self) -> std::sync::MutexGuard<'_, Store> {
+        self.store.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn add_vector(
+        &self,
+        id: &str,
+        embedding: DVector<f32>,
+        metadata: Option<Metadata>,
+    ) -> Result<(), String> {
+        let mut store = self.store();
+        if store.vectors.iter().any(|v| v.id == id) {
+            return Err(format!("vector '{}' already exists", id));
+        }
+        store.vectors.push(Vector {
+            id: id.to_string(),
+            embedding,
+            metadata: metadata.unwrap_or_default(),
+        });
+        Ok(())
+    }
+
+    pub fn add_relationship(
+        &self,
+        source_id: &str,
+        target_id: &str,
+        relationship_type: RelationshipType,
+        strength: f32,
+        _metadata: Option<Metadata>,
+    ) -> Result<(), String> {
+        let mut store = self.store();
+        for id in [source_id, target_id] {
+            if !store.vectors.iter().any(|v| v.id == id) {
+                return Err(format!("no vector '{}'", id));
+            }
+        }
+        store.relationships.push(Relationship {
+            source_id: source_id.to_string(),
+            target_id: target_id.to_string(),
+            relationship_type,
+            strength,
+        });
+        Ok(())
+    }
+
+    pub fn list_vectors(&self) -> Vec<String> {
+        self.store().vectors.iter().map(|v| v.id.clone()).collect()
+    }
+
+    pub fn get_vector(&self, id: &str) -> Result<Option<Vector>, String> {
+        Ok(self.store().vectors.iter().find(|v| v.id == id).cloned())
+    }
+
+    pub fn get_relationships(
+        &self,
+        source_id: &str,
+        relationship_type: Option<RelationshipType>,
+    ) -> Result<Vec<Relationship>, String> {
+        Ok(self
+            .store()
+            .relationships
+            .iter()
+            .filter(|r| r.source_id == source_id)
+            .filter(|r| {
+                relationship_type
+                    .as_ref()
+                    .is_none_or(|t| *t == r.relationship_type)
+            })
+            .cloned()
+            .collect())
+    }
+
+    /// Vectors by dot product with `query`, best first
+    pub fn search(
+        &self,
+        query: &DVector<f32>,
+        params: SearchParams,
+    ) -> Result<SearchResult, String> {
+        let mut results: Vec<VectorSearchResult> = self
+            .store()
+            .vectors
+            .iter()
+            .filter(|v| v.embedding.len() == query.len())
+            .map(|v| VectorSearchResult {
+                combined_score: v.embedding.dot(query),
+                vector: v.clone(),
+                source: ResultSource::Similarity,
+            })
+            .collect();
+        results.sort_by(|a, b| b.combined_score.total_cmp(&a.combined_score));
+        results.truncate(params.top_k.unwrap_or(10));
+        Ok(SearchResult { results })
+    }
+}
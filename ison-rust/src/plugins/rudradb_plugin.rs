@@ -1,6 +1,7 @@
 //! # ISON RudraDB Plugin
 //!
-//! Export RudraDB data to ISON format for LLM-friendly serialization.
+//! Export RudraDB data to ISON format for LLM-friendly serialization, and
+//! load it back.
 //! RudraDB is a high-performance Rust-based relationship-aware vector database.
 //!
 //! ## Features
@@ -11,6 +12,7 @@
 //! - Relationship type preservation
 //! - Streaming export for large datasets (ISONL)
 //! - RAG-optimized export with rank/score
+//! - Import of exported vectors and relationships into a RudraDB instance
 //!
 //! ## Usage
 //!
@@ -24,25 +26,31 @@
 //! let exporter = RudraDBToISON::new(&db);
 //! let ison = exporter.export_all()?;
 //! println!("{}", ison);
+//!
+//! // ... and back into another instance
+//! let copy = RudraDB::new();
+//! let stats = ISONToRudraDB::new(&copy).import_str(&ison)?;
 //! ```
 
 use std::collections::HashMap;
 
+#[cfg(not(feature = "rudradb"))]
+use super::rudradb_mock as rudradb;
 use rudradb::{RudraDB, RelationshipType, SearchParams, SearchResult, VectorSearchResult};
 
-use crate::{Block, Document, FieldInfo, Reference, Row, Value, dumps, ISONError, Result};
+use crate::embedding::{format_embedding, parse_embedding};
+use crate::{Block, Document, FieldInfo, Reference, Row, Value, dumps, parse, ISONError, Result};
 
 /// Configuration for RudraDB export
 #[derive(Debug, Clone)]
 pub struct ExportConfig {
-    /// Include vector embeddings in export (can be large)
+    /// Include vector embeddings in export (can be large), written in full
+    /// so [`ISONToRudraDB`] can load them back
     pub include_vectors: bool,
     /// Include relationship data
     pub include_relationships: bool,
     /// Maximum number of records per collection
     pub limit: Option<usize>,
-    /// Number of decimal places for float values
-    pub float_precision: usize,
     /// Align columns in output
     pub align_columns: bool,
 }
@@ -53,7 +61,6 @@ impl Default for ExportConfig {
             include_vectors: false,
             include_relationships: true,
             limit: None,
-            float_precision: 4,
             align_columns: true,
         }
     }
@@ -167,7 +174,7 @@ impl<'a> RudraDBToISON<'a> {
     /// # Arguments
     ///
     /// * `vector_ids` - Optional list of specific vector IDs to export.
-    ///                  If None, exports all vectors.
+    ///   If None, exports all vectors.
     ///
     /// # Returns
     ///
@@ -406,19 +413,19 @@ impl<'a> RudraDBToISON<'a> {
 
             if let Ok(Some(vector)) = self.db.get_vector(id) {
                 let mut row = Row::new();
-                row.insert("id".to_string(), Value::String(vector.id.clone()));
-                row.insert("dimension".to_string(), Value::Int(vector.embedding.len() as i64));
+                row.insert("id".into(), Value::String(vector.id.clone()));
+                row.insert("dimension".into(), Value::Int(vector.embedding.len() as i64));
 
                 if self.config.include_vectors {
-                    let embedding_str = self.format_embedding_f32(&vector.embedding);
-                    row.insert("embedding".to_string(), Value::String(embedding_str));
+                    let embedding_str = format_embedding(vector.embedding.as_slice());
+                    row.insert("embedding".into(), Value::String(embedding_str));
                 }
 
                 let metadata_str = self.format_metadata(&vector.metadata);
                 if !metadata_str.is_empty() {
-                    row.insert("metadata".to_string(), Value::String(metadata_str));
+                    row.insert("metadata".into(), Value::String(metadata_str));
                 } else {
-                    row.insert("metadata".to_string(), Value::Null);
+                    row.insert("metadata".into(), Value::Null);
                 }
 
                 block.rows.push(row);
@@ -454,10 +461,10 @@ impl<'a> RudraDBToISON<'a> {
             if let Ok(relationships) = self.db.get_relationships(source_id, filter_type.clone()) {
                 for rel in relationships {
                     let mut row = Row::new();
-                    row.insert("source".to_string(), Value::Reference(Reference::new(&rel.source_id)));
-                    row.insert("target".to_string(), Value::Reference(Reference::new(&rel.target_id)));
-                    row.insert("type".to_string(), Value::String(rel.relationship_type.to_string()));
-                    row.insert("strength".to_string(), Value::Float(rel.strength as f64));
+                    row.insert("source".into(), Value::Reference(Reference::new(&rel.source_id)));
+                    row.insert("target".into(), Value::Reference(Reference::new(&rel.target_id)));
+                    row.insert("type".into(), Value::String(rel.relationship_type.to_string()));
+                    row.insert("strength".into(), Value::Float(rel.strength as f64));
 
                     block.rows.push(row);
                 }
@@ -485,10 +492,10 @@ impl<'a> RudraDBToISON<'a> {
 
         for (i, result) in search_result.results.iter().enumerate() {
             let mut row = Row::new();
-            row.insert("rank".to_string(), Value::Int((i + 1) as i64));
-            row.insert("id".to_string(), Value::String(result.vector.id.clone()));
-            row.insert("score".to_string(), Value::Float(result.combined_score as f64));
-            row.insert("source".to_string(), Value::String(format!("{:?}", result.source)));
+            row.insert("rank".into(), Value::Int((i + 1) as i64));
+            row.insert("id".into(), Value::String(result.vector.id.clone()));
+            row.insert("score".into(), Value::Float(result.combined_score as f64));
+            row.insert("source".into(), Value::String(format!("{:?}", result.source)));
 
             block.rows.push(row);
         }
@@ -517,16 +524,16 @@ impl<'a> RudraDBToISON<'a> {
 
         for (i, result) in results.iter().enumerate() {
             let mut row = Row::new();
-            row.insert("rank".to_string(), Value::Int((i + 1) as i64));
-            row.insert("score".to_string(), Value::Float(result.combined_score as f64));
-            row.insert("id".to_string(), Value::String(result.vector.id.clone()));
+            row.insert("rank".into(), Value::Int((i + 1) as i64));
+            row.insert("score".into(), Value::Float(result.combined_score as f64));
+            row.insert("id".into(), Value::String(result.vector.id.clone()));
 
             if include_metadata {
                 let metadata_str = self.format_metadata(&result.vector.metadata);
                 if !metadata_str.is_empty() {
-                    row.insert("metadata".to_string(), Value::String(metadata_str));
+                    row.insert("metadata".into(), Value::String(metadata_str));
                 } else {
-                    row.insert("metadata".to_string(), Value::Null);
+                    row.insert("metadata".into(), Value::Null);
                 }
             }
 
@@ -561,14 +568,14 @@ impl<'a> RudraDBToISON<'a> {
 
             if let Ok(Some(vector)) = self.db.get_vector(id) {
                 let mut row = Row::new();
-                row.insert("id".to_string(), Value::String(vector.id.clone()));
-                row.insert("dimension".to_string(), Value::Int(vector.embedding.len() as i64));
+                row.insert("id".into(), Value::String(vector.id.clone()));
+                row.insert("dimension".into(), Value::Int(vector.embedding.len() as i64));
 
                 let metadata_str = self.format_metadata(&vector.metadata);
                 if !metadata_str.is_empty() {
-                    row.insert("metadata".to_string(), Value::String(metadata_str));
+                    row.insert("metadata".into(), Value::String(metadata_str));
                 } else {
-                    row.insert("metadata".to_string(), Value::Null);
+                    row.insert("metadata".into(), Value::Null);
                 }
 
                 // Get related vectors
@@ -578,9 +585,9 @@ impl<'a> RudraDBToISON<'a> {
                         .map(|r| format!(":{}", r))
                         .collect::<Vec<_>>()
                         .join(", ");
-                    row.insert("related_to".to_string(), Value::String(refs_str));
+                    row.insert("related_to".into(), Value::String(refs_str));
                 } else {
-                    row.insert("related_to".to_string(), Value::Null);
+                    row.insert("related_to".into(), Value::Null);
                 }
 
                 block.rows.push(row);
@@ -607,7 +614,7 @@ impl<'a> RudraDBToISON<'a> {
                 ];
 
                 if self.config.include_vectors {
-                    let embedding_str = self.format_embedding_f32(&vector.embedding);
+                    let embedding_str = format_embedding(vector.embedding.as_slice());
                     values.push(self.format_isonl_value(&embedding_str));
                 }
 
@@ -650,17 +657,6 @@ impl<'a> RudraDBToISON<'a> {
         related
     }
 
-    fn format_embedding_f32(&self, embedding: &nalgebra::DVector<f32>) -> String {
-        if embedding.len() > 10 {
-            format!("[{}d vector]", embedding.len())
-        } else {
-            let values: Vec<String> = embedding.iter()
-                .map(|v| format!("{:.prec$}", v, prec = self.config.float_precision))
-                .collect();
-            format!("[{}]", values.join(", "))
-        }
-    }
-
     fn format_metadata(&self, metadata: &HashMap<String, serde_json::Value>) -> String {
         if metadata.is_empty() {
             return String::new();
@@ -766,6 +762,209 @@ pub fn rudradb_rag_context(db: &RudraDB, query_vector: &[f32], limit: usize) ->
     RudraDBToISON::new(db).export_for_rag(query_vector, rag_config)
 }
 
+// =============================================================================
+// Import
+// =============================================================================
+
+/// Counts of what an import added to RudraDB
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportStats {
+    /// Vectors added from `table.vectors`
+    pub vectors: usize,
+    /// Relationships added from `table.relationships`
+    pub relationships: usize,
+}
+
+/// Import ISON data into RudraDB.
+///
+/// The inverse of [`RudraDBToISON`]: reads the `table.vectors` and
+/// `table.relationships` blocks it writes and adds their rows to a RudraDB
+/// instance. Vectors are added first, so relationships may point at them.
+///
+/// Each vector row needs its embedding as a bracketed list of floats, as
+/// written with [`ExportConfig::include_vectors`]. Placeholders such as
+/// `[384d vector]` hold no values and are rejected. The `metadata` cell is
+/// read back from the exporter's `key: value, ...` form, with numbers,
+/// booleans and `null` restored to their JSON types.
+pub struct ISONToRudraDB<'a> {
+    db: &'a RudraDB,
+}
+
+impl<'a> ISONToRudraDB<'a> {
+    /// Create a new importer.
+    ///
+    /// # Arguments
+    ///
+    /// * `db` - Reference to the RudraDB instance to populate
+    pub fn new(db: &'a RudraDB) -> Self {
+        Self { db }
+    }
+
+    /// Parse ISON text and import it.
+    ///
+    /// # Returns
+    ///
+    /// Counts of the imported vectors and relationships.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let db = RudraDB::new();
+    /// let stats = ISONToRudraDB::new(&db).import_str(&ison)?;
+    /// println!("{} vectors, {} relationships", stats.vectors, stats.relationships);
+    /// ```
+    pub fn import_str(&self, text: &str) -> Result<ImportStats> {
+        self.import_document(&parse(text)?)
+    }
+
+    /// Import the vectors and relationships of a parsed document.
+    ///
+    /// Either block may be missing; other blocks are ignored.
+    pub fn import_document(&self, doc: &Document) -> Result<ImportStats> {
+        let mut stats = ImportStats::default();
+
+        if let Some(block) = doc.get_kind_name("table", "vectors") {
+            stats.vectors = self.import_vectors(block)?;
+        }
+        if let Some(block) = doc.get_kind_name("table", "relationships") {
+            stats.relationships = self.import_relationships(block)?;
+        }
+
+        Ok(stats)
+    }
+
+    /// Add every row of a `table.vectors` block as a vector.
+    ///
+    /// # Returns
+    ///
+    /// Number of vectors added.
+    pub fn import_vectors(&self, block: &Block) -> Result<usize> {
+        for (i, row) in block.rows.iter().enumerate() {
+            let id = row_id(row, "id")
+                .ok_or_else(|| import_error(block, i, "has no id"))?;
+
+            let embedding = row.get("embedding")
+                .and_then(Value::as_str)
+                .and_then(parse_embedding)
+                .ok_or_else(|| import_error(block, i, "has no embedding array"))?;
+
+            if let Some(dimension) = row.get("dimension").and_then(Value::as_int) {
+                if dimension != embedding.len() as i64 {
+                    return Err(import_error(
+                        block,
+                        i,
+                        &format!("has dimension {} but {} values", dimension, embedding.len()),
+                    ));
+                }
+            }
+
+            let metadata = row.get("metadata")
+                .and_then(Value::as_str)
+                .map(parse_metadata)
+                .filter(|m| !m.is_empty());
+
+            self.db.add_vector(&id, nalgebra::DVector::from_vec(embedding), metadata)
+                .map_err(|e| ISONError {
+                    message: format!("RudraDB add_vector failed for '{}': {}", id, e),
+                    line: None,
+                })?;
+        }
+
+        Ok(block.rows.len())
+    }
+
+    /// Add every row of a `table.relationships` block as a relationship.
+    ///
+    /// A missing `strength` defaults to 1.0.
+    ///
+    /// # Returns
+    ///
+    /// Number of relationships added.
+    pub fn import_relationships(&self, block: &Block) -> Result<usize> {
+        for (i, row) in block.rows.iter().enumerate() {
+            let source = row_id(row, "source")
+                .ok_or_else(|| import_error(block, i, "has no source"))?;
+            let target = row_id(row, "target")
+                .ok_or_else(|| import_error(block, i, "has no target"))?;
+
+            let type_name = row.get("type")
+                .and_then(Value::as_str)
+                .ok_or_else(|| import_error(block, i, "has no relationship type"))?;
+            let relationship_type: RelationshipType = type_name.parse()
+                .map_err(|e| import_error(block, i, &format!("has unknown type '{}': {}", type_name, e)))?;
+
+            let strength = row.get("strength")
+                .and_then(Value::as_float)
+                .unwrap_or(1.0) as f32;
+
+            self.db.add_relationship(&source, &target, relationship_type, strength, None)
+                .map_err(|e| ISONError {
+                    message: format!(
+                        "RudraDB add_relationship failed for '{}' -> '{}': {}",
+                        source, target, e
+                    ),
+                    line: None,
+                })?;
+        }
+
+        Ok(block.rows.len())
+    }
+}
+
+/// The id in a row's `field` cell, written as a reference, string or number
+fn row_id(row: &Row, field: &str) -> Option<String> {
+    match row.get(field)? {
+        Value::Reference(r) => Some(r.id.clone()),
+        Value::String(s) => Some(s.clone()),
+        Value::Int(i) => Some(i.to_string()),
+        _ => None,
+    }
+}
+
+fn import_error(block: &Block, row: usize, problem: &str) -> ISONError {
+    ISONError {
+        message: format!("Row {} of {}.{} {}", row, block.kind, block.name, problem),
+        line: None,
+    }
+}
+
+/// Metadata from the exporter's `key: value, ...` form
+fn parse_metadata(text: &str) -> HashMap<String, serde_json::Value> {
+    text.split(", ")
+        .filter_map(|pair| pair.split_once(": "))
+        .map(|(key, value)| {
+            let value = match serde_json::from_str(value) {
+                Ok(v @ (serde_json::Value::Number(_)
+                | serde_json::Value::Bool(_)
+                | serde_json::Value::Null)) => v,
+                _ => serde_json::Value::String(value.to_string()),
+            };
+            (key.to_string(), value)
+        })
+        .collect()
+}
+
+/// Quick function to load ISON text into RudraDB.
+///
+/// # Arguments
+///
+/// * `db` - RudraDB instance to populate
+/// * `text` - ISON with `table.vectors` and/or `table.relationships` blocks
+///
+/// # Returns
+///
+/// Counts of the imported vectors and relationships.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let db = RudraDB::new();
+/// let stats = ison_to_rudradb(&db, &ison)?;
+/// ```
+pub fn ison_to_rudradb(db: &RudraDB, text: &str) -> Result<ImportStats> {
+    ISONToRudraDB::new(db).import_str(text)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -845,8 +1044,7 @@ mod tests {
         let ison = exporter.export_vectors(None).unwrap();
 
         assert!(ison.contains("embedding"));
-        // With 3 dimensions (< 10), actual values are shown
-        assert!(ison.contains("[1.0000, 2.0000, 3.0000]") || ison.contains("["));
+        assert!(ison.contains("\"[1, 2, 3]\""));
     }
 
     #[test]
@@ -858,4 +1056,107 @@ mod tests {
         assert!(ison.contains("table.vectors"));
         assert!(ison.contains("table.relationships"));
     }
+
+    #[test]
+    fn test_import_round_trip() {
+        let db = create_test_db();
+        let config = ExportConfig {
+            include_vectors: true,
+            ..Default::default()
+        };
+        let ison = RudraDBToISON::with_config(&db, config).export_all().unwrap();
+
+        let copy = RudraDB::with_config(RudraDBConfig::default().set_auto_normalize(false));
+        let stats = ison_to_rudradb(&copy, &ison).unwrap();
+        assert_eq!(stats, ImportStats { vectors: 3, relationships: 2 });
+
+        let doc1 = copy.get_vector("doc1").unwrap().unwrap();
+        assert_eq!(doc1.embedding.as_slice(), &[1.0f32, 2.0, 3.0]);
+        assert_eq!(
+            doc1.metadata.get("category"),
+            Some(&serde_json::Value::String("tech".to_string()))
+        );
+
+        let relationships = copy.get_relationships("doc1", None).unwrap();
+        assert_eq!(relationships.len(), 1);
+        assert_eq!(relationships[0].target_id, "doc2");
+        assert_eq!(relationships[0].relationship_type, RelationshipType::semantic());
+    }
+
+    #[test]
+    fn test_import_full_size_embeddings() {
+        let db = RudraDB::new();
+        let embedding: Vec<f32> = (0..384).map(|i| i as f32 / 7.0).collect();
+        db.add_vector("big", DVector::from_vec(embedding.clone()), None).unwrap();
+        let config = ExportConfig {
+            include_vectors: true,
+            ..Default::default()
+        };
+        let ison = RudraDBToISON::with_config(&db, config).export_all().unwrap();
+        assert!(!ison.contains("384d vector"));
+
+        let copy = RudraDB::new();
+        ison_to_rudradb(&copy, &ison).unwrap();
+        let big = copy.get_vector("big").unwrap().unwrap();
+        assert_eq!(big.embedding.as_slice(), embedding.as_slice());
+    }
+
+    #[test]
+    fn test_export_search_and_rag() {
+        let db = create_test_db();
+        let exporter = RudraDBToISON::new(&db);
+
+        let results = db
+            .search(&DVector::from_vec(vec![1.0f32, 0.0, 0.0]), SearchParams::default())
+            .unwrap();
+        let ison = rudradb_search_to_ison(&db, &results, Some("hits")).unwrap();
+        assert!(ison.starts_with("table.hits\nrank:int id score:float source"));
+        assert!(exporter.export_search_results(&results, None).unwrap().starts_with("table.search_results"));
+
+        let context = rudradb_rag_context(&db, &[0.0, 0.0, 1.0], 2).unwrap();
+        let doc = parse(&context).unwrap();
+        assert_eq!(doc["context"].rows.len(), 2);
+        assert_eq!(doc["context"][0]["id"].as_str(), Some("doc3"));
+
+        let config = RagExportConfig {
+            min_score: Some(4.5),
+            include_metadata: false,
+            ..Default::default()
+        };
+        let context = exporter.export_for_rag(&[0.0, 0.0, 1.0], config).unwrap();
+        assert_eq!(parse(&context).unwrap()["context"].rows.len(), 1);
+    }
+
+    #[test]
+    fn test_stream_and_related() {
+        let db = create_test_db();
+        let exporter = RudraDBToISON::new(&db);
+
+        let batches: Vec<String> = exporter.stream_vectors(2).map(|b| b.unwrap()).collect();
+        assert_eq!(batches.len(), 2);
+        assert!(batches[0].starts_with("table.vectors|id dimension metadata|doc1 3 "));
+
+        let ison = exporter.export_with_relationships(Some(&["doc1"]), 2).unwrap();
+        assert!(ison.contains("\":doc2, :doc3\""));
+    }
+
+    #[test]
+    fn test_import_rejects_placeholders() {
+        let db = RudraDB::new();
+        let err = ison_to_rudradb(&db, "table.vectors\nid dimension embedding\ndoc1 384 \"[384d vector]\"")
+            .unwrap_err();
+        assert!(err.message.contains("embedding"));
+
+        let err = ison_to_rudradb(&db, "table.vectors\nid dimension embedding\ndoc1 4 \"[1, 2, 3]\"")
+            .unwrap_err();
+        assert!(err.message.contains("dimension 4"));
+    }
+
+    #[test]
+    fn test_parse_metadata() {
+        let metadata = parse_metadata("category: tech, year: 2024, draft: false");
+        assert_eq!(metadata["category"], serde_json::json!("tech"));
+        assert_eq!(metadata["year"], serde_json::json!(2024));
+        assert_eq!(metadata["draft"], serde_json::json!(false));
+    }
 }
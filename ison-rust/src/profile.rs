@@ -0,0 +1,148 @@
+//! Data-quality profiles of blocks
+//!
+//! [`Block::profile`] summarizes every column in a block of its own, one row
+//! per field, so a profile can be printed, diffed, or shipped like any other
+//! ISON:
+//!
+//! ```text
+//! table.users_profile
+//! field types nulls:int distinct:int min max avg_len:float
+//! id int:3 0 3 1 3 null
+//! email string:2 1 2 "a@x.com" "b@x.com" 7.0
+//! ```
+
+use alloc::collections::{BTreeMap, BTreeSet};
+
+use crate::aggregate::Aggregate;
+use crate::prelude::*;
+use crate::{Block, Serializer, Value};
+
+impl Block {
+    /// Per-column profile as a `table.<name>_profile` block.
+    ///
+    /// For each field, in order: the value types seen with their counts
+    /// (`int:3 float:1`, most common first), nulls, distinct non-null
+    /// values, min and max (numeric columns, or lexicographic for all-string
+    /// columns), and the mean length in characters of its strings. Summary
+    /// rows are not profiled.
+    pub fn profile(&self) -> Block {
+        let serializer = Serializer::new(false);
+        let mut profile = Block::new("table", format!("{}_profile", self.name));
+        for def in [
+            "field",
+            "types",
+            "nulls:int",
+            "distinct:int",
+            "min",
+            "max",
+            "avg_len:float",
+        ] {
+            profile.add_field_def(def);
+        }
+        let keys = profile.field_keys();
+
+        for field in &self.fields {
+            let values: Vec<&Value> = self
+                .rows
+                .iter()
+                .map(|row| row.get(field.as_str()).unwrap_or(&Value::Null))
+                .collect();
+
+            let mut types: BTreeMap<&str, usize> = BTreeMap::new();
+            let mut distinct = BTreeSet::new();
+            let mut strings: Vec<&str> = Vec::new();
+            for value in values.iter().filter(|v| !v.is_null()) {
+                *types.entry(value.type_name()).or_insert(0) += 1;
+                distinct.insert(serializer.serialize_value(value));
+                if let Value::String(s) = value {
+                    strings.push(s);
+                }
+            }
+            let mut types: Vec<(&str, usize)> = types.into_iter().collect();
+            types.sort_by_key(|&(_, count)| core::cmp::Reverse(count));
+            let types = types
+                .iter()
+                .map(|(name, count)| format!("{}:{}", name, count))
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            let nulls = values.len() - values.iter().filter(|v| !v.is_null()).count();
+            let (min, max) = match self.column_stats(field) {
+                Some(stats) => (stats.value(Aggregate::Min), stats.value(Aggregate::Max)),
+                None if !strings.is_empty() && strings.len() + nulls == values.len() => (
+                    Value::String(strings.iter().min().unwrap().to_string()),
+                    Value::String(strings.iter().max().unwrap().to_string()),
+                ),
+                None => (Value::Null, Value::Null),
+            };
+            let avg_len = if strings.is_empty() {
+                Value::Null
+            } else {
+                let chars: usize = strings.iter().map(|s| s.chars().count()).sum();
+                Value::Float(chars as f64 / strings.len() as f64)
+            };
+
+            let cells = [
+                Value::String(field.clone()),
+                Value::String(types),
+                Value::Int(nulls as i64),
+                Value::Int(distinct.len() as i64),
+                min,
+                max,
+                avg_len,
+            ];
+            profile.rows.push(keys.iter().cloned().zip(cells).collect());
+        }
+        profile
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{dumps, parse, Document, Value};
+
+    #[test]
+    fn test_profile() {
+        let doc = parse(
+            "table.users\nid score email note\n1 2.5 a@x.com x\n2 3 ~ 7\n3 1.5 b@x.com x\n---\ntotal 7 ~ ~",
+        )
+        .unwrap();
+        let profile = doc["users"].profile();
+        assert_eq!(profile.name, "users_profile");
+        assert_eq!(profile.len(), 4);
+
+        let id = &profile[0];
+        assert_eq!(id["types"].as_str(), Some("int:3"));
+        assert_eq!(id["min"], Value::Int(1));
+        assert_eq!(id["max"], Value::Int(3));
+        assert!(id["avg_len"].is_null());
+
+        let score = &profile[1];
+        assert_eq!(score["types"].as_str(), Some("float:2 int:1"));
+        assert_eq!(score["max"], Value::Float(3.0));
+
+        let email = &profile[2];
+        assert_eq!(email["nulls"], Value::Int(1));
+        assert_eq!(email["distinct"], Value::Int(2));
+        assert_eq!(email["min"].as_str(), Some("a@x.com"));
+        assert_eq!(email["avg_len"], Value::Float(7.0));
+
+        // Mixed columns have no min or max
+        let note = &profile[3];
+        assert_eq!(note["types"].as_str(), Some("string:2 int:1"));
+        assert!(note["min"].is_null());
+
+        // The profile is ordinary ISON
+        let text = dumps(
+            &Document {
+                blocks: vec![profile.clone()],
+            },
+            false,
+        );
+        assert!(text.starts_with(
+            "table.users_profile\nfield types nulls:int distinct:int min max avg_len:float\n\
+             id int:3 0 3 1 3 null\n"
+        ));
+        assert_eq!(parse(&text).unwrap()["users_profile"].rows, profile.rows);
+    }
+}
@@ -0,0 +1,179 @@
+//! Progress reporting and cancellation
+//!
+//! Long parses and serializations can report how far they have got through
+//! a [`ProgressCallback`] and be stopped early with a [`CancellationToken`],
+//! both set on [`ParseOptions`](crate::ParseOptions) and
+//! [`DumpOptions`](crate::DumpOptions). The token is checked between lines;
+//! a cancelled call returns an error with the message `"Cancelled"`.
+
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::prelude::*;
+use crate::{ISONError, Result};
+
+/// Rows between two progress reports
+const REPORT_EVERY: usize = 1024;
+
+/// How far a parse or serialization has got
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// Bytes of text read, or written
+    pub bytes: usize,
+    /// Data and summary rows handled
+    pub rows: usize,
+}
+
+/// Called every 1024 rows, and once more when the work is done
+pub type ProgressCallback = Arc<dyn Fn(Progress) + Send + Sync>;
+
+/// Stops a parse or serialization from another thread; clones share one flag
+///
+/// ```rust
+/// use ison_rs::{parse_with_options, CancellationToken, ParseOptions};
+///
+/// let cancel = CancellationToken::new();
+/// let options = ParseOptions { cancel: Some(cancel.clone()), ..Default::default() };
+/// cancel.cancel();
+/// let err = parse_with_options("table.t\nx\n1", &options).unwrap_err();
+/// assert_eq!(err.message, "Cancelled");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask every operation holding a clone of this token to stop
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Counts rows and bytes for one parse or serialization
+#[derive(Default)]
+pub(crate) struct Monitor {
+    progress: Option<ProgressCallback>,
+    cancel: Option<CancellationToken>,
+    pub(crate) bytes: usize,
+    rows: usize,
+}
+
+impl Monitor {
+    pub(crate) fn new(
+        progress: Option<ProgressCallback>,
+        cancel: Option<CancellationToken>,
+    ) -> Self {
+        Self {
+            progress,
+            cancel,
+            ..Default::default()
+        }
+    }
+
+    fn report(&self) {
+        if let Some(progress) = &self.progress {
+            progress(Progress {
+                bytes: self.bytes,
+                rows: self.rows,
+            });
+        }
+    }
+
+    /// Count a row, reporting progress now and then
+    pub(crate) fn row(&mut self) {
+        self.rows += 1;
+        if self.rows.is_multiple_of(REPORT_EVERY) {
+            self.report();
+        }
+    }
+
+    /// Fail if the token has been cancelled
+    pub(crate) fn check(&self, line: Option<usize>) -> Result<()> {
+        match &self.cancel {
+            Some(cancel) if cancel.is_cancelled() => Err(ISONError {
+                message: "Cancelled".to_string(),
+                line,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Send the final report
+    pub(crate) fn finish(&self) {
+        self.report();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::{dumps_with_options, parse_with_options, DumpOptions, ParseOptions};
+
+    #[test]
+    fn test_progress() {
+        let text = format!(
+            "table.t\nx\n{}---\n0\n\nobject.o\nkey value\na 1",
+            "1\n".repeat(2500)
+        );
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let options = ParseOptions::default().progress(move |p| sink.lock().unwrap().push(p));
+        let doc = parse_with_options(&text, &options).unwrap();
+        let reports: Vec<Progress> = seen.lock().unwrap().drain(..).collect();
+        assert_eq!(
+            reports.iter().map(|p| p.rows).collect::<Vec<_>>(),
+            [1024, 2048, 2502]
+        );
+        assert!(reports[0].bytes < reports[1].bytes);
+        assert_eq!(reports[2].bytes, text.len());
+
+        let sink = seen.clone();
+        let options = DumpOptions::default().progress(move |p| sink.lock().unwrap().push(p));
+        let out = dumps_with_options(&doc, &options).unwrap();
+        let reports = seen.lock().unwrap();
+        assert_eq!(reports.len(), 3);
+        assert_eq!(
+            *reports.last().unwrap(),
+            Progress {
+                bytes: out.len(),
+                rows: 2502
+            }
+        );
+    }
+
+    #[test]
+    fn test_cancel() {
+        let cancel = CancellationToken::new();
+        let text = format!("table.t\nx\n{}", "1\n".repeat(3000));
+        let parse_options = ParseOptions {
+            cancel: Some(cancel.clone()),
+            ..Default::default()
+        };
+        let doc = parse_with_options(&text, &parse_options).unwrap();
+        let dump_options = DumpOptions {
+            cancel: Some(cancel.clone()),
+            ..Default::default()
+        };
+        assert!(dumps_with_options(&doc, &dump_options).is_ok());
+
+        // Cancelled from the progress callback at row 1024, on line 1026
+        let stop = cancel.clone();
+        let options = parse_options.clone().progress(move |_| stop.cancel());
+        let err = parse_with_options(&text, &options).unwrap_err();
+        assert_eq!((err.message.as_str(), err.line), ("Cancelled", Some(1026)));
+        assert!(cancel.is_cancelled());
+        assert_eq!(
+            dumps_with_options(&doc, &dump_options).unwrap_err().message,
+            "Cancelled"
+        );
+    }
+}
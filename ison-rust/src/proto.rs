@@ -0,0 +1,303 @@
+//! Protocol Buffers messages for documents
+//!
+//! The types here are the messages of `proto/ison.proto`, written out by
+//! hand so the crate builds without `protoc`; services that generate their
+//! own code from that file read and write the same bytes.
+//! [`Document::to_proto`] and [`Document::from_proto`] convert to and from
+//! them, so ISON data can travel in a gRPC message without being encoded as
+//! text first.
+//!
+//! Row values are positional, one per field in field order, with an unset
+//! [`Value::kind`] for a cell the row does not have; cells under other keys
+//! go in [`Row::extra`].
+//!
+//! Enable with the `prost` feature.
+
+use std::collections::HashMap;
+
+use crate::{intern, ISONError, Result};
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Document {
+    #[prost(message, repeated, tag = "1")]
+    pub blocks: Vec<Block>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Block {
+    #[prost(string, tag = "1")]
+    pub kind: String,
+    #[prost(string, tag = "2")]
+    pub name: String,
+    #[prost(message, repeated, tag = "3")]
+    pub fields: Vec<Field>,
+    #[prost(message, repeated, tag = "4")]
+    pub rows: Vec<Row>,
+    #[prost(message, repeated, tag = "5")]
+    pub summary_rows: Vec<Row>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Field {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(string, optional, tag = "2")]
+    pub r#type: Option<String>,
+    #[prost(bool, tag = "3")]
+    pub computed: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Row {
+    #[prost(message, repeated, tag = "1")]
+    pub values: Vec<Value>,
+    #[prost(map = "string, message", tag = "2")]
+    pub extra: HashMap<String, Value>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Value {
+    #[prost(oneof = "value::Kind", tags = "1, 2, 3, 4, 5, 6, 7, 8")]
+    pub kind: Option<value::Kind>,
+}
+
+/// The `kind` oneof of [`Value`]
+pub mod value {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Kind {
+        #[prost(bool, tag = "1")]
+        NullValue(bool),
+        #[prost(bool, tag = "2")]
+        BoolValue(bool),
+        #[prost(int64, tag = "3")]
+        IntValue(i64),
+        #[prost(double, tag = "4")]
+        FloatValue(f64),
+        #[prost(string, tag = "5")]
+        StringValue(String),
+        #[prost(message, tag = "6")]
+        ReferenceValue(super::Reference),
+        #[prost(bytes = "vec", tag = "7")]
+        BytesValue(Vec<u8>),
+        #[prost(string, tag = "8")]
+        DecimalValue(String),
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Reference {
+    #[prost(string, tag = "1")]
+    pub id: String,
+    #[prost(string, optional, tag = "2")]
+    pub ref_type: Option<String>,
+}
+
+fn value_message(value: &crate::Value) -> Value {
+    use value::Kind;
+    let kind = match value {
+        crate::Value::Null => Kind::NullValue(true),
+        crate::Value::Bool(b) => Kind::BoolValue(*b),
+        crate::Value::Int(i) => Kind::IntValue(*i),
+        crate::Value::Float(f) => Kind::FloatValue(*f),
+        crate::Value::String(s) => Kind::StringValue(s.clone()),
+        crate::Value::Reference(r) => Kind::ReferenceValue(Reference {
+            id: r.id.clone(),
+            ref_type: r.ref_type.clone(),
+        }),
+        crate::Value::Bytes(b) => Kind::BytesValue(b.clone()),
+        #[cfg(feature = "rust_decimal")]
+        crate::Value::Decimal(d) => Kind::DecimalValue(d.to_string()),
+    };
+    Value { kind: Some(kind) }
+}
+
+/// The value of a cell, `None` for one the row does not have
+fn message_value(message: Value) -> Result<Option<crate::Value>> {
+    use value::Kind;
+    Ok(Some(match message.kind {
+        None => return Ok(None),
+        Some(Kind::NullValue(_)) => crate::Value::Null,
+        Some(Kind::BoolValue(b)) => crate::Value::Bool(b),
+        Some(Kind::IntValue(i)) => crate::Value::Int(i),
+        Some(Kind::FloatValue(f)) => crate::Value::Float(f),
+        Some(Kind::StringValue(s)) => crate::Value::String(s),
+        Some(Kind::ReferenceValue(r)) => crate::Value::Reference(crate::Reference {
+            id: r.id,
+            ref_type: r.ref_type,
+        }),
+        Some(Kind::BytesValue(b)) => crate::Value::Bytes(b),
+        #[cfg(feature = "rust_decimal")]
+        Some(Kind::DecimalValue(s)) => match crate::parse_decimal(&s) {
+            Some(d) => crate::Value::Decimal(d),
+            None => return Err(error(&format!("invalid decimal '{}'", s))),
+        },
+        #[cfg(not(feature = "rust_decimal"))]
+        Some(Kind::DecimalValue(s)) => crate::Value::String(s),
+    }))
+}
+
+fn row_message(row: &crate::Row, fields: &[String]) -> Row {
+    let values = fields
+        .iter()
+        .map(|f| row.get(f.as_str()).map(value_message).unwrap_or_default())
+        .collect();
+    let extra = row
+        .iter()
+        .filter(|(k, _)| !fields.iter().any(|f| f == &***k))
+        .map(|(k, v)| (k.to_string(), value_message(v)))
+        .collect();
+    Row { values, extra }
+}
+
+fn error(reason: &str) -> ISONError {
+    ISONError {
+        message: format!("Invalid protobuf document: {}", reason),
+        line: None,
+    }
+}
+
+impl crate::Document {
+    /// The document as a protobuf message (see the [`proto`](crate::proto)
+    /// module)
+    ///
+    /// ```rust
+    /// use prost::Message;
+    ///
+    /// let doc = ison_rs::parse("table.users\nid name\n1 Alice")?;
+    /// let bytes = doc.to_proto().encode_to_vec();
+    ///
+    /// let message = ison_rs::proto::Document::decode(bytes.as_slice()).unwrap();
+    /// let back = ison_rs::Document::from_proto(message)?;
+    /// assert_eq!(back["users"][0]["name"].as_str(), Some("Alice"));
+    /// # Ok::<(), ison_rs::ISONError>(())
+    /// ```
+    pub fn to_proto(&self) -> Document {
+        let blocks = self
+            .blocks
+            .iter()
+            .map(|block| Block {
+                kind: block.kind.clone(),
+                name: block.name.clone(),
+                fields: block
+                    .fields
+                    .iter()
+                    .enumerate()
+                    .map(|(i, name)| {
+                        let info = block.field_info.get(i).filter(|fi| &fi.name == name);
+                        Field {
+                            name: name.clone(),
+                            r#type: info.and_then(|fi| fi.field_type.clone()),
+                            computed: info.is_some_and(|fi| fi.is_computed),
+                        }
+                    })
+                    .collect(),
+                rows: block
+                    .rows
+                    .iter()
+                    .map(|r| row_message(r, &block.fields))
+                    .collect(),
+                summary_rows: block
+                    .summary_rows
+                    .iter()
+                    .map(|r| row_message(r, &block.fields))
+                    .collect(),
+            })
+            .collect();
+        Document { blocks }
+    }
+
+    /// Convert a message made by [`Document::to_proto`], or by another
+    /// service from `proto/ison.proto`
+    pub fn from_proto(message: Document) -> Result<crate::Document> {
+        let mut doc = crate::Document::new();
+        for message in message.blocks {
+            let mut block = crate::Block::new(message.kind, message.name);
+            for field in message.fields {
+                block.fields.push(field.name.clone());
+                block.field_info.push(crate::FieldInfo {
+                    name: field.name,
+                    field_type: field.r#type,
+                    is_computed: field.computed,
+                });
+            }
+            let keys = block.field_keys();
+            for (rows, target) in [
+                (message.rows, &mut block.rows),
+                (message.summary_rows, &mut block.summary_rows),
+            ] {
+                for row in rows {
+                    if row.values.len() > keys.len() {
+                        return Err(error(&format!(
+                            "row of block '{}' has {} values for {} fields",
+                            block.name,
+                            row.values.len(),
+                            keys.len()
+                        )));
+                    }
+                    let mut decoded = crate::Row::new();
+                    for (key, value) in keys.iter().zip(row.values) {
+                        if let Some(value) = message_value(value)? {
+                            decoded.insert(key.clone(), value);
+                        }
+                    }
+                    for (key, value) in row.extra {
+                        if let Some(value) = message_value(value)? {
+                            decoded.insert(intern(&keys, &key), value);
+                        }
+                    }
+                    target.push(decoded);
+                }
+            }
+            doc.blocks.push(block);
+        }
+        Ok(doc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost::Message;
+
+    use super::*;
+    use crate::{dumps, parse};
+
+    #[test]
+    fn test_proto_roundtrip() {
+        let mut doc = parse(
+            "table.users\nid:int name boss total:computed\n\
+             1 Alice :MANAGES:2 ~\n2 \"Bob Smith\" :user:1 2.5\n3 b64:AP8= ~ true\n---\n2 ~ ~ ~",
+        )
+        .unwrap();
+        // A missing cell and a key that is not a field
+        doc.get_mut("users").unwrap().rows[0].remove("total");
+        doc.get_mut("users").unwrap().rows[1].insert("note".into(), "x".into());
+
+        let bytes = doc.to_proto().encode_to_vec();
+        let back =
+            crate::Document::from_proto(Document::decode(bytes.as_slice()).unwrap()).unwrap();
+        let (a, b) = (&doc["users"], &back["users"]);
+        assert_eq!(a.rows, b.rows);
+        assert_eq!(a.summary_rows, b.summary_rows);
+        assert_eq!(b.get_field_type("id"), Some("int"));
+        assert_eq!(b.get_computed_fields(), ["total"]);
+        assert_eq!(dumps(&back, false), dumps(&doc, false));
+
+        let mut message = doc.to_proto();
+        message.blocks[0].rows[0].values.push(Value::default());
+        message.blocks[0].rows[0].values.push(Value::default());
+        let err = crate::Document::from_proto(message).unwrap_err();
+        assert_eq!(
+            err.message,
+            "Invalid protobuf document: row of block 'users' has 6 values for 4 fields"
+        );
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    #[test]
+    fn test_proto_decimal() {
+        let doc = parse("table.prices\nsku price:decimal\nA 19.90").unwrap();
+        let back = crate::Document::from_proto(doc.to_proto()).unwrap();
+        assert_eq!(back["prices"][0]["price"].to_string(), "19.90");
+        assert!(back["prices"][0]["price"].is_decimal());
+    }
+}
@@ -0,0 +1,207 @@
+//! PII redaction before data leaves for an LLM
+//!
+//! [`Document::redact`] returns a sanitized copy with each field named in
+//! [`RedactionRules`] hashed, masked, tokenized, or dropped. Fields are named
+//! either bare (`email`, in every block) or by block (`users.email`), and the
+//! block-qualified rule wins when both match. Null cells stay null.
+
+use alloc::collections::BTreeMap;
+
+use crate::prelude::*;
+use crate::{Block, Document, FieldInfo, Serializer, Value};
+
+/// What [`Document::redact`] does to a field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Redaction {
+    /// Replace with an HMAC-SHA256 keyed by the salt, `h:` and its first
+    /// 16 hex digits; equal values hash equally, so joins and grouping
+    /// still work
+    Hash,
+    /// Replace every character but the last four with `*`
+    MaskLast4,
+    /// Remove the column
+    Drop,
+    /// Replace with a placeholder named after the field (`EMAIL_1`,
+    /// `EMAIL_2`, ...), numbered by first appearance in the document
+    Tokenize,
+}
+
+/// Fields to redact and how; see [`Document::redact`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RedactionRules {
+    /// Strategy per field, `field` or `block.field`
+    pub fields: BTreeMap<String, Redaction>,
+    /// Key of [`Redaction::Hash`]. Without it hashes cannot be matched
+    /// against hashes of guessed values, so keep it secret and make it long
+    /// and random; low-entropy values such as phone numbers are only as
+    /// safe as the key.
+    pub salt: String,
+}
+
+impl RedactionRules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a rule for `field` (`email` or `users.email`)
+    pub fn field(mut self, field: &str, redaction: Redaction) -> Self {
+        self.fields.insert(field.to_string(), redaction);
+        self
+    }
+
+    /// Set the hash salt
+    pub fn salt(mut self, salt: &str) -> Self {
+        self.salt = salt.to_string();
+        self
+    }
+
+    fn rule(&self, block: &str, field: &str) -> Option<Redaction> {
+        self.fields
+            .get(&format!("{}.{}", block, field))
+            .or_else(|| self.fields.get(field))
+            .copied()
+    }
+}
+
+/// Text a value is redacted from: strings as they are, others as ISON tokens
+fn plain_text(serializer: &Serializer, value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => serializer.serialize_value(other),
+    }
+}
+
+fn mask_last4(text: &str) -> String {
+    let keep = text.chars().count().saturating_sub(4);
+    text.chars()
+        .enumerate()
+        .map(|(i, c)| if i < keep { '*' } else { c })
+        .collect()
+}
+
+/// Placeholders handed out by [`Redaction::Tokenize`], per field
+#[derive(Default)]
+struct Tokens {
+    assigned: BTreeMap<(String, String), String>,
+    counts: BTreeMap<String, usize>,
+}
+
+impl Tokens {
+    fn get(&mut self, field: &str, text: String) -> String {
+        let key = (field.to_string(), text);
+        if let Some(token) = self.assigned.get(&key) {
+            return token.clone();
+        }
+        let count = self.counts.entry(field.to_string()).or_insert(0);
+        *count += 1;
+        let token = format!("{}_{}", field.to_uppercase(), count);
+        self.assigned.insert(key, token.clone());
+        token
+    }
+}
+
+impl Document {
+    /// A copy with the fields named in `rules` redacted.
+    ///
+    /// Summary rows are redacted along with data rows, and redacted columns
+    /// lose their type annotation since their cells become strings.
+    pub fn redact(&self, rules: &RedactionRules) -> Document {
+        let serializer = Serializer::new(false);
+        let mut tokens = Tokens::default();
+        let mut doc = self.clone();
+        for block in &mut doc.blocks {
+            let plan: Vec<(String, Redaction)> = block
+                .fields
+                .iter()
+                .filter_map(|f| Some((f.clone(), rules.rule(&block.name, f)?)))
+                .collect();
+            for (field, redaction) in plan {
+                if redaction == Redaction::Drop {
+                    let _ = block.drop_field(&field);
+                    continue;
+                }
+                redact_field(block, &field, |value| {
+                    let text = plain_text(&serializer, value);
+                    match redaction {
+                        Redaction::Hash => {
+                            let mac = hmac_sha256::HMAC::mac(text, &rules.salt);
+                            let mut hash = String::from("h:");
+                            for byte in &mac[..8] {
+                                hash.push_str(&format!("{:02x}", byte));
+                            }
+                            hash
+                        }
+                        Redaction::MaskLast4 => mask_last4(&text),
+                        Redaction::Tokenize => tokens.get(&field, text),
+                        Redaction::Drop => unreachable!(),
+                    }
+                });
+            }
+        }
+        doc
+    }
+}
+
+/// Replace the non-null cells of `field` and clear its annotation
+fn redact_field(block: &mut Block, field: &str, mut replace: impl FnMut(&Value) -> String) {
+    for row in block.rows.iter_mut().chain(block.summary_rows.iter_mut()) {
+        if let Some(value) = row.get_mut(field) {
+            if !value.is_null() {
+                *value = Value::String(replace(value));
+            }
+        }
+    }
+    for info in block.field_info.iter_mut().filter(|fi| fi.name == field) {
+        *info = FieldInfo::new(field);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dumps, parse};
+
+    #[test]
+    fn test_redact() {
+        let doc = parse(
+            "table.users\nid name email ssn:string phone\n\
+             1 Alice a@x.com \"123-45-6789\" 5551234\n\
+             2 Bob b@x.com ~ 5559876\n3 Carol a@x.com \"987-65-4321\" 12\n\n\
+             table.orders\nid email\n9 b@x.com",
+        )
+        .unwrap();
+        let rules = RedactionRules::new()
+            .field("email", Redaction::Tokenize)
+            .field("users.name", Redaction::Hash)
+            .field("ssn", Redaction::MaskLast4)
+            .field("phone", Redaction::Drop)
+            .salt("s3cret");
+        let redacted = doc.redact(&rules);
+        let users = &redacted["users"];
+
+        assert_eq!(users.fields, vec!["id", "name", "email", "ssn"]);
+        assert_eq!(users[0]["ssn"].as_str(), Some("*******6789"));
+        assert!(users[1]["ssn"].is_null());
+        assert_eq!(users.get_field_type("ssn"), None);
+
+        // Tokens and hashes are consistent across rows and blocks
+        let emails: Vec<_> = users
+            .rows
+            .iter()
+            .map(|r| r["email"].as_str().unwrap())
+            .collect();
+        assert_eq!(emails, ["EMAIL_1", "EMAIL_2", "EMAIL_1"]);
+        assert_eq!(redacted["orders"][0]["email"].as_str(), Some("EMAIL_2"));
+        // HMAC-SHA256 of "Alice" keyed by the salt, truncated
+        assert_eq!(users[0]["name"].as_str(), Some("h:f5a0f8056e93af26"));
+        assert_eq!(doc.redact(&rules)["users"][0]["name"], users[0]["name"]);
+        assert_ne!(
+            doc.redact(&rules.clone().salt("other"))["users"][0]["name"],
+            users[0]["name"]
+        );
+
+        let text = dumps(&redacted, false);
+        assert!(!text.contains("Alice") && !text.contains("a@x.com") && !text.contains("555"));
+        assert_eq!(doc["users"].fields.len(), 5);
+    }
+}
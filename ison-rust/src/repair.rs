@@ -0,0 +1,641 @@
+//! Repairing almost-valid ISON from language models
+//!
+//! [`parse_repair`] never fails. It fixes what a reader would fix by hand
+//! and reports each change:
+//!
+//! - code fences, markdown around headers (`### table.users`,
+//!   `**table.users**`), and prose between blocks are removed
+//! - a quote left open at the end of a row is closed; `"""` strings,
+//!   which may span lines, are left as they are
+//! - `True`, `FALSE`, and other casings become `true` and `false`
+//! - a row with too many values has the surplus joined into one string, in
+//!   the text column where the other values then best fit their columns
+//!   (or, among equals, the one that usually holds the longest text);
+//!   a row with too few is padded with nulls
+//! - a block whose fields line is missing takes it from an earlier block of
+//!   the same name or, failing that, of the same width
+//! - rows after a blank line with no header of their own join the block
+//!   before them
+//!
+//! A block that still does not parse is dropped and reported.
+
+use alloc::borrow::Cow;
+use core::fmt;
+
+use crate::llm::{is_fields, is_header};
+use crate::prelude::*;
+use crate::{parse, parse_token, Document, Parser, Value, BLOCK_QUOTE};
+
+/// What [`parse_repair`] changed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RepairKind {
+    /// Removed a code fence or markdown around a header
+    StrippedMarkdown,
+    /// Removed a line that is not part of any block
+    DroppedText,
+    /// Closed a quote left open at the end of the line
+    ClosedQuote,
+    /// Lowercased `True`/`FALSE`
+    Boolean,
+    /// Joined `count` values into one string in `field`
+    MergedValues { field: String, count: usize },
+    /// Filled `missing` absent values with null
+    PaddedRow { missing: usize },
+    /// Used the fields line of block `from`
+    CopiedFields { from: String },
+    /// Added rows with no header of their own to `block`
+    MissingHeader { block: String },
+    /// Dropped a block that did not parse even after repair
+    DroppedBlock { block: String, message: String },
+}
+
+/// One change, at a 1-based line of the original text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repair {
+    pub line: usize,
+    pub kind: RepairKind,
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            RepairKind::StrippedMarkdown => write!(f, "removed markdown"),
+            RepairKind::DroppedText => write!(f, "removed text that is not ISON"),
+            RepairKind::ClosedQuote => write!(f, "closed an unterminated quote"),
+            RepairKind::Boolean => write!(f, "lowercased a boolean"),
+            RepairKind::MergedValues { field, count } => {
+                write!(f, "joined {} values into '{}'", count, field)
+            }
+            RepairKind::PaddedRow { missing } => {
+                write!(f, "filled {} missing values with null", missing)
+            }
+            RepairKind::CopiedFields { from } => {
+                write!(f, "took the fields line from block '{}'", from)
+            }
+            RepairKind::MissingHeader { block } => {
+                write!(f, "added rows without a header to block '{}'", block)
+            }
+            RepairKind::DroppedBlock { block, message } => {
+                write!(f, "dropped block '{}': {}", block, message)
+            }
+        }
+    }
+}
+
+/// Every change [`parse_repair`] made, in line order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepairReport {
+    pub repairs: Vec<Repair>,
+}
+
+impl RepairReport {
+    /// Whether the text parsed without changes
+    pub fn is_clean(&self) -> bool {
+        self.repairs.is_empty()
+    }
+
+    fn add(&mut self, line: usize, kind: RepairKind) {
+        self.repairs.push(Repair { line, kind });
+    }
+}
+
+/// Broad type of a value, for matching values to columns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Null,
+    Number,
+    Bool,
+    Ref,
+    Text,
+}
+
+/// A value as written (quotes included) and as read
+struct Token<'a> {
+    raw: String,
+    text: Cow<'a, str>,
+}
+
+impl Token<'_> {
+    fn kind(&self) -> Kind {
+        if self.raw.starts_with('"') {
+            return Kind::Text;
+        }
+        match parse_token(&self.raw) {
+            Ok(Value::Null) => Kind::Null,
+            Ok(Value::Bool(_)) => Kind::Bool,
+            Ok(Value::Int(_) | Value::Float(_)) => Kind::Number,
+            Ok(Value::Reference(_)) => Kind::Ref,
+            _ => Kind::Text,
+        }
+    }
+}
+
+fn tokens<'l>(parser: &Parser, line: &'l str) -> Vec<Token<'l>> {
+    let mut tokens = Vec::new();
+    parser.tokenize_line_with(line, |range, text| {
+        tokens.push(Token {
+            raw: line[range].to_string(),
+            text,
+        })
+    });
+    tokens
+}
+
+/// Whether a line holds values rather than field names
+fn is_data(tokens: &[Token]) -> bool {
+    tokens.iter().any(|t| t.kind() != Kind::Text) || !is_fields(&texts(tokens))
+}
+
+fn texts<'a>(tokens: &'a [Token]) -> Vec<Cow<'a, str>> {
+    tokens.iter().map(|t| Cow::Borrowed(&*t.text)).collect()
+}
+
+/// `line` with a quote left open at its end closed
+fn close_quote(line: &str) -> Option<String> {
+    let mut in_quote = false;
+    let mut escaped = false;
+    let mut prev = ' ';
+    // End of the `"""` string being skipped, which may hold quotes
+    let mut skip_to = 0;
+    for (i, c) in line.char_indices() {
+        if i < skip_to {
+            continue;
+        }
+        if !in_quote && prev.is_whitespace() && line[i..].starts_with(BLOCK_QUOTE) {
+            let body = i + BLOCK_QUOTE.len();
+            skip_to = body + line[body..].find(BLOCK_QUOTE)? + BLOCK_QUOTE.len();
+            prev = '"';
+            continue;
+        }
+        if in_quote {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_quote = false,
+                _ => {}
+            }
+        } else if c == '"' {
+            in_quote = true;
+        } else if c == '#' && prev.is_whitespace() {
+            break;
+        }
+        prev = c;
+    }
+    in_quote.then(|| format!("{}\"", line))
+}
+
+/// The header in a line such as `### table.users` or `**table.users**`
+fn markdown_header(line: &str) -> Option<&str> {
+    let inner = line.trim_start_matches('#').trim();
+    let inner = inner
+        .strip_prefix("**")
+        .and_then(|s| s.strip_suffix("**"))
+        .or_else(|| inner.strip_prefix('`').and_then(|s| s.strip_suffix('`')))
+        .unwrap_or(inner);
+    is_header(inner).then_some(inner)
+}
+
+/// A block as read so far, with line numbers
+struct Pending {
+    header: (usize, String),
+    fields: Option<(usize, String)>,
+    rows: Vec<(usize, String)>,
+}
+
+impl Pending {
+    fn width(&self, parser: &Parser) -> usize {
+        self.fields
+            .as_ref()
+            .map_or(0, |(_, f)| tokens(parser, f).len())
+    }
+}
+
+enum State {
+    Between,
+    Fields,
+    Rows,
+}
+
+/// The lines of `text` with their 1-based numbers, each extended through
+/// the line that closes any `"""` string it opens
+fn logical_lines(text: &str) -> Vec<(usize, Cow<'_, str>)> {
+    let mut lines = Vec::new();
+    let mut raw_lines = text.lines().enumerate();
+    while let Some((index, raw)) = raw_lines.next() {
+        let mut line = Cow::Borrowed(raw);
+        while Parser::scan_line(&line).1 {
+            let Some((_, next)) = raw_lines.next() else {
+                break;
+            };
+            let joined = line.to_mut();
+            joined.push('\n');
+            joined.push_str(next);
+        }
+        lines.push((index + 1, line));
+    }
+    lines
+}
+
+/// Split `text` into blocks, repairing markdown, headers, and fields lines
+fn read_blocks(text: &str, parser: &Parser, report: &mut RepairReport) -> Vec<Pending> {
+    let mut blocks: Vec<Pending> = Vec::new();
+    let mut state = State::Between;
+    for (number, raw) in logical_lines(text) {
+        let line = raw.trim();
+        if line.starts_with("```") || line.starts_with("~~~") {
+            report.add(number, RepairKind::StrippedMarkdown);
+            continue;
+        }
+        if let Some(header) = markdown_header(line) {
+            if header != line {
+                report.add(number, RepairKind::StrippedMarkdown);
+            }
+            blocks.push(Pending {
+                header: (number, header.to_string()),
+                fields: None,
+                rows: Vec::new(),
+            });
+            state = State::Fields;
+            continue;
+        }
+        if line.is_empty() {
+            if let State::Rows = state {
+                state = State::Between;
+            }
+            continue;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+        let row = tokens(parser, line);
+        match (&state, blocks.last_mut()) {
+            (State::Fields, Some(_)) => {
+                let last = blocks.len() - 1;
+                let (earlier, current) = blocks.split_at_mut(last);
+                let block = &mut current[0];
+                let sibling = if is_data(&row) {
+                    let kind = block.header.1.split_once('.').unwrap().0;
+                    let kind_prefix = format!("{}.", kind);
+                    earlier
+                        .iter()
+                        .rev()
+                        .find(|b| b.fields.is_some() && b.header.1 == block.header.1)
+                        .or_else(|| {
+                            earlier
+                                .iter()
+                                .rev()
+                                .filter(|b| b.width(parser) == row.len())
+                                .min_by_key(|b| !b.header.1.starts_with(&kind_prefix))
+                        })
+                } else {
+                    None
+                };
+                match sibling {
+                    Some(sibling) => {
+                        let from = sibling.header.1.split_once('.').unwrap().1.to_string();
+                        report.add(number, RepairKind::CopiedFields { from });
+                        block.fields = Some((number, sibling.fields.clone().unwrap().1));
+                        block.rows.push((number, line.to_string()));
+                    }
+                    None => block.fields = Some((number, line.to_string())),
+                }
+                state = State::Rows;
+            }
+            (State::Rows, Some(block)) => {
+                let ends_prose = line.ends_with(['.', '!', '?', ':']) && !line.ends_with('"');
+                if row.len() != block.width(parser) && ends_prose {
+                    report.add(number, RepairKind::DroppedText);
+                    state = State::Between;
+                } else {
+                    block.rows.push((number, line.to_string()));
+                }
+            }
+            (_, Some(block)) if is_data(&row) && row.len() == block.width(parser) => {
+                let name = block.header.1.split_once('.').unwrap().1.to_string();
+                report.add(number, RepairKind::MissingHeader { block: name });
+                block.rows.push((number, line.to_string()));
+                state = State::Rows;
+            }
+            _ => report.add(number, RepairKind::DroppedText),
+        }
+    }
+    blocks
+}
+
+/// Broad type of each column, from its annotation or else the most common
+/// non-null kind among rows of the right width
+fn column_kinds(fields: &[Token], rows: &[(usize, Vec<Token>)]) -> Vec<Option<Kind>> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let annotation = field
+                .text
+                .split_once(':')
+                .map(|(_, t)| t.trim_end_matches('?'));
+            match annotation {
+                Some("int" | "float" | "number" | "decimal") => return Some(Kind::Number),
+                Some("bool") => return Some(Kind::Bool),
+                Some("ref") => return Some(Kind::Ref),
+                Some("string") => return Some(Kind::Text),
+                _ => {}
+            }
+            let mut counts: Vec<(Kind, usize)> = Vec::new();
+            for (_, row) in rows.iter().filter(|(_, r)| r.len() == fields.len()) {
+                let kind = row[i].kind();
+                if kind == Kind::Null {
+                    continue;
+                }
+                match counts.iter_mut().find(|(k, _)| *k == kind) {
+                    Some((_, n)) => *n += 1,
+                    None => counts.push((kind, 1)),
+                }
+            }
+            counts.into_iter().max_by_key(|(_, n)| *n).map(|(k, _)| k)
+        })
+        .collect()
+}
+
+/// Total length of each column's values among rows of the right width
+fn text_lengths(width: usize, rows: &[(usize, Vec<Token>)]) -> Vec<usize> {
+    let mut lengths = vec![0; width];
+    for (_, row) in rows.iter().filter(|(_, r)| r.len() == width) {
+        for (length, token) in lengths.iter_mut().zip(row) {
+            *length += token.text.len();
+        }
+    }
+    lengths
+}
+
+/// Cells of a row with too many values, the surplus joined at the text
+/// column where the other values best fit their columns, or among equals
+/// where the most joined values are text, then where the column usually
+/// holds the longest text
+fn merge_surplus(row: &[Token], kinds: &[Option<Kind>], lengths: &[usize]) -> (usize, Vec<String>) {
+    let extra = row.len() - kinds.len();
+    let fits = |cell: &Token, kind: Option<Kind>| {
+        kind.is_none_or(|k| k == cell.kind() || cell.kind() == Kind::Null)
+    };
+    let best = (0..kinds.len())
+        .filter(|&p| kinds[p].is_none_or(|k| k == Kind::Text))
+        .max_by_key(|&p| {
+            let before = row[..p].iter().zip(&kinds[..p]);
+            let after = row[p + extra + 1..].iter().zip(&kinds[p + 1..]);
+            let score = before.chain(after).filter(|(c, k)| fits(c, **k)).count();
+            let text = row[p..=p + extra]
+                .iter()
+                .filter(|t| t.kind() == Kind::Text)
+                .count();
+            (score, text, lengths[p], core::cmp::Reverse(p))
+        })
+        .unwrap_or(kinds.len() - 1);
+    let joined: Vec<&str> = row[best..=best + extra].iter().map(|t| &*t.text).collect();
+    let merged = format!(
+        "\"{}\"",
+        joined.join(" ").replace('\\', "\\\\").replace('"', "\\\"")
+    );
+    let mut cells: Vec<String> = row[..best].iter().map(|t| t.raw.clone()).collect();
+    cells.push(merged);
+    cells.extend(row[best + extra + 1..].iter().map(|t| t.raw.clone()));
+    (best, cells)
+}
+
+/// The block's text with every row repaired
+fn repair_rows(block: &Pending, parser: &Parser, report: &mut RepairReport) -> String {
+    let fields_line = block.fields.as_ref().map_or("", |(_, f)| f.as_str());
+    let fields = tokens(parser, fields_line);
+    let names: Vec<&str> = fields
+        .iter()
+        .map(|f| f.text.split(':').next().unwrap_or(""))
+        .collect();
+
+    let mut lines = Vec::new();
+    for (number, row) in &block.rows {
+        if row == "---" {
+            lines.push((*number, row.clone()));
+            continue;
+        }
+        let closed = close_quote(row);
+        if closed.is_some() {
+            report.add(*number, RepairKind::ClosedQuote);
+        }
+        lines.push((*number, closed.unwrap_or_else(|| row.clone())));
+    }
+
+    let mut rows: Vec<(usize, Vec<Token>)> = Vec::new();
+    for (number, line) in &lines {
+        let mut row = tokens(parser, line);
+        let mut lowered = false;
+        for (i, token) in row.iter_mut().enumerate() {
+            let is_string = fields.get(i).is_some_and(|f| f.text.ends_with(":string"));
+            let bool_text = ["true", "false"]
+                .into_iter()
+                .find(|b| token.raw.eq_ignore_ascii_case(b) && token.raw != *b);
+            if let (Some(b), false) = (bool_text, is_string) {
+                *token = Token {
+                    raw: b.to_string(),
+                    text: Cow::Borrowed(b),
+                };
+                lowered = true;
+            }
+        }
+        if lowered {
+            report.add(*number, RepairKind::Boolean);
+        }
+        rows.push((*number, row));
+    }
+
+    let kinds = column_kinds(&fields, &rows);
+    let lengths = text_lengths(fields.len(), &rows);
+
+    let mut text = vec![block.header.1.clone(), fields_line.to_string()];
+    for (number, row) in &rows {
+        let width = fields.len();
+        let cells: Vec<String> = if row.len() == 1 && row[0].raw == "---" {
+            vec!["---".to_string()]
+        } else if row.len() > width && width > 0 {
+            let (at, cells) = merge_surplus(row, &kinds, &lengths);
+            report.add(
+                *number,
+                RepairKind::MergedValues {
+                    field: names[at].to_string(),
+                    count: row.len() - width + 1,
+                },
+            );
+            cells
+        } else {
+            let mut cells: Vec<String> = row.iter().map(|t| t.raw.clone()).collect();
+            if cells.len() < width {
+                report.add(
+                    *number,
+                    RepairKind::PaddedRow {
+                        missing: width - cells.len(),
+                    },
+                );
+                cells.resize(width, "~".to_string());
+            }
+            cells
+        };
+        text.push(cells.join(" "));
+    }
+    text.join("\n")
+}
+
+/// Parse `text`, repairing the mistakes language models commonly make (see
+/// the [module docs](self)).
+///
+/// ```rust
+/// let (doc, report) = ison_rs::parse_repair(
+///     "```ison\ntable.users\nid name active\n1 Alice Smith True\n2 \"Bob\n```",
+/// );
+/// assert_eq!(doc["users"][0]["name"].as_str(), Some("Alice Smith"));
+/// assert_eq!(doc["users"][0]["active"].as_bool(), Some(true));
+/// assert_eq!(doc["users"][1]["name"].as_str(), Some("Bob"));
+/// assert_eq!(report.repairs.len(), 6);
+/// ```
+pub fn parse_repair(text: &str) -> (Document, RepairReport) {
+    let parser = Parser::new("");
+    let mut report = RepairReport::default();
+    let mut doc = Document::new();
+    for block in read_blocks(text, &parser, &mut report) {
+        let repaired = repair_rows(&block, &parser, &mut report);
+        match parse(&repaired) {
+            Ok(parsed) => doc.blocks.extend(parsed.blocks),
+            Err(e) => {
+                let name = block.header.1.split_once('.').unwrap().1.to_string();
+                report.add(
+                    block.header.0,
+                    RepairKind::DroppedBlock {
+                        block: name,
+                        message: e.message,
+                    },
+                );
+            }
+        }
+    }
+    report.repairs.sort_by_key(|r| r.line);
+    (doc, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dumps;
+
+    fn kinds(report: &RepairReport) -> Vec<(usize, &RepairKind)> {
+        report.repairs.iter().map(|r| (r.line, &r.kind)).collect()
+    }
+
+    #[test]
+    fn test_clean_input() {
+        let text =
+            "table.users\nid:int name score\n1 Alice 3.5\n2 \"Bob Smith\" ~\n---\n~ ~ 3.5\n\n\
+                    table.points\nx\n3.14\n2.72";
+        let (doc, report) = parse_repair(text);
+        assert!(report.is_clean(), "{:?}", report);
+        assert_eq!(dumps(&doc, false), dumps(&parse(text).unwrap(), false));
+
+        let text =
+            "table.notes\nid body\n1 \"\"\"first line\nsecond \"line\" end\"\"\"\n2 \"\"\"\"\"\"";
+        let (doc, report) = parse_repair(text);
+        assert!(report.is_clean(), "{:?}", report);
+        assert_eq!(
+            doc["notes"][0]["body"].as_str(),
+            Some("first line\nsecond \"line\" end")
+        );
+        assert_eq!(dumps(&doc, false), dumps(&parse(text).unwrap(), false));
+    }
+
+    #[test]
+    fn test_repairs() {
+        let text = "Here is the data:\n\
+                    ```ison\n\
+                    ### table.users\n\
+                    id:int name age:int active\n\
+                    1 Alice Smith 30 True\n\
+                    2 \"Bob 41 FALSE\n\
+                    3 Carol\n\
+                    ```\n\
+                    \n\
+                    4 Dave 25 false\n\
+                    \n\
+                    **table.orders**\n\
+                    10 :user:1 9.99\n\
+                    \n\
+                    table.users\n\
+                    5 Erin 22 true\n\
+                    Hope this helps!";
+        let (doc, report) = parse_repair(text);
+        assert_eq!(
+            kinds(&report),
+            [
+                (1, &RepairKind::DroppedText),
+                (2, &RepairKind::StrippedMarkdown),
+                (3, &RepairKind::StrippedMarkdown),
+                (5, &RepairKind::Boolean),
+                (
+                    5,
+                    &RepairKind::MergedValues {
+                        field: "name".into(),
+                        count: 2
+                    }
+                ),
+                (6, &RepairKind::ClosedQuote),
+                (6, &RepairKind::PaddedRow { missing: 2 }),
+                (7, &RepairKind::PaddedRow { missing: 2 }),
+                (8, &RepairKind::StrippedMarkdown),
+                (
+                    10,
+                    &RepairKind::MissingHeader {
+                        block: "users".into()
+                    }
+                ),
+                (12, &RepairKind::StrippedMarkdown),
+                (
+                    16,
+                    &RepairKind::CopiedFields {
+                        from: "users".into()
+                    }
+                ),
+                (17, &RepairKind::DroppedText),
+            ]
+        );
+        assert_eq!(
+            report.repairs[4].to_string(),
+            "line 5: joined 2 values into 'name'"
+        );
+
+        let users = &doc.blocks[0];
+        assert_eq!(users.len(), 4);
+        assert_eq!(users[0]["name"].as_str(), Some("Alice Smith"));
+        assert_eq!(users[0]["age"].as_int(), Some(30));
+        assert_eq!(users[0]["active"].as_bool(), Some(true));
+        assert_eq!(users[1]["name"].as_str(), Some("Bob 41 FALSE"));
+        assert!(users[2]["active"].is_null());
+        assert_eq!(users[3]["name"].as_str(), Some("Dave"));
+
+        // `table.orders` lost its fields line and no block has its width,
+        // so its first row became the fields; the second `table.users`
+        // took the first one's
+        assert_eq!(doc.blocks[1].fields[0], "10");
+        assert_eq!(doc.blocks[2][0]["name"].as_str(), Some("Erin"));
+    }
+
+    #[test]
+    fn test_merge_position() {
+        // The surplus goes where the numbers still line up
+        let text = "table.items\nsku title price:float qty\nA1 Red Mug 4.5 2\nB2 Plate 3.0 1";
+        let (doc, report) = parse_repair(text);
+        assert_eq!(doc["items"][0]["title"].as_str(), Some("Red Mug"));
+        assert_eq!(doc["items"][0]["qty"].as_int(), Some(2));
+        assert_eq!(report.repairs.len(), 1);
+
+        let text = "table.notes\nid body\n1 \"say \\\"hi\\\"\" and leave";
+        let (doc, _) = parse_repair(text);
+        assert_eq!(
+            doc["notes"][0]["body"].as_str(),
+            Some("say \"hi\" and leave")
+        );
+    }
+}
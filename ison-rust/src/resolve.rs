@@ -0,0 +1,87 @@
+//! Finding the rows that references point at
+//!
+//! A block-qualified reference (`:users.id:42`, or `:lines.order_id,line:42,3`
+//! for a composite key) matches rows of that block whose key fields equal the
+//! key parts. Any other reference (`:42`, `:user:42`, `:MANAGES:42`) matches
+//! the `id` field of every block, which is ambiguous when tables share ids.
+
+use crate::prelude::*;
+use crate::{parse_token, Block, Document, Reference, Row, Value};
+
+impl Document {
+    /// First row the reference points at, with its block
+    pub fn resolve(&self, reference: &Reference) -> Option<(&Block, &Row)> {
+        self.resolve_all(reference).into_iter().next()
+    }
+
+    /// Every row the reference points at, in document order
+    pub fn resolve_all(&self, reference: &Reference) -> Vec<(&Block, &Row)> {
+        let target = reference.target_block();
+        let (fields, parts) = match target {
+            Some(_) => (reference.target_fields(), reference.key_parts()),
+            None => (vec!["id"], vec![reference.id.as_str()]),
+        };
+        if fields.len() != parts.len() {
+            return Vec::new();
+        }
+
+        self.blocks
+            .iter()
+            .filter(|block| target.is_none_or(|name| block.name == name))
+            .flat_map(|block| block.rows.iter().map(move |row| (block, row)))
+            .filter(|(_, row)| {
+                fields
+                    .iter()
+                    .zip(&parts)
+                    .all(|(field, part)| key_matches(row.get(*field), part))
+            })
+            .collect()
+    }
+}
+
+/// Whether a cell equals a key part, compared as ISON values so `:42`
+/// matches both `42` and `"42"`
+fn key_matches(value: Option<&Value>, part: &str) -> bool {
+    match value {
+        Some(Value::String(s)) => s == part,
+        Some(value) => parse_token(part).is_ok_and(|key| key == *value),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_resolve() {
+        let doc = parse(
+            "table.users\nid name\n42 Alice\n7 Bob\n\n\
+             table.teams\nid name\n42 Core\n\n\
+             table.lines\norder_id line sku\n42 1 A\n42 3 B",
+        )
+        .unwrap();
+
+        let (block, row) = doc.resolve(&Reference::new("42")).unwrap();
+        assert_eq!(
+            (block.name.as_str(), row["name"].as_str()),
+            ("users", Some("Alice"))
+        );
+        assert_eq!(
+            doc.resolve_all(&Reference::with_type("42", "user")).len(),
+            2
+        );
+
+        let team = Reference::qualified("teams", &["id"], &["42"]);
+        assert_eq!(doc.resolve(&team).unwrap().1["name"].as_str(), Some("Core"));
+
+        let line = Reference::qualified("lines", &["order_id", "line"], &["42", "3"]);
+        assert_eq!(doc.resolve_all(&line).len(), 1);
+        assert_eq!(doc.resolve(&line).unwrap().1["sku"].as_str(), Some("B"));
+
+        assert!(doc.resolve(&Reference::new("9")).is_none());
+        let mismatched = Reference::qualified("lines", &["order_id", "line"], &["42"]);
+        assert!(doc.resolve(&mismatched).is_none());
+    }
+}
@@ -0,0 +1,198 @@
+//! Row sampling and truncation for LLM context
+//!
+//! Reduces blocks to a handful of representative rows before prompting.
+//! Random sampling uses a small seeded generator so the same seed always
+//! picks the same rows.
+
+use crate::prelude::*;
+use crate::{Block, Document, Row, Value};
+
+/// How [`Block::sample`] picks rows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SampleStrategy {
+    /// First `n` rows
+    Head,
+    /// Last `n` rows
+    Tail,
+    /// `n` rows chosen uniformly with the given seed, kept in original order
+    Random(u64),
+    /// Rows from every value of the field, proportional to its frequency
+    Stratified(String),
+}
+
+/// SplitMix64, enough for reproducible sampling without a `rand` dependency
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+/// Split `n` across groups proportionally (largest remainder), each capped at its size
+fn allocate(sizes: &[usize], n: usize) -> Vec<usize> {
+    let total: usize = sizes.iter().sum();
+    if total <= n {
+        return sizes.to_vec();
+    }
+    let mut counts: Vec<usize> = sizes.iter().map(|s| s * n / total).collect();
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|&i| core::cmp::Reverse((sizes[i] * n) % total));
+    let mut remaining = n - counts.iter().sum::<usize>();
+    for i in order {
+        if remaining == 0 {
+            break;
+        }
+        if counts[i] < sizes[i] {
+            counts[i] += 1;
+            remaining -= 1;
+        }
+    }
+    counts
+}
+
+impl Block {
+    /// Copy of the block with at most `n` data rows chosen by `strategy`.
+    ///
+    /// Summary rows are kept as-is.
+    pub fn sample(&self, n: usize, strategy: SampleStrategy) -> Block {
+        let len = self.rows.len();
+        let indices: Vec<usize> = if n >= len {
+            (0..len).collect()
+        } else {
+            match strategy {
+                SampleStrategy::Head => (0..n).collect(),
+                SampleStrategy::Tail => (len - n..len).collect(),
+                SampleStrategy::Random(seed) => {
+                    // Partial Fisher-Yates, then restore document order
+                    let mut rng = SplitMix64(seed);
+                    let mut pool: Vec<usize> = (0..len).collect();
+                    for i in 0..n {
+                        let j = i + rng.below(len - i);
+                        pool.swap(i, j);
+                    }
+                    let mut picked = pool[..n].to_vec();
+                    picked.sort_unstable();
+                    picked
+                }
+                SampleStrategy::Stratified(field) => {
+                    let groups = self.group_indices(&field);
+                    let sizes: Vec<usize> = groups.iter().map(|g| g.len()).collect();
+                    let mut picked: Vec<usize> = groups
+                        .iter()
+                        .zip(allocate(&sizes, n))
+                        .flat_map(|(group, k)| group[..k].to_vec())
+                        .collect();
+                    picked.sort_unstable();
+                    picked
+                }
+            }
+        };
+
+        let mut block = self.clone();
+        block.rows = indices.into_iter().map(|i| self.rows[i].clone()).collect();
+        block
+    }
+
+    /// Row indices grouped by the value of `field`, in order of first appearance
+    fn group_indices(&self, field: &str) -> Vec<Vec<usize>> {
+        let mut keys: Vec<Option<&Value>> = Vec::new();
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        for (i, row) in self.rows.iter().enumerate() {
+            let key = row.get(field);
+            match keys.iter().position(|k| *k == key) {
+                Some(g) => groups[g].push(i),
+                None => {
+                    keys.push(key);
+                    groups.push(vec![i]);
+                }
+            }
+        }
+        groups
+    }
+}
+
+impl Document {
+    /// Copy of the document keeping the first `per_block_limit` rows of each block.
+    ///
+    /// Blocks that lost rows get a summary row whose first field reads
+    /// `"N more rows omitted"`, so the reader knows the data is partial.
+    pub fn truncate_rows(&self, per_block_limit: usize) -> Document {
+        let mut doc = self.clone();
+        for block in &mut doc.blocks {
+            let omitted = block.rows.len().saturating_sub(per_block_limit);
+            if omitted == 0 {
+                continue;
+            }
+            block.rows.truncate(per_block_limit);
+            if let Some(first) = block.fields.first() {
+                let mut note = Row::new();
+                note.insert(
+                    first.as_str().into(),
+                    Value::String(format!("{} more rows omitted", omitted)),
+                );
+                block.summary_rows.push(note);
+            }
+        }
+        doc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    fn block() -> Block {
+        let doc = parse("table.items\nid kind\n1 a\n2 a\n3 a\n4 a\n5 a\n6 a\n7 b\n8 b").unwrap();
+        doc["items"].clone()
+    }
+
+    fn ids(block: &Block) -> Vec<i64> {
+        block
+            .rows
+            .iter()
+            .map(|r| r["id"].as_int().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_sample_strategies() {
+        let items = block();
+        assert_eq!(ids(&items.sample(2, SampleStrategy::Head)), vec![1, 2]);
+        assert_eq!(ids(&items.sample(2, SampleStrategy::Tail)), vec![7, 8]);
+        assert_eq!(items.sample(20, SampleStrategy::Tail).len(), 8);
+
+        let random = items.sample(3, SampleStrategy::Random(7));
+        assert_eq!(random.len(), 3);
+        assert_eq!(
+            ids(&random),
+            ids(&items.sample(3, SampleStrategy::Random(7)))
+        );
+        assert!(ids(&random).windows(2).all(|w| w[0] < w[1]));
+
+        let stratified = items.sample(4, SampleStrategy::Stratified("kind".into()));
+        assert_eq!(ids(&stratified), vec![1, 2, 3, 7]);
+    }
+
+    #[test]
+    fn test_truncate_rows() {
+        let doc = Document {
+            blocks: vec![block()],
+        };
+        let truncated = doc.truncate_rows(3);
+        assert_eq!(truncated["items"].len(), 3);
+        assert_eq!(
+            truncated["items"].summary_rows[0]["id"],
+            Value::String("5 more rows omitted".into())
+        );
+        assert!(doc.truncate_rows(10)["items"].summary_rows.is_empty());
+    }
+}
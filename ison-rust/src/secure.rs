@@ -0,0 +1,339 @@
+//! Field-level encryption for columns annotated `:encrypted`
+//!
+//! ```text
+//! table.patients
+//! id name ssn:encrypted
+//! 1 Alice b64:8c2Rk0m1...
+//! ```
+//!
+//! Each non-null cell of an encrypted column, summary rows included, holds
+//! the ciphertext of the cell's ISON token, so its type survives the round
+//! trip. Null cells stay null. The cipher is anything implementing
+//! [`Cipher`]; with the `aes-gcm` feature [`AesGcmCipher`] provides
+//! AES-256-GCM, bound to the cell's `block.field` column.
+
+use crate::prelude::*;
+use crate::{parse, Block, CellType, Document, ISONError, Parser, Result, Row, Serializer, Value};
+
+/// Type annotation that marks a column for encryption
+pub const ENCRYPTED_TYPE: &str = "encrypted";
+
+/// Encrypts and decrypts cell contents
+pub trait Cipher {
+    fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>>;
+    fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>>;
+
+    /// Encrypt a cell of `column`, written `block.field`. Ciphers that
+    /// authenticate associated data should bind the ciphertext to it, so
+    /// it does not decrypt once moved to another column; by default the
+    /// column is ignored.
+    fn encrypt_cell(&self, plaintext: &[u8], column: &str) -> Result<Vec<u8>> {
+        let _ = column;
+        self.encrypt(plaintext)
+    }
+
+    /// Decrypt a cell of `column`; see [`encrypt_cell`](Self::encrypt_cell)
+    fn decrypt_cell(&self, ciphertext: &[u8], column: &str) -> Result<Vec<u8>> {
+        let _ = column;
+        self.decrypt(ciphertext)
+    }
+}
+
+/// Fields of `block` annotated `:encrypted`
+fn encrypted_fields(block: &Block) -> Vec<&str> {
+    block
+        .field_info
+        .iter()
+        .filter(|fi| fi.field_type.as_deref() == Some(ENCRYPTED_TYPE))
+        .map(|fi| fi.name.as_str())
+        .collect()
+}
+
+/// Where a cell is, for errors: `row 2` or `summary row 1`
+fn row_label(summary: bool, row: usize) -> String {
+    if summary {
+        format!("summary row {}", row + 1)
+    } else {
+        format!("row {}", row + 1)
+    }
+}
+
+fn cell_error(block_name: &str, row: &str, field: &str, problem: &str) -> ISONError {
+    ISONError {
+        message: format!("Field '{}' in {} {} {}", field, block_name, row, problem),
+        line: None,
+    }
+}
+
+/// Decrypt the `fields` cells of one row of `block_name` in place
+fn decrypt_row(
+    row: &mut Row,
+    fields: &[String],
+    cipher: &impl Cipher,
+    block_name: &str,
+    label: &str,
+) -> Result<()> {
+    for field in fields {
+        let plain = match row.get(field.as_str()) {
+            Some(Value::Bytes(ciphertext)) => {
+                let column = format!("{}.{}", block_name, field);
+                let plaintext = cipher.decrypt_cell(ciphertext, &column).map_err(|e| {
+                    cell_error(
+                        block_name,
+                        label,
+                        field,
+                        &format!("does not decrypt: {}", e.message),
+                    )
+                })?;
+                let text = String::from_utf8(plaintext).map_err(|_| {
+                    cell_error(
+                        block_name,
+                        label,
+                        field,
+                        "is not valid UTF-8 once decrypted",
+                    )
+                })?;
+                // Read back as a cell, so quoted strings lose their quotes
+                let parser = Parser::new("");
+                match parser.tokenize_line(&text).first() {
+                    Some(token) => parser.parse_cell(token, CellType::Any)?,
+                    None => Value::Null,
+                }
+            }
+            None | Some(Value::Null) => continue,
+            Some(_) => return Err(cell_error(block_name, label, field, "is not encrypted")),
+        };
+        row.insert(field.as_str().into(), plain);
+    }
+    Ok(())
+}
+
+impl Document {
+    /// Replace every non-null cell of an `:encrypted` column, in data and
+    /// summary rows, with its ciphertext, as a bytes value
+    pub fn encrypt_fields(&mut self, cipher: &impl Cipher) -> Result<()> {
+        let serializer = Serializer::new(false);
+        for block in &mut self.blocks {
+            let fields: Vec<String> = encrypted_fields(block)
+                .into_iter()
+                .map(String::from)
+                .collect();
+            for row in block.rows.iter_mut().chain(block.summary_rows.iter_mut()) {
+                for field in &fields {
+                    if let Some(value) = row.get_mut(field.as_str()) {
+                        if !value.is_null() {
+                            let plaintext = serializer.serialize_value(value);
+                            let column = format!("{}.{}", block.name, field);
+                            *value =
+                                Value::Bytes(cipher.encrypt_cell(plaintext.as_bytes(), &column)?);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Decrypt every non-null cell of an `:encrypted` column back to the
+    /// value it held.
+    ///
+    /// Fails on a cell that is not a bytes value or does not decrypt.
+    pub fn decrypt_fields(&mut self, cipher: &impl Cipher) -> Result<()> {
+        for block in &mut self.blocks {
+            let fields: Vec<String> = encrypted_fields(block)
+                .into_iter()
+                .map(String::from)
+                .collect();
+            for (idx, row) in block.rows.iter_mut().enumerate() {
+                decrypt_row(row, &fields, cipher, &block.name, &row_label(false, idx))?;
+            }
+            for (idx, row) in block.summary_rows.iter_mut().enumerate() {
+                decrypt_row(row, &fields, cipher, &block.name, &row_label(true, idx))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Serialize `doc` with its `:encrypted` columns encrypted, leaving `doc` as is
+pub fn dumps_encrypted(doc: &Document, cipher: &impl Cipher) -> Result<String> {
+    let mut doc = doc.clone();
+    doc.encrypt_fields(cipher)?;
+    Ok(crate::dumps(&doc, false))
+}
+
+/// Parse ISON text and decrypt its `:encrypted` columns
+pub fn parse_decrypted(text: &str, cipher: &impl Cipher) -> Result<Document> {
+    let mut doc = parse(text)?;
+    doc.decrypt_fields(cipher)?;
+    Ok(doc)
+}
+
+/// AES-256-GCM with a random nonce per cell, stored before the ciphertext.
+///
+/// Cells are authenticated with their `block.field` column as associated
+/// data, so a ciphertext copied into another column fails to decrypt.
+/// Rows are not bound: a ciphertext moved to another row of the same column
+/// still decrypts, so rows can be sorted, filtered or deleted without
+/// re-encrypting, but an attacker who can edit the file can swap values
+/// between rows. Where that matters, authenticate the whole text as well,
+/// with a MAC or signature kept alongside it.
+#[cfg(feature = "aes-gcm")]
+pub struct AesGcmCipher {
+    cipher: aes_gcm::Aes256Gcm,
+}
+
+#[cfg(feature = "aes-gcm")]
+impl AesGcmCipher {
+    const NONCE_LEN: usize = 12;
+
+    /// Cipher for a 256-bit key
+    pub fn new(key: &[u8; 32]) -> Self {
+        use aes_gcm::KeyInit;
+        Self {
+            cipher: aes_gcm::Aes256Gcm::new(key.into()),
+        }
+    }
+}
+
+#[cfg(feature = "aes-gcm")]
+impl Cipher for AesGcmCipher {
+    fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        self.encrypt_cell(plaintext, "")
+    }
+
+    fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>> {
+        self.decrypt_cell(ciphertext, "")
+    }
+
+    fn encrypt_cell(&self, plaintext: &[u8], column: &str) -> Result<Vec<u8>> {
+        use aes_gcm::aead::{Aead, AeadCore, OsRng, Payload};
+        let nonce = aes_gcm::Aes256Gcm::generate_nonce(&mut OsRng);
+        let payload = Payload {
+            msg: plaintext,
+            aad: column.as_bytes(),
+        };
+        let mut out = nonce.to_vec();
+        out.extend(
+            self.cipher
+                .encrypt(&nonce, payload)
+                .map_err(|_| ISONError {
+                    message: "Encryption failed".to_string(),
+                    line: None,
+                })?,
+        );
+        Ok(out)
+    }
+
+    fn decrypt_cell(&self, ciphertext: &[u8], column: &str) -> Result<Vec<u8>> {
+        use aes_gcm::aead::{Aead, Payload};
+        let failed = || ISONError {
+            message: "Authentication failed".to_string(),
+            line: None,
+        };
+        if ciphertext.len() < Self::NONCE_LEN {
+            return Err(failed());
+        }
+        let (nonce, body) = ciphertext.split_at(Self::NONCE_LEN);
+        let payload = Payload {
+            msg: body,
+            aad: column.as_bytes(),
+        };
+        self.cipher
+            .decrypt(aes_gcm::Nonce::from_slice(nonce), payload)
+            .map_err(|_| failed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dumps;
+
+    /// XOR with a fixed key byte; only good for tests
+    struct Xor(u8);
+
+    impl Cipher for Xor {
+        fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+            Ok(plaintext.iter().map(|b| b ^ self.0).collect())
+        }
+
+        fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>> {
+            Ok(ciphertext.iter().map(|b| b ^ self.0).collect())
+        }
+    }
+
+    #[test]
+    fn test_encrypt_fields() {
+        let doc = parse(
+            "table.patients\nid name ssn:encrypted pin:encrypted\n\
+             1 Alice \"123 45 6789\" 42\n2 Bob ~ 7",
+        )
+        .unwrap();
+        let text = dumps_encrypted(&doc, &Xor(0x5a)).unwrap();
+        assert!(!text.contains("6789"));
+        assert!(text.contains("\n2 Bob null b64:"));
+
+        let encrypted = parse(&text).unwrap();
+        assert!(encrypted["patients"][0]["ssn"].is_bytes());
+        let back = parse_decrypted(&text, &Xor(0x5a)).unwrap();
+        assert_eq!(back["patients"].rows, doc["patients"].rows);
+        assert_eq!(back["patients"][0]["pin"], Value::Int(42));
+
+        // Plain values where ciphertext is expected
+        let err = parse_decrypted(&dumps(&doc, false), &Xor(0x5a)).unwrap_err();
+        assert_eq!(
+            err.message,
+            "Field 'ssn' in patients row 1 is not encrypted"
+        );
+    }
+
+    #[test]
+    fn test_encrypt_summary_rows() {
+        let doc =
+            parse("table.pay\nid salary:encrypted\n1 5000\n2 7000\n---\ntotal 12000").unwrap();
+        let text = dumps_encrypted(&doc, &Xor(0x5a)).unwrap();
+        assert!(!text.contains("12000"));
+        let back = parse_decrypted(&text, &Xor(0x5a)).unwrap();
+        assert_eq!(back["pay"].summary_rows, doc["pay"].summary_rows);
+
+        let mut encrypted = parse(&text).unwrap();
+        encrypted.blocks[0].summary_rows[0].insert("salary".into(), Value::Int(1));
+        let err = encrypted.decrypt_fields(&Xor(0x5a)).unwrap_err();
+        assert_eq!(
+            err.message,
+            "Field 'salary' in pay summary row 1 is not encrypted"
+        );
+    }
+
+    #[cfg(feature = "aes-gcm")]
+    #[test]
+    fn test_aes_gcm() {
+        let doc = parse("table.t\nid secret:encrypted\n1 \"top secret\"").unwrap();
+        let cipher = AesGcmCipher::new(&[7; 32]);
+        let text = dumps_encrypted(&doc, &cipher).unwrap();
+        // Fresh nonce each time
+        assert_ne!(text, dumps_encrypted(&doc, &cipher).unwrap());
+        assert_eq!(
+            parse_decrypted(&text, &cipher).unwrap()["t"].rows,
+            doc["t"].rows
+        );
+
+        let err = parse_decrypted(&text, &AesGcmCipher::new(&[8; 32])).unwrap_err();
+        assert_eq!(
+            err.message,
+            "Field 'secret' in t row 1 does not decrypt: Authentication failed"
+        );
+
+        // A ciphertext moved to another column does not decrypt
+        let doc = parse("table.t\nid a:encrypted b:encrypted\n1 x y").unwrap();
+        let mut encrypted = parse(&dumps_encrypted(&doc, &cipher).unwrap()).unwrap();
+        let a = encrypted["t"][0]["a"].clone();
+        encrypted.blocks[0].rows[0].insert("b".into(), a);
+        let err = encrypted.decrypt_fields(&cipher).unwrap_err();
+        assert_eq!(
+            err.message,
+            "Field 'b' in t row 1 does not decrypt: Authentication failed"
+        );
+    }
+}
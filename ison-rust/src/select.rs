@@ -0,0 +1,208 @@
+//! Path selectors for pinpoint extraction
+//!
+//! A selector names a block, an optional row filter and a field:
+//!
+//! - `users.email` / `users[*].email` - every `email` in `users`
+//! - `users[0].email` - `email` of the first row (negative indexes count from the end)
+//! - `orders[user_id=:1].price` - `price` of rows whose `user_id` is `:1`
+//! - `orders[status!=done].*` - every field of rows whose `status` is not `done`
+//!
+//! Filter values use ISON value syntax, so `:1` is a reference and `42` an integer.
+
+use crate::prelude::*;
+use crate::{Document, ISONError, Result, Value};
+
+/// A value matched by a selector, with its location
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selected<'a> {
+    pub block: &'a str,
+    pub row: usize,
+    pub field: &'a str,
+    pub value: &'a Value,
+}
+
+#[derive(Debug)]
+enum RowFilter {
+    All,
+    Index(i64),
+    Eq(String, Value),
+    Ne(String, Value),
+}
+
+#[derive(Debug)]
+struct Selector {
+    block: String,
+    filter: RowFilter,
+    field: Option<String>,
+}
+
+fn selector_error(path: &str, reason: &str) -> ISONError {
+    ISONError {
+        message: format!("Invalid selector '{}': {}", path, reason),
+        line: None,
+    }
+}
+
+impl Selector {
+    fn parse(path: &str) -> Result<Self> {
+        let path = path.trim();
+        let block_end = path.find(['[', '.']).unwrap_or(path.len());
+        let block = &path[..block_end];
+        if block.is_empty() {
+            return Err(selector_error(path, "missing block name"));
+        }
+
+        let mut rest = &path[block_end..];
+        let mut filter = RowFilter::All;
+        if let Some(inner) = rest.strip_prefix('[') {
+            let close = inner
+                .find(']')
+                .ok_or_else(|| selector_error(path, "unclosed '['"))?;
+            filter = Self::parse_filter(path, inner[..close].trim())?;
+            rest = &inner[close + 1..];
+        }
+
+        let field = match rest.strip_prefix('.') {
+            Some("") => return Err(selector_error(path, "missing field after '.'")),
+            Some("*") => None,
+            Some(field) => Some(field.to_string()),
+            None if rest.is_empty() => None,
+            None => return Err(selector_error(path, "expected '.' before field")),
+        };
+
+        Ok(Self {
+            block: block.to_string(),
+            filter,
+            field,
+        })
+    }
+
+    fn parse_filter(path: &str, filter: &str) -> Result<RowFilter> {
+        if filter == "*" {
+            return Ok(RowFilter::All);
+        }
+        if let Ok(index) = filter.parse::<i64>() {
+            return Ok(RowFilter::Index(index));
+        }
+        let (field, value, negate) = if let Some((f, v)) = filter.split_once("!=") {
+            (f, v, true)
+        } else if let Some((f, v)) = filter.split_once('=') {
+            (f, v, false)
+        } else {
+            return Err(selector_error(
+                path,
+                "expected '*', an index, or field=value",
+            ));
+        };
+        let field = field.trim().to_string();
+        if field.is_empty() {
+            return Err(selector_error(path, "missing filter field"));
+        }
+        let value = crate::parse_token(value.trim().trim_matches('"'))?;
+        Ok(if negate {
+            RowFilter::Ne(field, value)
+        } else {
+            RowFilter::Eq(field, value)
+        })
+    }
+}
+
+impl Document {
+    /// Select values by path, e.g. `users[*].email` or `orders[user_id=:1].price`.
+    ///
+    /// Returns an error for malformed selectors or unknown blocks; rows
+    /// without the selected field are skipped.
+    pub fn select(&self, path: &str) -> Result<Vec<Selected<'_>>> {
+        let selector = Selector::parse(path)?;
+        let block = self.get(&selector.block).ok_or_else(|| ISONError {
+            message: format!("Block not found: {}", selector.block),
+            line: None,
+        })?;
+
+        let rows: Vec<usize> = match &selector.filter {
+            RowFilter::All => (0..block.rows.len()).collect(),
+            RowFilter::Index(i) => {
+                let len = block.rows.len() as i64;
+                let idx = if *i < 0 { len + i } else { *i };
+                if (0..len).contains(&idx) {
+                    vec![idx as usize]
+                } else {
+                    Vec::new()
+                }
+            }
+            RowFilter::Eq(field, value) => (0..block.rows.len())
+                .filter(|&i| block.rows[i].get(field.as_str()) == Some(value))
+                .collect(),
+            RowFilter::Ne(field, value) => (0..block.rows.len())
+                .filter(|&i| block.rows[i].get(field.as_str()) != Some(value))
+                .collect(),
+        };
+
+        let fields: Vec<&String> = match &selector.field {
+            Some(field) => block.fields.iter().filter(|f| *f == field).collect(),
+            None => block.fields.iter().collect(),
+        };
+
+        let mut selected = Vec::new();
+        for row in rows {
+            for field in &fields {
+                if let Some(value) = block.rows[row].get(field.as_str()) {
+                    selected.push(Selected {
+                        block: &block.name,
+                        row,
+                        field,
+                        value,
+                    });
+                }
+            }
+        }
+        Ok(selected)
+    }
+
+    /// Values matched by [`Document::select`], without locations
+    pub fn select_values(&self, path: &str) -> Result<Vec<&Value>> {
+        Ok(self.select(path)?.into_iter().map(|s| s.value).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    const DOC: &str = "table.users\nid name email\n1 Alice alice@example.com\n2 Bob bob@example.com\n\n\
+                       table.orders\nid user_id price status\n10 :1 9.5 done\n11 :2 20 open\n12 :1 3 open";
+
+    #[test]
+    fn test_select_column_and_index() {
+        let doc = parse(DOC).unwrap();
+        let emails = doc.select_values("users[*].email").unwrap();
+        assert_eq!(emails.len(), 2);
+        assert_eq!(doc.select_values("users.email").unwrap(), emails);
+
+        let last = doc.select("users[-1].name").unwrap();
+        assert_eq!(last.len(), 1);
+        assert_eq!(last[0].row, 1);
+        assert_eq!(last[0].value, &Value::String("Bob".into()));
+        assert!(doc.select("users[5].name").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_select_filters() {
+        let doc = parse(DOC).unwrap();
+        let prices = doc.select_values("orders[user_id=:1].price").unwrap();
+        assert_eq!(prices, vec![&Value::Float(9.5), &Value::Int(3)]);
+
+        let open = doc.select("orders[status!=done].*").unwrap();
+        assert_eq!(open.len(), 8);
+        assert_eq!((open[0].row, open[0].field), (1, "id"));
+    }
+
+    #[test]
+    fn test_select_errors() {
+        let doc = parse(DOC).unwrap();
+        assert!(doc.select("missing.id").is_err());
+        assert!(doc.select("users[id=1.name").is_err());
+        assert!(doc.select("users[0]name").is_err());
+        assert!(doc.select("").is_err());
+    }
+}
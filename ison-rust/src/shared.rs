@@ -0,0 +1,163 @@
+//! Documents shared between threads
+//!
+//! A [`SharedDocument`] holds each block behind an `Arc`, so cloning one
+//! copies a list of pointers rather than rows. Mutation goes through
+//! [`SharedDocument::get_mut`], which copies a block only if another clone
+//! still shares it: a server can hand every request its own document and
+//! pay only for the blocks that request changes.
+
+use alloc::sync::Arc;
+
+use crate::prelude::*;
+use crate::{Block, Document};
+
+/// A [`Document`] whose blocks are shared copy-on-write between clones
+///
+/// ```rust
+/// use ison_rs::{SharedDocument, Value};
+///
+/// let doc = ison_rs::parse("table.users\nid\n1\n\ntable.orders\nid\n9")?;
+/// let base = SharedDocument::from(doc);
+/// let mut mine = base.clone();
+/// mine.get_mut("users").unwrap().rows[0].insert("id".into(), Value::Int(2));
+///
+/// assert_eq!(base["users"][0]["id"], Value::Int(1));
+/// assert!(mine.shares("orders", &base));
+/// assert!(!mine.shares("users", &base));
+/// # Ok::<(), ison_rs::ISONError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SharedDocument {
+    blocks: Vec<Arc<Block>>,
+}
+
+impl SharedDocument {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The blocks, in document order
+    pub fn blocks(&self) -> &[Arc<Block>] {
+        &self.blocks
+    }
+
+    /// Get block by name
+    pub fn get(&self, name: &str) -> Option<&Block> {
+        self.get_arc(name).map(|b| &**b)
+    }
+
+    /// The shared pointer to a block, to keep it beyond this document
+    pub fn get_arc(&self, name: &str) -> Option<&Arc<Block>> {
+        self.blocks.iter().find(|b| b.name == name)
+    }
+
+    /// Get mutable block by name, copying it first if it is shared
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Block> {
+        self.blocks
+            .iter_mut()
+            .find(|b| b.name == name)
+            .map(Arc::make_mut)
+    }
+
+    /// Whether block `name` is the same allocation in both documents
+    pub fn shares(&self, name: &str, other: &SharedDocument) -> bool {
+        match (self.get_arc(name), other.get_arc(name)) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+
+    pub fn push(&mut self, block: impl Into<Arc<Block>>) {
+        self.blocks.push(block.into());
+    }
+
+    /// Remove the first block named `name`
+    pub fn remove(&mut self, name: &str) -> Option<Arc<Block>> {
+        let index = self.blocks.iter().position(|b| b.name == name)?;
+        Some(self.blocks.remove(index))
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// A plain document, copying only the blocks that are still shared
+    pub fn into_document(self) -> Document {
+        Document {
+            blocks: self.blocks.into_iter().map(Arc::unwrap_or_clone).collect(),
+        }
+    }
+}
+
+impl From<Document> for SharedDocument {
+    fn from(doc: Document) -> Self {
+        Self {
+            blocks: doc.blocks.into_iter().map(Arc::new).collect(),
+        }
+    }
+}
+
+impl From<SharedDocument> for Document {
+    fn from(doc: SharedDocument) -> Self {
+        doc.into_document()
+    }
+}
+
+impl core::ops::Index<&str> for SharedDocument {
+    type Output = Block;
+
+    fn index(&self, name: &str) -> &Self::Output {
+        match self.get(name) {
+            Some(block) => block,
+            None => panic!("Block '{}' not found", name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dumps, parse, Value};
+
+    #[test]
+    fn test_shared_document() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SharedDocument>();
+
+        let doc = parse("table.a\nx\n1\n\ntable.b\ny\n2").unwrap();
+        let text = dumps(&doc, false);
+        let base = SharedDocument::from(doc);
+
+        let worker = {
+            let mut mine = base.clone();
+            std::thread::spawn(move || {
+                mine.get_mut("b").unwrap().rows[0].insert("y".into(), Value::Int(3));
+                mine
+            })
+        };
+        let mut changed = worker.join().unwrap();
+        assert!(changed.shares("a", &base));
+        assert!(!changed.shares("b", &base));
+        assert_eq!(changed["b"][0]["y"], Value::Int(3));
+        assert_eq!(dumps(&base.clone().into_document(), false), text);
+
+        // An unshared block is changed in place
+        let before = Arc::as_ptr(changed.get_arc("b").unwrap());
+        changed.get_mut("b").unwrap().rows.clear();
+        assert_eq!(Arc::as_ptr(changed.get_arc("b").unwrap()), before);
+
+        changed.push(Block::new("table", "c"));
+        assert_eq!(changed.remove("a").unwrap().name, "a");
+        let names: Vec<_> = Document::from(changed)
+            .blocks
+            .into_iter()
+            .map(|b| b.name)
+            .collect();
+        assert_eq!(names, ["b", "c"]);
+        assert!(base.get("c").is_none());
+    }
+}
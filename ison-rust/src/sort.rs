@@ -0,0 +1,257 @@
+//! Sorting rows by one or more fields
+//!
+//! Cells are compared by [`compare_values`], which orders every pair of
+//! values, so columns of mixed types sort without a hand-written comparator.
+
+use core::cmp::Ordering;
+
+use crate::prelude::*;
+use crate::{Block, ISONError, Result, Value};
+
+/// Direction of one sort key
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Where null (and absent) cells go, whatever the direction
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NullOrder {
+    First,
+    #[default]
+    Last,
+}
+
+/// Rank of a value's type: booleans, then numbers, strings, bytes, references
+fn type_rank(value: &Value) -> u8 {
+    match value {
+        Value::Null => 0,
+        Value::Bool(_) => 1,
+        Value::Int(_) | Value::Float(_) => 2,
+        #[cfg(feature = "rust_decimal")]
+        Value::Decimal(_) => 2,
+        Value::String(_) => 3,
+        Value::Bytes(_) => 4,
+        Value::Reference(_) => 5,
+    }
+}
+
+/// Order two values, for sorting; this is also `Value`'s [`Ord`].
+///
+/// Values of different types follow the type order null, bool, number,
+/// string, bytes, reference. Numbers compare by value across `Int`, `Float`,
+/// and `Decimal`; NaN comes after every other number, and numbers equal in
+/// value order `Int`, `Float`, `Decimal`. Strings compare by bytes, and
+/// references by id and then type.
+pub fn compare_values(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Bool(x), Value::Bool(y)) => x.cmp(y),
+        (Value::Int(x), Value::Int(y)) => x.cmp(y),
+        #[cfg(feature = "rust_decimal")]
+        (Value::Decimal(x), Value::Decimal(y)) => x.cmp(y),
+        (Value::String(x), Value::String(y)) => x.cmp(y),
+        (Value::Bytes(x), Value::Bytes(y)) => x.cmp(y),
+        (Value::Reference(x), Value::Reference(y)) => x.cmp(y),
+        _ if type_rank(a) == 2 && type_rank(b) == 2 => {
+            let (x, y) = (
+                a.as_float().unwrap_or(f64::NAN),
+                b.as_float().unwrap_or(f64::NAN),
+            );
+            let by_value = match (x.is_nan(), y.is_nan()) {
+                (false, false) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+                (nan_x, nan_y) => nan_x.cmp(&nan_y),
+            };
+            by_value.then_with(|| number_rank(a).cmp(&number_rank(b)))
+        }
+        _ => type_rank(a).cmp(&type_rank(b)),
+    }
+}
+
+/// Tie-break between numbers equal in value, so only equal values compare equal
+fn number_rank(value: &Value) -> u8 {
+    match value {
+        Value::Int(_) => 0,
+        Value::Float(_) => 1,
+        _ => 2,
+    }
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Value {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare_values(self, other)
+    }
+}
+
+impl Block {
+    /// Sort data rows by `keys`, each a field and direction, with nulls last.
+    ///
+    /// The sort is stable, so rows equal on every key keep their order.
+    ///
+    /// ```rust
+    /// use ison_rs::SortOrder::{Asc, Desc};
+    ///
+    /// let mut doc = ison_rs::parse("table.items\nname price\npen 2\nink 5\ncap 2")?;
+    /// let items = doc.get_mut("items").unwrap();
+    /// items.sort_by(&[("price", Desc), ("name", Asc)])?;
+    /// let names: Vec<_> = items.rows.iter().map(|r| r["name"].to_string()).collect();
+    /// assert_eq!(names, ["ink", "cap", "pen"]);
+    /// # Ok::<(), ison_rs::ISONError>(())
+    /// ```
+    pub fn sort_by(&mut self, keys: &[(&str, SortOrder)]) -> Result<()> {
+        self.sort_by_with_nulls(keys, NullOrder::Last)
+    }
+
+    /// [`sort_by`](Block::sort_by) with nulls placed by `nulls`
+    pub fn sort_by_with_nulls(
+        &mut self,
+        keys: &[(&str, SortOrder)],
+        nulls: NullOrder,
+    ) -> Result<()> {
+        if let Some((field, _)) = keys
+            .iter()
+            .find(|(f, _)| !self.fields.iter().any(|x| x == f))
+        {
+            return Err(ISONError {
+                message: format!("Block '{}': Unknown field '{}'", self.name, field),
+                line: None,
+            });
+        }
+
+        let mut order: Vec<usize> = (0..self.rows.len()).collect();
+        order.sort_by(|&i, &j| {
+            for &(field, direction) in keys {
+                let a = self.rows[i].get(field).unwrap_or(&Value::Null);
+                let b = self.rows[j].get(field).unwrap_or(&Value::Null);
+                let ordering = match (a.is_null(), b.is_null()) {
+                    (true, true) => Ordering::Equal,
+                    (true, false) | (false, true) => {
+                        let null_first = a.is_null().cmp(&b.is_null()).reverse();
+                        match nulls {
+                            NullOrder::First => null_first,
+                            NullOrder::Last => null_first.reverse(),
+                        }
+                    }
+                    (false, false) => match direction {
+                        SortOrder::Asc => compare_values(a, b),
+                        SortOrder::Desc => compare_values(b, a),
+                    },
+                };
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            Ordering::Equal
+        });
+
+        let mut rows: Vec<_> = self.rows.drain(..).map(Some).collect();
+        self.rows = order.iter().map(|&i| rows[i].take().unwrap()).collect();
+        self.number_text.reorder(&order);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_compare_values() {
+        let mut values = vec![
+            Value::String("b".into()),
+            Value::Float(f64::NAN),
+            Value::Int(3),
+            Value::Reference(crate::Reference::new("1")),
+            Value::Float(2.5),
+            Value::Bool(true),
+            Value::String("a".into()),
+            Value::Null,
+            Value::Int(-1),
+        ];
+        values.sort_by(compare_values);
+        let text: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+        assert_eq!(
+            text,
+            ["null", "true", "-1", "2.5", "3", "NaN", "a", "b", ":1"]
+        );
+    }
+
+    #[test]
+    fn test_value_traits() {
+        use alloc::collections::BTreeSet;
+        use std::collections::HashSet;
+
+        let nan = Value::Float(f64::NAN);
+        assert_eq!(nan, Value::Float(-f64::NAN));
+        assert_eq!(Value::Float(0.0), Value::Float(-0.0));
+        assert_ne!(Value::Int(1), Value::Float(1.0));
+        assert!(Value::Int(1) < Value::Float(1.0));
+        assert!(Value::Float(1.0) < Value::Int(2));
+        assert!(Value::Int(i64::MAX) < nan);
+
+        let values = [
+            nan.clone(),
+            Value::Float(f64::NAN),
+            Value::Float(0.0),
+            Value::Float(-0.0),
+            Value::Int(1),
+            Value::Float(1.0),
+            Value::Reference(crate::Reference::with_type("1", "user")),
+            Value::Reference(crate::Reference::new("1")),
+        ];
+        let hashed: HashSet<&Value> = values.iter().collect();
+        let ordered: BTreeSet<&Value> = values.iter().collect();
+        assert_eq!(hashed.len(), 6);
+        assert_eq!(ordered.len(), 6);
+        assert_eq!(ordered.first(), Some(&&Value::Float(-0.0)));
+    }
+
+    #[test]
+    fn test_sort_by() {
+        let text = "table.items\nname price\npen 2\nink ~\ncap 2\nbox 10.5\ntag \"x\"";
+        let names = |doc: &crate::Document| -> Vec<String> {
+            doc["items"]
+                .rows
+                .iter()
+                .map(|r| r["name"].to_string())
+                .collect()
+        };
+
+        let mut doc = parse(text).unwrap();
+        let items = doc.get_mut("items").unwrap();
+        items
+            .sort_by(&[("price", SortOrder::Desc), ("name", SortOrder::Asc)])
+            .unwrap();
+        assert_eq!(names(&doc), ["tag", "box", "cap", "pen", "ink"]);
+
+        let mut doc = parse(text).unwrap();
+        let items = doc.get_mut("items").unwrap();
+        items
+            .sort_by_with_nulls(&[("price", SortOrder::Asc)], NullOrder::First)
+            .unwrap();
+        assert!(items.sort_by(&[("weight", SortOrder::Asc)]).is_err());
+        assert_eq!(names(&doc), ["ink", "pen", "cap", "box", "tag"]);
+    }
+
+    #[test]
+    fn test_sort_keeps_number_text() {
+        let options = crate::ParseOptions {
+            lossless_numbers: true,
+            ..Default::default()
+        };
+        let mut doc = crate::parse_with_options("table.t\nx\n1.50\n0.10\n1.5", &options).unwrap();
+        doc.get_mut("t")
+            .unwrap()
+            .sort_by(&[("x", SortOrder::Asc)])
+            .unwrap();
+        assert_eq!(crate::dumps(&doc, false), "table.t\nx\n0.10\n1.50\n1.5");
+    }
+}
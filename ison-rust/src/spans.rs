@@ -0,0 +1,465 @@
+//! Source spans, symbols, and diagnostics for editor tooling
+//!
+//! [`parse_with_spans`] parses like [`parse`](crate::parse) and also records
+//! the byte range of every block, header, field definition, row, and cell,
+//! which is what a language server needs to highlight errors, list symbols,
+//! and jump from a reference to the row it points at. [`diagnostics`] keeps
+//! going past errors, reporting one per broken block, plus warnings for rows
+//! whose width does not match their header.
+//!
+//! Ranges are byte offsets into the parsed text. Cell and field ranges cover
+//! the token as written, quotes included.
+
+use core::ops::Range;
+
+use crate::prelude::*;
+use crate::{Document, ISONError, ParseOptions, Parser, Result, Value};
+
+/// Byte ranges of one block; see [`parse_with_spans`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockSpans {
+    /// Header through the last row
+    pub span: Range<usize>,
+    /// The `kind.name` line
+    pub header: Range<usize>,
+    pub kind: Range<usize>,
+    pub name: Range<usize>,
+    /// Each field definition (`id`, `email:string`), in header order; for
+    /// `key value` blocks, each key
+    pub fields: Vec<Range<usize>>,
+    pub rows: Vec<RowSpans>,
+    pub summary_rows: Vec<RowSpans>,
+    /// Whether the block is written as `key value` pairs, one field per line
+    pub pairs: bool,
+}
+
+/// Byte ranges of one row
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RowSpans {
+    /// The whole row, over several lines for `"""` strings
+    pub span: Range<usize>,
+    /// Each cell, in field order; tokens beyond the header's fields are
+    /// included, missing cells are not
+    pub cells: Vec<Range<usize>>,
+}
+
+/// Spans collected while parsing, kept by the parser
+#[derive(Debug, Default)]
+pub(crate) struct SpanRecorder {
+    blocks: Vec<BlockSpans>,
+    /// The line being parsed and its tokens
+    line: Range<usize>,
+    tokens: Vec<Range<usize>>,
+}
+
+impl SpanRecorder {
+    pub(crate) fn line(&mut self, line: Range<usize>, tokens: Vec<Range<usize>>) {
+        self.line = line;
+        self.tokens = tokens;
+    }
+
+    /// Start a block whose header is the current line
+    pub(crate) fn header(&mut self, kind: Range<usize>, name: Range<usize>) {
+        self.blocks.push(BlockSpans {
+            span: self.line.clone(),
+            header: self.line.clone(),
+            kind,
+            name,
+            ..Default::default()
+        });
+    }
+
+    fn block(&mut self) -> &mut BlockSpans {
+        let block = self
+            .blocks
+            .last_mut()
+            .expect("spans recorded after a header");
+        block.span.end = self.line.end;
+        block
+    }
+
+    /// The current line is the block's field definitions
+    pub(crate) fn fields(&mut self) {
+        let tokens = core::mem::take(&mut self.tokens);
+        self.block().fields = tokens;
+    }
+
+    /// The current line is a data or summary row
+    pub(crate) fn row(&mut self, summary: bool) {
+        let row = RowSpans {
+            span: self.line.clone(),
+            cells: core::mem::take(&mut self.tokens),
+        };
+        let block = self.block();
+        if summary {
+            block.summary_rows.push(row);
+        } else {
+            block.rows.push(row);
+        }
+    }
+
+    /// The current line is a `key value` pair setting field `index`
+    pub(crate) fn pair(&mut self, index: usize, is_new: bool, summary: bool) {
+        let (line, tokens) = (self.line.clone(), core::mem::take(&mut self.tokens));
+        let block = self.block();
+        block.pairs = true;
+        if is_new {
+            block.fields.push(tokens[0].clone());
+        }
+        let rows = if summary {
+            &mut block.summary_rows
+        } else {
+            &mut block.rows
+        };
+        if rows.is_empty() {
+            rows.push(RowSpans {
+                span: line.clone(),
+                cells: Vec::new(),
+            });
+        }
+        let row = &mut rows[0];
+        row.span.end = line.end;
+        if row.cells.len() <= index {
+            row.cells.resize(index + 1, line.end..line.end);
+        }
+        row.cells[index] = tokens[1].clone();
+    }
+
+    /// A `key value` block always has its data row, even with no pairs
+    pub(crate) fn object_row(&mut self) {
+        let block = self
+            .blocks
+            .last_mut()
+            .expect("spans recorded after a header");
+        block.pairs = true;
+        if block.rows.is_empty() {
+            block.rows.push(RowSpans {
+                span: block.span.end..block.span.end,
+                cells: Vec::new(),
+            });
+        }
+    }
+}
+
+/// A parsed document with the spans of its blocks; see [`parse_with_spans`]
+#[derive(Debug, Clone)]
+pub struct SpannedDocument {
+    pub document: Document,
+    /// One per block of `document`, in the same order
+    pub blocks: Vec<BlockSpans>,
+}
+
+/// What a [`Symbol`] names
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Block,
+    Field,
+}
+
+/// A block or field, for outlines and workspace symbol search
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub kind: SymbolKind,
+    /// `kind.name` for blocks, the field name for fields
+    pub name: String,
+    /// The whole block for a block, the definition for a field
+    pub span: Range<usize>,
+    /// Index of the block the symbol is, or belongs to
+    pub block: usize,
+}
+
+/// Position of a cell within a [`SpannedDocument`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellPosition {
+    pub block: usize,
+    pub row: usize,
+    /// Whether `row` counts summary rows
+    pub summary: bool,
+    pub field: usize,
+}
+
+impl SpannedDocument {
+    /// Every block, followed by its fields, in document order
+    pub fn symbols(&self) -> Vec<Symbol> {
+        let mut symbols = Vec::new();
+        for (index, (block, spans)) in self.document.blocks.iter().zip(&self.blocks).enumerate() {
+            symbols.push(Symbol {
+                kind: SymbolKind::Block,
+                name: format!("{}.{}", block.kind, block.name),
+                span: spans.span.clone(),
+                block: index,
+            });
+            for (field, span) in block.fields.iter().zip(&spans.fields) {
+                symbols.push(Symbol {
+                    kind: SymbolKind::Field,
+                    name: field.clone(),
+                    span: span.clone(),
+                    block: index,
+                });
+            }
+        }
+        symbols
+    }
+
+    /// The cell whose token contains byte `offset`
+    pub fn cell_at(&self, offset: usize) -> Option<CellPosition> {
+        let block = self.blocks.iter().position(|b| b.span.contains(&offset))?;
+        let spans = &self.blocks[block];
+        for (summary, rows) in [(false, &spans.rows), (true, &spans.summary_rows)] {
+            for (row, row_spans) in rows.iter().enumerate() {
+                if let Some(field) = row_spans.cells.iter().position(|c| c.contains(&offset)) {
+                    return Some(CellPosition {
+                        block,
+                        row,
+                        summary,
+                        field,
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    /// The value of the cell at `position`, if the row has that field
+    pub fn value(&self, position: CellPosition) -> Option<&Value> {
+        let block = self.document.blocks.get(position.block)?;
+        let rows = if position.summary {
+            &block.summary_rows
+        } else {
+            &block.rows
+        };
+        rows.get(position.row)?
+            .get(block.fields.get(position.field)?.as_str())
+    }
+
+    /// Go to definition: the span of the row that the reference at byte
+    /// `offset` [resolves](Document::resolve) to
+    pub fn definition(&self, offset: usize) -> Option<Range<usize>> {
+        let Value::Reference(reference) = self.value(self.cell_at(offset)?)? else {
+            return None;
+        };
+        let (block, row) = self.document.resolve(reference)?;
+        let index = self
+            .document
+            .blocks
+            .iter()
+            .position(|b| core::ptr::eq(b, block))?;
+        let row = block.rows.iter().position(|r| core::ptr::eq(r, row))?;
+        Some(self.blocks[index].rows.get(row)?.span.clone())
+    }
+}
+
+/// Parse `text` with the source span of every block, field, row, and cell.
+///
+/// The document is the one [`parse`](crate::parse) returns, and so are the
+/// errors.
+///
+/// ```rust
+/// let text = "table.users\nid name\n1 Alice\n\ntable.orders\nid user\n7 :1";
+/// let spanned = ison_rs::parse_with_spans(text)?;
+/// let name = &spanned.blocks[0].rows[0].cells[1];
+/// assert_eq!(&text[name.clone()], "Alice");
+///
+/// // Go to definition on `:1`
+/// let target = spanned.definition(text.find(":1").unwrap()).unwrap();
+/// assert_eq!(&text[target], "1 Alice");
+/// # Ok::<(), ison_rs::ISONError>(())
+/// ```
+pub fn parse_with_spans(text: &str) -> Result<SpannedDocument> {
+    parse_with_spans_and_options(text, &ParseOptions::default())
+}
+
+/// [`parse_with_spans`] under `options`, as in
+/// [`parse_with_options`](crate::parse_with_options)
+pub fn parse_with_spans_and_options(text: &str, options: &ParseOptions) -> Result<SpannedDocument> {
+    let mut parser = Parser::with_options(text, options.clone());
+    parser.spans = Some(SpanRecorder::default());
+    let document = parser.parse()?;
+    let blocks = parser.spans.take().map(|s| s.blocks).unwrap_or_default();
+    Ok(SpannedDocument { document, blocks })
+}
+
+/// How serious a [`Diagnostic`] is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem found by [`diagnostics`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// The line or token at fault
+    pub span: Range<usize>,
+    /// 1-based line of the start of `span`
+    pub line: usize,
+}
+
+fn line_of(text: &str, offset: usize) -> usize {
+    text[..offset].matches('\n').count() + 1
+}
+
+/// Problems in `text`, in document order.
+///
+/// Unlike [`parse`](crate::parse), this does not stop at the first error:
+/// each block is parsed on its own, so every broken block gets an error on
+/// the line at fault. Rows with more or fewer values than their header has
+/// fields get a warning.
+///
+/// ```rust
+/// use ison_rs::spans::Severity;
+///
+/// let found = ison_rs::diagnostics("table.a\nx y\n1 2 3\n\ntable.b\nv\n:a:b:c\n\nnot a header");
+/// let found: Vec<_> = found.iter().map(|d| (d.severity, d.line)).collect();
+/// assert_eq!(found, [(Severity::Warning, 3), (Severity::Error, 7), (Severity::Error, 9)]);
+/// ```
+pub fn diagnostics(text: &str) -> Vec<Diagnostic> {
+    diagnostics_with_options(text, &ParseOptions::default())
+}
+
+/// [`diagnostics`] for `text` read under `options`
+pub fn diagnostics_with_options(text: &str, options: &ParseOptions) -> Vec<Diagnostic> {
+    let mut found = Vec::new();
+    let mut report = |severity, message: String, span: Range<usize>| {
+        found.push(Diagnostic {
+            severity,
+            message,
+            line: line_of(text, span.start),
+            span,
+        });
+    };
+
+    let mut scanner = Parser::new(text);
+    scanner.skip_whitespace_and_comments();
+    while scanner.pos < text.len() {
+        let start = scanner.pos;
+        let header = scanner.parse_header();
+        // A broken header takes its block's lines with it
+        if !matches!(header, Ok(None)) {
+            scanner.skip_block();
+        }
+        let slice = &text[start..scanner.pos];
+        match header {
+            Err(e) => {
+                let line = slice.lines().next().unwrap_or("").trim_end();
+                report(Severity::Error, e.message, start..start + line.len());
+            }
+            Ok(None) => {}
+            Ok(Some(_)) => {
+                let mut parser = Parser::with_options(slice, options.clone());
+                parser.spans = Some(SpanRecorder::default());
+                let result = parser.parse();
+                let spans = parser.spans.take().unwrap_or_default();
+                let shift = |r: &Range<usize>| start + r.start..start + r.end;
+                match result {
+                    Err(ISONError { message, .. }) => {
+                        report(Severity::Error, message, shift(&spans.line));
+                    }
+                    Ok(_) => {
+                        for block in spans.blocks.iter().filter(|b| !b.pairs) {
+                            let fields = block.fields.len();
+                            for row in block.rows.iter().chain(&block.summary_rows) {
+                                if row.cells.len() != fields {
+                                    report(
+                                        Severity::Warning,
+                                        format!(
+                                            "Row has {} values, header has {} fields",
+                                            row.cells.len(),
+                                            fields
+                                        ),
+                                        shift(&row.span),
+                                    );
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        scanner.skip_whitespace_and_comments();
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dumps, parse_with_options};
+
+    #[test]
+    fn test_parse_with_spans() {
+        let text = "# users\ntable.users\nid name:string  email\n1 \"Alice A\" a@x.com # first\n\
+                    2 \"\"\"Bob\nB\"\"\" ~\n---\ntotal 2 ~\n\n\
+                    object.config\nkey value\nmode fast\nretries:int 3\n\n\
+                    table.orders\nid user\n7 :user:2\n8 :9";
+        let options = ParseOptions {
+            key_value_objects: true,
+            ..Default::default()
+        };
+        let spanned = parse_with_spans_and_options(text, &options).unwrap();
+        assert_eq!(
+            dumps(&spanned.document, false),
+            dumps(&parse_with_options(text, &options).unwrap(), false)
+        );
+        let at = |r: &Range<usize>| &text[r.clone()];
+
+        let users = &spanned.blocks[0];
+        assert_eq!(at(&users.header), "table.users");
+        assert_eq!((at(&users.kind), at(&users.name)), ("table", "users"));
+        let fields: Vec<_> = users.fields.iter().map(at).collect();
+        assert_eq!(fields, ["id", "name:string", "email"]);
+        assert_eq!(at(&users.rows[0].cells[1]), "\"Alice A\"");
+        assert_eq!(at(&users.rows[1].span), "2 \"\"\"Bob\nB\"\"\" ~");
+        assert_eq!(at(&users.summary_rows[0].cells[0]), "total");
+        assert!(
+            at(&users.span).starts_with("table.users") && at(&users.span).ends_with("total 2 ~")
+        );
+
+        let config = &spanned.blocks[1];
+        let keys: Vec<_> = config.fields.iter().map(at).collect();
+        assert_eq!(keys, ["mode", "retries:int"]);
+        assert_eq!(at(&config.rows[0].cells[1]), "3");
+
+        let symbols = spanned.symbols();
+        assert_eq!(symbols[0].name, "table.users");
+        assert_eq!(symbols[2].kind, SymbolKind::Field);
+        assert_eq!(at(&symbols[2].span), "name:string");
+        assert_eq!(symbols.len(), 10);
+
+        let reference = text.find(":user:2").unwrap() + 3;
+        let position = spanned.cell_at(reference).unwrap();
+        assert_eq!((position.block, position.row, position.field), (2, 0, 1));
+        assert_eq!(
+            at(&spanned.definition(reference).unwrap()),
+            "2 \"\"\"Bob\nB\"\"\" ~"
+        );
+        assert_eq!(spanned.definition(text.find(":9").unwrap()), None);
+        assert_eq!(spanned.definition(text.find("mode").unwrap()), None);
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let text = "table.a\nx:int y\n1 2\n\"two\" 3\n\n\
+                    bad header\nx\n1\n\n\
+                    table.b\nv w\n1\n---\n1 2 3\n\n\
+                    table.c\nr\n:a:b:c";
+        let found = diagnostics(text);
+        let summary: Vec<_> = found
+            .iter()
+            .map(|d| (d.severity, d.line, &text[d.span.clone()]))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (Severity::Error, 6, "bad header"),
+                (Severity::Warning, 12, "1"),
+                (Severity::Warning, 14, "1 2 3"),
+                (Severity::Error, 18, ":a:b:c"),
+            ]
+        );
+        assert_eq!(found[3].message, "Invalid reference: :a:b:c");
+        assert!(diagnostics("table.ok\nx\n1").is_empty());
+    }
+}
@@ -0,0 +1,775 @@
+//! An in-memory datastore
+//!
+//! [`IsonStore`] keeps a [`Document`] behind an `RwLock` so any number of
+//! threads can read it while writes take turns. Blocks can have a primary
+//! key, which must be unique, and secondary indexes; both map field values
+//! to rows and are kept up to date by the store's own edit methods.
+//! [`IsonStore::save`] writes the data back as ISON or ISONL, and a store
+//! opened with [`IsonStore::open_journaled`] also records each edit in a
+//! write-ahead [`Journal`] as it is made.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::journal::{Journal, JournalEntry};
+use crate::prelude::*;
+use crate::{
+    dumps, dumps_isonl, intern, parse, parse_isonl, Block, Document, ISONError, Result, Row, Value,
+};
+
+/// Field values of one row, in index field order; missing fields are null
+type Key = Vec<Value>;
+
+struct Index {
+    block: String,
+    fields: Vec<String>,
+    primary: bool,
+    rows: HashMap<Key, Vec<usize>>,
+}
+
+impl Index {
+    fn key(&self, row: &Row) -> Key {
+        self.fields
+            .iter()
+            .map(|f| row.get(f.as_str()).cloned().unwrap_or(Value::Null))
+            .collect()
+    }
+
+    /// Add row `i`; a primary key that is already taken is an error
+    fn add(&mut self, i: usize, row: &Row) -> Result<()> {
+        let key = self.key(row);
+        if self.primary && self.rows.contains_key(&key) {
+            return Err(duplicate_key(&self.block, &key));
+        }
+        let rows = self.rows.entry(key).or_default();
+        rows.push(i);
+        Ok(())
+    }
+
+    fn build(&mut self, block: &Block) -> Result<()> {
+        self.rows.clear();
+        for (i, row) in block.rows.iter().enumerate() {
+            self.add(i, row)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct State {
+    doc: Document,
+    indexes: Vec<Index>,
+    journal: Option<Journal>,
+}
+
+impl State {
+    fn block(&self, name: &str) -> Result<&Block> {
+        self.doc.get(name).ok_or_else(|| not_found(name))
+    }
+
+    /// Rebuild the indexes of block `name`
+    fn reindex(&mut self, name: &str) -> Result<()> {
+        let Some(block) = self.doc.get(name) else {
+            return Ok(());
+        };
+        for index in self.indexes.iter_mut().filter(|i| i.block == name) {
+            index.build(block)?;
+        }
+        Ok(())
+    }
+
+    /// Index of block `name` over exactly `fields`, in any order
+    fn index_for(&self, name: &str, fields: &[&str]) -> Option<&Index> {
+        self.indexes.iter().find(|i| {
+            i.block == name
+                && i.fields.len() == fields.len()
+                && i.fields.iter().all(|f| fields.contains(&f.as_str()))
+        })
+    }
+}
+
+/// A document shared by concurrent readers and writers, with indexes
+///
+/// ```rust
+/// use ison_rs::store::IsonStore;
+/// use ison_rs::Value;
+///
+/// let store = IsonStore::new(ison_rs::parse("table.users\nid name team\n1 Alice a\n2 Bob b\n3 Cy a")?);
+/// store.primary_key("users", &["id"])?;
+/// store.create_index("users", &["team"])?;
+///
+/// let bob = store.get("users", &[Value::Int(2)])?.unwrap();
+/// assert_eq!(bob["name"].as_str(), Some("Bob"));
+/// assert_eq!(store.find("users", &[("team", Value::from("a"))])?.len(), 2);
+/// assert_eq!(store.read(|doc| doc["users"].len()), 3);
+/// # Ok::<(), ison_rs::ISONError>(())
+/// ```
+#[derive(Default)]
+pub struct IsonStore {
+    state: RwLock<State>,
+}
+
+impl IsonStore {
+    pub fn new(doc: Document) -> Self {
+        Self {
+            state: RwLock::new(State {
+                doc,
+                indexes: Vec::new(),
+                journal: None,
+            }),
+        }
+    }
+
+    /// Load a file, as ISONL if its extension is `isonl` and ISON otherwise
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        let doc = if is_isonl(path) {
+            parse_isonl(&text)?
+        } else {
+            parse(&text)?
+        };
+        Ok(Self::new(doc))
+    }
+
+    /// Open a store whose edits are journaled: load `snapshot`, replay the
+    /// journal at `journal` onto it, and from then on append every edit to
+    /// the journal before it takes effect.
+    ///
+    /// Edits the journal cannot record fail and change nothing. Indexes are
+    /// not journaled; create them again after opening.
+    /// [`compact`](Self::compact) folds the journal into the snapshot.
+    pub fn open_journaled(snapshot: impl AsRef<Path>, journal: impl AsRef<Path>) -> Result<Self> {
+        let (journal, doc) = Journal::open(snapshot, journal)?;
+        let store = Self::new(doc);
+        store.write_state().journal = Some(journal);
+        Ok(store)
+    }
+
+    /// Write the document as the journal's new snapshot and clear the
+    /// journal; see [`Journal::compact`]
+    pub fn compact(&self) -> Result<()> {
+        let mut state = self.write_state();
+        let state = &mut *state;
+        match &mut state.journal {
+            Some(journal) => journal.compact(&state.doc),
+            None => Err(ISONError {
+                message: "IsonStore has no journal".to_string(),
+                line: None,
+            }),
+        }
+    }
+
+    fn read_state(&self) -> RwLockReadGuard<'_, State> {
+        self.state.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write_state(&self) -> RwLockWriteGuard<'_, State> {
+        // User closures run on copies, before anything changes, so one
+        // that panics poisons the lock but leaves the state whole
+        self.state.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Add the blocks of `doc`, replacing blocks of the same name.
+    ///
+    /// Indexes on replaced blocks are rebuilt. If a new block repeats a
+    /// primary key, nothing is loaded.
+    pub fn load(&self, doc: Document) -> Result<()> {
+        let mut state = self.write_state();
+        for block in &doc.blocks {
+            for index in state.indexes.iter().filter(|i| i.block == block.name) {
+                let mut check = Index {
+                    block: index.block.clone(),
+                    fields: index.fields.clone(),
+                    primary: index.primary,
+                    rows: HashMap::new(),
+                };
+                check.build(block)?;
+            }
+        }
+        let mut entries = Vec::new();
+        for block in &doc.blocks {
+            entries.push(JournalEntry::Define(block));
+            entries.extend(
+                block
+                    .rows
+                    .iter()
+                    .enumerate()
+                    .map(|(i, row)| JournalEntry::Insert(block, i, row)),
+            );
+        }
+        log(&mut state.journal, &entries)?;
+        for block in doc.blocks {
+            let name = block.name.clone();
+            match state.doc.get_mut(&name) {
+                Some(existing) => *existing = block,
+                None => state.doc.blocks.push(block),
+            }
+            state.reindex(&name)?;
+        }
+        Ok(())
+    }
+
+    /// Make `fields` the primary key of block `name`, replacing any
+    /// earlier one; fails if two rows share a key
+    pub fn primary_key(&self, name: &str, fields: &[&str]) -> Result<()> {
+        self.add_index(name, fields, true)
+    }
+
+    /// Index block `name` on `fields` for [`find`](Self::find)
+    pub fn create_index(&self, name: &str, fields: &[&str]) -> Result<()> {
+        self.add_index(name, fields, false)
+    }
+
+    fn add_index(&self, name: &str, fields: &[&str], primary: bool) -> Result<()> {
+        let mut state = self.write_state();
+        let block = state.block(name)?;
+        if let Some(missing) = fields
+            .iter()
+            .find(|f| !block.fields.iter().any(|b| b == *f))
+        {
+            return Err(no_field(name, missing));
+        }
+        let mut index = Index {
+            block: name.to_string(),
+            fields: fields.iter().map(|f| f.to_string()).collect(),
+            primary,
+            rows: HashMap::new(),
+        };
+        index.build(block)?;
+        state
+            .indexes
+            .retain(|i| !(i.block == name && (i.fields == index.fields || primary && i.primary)));
+        state.indexes.push(index);
+        Ok(())
+    }
+
+    /// Run `f` with shared access to the document
+    pub fn read<R>(&self, f: impl FnOnce(&Document) -> R) -> R {
+        f(&self.read_state().doc)
+    }
+
+    /// A copy of the document
+    pub fn to_document(&self) -> Document {
+        self.read_state().doc.clone()
+    }
+
+    /// Row of block `name` whose primary key is `key`
+    pub fn get(&self, name: &str, key: &[Value]) -> Result<Option<Row>> {
+        let state = self.read_state();
+        let block = state.block(name)?;
+        let index = state
+            .indexes
+            .iter()
+            .find(|i| i.block == name && i.primary)
+            .ok_or_else(|| ISONError {
+                message: format!("Block '{}' has no primary key", name),
+                line: None,
+            })?;
+        Ok(index
+            .rows
+            .get(key)
+            .and_then(|rows| rows.first())
+            .map(|&i| block.rows[i].clone()))
+    }
+
+    /// Rows of block `name` with the given field values, in block order.
+    ///
+    /// Uses an index over exactly those fields if there is one, and scans
+    /// the block otherwise.
+    pub fn find(&self, name: &str, values: &[(&str, Value)]) -> Result<Vec<Row>> {
+        let state = self.read_state();
+        let block = state.block(name)?;
+        let fields: Vec<&str> = values.iter().map(|(f, _)| *f).collect();
+        let rows = match state.index_for(name, &fields) {
+            Some(index) => {
+                let key: Key = index
+                    .fields
+                    .iter()
+                    .map(|f| {
+                        let (_, v) = values.iter().find(|(q, _)| q == f).unwrap();
+                        v.clone()
+                    })
+                    .collect();
+                index
+                    .rows
+                    .get(&key)
+                    .map(|rows| rows.iter().map(|&i| block.rows[i].clone()).collect())
+                    .unwrap_or_default()
+            }
+            None => block
+                .rows
+                .iter()
+                .filter(|row| {
+                    values
+                        .iter()
+                        .all(|(f, v)| row.get(*f).unwrap_or(&Value::Null) == v)
+                })
+                .cloned()
+                .collect(),
+        };
+        Ok(rows)
+    }
+
+    /// Append a data row to block `name`; every key must be one of its
+    /// fields, and the primary key must be new
+    pub fn insert(&self, name: &str, row: Row) -> Result<()> {
+        let mut state = self.write_state();
+        let state = &mut *state;
+        let block = state.doc.get_mut(name).ok_or_else(|| not_found(name))?;
+        if let Some(unknown) = row
+            .keys()
+            .find(|k| !block.fields.iter().any(|f| f == &***k))
+        {
+            return Err(no_field(name, unknown));
+        }
+        let keys = block.row_keys();
+        let row: Row = row
+            .into_iter()
+            .map(|(k, v)| (intern(&keys, &k), v))
+            .collect();
+
+        let indexes: Vec<&mut Index> = state
+            .indexes
+            .iter_mut()
+            .filter(|i| i.block == name)
+            .collect();
+        if let Some(index) = indexes
+            .iter()
+            .find(|i| i.primary && i.rows.contains_key(&i.key(&row)))
+        {
+            return Err(duplicate_key(name, &index.key(&row)));
+        }
+        let i = block.rows.len();
+        log(&mut state.journal, &[JournalEntry::Insert(block, i, &row)])?;
+        for index in indexes {
+            index.add(i, &row)?;
+        }
+        block.rows.push(row);
+        Ok(())
+    }
+
+    /// [`Block::update_where`] on block `name`. Updated rows may only use
+    /// the block's fields, like [`insert`](Self::insert); if the result
+    /// repeats a primary key, the rows are restored and an error returned.
+    pub fn update_where(
+        &self,
+        name: &str,
+        mut predicate: impl FnMut(&Row) -> bool,
+        mut update: impl FnMut(&mut Row),
+    ) -> Result<usize> {
+        let mut state = self.write_state();
+        let block = state.doc.get_mut(name).ok_or_else(|| not_found(name))?;
+        let mut updated = Vec::new();
+        for (i, row) in block.rows.iter().enumerate() {
+            if predicate(row) {
+                let mut row = row.clone();
+                update(&mut row);
+                updated.push((i, row));
+            }
+        }
+        let keys = block.row_keys();
+        for (_, row) in &mut updated {
+            if let Some(unknown) = row
+                .keys()
+                .find(|k| !block.fields.iter().any(|f| f == &***k))
+            {
+                return Err(no_field(name, unknown));
+            }
+            *row = core::mem::take(row)
+                .into_iter()
+                .map(|(k, v)| (intern(&keys, &k), v))
+                .collect();
+        }
+        let before: Vec<_> = updated
+            .into_iter()
+            .map(|(i, row)| (i, core::mem::replace(&mut block.rows[i], row)))
+            .collect();
+        let logged = state.reindex(name).and_then(|()| {
+            let state = &mut *state;
+            let block = state.doc.get(name).unwrap();
+            let entries: Vec<_> = before
+                .iter()
+                .filter(|(i, row)| block.rows[*i] != *row)
+                .map(|(i, _)| JournalEntry::Update(block, *i, &block.rows[*i]))
+                .collect();
+            log(&mut state.journal, &entries)
+        });
+        if let Err(e) = logged {
+            let block = state.doc.get_mut(name).unwrap();
+            for (i, row) in before {
+                block.rows[i] = row;
+            }
+            state.reindex(name)?;
+            return Err(e);
+        }
+        Ok(before.len())
+    }
+
+    /// [`Block::delete_where`] on block `name`
+    pub fn delete_where(
+        &self,
+        name: &str,
+        mut predicate: impl FnMut(&Row) -> bool,
+    ) -> Result<usize> {
+        let mut state = self.write_state();
+        let state = &mut *state;
+        let block = state.doc.get_mut(name).ok_or_else(|| not_found(name))?;
+        let deleted: Vec<bool> = block.rows.iter().map(&mut predicate).collect();
+        // Last first, so each index is still valid when replayed
+        let entries: Vec<_> = (0..deleted.len())
+            .rev()
+            .filter(|&i| deleted[i])
+            .map(|i| JournalEntry::Delete(block, i))
+            .collect();
+        let count = entries.len();
+        if count == 0 {
+            return Ok(0);
+        }
+        log(&mut state.journal, &entries)?;
+        let mut i = 0;
+        block.rows.retain(|_| {
+            i += 1;
+            !deleted[i - 1]
+        });
+        state.reindex(name)?;
+        Ok(count)
+    }
+
+    /// Remove block `name` and its indexes
+    pub fn remove_block(&self, name: &str) -> Result<Option<Block>> {
+        let mut state = self.write_state();
+        let state = &mut *state;
+        let Some(block) = state.doc.get(name) else {
+            return Ok(None);
+        };
+        log(&mut state.journal, &[JournalEntry::Drop(block)])?;
+        state.indexes.retain(|i| i.block != name);
+        Ok(state.doc.remove_block(name))
+    }
+
+    /// Write the document to `path`, as ISONL if its extension is `isonl`
+    /// and ISON otherwise.
+    ///
+    /// The text goes to a temporary file next to `path` that is then
+    /// renamed over it, so a crash leaves the old file or the new one.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let text = {
+            let state = self.read_state();
+            if is_isonl(path) {
+                dumps_isonl(&state.doc)
+            } else {
+                dumps(&state.doc, false)
+            }
+        };
+        write_atomic(path, &text)
+    }
+}
+
+impl core::fmt::Debug for IsonStore {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let state = self.read_state();
+        let indexes: Vec<_> = state
+            .indexes
+            .iter()
+            .map(|i| format!("{}({})", i.block, i.fields.join(",")))
+            .collect();
+        f.debug_struct("IsonStore")
+            .field("doc", &state.doc)
+            .field("indexes", &indexes)
+            .finish()
+    }
+}
+
+impl From<Document> for IsonStore {
+    fn from(doc: Document) -> Self {
+        Self::new(doc)
+    }
+}
+
+/// Append `entries` to the journal, if there is one
+fn log(journal: &mut Option<Journal>, entries: &[JournalEntry]) -> Result<()> {
+    match journal {
+        Some(journal) if !entries.is_empty() => journal.append(entries),
+        _ => Ok(()),
+    }
+}
+
+fn is_isonl(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "isonl")
+}
+
+/// Replace `path` with `text` by way of a temporary file, returning once
+/// the rename itself is on disk
+pub(crate) fn write_atomic(path: &Path, text: &str) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    std::fs::write(&tmp, text)?;
+    std::fs::File::open(&tmp)?.sync_all()?;
+    std::fs::rename(&tmp, path)?;
+    // The rename is an entry in the directory, which needs its own sync
+    // (not possible on Windows, where directories cannot be opened as files)
+    #[cfg(unix)]
+    {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        std::fs::File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+fn not_found(name: &str) -> ISONError {
+    ISONError {
+        message: format!("Block '{}' not found", name),
+        line: None,
+    }
+}
+
+fn no_field(name: &str, field: &str) -> ISONError {
+    ISONError {
+        message: format!("Block '{}' has no field '{}'", name, field),
+        line: None,
+    }
+}
+
+fn duplicate_key(name: &str, key: &[Value]) -> ISONError {
+    let parts: Vec<String> = key.iter().map(|v| v.to_string()).collect();
+    ISONError {
+        message: format!(
+            "Duplicate primary key ({}) in block '{}'",
+            parts.join(", "),
+            name
+        ),
+        line: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    fn row(cells: &[(&str, Value)]) -> Row {
+        cells
+            .iter()
+            .map(|(k, v)| ((*k).into(), v.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_store() {
+        let store =
+            IsonStore::new(parse("table.users\nid name team\n1 Alice a\n2 Bob b\n3 Cy a").unwrap());
+        store.primary_key("users", &["id"]).unwrap();
+        store.create_index("users", &["team"]).unwrap();
+        assert!(store.primary_key("users", &["team"]).is_err());
+        assert!(store.create_index("users", &["email"]).is_err());
+
+        store
+            .insert(
+                "users",
+                row(&[
+                    ("id", Value::Int(4)),
+                    ("name", "Di".into()),
+                    ("team", "b".into()),
+                ]),
+            )
+            .unwrap();
+        let err = store
+            .insert(
+                "users",
+                row(&[("id", Value::Int(4)), ("name", "Ed".into())]),
+            )
+            .unwrap_err();
+        assert_eq!(err.message, "Duplicate primary key (4) in block 'users'");
+        assert!(store
+            .insert("users", row(&[("email", "x".into())]))
+            .is_err());
+
+        let team = |t: &str| {
+            let rows = store.find("users", &[("team", Value::from(t))]).unwrap();
+            rows.iter()
+                .map(|r| r["name"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(team("b"), ["Bob", "Di"]);
+
+        // Deletes and updates shift rows; the indexes follow
+        assert_eq!(
+            store
+                .delete_where("users", |r| r["id"] == Value::Int(1))
+                .unwrap(),
+            1
+        );
+        store
+            .update_where(
+                "users",
+                |r| r["id"] == Value::Int(2),
+                |r| {
+                    r.insert("team".into(), "a".into());
+                },
+            )
+            .unwrap();
+        assert_eq!(team("a"), ["Bob", "Cy"]);
+        assert_eq!(
+            store.get("users", &[Value::Int(4)]).unwrap().unwrap()["name"],
+            Value::from("Di")
+        );
+        assert!(store.get("users", &[Value::Int(1)]).unwrap().is_none());
+
+        // An update that repeats a primary key is undone
+        let err = store
+            .update_where(
+                "users",
+                |_| true,
+                |r| {
+                    r.insert("id".into(), Value::Int(9));
+                },
+            )
+            .unwrap_err();
+        assert!(err.message.starts_with("Duplicate primary key (9)"));
+        assert!(store.get("users", &[Value::Int(3)]).unwrap().is_some());
+
+        // As with insert, updated rows keep to the block's fields
+        let err = store
+            .update_where(
+                "users",
+                |_| true,
+                |r| {
+                    r.insert("email".into(), "x".into());
+                },
+            )
+            .unwrap_err();
+        assert_eq!(err.message, "Block 'users' has no field 'email'");
+
+        // A closure that panics changes nothing, and the store stays usable
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            store.update_where(
+                "users",
+                |_| true,
+                |r| {
+                    r.insert("team".into(), "z".into());
+                    if r["id"] == Value::Int(3) {
+                        panic!("update failed");
+                    }
+                },
+            )
+        }));
+        assert!(panicked.is_err());
+        assert_eq!(team("a"), ["Bob", "Cy"]);
+        assert!(std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            store.delete_where("users", |_| panic!("predicate failed"))
+        }))
+        .is_err());
+        assert_eq!(store.read(|doc| doc["users"].len()), 3);
+
+        // Scans without an index; load replaces a block and reindexes it
+        assert_eq!(
+            store
+                .find("users", &[("name", Value::from("Cy"))])
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(store
+            .load(parse("table.users\nid name\n1 A\n1 B").unwrap())
+            .is_err());
+        store
+            .load(parse("table.users\nid name team\n7 Gil c\n\ntable.logs\nmsg\nhi").unwrap())
+            .unwrap();
+        assert_eq!(team("c"), ["Gil"]);
+        assert!(store.get("logs", &[]).is_err());
+        assert_eq!(store.remove_block("logs").unwrap().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_store_threads_and_files() {
+        let store = Arc::new(IsonStore::new(parse("table.n\nid\n0").unwrap()));
+        store.primary_key("n", &["id"]).unwrap();
+        let workers: Vec<_> = (1..=4)
+            .map(|t| {
+                let store = store.clone();
+                std::thread::spawn(move || {
+                    for i in 0..25 {
+                        store
+                            .insert("n", row(&[("id", Value::Int(t * 100 + i))]))
+                            .unwrap();
+                        assert!(store.read(|doc| doc["n"].len()) > 1);
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        assert_eq!(store.read(|doc| doc["n"].len()), 101);
+
+        let dir = std::env::temp_dir().join(format!("ison-store-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for file in ["data.ison", "data.isonl"] {
+            let path = dir.join(file);
+            store.save(&path).unwrap();
+            let reopened = IsonStore::open(&path).unwrap();
+            assert_eq!(
+                dumps(&reopened.to_document(), false),
+                dumps(&store.to_document(), false)
+            );
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_store_journal() {
+        let dir = std::env::temp_dir().join(format!("ison-store-journal-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (snapshot, journal) = (dir.join("data.ison"), dir.join("data.isonl"));
+        std::fs::write(&snapshot, "table.users\nid name\n1 Alice\n2 Bob\n3 Cy").unwrap();
+
+        let store = IsonStore::open_journaled(&snapshot, &journal).unwrap();
+        store.primary_key("users", &["id"]).unwrap();
+        store
+            .insert(
+                "users",
+                row(&[("id", Value::Int(4)), ("name", "Di".into())]),
+            )
+            .unwrap();
+        // Rejected edits are not journaled
+        assert!(store
+            .insert("users", row(&[("id", Value::Int(4))]))
+            .is_err());
+        store
+            .update_where(
+                "users",
+                |r| r["id"] == Value::Int(2),
+                |r| {
+                    r.insert("name".into(), "Robert".into());
+                },
+            )
+            .unwrap();
+        store
+            .delete_where("users", |r| r["id"].as_int() != Some(2))
+            .unwrap();
+        store
+            .load(parse("table.logs\nmsg\nstart\n\ntable.tmp\nx\n1").unwrap())
+            .unwrap();
+        store.remove_block("tmp").unwrap();
+        let expected = dumps(&store.to_document(), false);
+        drop(store);
+
+        let store = IsonStore::open_journaled(&snapshot, &journal).unwrap();
+        assert_eq!(dumps(&store.to_document(), false), expected);
+        store.compact().unwrap();
+        assert_eq!(std::fs::read_to_string(&journal).unwrap(), "#journal: 1\n");
+        drop(store);
+        let store = IsonStore::open_journaled(&snapshot, &journal).unwrap();
+        assert_eq!(dumps(&store.to_document(), false), expected);
+
+        assert!(IsonStore::new(Document::new()).compact().is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
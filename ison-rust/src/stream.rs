@@ -0,0 +1,369 @@
+//! Streaming ISON ⇄ ISONL conversion
+//!
+//! [`ison_to_isonl`](crate::ison_to_isonl) and
+//! [`isonl_to_ison`](crate::isonl_to_ison) build a whole [`Document`](crate::Document)
+//! first. The functions here read and write a row at a time instead, so memory
+//! stays bounded by the longest row (or `key value` object block) however
+//! large the input is.
+//!
+//! The output matches the in-memory conversions, with a newline after every
+//! line, except where that needs the whole document: ISON to ISONL leaves
+//! document metadata where it is instead of moving it first, and ISONL to ISON
+//! starts a new block whenever the lines switch blocks, where `isonl_to_ison`
+//! gathers each block's rows together.
+
+use std::io::{BufRead, Write};
+
+use crate::prelude::*;
+use crate::{
+    dumps_isonl, ends_block, is_pairs_header, isonl_prefix, Block, CellType, FieldName, ISONError,
+    IsonlReader, ParseOptions, Parser, Result, Row, Serializer,
+};
+
+/// Where the ISON reader is
+enum State {
+    /// Outside any block
+    Between,
+    /// After a block header, before its fields
+    Header {
+        block: Block,
+        text: String,
+        start: usize,
+    },
+    /// In the rows of a table
+    Rows {
+        block: Block,
+        prefix: String,
+        keys: Vec<FieldName>,
+        cell_types: Vec<CellType>,
+        in_summary: bool,
+    },
+    /// In a `key value` block, collected whole; `start` is its header's line
+    Pairs { text: String, start: usize },
+}
+
+/// Convert ISON from `reader` to ISONL on `writer`, row by row.
+///
+/// Summary rows are dropped, as ISONL has no place for them.
+///
+/// ```rust
+/// let ison = "table.users\nid name\n1 Alice\n2 Bob\n";
+/// let mut isonl = Vec::new();
+/// ison_rs::convert_ison_to_isonl(ison.as_bytes(), &mut isonl)?;
+/// assert_eq!(isonl, b"table.users|id name|1 Alice\ntable.users|id name|2 Bob\n");
+/// # Ok::<(), ison_rs::ISONError>(())
+/// ```
+pub fn convert_ison_to_isonl<R: BufRead, W: Write>(reader: R, writer: W) -> Result<()> {
+    convert_ison_to_isonl_with_options(reader, writer, &ParseOptions::default())
+}
+
+/// [`convert_ison_to_isonl`], reading cells under `options`' dialect and
+/// number policies and `key value` blocks under
+/// [`key_value_objects`](ParseOptions::key_value_objects).
+///
+/// Field renames, field and row filters, and progress reporting are not
+/// applied.
+pub fn convert_ison_to_isonl_with_options<R: BufRead, W: Write>(
+    reader: R,
+    mut writer: W,
+    options: &ParseOptions,
+) -> Result<()> {
+    let serializer = Serializer::new(false);
+    let options = ParseOptions {
+        field_aliases: Default::default(),
+        include_fields: Default::default(),
+        row_filter: None,
+        progress: None,
+        cancel: None,
+        ..options.clone()
+    };
+    let parser = Parser::with_options("", options.clone());
+    let mut lines = SourceLines::new(reader);
+    let mut state = State::Between;
+
+    while let Some(line) = lines.next()? {
+        let trimmed = line.trim();
+        state = match state {
+            State::Between if trimmed.is_empty() || trimmed.starts_with('#') => State::Between,
+            State::Between => State::Header {
+                block: block_header(trimmed, lines.line)?,
+                start: lines.line,
+                text: line,
+            },
+
+            State::Header {
+                mut block,
+                mut text,
+                start,
+            } => {
+                text.push('\n');
+                text.push_str(&line);
+                let tokens = parser.tokenize_line(trimmed);
+                // Only spaces, tabs, and comments are skipped before the fields
+                let rest = line.trim_start_matches([' ', '\t', '\r']);
+                if rest.is_empty() || rest.starts_with('#') {
+                    State::Header { block, text, start }
+                } else if options.key_value_objects
+                    && is_pairs_header(&block.kind, tokens.iter().map(|t| &**t))
+                {
+                    State::Pairs { text, start }
+                } else {
+                    for token in &tokens {
+                        block.add_field_def(token);
+                    }
+                    State::Rows {
+                        prefix: isonl_prefix(&block),
+                        keys: block.field_keys(),
+                        cell_types: block.cell_types(),
+                        block,
+                        in_summary: false,
+                    }
+                }
+            }
+
+            State::Rows { .. } | State::Pairs { .. } if ends_block(trimmed) => {
+                if let State::Pairs { text, start } = &state {
+                    write_pairs(text, *start, &options, &mut writer)?;
+                }
+                lines.push_back(line);
+                State::Between
+            }
+
+            State::Pairs { mut text, start } => {
+                text.push('\n');
+                text.push_str(&lines.continue_block_string(line)?);
+                State::Pairs { text, start }
+            }
+
+            rows @ State::Rows { .. } if trimmed.starts_with('#') => rows,
+            State::Rows {
+                block,
+                prefix,
+                keys,
+                cell_types,
+                mut in_summary,
+            } => {
+                let row_line = lines.line;
+                let full = lines.continue_block_string(line.trim_start().to_string())?;
+                let tokens = parser.tokenize_line(&full);
+                if full.trim() == "---" {
+                    in_summary = true;
+                } else if tokens.is_empty() {
+                    // A line of nothing but an empty block string ends the block
+                    state = State::Between;
+                    continue;
+                } else if !in_summary {
+                    let mut row = Row::new();
+                    for ((key, token), &cell_type) in keys.iter().zip(&tokens).zip(&cell_types) {
+                        let value = parser.parse_cell(token, cell_type).map_err(|e| ISONError {
+                            line: Some(row_line),
+                            ..e
+                        })?;
+                        row.insert(key.clone(), value);
+                    }
+                    let values = serializer.serialize_values(&row, &block.fields);
+                    writeln!(writer, "{}{}", prefix, values)?;
+                }
+                State::Rows {
+                    block,
+                    prefix,
+                    keys,
+                    cell_types,
+                    in_summary,
+                }
+            }
+        };
+    }
+
+    if let State::Pairs { text, start } = state {
+        write_pairs(&text, start, &options, &mut writer)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Convert ISONL from `reader` to ISON on `writer`, line by line.
+///
+/// Each run of lines for the same block becomes one ISON block.
+///
+/// ```rust
+/// let isonl = "table.users|id name|1 Alice\ntable.users|id name|2 Bob\n";
+/// let mut ison = Vec::new();
+/// ison_rs::convert_isonl_to_ison(isonl.as_bytes(), &mut ison)?;
+/// assert_eq!(ison, b"table.users\nid name\n1 Alice\n2 Bob\n");
+/// # Ok::<(), ison_rs::ISONError>(())
+/// ```
+pub fn convert_isonl_to_ison<R: BufRead, W: Write>(reader: R, mut writer: W) -> Result<()> {
+    let serializer = Serializer::new(false);
+    let mut isonl = IsonlReader::default();
+    let mut current = None;
+
+    for (line_num, line) in reader.lines().enumerate() {
+        let Some((block_idx, row)) = isonl.read_line(&line?, line_num + 1)? else {
+            continue;
+        };
+        let block = &isonl.blocks[block_idx].block;
+        if current != Some(block_idx) {
+            if current.is_some() {
+                writeln!(writer)?;
+            }
+            writeln!(writer, "{}", serializer.serialize_block(block))?;
+            current = Some(block_idx);
+        }
+        writeln!(
+            writer,
+            "{}",
+            serializer.serialize_values(&row, &block.fields)
+        )?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// A block with the kind and name from a header line
+fn block_header(line: &str, line_num: usize) -> Result<Block> {
+    let invalid = || ISONError {
+        message: format!("Invalid block header: {}", line),
+        line: Some(line_num),
+    };
+    let (kind, name) = line.split_once('.').ok_or_else(invalid)?;
+    let (kind, name) = (kind.trim(), name.trim());
+    if kind.is_empty() || name.is_empty() {
+        return Err(invalid());
+    }
+    Ok(Block::new(kind, name))
+}
+
+/// Parse a collected `key value` block and write it as ISONL
+fn write_pairs(
+    text: &str,
+    start: usize,
+    options: &ParseOptions,
+    writer: &mut impl Write,
+) -> Result<()> {
+    let doc = Parser::with_options(text, options.clone())
+        .parse()
+        .map_err(|e| ISONError {
+            line: e.line.map(|line| line + start - 1),
+            ..e
+        })?;
+    let isonl = dumps_isonl(&doc);
+    if !isonl.is_empty() {
+        writeln!(writer, "{}", isonl)?;
+    }
+    Ok(())
+}
+
+/// Lines of a reader, numbered from 1, with one line of push-back.
+///
+/// Only `\n` is removed: like the in-memory parser, a `\r` before it stays
+/// part of the line, which matters inside `"""` strings.
+struct SourceLines<R> {
+    reader: R,
+    pushed_back: Option<String>,
+    /// Number of the line last returned
+    line: usize,
+}
+
+impl<R: BufRead> SourceLines<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            pushed_back: None,
+            line: 0,
+        }
+    }
+
+    fn next(&mut self) -> Result<Option<String>> {
+        if let Some(line) = self.pushed_back.take() {
+            return Ok(Some(line));
+        }
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        if line.ends_with('\n') {
+            line.pop();
+        }
+        self.line += 1;
+        Ok(Some(line))
+    }
+
+    /// Hand back the line last returned, to be returned again
+    fn push_back(&mut self, line: String) {
+        self.pushed_back = Some(line);
+    }
+
+    /// Extend `line` through the line that closes any `"""` string it opens,
+    /// or to the end of the input
+    fn continue_block_string(&mut self, mut line: String) -> Result<String> {
+        while Parser::scan_line(&line).1 {
+            let Some(next) = self.next()? else {
+                break;
+            };
+            line.push('\n');
+            line.push_str(&next);
+        }
+        Ok(line.trim_end().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ison_to_isonl, isonl_to_ison};
+
+    fn to_isonl(ison: &str) -> Result<String> {
+        let mut out = Vec::new();
+        convert_ison_to_isonl(ison.as_bytes(), &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_convert_ison_to_isonl() {
+        let ison =
+            "# users\ntable.users\n\nid:int name   note\n1 Alice \"\"\"\nline one\n\nx.y\"\"\"\n\
+                    # comment\n2  \"Bob B\" ~\n---\ntotal 2 ~\n\n\
+                    object.config\nkey value\nmode \"\"\"\nfast\"\"\"\nretries 3\napp.name demo\n\
+                    table.empty\nid";
+        let streamed = to_isonl(ison).unwrap();
+        assert_eq!(streamed, format!("{}\n", ison_to_isonl(ison).unwrap()));
+        let options = ParseOptions {
+            key_value_objects: true,
+            ..Default::default()
+        };
+        let mut pairs = Vec::new();
+        convert_ison_to_isonl_with_options(ison.as_bytes(), &mut pairs, &options).unwrap();
+        let doc = crate::parse_with_options(ison, &options).unwrap();
+        assert_eq!(pairs, format!("{}\n", dumps_isonl(&doc)).into_bytes());
+        assert!(String::from_utf8(pairs)
+            .unwrap()
+            .contains("object.config|mode retries app.name|fast 3 demo\n"));
+        assert!(
+            streamed.starts_with("table.users|id:int name note|1 Alice \"line one\\n\\nx.y\"\n")
+        );
+
+        let err = to_isonl("table.t\nid\n1\n\nbad header").unwrap_err();
+        assert_eq!(err.line, Some(5));
+        let err = to_isonl("table.t\nid:bytes\nb64:***").unwrap_err();
+        assert_eq!(err.line, Some(3));
+    }
+
+    #[test]
+    fn test_convert_isonl_to_ison() {
+        let isonl = "table.a|x|1\ntable.a|x|2\n# note\ntable.b|y:float|3.5\n";
+        let mut out = Vec::new();
+        convert_isonl_to_ison(isonl.as_bytes(), &mut out).unwrap();
+        let streamed = String::from_utf8(out).unwrap();
+        assert_eq!(streamed, format!("{}\n", isonl_to_ison(isonl).unwrap()));
+
+        // Interleaved blocks reopen instead of regrouping
+        let mut out = Vec::new();
+        convert_isonl_to_ison("table.a|x|1\ntable.b|y|2\ntable.a|x|3".as_bytes(), &mut out)
+            .unwrap();
+        assert_eq!(out, b"table.a\nx\n1\n\ntable.b\ny\n2\n\ntable.a\nx\n3\n");
+
+        let err = convert_isonl_to_ison("table.a|x|1\noops".as_bytes(), Vec::new()).unwrap_err();
+        assert_eq!(err.line, Some(2));
+    }
+}
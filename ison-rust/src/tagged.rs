@@ -0,0 +1,234 @@
+//! Lossless serde representation of [`Value`]
+//!
+//! `Value`'s own serde impls are untagged, which reads well as JSON but does
+//! not round-trip: a reference comes back as a map or string, bytes and
+//! decimals as strings, and NaN as null. [`TaggedValue`] (or
+//! `#[serde(with = "ison_rs::tagged")]` on a `Value` field) marks those
+//! variants with a `$` key instead:
+//!
+//! | Value                         | Tagged form                          |
+//! |-------------------------------|--------------------------------------|
+//! | `Reference` `:user:42`        | `{"$ref": "42", "$type": "user"}`    |
+//! | `Bytes`                       | `{"$bytes": "SGk="}`                 |
+//! | `Decimal` `19.99`             | `{"$decimal": "19.99"}`              |
+//! | `Float` NaN, `inf`, `-inf`    | `{"$float": "nan"}`                  |
+//!
+//! Null, booleans, ints, strings, and finite floats stay plain, so any
+//! format that tells integers from floats (JSON via `serde_json` does)
+//! round-trips every value.
+
+use core::fmt;
+
+use serde::de::{self, Error as _, MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::prelude::*;
+use crate::{base64, Reference, Value};
+
+/// A [`Value`] with the lossless serde representation described in the
+/// [module docs](self)
+///
+/// ```rust
+/// use ison_rs::{tagged::TaggedValue, Reference, Value};
+///
+/// let value = Value::Reference(Reference::with_type("42", "user"));
+/// let json = serde_json::to_string(&TaggedValue(value.clone())).unwrap();
+/// assert_eq!(json, r#"{"$ref":"42","$type":"user"}"#);
+/// let back: TaggedValue = serde_json::from_str(&json).unwrap();
+/// assert_eq!(back.0, value);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TaggedValue(pub Value);
+
+impl Serialize for TaggedValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TaggedValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize(deserializer).map(TaggedValue)
+    }
+}
+
+fn tagged<S: Serializer>(serializer: S, entries: &[(&str, &str)]) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(Some(entries.len()))?;
+    for (key, value) in entries {
+        map.serialize_entry(key, value)?;
+    }
+    map.end()
+}
+
+/// Serialize a `Value` in tagged form, for `#[serde(with = "ison_rs::tagged")]`
+pub fn serialize<S: Serializer>(value: &Value, serializer: S) -> Result<S::Ok, S::Error> {
+    match value {
+        Value::Null => serializer.serialize_unit(),
+        Value::Bool(b) => serializer.serialize_bool(*b),
+        Value::Int(i) => serializer.serialize_i64(*i),
+        Value::Float(f) if f.is_finite() => serializer.serialize_f64(*f),
+        Value::Float(f) => {
+            let text = if f.is_nan() {
+                "nan"
+            } else if *f > 0.0 {
+                "inf"
+            } else {
+                "-inf"
+            };
+            tagged(serializer, &[("$float", text)])
+        }
+        Value::String(s) => serializer.serialize_str(s),
+        Value::Reference(r) => match &r.ref_type {
+            Some(ref_type) => tagged(serializer, &[("$ref", &r.id), ("$type", ref_type)]),
+            None => tagged(serializer, &[("$ref", &r.id)]),
+        },
+        Value::Bytes(b) => tagged(serializer, &[("$bytes", &base64::encode(b))]),
+        #[cfg(feature = "rust_decimal")]
+        Value::Decimal(d) => tagged(serializer, &[("$decimal", &d.to_string())]),
+    }
+}
+
+/// Deserialize a `Value` from tagged form, for `#[serde(with = "ison_rs::tagged")]`
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Value, D::Error> {
+    deserializer.deserialize_any(TaggedVisitor)
+}
+
+struct TaggedVisitor;
+
+impl<'de> Visitor<'de> for TaggedVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an ISON value")
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserialize(deserializer)
+    }
+
+    fn visit_bool<E>(self, b: bool) -> Result<Value, E> {
+        Ok(Value::Bool(b))
+    }
+
+    fn visit_i64<E>(self, i: i64) -> Result<Value, E> {
+        Ok(Value::Int(i))
+    }
+
+    fn visit_u64<E: de::Error>(self, u: u64) -> Result<Value, E> {
+        i64::try_from(u)
+            .map(Value::Int)
+            .map_err(|_| E::custom(format!("integer {} is out of range", u)))
+    }
+
+    fn visit_f64<E>(self, f: f64) -> Result<Value, E> {
+        Ok(Value::Float(f))
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Value, E> {
+        Ok(Value::String(s.to_string()))
+    }
+
+    fn visit_string<E>(self, s: String) -> Result<Value, E> {
+        Ok(Value::String(s))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut entries: Vec<(String, String)> = Vec::new();
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+        let get = |key: &str| {
+            entries
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.clone())
+        };
+        let invalid = |what: &str| A::Error::custom(format!("invalid {}", what));
+
+        let value = if let Some(id) = get("$ref") {
+            Value::Reference(Reference {
+                id,
+                ref_type: get("$type"),
+            })
+        } else if let Some(encoded) = get("$bytes") {
+            Value::Bytes(base64::decode(&encoded).ok_or_else(|| invalid("$bytes"))?)
+        } else if let Some(text) = get("$float") {
+            Value::Float(match text.as_str() {
+                "nan" => f64::NAN,
+                "inf" => f64::INFINITY,
+                "-inf" => f64::NEG_INFINITY,
+                _ => return Err(invalid("$float")),
+            })
+        } else if let Some(text) = get("$decimal") {
+            #[cfg(feature = "rust_decimal")]
+            {
+                Value::Decimal(crate::parse_decimal(&text).ok_or_else(|| invalid("$decimal"))?)
+            }
+            #[cfg(not(feature = "rust_decimal"))]
+            {
+                text.parse()
+                    .map(Value::Float)
+                    .map_err(|_| invalid("$decimal"))?
+            }
+        } else {
+            return Err(A::Error::custom(
+                "expected a $ref, $bytes, $float, or $decimal map",
+            ));
+        };
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Cell {
+        #[serde(with = "crate::tagged")]
+        value: Value,
+    }
+
+    #[test]
+    fn test_tagged_round_trip() {
+        let values = vec![
+            Value::Null,
+            Value::Bool(true),
+            Value::Int(-7),
+            Value::Float(1.0),
+            Value::Float(f64::NEG_INFINITY),
+            Value::Float(f64::NAN),
+            Value::String(":not-a-ref".into()),
+            Value::Reference(Reference::new("1")),
+            Value::Reference(Reference::with_type("2", "MANAGES")),
+            Value::Bytes(b"Hi".to_vec()),
+            #[cfg(feature = "rust_decimal")]
+            Value::Decimal("19.990".parse().unwrap()),
+        ];
+        for value in values {
+            let json = serde_json::to_string(&TaggedValue(value.clone())).unwrap();
+            let back: TaggedValue = serde_json::from_str(&json).unwrap();
+            assert_eq!(back.0, value, "{}", json);
+            assert_eq!(back.0.type_name(), value.type_name());
+        }
+
+        let cell = Cell {
+            value: Value::Bytes(vec![0xde, 0xad]),
+        };
+        let json = serde_json::to_string(&cell).unwrap();
+        assert_eq!(json, r#"{"value":{"$bytes":"3q0="}}"#);
+        assert_eq!(serde_json::from_str::<Cell>(&json).unwrap(), cell);
+
+        assert!(serde_json::from_str::<TaggedValue>(r#"{"id":"1"}"#).is_err());
+        assert!(serde_json::from_str::<TaggedValue>(r#"{"$float":"big"}"#).is_err());
+    }
+}
@@ -0,0 +1,193 @@
+//! Token counting and budget-aware serialization
+//!
+//! Estimates how many LLM tokens a document costs and serializes it within a
+//! token budget by dropping trailing rows. Counting goes through the
+//! [`Tokenizer`] trait so a real tokenizer can replace the built-in heuristic.
+
+use crate::prelude::*;
+use crate::{Block, Document, ISONError, Result, Serializer};
+
+/// Counts tokens in a piece of text
+pub trait Tokenizer {
+    fn count_tokens(&self, text: &str) -> usize;
+}
+
+impl<F: Fn(&str) -> usize> Tokenizer for F {
+    fn count_tokens(&self, text: &str) -> usize {
+        self(text)
+    }
+}
+
+/// Tokenizer-free estimate: one token per 4 characters of each word, plus one per line break.
+///
+/// Close enough to BPE tokenizers on tabular ISON to size a prompt, not to bill it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeuristicTokenizer;
+
+impl Tokenizer for HeuristicTokenizer {
+    fn count_tokens(&self, text: &str) -> usize {
+        let words: usize = text
+            .split_whitespace()
+            .map(|w| w.chars().count().div_ceil(4))
+            .sum();
+        words + text.matches('\n').count()
+    }
+}
+
+/// Token cost of one block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenEstimate {
+    pub block: String,
+    /// Header, fields line and summary rows
+    pub header_tokens: usize,
+    /// All data rows
+    pub row_tokens: usize,
+    pub rows: usize,
+}
+
+impl TokenEstimate {
+    pub fn total(&self) -> usize {
+        self.header_tokens + self.row_tokens
+    }
+}
+
+fn header_tokens(serializer: &Serializer, block: &Block, tokenizer: &impl Tokenizer) -> usize {
+    let mut header = block.clone();
+    header.rows.clear();
+    tokenizer.count_tokens(&serializer.serialize_block(&header))
+}
+
+fn row_costs(serializer: &Serializer, block: &Block, tokenizer: &impl Tokenizer) -> Vec<usize> {
+    (0..block.rows.len())
+        .map(|i| {
+            let line = serializer.serialize_row(block, false, i, &[]);
+            tokenizer.count_tokens(&format!("\n{}", line))
+        })
+        .collect()
+}
+
+/// Per-block token estimates for the compact (`dumps(doc, false)`) form
+pub fn estimate_tokens(doc: &Document, tokenizer: &impl Tokenizer) -> Vec<TokenEstimate> {
+    let serializer = Serializer::new(false);
+    doc.blocks
+        .iter()
+        .map(|block| TokenEstimate {
+            block: block.name.clone(),
+            header_tokens: header_tokens(&serializer, block, tokenizer),
+            row_tokens: row_costs(&serializer, block, tokenizer).iter().sum(),
+            rows: block.rows.len(),
+        })
+        .collect()
+}
+
+/// Serialize `doc` in compact form using at most `max_tokens` tokens.
+///
+/// Every block keeps its header, fields line and summary rows; data rows are
+/// then admitted round-robin across blocks, in order, while they fit, so no
+/// single large table starves the others. Fails if the headers alone exceed
+/// the budget.
+pub fn dumps_within_budget(
+    doc: &Document,
+    max_tokens: usize,
+    tokenizer: &impl Tokenizer,
+) -> Result<String> {
+    let serializer = Serializer::new(false);
+    let headers: usize = doc
+        .blocks
+        .iter()
+        .map(|b| header_tokens(&serializer, b, tokenizer))
+        .sum::<usize>()
+        + tokenizer.count_tokens(&"\n\n".repeat(doc.blocks.len().saturating_sub(1)));
+    if headers > max_tokens {
+        return Err(ISONError {
+            message: format!(
+                "Token budget {} is smaller than the block headers ({} tokens)",
+                max_tokens, headers
+            ),
+            line: None,
+        });
+    }
+
+    let costs: Vec<Vec<usize>> = doc
+        .blocks
+        .iter()
+        .map(|b| row_costs(&serializer, b, tokenizer))
+        .collect();
+    let mut kept = vec![0usize; doc.blocks.len()];
+    let mut used = headers;
+    let mut open: Vec<bool> = costs.iter().map(|c| !c.is_empty()).collect();
+    while open.iter().any(|o| *o) {
+        for (i, block_costs) in costs.iter().enumerate() {
+            if !open[i] {
+                continue;
+            }
+            let cost = block_costs[kept[i]];
+            if used + cost > max_tokens {
+                open[i] = false;
+                continue;
+            }
+            used += cost;
+            kept[i] += 1;
+            open[i] = kept[i] < block_costs.len();
+        }
+    }
+
+    let mut reduced = Document::new();
+    for (block, keep) in doc.blocks.iter().zip(&kept) {
+        let mut block = block.clone();
+        block.rows.truncate(*keep);
+        reduced.blocks.push(block);
+    }
+
+    // Per-part counts are an estimate for real tokenizers; trim until the whole fits
+    let mut text = serializer.serialize(&reduced);
+    while tokenizer.count_tokens(&text) > max_tokens {
+        match reduced
+            .blocks
+            .iter_mut()
+            .filter(|b| !b.rows.is_empty())
+            .max_by_key(|b| b.rows.len())
+        {
+            Some(block) => block.rows.pop(),
+            None => break,
+        };
+        text = serializer.serialize(&reduced);
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    const DOC: &str =
+        "table.users\nid name\n1 Alice\n2 Bob\n3 Carol\n4 Dave\n\ntable.tags\nid label\n1 a\n2 b";
+
+    #[test]
+    fn test_estimate_tokens() {
+        let doc = parse(DOC).unwrap();
+        let estimates = estimate_tokens(&doc, &HeuristicTokenizer);
+        assert_eq!(estimates.len(), 2);
+        assert_eq!(estimates[0].rows, 4);
+        // "\n1 Alice" = newline + "1" + "Alice" (2 tokens), and so on
+        assert_eq!(estimates[0].row_tokens, 14);
+        assert_eq!(HeuristicTokenizer.count_tokens("table.users\nid name"), 6);
+    }
+
+    #[test]
+    fn test_dumps_within_budget() {
+        let doc = parse(DOC).unwrap();
+        let full = crate::dumps(&doc, false);
+        let words = |s: &str| s.split_whitespace().count();
+        assert_eq!(dumps_within_budget(&doc, 1000, &words).unwrap(), full);
+
+        let text = dumps_within_budget(&doc, 13, &words).unwrap();
+        assert!(words(&text) <= 13);
+        let reduced = parse(&text).unwrap();
+        assert_eq!(reduced["users"].len(), 2);
+        assert_eq!(reduced["tags"].len(), 1);
+
+        assert!(dumps_within_budget(&doc, 3, &words).is_err());
+    }
+}
@@ -0,0 +1,376 @@
+//! All-or-nothing batches of edits
+//!
+//! [`Document::transaction`] runs a closure against a [`Transaction`], a
+//! staged view of the document. Blocks are copied the first time the
+//! transaction changes them. When the closure returns, the staged blocks
+//! are checked: every error the edits reported, plus, with
+//! [`Document::transaction_with_schema`], every [`SchemaViolation`] in the
+//! blocks they touched. Only if there are none do the changes reach the
+//! document; otherwise it is left exactly as it was.
+
+use core::fmt;
+
+use crate::check::{check_schema, SchemaViolation};
+use crate::prelude::*;
+use crate::{intern, Block, Document, ISONError, Row};
+
+/// Why a transaction was not applied
+#[derive(Debug, Clone, Default)]
+pub struct TransactionError {
+    /// Errors reported by the edits, in order
+    pub errors: Vec<ISONError>,
+    /// Schema violations in the blocks the edits touched
+    pub violations: Vec<SchemaViolation>,
+}
+
+impl fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Transaction not applied")?;
+        let mut sep = ": ";
+        for error in &self.errors {
+            write!(f, "{}{}", sep, error)?;
+            sep = "; ";
+        }
+        for violation in &self.violations {
+            write!(f, "{}{}", sep, violation)?;
+            sep = "; ";
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TransactionError {}
+
+impl From<TransactionError> for ISONError {
+    fn from(e: TransactionError) -> Self {
+        ISONError {
+            message: e.to_string(),
+            line: None,
+        }
+    }
+}
+
+/// A block of the staged document
+enum Slot {
+    /// Unchanged block `i` of the document
+    Original(usize),
+    Changed(Block),
+}
+
+/// Staged edits to a document; see [`Document::transaction`]
+///
+/// Edits that fail record an error and change nothing, and the transaction
+/// carries on, so one call reports every problem in the batch.
+pub struct Transaction<'d> {
+    doc: &'d Document,
+    slots: Vec<Slot>,
+    /// Names of blocks added, changed, or removed
+    touched: Vec<String>,
+    errors: Vec<ISONError>,
+}
+
+impl<'d> Transaction<'d> {
+    fn new(doc: &'d Document) -> Self {
+        Self {
+            doc,
+            slots: (0..doc.blocks.len()).map(Slot::Original).collect(),
+            touched: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    fn resolve<'s>(&'s self, slot: &'s Slot) -> &'s Block {
+        match slot {
+            Slot::Original(i) => &self.doc.blocks[*i],
+            Slot::Changed(block) => block,
+        }
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.slots
+            .iter()
+            .position(|slot| self.resolve(slot).name == name)
+    }
+
+    fn touch(&mut self, name: &str) {
+        if !self.touched.iter().any(|t| t == name) {
+            self.touched.push(name.to_string());
+        }
+    }
+
+    /// Record an error; the transaction will not be applied
+    pub fn fail(&mut self, message: impl Into<String>) {
+        self.errors.push(ISONError {
+            message: message.into(),
+            line: None,
+        });
+    }
+
+    /// Block by name, as staged so far
+    pub fn block(&self, name: &str) -> Option<&Block> {
+        self.position(name).map(|i| self.resolve(&self.slots[i]))
+    }
+
+    /// Mutable block by name, copied from the document on first use
+    pub fn block_mut(&mut self, name: &str) -> Option<&mut Block> {
+        let Some(i) = self.position(name) else {
+            self.fail(format!("Block '{}' not found", name));
+            return None;
+        };
+        self.touch(name);
+        if let Slot::Original(original) = self.slots[i] {
+            self.slots[i] = Slot::Changed(self.doc.blocks[original].clone());
+        }
+        match &mut self.slots[i] {
+            Slot::Changed(block) => Some(block),
+            Slot::Original(_) => None,
+        }
+    }
+
+    /// Append a data row to block `name`; every key must be one of its fields
+    pub fn insert(&mut self, name: &str, row: Row) {
+        let Some(block) = self.block_mut(name) else {
+            return;
+        };
+        if let Some(unknown) = row
+            .keys()
+            .find(|k| !block.fields.iter().any(|f| f == &***k))
+        {
+            let message = format!("Block '{}' has no field '{}'", name, unknown);
+            self.fail(message);
+            return;
+        }
+        let keys = block.row_keys();
+        let row = row
+            .into_iter()
+            .map(|(k, v)| (intern(&keys, &k), v))
+            .collect();
+        block.rows.push(row);
+    }
+
+    /// [`Block::update_where`] on block `name`
+    pub fn update(
+        &mut self,
+        name: &str,
+        predicate: impl FnMut(&Row) -> bool,
+        update: impl FnMut(&mut Row),
+    ) -> usize {
+        self.block_mut(name)
+            .map_or(0, |block| block.update_where(predicate, update))
+    }
+
+    /// [`Block::delete_where`] on block `name`
+    pub fn delete(&mut self, name: &str, predicate: impl FnMut(&Row) -> bool) -> usize {
+        self.block_mut(name)
+            .map_or(0, |block| block.delete_where(predicate))
+    }
+
+    /// Append a block; its name must be new
+    pub fn add_block(&mut self, block: Block) {
+        if self.position(&block.name).is_some() {
+            self.fail(format!("Block '{}' already exists", block.name));
+            return;
+        }
+        self.touch(&block.name);
+        self.slots.push(Slot::Changed(block));
+    }
+
+    /// Remove block `name`
+    pub fn remove_block(&mut self, name: &str) {
+        match self.position(name) {
+            Some(i) => {
+                self.touch(name);
+                self.slots.remove(i);
+            }
+            None => self.fail(format!("Block '{}' not found", name)),
+        }
+    }
+}
+
+impl Document {
+    /// Run `edits` against a staged copy of this document and apply them
+    /// only if none of them failed.
+    ///
+    /// ```rust
+    /// use ison_rs::{Row, Value};
+    ///
+    /// let mut doc = ison_rs::parse("table.users\nid name\n1 Alice")?;
+    /// let row: Row = [("id".into(), Value::Int(2)), ("nmae".into(), Value::from("Bob"))]
+    ///     .into_iter()
+    ///     .collect();
+    /// let result = doc.transaction(|tx| {
+    ///     tx.update("users", |r| r["id"] == Value::Int(1), |r| {
+    ///         r.insert("name".into(), "Alicia".into());
+    ///     });
+    ///     tx.insert("users", row);
+    /// });
+    /// assert_eq!(result.unwrap_err().errors[0].message, "Block 'users' has no field 'nmae'");
+    /// assert_eq!(doc["users"][0]["name"].as_str(), Some("Alice"));
+    /// # Ok::<(), ison_rs::ISONError>(())
+    /// ```
+    pub fn transaction<R>(
+        &mut self,
+        edits: impl FnOnce(&mut Transaction) -> R,
+    ) -> core::result::Result<R, TransactionError> {
+        self.run_transaction(None, edits)
+    }
+
+    /// [`transaction`](Self::transaction), also failing if a block it
+    /// touched no longer matches `schema` (see [`check_schema`])
+    pub fn transaction_with_schema<R>(
+        &mut self,
+        schema: &Document,
+        edits: impl FnOnce(&mut Transaction) -> R,
+    ) -> core::result::Result<R, TransactionError> {
+        self.run_transaction(Some(schema), edits)
+    }
+
+    fn run_transaction<R>(
+        &mut self,
+        schema: Option<&Document>,
+        edits: impl FnOnce(&mut Transaction) -> R,
+    ) -> core::result::Result<R, TransactionError> {
+        let mut tx = Transaction::new(self);
+        let result = edits(&mut tx);
+        let Transaction {
+            slots,
+            touched,
+            errors,
+            ..
+        } = tx;
+        if !errors.is_empty() {
+            return Err(TransactionError {
+                errors,
+                violations: Vec::new(),
+            });
+        }
+
+        // Move unchanged blocks across, remembering where from in case the
+        // schema check fails
+        let mut originals: Vec<Option<Block>> = core::mem::take(&mut self.blocks)
+            .into_iter()
+            .map(Some)
+            .collect();
+        let mut sources = Vec::with_capacity(slots.len());
+        for slot in slots {
+            match slot {
+                Slot::Original(i) => {
+                    self.blocks.extend(originals[i].take());
+                    sources.push(Some(i));
+                }
+                Slot::Changed(block) => {
+                    self.blocks.push(block);
+                    sources.push(None);
+                }
+            }
+        }
+
+        let violations = match schema {
+            Some(schema) => {
+                let names: Vec<&str> = touched.iter().map(String::as_str).collect();
+                check_schema(self, &schema.subset(&names))
+            }
+            None => Vec::new(),
+        };
+        if violations.is_empty() {
+            return Ok(result);
+        }
+
+        for (block, source) in core::mem::take(&mut self.blocks).into_iter().zip(sources) {
+            if let Some(i) = source {
+                originals[i] = Some(block);
+            }
+        }
+        self.blocks = originals.into_iter().flatten().collect();
+        Err(TransactionError {
+            errors: Vec::new(),
+            violations,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dumps, parse, Value};
+
+    fn row(cells: &[(&str, Value)]) -> Row {
+        cells
+            .iter()
+            .map(|(k, v)| ((*k).into(), v.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_transaction() {
+        let mut doc =
+            parse("table.users\nid name\n1 Alice\n2 Bob\n\ntable.logs\nmsg\nstart").unwrap();
+        let schema = parse("table.users\nid:int name:string").unwrap();
+
+        let updated = doc
+            .transaction_with_schema(&schema, |tx| {
+                tx.insert(
+                    "users",
+                    row(&[("id", Value::Int(3)), ("name", "Cy".into())]),
+                );
+                tx.delete("logs", |_| true);
+                tx.add_block(Block::new("table", "audit"));
+                tx.update(
+                    "users",
+                    |r| r["id"] == Value::Int(2),
+                    |r| {
+                        r.insert("name".into(), "Robert".into());
+                    },
+                )
+            })
+            .unwrap();
+        assert_eq!(updated, 1);
+        assert_eq!(
+            dumps(&doc, false),
+            "table.users\nid name\n1 Alice\n2 Robert\n3 Cy\n\ntable.logs\nmsg\n\ntable.audit\n"
+        );
+        let users = &doc["users"];
+        let key = |r: &Row| r.get_key_value("name").unwrap().0.clone();
+        assert!(alloc::sync::Arc::ptr_eq(&key(&users[0]), &key(&users[2])));
+
+        // A schema violation rolls everything back, blocks and order included
+        let before = dumps(&doc, false);
+        let err = doc
+            .transaction_with_schema(&schema, |tx| {
+                tx.remove_block("logs");
+                tx.insert("users", row(&[("id", "four".into())]));
+            })
+            .unwrap_err();
+        assert!(err.errors.is_empty());
+        let fields: Vec<_> = err
+            .violations
+            .iter()
+            .map(|v| (v.row, v.field.as_deref()))
+            .collect();
+        assert_eq!(fields, [(Some(3), Some("id")), (Some(3), Some("name"))]);
+        assert_eq!(dumps(&doc, false), before);
+
+        // Every failed edit is reported
+        let err = doc
+            .transaction(|tx| {
+                tx.remove_block("missing");
+                tx.add_block(Block::new("table", "users"));
+                tx.fail("stop");
+            })
+            .unwrap_err();
+        let messages: Vec<_> = err.errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "Block 'missing' not found",
+                "Block 'users' already exists",
+                "stop"
+            ]
+        );
+        assert!(ISONError::from(err)
+            .message
+            .starts_with("Transaction not applied: "));
+        assert_eq!(dumps(&doc, false), before);
+    }
+}
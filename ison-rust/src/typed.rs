@@ -0,0 +1,160 @@
+//! Typed views of blocks
+//!
+//! [`Block::rows_as`] reads each row into a `Deserialize` type and
+//! [`Block::from_rows`] builds a block from `Serialize` values, so a block
+//! can be handled as a `Vec<T>` without going through a whole serde
+//! document. Cells map to fields by name; references read into
+//! [`Reference`](crate::Reference) fields.
+
+use core::fmt;
+
+use serde::de::{DeserializeOwned, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::prelude::*;
+use crate::{Block, ISONError, Result, Row, Value};
+
+fn row_error(block: &str, row: usize, error: impl fmt::Display) -> ISONError {
+    ISONError {
+        message: format!("Block '{}' row {}: {}", block, row + 1, error),
+        line: None,
+    }
+}
+
+/// A serialized row with its fields in serialization order, which
+/// `serde_json::Map` would sort
+struct OrderedRow(Vec<(String, Value)>);
+
+impl<'de> Deserialize<'de> for OrderedRow {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> core::result::Result<Self, D::Error> {
+        deserializer.deserialize_map(OrderedRowVisitor)
+    }
+}
+
+struct OrderedRowVisitor;
+
+impl<'de> Visitor<'de> for OrderedRowVisitor {
+    type Value = OrderedRow;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a struct or map of ISON values")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(
+        self,
+        mut map: A,
+    ) -> core::result::Result<OrderedRow, A::Error> {
+        let mut cells = Vec::new();
+        while let Some(cell) = map.next_entry()? {
+            cells.push(cell);
+        }
+        Ok(OrderedRow(cells))
+    }
+}
+
+impl Block {
+    /// Deserialize every data row into a `T`
+    ///
+    /// ```rust
+    /// #[derive(serde::Deserialize)]
+    /// struct User {
+    ///     id: i64,
+    ///     name: String,
+    ///     email: Option<String>,
+    /// }
+    ///
+    /// let doc = ison_rs::parse("table.users\nid name email\n1 Alice ~\n2 Bob b@x.com")?;
+    /// let users: Vec<User> = doc["users"].rows_as()?;
+    /// assert_eq!(users[1].email.as_deref(), Some("b@x.com"));
+    /// # Ok::<(), ison_rs::ISONError>(())
+    /// ```
+    pub fn rows_as<T: DeserializeOwned>(&self) -> Result<Vec<T>> {
+        self.rows
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                let json = serde_json::to_value(row).map_err(|e| row_error(&self.name, i, e))?;
+                serde_json::from_value(json).map_err(|e| row_error(&self.name, i, e))
+            })
+            .collect()
+    }
+
+    /// A `table.<name>` block with one row per item of `rows`
+    ///
+    /// Fields follow the serialization order of the first item, with fields
+    /// first seen in later items appended; cells an item does not have are
+    /// null. Each field must serialize to a single ISON value: a scalar, a
+    /// [`Reference`](crate::Reference), or `None`.
+    pub fn from_rows<T: Serialize>(name: impl Into<String>, rows: &[T]) -> Result<Block> {
+        let mut block = Block::new("table", name);
+        let mut cells: Vec<Vec<(String, Value)>> = Vec::with_capacity(rows.len());
+        for (i, item) in rows.iter().enumerate() {
+            let json = serde_json::to_string(item).map_err(|e| row_error(&block.name, i, e))?;
+            let OrderedRow(row) =
+                serde_json::from_str(&json).map_err(|e| row_error(&block.name, i, e))?;
+            for (field, _) in &row {
+                if !block.fields.contains(field) {
+                    block.add_field_def(field);
+                }
+            }
+            cells.push(row);
+        }
+
+        let keys = block.field_keys();
+        for row in cells {
+            let mut out: Row = keys.iter().map(|k| (k.clone(), Value::Null)).collect();
+            for (field, value) in row {
+                out.insert(field.as_str().into(), value);
+            }
+            block.rows.push(out);
+        }
+        Ok(block)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dumps, parse, Document, Reference};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Order {
+        id: i64,
+        total: f64,
+        customer: Reference,
+        note: Option<String>,
+    }
+
+    #[test]
+    fn test_typed_rows() {
+        let doc =
+            parse("table.orders\nid total customer note\n1 9.5 :c:7 ~\n2 3.0 :c:8 rush").unwrap();
+        let orders: Vec<Order> = doc["orders"].rows_as().unwrap();
+        assert_eq!(orders[0].customer, Reference::with_type("7", "c"));
+        assert_eq!(orders[1].note.as_deref(), Some("rush"));
+
+        let block = Block::from_rows("orders", &orders).unwrap();
+        assert_eq!(block.fields, ["id", "total", "customer", "note"]);
+        assert_eq!(block.rows, doc["orders"].rows);
+        let text = dumps(
+            &Document {
+                blocks: vec![block],
+            },
+            false,
+        );
+        assert_eq!(
+            text,
+            "table.orders\nid total customer note\n1 9.5 :c:7 null\n2 3.0 :c:8 rush"
+        );
+
+        #[derive(Debug, Deserialize)]
+        struct Wrong {
+            #[allow(dead_code)]
+            id: String,
+        }
+        let error = doc["orders"].rows_as::<Wrong>().unwrap_err();
+        assert!(error.message.starts_with("Block 'orders' row 1:"));
+        assert!(Block::from_rows("t", &[(1, 2)]).is_err());
+        assert!(Block::from_rows("t", &[vec![vec![1]]]).is_err());
+    }
+}
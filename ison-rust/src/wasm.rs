@@ -0,0 +1,84 @@
+//! JavaScript bindings via wasm-bindgen
+//!
+//! Built as a `cdylib` for `wasm32-unknown-unknown` and post-processed with
+//! `wasm-bindgen` (see the README). Documents cross the boundary
+//! as plain objects with the same shape as the serde representation:
+//! `{ blocks: [{ kind, name, fields, field_info, rows, summary_rows }] }`,
+//! where references are `{ id, ref_type }` objects.
+//!
+//! Failures throw `{ name: "ISONError", message, line }` objects instead of
+//! strings so callers can point at the offending line.
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{Document, ISONError};
+
+#[derive(Serialize)]
+struct JsError<'a> {
+    name: &'static str,
+    message: &'a str,
+    line: Option<usize>,
+}
+
+fn js_error(message: &str, line: Option<usize>) -> JsValue {
+    serde_wasm_bindgen::to_value(&JsError {
+        name: "ISONError",
+        message,
+        line,
+    })
+    .unwrap_or_else(|_| JsValue::from_str(message))
+}
+
+impl From<ISONError> for JsValue {
+    fn from(e: ISONError) -> Self {
+        js_error(&e.message, e.line)
+    }
+}
+
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsValue> {
+    // Maps as plain objects so rows read as `row.name`, not `row.get("name")`
+    let serializer = serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+    value
+        .serialize(&serializer)
+        .map_err(|e| js_error(&e.to_string(), None))
+}
+
+fn from_js(doc: JsValue) -> Result<Document, JsValue> {
+    serde_wasm_bindgen::from_value(doc)
+        .map_err(|e| js_error(&format!("Invalid document: {}", e), None))
+}
+
+/// Parse ISON text into a document object
+#[wasm_bindgen(js_name = parse)]
+pub fn parse_js(text: &str) -> Result<JsValue, JsValue> {
+    to_js(&crate::parse(text)?)
+}
+
+/// Serialize a document object back to ISON text
+#[wasm_bindgen(js_name = dumps)]
+pub fn dumps_js(doc: JsValue, align_columns: Option<bool>) -> Result<String, JsValue> {
+    Ok(crate::dumps(&from_js(doc)?, align_columns.unwrap_or(false)))
+}
+
+/// Convert ISON text to JSON text
+#[wasm_bindgen(js_name = toJson)]
+pub fn to_json_js(text: &str, pretty: Option<bool>) -> Result<String, JsValue> {
+    Ok(crate::ison_to_json(text, pretty.unwrap_or(false))?)
+}
+
+/// Check ISON text against an annotated schema document (see [`crate::check`]).
+///
+/// Returns an array of `{ block, row, field, message }` violations; empty when valid.
+#[wasm_bindgen(js_name = validate)]
+pub fn validate_js(text: &str, schema: &str) -> Result<JsValue, JsValue> {
+    let doc = crate::parse(text)?;
+    let schema = crate::parse(schema)?;
+    to_js(&crate::check_schema(&doc, &schema))
+}
+
+/// Crate version
+#[wasm_bindgen(js_name = version)]
+pub fn version_js() -> String {
+    crate::VERSION.to_string()
+}
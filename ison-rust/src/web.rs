@@ -0,0 +1,436 @@
+//! HTTP glue for axum and actix-web
+//!
+//! With the `axum` or `actix` feature, [`IsonDocument`] is both an
+//! extractor and a responder in that framework: as an extractor it reads an
+//! `application/ison` (or `application/isonl`) request body and parses it,
+//! and as a return value it writes the document with an `application/ison`
+//! content type.
+//! [`IsonlStream`] sends blocks as ISONL, one chunk per block, as they are
+//! produced. Requests that cannot be read are turned away with an
+//! [`IsonRejection`]: 415 for another content type, 400 for a body that
+//! does not read or parse. The `web` feature alone has the shared types and
+//! [`BodyFormat`], for other frameworks.
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "axum")] {
+//! use ison_rs::web::{IsonDocument, IsonlStream};
+//! use ison_rs::Block;
+//!
+//! async fn echo(IsonDocument(doc): IsonDocument) -> IsonDocument {
+//!     IsonDocument(doc)
+//! }
+//!
+//! async fn export() -> IsonlStream<std::vec::IntoIter<Block>> {
+//!     IsonlStream::new(vec![Block::new("table", "users")])
+//! }
+//!
+//! let app: axum::Router = axum::Router::new()
+//!     .route("/echo", axum::routing::post(echo))
+//!     .route("/export", axum::routing::get(export));
+//! # }
+//! ```
+
+use core::convert::Infallible;
+use core::fmt;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use bytes::Bytes;
+use futures_core::Stream;
+
+use crate::{dumps, dumps_isonl, parse, parse_isonl, Block, Document, ISONError, Result};
+
+/// Content type of ISON bodies
+pub const ISON_CONTENT_TYPE: &str = "application/ison";
+/// Content type of ISONL bodies
+pub const ISONL_CONTENT_TYPE: &str = "application/isonl";
+
+/// The two text formats a body can be in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyFormat {
+    Ison,
+    Isonl,
+}
+
+impl BodyFormat {
+    /// The format named by a `Content-Type` value, ignoring parameters
+    /// such as `charset`
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let media_type = content_type.split(';').next().unwrap_or_default().trim();
+        if media_type.eq_ignore_ascii_case(ISON_CONTENT_TYPE) {
+            Some(BodyFormat::Ison)
+        } else if media_type.eq_ignore_ascii_case(ISONL_CONTENT_TYPE) {
+            Some(BodyFormat::Isonl)
+        } else {
+            None
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            BodyFormat::Ison => ISON_CONTENT_TYPE,
+            BodyFormat::Isonl => ISONL_CONTENT_TYPE,
+        }
+    }
+
+    pub fn parse(&self, text: &str) -> Result<Document> {
+        match self {
+            BodyFormat::Ison => parse(text),
+            BodyFormat::Isonl => parse_isonl(text),
+        }
+    }
+
+    pub fn dumps(&self, doc: &Document) -> String {
+        match self {
+            BodyFormat::Ison => dumps(doc, false),
+            BodyFormat::Isonl => dumps_isonl(doc),
+        }
+    }
+}
+
+/// Why a request body was not extracted as an [`IsonDocument`]
+#[derive(Debug)]
+pub enum IsonRejection {
+    /// The content type is missing or not ISON or ISONL
+    ContentType,
+    /// The body could not be read, or is not UTF-8
+    Body(String),
+    Parse(ISONError),
+}
+
+impl IsonRejection {
+    /// The HTTP status to answer with
+    pub fn status(&self) -> u16 {
+        match self {
+            IsonRejection::ContentType => 415,
+            IsonRejection::Body(_) | IsonRejection::Parse(_) => 400,
+        }
+    }
+}
+
+impl fmt::Display for IsonRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IsonRejection::ContentType => write!(
+                f,
+                "Expected content type {} or {}",
+                ISON_CONTENT_TYPE, ISONL_CONTENT_TYPE
+            ),
+            IsonRejection::Body(message) => write!(f, "Invalid request body: {}", message),
+            IsonRejection::Parse(e) => write!(f, "Invalid ISON: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for IsonRejection {}
+
+/// A document read from a request body or written to a response
+#[derive(Debug, Clone, Default)]
+pub struct IsonDocument(pub Document);
+
+impl IsonDocument {
+    #[cfg(any(feature = "axum", feature = "actix"))]
+    fn decode(
+        content_type: Option<&str>,
+        body: core::result::Result<Bytes, String>,
+    ) -> core::result::Result<Self, IsonRejection> {
+        let format = content_type
+            .and_then(BodyFormat::from_content_type)
+            .ok_or(IsonRejection::ContentType)?;
+        let body = body.map_err(IsonRejection::Body)?;
+        let text = core::str::from_utf8(&body)
+            .map_err(|_| IsonRejection::Body("not valid UTF-8".to_string()))?;
+        format
+            .parse(text)
+            .map(IsonDocument)
+            .map_err(IsonRejection::Parse)
+    }
+}
+
+impl From<Document> for IsonDocument {
+    fn from(doc: Document) -> Self {
+        IsonDocument(doc)
+    }
+}
+
+/// A streamed ISONL response body, written one block at a time
+///
+/// Each block becomes a chunk of ISONL lines as soon as the iterator
+/// yields it, so a large result can be sent as a series of smaller blocks
+/// with the same header without building the whole document first.
+#[derive(Debug)]
+pub struct IsonlStream<I> {
+    blocks: I,
+}
+
+impl<I: Iterator<Item = Block>> IsonlStream<I> {
+    pub fn new(blocks: impl IntoIterator<IntoIter = I>) -> Self {
+        Self {
+            blocks: blocks.into_iter(),
+        }
+    }
+}
+
+impl<I: Iterator<Item = Block> + Unpin> Stream for IsonlStream<I> {
+    type Item = core::result::Result<Bytes, Infallible>;
+
+    fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        for block in self.blocks.by_ref() {
+            let mut lines = dumps_isonl(&Document {
+                blocks: vec![block],
+            });
+            if !lines.is_empty() {
+                lines.push('\n');
+                return Poll::Ready(Some(Ok(Bytes::from(lines))));
+            }
+        }
+        Poll::Ready(None)
+    }
+}
+
+#[cfg(feature = "axum")]
+mod axum_impl {
+    use axum::body::Body;
+    use axum::extract::{FromRequest, Request};
+    use axum::http::header::CONTENT_TYPE;
+    use axum::http::StatusCode;
+    use axum::response::{IntoResponse, Response};
+
+    use super::*;
+
+    impl<S: Send + Sync> FromRequest<S> for IsonDocument {
+        type Rejection = IsonRejection;
+
+        async fn from_request(
+            req: Request,
+            state: &S,
+        ) -> core::result::Result<Self, Self::Rejection> {
+            let content_type = req
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string);
+            if content_type.is_none() {
+                return Err(IsonRejection::ContentType);
+            }
+            let body = Bytes::from_request(req, state)
+                .await
+                .map_err(|e| e.body_text());
+            IsonDocument::decode(content_type.as_deref(), body)
+        }
+    }
+
+    impl IntoResponse for IsonDocument {
+        fn into_response(self) -> Response {
+            ([(CONTENT_TYPE, ISON_CONTENT_TYPE)], dumps(&self.0, false)).into_response()
+        }
+    }
+
+    impl IntoResponse for IsonRejection {
+        fn into_response(self) -> Response {
+            let status = StatusCode::from_u16(self.status()).unwrap_or(StatusCode::BAD_REQUEST);
+            (status, self.to_string()).into_response()
+        }
+    }
+
+    impl<I: Iterator<Item = Block> + Unpin + Send + 'static> IntoResponse for IsonlStream<I> {
+        fn into_response(self) -> Response {
+            (
+                [(CONTENT_TYPE, ISONL_CONTENT_TYPE)],
+                Body::from_stream(self),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[cfg(feature = "actix")]
+mod actix_impl {
+    use std::future::Future;
+
+    use actix_web::body::BoxBody;
+    use actix_web::dev::Payload;
+    use actix_web::http::header::CONTENT_TYPE;
+    use actix_web::http::StatusCode;
+    use actix_web::{FromRequest, HttpRequest, HttpResponse, Responder, ResponseError};
+
+    use super::*;
+
+    impl FromRequest for IsonDocument {
+        type Error = IsonRejection;
+        type Future = Pin<Box<dyn Future<Output = core::result::Result<Self, Self::Error>>>>;
+
+        fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+            let content_type = req
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string);
+            let body = Bytes::from_request(req, payload);
+            Box::pin(async move {
+                if content_type.is_none() {
+                    return Err(IsonRejection::ContentType);
+                }
+                let body = body.await.map_err(|e| e.to_string());
+                IsonDocument::decode(content_type.as_deref(), body)
+            })
+        }
+    }
+
+    impl Responder for IsonDocument {
+        type Body = BoxBody;
+
+        fn respond_to(self, _req: &HttpRequest) -> HttpResponse {
+            HttpResponse::Ok()
+                .content_type(ISON_CONTENT_TYPE)
+                .body(dumps(&self.0, false))
+        }
+    }
+
+    impl ResponseError for IsonRejection {
+        fn status_code(&self) -> StatusCode {
+            StatusCode::from_u16(self.status()).unwrap_or(StatusCode::BAD_REQUEST)
+        }
+    }
+
+    impl<I: Iterator<Item = Block> + Unpin + 'static> Responder for IsonlStream<I> {
+        type Body = BoxBody;
+
+        fn respond_to(self, _req: &HttpRequest) -> HttpResponse {
+            HttpResponse::Ok()
+                .content_type(ISONL_CONTENT_TYPE)
+                .streaming(self)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run a future whose I/O is all in memory
+    #[cfg(any(feature = "axum", feature = "actix"))]
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        let mut future = core::pin::pin!(future);
+        let mut cx = Context::from_waker(std::task::Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    const USERS: &str = "table.users\nid name\n1 Alice\n2 Bob";
+
+    #[test]
+    fn test_body_format() {
+        assert_eq!(
+            BodyFormat::from_content_type("Application/ISON; charset=utf-8"),
+            Some(BodyFormat::Ison)
+        );
+        assert_eq!(
+            BodyFormat::from_content_type(ISONL_CONTENT_TYPE),
+            Some(BodyFormat::Isonl)
+        );
+        assert_eq!(BodyFormat::from_content_type("application/json"), None);
+        let doc = BodyFormat::Ison.parse(USERS).unwrap();
+        assert_eq!(
+            BodyFormat::Isonl.dumps(&doc),
+            "table.users|id name|1 Alice\ntable.users|id name|2 Bob"
+        );
+    }
+
+    #[cfg(feature = "axum")]
+    #[test]
+    fn test_axum() {
+        use axum::body::{to_bytes, Body};
+        use axum::extract::{FromRequest, Request};
+        use axum::response::IntoResponse;
+
+        let request = |content_type: &str, body: &str| {
+            Request::builder()
+                .header("content-type", content_type)
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let doc = block_on(IsonDocument::from_request(
+            request(ISON_CONTENT_TYPE, USERS),
+            &(),
+        ))
+        .unwrap();
+        assert_eq!(doc.0["users"].len(), 2);
+        let isonl = block_on(IsonDocument::from_request(
+            request(ISONL_CONTENT_TYPE, "table.users|id|1"),
+            &(),
+        ))
+        .unwrap();
+        assert_eq!(isonl.0["users"].len(), 1);
+
+        let rejection = |req| block_on(IsonDocument::from_request(req, &())).unwrap_err();
+        let err = rejection(request("application/json", "{}"));
+        assert_eq!(err.into_response().status(), 415);
+        let err = rejection(request(ISON_CONTENT_TYPE, "users\nid\n1"));
+        assert!(matches!(err, IsonRejection::Parse(_)));
+        assert_eq!(err.into_response().status(), 400);
+
+        let response = doc.into_response();
+        assert_eq!(response.headers()["content-type"], ISON_CONTENT_TYPE);
+        let body = block_on(to_bytes(response.into_body(), usize::MAX)).unwrap();
+        assert_eq!(body, USERS);
+
+        let blocks = parse("table.a\nx\n1\n\ntable.b\ny\n\ntable.a\nx\n2")
+            .unwrap()
+            .blocks;
+        let response = IsonlStream::new(blocks).into_response();
+        assert_eq!(response.headers()["content-type"], ISONL_CONTENT_TYPE);
+        let body = block_on(to_bytes(response.into_body(), usize::MAX)).unwrap();
+        assert_eq!(body, "table.a|x|1\ntable.a|x|2\n");
+    }
+
+    #[cfg(feature = "actix")]
+    #[test]
+    fn test_actix() {
+        use actix_web::body::{to_bytes, MessageBody};
+        use actix_web::test::TestRequest;
+        use actix_web::{FromRequest, Responder, ResponseError};
+
+        let extract = |content_type: &str, body: &'static str| {
+            let (req, mut payload) = TestRequest::default()
+                .insert_header(("content-type", content_type))
+                .set_payload(body)
+                .to_http_parts();
+            block_on(IsonDocument::from_request(&req, &mut payload))
+        };
+        let doc = extract("application/ison; charset=utf-8", USERS).unwrap();
+        assert_eq!(doc.0["users"][1]["name"].as_str(), Some("Bob"));
+        let err = extract("text/plain", USERS).unwrap_err();
+        assert_eq!(err.status_code(), 415);
+        assert_eq!(
+            err.to_string(),
+            "Expected content type application/ison or application/isonl"
+        );
+        assert_eq!(
+            extract(ISON_CONTENT_TYPE, "users\nid\n1")
+                .unwrap_err()
+                .status_code(),
+            400
+        );
+
+        let req = TestRequest::default().to_http_request();
+        let response = doc.respond_to(&req);
+        assert_eq!(
+            response.headers().get("content-type").unwrap(),
+            ISON_CONTENT_TYPE
+        );
+        let body = block_on(to_bytes(response.into_body())).unwrap();
+        assert_eq!(body, USERS);
+
+        let stream = IsonlStream::new(parse(USERS).unwrap().blocks);
+        let body = stream.respond_to(&req).into_body();
+        assert_eq!(body.size(), actix_web::body::BodySize::Stream);
+        assert_eq!(
+            block_on(to_bytes(body)).unwrap(),
+            "table.users|id name|1 Alice\ntable.users|id name|2 Bob\n"
+        );
+    }
+}
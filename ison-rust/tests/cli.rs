@@ -0,0 +1,76 @@
+#![cfg(feature = "cli")]
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn ison(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ison"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // Commands rejected up front exit without reading their input
+    let written = child.stdin.take().unwrap().write_all(stdin.as_bytes());
+    if let Err(e) = written {
+        assert_eq!(e.kind(), std::io::ErrorKind::BrokenPipe);
+    }
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+const USERS: &str = "table.users\nid name manager\n1 Alice ~\n2 Bob :1\n";
+
+#[test]
+fn test_convert_and_query() {
+    let out = ison(
+        &["from-csv", "--name", "users"],
+        "id,name\n1,Alice\n2,Bob\n",
+    );
+    assert!(out.status.success());
+    assert_eq!(stdout(&out), "table.users\nid name\n1 Alice\n2 Bob\n");
+
+    let out = ison(&["query", "users[manager=:1].name"], USERS);
+    assert_eq!(stdout(&out), "Bob\n");
+
+    let out = ison(&["to-isonl"], USERS);
+    let back = ison(&["from-isonl"], &stdout(&out));
+    assert_eq!(stdout(&back), stdout(&ison(&["fmt"], USERS)));
+    let out = ison(&["to-isonl", "--schema"], USERS);
+    assert!(stdout(&out).contains("table.users||2 Bob :1"));
+    let back = ison(&["from-isonl"], &stdout(&out));
+    assert_eq!(stdout(&back), stdout(&ison(&["fmt"], USERS)));
+
+    let out = ison(&["to-json"], USERS);
+    assert!(stdout(&out).contains("\"users\""));
+
+    let out = ison(&["fmt", "--infer-types"], USERS);
+    assert!(stdout(&out).starts_with("table.users\nid:int name:string manager:ref?\n"));
+}
+
+#[test]
+fn test_validate_and_errors() {
+    let dir = std::env::temp_dir().join(format!("ison-cli-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let schema = dir.join("schema.ison");
+    std::fs::write(&schema, "table.users\nid:int name:string manager:ref?").unwrap();
+    let schema = schema.to_str().unwrap();
+
+    let out = ison(&["validate", "--schema", schema], USERS);
+    assert!(out.status.success());
+
+    let out = ison(
+        &["validate", "--schema", schema],
+        "table.users\nid name manager\nx Alice ~",
+    );
+    assert_eq!(out.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("users[0].id: expected int"));
+
+    assert_eq!(ison(&["bogus"], "").status.code(), Some(2));
+    assert_eq!(ison(&["from-csv"], "a\n1").status.code(), Some(2));
+    std::fs::remove_dir_all(dir).unwrap();
+}
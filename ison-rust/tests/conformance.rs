@@ -0,0 +1,138 @@
+#![cfg(feature = "serde")]
+//! The shared conformance fixtures in `../conformance` (see its README).
+//!
+//! Run with `ISON_BLESS=1` to rewrite the expected JSON of the valid cases
+//! from this parser's output, then review the diff before committing it.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ison_rs::{parse, Block, Document, Value};
+use serde_json::{json, Value as Json};
+
+fn fixtures(kind: &str) -> Vec<(String, PathBuf)> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../conformance")
+        .join(kind);
+    let mut cases: Vec<_> = fs::read_dir(&dir)
+        .unwrap_or_else(|e| panic!("{}: {}", dir.display(), e))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ison"))
+        .map(|path| {
+            (
+                path.file_stem().unwrap().to_string_lossy().into_owned(),
+                path,
+            )
+        })
+        .collect();
+    cases.sort();
+    assert!(!cases.is_empty(), "no fixtures in {}", dir.display());
+    cases
+}
+
+fn value_json(value: &Value) -> Json {
+    match value {
+        Value::Null => Json::Null,
+        Value::Bool(b) => json!(b),
+        Value::Int(i) => json!(i),
+        Value::Float(f) => json!(f),
+        Value::String(s) => json!(s),
+        Value::Reference(r) => match &r.ref_type {
+            Some(ref_type) => json!({"ref": r.id, "type": ref_type}),
+            None => json!({"ref": r.id}),
+        },
+        Value::Bytes(_) => {
+            json!({"bytes": value.to_string().trim_start_matches(ison_rs::BYTES_PREFIX)})
+        }
+        #[cfg(feature = "rust_decimal")]
+        Value::Decimal(d) => json!({"decimal": d.to_string()}),
+    }
+}
+
+fn block_json(block: &Block) -> Json {
+    let rows = |rows: &[ison_rs::Row]| -> Vec<Json> {
+        rows.iter()
+            .map(|row| {
+                let cells = row.iter().map(|(k, v)| (k.to_string(), value_json(v)));
+                Json::Object(cells.collect())
+            })
+            .collect()
+    };
+    let fields: Vec<Json> = block
+        .fields
+        .iter()
+        .map(|name| json!({"name": name, "type": block.get_field_type(name)}))
+        .collect();
+    json!({
+        "kind": block.kind,
+        "name": block.name,
+        "fields": fields,
+        "rows": rows(&block.rows),
+        "summary": rows(&block.summary_rows),
+    })
+}
+
+fn document_json(doc: &Document) -> Json {
+    json!({"blocks": doc.blocks.iter().map(block_json).collect::<Vec<_>>()})
+}
+
+#[test]
+fn test_valid_fixtures() {
+    let bless = std::env::var_os("ISON_BLESS").is_some();
+    let mut failures = Vec::new();
+    for (name, path) in fixtures("valid") {
+        let text = fs::read_to_string(&path).unwrap();
+        let expected_path = path.with_extension("json");
+        let actual = match parse(&text) {
+            Ok(doc) => document_json(&doc),
+            Err(e) => {
+                failures.push(format!("{}: {}", name, e));
+                continue;
+            }
+        };
+        if bless {
+            let pretty = serde_json::to_string_pretty(&actual).unwrap();
+            fs::write(&expected_path, pretty + "\n").unwrap();
+            continue;
+        }
+        let expected: Json = match fs::read_to_string(&expected_path) {
+            Ok(json) => serde_json::from_str(&json).unwrap(),
+            Err(e) => {
+                failures.push(format!("{}: {}: {}", name, expected_path.display(), e));
+                continue;
+            }
+        };
+        if actual != expected {
+            failures.push(format!(
+                "{}:\n  expected {}\n  actual   {}",
+                name, expected, actual
+            ));
+        }
+    }
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+#[test]
+fn test_invalid_fixtures() {
+    let mut failures = Vec::new();
+    for (name, path) in fixtures("invalid") {
+        let text = fs::read_to_string(&path).unwrap();
+        let expected: Json =
+            serde_json::from_str(&fs::read_to_string(path.with_extension("json")).unwrap())
+                .unwrap();
+        match parse(&text) {
+            Ok(_) => failures.push(format!("{}: parsed without error", name)),
+            Err(e) => {
+                let line = expected["line"].as_u64().map(|l| l as usize);
+                let message = expected["message"].as_str().unwrap_or("");
+                if e.line != line || !e.message.contains(message) {
+                    failures.push(format!(
+                        "{}: expected line {:?} with '{}', got line {:?}: {}",
+                        name, line, message, e.line, e.message
+                    ));
+                }
+            }
+        }
+    }
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
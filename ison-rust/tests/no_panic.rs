@@ -0,0 +1,77 @@
+//! Parsers must return `Err` on malformed input, never panic.
+//!
+//! A cheap stand-in for the cargo-fuzz targets in `fuzz/` that runs on stable:
+//! random splices of ISON fragments, multi-byte characters, and raw bytes.
+
+use ison_rs::{from_csv, from_isonb, from_markdown_table, parse, parse_isonl, to_isonb};
+
+/// SplitMix64, so failures reproduce from the iteration number
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+const FRAGMENTS: &[&str] = &[
+    "table.", "object.", "users", "\n", "\r\n", " ", "\t", "\"", "\\", "#", ":", "::", ".", "|",
+    ",", "---", "~", "null", "true", "1", "-2.5", "1e400", ":user:1", "id:int", "é", "日本", "🦀",
+    "\u{0301}", "\u{feff}", "**", "[", "]", "=", "!=", "*", "-1",
+];
+
+fn random_text(rng: &mut Rng) -> String {
+    let mut text = String::new();
+    for _ in 0..rng.below(40) {
+        text.push_str(FRAGMENTS[rng.below(FRAGMENTS.len())]);
+    }
+    text
+}
+
+fn random_bytes(rng: &mut Rng) -> Vec<u8> {
+    (0..rng.below(64)).map(|_| rng.next() as u8).collect()
+}
+
+#[test]
+fn test_text_parsers_never_panic() {
+    let mut rng = Rng(1);
+    for _ in 0..20_000 {
+        let text = if rng.below(4) == 0 {
+            String::from_utf8_lossy(&random_bytes(&mut rng)).into_owned()
+        } else {
+            random_text(&mut rng)
+        };
+        if let Ok(doc) = parse(&text) {
+            let _ = doc.select(&random_text(&mut rng));
+            let _ = doc.to_markdown();
+            let _ = ison_rs::dumps(&doc, true);
+        }
+        let _ = parse_isonl(&text);
+        let _ = from_csv(&text, "t");
+        let _ = from_markdown_table(&text, "t");
+    }
+}
+
+#[test]
+fn test_isonb_decoder_never_panics() {
+    let valid = to_isonb(&parse("table.t\nid:int name ref\n1 \"é\" :a:1\n---\n2 ~ true").unwrap());
+    let mut rng = Rng(2);
+    for _ in 0..20_000 {
+        let mut bytes = valid.clone();
+        for _ in 0..=rng.below(4) {
+            let i = rng.below(bytes.len());
+            bytes[i] = rng.next() as u8;
+        }
+        bytes.truncate(rng.below(bytes.len() + 1));
+        let _ = from_isonb(&bytes);
+        let _ = from_isonb(&random_bytes(&mut rng));
+    }
+}
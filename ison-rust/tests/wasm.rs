@@ -0,0 +1,57 @@
+//! The JavaScript bindings, run under Node with `wasm-pack test --node --features wasm`
+
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use ison_rs::wasm::{dumps_js, parse_js, to_json_js, validate_js, version_js};
+use js_sys::{Array, Reflect};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const USERS: &str = "table.users\nid name manager\n1 Alice null\n2 Bob :1";
+
+fn get(value: &JsValue, key: &str) -> JsValue {
+    Reflect::get(value, &key.into()).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn test_parse_and_dumps() {
+    let doc = parse_js(USERS).unwrap();
+    let block = Array::from(&get(&doc, "blocks")).get(0);
+    assert_eq!(get(&block, "name").as_string().as_deref(), Some("users"));
+
+    // Rows are plain objects and references `{ id, ref_type }`
+    let bob = Array::from(&get(&block, "rows")).get(1);
+    assert_eq!(get(&bob, "name").as_string().as_deref(), Some("Bob"));
+    assert_eq!(
+        get(&get(&bob, "manager"), "id").as_string().as_deref(),
+        Some("1")
+    );
+
+    assert_eq!(dumps_js(doc, None).unwrap(), USERS);
+    assert_eq!(version_js(), ison_rs::VERSION);
+}
+
+#[wasm_bindgen_test]
+fn test_json_and_validate() {
+    assert!(to_json_js(USERS, None).unwrap().starts_with("{\"users\":"));
+
+    let schema = "table.users\nid:int name:string";
+    let violations = Array::from(&validate_js(USERS, schema).unwrap());
+    assert_eq!(violations.length(), 0);
+    let violations =
+        Array::from(&validate_js("table.users\nid\nx", "table.users\nid:int").unwrap());
+    assert_eq!(violations.length(), 1);
+}
+
+#[wasm_bindgen_test]
+fn test_errors() {
+    let err = parse_js("table.t\nid\n1\nb64:***").unwrap_err();
+    assert_eq!(get(&err, "name").as_string().as_deref(), Some("ISONError"));
+    assert_eq!(get(&err, "line").as_f64(), Some(4.0));
+
+    let err = dumps_js(JsValue::from_str("not a document"), None).unwrap_err();
+    assert!(get(&err, "message")
+        .as_string()
+        .unwrap()
+        .starts_with("Invalid document"));
+}
@@ -0,0 +1,36 @@
+//! # ISON
+//!
+//! The ISON parser ([`ison_rs`]) and its validation layer ([`isonantic_rs`])
+//! behind one dependency. Parsing lives at the crate root, validation under
+//! [`validation`], and [`prelude`] brings in both:
+//!
+//! ```rust
+//! use ison::prelude::*;
+//!
+//! let doc = ison::parse("table.users\nid name boss\n1 Alice :2").unwrap();
+//! let users = table("users")
+//!     .field("id", int())
+//!     .field("name", string())
+//!     .field("boss", reference())
+//!     .validate(&doc)
+//!     .unwrap();
+//!
+//! // One reference type, and `From` between parsed and validated values
+//! let boss: &Reference = users.rows[0].get("boss").unwrap().as_reference().unwrap();
+//! assert_eq!(boss.id, "2");
+//! let name: Value = users.rows[0].get("name").cloned().unwrap().into();
+//! assert_eq!(name, Value::from("Alice"));
+//! ```
+
+pub use ison_rs::*;
+
+/// Schemas and validation, re-exported from `isonantic-rs`
+pub mod validation {
+    pub use isonantic_rs::*;
+}
+
+/// The parser's core types and functions plus the validation prelude
+pub mod prelude {
+    pub use ison_rs::{dumps, parse, Block, Document, Reference, Row, Value};
+    pub use isonantic_rs::prelude::*;
+}
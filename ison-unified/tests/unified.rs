@@ -0,0 +1,37 @@
+use ison::prelude::*;
+use ison::validation::messages;
+
+#[test]
+fn test_parse_validate_and_dump() {
+    let doc = parse("table.orders\nid user total\n1 :7 9.5\n2 ~ x").unwrap();
+    let schema = table("orders")
+        .field("id", int())
+        .field("user", reference().nullable())
+        .field("total", float());
+
+    let err = schema.validate(&doc).unwrap_err();
+    assert_eq!(err.errors[0].field, "[1].total");
+    assert_eq!(
+        messages::template(&err.errors[0].code),
+        Some("Expected number")
+    );
+
+    let doc = parse("table.orders\nid user total\n1 :7 9.5").unwrap();
+    let row = &schema.validate(&doc).unwrap().rows[0];
+    let values: Vec<Value> = ["id", "user", "total"]
+        .iter()
+        .map(|f| row.get(f).cloned().unwrap().into())
+        .collect();
+    assert_eq!(
+        values,
+        [
+            Value::Int(1),
+            Value::Reference(Reference::new("7")),
+            Value::Float(9.5)
+        ]
+    );
+    assert_eq!(
+        ison::dumps(&doc, false),
+        "table.orders\nid user total\n1 :7 9.5"
+    );
+}
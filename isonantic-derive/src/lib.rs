@@ -0,0 +1,246 @@
+//! # ISONantic Derive
+//!
+//! `#[derive(IsonSchema)]` for structs, generating an `isonantic_rs::IsonSchema`
+//! implementation: the `TableSchema` describing the table and the conversion
+//! from validated rows back into the struct.
+//!
+//! Use it through `isonantic-rs` with the `derive` feature enabled.
+//!
+//! ## Attributes
+//!
+//! Container:
+//! - `#[ison(table = "users")]` - table name (default: snake_case struct name)
+//!
+//! Field:
+//! - `#[ison(rename = "user_id")]` - column name (default: field name)
+//! - `#[ison(min = 1)]`, `#[ison(max = 100)]` - value bounds (length for strings)
+//! - `#[ison(email)]`, `#[ison(positive)]` - builder flags of the same name
+//! - `#[ison(one_of = ["a", "b"])]` - enum field with the listed variants
+//! - `#[ison(ignore_case)]` - case-insensitive `one_of` matching
+//! - `#[ison(default = expr)]` - default when the column is missing
+//!
+//! Fields of type `Option<T>` may be absent or `null`; all others are required.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, Data, DeriveInput, Expr, Fields, GenericArgument, LitStr, PathArguments,
+    Type,
+};
+
+/// Derive `isonantic_rs::IsonSchema` for a struct with named fields
+#[proc_macro_derive(IsonSchema, attributes(ison))]
+pub fn derive_ison_schema(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct FieldAttrs {
+    rename: Option<String>,
+    min: Option<Expr>,
+    max: Option<Expr>,
+    email: bool,
+    positive: bool,
+    one_of: Option<Expr>,
+    ignore_case: bool,
+    default: Option<Expr>,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+
+    let mut table_name = to_snake_case(&ident.to_string());
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("ison")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("table") {
+                table_name = meta.value()?.parse::<LitStr>()?.value();
+                Ok(())
+            } else {
+                Err(meta.error("unsupported container attribute, expected `table`"))
+            }
+        })?;
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(named) => &named.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "IsonSchema can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "IsonSchema can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut schema_fields = Vec::new();
+    let mut conversions = Vec::new();
+
+    for field in fields {
+        let field_ident = field.ident.as_ref().expect("named field");
+        let attrs = parse_field_attrs(field)?;
+        let column = attrs
+            .rename
+            .clone()
+            .unwrap_or_else(|| field_ident.to_string());
+
+        let (inner_ty, optional) = match option_inner(&field.ty) {
+            Some(inner) => (inner, true),
+            None => (&field.ty, false),
+        };
+
+        let mut builder = if let Some(variants) = &attrs.one_of {
+            quote! { ::isonantic_rs::enum_of(#variants) }
+        } else {
+            builder_for(inner_ty)?
+        };
+        if let Some(min) = &attrs.min {
+            builder = quote! { #builder.min(#min) };
+        }
+        if let Some(max) = &attrs.max {
+            builder = quote! { #builder.max(#max) };
+        }
+        if attrs.email {
+            builder = quote! { #builder.email() };
+        }
+        if attrs.positive {
+            builder = quote! { #builder.positive() };
+        }
+        if attrs.ignore_case {
+            builder = quote! { #builder.ignore_case() };
+        }
+        if let Some(default) = &attrs.default {
+            builder = quote! { #builder.default_value(#default) };
+        }
+        if optional {
+            builder = quote! { #builder.nullable() };
+        } else {
+            builder = quote! { #builder.required() };
+        }
+
+        schema_fields.push(quote! { .field(#column, #builder) });
+        conversions.push(quote! {
+            #field_ident: ::isonantic_rs::FromValidatedValue::from_validated(row.get(#column), #column)?
+        });
+    }
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::isonantic_rs::IsonSchema for #ident #ty_generics #where_clause {
+            fn schema() -> ::isonantic_rs::TableSchema {
+                ::isonantic_rs::table(#table_name)
+                    #(#schema_fields)*
+            }
+
+            fn from_validated_row(
+                row: &::isonantic_rs::ValidatedRow,
+            ) -> ::isonantic_rs::Result<Self> {
+                ::std::result::Result::Ok(Self {
+                    #(#conversions,)*
+                })
+            }
+        }
+    })
+}
+
+fn parse_field_attrs(field: &syn::Field) -> syn::Result<FieldAttrs> {
+    let mut attrs = FieldAttrs::default();
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("ison")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                attrs.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("min") {
+                attrs.min = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("max") {
+                attrs.max = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("email") {
+                attrs.email = true;
+            } else if meta.path.is_ident("positive") {
+                attrs.positive = true;
+            } else if meta.path.is_ident("one_of") {
+                attrs.one_of = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("ignore_case") {
+                attrs.ignore_case = true;
+            } else if meta.path.is_ident("default") {
+                attrs.default = Some(meta.value()?.parse()?);
+            } else {
+                return Err(meta.error("unsupported field attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(attrs)
+}
+
+/// Field builder matching a Rust type
+fn builder_for(ty: &Type) -> syn::Result<TokenStream2> {
+    let name = last_segment(ty)
+        .map(|s| s.ident.to_string())
+        .unwrap_or_default();
+    let builder = match name.as_str() {
+        "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32" | "u64" | "usize" => {
+            quote! { ::isonantic_rs::int() }
+        }
+        "f32" | "f64" => quote! { ::isonantic_rs::float() },
+        "bool" => quote! { ::isonantic_rs::boolean() },
+        "String" => quote! { ::isonantic_rs::string() },
+        "NaiveDate" => quote! { ::isonantic_rs::date() },
+        "DateTime" => quote! { ::isonantic_rs::datetime() },
+        "ISONReference" | "Reference" => quote! { ::isonantic_rs::reference() },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                ty,
+                "unsupported field type for IsonSchema",
+            ))
+        }
+    };
+    Ok(builder)
+}
+
+fn last_segment(ty: &Type) -> Option<&syn::PathSegment> {
+    match ty {
+        Type::Path(p) if p.qself.is_none() => p.path.segments.last(),
+        _ => None,
+    }
+}
+
+/// `T` when `ty` is `Option<T>`
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let segment = last_segment(ty)?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => match args.args.first()? {
+            GenericArgument::Type(inner) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn to_snake_case(name: &str) -> String {
+    let mut out = String::new();
+    for (i, ch) in name.chars().enumerate() {
+        if ch.is_uppercase() {
+            if i > 0 {
+                out.push('_');
+            }
+            out.extend(ch.to_lowercase());
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
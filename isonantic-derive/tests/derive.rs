@@ -0,0 +1,60 @@
+use ison_rs::parse;
+use isonantic_rs::chrono::NaiveDate;
+use isonantic_rs::prelude::*;
+
+#[derive(Debug, IsonSchema)]
+#[ison(table = "users")]
+struct User {
+    #[ison(min = 1)]
+    id: i64,
+    #[ison(email)]
+    email: String,
+    #[ison(one_of = ["active", "suspended"], ignore_case)]
+    status: String,
+    #[ison(rename = "joined_on")]
+    joined: Option<NaiveDate>,
+    #[ison(default = 0.0)]
+    score: f64,
+}
+
+#[derive(Debug, IsonSchema)]
+struct OrderLine {
+    qty: u32,
+}
+
+#[test]
+fn test_validate_into_structs() {
+    let doc = parse(
+        "table.users\nid email status joined_on\n1 a@x.io Active 2024-01-02\n2 b@x.io suspended null",
+    )
+    .unwrap();
+
+    let users: Vec<User> = validate_into(&doc).unwrap();
+    assert_eq!(users.len(), 2);
+    assert_eq!(users[0].id, 1);
+    assert_eq!(users[1].email, "b@x.io");
+    assert_eq!(users[0].status, "active");
+    assert_eq!(users[0].joined, NaiveDate::from_ymd_opt(2024, 1, 2));
+    assert_eq!(users[0].score, 0.0);
+    assert!(users[1].joined.is_none());
+}
+
+#[test]
+fn test_generated_schema_rejects_invalid_rows() {
+    let doc = parse("table.users\nid email status\n0 nope active").unwrap();
+    let err = User::validate_into(&doc).unwrap_err();
+    let fields: Vec<&str> = err.errors.iter().map(|e| e.field.as_str()).collect();
+    assert_eq!(fields, vec!["[0].id", "[0].email"]);
+}
+
+#[test]
+fn test_default_table_name_and_range_check() {
+    assert_eq!(OrderLine::schema().name, "order_line");
+
+    let doc = parse("table.order_line\nqty\n3").unwrap();
+    assert_eq!(OrderLine::validate_into(&doc).unwrap()[0].qty, 3);
+
+    let doc = parse("table.order_line\nqty\n-3").unwrap();
+    let err = OrderLine::validate_into(&doc).unwrap_err();
+    assert_eq!(err.errors[0].message, "Value out of range for u32");
+}
@@ -0,0 +1,327 @@
+//! Async field validators (feature `tokio`)
+//!
+//! Checks that need I/O, such as "the user id exists in the accounts
+//! service", implement [`AsyncFieldValidator`] and are attached with
+//! [`TableSchema::async_validator`]. [`TableSchema::validate_async`] first
+//! runs the ordinary validation, then awaits the async validators of every
+//! non-null value, at most [`ValidationOptions::max_concurrency`] at a time:
+//!
+//! ```rust
+//! use isonantic_rs::prelude::*;
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! let schema = table("users")
+//!     .field("id", int())
+//!     .field("homepage", string().nullable())
+//!     .async_validator(
+//!         "homepage",
+//!         async_custom(
+//!             |value| async move { value.as_str().is_some_and(|url| url.starts_with("https://")) },
+//!             "URL is not reachable",
+//!         ),
+//!     );
+//!
+//! let doc = ison_rs::parse("table.users\nid homepage\n1 https://a.dev\n2 http://b.dev\n3 ~").unwrap();
+//! let err = schema.validate_async(&doc).await.unwrap_err();
+//! assert_eq!(err.errors[0].field, "[1].homepage");
+//! # }
+//! ```
+//!
+//! Validators run as tokio tasks, so `validate_async` must be awaited
+//! inside a tokio runtime.
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use tokio::task::JoinSet;
+
+use crate::schema::{TableSchema, ValidationOptions};
+use crate::{FieldError, Result, ValidatedTable, ValidatedValue, ValidationError};
+
+/// Async validators awaited at once when `max_concurrency` is not set
+pub const DEFAULT_MAX_CONCURRENCY: usize = 16;
+
+/// A boxed future returned by [`AsyncFieldValidator::validate`]
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Field validator that awaits, e.g. on a network call
+pub trait AsyncFieldValidator: fmt::Debug + Send + Sync {
+    fn validate<'a>(
+        &'a self,
+        value: &'a ValidatedValue,
+        field: &'a str,
+    ) -> BoxFuture<'a, Result<()>>;
+}
+
+/// Shares one validator, e.g. holding a client, between schemas
+impl<T: AsyncFieldValidator + ?Sized> AsyncFieldValidator for Arc<T> {
+    fn validate<'a>(
+        &'a self,
+        value: &'a ValidatedValue,
+        field: &'a str,
+    ) -> BoxFuture<'a, Result<()>> {
+        (**self).validate(value, field)
+    }
+}
+
+/// An async validator attached to a field
+#[derive(Debug, Clone)]
+pub struct AsyncValidator {
+    pub field: String,
+    pub validator: Arc<dyn AsyncFieldValidator>,
+}
+
+/// Async validation function, see [`async_custom`]
+pub struct AsyncCustomValidator<F> {
+    func: F,
+    message: String,
+}
+
+impl<F> fmt::Debug for AsyncCustomValidator<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncCustomValidator")
+            .field("message", &self.message)
+            .finish()
+    }
+}
+
+impl<F, Fut> AsyncFieldValidator for AsyncCustomValidator<F>
+where
+    F: Fn(ValidatedValue) -> Fut + Send + Sync,
+    Fut: Future<Output = bool> + Send,
+{
+    fn validate<'a>(
+        &'a self,
+        value: &'a ValidatedValue,
+        field: &'a str,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            if !(self.func)(value.clone()).await {
+                return Err(ValidationError::single(field, &self.message));
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Create an async validator from a function returning whether the value
+/// is valid
+pub fn async_custom<F, Fut>(func: F, message: impl Into<String>) -> AsyncCustomValidator<F>
+where
+    F: Fn(ValidatedValue) -> Fut + Send + Sync,
+    Fut: Future<Output = bool> + Send,
+{
+    AsyncCustomValidator {
+        func,
+        message: message.into(),
+    }
+}
+
+impl TableSchema {
+    /// Check `field` with `validator` in [`validate_async`](Self::validate_async);
+    /// [`validate`](Self::validate) ignores it
+    pub fn async_validator(
+        mut self,
+        field: impl Into<String>,
+        validator: impl AsyncFieldValidator + 'static,
+    ) -> Self {
+        self.async_validators.push(AsyncValidator {
+            field: field.into(),
+            validator: Arc::new(validator),
+        });
+        self
+    }
+
+    pub async fn validate_async(&self, doc: &ison_rs::Document) -> Result<ValidatedTable> {
+        self.validate_async_with(doc, &ValidationOptions::default())
+            .await
+    }
+
+    /// Validate, then run the async validators on every non-null value of
+    /// the valid table.
+    ///
+    /// Errors are reported in row order whichever validator finishes first.
+    /// Once `options` allow no more errors the remaining validations are
+    /// cancelled.
+    pub async fn validate_async_with(
+        &self,
+        doc: &ison_rs::Document,
+        options: &ValidationOptions,
+    ) -> Result<ValidatedTable> {
+        let table = self.validate_with(doc, options)?;
+        let limit = options.error_limit();
+        let max_concurrency = options
+            .max_concurrency
+            .unwrap_or(DEFAULT_MAX_CONCURRENCY)
+            .max(1);
+
+        let jobs = table.rows.iter().enumerate().flat_map(|(row_idx, row)| {
+            self.async_validators
+                .iter()
+                .enumerate()
+                .filter_map(move |(order, v)| {
+                    let value = row.get(&v.field).filter(|value| !value.is_null())?;
+                    Some((row_idx, order, v.clone(), value.clone()))
+                })
+        });
+
+        let mut tasks = JoinSet::new();
+        let mut errors: Vec<(usize, usize, ValidationError)> = Vec::new();
+        let mut error_count = 0;
+        let full = |count: usize| limit.is_some_and(|max| count >= max);
+        let mut jobs = jobs.peekable();
+        while !full(error_count) && (jobs.peek().is_some() || !tasks.is_empty()) {
+            if tasks.len() < max_concurrency {
+                if let Some((row_idx, order, v, value)) = jobs.next() {
+                    tasks.spawn(async move {
+                        let result = v.validator.validate(&value, &v.field).await;
+                        (row_idx, order, result)
+                    });
+                    continue;
+                }
+            }
+            let finished = match tasks.join_next().await {
+                Some(Ok(finished)) => finished,
+                Some(Err(e)) => std::panic::resume_unwind(e.into_panic()),
+                None => continue,
+            };
+            if let (row_idx, order, Err(e)) = finished {
+                error_count += e.errors.len();
+                errors.push((row_idx, order, e));
+            }
+        }
+        let truncated = (full(error_count) && (jobs.peek().is_some() || !tasks.is_empty()))
+            || limit.is_some_and(|max| error_count > max);
+        tasks.abort_all();
+
+        if errors.is_empty() {
+            return Ok(table);
+        }
+        errors.sort_by_key(|(row_idx, order, _)| (*row_idx, *order));
+        let block = doc.get(&self.name);
+        let mut all_errors: Vec<FieldError> = errors
+            .into_iter()
+            .flat_map(|(row_idx, _, e)| {
+                let row = block.and_then(|b| b.rows.get(row_idx));
+                e.errors.into_iter().map(move |err| FieldError {
+                    value: err.value.or_else(|| {
+                        row.and_then(|r| r.get(err.field.as_str()))
+                            .map(|v| v.to_string())
+                    }),
+                    field: format!("[{}].{}", row_idx, err.field),
+                    row_snapshot: options.include_row_snapshot.then(|| row.cloned()).flatten(),
+                    ..err
+                })
+            })
+            .collect();
+
+        if let Some(max) = limit {
+            all_errors.truncate(max);
+        }
+        let mut error = ValidationError::new(all_errors);
+        error.truncated = truncated;
+        Err(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::prelude::*;
+    use ison_rs::parse;
+
+    /// Allows ids below `known`, tracking how many checks run at once
+    #[derive(Debug, Default)]
+    struct KnownIds {
+        known: i64,
+        running: AtomicUsize,
+        peak: AtomicUsize,
+    }
+
+    impl AsyncFieldValidator for KnownIds {
+        fn validate<'a>(
+            &'a self,
+            value: &'a ValidatedValue,
+            field: &'a str,
+        ) -> BoxFuture<'a, crate::Result<()>> {
+            Box::pin(async move {
+                let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+                self.peak.fetch_max(running, Ordering::SeqCst);
+                let id = value.as_int().unwrap_or_default();
+                // Later rows finish first
+                tokio::time::sleep(Duration::from_millis(20 - id as u64)).await;
+                self.running.fetch_sub(1, Ordering::SeqCst);
+                if id >= self.known {
+                    return Err(ValidationError::single(field, "Unknown user"));
+                }
+                Ok(())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_validate_async() {
+        let known = Arc::new(KnownIds {
+            known: 3,
+            ..Default::default()
+        });
+        let schema = table("orders")
+            .field("user", int())
+            .field("note", string().nullable())
+            .async_validator("user", known.clone())
+            .async_validator(
+                "note",
+                async_custom(
+                    |value| async move { value.as_str() != Some("spam") },
+                    "Spam",
+                ),
+            );
+
+        let doc = parse("table.orders\nuser note\n1 spam\n2 ~\n3 hi\n4 ~\n5 ~\n6 ~").unwrap();
+        assert!(schema.validate(&doc).is_ok());
+        let options = ValidationOptions {
+            max_concurrency: Some(2),
+            ..Default::default()
+        };
+        let err = schema
+            .validate_async_with(&doc, &options)
+            .await
+            .unwrap_err();
+        let errors: Vec<String> = err.errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            errors,
+            [
+                "[0].note: Spam",
+                "[2].user: Unknown user",
+                "[3].user: Unknown user",
+                "[4].user: Unknown user",
+                "[5].user: Unknown user",
+            ]
+        );
+        assert_eq!(err.errors[1].value.as_deref(), Some("3"));
+        assert!(known.peak.load(Ordering::SeqCst) <= 2);
+
+        let options = ValidationOptions {
+            max_errors: Some(2),
+            ..Default::default()
+        };
+        let err = schema
+            .validate_async_with(&doc, &options)
+            .await
+            .unwrap_err();
+        assert_eq!(err.errors.len(), 2);
+        assert!(err.truncated);
+
+        // Async validators only run on tables that passed validation
+        let doc = parse("table.orders\nuser\nx\n9").unwrap();
+        let err = schema.validate_async(&doc).await.unwrap_err();
+        assert_eq!(err.errors.len(), 1);
+        assert_eq!(err.errors[0].message, "Expected integer");
+    }
+}
@@ -0,0 +1,239 @@
+//! Conditional field rules
+//!
+//! Some fields only matter depending on another one: a card number is
+//! needed when the payment type is `card`, and must be left out otherwise.
+//! A [`ConditionalRule`] states that on a [`TableSchema`]:
+//!
+//! ```rust
+//! use isonantic_rs::prelude::*;
+//!
+//! let schema = table("payments")
+//!     .field("type", enum_of(["card", "cash"]))
+//!     .field("card_number", string().nullable())
+//!     .conditional(when("type").equals("card").then(field("card_number").required()))
+//!     .conditional(when("type").equals("cash").then(field("card_number").forbidden()));
+//!
+//! let doc = ison_rs::parse("table.payments\ntype card_number\ncash ~\ncard").unwrap();
+//! let err = schema.validate(&doc).unwrap_err();
+//! assert_eq!(err.errors[0].field, "[1].card_number");
+//! assert_eq!(err.errors[0].message, "Field is required when type is card");
+//! ```
+//!
+//! Rules look at the validated row, so enums are compared in their
+//! canonical spelling, and run once the row's fields have validated.
+
+use std::fmt;
+
+use ison_rs::Value;
+
+use crate::schema::TableSchema;
+use crate::{FieldError, ValidatedRow};
+
+/// Test on the value of one field
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    /// Present and not null
+    Present,
+    Equals(Value),
+    OneOf(Vec<Value>),
+}
+
+/// What a rule asks of its field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Requirement {
+    /// Present and not null
+    Required,
+    /// Absent or null
+    Forbidden,
+}
+
+/// Condition builder, see [`when`]
+#[derive(Debug, Clone)]
+pub struct When {
+    field: String,
+    condition: Condition,
+}
+
+impl When {
+    pub fn equals(mut self, value: impl Into<Value>) -> Self {
+        self.condition = Condition::Equals(value.into());
+        self
+    }
+
+    pub fn one_of<I, V>(mut self, values: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<Value>,
+    {
+        self.condition = Condition::OneOf(values.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Require `then` of every row matching the condition
+    pub fn then(self, then: FieldRequirement) -> ConditionalRule {
+        ConditionalRule {
+            field: self.field,
+            condition: self.condition,
+            then_field: then.field,
+            requirement: then.requirement,
+        }
+    }
+}
+
+/// Requirement builder, see [`field`]
+#[derive(Debug, Clone)]
+pub struct FieldRequirement {
+    field: String,
+    requirement: Requirement,
+}
+
+impl FieldRequirement {
+    pub fn required(mut self) -> Self {
+        self.requirement = Requirement::Required;
+        self
+    }
+
+    pub fn forbidden(mut self) -> Self {
+        self.requirement = Requirement::Forbidden;
+        self
+    }
+}
+
+/// A requirement on one field that applies when another field matches a
+/// condition
+#[derive(Debug, Clone)]
+pub struct ConditionalRule {
+    pub field: String,
+    pub condition: Condition,
+    pub then_field: String,
+    pub requirement: Requirement,
+}
+
+impl ConditionalRule {
+    /// Whether `row` matches the condition
+    pub fn applies(&self, row: &ValidatedRow) -> bool {
+        let value = row
+            .get(&self.field)
+            .map(|v| v.to_ison_value())
+            .unwrap_or(Value::Null);
+        match &self.condition {
+            Condition::Present => !value.is_null(),
+            Condition::Equals(expected) => value == *expected,
+            Condition::OneOf(allowed) => allowed.contains(&value),
+        }
+    }
+
+    /// The error for `row`, against the constrained field, or `None` if it
+    /// satisfies the rule
+    pub(crate) fn check(&self, row: &ValidatedRow) -> Option<FieldError> {
+        if !self.applies(row) {
+            return None;
+        }
+        let present = row.get(&self.then_field).is_some_and(|v| !v.is_null());
+        let code = match (self.requirement, present) {
+            (Requirement::Required, false) => "conditional.required",
+            (Requirement::Forbidden, true) => "conditional.forbidden",
+            _ => return None,
+        };
+        Some(FieldError::coded(
+            &self.then_field,
+            code,
+            [("condition", self.to_string())],
+        ))
+    }
+}
+
+/// The condition, as in "when `type is card`"
+impl fmt::Display for ConditionalRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.condition {
+            Condition::Present => write!(f, "{} is present", self.field),
+            Condition::Equals(value) => write!(f, "{} is {}", self.field, value),
+            Condition::OneOf(values) => {
+                let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+                write!(f, "{} is one of {}", self.field, values.join(", "))
+            }
+        }
+    }
+}
+
+impl TableSchema {
+    /// Add a rule requiring or forbidding a field depending on another
+    pub fn conditional(mut self, rule: ConditionalRule) -> Self {
+        self.conditionals.push(rule);
+        self
+    }
+}
+
+/// Start a rule that applies when `field` is present; narrow it with
+/// [`equals`](When::equals) or [`one_of`](When::one_of)
+pub fn when(field: impl Into<String>) -> When {
+    When {
+        field: field.into(),
+        condition: Condition::Present,
+    }
+}
+
+/// The field a conditional rule constrains; required unless
+/// [`forbidden`](FieldRequirement::forbidden)
+pub fn field(name: impl Into<String>) -> FieldRequirement {
+    FieldRequirement {
+        field: name.into(),
+        requirement: Requirement::Required,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use ison_rs::parse;
+
+    #[test]
+    fn test_conditional_rules() {
+        let schema = table("accounts")
+            .field(
+                "kind",
+                enum_of(["personal", "business", "charity"]).ignore_case(),
+            )
+            .field("company", string().nullable())
+            .field("tax_id", string().nullable())
+            .field("birthday", date().nullable())
+            .conditional(
+                when("kind")
+                    .one_of(["business", "charity"])
+                    .then(field("company")),
+            )
+            .conditional(when("company").then(field("tax_id").required()))
+            .conditional(
+                when("kind")
+                    .equals("personal")
+                    .then(field("company").forbidden()),
+            );
+
+        let doc = parse(
+            "table.accounts\nkind company tax_id birthday\n\
+             personal ~ ~ 1990-01-01\n\
+             Business Acme T-1 null\n\
+             charity\n\
+             personal Acme T-2\n\
+             business Initech",
+        )
+        .unwrap();
+        let err = schema.validate(&doc).unwrap_err();
+        let errors: Vec<String> = err.errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            errors,
+            [
+                "[2].company: Field is required when kind is one of business, charity",
+                "[3].company: Field is not allowed when kind is personal",
+                "[4].tax_id: Field is required when company is present",
+            ]
+        );
+
+        // Rows with field errors are not checked against the rules
+        let doc = parse("table.accounts\nkind company\nother").unwrap();
+        let err = schema.validate(&doc).unwrap_err();
+        assert_eq!(err.errors.len(), 1);
+        assert_eq!(err.errors[0].field, "[0].kind");
+    }
+}
@@ -0,0 +1,282 @@
+//! Whole-document validation
+//!
+//! A [`DocumentSchema`] groups the table schemas of one document and
+//! validates them together, reporting every table's errors at once with the
+//! table name in front (`orders.[2].user`). Reference fields declared with
+//! [`points_to`](crate::RefFieldBuilder::points_to) are checked against the
+//! rows of their target table:
+//!
+//! ```rust
+//! use isonantic_rs::prelude::*;
+//!
+//! let doc = ison_rs::parse(
+//!     "table.users\nid name\n1 Alice\n\ntable.orders\nid user\n10 :1\n11 :2",
+//! )
+//! .unwrap();
+//! let schema = document()
+//!     .table(table("users").field("id", int()).field("name", string()))
+//!     .table(table("orders").field("id", int()).field("user", reference().points_to("users")));
+//!
+//! let err = schema.validate(&doc).unwrap_err();
+//! assert_eq!(err.errors[0].field, "orders.[1].user");
+//! assert_eq!(err.errors[0].message, "Reference :2 has no match in users");
+//! ```
+
+use std::ops::Index;
+
+use crate::schema::{TableSchema, ValidationOptions};
+use crate::{FieldError, Result, ValidatedTable, ValidationError};
+
+/// Schema for a document of several tables
+#[derive(Debug, Clone, Default)]
+pub struct DocumentSchema {
+    pub tables: Vec<TableSchema>,
+}
+
+impl DocumentSchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A schema with [`TableSchema::from_block_header`] for every block of
+    /// `doc`
+    pub fn from_headers(doc: &ison_rs::Document) -> Self {
+        Self {
+            tables: doc
+                .blocks
+                .iter()
+                .map(TableSchema::from_block_header)
+                .collect(),
+        }
+    }
+
+    pub fn table(mut self, schema: TableSchema) -> Self {
+        self.tables.push(schema);
+        self
+    }
+
+    pub fn validate(&self, doc: &ison_rs::Document) -> Result<ValidatedDocument> {
+        self.validate_with(doc, &ValidationOptions::default())
+    }
+
+    /// Validate every table, with `options` bounding the errors collected
+    /// across all of them
+    pub fn validate_with(
+        &self,
+        doc: &ison_rs::Document,
+        options: &ValidationOptions,
+    ) -> Result<ValidatedDocument> {
+        let limit = options.error_limit();
+        let mut validated = ValidatedDocument::default();
+        let mut errors: Vec<FieldError> = Vec::new();
+        let mut truncated = false;
+
+        for (i, schema) in self.tables.iter().enumerate() {
+            match schema.validate_with(doc, options) {
+                Ok(table) => validated.tables.push(table),
+                Err(e) => {
+                    truncated |= e.truncated;
+                    errors.extend(e.errors.into_iter().map(|mut err| {
+                        err.field = if err.field.is_empty() {
+                            schema.name.clone()
+                        } else {
+                            format!("{}.{}", schema.name, err.field)
+                        };
+                        err
+                    }));
+                    if let Some(max) = limit.filter(|max| errors.len() >= *max) {
+                        truncated |= errors.len() > max || i + 1 < self.tables.len();
+                        errors.truncate(max);
+                        break;
+                    }
+                }
+            }
+        }
+
+        if !errors.is_empty() {
+            let mut error = ValidationError::new(errors);
+            error.truncated = truncated;
+            return Err(error);
+        }
+        Ok(validated)
+    }
+}
+
+/// The validated tables of a document, in schema order
+#[derive(Debug, Clone, Default)]
+pub struct ValidatedDocument {
+    pub tables: Vec<ValidatedTable>,
+}
+
+impl ValidatedDocument {
+    pub fn get(&self, name: &str) -> Option<&ValidatedTable> {
+        self.tables.iter().find(|t| t.name == name)
+    }
+
+    /// Every table's [`to_document`](ValidatedTable::to_document) block in
+    /// one document
+    pub fn to_document(&self) -> ison_rs::Document {
+        let mut doc = ison_rs::Document::new();
+        for table in &self.tables {
+            doc.blocks.extend(table.to_document().blocks);
+        }
+        doc
+    }
+
+    pub fn to_ison(&self, align: bool) -> String {
+        ison_rs::dumps(&self.to_document(), align)
+    }
+}
+
+impl Index<&str> for ValidatedDocument {
+    type Output = ValidatedTable;
+
+    fn index(&self, name: &str) -> &Self::Output {
+        self.get(name)
+            .unwrap_or_else(|| panic!("No validated table: {}", name))
+    }
+}
+
+/// Create a document schema
+pub fn document() -> DocumentSchema {
+    DocumentSchema::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use ison_rs::parse;
+
+    const DOC: &str = "table.users\nid email\n1 a@x.com\n2 b@x.com\n\n\
+                       table.orders\nid user buyer\n10 :user:1 :a@x.com\n11 :2 :c@x.com\n12 :user:9 ~";
+
+    #[test]
+    fn test_reference_targets() {
+        let doc = parse(DOC).unwrap();
+        let users = table("users")
+            .field("id", int())
+            .field("email", string().email());
+        let orders = table("orders")
+            .field("user", reference().points_to("users").ref_type("user"))
+            .field(
+                "buyer",
+                reference()
+                    .points_to("users")
+                    .target_key("email")
+                    .nullable(),
+            );
+
+        let err = document()
+            .table(users.clone())
+            .table(orders.clone())
+            .validate(&doc)
+            .unwrap_err();
+        let errors: Vec<String> = err.errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            errors,
+            [
+                "orders.[1].user: Expected reference of type user",
+                "orders.[1].buyer: Reference :c@x.com has no match in users",
+                "orders.[2].user: Reference :user:9 has no match in users",
+            ]
+        );
+        assert_eq!(err.errors[2].value.as_deref(), Some(":user:9"));
+
+        // The same checks run when the table is validated on its own
+        let fixed =
+            parse(&DOC.replace(":2 :c@x.com\n12 :user:9", ":user:2 :b@x.com\n12 :user:1")).unwrap();
+        assert_eq!(orders.validate(&fixed).unwrap().len(), 3);
+        let validated = document()
+            .table(users)
+            .table(orders)
+            .validate(&fixed)
+            .unwrap();
+        assert_eq!(validated["orders"].len(), 3);
+        assert_eq!(validated.get("users").map(|t| t.len()), Some(2));
+
+        let doc = parse("table.orders\nid user\n10 :1").unwrap();
+        let err = document()
+            .table(table("orders").field("user", reference().points_to("users")))
+            .table(table("users").field("id", int()))
+            .validate(&doc)
+            .unwrap_err();
+        let errors: Vec<String> = err.errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            errors,
+            [
+                "orders.user: Referenced table users is missing",
+                "users: Missing table: users"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_document_error_limit() {
+        let doc = parse("table.a\nn\nx\ny\n\ntable.b\nn\nz").unwrap();
+        let schema = document()
+            .table(table("a").field("n", int()))
+            .table(table("b").field("n", int()));
+
+        assert_eq!(schema.validate(&doc).unwrap_err().errors.len(), 3);
+        let options = ValidationOptions {
+            max_errors: Some(2),
+            ..Default::default()
+        };
+        let err = schema.validate_with(&doc, &options).unwrap_err();
+        assert_eq!(err.errors.len(), 2);
+        assert!(err.truncated);
+        assert_eq!(err.errors[1].field, "a.[1].n");
+    }
+
+    #[test]
+    fn test_to_document() {
+        let doc = parse(
+            "table.users\nid email\n1 a@x.com\n2 ~\n\n\
+             table.orders\nplaced user status\n2024-1-05 :1 OPEN",
+        )
+        .unwrap();
+        let schema = document()
+            .table(
+                table("users")
+                    .field("id", int())
+                    .field("email", string().nullable()),
+            )
+            .table(
+                table("orders")
+                    .field("user", reference().points_to("users"))
+                    .field("status", enum_of(["open", "shipped"]).ignore_case())
+                    .field("placed", date().format("%Y-%m-%d"))
+                    .field("note", string()),
+            );
+
+        let validated = schema.validate(&doc).unwrap();
+        let text = validated.to_ison(false);
+        assert_eq!(
+            text,
+            "table.users\nid email\n1 \"a@x.com\"\n2 null\n\n\
+             table.orders\nuser status placed\n:1 open 2024-01-05"
+        );
+        assert!(schema.validate(&parse(&text).unwrap()).is_ok());
+
+        // A column that is null in every row is still written
+        let schema = table("t")
+            .field("id", int())
+            .field("note", string().nullable().required());
+        let validated = schema
+            .validate(&parse("table.t\nid note\n1 ~\n2 ~").unwrap())
+            .unwrap();
+        let text = validated.to_ison(false);
+        assert_eq!(text, "table.t\nid note\n1 null\n2 null");
+        assert!(schema.validate(&parse(&text).unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_from_headers() {
+        let doc = parse("table.a\nx:int\n1\n\ntable.b\ny:bool z\nmaybe 2").unwrap();
+        let err = DocumentSchema::from_headers(&doc)
+            .validate(&doc)
+            .unwrap_err();
+        assert_eq!(err.errors.len(), 1);
+        assert_eq!(err.errors[0].field, "b.[0].y");
+    }
+}
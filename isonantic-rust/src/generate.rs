@@ -0,0 +1,329 @@
+//! Synthetic sample data (feature `rand`)
+//!
+//! [`TableSchema::generate`] runs validation in reverse: it builds a table of
+//! random rows that satisfy the schema, useful as test fixtures or as
+//! few-shot examples of the expected shape for an LLM:
+//!
+//! ```rust
+//! use isonantic_rs::prelude::*;
+//! use isonantic_rs::rand::{rngs::StdRng, SeedableRng};
+//!
+//! let schema = table("users")
+//!     .field("id", int().positive())
+//!     .field("email", string().email())
+//!     .field("role", enum_of(["admin", "member"]))
+//!     .field("joined", date().nullable());
+//!
+//! let doc = schema.generate(5, &mut StdRng::seed_from_u64(7));
+//! assert_eq!(doc["users"].rows.len(), 5);
+//! assert!(schema.validate(&doc).is_ok());
+//! ```
+//!
+//! Values are drawn within each field's constraints. Fields limited by
+//! [`one_of`](crate::IntFieldBuilder::one_of) take one of the listed values.
+//! Rows failing custom validators, row checks or conditional rules are
+//! redrawn a bounded number of times, after which the last attempt is kept.
+//! Table checks and summary rows are not generated for.
+
+use chrono::{Duration, NaiveDate, Offset, Utc};
+use ison_rs::{Block, Document, FieldInfo, Reference, Row, Value};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::document::DocumentSchema;
+use crate::schema::{
+    id_text, DateConstraints, DateTimeConstraints, FieldSchema, FieldType, NumberConstraints,
+    StringConstraints, TableSchema, ValidationOptions,
+};
+
+/// Draws of one row before giving up on its custom checks
+const MAX_ATTEMPTS: usize = 100;
+
+/// Span drawn from on the open side of a one-sided or unbounded range
+const DEFAULT_SPAN: i64 = 1000;
+
+impl TableSchema {
+    /// A document holding this schema's table with `n` random valid rows
+    pub fn generate<R: Rng + ?Sized>(&self, n: usize, rng: &mut R) -> Document {
+        let mut doc = Document::new();
+        doc.blocks.push(self.generate_block(n, rng));
+        doc
+    }
+
+    fn generate_block<R: Rng + ?Sized>(&self, n: usize, rng: &mut R) -> Block {
+        let mut block = Block::new("table", self.name.clone());
+        for field in &self.fields {
+            block.fields.push(field.name.clone());
+            block.field_info.push(FieldInfo::new(field.name.clone()));
+        }
+        for _ in 0..n {
+            let mut row = self.generate_row(rng);
+            for _ in 1..MAX_ATTEMPTS {
+                if self.accepts(&row) {
+                    break;
+                }
+                row = self.generate_row(rng);
+            }
+            block.rows.push(row);
+        }
+        block
+    }
+
+    fn generate_row<R: Rng + ?Sized>(&self, rng: &mut R) -> Row {
+        self.fields
+            .iter()
+            .map(|field| {
+                let value = if field.nullable && rng.gen_ratio(1, 10) {
+                    Value::Null
+                } else {
+                    generate_value(field, rng)
+                };
+                (field.name.as_str().into(), value)
+            })
+            .collect()
+    }
+
+    /// Whether `row` passes the row-level validation, references aside
+    fn accepts(&self, row: &Row) -> bool {
+        let (_, errors, _) =
+            self.validate_row(0, row, &[], &ValidationOptions::default(), &mut Vec::new());
+        errors.is_empty()
+    }
+}
+
+impl DocumentSchema {
+    /// A document with `n` random valid rows in every table.
+    ///
+    /// References that [`points_to`](crate::RefFieldBuilder::points_to) a
+    /// table of the schema name ids drawn from that table's rows.
+    pub fn generate<R: Rng + ?Sized>(&self, n: usize, rng: &mut R) -> Document {
+        let mut doc = Document::new();
+        for schema in &self.tables {
+            doc.blocks.push(schema.generate_block(n, rng));
+        }
+
+        for (block_idx, schema) in self.tables.iter().enumerate() {
+            for field in &schema.fields {
+                let FieldType::Reference(c) = &field.field_type else {
+                    continue;
+                };
+                let Some(target) = c.target.as_deref() else {
+                    continue;
+                };
+                let key = c.target_key.as_deref().unwrap_or("id");
+                let ids: Vec<String> = doc
+                    .get(target)
+                    .map(|b| {
+                        b.rows
+                            .iter()
+                            .filter_map(|r| r.get(key))
+                            .filter_map(id_text)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                for row in doc.blocks[block_idx].rows.iter_mut() {
+                    if let Some(Value::Reference(r)) = row.get_mut(field.name.as_str()) {
+                        if let Some(id) = ids.choose(rng) {
+                            r.id = id.clone();
+                        }
+                    }
+                }
+            }
+        }
+        doc
+    }
+}
+
+fn generate_value<R: Rng + ?Sized>(field: &FieldSchema, rng: &mut R) -> Value {
+    let allowed = field.validators.iter().find_map(|v| v.allowed_values());
+    if let Some(text) = allowed.and_then(|allowed| allowed.choose(rng)) {
+        let value = Value::String(text.clone());
+        return field.field_type.coerce(&value).unwrap_or(value);
+    }
+
+    match &field.field_type {
+        FieldType::String(c) => Value::String(generate_string(c, rng)),
+        FieldType::Int(c) => Value::Int(generate_int(c, rng)),
+        FieldType::Float(c) => Value::Float(generate_float(c, rng)),
+        FieldType::Bool => Value::Bool(rng.gen()),
+        FieldType::Enum(c) => c
+            .variants
+            .choose(rng)
+            .map(|v| Value::String(v.clone()))
+            .unwrap_or(Value::Null),
+        FieldType::Date(c) => Value::String(generate_date(c, rng)),
+        FieldType::DateTime(c) => Value::String(generate_datetime(c, rng)),
+        FieldType::Reference(c) => Value::Reference(Reference {
+            id: rng.gen_range(1..=DEFAULT_SPAN).to_string(),
+            ref_type: c.ref_type.clone(),
+        }),
+        FieldType::Null => Value::Null,
+    }
+}
+
+/// Lowercase letters between the prefix and suffix, as long as the
+/// length limits allow
+fn generate_string<R: Rng + ?Sized>(c: &StringConstraints, rng: &mut R) -> String {
+    let prefix = c.starts_with.as_deref().unwrap_or("");
+    let mut suffix = c.ends_with.as_deref().unwrap_or("").to_string();
+    if c.email {
+        suffix.push_str("@example.com");
+    }
+    let fixed = prefix.len() + suffix.len();
+    let min = c.min_length.unwrap_or(1).max(fixed + 1);
+    let max = [c.max_length, c.max_bytes]
+        .into_iter()
+        .flatten()
+        .min()
+        .unwrap_or(min + 8)
+        .max(min);
+
+    let len = rng.gen_range(min..=max) - fixed;
+    let letters: String = (0..len).map(|_| rng.gen_range('a'..='z')).collect();
+    format!("{prefix}{letters}{suffix}")
+}
+
+fn generate_int<R: Rng + ?Sized>(c: &NumberConstraints, rng: &mut R) -> i64 {
+    let mut lo = c.min.map(|v| v.ceil() as i64);
+    if let Some(gt) = c.gt {
+        lo = lo.max(Some(gt.floor() as i64 + 1));
+    }
+    if c.positive {
+        lo = lo.max(Some(1));
+    }
+    let mut hi = c.max.map(|v| v.floor() as i64);
+    if let Some(lt) = c.lt {
+        hi = Some(hi.map_or(lt.ceil() as i64 - 1, |hi| hi.min(lt.ceil() as i64 - 1)));
+    }
+    if c.negative {
+        hi = Some(hi.map_or(-1, |hi| hi.min(-1)));
+    }
+    let (lo, hi) = match (lo, hi) {
+        (Some(lo), Some(hi)) => (lo, hi.max(lo)),
+        (Some(lo), None) => (lo, lo.saturating_add(DEFAULT_SPAN)),
+        (None, Some(hi)) => (hi.saturating_sub(DEFAULT_SPAN), hi),
+        (None, None) => (0, DEFAULT_SPAN),
+    };
+
+    match c.multiple_of.filter(|m| m.fract() == 0.0 && *m >= 1.0) {
+        Some(m) => {
+            let m = m as i64;
+            let first = lo.div_euclid(m) + i64::from(lo.rem_euclid(m) != 0);
+            let last = hi.div_euclid(m).max(first);
+            rng.gen_range(first..=last) * m
+        }
+        None => rng.gen_range(lo..=hi),
+    }
+}
+
+/// A value on a grid of `multiple_of`, or of hundredths when unset
+fn generate_float<R: Rng + ?Sized>(c: &NumberConstraints, rng: &mut R) -> f64 {
+    let mut lo = c.min.into_iter().chain(c.gt).reduce(f64::max);
+    if c.positive {
+        lo = Some(lo.map_or(0.0, |lo| lo.max(0.0)));
+    }
+    let mut hi = c.max.into_iter().chain(c.lt).reduce(f64::min);
+    if c.negative {
+        hi = Some(hi.map_or(0.0, |hi| hi.min(0.0)));
+    }
+    let span = DEFAULT_SPAN as f64;
+    let (lo, hi) = match (lo, hi) {
+        (Some(lo), Some(hi)) => (lo, hi.max(lo)),
+        (Some(lo), None) => (lo, lo + span),
+        (None, Some(hi)) => (hi - span, hi),
+        (None, None) => (0.0, span),
+    };
+
+    // Exclusive bounds and `positive`/`negative` exclude the grid's ends
+    let step = c.multiple_of.filter(|m| *m > 0.0).unwrap_or(0.01);
+    let first = (lo / step).ceil() as i64 + 1;
+    let last = ((hi / step).floor() as i64 - 1).max(first);
+    let value = rng.gen_range(first..=last) as f64 * step;
+    // Trim the float noise of e.g. 7 * 0.01
+    (value * 1e9).round() / 1e9
+}
+
+fn generate_date<R: Rng + ?Sized>(c: &DateConstraints, rng: &mut R) -> String {
+    let lo = c
+        .min
+        .unwrap_or_else(|| NaiveDate::from_ymd_opt(2020, 1, 1).unwrap_or_default());
+    let hi = c.max.unwrap_or(lo + Duration::days(5 * 365)).max(lo);
+    let date = lo + Duration::days(rng.gen_range(0..=(hi - lo).num_days()));
+    let format = c.formats.first().map_or("%Y-%m-%d", String::as_str);
+    date.format(format).to_string()
+}
+
+fn generate_datetime<R: Rng + ?Sized>(c: &DateTimeConstraints, rng: &mut R) -> String {
+    let offset = c.convert_to.or(c.default_offset).unwrap_or(Utc.fix());
+    let lo = c.min.unwrap_or_else(|| {
+        NaiveDate::from_ymd_opt(2020, 1, 1)
+            .unwrap_or_default()
+            .and_hms_opt(0, 0, 0)
+            .unwrap_or_default()
+            .and_utc()
+            .fixed_offset()
+    });
+    let hi = c.max.unwrap_or(lo + Duration::days(5 * 365)).max(lo);
+    let seconds = rng.gen_range(0..=(hi - lo).num_seconds());
+    let datetime = (lo + Duration::seconds(seconds)).with_timezone(&offset);
+    match c.formats.first() {
+        Some(format) => datetime.format(format).to_string(),
+        None => datetime.to_rfc3339(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_generate() {
+        let schema = table("products")
+            .field("id", int().range(1..=50))
+            .field("sku", string().trimmed().starts_with("SKU-").max(8))
+            .field("price", float().gt(0.0).lt(10.0).multiple_of(0.25))
+            .field("stock", int().lt(0).multiple_of(5))
+            .field("size", string().one_of(["S", "M", "L"]))
+            .field("pack", int().one_of([6, 12]))
+            .field("state", enum_of(["new", "sold"]))
+            .field(
+                "added",
+                date().min(chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()),
+            )
+            .field("seen", datetime().nullable())
+            .field("owner", reference().nullable())
+            .field("discount", float().nullable())
+            .conditional(when("state").equals("sold").then(field("owner").required()))
+            .row_check(|row| row.get_int("id") != Some(13), "13 is unlucky");
+
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..20 {
+            let doc = schema.generate(10, &mut rng);
+            let table = schema.validate(&doc).unwrap();
+            assert_eq!(table.rows.len(), 10);
+            // The output is plain ISON
+            let text = ison_rs::dumps(&doc, false);
+            assert!(schema.validate(&ison_rs::parse(&text).unwrap()).is_ok());
+        }
+        let a = schema.generate(3, &mut StdRng::seed_from_u64(1));
+        let b = schema.generate(3, &mut StdRng::seed_from_u64(1));
+        assert_eq!(ison_rs::dumps(&a, false), ison_rs::dumps(&b, false));
+    }
+
+    #[test]
+    fn test_generate_document() {
+        let schema = document()
+            .table(table("users").field("id", int()).field("name", string()))
+            .table(
+                table("orders")
+                    .field("id", int())
+                    .field("user", reference().points_to("users")),
+            );
+        let doc = schema.generate(4, &mut StdRng::seed_from_u64(3));
+        assert_eq!(doc["orders"].rows.len(), 4);
+        assert!(schema.validate(&doc).is_ok());
+    }
+}
@@ -0,0 +1,186 @@
+//! JSON Schema export
+//!
+//! Converts isonantic schemas into standard JSON Schema (draft 2020-12) so the
+//! same rules can be enforced outside Rust. A table maps to an array of
+//! objects; each field maps to a property carrying its constraints.
+//! Row/table checks and custom validators have no JSON Schema equivalent and
+//! are not exported.
+
+use serde_json::{json, Map, Value as JsonValue};
+
+use crate::schema::{FieldSchema, FieldType, NumberConstraints};
+use crate::TableSchema;
+
+const DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+impl TableSchema {
+    /// Export this schema as a JSON Schema document
+    ///
+    /// ```rust
+    /// use isonantic_rs::prelude::*;
+    ///
+    /// let schema = table("users").field("id", int().required().min(1));
+    /// let json = schema.to_json_schema();
+    /// assert_eq!(json["items"]["properties"]["id"]["type"], "integer");
+    /// ```
+    pub fn to_json_schema(&self) -> JsonValue {
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+
+        for field in &self.fields {
+            properties.insert(field.name.clone(), field.to_json_schema());
+            if field.required && field.default.is_none() {
+                required.push(JsonValue::String(field.name.clone()));
+            }
+        }
+
+        let mut items = json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+        });
+        if self.deny_unknown_fields {
+            items["additionalProperties"] = JsonValue::Bool(false);
+        }
+
+        json!({
+            "$schema": DRAFT,
+            "title": self.name,
+            "type": "array",
+            "items": items,
+        })
+    }
+}
+
+impl FieldSchema {
+    /// JSON Schema for a single field's values
+    pub fn to_json_schema(&self) -> JsonValue {
+        let mut schema = Map::new();
+
+        match &self.field_type {
+            FieldType::String(c) => {
+                schema.insert("type".into(), json!("string"));
+                if let Some(min) = c.min_length {
+                    schema.insert("minLength".into(), json!(min));
+                }
+                if let Some(max) = c.max_length {
+                    schema.insert("maxLength".into(), json!(max));
+                }
+                if let Some(pattern) = &c.pattern {
+                    schema.insert("pattern".into(), json!(pattern));
+                }
+                if c.email {
+                    schema.insert("format".into(), json!("email"));
+                }
+            }
+            FieldType::Int(c) => {
+                schema.insert("type".into(), json!("integer"));
+                number_bounds(c, &mut schema);
+            }
+            FieldType::Float(c) => {
+                schema.insert("type".into(), json!("number"));
+                number_bounds(c, &mut schema);
+            }
+            FieldType::Bool => {
+                schema.insert("type".into(), json!("boolean"));
+            }
+            FieldType::Enum(c) => {
+                let mut allowed: Vec<JsonValue> = c.variants.iter().map(|v| json!(v)).collect();
+                allowed.extend(c.codes.iter().map(|code| json!(code)));
+                schema.insert("enum".into(), JsonValue::Array(allowed));
+            }
+            FieldType::Date(_) => {
+                schema.insert("type".into(), json!("string"));
+                schema.insert("format".into(), json!("date"));
+            }
+            FieldType::DateTime(_) => {
+                schema.insert("type".into(), json!("string"));
+                schema.insert("format".into(), json!("date-time"));
+            }
+            FieldType::Reference(c) => {
+                schema.insert("type".into(), json!("string"));
+                let pattern = match &c.ref_type {
+                    Some(ref_type) => format!("^:{}:", ref_type),
+                    None => "^:".to_string(),
+                };
+                schema.insert("pattern".into(), json!(pattern));
+            }
+            FieldType::Null => {
+                schema.insert("type".into(), json!("null"));
+            }
+        }
+
+        if let Some(default) = &self.default {
+            schema.insert("default".into(), default.to_json());
+        }
+
+        if self.nullable {
+            if let Some(JsonValue::String(t)) = schema.get("type").cloned() {
+                schema.insert("type".into(), json!([t, "null"]));
+            } else if let Some(JsonValue::Array(allowed)) = schema.get_mut("enum") {
+                allowed.push(JsonValue::Null);
+            }
+        }
+
+        JsonValue::Object(schema)
+    }
+}
+
+fn number_bounds(c: &NumberConstraints, schema: &mut Map<String, JsonValue>) {
+    if let Some(min) = c.min {
+        schema.insert("minimum".into(), json!(min));
+    }
+    if let Some(max) = c.max {
+        schema.insert("maximum".into(), json!(max));
+    }
+    if c.positive {
+        schema.insert("exclusiveMinimum".into(), json!(0));
+    }
+    if let Some(gt) = c.gt.filter(|gt| !c.positive || *gt > 0.0) {
+        schema.insert("exclusiveMinimum".into(), json!(gt));
+    }
+    if c.negative {
+        schema.insert("exclusiveMaximum".into(), json!(0));
+    }
+    if let Some(lt) = c.lt.filter(|lt| !c.negative || *lt < 0.0) {
+        schema.insert("exclusiveMaximum".into(), json!(lt));
+    }
+    if let Some(m) = c.multiple_of {
+        schema.insert("multipleOf".into(), json!(m));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[test]
+    fn test_table_to_json_schema() {
+        let schema = table("users")
+            .field("id", int().required().positive())
+            .field("email", string().email().max(200))
+            .field(
+                "status",
+                enum_of(["active", "banned"]).default_value("active"),
+            )
+            .field("joined", date().nullable());
+
+        let json = schema.to_json_schema();
+        assert_eq!(json["title"], "users");
+        assert_eq!(json["type"], "array");
+
+        let props = &json["items"]["properties"];
+        assert_eq!(props["id"]["type"], "integer");
+        assert_eq!(props["id"]["exclusiveMinimum"], 0);
+        assert_eq!(props["email"]["format"], "email");
+        assert_eq!(props["email"]["maxLength"], 200);
+        assert_eq!(props["status"]["enum"][1], "banned");
+        assert_eq!(props["status"]["default"], "active");
+        assert_eq!(props["joined"]["format"], "date");
+        assert_eq!(
+            props["joined"]["type"],
+            serde_json::json!(["string", "null"])
+        );
+        assert_eq!(json["items"]["required"], serde_json::json!(["id"]));
+    }
+}
@@ -27,15 +27,47 @@
 //! let users = user_schema.validate(&doc).expect("Validation failed");
 //! ```
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 
+use chrono::{DateTime, FixedOffset, NaiveDate};
+
+#[cfg(feature = "tokio")]
+pub mod async_validation;
+pub mod conditional;
+pub mod document;
+#[cfg(feature = "rand")]
+pub mod generate;
+#[cfg(feature = "serde")]
+pub mod json_schema;
+pub mod messages;
+pub mod migration;
+pub mod normalize;
 pub mod schema;
+pub mod summary;
+pub mod typed;
 pub mod validators;
 
+#[cfg(feature = "tokio")]
+pub use async_validation::*;
+pub use conditional::*;
+pub use document::*;
+pub use migration::*;
 pub use schema::*;
+pub use summary::*;
+pub use typed::*;
 pub use validators::*;
 
+#[cfg(feature = "derive")]
+pub use isonantic_derive::IsonSchema;
+
+/// Re-export of the date/time library used by `date()` and `datetime()` fields
+pub use chrono;
+
+/// Re-export of the random number library used by `generate` (feature `rand`)
+#[cfg(feature = "rand")]
+pub use rand;
+
 /// Library version
 pub const VERSION: &str = "1.0.0";
 
@@ -47,8 +79,44 @@ pub const VERSION: &str = "1.0.0";
 #[derive(Debug, Clone)]
 pub struct FieldError {
     pub field: String,
+    /// Stable machine-readable code, e.g. `string.too_short` (see [`messages`])
+    pub code: String,
+    /// English message, rendered from the code's template and `params`
     pub message: String,
+    /// Values of the template's `{name}` placeholders
+    pub params: BTreeMap<String, String>,
     pub value: Option<String>,
+    /// Copy of the offending source row (see `ValidationOptions::include_row_snapshot`)
+    pub row_snapshot: Option<ison_rs::Row>,
+}
+
+impl FieldError {
+    /// Error with a built-in `code`, its message rendered from the code's
+    /// template in [`messages::TEMPLATES`]
+    pub fn coded<'a>(
+        field: impl Into<String>,
+        code: &str,
+        params: impl IntoIterator<Item = (&'a str, String)>,
+    ) -> Self {
+        let params: BTreeMap<String, String> = params
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect();
+        let message = messages::render(messages::template(code).unwrap_or(code), &params);
+        Self {
+            field: field.into(),
+            code: code.to_string(),
+            message,
+            params,
+            value: None,
+            row_snapshot: None,
+        }
+    }
+
+    /// The message rendered from `template` instead, e.g. a translation
+    pub fn localize(&self, template: &str) -> String {
+        messages::render(template, &self.params)
+    }
 }
 
 impl fmt::Display for FieldError {
@@ -61,21 +129,40 @@ impl fmt::Display for FieldError {
 #[derive(Debug, Clone)]
 pub struct ValidationError {
     pub errors: Vec<FieldError>,
+    /// Validation stopped early, so `errors` may not list every problem
+    pub truncated: bool,
 }
 
 impl ValidationError {
     pub fn new(errors: Vec<FieldError>) -> Self {
-        Self { errors }
+        Self {
+            errors,
+            truncated: false,
+        }
     }
 
+    /// Error with a free-form message and the `custom` code
     pub fn single(field: impl Into<String>, message: impl Into<String>) -> Self {
-        Self {
-            errors: vec![FieldError {
-                field: field.into(),
-                message: message.into(),
-                value: None,
-            }],
+        Self::coded(field, messages::CUSTOM, [("message", message.into())])
+    }
+
+    /// Error with a built-in code, see [`FieldError::coded`]
+    pub fn coded<'a>(
+        field: impl Into<String>,
+        code: &str,
+        params: impl IntoIterator<Item = (&'a str, String)>,
+    ) -> Self {
+        Self::new(vec![FieldError::coded(field, code, params)])
+    }
+
+    /// Replace the code of every error, e.g. to tag a custom validator's
+    /// errors
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        let code = code.into();
+        for error in &mut self.errors {
+            error.code = code.clone();
         }
+        self
     }
 }
 
@@ -85,12 +172,46 @@ impl fmt::Display for ValidationError {
         for error in &self.errors {
             write!(f, "\n  - {}", error)?;
         }
+        if self.truncated {
+            write!(f, "\n  (stopped early, more errors may exist)")?;
+        }
         Ok(())
     }
 }
 
 impl std::error::Error for ValidationError {}
 
+/// Outcome of validation with errors and warnings kept apart (see
+/// `TableSchema::validate_report`)
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+    /// The validated table, when there were no errors
+    pub table: Option<ValidatedTable>,
+    pub errors: Vec<FieldError>,
+    /// Failures of warning rules, which do not make the data invalid
+    pub warnings: Vec<FieldError>,
+    /// Validation stopped early, so `errors` may not list every problem
+    pub truncated: bool,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// The validated table, or the errors; warnings are dropped
+    pub fn into_result(self) -> Result<ValidatedTable> {
+        match self.table {
+            Some(table) if self.errors.is_empty() => Ok(table),
+            _ => {
+                let mut error = ValidationError::new(self.errors);
+                error.truncated = self.truncated;
+                Err(error)
+            }
+        }
+    }
+}
+
 /// Result type for validation operations
 pub type Result<T> = std::result::Result<T, ValidationError>;
 
@@ -106,6 +227,9 @@ pub enum ValidatedValue {
     Int(i64),
     Float(f64),
     String(String),
+    Enum(String),
+    Date(NaiveDate),
+    DateTime(DateTime<FixedOffset>),
     Reference(ISONReference),
     Array(Vec<ValidatedValue>),
     Object(HashMap<String, ValidatedValue>),
@@ -136,7 +260,29 @@ impl ValidatedValue {
 
     pub fn as_str(&self) -> Option<&str> {
         match self {
-            ValidatedValue::String(s) => Some(s),
+            ValidatedValue::String(s) | ValidatedValue::Enum(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_enum(&self) -> Option<&str> {
+        match self {
+            ValidatedValue::Enum(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_date(&self) -> Option<NaiveDate> {
+        match self {
+            ValidatedValue::Date(d) => Some(*d),
+            ValidatedValue::DateTime(dt) => Some(dt.date_naive()),
+            _ => None,
+        }
+    }
+
+    pub fn as_datetime(&self) -> Option<&DateTime<FixedOffset>> {
+        match self {
+            ValidatedValue::DateTime(dt) => Some(dt),
             _ => None,
         }
     }
@@ -151,38 +297,94 @@ impl ValidatedValue {
     pub fn is_null(&self) -> bool {
         matches!(self, ValidatedValue::Null)
     }
-}
 
-/// ISON reference
-#[derive(Debug, Clone, PartialEq)]
-pub struct ISONReference {
-    pub id: String,
-    pub ref_type: Option<String>,
-}
+    /// Convert into a JSON value (dates as ISO 8601, references as `:id` strings)
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> serde_json::Value {
+        use serde_json::{json, Value as JsonValue};
 
-impl ISONReference {
-    pub fn new(id: impl Into<String>) -> Self {
-        Self {
-            id: id.into(),
-            ref_type: None,
+        match self {
+            ValidatedValue::Null => JsonValue::Null,
+            ValidatedValue::Bool(b) => json!(b),
+            ValidatedValue::Int(i) => json!(i),
+            ValidatedValue::Float(f) => json!(f),
+            ValidatedValue::String(s) | ValidatedValue::Enum(s) => json!(s),
+            ValidatedValue::Date(d) => json!(d.to_string()),
+            ValidatedValue::DateTime(dt) => json!(dt.to_rfc3339()),
+            ValidatedValue::Reference(r) => json!(r.to_ison()),
+            ValidatedValue::Array(items) => items.iter().map(|v| v.to_json()).collect(),
+            ValidatedValue::Object(map) => JsonValue::Object(
+                map.iter().map(|(k, v)| (k.clone(), v.to_json())).collect(),
+            ),
         }
     }
 
-    pub fn with_type(id: impl Into<String>, ref_type: impl Into<String>) -> Self {
-        Self {
-            id: id.into(),
-            ref_type: Some(ref_type.into()),
+    /// Convert back into a plain ISON value.
+    ///
+    /// Enums become strings, dates are written as ISO 8601 and datetimes as
+    /// RFC 3339. Arrays and objects have no ISON cell form and are rendered
+    /// as bracketed text.
+    pub fn to_ison_value(&self) -> ison_rs::Value {
+        use ison_rs::Value;
+
+        match self {
+            ValidatedValue::Null => Value::Null,
+            ValidatedValue::Bool(b) => Value::Bool(*b),
+            ValidatedValue::Int(i) => Value::Int(*i),
+            ValidatedValue::Float(f) => Value::Float(*f),
+            ValidatedValue::String(s) | ValidatedValue::Enum(s) => Value::String(s.clone()),
+            ValidatedValue::Date(d) => Value::String(d.to_string()),
+            ValidatedValue::DateTime(dt) => Value::String(dt.to_rfc3339()),
+            ValidatedValue::Reference(r) => Value::Reference(r.clone()),
+            ValidatedValue::Array(items) => {
+                let parts: Vec<String> = items.iter().map(|v| v.to_ison_value().to_string()).collect();
+                Value::String(format!("[{}]", parts.join(", ")))
+            }
+            ValidatedValue::Object(map) => {
+                let mut keys: Vec<&String> = map.keys().collect();
+                keys.sort();
+                let parts: Vec<String> = keys
+                    .into_iter()
+                    .map(|k| format!("{}: {}", k, map[k].to_ison_value()))
+                    .collect();
+                Value::String(format!("{{{}}}", parts.join(", ")))
+            }
         }
     }
+}
 
-    pub fn to_ison(&self) -> String {
-        match &self.ref_type {
-            Some(t) => format!(":{}:{}", t, self.id),
-            None => format!(":{}", self.id),
+impl From<ValidatedValue> for ison_rs::Value {
+    fn from(value: ValidatedValue) -> Self {
+        value.to_ison_value()
+    }
+}
+
+/// Plain ISON values as they would validate without constraints: strings
+/// stay strings (not enums or dates), and bytes become their `b64:` text
+impl From<ison_rs::Value> for ValidatedValue {
+    fn from(value: ison_rs::Value) -> Self {
+        use ison_rs::Value;
+
+        match value {
+            Value::Null => ValidatedValue::Null,
+            Value::Bool(b) => ValidatedValue::Bool(b),
+            Value::Int(i) => ValidatedValue::Int(i),
+            Value::Float(f) => ValidatedValue::Float(f),
+            Value::String(s) => ValidatedValue::String(s),
+            Value::Reference(r) => ValidatedValue::Reference(r),
+            // Bytes, and decimals when ison-rs is built with them
+            other => match other.as_float() {
+                Some(f) => ValidatedValue::Float(f),
+                None => ValidatedValue::String(other.to_string()),
+            },
         }
     }
 }
 
+/// ISON reference, the same type as the parser's, so references need no
+/// conversion between `ison_rs::Value` and [`ValidatedValue`]
+pub use ison_rs::Reference as ISONReference;
+
 // =============================================================================
 // Validated Row/Table
 // =============================================================================
@@ -215,6 +417,30 @@ impl ValidatedRow {
     pub fn get_bool(&self, field: &str) -> Option<bool> {
         self.fields.get(field).and_then(|v| v.as_bool())
     }
+
+    pub fn get_date(&self, field: &str) -> Option<NaiveDate> {
+        self.fields.get(field).and_then(|v| v.as_date())
+    }
+
+    pub fn get_datetime(&self, field: &str) -> Option<&DateTime<FixedOffset>> {
+        self.fields.get(field).and_then(|v| v.as_datetime())
+    }
+
+    /// Deserialize this row into a user struct via serde.
+    ///
+    /// Field names map to struct fields; dates arrive as ISO 8601 strings
+    /// (deserializable into `chrono` types) and references as `:id` strings.
+    #[cfg(feature = "serde")]
+    pub fn deserialize<T: serde::de::DeserializeOwned>(&self) -> Result<T> {
+        let map: serde_json::Map<String, serde_json::Value> = self
+            .fields
+            .iter()
+            .map(|(k, v)| (k.clone(), v.to_json()))
+            .collect();
+        serde_json::from_value(serde_json::Value::Object(map)).map_err(|e| {
+            ValidationError::coded("", "convert.deserialize", [("message", e.to_string())])
+        })
+    }
 }
 
 impl Default for ValidatedRow {
@@ -223,21 +449,114 @@ impl Default for ValidatedRow {
     }
 }
 
+/// Record of a value converted to its field type in coercion mode
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coercion {
+    pub row: usize,
+    pub field: String,
+    pub original: ison_rs::Value,
+    pub coerced: ValidatedValue,
+}
+
 /// A validated table of rows
 #[derive(Debug, Clone)]
 pub struct ValidatedTable {
     pub name: String,
+    /// Declared fields in schema order, the columns of [`to_document`](Self::to_document)
+    pub fields: Vec<String>,
+    /// Declared fields that accept a present null
+    pub nullable_fields: Vec<String>,
     pub rows: Vec<ValidatedRow>,
+    /// Validated summary rows (only when the schema declares a summary)
+    pub summary_rows: Vec<ValidatedRow>,
+    /// Values that only passed validation through coercion
+    pub coercions: Vec<Coercion>,
 }
 
 impl ValidatedTable {
     pub fn new(name: impl Into<String>) -> Self {
         Self {
             name: name.into(),
+            fields: Vec::new(),
+            nullable_fields: Vec::new(),
             rows: Vec::new(),
+            summary_rows: Vec::new(),
+            coercions: Vec::new(),
         }
     }
 
+    /// The validated values as an ISON table, with defaults filled in and
+    /// coerced values in their new type.
+    ///
+    /// Columns follow `fields`, or the sorted names found in the rows when it
+    /// is empty. A field that is null in every row is left out, as optional
+    /// fields the source omitted are, unless it is one of `nullable_fields`,
+    /// so the table validates against its schema again. Values are converted with
+    /// [`ValidatedValue::to_ison_value`], so enums are written in canonical
+    /// form and dates as ISO 8601.
+    ///
+    /// ```rust
+    /// use isonantic_rs::prelude::*;
+    ///
+    /// let doc = ison_rs::parse("table.users\nname id extra\nAda \"7\" x").unwrap();
+    /// let users = table("users")
+    ///     .field("id", int())
+    ///     .field("name", string())
+    ///     .field("role", enum_of(["admin", "member"]).default_value("member"))
+    ///     .coerce(true)
+    ///     .validate(&doc)
+    ///     .unwrap();
+    /// assert_eq!(users.to_ison(false), "table.users\nid name role\n7 Ada member");
+    /// ```
+    pub fn to_document(&self) -> ison_rs::Document {
+        let mut block = ison_rs::Block::new("table", self.name.clone());
+        let all_rows = || self.rows.iter().chain(&self.summary_rows);
+        block.fields = if self.fields.is_empty() {
+            let mut names: Vec<String> = all_rows()
+                .flat_map(|row| row.fields.keys().cloned())
+                .collect();
+            names.sort();
+            names.dedup();
+            names
+        } else {
+            self.fields.clone()
+        };
+        block.fields.retain(|name| {
+            self.nullable_fields.contains(name)
+                || all_rows().any(|row| row.get(name).is_some_and(|v| !v.is_null()))
+        });
+        block.field_info = block.fields.iter().map(ison_rs::FieldInfo::new).collect();
+
+        let keys = block.field_keys();
+        let to_row = |row: &ValidatedRow| -> ison_rs::Row {
+            block
+                .fields
+                .iter()
+                .zip(&keys)
+                .map(|(name, key)| {
+                    let value = row
+                        .get(name)
+                        .map_or(ison_rs::Value::Null, ValidatedValue::to_ison_value);
+                    (key.clone(), value)
+                })
+                .collect()
+        };
+        let rows = self.rows.iter().map(to_row).collect();
+        let summary_rows = self.summary_rows.iter().map(to_row).collect();
+        block.rows = rows;
+        block.summary_rows = summary_rows;
+
+        let mut doc = ison_rs::Document::new();
+        doc.blocks.push(block);
+        doc
+    }
+
+    /// [`to_document`](Self::to_document) as ISON text, with columns padded
+    /// to line up when `align` is set
+    pub fn to_ison(&self, align: bool) -> String {
+        ison_rs::dumps(&self.to_document(), align)
+    }
+
     pub fn len(&self) -> usize {
         self.rows.len()
     }
@@ -249,6 +568,40 @@ impl ValidatedTable {
     pub fn iter(&self) -> impl Iterator<Item = &ValidatedRow> {
         self.rows.iter()
     }
+
+    /// Deserialize every row into `T`, reporting failures per row index
+    ///
+    /// ```rust
+    /// use isonantic_rs::prelude::*;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct User { id: i64, name: String }
+    ///
+    /// let doc = ison_rs::parse("table.users\nid name\n1 Alice").unwrap();
+    /// let table = table("users").field("id", int()).field("name", string());
+    /// let users: Vec<User> = table.validate(&doc).unwrap().deserialize_rows().unwrap();
+    /// assert_eq!(users[0].name, "Alice");
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn deserialize_rows<T: serde::de::DeserializeOwned>(&self) -> Result<Vec<T>> {
+        let mut out = Vec::with_capacity(self.rows.len());
+        let mut errors = Vec::new();
+        for (idx, row) in self.rows.iter().enumerate() {
+            match row.deserialize() {
+                Ok(value) => out.push(value),
+                Err(e) => errors.extend(e.errors.into_iter().map(|mut err| {
+                    err.field = format!("[{}]", idx);
+                    err
+                })),
+            }
+        }
+        if errors.is_empty() {
+            Ok(out)
+        } else {
+            Err(ValidationError::new(errors))
+        }
+    }
 }
 
 impl std::ops::Index<usize> for ValidatedTable {
@@ -264,10 +617,91 @@ impl std::ops::Index<usize> for ValidatedTable {
 // =============================================================================
 
 pub mod prelude {
+    #[cfg(feature = "tokio")]
+    pub use crate::async_validation::*;
+    pub use crate::conditional::*;
+    pub use crate::document::*;
+    pub use crate::migration::*;
     pub use crate::schema::*;
+    pub use crate::summary::*;
+    pub use crate::typed::*;
     pub use crate::validators::*;
     pub use crate::{
-        FieldError, ISONReference, Result, ValidatedRow, ValidatedTable,
-        ValidatedValue, ValidationError,
+        Coercion, FieldError, ISONReference, Result, ValidatedRow, ValidatedTable,
+        ValidatedValue, ValidationError, ValidationReport,
     };
+
+    #[cfg(feature = "derive")]
+    pub use isonantic_derive::IsonSchema;
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::prelude::*;
+    use chrono::NaiveDate;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    struct Event {
+        id: u32,
+        day: NaiveDate,
+        owner: Option<String>,
+    }
+
+    #[test]
+    fn test_deserialize_rows() {
+        let doc = ison_rs::parse("table.events\nid day owner\n1 2024-05-01 :u1\n-2 2024-05-02").unwrap();
+        let schema = table("events")
+            .field("id", int())
+            .field("day", date())
+            .field("owner", reference());
+        let validated = schema.validate(&doc).unwrap();
+
+        let err = validated.deserialize_rows::<Event>().unwrap_err();
+        assert_eq!(err.errors.len(), 1);
+        assert_eq!(err.errors[0].field, "[1]");
+
+        let doc = ison_rs::parse("table.events\nid day owner\n1 2024-05-01 :u1").unwrap();
+        let events: Vec<Event> = schema.validate(&doc).unwrap().deserialize_rows().unwrap();
+        assert_eq!(events[0].id, 1);
+        assert_eq!(events[0].day, NaiveDate::from_ymd_opt(2024, 5, 1).unwrap());
+        assert_eq!(events[0].owner.as_deref(), Some(":u1"));
+    }
+
+    #[test]
+    fn test_value_conversions() {
+        use ison_rs::{Reference, Value};
+
+        let doc = ison_rs::parse("table.t\nowner day n\n:user:7 2024-05-01 3").unwrap();
+        let schema = table("t")
+            .field("owner", reference())
+            .field("day", date())
+            .field("n", int());
+        let row = &schema.validate(&doc).unwrap().rows[0];
+
+        // References are the parser's type
+        let owner: &Reference = row.get("owner").unwrap().as_reference().unwrap();
+        assert_eq!(
+            owner,
+            doc["t"][0].get("owner").unwrap().as_reference().unwrap()
+        );
+
+        let day: Value = row.get("day").cloned().unwrap().into();
+        assert_eq!(day, Value::String("2024-05-01".into()));
+        for value in [
+            Value::Null,
+            Value::Bool(true),
+            Value::Int(3),
+            Value::Float(1.5),
+            Value::String("x".into()),
+            Value::Reference(Reference::with_type("7", "user")),
+        ] {
+            let validated = ValidatedValue::from(value.clone());
+            assert_eq!(Value::from(validated), value);
+        }
+        assert_eq!(
+            ValidatedValue::from(Value::Bytes(vec![1, 2])),
+            ValidatedValue::String("b64:AQI=".into())
+        );
+    }
 }
@@ -0,0 +1,224 @@
+//! Error codes and message templates
+//!
+//! Every [`FieldError`](crate::FieldError) carries a stable `code`, such as
+//! `string.too_short` or `ref.dangling`, and the `params` its message was
+//! rendered from. The English message is the code's template in
+//! [`TEMPLATES`] with each `{name}` replaced by the param of that name, so a
+//! frontend can branch on the code and render its own translation:
+//!
+//! ```rust
+//! use isonantic_rs::prelude::*;
+//!
+//! let doc = ison_rs::parse("table.users\nname\nAl").unwrap();
+//! let err = table("users")
+//!     .field("name", string().min(3))
+//!     .validate(&doc)
+//!     .unwrap_err();
+//!
+//! let error = &err.errors[0];
+//! assert_eq!(error.code, "string.too_short");
+//! assert_eq!(error.message, "String must be at least 3 characters");
+//! assert_eq!(
+//!     error.localize("Au moins {min} caractères"),
+//!     "Au moins 3 caractères"
+//! );
+//! ```
+//!
+//! Errors with a message of their own, from row checks or
+//! [`ValidationError::single`](crate::ValidationError::single), pass it as
+//! the `message` param of a `{message}` template.
+
+use std::collections::BTreeMap;
+
+/// Code of errors built from a free-form message
+pub const CUSTOM: &str = "custom";
+
+/// English message template of every built-in code
+pub const TEMPLATES: &[(&str, &str)] = &[
+    (CUSTOM, "{message}"),
+    ("field.required", "Field is required"),
+    ("field.null", "Field is present but null is not allowed"),
+    ("string.expected", "Expected string"),
+    (
+        "string.too_short",
+        "String must be at least {min} characters",
+    ),
+    ("string.too_long", "String must be at most {max} characters"),
+    (
+        "string.too_many_bytes",
+        "String must be at most {max} bytes",
+    ),
+    ("string.email", "Invalid email format"),
+    (
+        "string.not_ascii",
+        "String must contain only ASCII characters",
+    ),
+    ("string.prefix", "String must start with {prefix}"),
+    ("string.suffix", "String must end with {suffix}"),
+    ("string.empty", "String cannot be empty"),
+    ("int.expected", "Expected integer"),
+    ("float.expected", "Expected number"),
+    ("number.too_small", "Value must be >= {min}"),
+    ("number.too_large", "Value must be <= {max}"),
+    ("number.not_positive", "Value must be positive"),
+    ("number.not_negative", "Value must be negative"),
+    ("number.not_greater", "Value must be > {min}"),
+    ("number.not_less", "Value must be < {max}"),
+    (
+        "number.not_multiple",
+        "Value must be a multiple of {multiple_of}",
+    ),
+    ("number.not_finite", "Value must be finite"),
+    ("bool.expected", "Expected boolean"),
+    ("enum.invalid", "Value must be one of: {variants}"),
+    ("value.not_allowed", "Value must be one of: {allowed}"),
+    ("date.expected", "Expected date string"),
+    ("date.format", "Invalid date format"),
+    ("date.too_early", "Date must be on or after {min}"),
+    ("date.too_late", "Date must be on or before {max}"),
+    ("datetime.expected", "Expected datetime string"),
+    ("datetime.format", "Invalid datetime format"),
+    (
+        "datetime.no_offset",
+        "Datetime must include a timezone offset",
+    ),
+    ("datetime.invalid_offset", "Invalid datetime for offset"),
+    ("datetime.too_early", "Datetime must be at or after {min}"),
+    ("datetime.too_late", "Datetime must be at or before {max}"),
+    ("ref.expected", "Expected reference"),
+    ("ref.wrong_type", "Expected reference of type {ref_type}"),
+    (
+        "ref.dangling",
+        "Reference {reference} has no match in {table}",
+    ),
+    ("ref.missing_table", "Referenced table {table} is missing"),
+    ("null.expected", "Expected null"),
+    ("conditional.required", "Field is required when {condition}"),
+    (
+        "conditional.forbidden",
+        "Field is not allowed when {condition}",
+    ),
+    ("row.check", "{message}"),
+    ("table.check", "{message}"),
+    ("table.missing", "Missing table: {table}"),
+    ("column.unknown", "Unknown column not declared in schema"),
+    ("column.missing", "Declared column missing from header"),
+    ("summary.count", "Expected row count {expected}"),
+    (
+        "summary.aggregate",
+        "Expected {aggregate} of {column} = {expected}",
+    ),
+    ("convert.type", "Cannot convert to {expected}"),
+    ("convert.range", "Value out of range for {type}"),
+    ("convert.deserialize", "{message}"),
+];
+
+/// The English template of `code`
+pub fn template(code: &str) -> Option<&'static str> {
+    TEMPLATES
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map(|(_, template)| *template)
+}
+
+/// `template` with each `{name}` replaced by `params[name]`; unknown names
+/// are left as they are
+pub fn render(template: &str, params: &BTreeMap<String, String>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let name_and_rest = &rest[start + 1..];
+        match name_and_rest
+            .find('}')
+            .and_then(|end| Some((params.get(&name_and_rest[..end])?, end)))
+        {
+            Some((value, end)) => {
+                out.push_str(value);
+                rest = &name_and_rest[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = name_and_rest;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use ison_rs::parse;
+
+    #[test]
+    fn test_codes_and_templates() {
+        let doc = parse(
+            "table.orders\nid user total status placed\n\
+             1 :9 -5 lost 2024-13-01\n\
+             2 ~ 10 open 2024-01-01",
+        )
+        .unwrap();
+        let schema = table("orders")
+            .field("id", int())
+            .field("user", reference().points_to("users"))
+            .field("total", float().positive())
+            .field("status", enum_of(["open", "closed"]))
+            .field("placed", date())
+            .row_check(|row| row.get_int("id") != Some(2), "order 2 is void");
+        let err = schema.validate(&doc).unwrap_err();
+        let codes: Vec<&str> = err.errors.iter().map(|e| e.code.as_str()).collect();
+        assert_eq!(
+            codes,
+            [
+                "ref.missing_table",
+                "number.not_positive",
+                "enum.invalid",
+                "date.format",
+                "field.null",
+            ]
+        );
+
+        let doc = parse("table.users\nid\n1\n\ntable.orders\nid user\n2 :1\n3 :7").unwrap();
+        let err = schema.validate(&doc).unwrap_err();
+        let errors: Vec<(&str, &str)> = err
+            .errors
+            .iter()
+            .map(|e| (e.code.as_str(), e.message.as_str()))
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                ("row.check", "order 2 is void"),
+                ("ref.dangling", "Reference :7 has no match in users"),
+            ]
+        );
+        let dangling = &err.errors[1];
+        assert_eq!(dangling.params["reference"], ":7");
+        assert_eq!(
+            dangling.localize("{reference} fehlt in {table}"),
+            ":7 fehlt in users"
+        );
+
+        // Every error's message is its template rendered with its params
+        for error in &err.errors {
+            assert_eq!(
+                render(template(&error.code).unwrap(), &error.params),
+                error.message
+            );
+        }
+        let err = ValidationError::single("promo", "Promotion has ended").with_code("promo.ended");
+        assert_eq!(err.errors[0].code, "promo.ended");
+        assert_eq!(err.errors[0].message, "Promotion has ended");
+    }
+
+    #[test]
+    fn test_render() {
+        let params = BTreeMap::from([("min".to_string(), "3".to_string())]);
+        assert_eq!(render("at least {min}", &params), "at least 3");
+        assert_eq!(render("{min}{max} {", &params), "3{max} {");
+        assert_eq!(render("{{min}}", &params), "{3}");
+    }
+}
@@ -0,0 +1,488 @@
+//! Schema evolution
+//!
+//! [`SchemaDiff::compare`] lists what changed between two versions of a
+//! table schema, and [`Migration`] rewrites documents written against the old
+//! version so they fit the new one:
+//!
+//! ```rust
+//! use isonantic_rs::prelude::*;
+//!
+//! let v1 = table("users")
+//!     .field("id", int())
+//!     .field("fullname", string())
+//!     .field("legacy", string().nullable());
+//! let v2 = table("users")
+//!     .field("id", int().positive())
+//!     .field("name", string().max(50))
+//!     .field("role", string().default_value("member"));
+//!
+//! let diff = SchemaDiff::compare(&v1, &v2);
+//! assert!(diff.is_breaking());
+//!
+//! let migration = diff.migration().rename("fullname", "name");
+//! let doc = ison_rs::parse("table.users\nid fullname legacy\n1 Ada ~").unwrap();
+//! let migrated = migration.apply(&doc);
+//! assert_eq!(
+//!     ison_rs::dumps(&migrated, false),
+//!     "table.users\nid name role\n1 Ada member"
+//! );
+//! assert!(v2.validate(&migrated).is_ok());
+//! ```
+
+use std::fmt;
+
+use ison_rs::{Document, Value};
+
+use crate::schema::{FieldSchema, FieldType, NumberConstraints, TableSchema};
+use crate::ValidatedValue;
+
+/// One difference between two versions of a table schema
+#[derive(Debug, Clone, PartialEq)]
+pub enum SchemaChange {
+    FieldAdded {
+        field: String,
+        required: bool,
+        default: Option<ValidatedValue>,
+    },
+    FieldRemoved {
+        field: String,
+    },
+    FieldRetyped {
+        field: String,
+        from: &'static str,
+        to: &'static str,
+    },
+    /// A constraint that values valid before may now fail
+    Tightened {
+        field: String,
+        constraint: String,
+    },
+    /// A constraint that now accepts more values
+    Loosened {
+        field: String,
+        constraint: String,
+    },
+}
+
+impl SchemaChange {
+    /// Whether data valid under the old schema may fail the new one
+    pub fn is_breaking(&self) -> bool {
+        match self {
+            SchemaChange::FieldAdded {
+                required, default, ..
+            } => *required && default.is_none(),
+            SchemaChange::FieldRemoved { .. } | SchemaChange::Loosened { .. } => false,
+            SchemaChange::FieldRetyped { .. } | SchemaChange::Tightened { .. } => true,
+        }
+    }
+}
+
+impl fmt::Display for SchemaChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaChange::FieldAdded {
+                field,
+                required,
+                default,
+            } => {
+                write!(f, "added {}", field)?;
+                if *required {
+                    write!(f, " (required)")?;
+                }
+                if let Some(default) = default {
+                    write!(f, " with default {}", default.to_ison_value())?;
+                }
+                Ok(())
+            }
+            SchemaChange::FieldRemoved { field } => write!(f, "removed {}", field),
+            SchemaChange::FieldRetyped { field, from, to } => {
+                write!(f, "retyped {} from {} to {}", field, from, to)
+            }
+            SchemaChange::Tightened { field, constraint } => {
+                write!(f, "tightened {}: {}", field, constraint)
+            }
+            SchemaChange::Loosened { field, constraint } => {
+                write!(f, "loosened {}: {}", field, constraint)
+            }
+        }
+    }
+}
+
+/// The changes between two versions of a table schema, in field order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SchemaDiff {
+    pub table: String,
+    pub changes: Vec<SchemaChange>,
+}
+
+impl SchemaDiff {
+    /// Compare the fields of `old` and `new`; fields are matched by name.
+    ///
+    /// Row checks, table checks, conditional rules and custom validators are
+    /// not compared.
+    pub fn compare(old: &TableSchema, new: &TableSchema) -> Self {
+        let mut changes = Vec::new();
+        for field in &old.fields {
+            if find(new, &field.name).is_none() {
+                changes.push(SchemaChange::FieldRemoved {
+                    field: field.name.clone(),
+                });
+            }
+        }
+        for field in &new.fields {
+            match find(old, &field.name) {
+                Some(before) => compare_field(before, field, &mut changes),
+                None => changes.push(SchemaChange::FieldAdded {
+                    field: field.name.clone(),
+                    required: field.required,
+                    default: field.default.clone(),
+                }),
+            }
+        }
+        Self {
+            table: new.name.clone(),
+            changes,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Whether any change may reject data valid under the old schema
+    pub fn is_breaking(&self) -> bool {
+        self.changes.iter().any(SchemaChange::is_breaking)
+    }
+
+    /// A migration dropping removed fields and filling added fields that
+    /// have a default; declare renames on it with [`Migration::rename`]
+    pub fn migration(&self) -> Migration {
+        let mut migration = Migration::new(self.table.clone());
+        for change in &self.changes {
+            match change {
+                SchemaChange::FieldRemoved { field } => migration = migration.drop(field.clone()),
+                SchemaChange::FieldAdded {
+                    field,
+                    default: Some(default),
+                    ..
+                } => migration = migration.add(field.clone(), default.to_ison_value()),
+                _ => {}
+            }
+        }
+        migration
+    }
+}
+
+impl fmt::Display for SchemaDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, change) in self.changes.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+/// Column changes that move a table's data from one schema version to the
+/// next
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Migration {
+    pub table: String,
+    /// `(from, to)` column renames, applied first
+    pub renames: Vec<(String, String)>,
+    /// Columns removed after the renames
+    pub drops: Vec<String>,
+    /// Columns added last, with the value of rows that lack one
+    pub defaults: Vec<(String, Value)>,
+}
+
+impl Migration {
+    pub fn new(table: impl Into<String>) -> Self {
+        Self {
+            table: table.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Rename column `from` to `to`; a drop of `from` or a default for `to`
+    /// no longer affects the renamed values
+    pub fn rename(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.renames.push((from.into(), to.into()));
+        self
+    }
+
+    pub fn drop(mut self, field: impl Into<String>) -> Self {
+        self.drops.push(field.into());
+        self
+    }
+
+    /// Add column `field`, or fill its missing cells, with `default`
+    pub fn add(mut self, field: impl Into<String>, default: impl Into<Value>) -> Self {
+        self.defaults.push((field.into(), default.into()));
+        self
+    }
+
+    /// Return a copy of `doc` with the migration applied to its table.
+    ///
+    /// Steps that do not fit the document are skipped: renames of missing
+    /// columns or onto existing ones, and drops of missing columns. Other
+    /// blocks are copied unchanged.
+    pub fn apply(&self, doc: &Document) -> Document {
+        let mut out = doc.clone();
+        let block = match out.get_mut(&self.table) {
+            Some(block) => block,
+            None => return out,
+        };
+
+        for (from, to) in &self.renames {
+            let _ = block.rename_field(from, to);
+        }
+        for field in &self.drops {
+            let _ = block.drop_field(field);
+        }
+        for (field, default) in &self.defaults {
+            if !block.fields.contains(field) {
+                let _ = block.add_field(field, "", Value::Null);
+            }
+            for row in block.rows.iter_mut() {
+                if !row.contains_key(field.as_str()) {
+                    row.insert(field.as_str().into(), default.clone());
+                }
+            }
+        }
+        out
+    }
+}
+
+fn find<'a>(schema: &'a TableSchema, name: &str) -> Option<&'a FieldSchema> {
+    schema.fields.iter().find(|f| f.name == name)
+}
+
+fn type_name(field_type: &FieldType) -> &'static str {
+    match field_type {
+        FieldType::String(_) => "string",
+        FieldType::Int(_) => "int",
+        FieldType::Float(_) => "float",
+        FieldType::Bool => "bool",
+        FieldType::Enum(_) => "enum",
+        FieldType::Date(_) => "date",
+        FieldType::DateTime(_) => "datetime",
+        FieldType::Reference(_) => "reference",
+        FieldType::Null => "null",
+    }
+}
+
+/// Collects the constraint changes of one field
+struct Changes<'a> {
+    field: &'a str,
+    out: &'a mut Vec<SchemaChange>,
+}
+
+impl Changes<'_> {
+    fn push(&mut self, tightened: bool, constraint: String) {
+        let field = self.field.to_string();
+        self.out.push(if tightened {
+            SchemaChange::Tightened { field, constraint }
+        } else {
+            SchemaChange::Loosened { field, constraint }
+        });
+    }
+
+    /// A flag that restricts values when set
+    fn flag(&mut self, name: &str, old: bool, new: bool) {
+        if old != new {
+            let state = if new { "set" } else { "unset" };
+            self.push(new, format!("{} {}", name, state));
+        }
+    }
+
+    /// A lower bound, tighter when raised
+    fn lower<T: PartialOrd + fmt::Display>(&mut self, name: &str, old: Option<T>, new: Option<T>) {
+        self.bound(name, old, new, |old, new| new > old);
+    }
+
+    /// An upper bound, tighter when lowered
+    fn upper<T: PartialOrd + fmt::Display>(&mut self, name: &str, old: Option<T>, new: Option<T>) {
+        self.bound(name, old, new, |old, new| new < old);
+    }
+
+    fn bound<T: PartialOrd + fmt::Display>(
+        &mut self,
+        name: &str,
+        old: Option<T>,
+        new: Option<T>,
+        tighter: impl Fn(&T, &T) -> bool,
+    ) {
+        let tightened = match (&old, &new) {
+            (Some(old), Some(new)) if old == new => return,
+            (Some(old), Some(new)) => tighter(old, new),
+            (None, Some(_)) => true,
+            (Some(_), None) => false,
+            (None, None) => return,
+        };
+        self.push(
+            tightened,
+            format!("{} {} -> {}", name, show(&old), show(&new)),
+        );
+    }
+
+    /// A value that must match exactly, so any new one may reject data
+    fn exact<T: PartialEq + fmt::Display>(&mut self, name: &str, old: Option<T>, new: Option<T>) {
+        if old != new {
+            let tightened = new.is_some();
+            self.push(
+                tightened,
+                format!("{} {} -> {}", name, show(&old), show(&new)),
+            );
+        }
+    }
+
+    fn numbers(&mut self, old: &NumberConstraints, new: &NumberConstraints) {
+        self.lower("min", old.min, new.min);
+        self.upper("max", old.max, new.max);
+        self.lower("gt", old.gt, new.gt);
+        self.upper("lt", old.lt, new.lt);
+        self.exact("multiple_of", old.multiple_of, new.multiple_of);
+        self.flag("positive", old.positive, new.positive);
+        self.flag("negative", old.negative, new.negative);
+        self.flag("finite", old.finite, new.finite);
+    }
+}
+
+fn show<T: fmt::Display>(value: &Option<T>) -> String {
+    value
+        .as_ref()
+        .map_or_else(|| "none".to_string(), |v| v.to_string())
+}
+
+fn compare_field(old: &FieldSchema, new: &FieldSchema, out: &mut Vec<SchemaChange>) {
+    let (from, to) = (type_name(&old.field_type), type_name(&new.field_type));
+    if from != to {
+        out.push(SchemaChange::FieldRetyped {
+            field: new.name.clone(),
+            from,
+            to,
+        });
+        return;
+    }
+
+    let mut changes = Changes {
+        field: &new.name,
+        out,
+    };
+    changes.flag("required", old.required, new.required);
+    // Nullability loosens when set
+    changes.flag("non-null", !old.nullable, !new.nullable);
+
+    match (&old.field_type, &new.field_type) {
+        (FieldType::String(old), FieldType::String(new)) => {
+            changes.lower("min length", old.min_length, new.min_length);
+            changes.upper("max length", old.max_length, new.max_length);
+            changes.upper("max bytes", old.max_bytes, new.max_bytes);
+            changes.exact("pattern", old.pattern.as_ref(), new.pattern.as_ref());
+            changes.exact("prefix", old.starts_with.as_ref(), new.starts_with.as_ref());
+            changes.exact("suffix", old.ends_with.as_ref(), new.ends_with.as_ref());
+            changes.flag("email", old.email, new.email);
+            changes.flag("ascii only", old.ascii_only, new.ascii_only);
+        }
+        (FieldType::Int(old), FieldType::Int(new))
+        | (FieldType::Float(old), FieldType::Float(new)) => changes.numbers(old, new),
+        (FieldType::Enum(old), FieldType::Enum(new)) => {
+            for variant in old.variants.iter().filter(|v| !new.variants.contains(v)) {
+                changes.push(true, format!("variant {} removed", variant));
+            }
+            for variant in new.variants.iter().filter(|v| !old.variants.contains(v)) {
+                changes.push(false, format!("variant {} added", variant));
+            }
+            changes.flag(
+                "case sensitive",
+                !old.case_insensitive,
+                !new.case_insensitive,
+            );
+        }
+        (FieldType::Date(old), FieldType::Date(new)) => {
+            changes.lower("min", old.min, new.min);
+            changes.upper("max", old.max, new.max);
+        }
+        (FieldType::DateTime(old), FieldType::DateTime(new)) => {
+            changes.lower("min", old.min, new.min);
+            changes.upper("max", old.max, new.max);
+            changes.flag("offset required", old.require_offset, new.require_offset);
+        }
+        (FieldType::Reference(old), FieldType::Reference(new)) => {
+            changes.exact("target", old.target.as_ref(), new.target.as_ref());
+            changes.exact("type", old.ref_type.as_ref(), new.ref_type.as_ref());
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use ison_rs::parse;
+
+    #[test]
+    fn test_schema_diff() {
+        let old = table("products")
+            .field("id", int().positive())
+            .field("name", string().max(100))
+            .field("price", float())
+            .field("status", enum_of(["draft", "live", "retired"]))
+            .field("note", string().nullable())
+            .field("code", string());
+        let new = table("products")
+            .field("id", int().positive())
+            .field("name", string().max(50).nullable())
+            .field("price", int())
+            .field("status", enum_of(["draft", "live", "archived"]))
+            .field("sku", string().required())
+            .field("stock", int().default_value(0));
+
+        let diff = SchemaDiff::compare(&old, &new);
+        let changes: Vec<String> = diff.changes.iter().map(|c| c.to_string()).collect();
+        assert_eq!(
+            changes,
+            [
+                "removed note",
+                "removed code",
+                "loosened name: non-null unset",
+                "tightened name: max length 100 -> 50",
+                "retyped price from float to int",
+                "tightened status: variant retired removed",
+                "loosened status: variant archived added",
+                "added sku (required)",
+                "added stock with default 0",
+            ]
+        );
+        assert!(diff.is_breaking());
+        assert!(SchemaDiff::compare(&old, &old).is_empty());
+
+        let relaxed = SchemaDiff::compare(&new, &table("products").field("id", int()));
+        assert!(!relaxed.is_breaking());
+    }
+
+    #[test]
+    fn test_migration_apply() {
+        let doc = parse(
+            "table.products\nid title legacy stock\n1 Pen x 5\n2 Cup y\n\n\
+             table.other\nlegacy\n1",
+        )
+        .unwrap();
+        let migration = Migration::new("products")
+            .rename("title", "name")
+            .rename("missing", "whatever")
+            .drop("legacy")
+            .add("stock", 0)
+            .add("active", true);
+
+        let migrated = migration.apply(&doc);
+        assert_eq!(
+            ison_rs::dumps(&migrated, false),
+            "table.products\nid name stock active\n1 Pen 5 true\n2 Cup 0 true\n\n\
+             table.other\nlegacy\n1"
+        );
+    }
+}
@@ -0,0 +1,106 @@
+//! Normalization pass that repairs a Document in place of rejecting it
+//!
+//! Where validation answers "is this table valid?", normalization produces
+//! the closest valid version of it: missing fields get their defaults, string
+//! cells are trimmed, values of the wrong type are coerced, and enum/date
+//! values are rewritten in canonical form. The result is a regular
+//! `ison_rs::Document` that can be serialized back to ISON.
+
+use ison_rs::{Document, FieldInfo, FieldName, Value};
+
+use crate::schema::FieldSchema;
+use crate::TableSchema;
+
+impl TableSchema {
+    /// Return a copy of `doc` with this schema's table normalized.
+    ///
+    /// Values that still fail validation after normalization are left as
+    /// they were, so `validate` on the result reports what could not be
+    /// repaired. Other blocks and undeclared columns are copied unchanged.
+    ///
+    /// ```rust
+    /// use isonantic_rs::prelude::*;
+    ///
+    /// let doc = ison_rs::parse("table.users\nid status\n\" 7 \" ACTIVE").unwrap();
+    /// let schema = table("users")
+    ///     .field("id", int())
+    ///     .field("status", enum_of(["active"]).ignore_case())
+    ///     .field("role", string().default_value("member"));
+    ///
+    /// let fixed = schema.normalize(&doc);
+    /// assert_eq!(ison_rs::dumps(&fixed, false), "table.users\nid status role\n7 active member");
+    /// ```
+    pub fn normalize(&self, doc: &Document) -> Document {
+        let mut out = doc.clone();
+        let block = match out.get_mut(&self.name) {
+            Some(block) => block,
+            None => return out,
+        };
+
+        // Declared fields with defaults become columns even if the header lacks them
+        for field in self.fields.iter().filter(|f| f.default.is_some()) {
+            if !block.fields.contains(&field.name) {
+                block.fields.push(field.name.clone());
+                block.field_info.push(FieldInfo::new(field.name.clone()));
+            }
+        }
+
+        let keys: Vec<FieldName> = self.fields.iter().map(|f| f.name.as_str().into()).collect();
+        for row in block.rows.iter_mut() {
+            for (field, key) in self.fields.iter().zip(&keys) {
+                match normalize_value(field, row.get(key)) {
+                    Some(value) => {
+                        row.insert(key.clone(), value);
+                    }
+                    None => {
+                        row.remove(key);
+                    }
+                }
+            }
+        }
+
+        out
+    }
+}
+
+fn normalize_value(field: &FieldSchema, value: Option<&Value>) -> Option<Value> {
+    let value = match value {
+        Some(v) => v,
+        None => return field.default.as_ref().map(|d| d.to_ison_value()),
+    };
+
+    let trimmed = match value {
+        Value::String(s) if s.trim().len() != s.len() => Value::String(s.trim().to_string()),
+        other => other.clone(),
+    };
+    let candidate = field.field_type.coerce(&trimmed).unwrap_or(trimmed);
+
+    match field.field_type.convert(&candidate, &field.name) {
+        Ok(validated) => Some(validated.to_ison_value()),
+        Err(_) => Some(candidate),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use ison_rs::{parse, Value};
+
+    #[test]
+    fn test_normalize_keeps_unrepairable_values() {
+        let doc =
+            parse("table.items\nqty note\nlots \" hi \"\n\" 3\" ok\n\ntable.other\nx\n1").unwrap();
+        let schema = table("items").field("qty", int()).field("note", string());
+
+        let fixed = schema.normalize(&doc);
+        let items = fixed.get("items").unwrap();
+        assert_eq!(items[0].get("qty"), Some(&Value::String("lots".into())));
+        assert_eq!(items[0].get("note"), Some(&Value::String("hi".into())));
+        assert_eq!(items[1].get("qty"), Some(&Value::Int(3)));
+        assert!(fixed.has("other"));
+
+        let err = schema.validate(&fixed).unwrap_err();
+        assert_eq!(err.errors.len(), 1);
+        assert_eq!(err.errors[0].field, "[0].qty");
+    }
+}
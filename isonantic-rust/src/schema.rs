@@ -1,6 +1,19 @@
 //! Schema definitions for ISON validation
 
-use crate::{FieldError, Result, ValidatedRow, ValidatedTable, ValidatedValue, ValidationError};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::ops::{Bound, RangeBounds};
+use std::sync::Arc;
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, Offset, Utc};
+
+use crate::conditional::ConditionalRule;
+use crate::summary::SummarySchema;
+use crate::validators::{NotEmptyValidator, OneOfValidator};
+use crate::{
+    Coercion, FieldError, Result, ValidatedRow, ValidatedTable, ValidatedValue, ValidationError,
+    ValidationReport,
+};
 
 // =============================================================================
 // Field Schema
@@ -11,7 +24,10 @@ use crate::{FieldError, Result, ValidatedRow, ValidatedTable, ValidatedValue, Va
 pub struct FieldSchema {
     pub name: String,
     pub field_type: FieldType,
+    /// The field must be present in the row
     pub required: bool,
+    /// A present field may hold `null`
+    pub nullable: bool,
     pub default: Option<ValidatedValue>,
     pub validators: Vec<Box<dyn FieldValidator>>,
 }
@@ -22,26 +38,52 @@ impl FieldSchema {
             name: name.into(),
             field_type,
             required: false,
+            nullable: false,
             default: None,
             validators: Vec::new(),
         }
     }
 
     pub fn validate(&self, value: Option<&ison_rs::Value>) -> Result<ValidatedValue> {
+        self.validate_coercing(value, false).map(|(v, _)| v)
+    }
+
+    /// Validate a value, optionally coercing it to the field type first.
+    ///
+    /// The flag in the returned tuple is `true` when the value only passed
+    /// because it was coerced.
+    pub fn validate_coercing(
+        &self,
+        value: Option<&ison_rs::Value>,
+        coerce: bool,
+    ) -> Result<(ValidatedValue, bool)> {
         // Handle missing values
         let value = match value {
             Some(v) => v,
             None => {
                 if let Some(default) = &self.default {
-                    return Ok(default.clone());
+                    return Ok((default.clone(), false));
                 }
                 if self.required {
-                    return Err(ValidationError::single(&self.name, "Field is required"));
+                    return Err(ValidationError::coded(&self.name, "field.required", []));
                 }
-                return Ok(ValidatedValue::Null);
+                return Ok((ValidatedValue::Null, false));
             }
         };
 
+        // Present but null is only accepted for nullable fields
+        if value.is_null() && !matches!(self.field_type, FieldType::Null) {
+            if self.nullable {
+                return Ok((ValidatedValue::Null, false));
+            }
+            return Err(ValidationError::coded(&self.name, "field.null", []));
+        }
+
+        // Coerce mismatched values when enabled
+        let coerced = if coerce { self.field_type.coerce(value) } else { None };
+        let was_coerced = coerced.is_some();
+        let value = coerced.as_ref().unwrap_or(value);
+
         // Convert and validate type
         let validated = self.field_type.convert(value, &self.name)?;
 
@@ -50,7 +92,7 @@ impl FieldSchema {
             validator.validate(&validated, &self.name)?;
         }
 
-        Ok(validated)
+        Ok((validated, was_coerced))
     }
 }
 
@@ -61,23 +103,57 @@ pub enum FieldType {
     Int(NumberConstraints),
     Float(NumberConstraints),
     Bool,
-    Reference,
+    Enum(EnumConstraints),
+    Date(DateConstraints),
+    DateTime(DateTimeConstraints),
+    Reference(RefConstraints),
     Null,
 }
 
 impl FieldType {
-    fn convert(&self, value: &ison_rs::Value, field: &str) -> Result<ValidatedValue> {
+    /// Convert a value of the wrong ISON type into the expected one.
+    ///
+    /// Returns `None` when the value already has the right type or cannot be
+    /// converted, leaving the normal type check to report the error.
+    pub(crate) fn coerce(&self, value: &ison_rs::Value) -> Option<ison_rs::Value> {
+        use ison_rs::Value;
+
+        match (self, value) {
+            (FieldType::Int(_), Value::String(s)) => s.trim().parse::<i64>().ok().map(Value::Int),
+            // Both bounds are exact as f64 (-2^63 and 2^63); past them `as` saturates
+            (FieldType::Int(_), Value::Float(f))
+                if f.fract() == 0.0 && (i64::MIN as f64..i64::MAX as f64).contains(f) =>
+            {
+                Some(Value::Int(*f as i64))
+            }
+            (FieldType::Float(_), Value::String(s)) => s.trim().parse::<f64>().ok().map(Value::Float),
+            (FieldType::Bool, Value::Int(1)) => Some(Value::Bool(true)),
+            (FieldType::Bool, Value::Int(0)) => Some(Value::Bool(false)),
+            (FieldType::Bool, Value::String(s)) => match s.trim().to_ascii_lowercase().as_str() {
+                "true" | "yes" | "y" | "on" | "1" => Some(Value::Bool(true)),
+                "false" | "no" | "n" | "off" | "0" => Some(Value::Bool(false)),
+                _ => None,
+            },
+            (FieldType::String(_), Value::Int(_) | Value::Float(_) | Value::Bool(_)) => {
+                Some(Value::String(value.to_string()))
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn convert(&self, value: &ison_rs::Value, field: &str) -> Result<ValidatedValue> {
         match self {
             FieldType::String(constraints) => {
                 let s = value.as_str().ok_or_else(|| {
-                    ValidationError::single(field, "Expected string")
+                    ValidationError::coded(field, "string.expected", [])
                 })?;
-                constraints.validate(s, field)?;
-                Ok(ValidatedValue::String(s.to_string()))
+                let s = constraints.normalize(s);
+                constraints.validate(&s, field)?;
+                Ok(ValidatedValue::String(s.into_owned()))
             }
             FieldType::Int(constraints) => {
                 let i = value.as_int().ok_or_else(|| {
-                    ValidationError::single(field, "Expected integer")
+                    ValidationError::coded(field, "int.expected", [])
                 })?;
                 constraints.validate_int(i, field)?;
                 Ok(ValidatedValue::Int(i))
@@ -85,31 +161,63 @@ impl FieldType {
             FieldType::Float(constraints) => {
                 let f = value.as_float().or_else(|| value.as_int().map(|i| i as f64))
                     .ok_or_else(|| {
-                        ValidationError::single(field, "Expected number")
+                        ValidationError::coded(field, "float.expected", [])
                     })?;
                 constraints.validate_float(f, field)?;
                 Ok(ValidatedValue::Float(f))
             }
             FieldType::Bool => {
                 let b = value.as_bool().ok_or_else(|| {
-                    ValidationError::single(field, "Expected boolean")
+                    ValidationError::coded(field, "bool.expected", [])
                 })?;
                 Ok(ValidatedValue::Bool(b))
             }
-            FieldType::Reference => {
+            FieldType::Enum(constraints) => {
+                let variant = constraints.resolve(value).ok_or_else(|| {
+                    ValidationError::coded(
+                        field,
+                        "enum.invalid",
+                        [("variants", format!("{:?}", constraints.variants))],
+                    )
+                })?;
+                Ok(ValidatedValue::Enum(variant.to_string()))
+            }
+            FieldType::Date(constraints) => {
+                let s = value.as_str().ok_or_else(|| {
+                    ValidationError::coded(field, "date.expected", [])
+                })?;
+                let d = constraints.parse(s, field)?;
+                constraints.validate(d, field)?;
+                Ok(ValidatedValue::Date(d))
+            }
+            FieldType::DateTime(constraints) => {
+                let s = value.as_str().ok_or_else(|| {
+                    ValidationError::coded(field, "datetime.expected", [])
+                })?;
+                let dt = constraints.parse(s, field)?;
+                constraints.validate(&dt, field)?;
+                Ok(ValidatedValue::DateTime(dt))
+            }
+            FieldType::Reference(constraints) => {
                 let r = value.as_reference().ok_or_else(|| {
-                    ValidationError::single(field, "Expected reference")
+                    ValidationError::coded(field, "ref.expected", [])
                 })?;
-                Ok(ValidatedValue::Reference(crate::ISONReference {
-                    id: r.id.clone(),
-                    ref_type: r.ref_type.clone(),
-                }))
+                if let Some(ref_type) = &constraints.ref_type {
+                    if r.ref_type.as_ref() != Some(ref_type) {
+                        return Err(ValidationError::coded(
+                            field,
+                            "ref.wrong_type",
+                            [("ref_type", ref_type.clone())],
+                        ));
+                    }
+                }
+                Ok(ValidatedValue::Reference(r.clone()))
             }
             FieldType::Null => {
                 if value.is_null() {
                     Ok(ValidatedValue::Null)
                 } else {
-                    Err(ValidationError::single(field, "Expected null"))
+                    Err(ValidationError::coded(field, "null.expected", []))
                 }
             }
         }
@@ -126,28 +234,80 @@ pub struct StringConstraints {
     pub max_length: Option<usize>,
     pub pattern: Option<String>,
     pub email: bool,
+    /// Strip leading and trailing whitespace before validating
+    pub trim: bool,
+    /// Lowercase before validating
+    pub lowercase: bool,
+    pub ascii_only: bool,
+    pub starts_with: Option<String>,
+    pub ends_with: Option<String>,
+    /// Upper bound on the UTF-8 encoded length
+    pub max_bytes: Option<usize>,
 }
 
 impl StringConstraints {
+    /// `value` trimmed and lowercased as configured; the validated value
+    /// is the normalized one
+    fn normalize<'a>(&self, value: &'a str) -> Cow<'a, str> {
+        let value = if self.trim { value.trim() } else { value };
+        if self.lowercase {
+            Cow::Owned(value.to_lowercase())
+        } else {
+            Cow::Borrowed(value)
+        }
+    }
+
     fn validate(&self, value: &str, field: &str) -> Result<()> {
         if let Some(min) = self.min_length {
             if value.len() < min {
-                return Err(ValidationError::single(
+                return Err(ValidationError::coded(
                     field,
-                    format!("String must be at least {} characters", min),
+                    "string.too_short",
+                    [("min", min.to_string())],
                 ));
             }
         }
         if let Some(max) = self.max_length {
             if value.len() > max {
-                return Err(ValidationError::single(
+                return Err(ValidationError::coded(
+                    field,
+                    "string.too_long",
+                    [("max", max.to_string())],
+                ));
+            }
+        }
+        if let Some(max) = self.max_bytes {
+            if value.len() > max {
+                return Err(ValidationError::coded(
                     field,
-                    format!("String must be at most {} characters", max),
+                    "string.too_many_bytes",
+                    [("max", max.to_string())],
                 ));
             }
         }
         if self.email && !value.contains('@') {
-            return Err(ValidationError::single(field, "Invalid email format"));
+            return Err(ValidationError::coded(field, "string.email", []));
+        }
+        if self.ascii_only && !value.is_ascii() {
+            return Err(ValidationError::coded(field, "string.not_ascii", []));
+        }
+        if let Some(prefix) = &self.starts_with {
+            if !value.starts_with(prefix.as_str()) {
+                return Err(ValidationError::coded(
+                    field,
+                    "string.prefix",
+                    [("prefix", prefix.clone())],
+                ));
+            }
+        }
+        if let Some(suffix) = &self.ends_with {
+            if !value.ends_with(suffix.as_str()) {
+                return Err(ValidationError::coded(
+                    field,
+                    "string.suffix",
+                    [("suffix", suffix.clone())],
+                ));
+            }
         }
         Ok(())
     }
@@ -155,44 +315,306 @@ impl StringConstraints {
 
 #[derive(Debug, Clone, Default)]
 pub struct NumberConstraints {
+    /// Inclusive lower bound
     pub min: Option<f64>,
+    /// Inclusive upper bound
     pub max: Option<f64>,
+    /// Exclusive lower bound
+    pub gt: Option<f64>,
+    /// Exclusive upper bound
+    pub lt: Option<f64>,
+    pub multiple_of: Option<f64>,
     pub positive: bool,
     pub negative: bool,
+    /// Reject NaN and infinities
+    pub finite: bool,
 }
 
 impl NumberConstraints {
     fn validate_int(&self, value: i64, field: &str) -> Result<()> {
-        self.validate_float(value as f64, field)
+        self.validate_bounds(value as f64, field)?;
+        match self.multiple_of {
+            // Whole divisors are checked exactly, beyond f64 precision
+            Some(m) if m.fract() == 0.0 && m != 0.0 => {
+                // `i64::MIN % -1` overflows, but is a whole multiple
+                if value.checked_rem(m as i64).is_some_and(|r| r != 0) {
+                    return Err(self.not_multiple(field));
+                }
+                Ok(())
+            }
+            _ => self.validate_multiple(value as f64, field),
+        }
     }
 
     fn validate_float(&self, value: f64, field: &str) -> Result<()> {
+        if self.finite && !value.is_finite() {
+            return Err(ValidationError::coded(field, "number.not_finite", []));
+        }
+        self.validate_bounds(value, field)?;
+        self.validate_multiple(value, field)
+    }
+
+    fn validate_multiple(&self, value: f64, field: &str) -> Result<()> {
+        if let Some(m) = self.multiple_of.filter(|m| *m != 0.0) {
+            // Tolerance scales with the quotient, as f64 rounding error does
+            let quotient = value / m;
+            if (quotient - quotient.round()).abs() > 1e-9 * quotient.abs().max(1.0) {
+                return Err(self.not_multiple(field));
+            }
+        }
+        Ok(())
+    }
+
+    fn not_multiple(&self, field: &str) -> ValidationError {
+        let m = self.multiple_of.unwrap_or_default();
+        ValidationError::coded(
+            field,
+            "number.not_multiple",
+            [("multiple_of", m.to_string())],
+        )
+    }
+
+    fn validate_bounds(&self, value: f64, field: &str) -> Result<()> {
         if let Some(min) = self.min {
             if value < min {
-                return Err(ValidationError::single(
+                return Err(ValidationError::coded(
                     field,
-                    format!("Value must be >= {}", min),
+                    "number.too_small",
+                    [("min", min.to_string())],
                 ));
             }
         }
         if let Some(max) = self.max {
             if value > max {
-                return Err(ValidationError::single(
+                return Err(ValidationError::coded(
+                    field,
+                    "number.too_large",
+                    [("max", max.to_string())],
+                ));
+            }
+        }
+        if let Some(gt) = self.gt {
+            if value <= gt {
+                return Err(ValidationError::coded(
+                    field,
+                    "number.not_greater",
+                    [("min", gt.to_string())],
+                ));
+            }
+        }
+        if let Some(lt) = self.lt {
+            if value >= lt {
+                return Err(ValidationError::coded(
                     field,
-                    format!("Value must be <= {}", max),
+                    "number.not_less",
+                    [("max", lt.to_string())],
                 ));
             }
         }
         if self.positive && value <= 0.0 {
-            return Err(ValidationError::single(field, "Value must be positive"));
+            return Err(ValidationError::coded(field, "number.not_positive", []));
         }
         if self.negative && value >= 0.0 {
-            return Err(ValidationError::single(field, "Value must be negative"));
+            return Err(ValidationError::coded(field, "number.not_negative", []));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EnumConstraints {
+    /// Canonical variant names
+    pub variants: Vec<String>,
+    /// Integer codes parallel to `variants` (empty for string-only enums)
+    pub codes: Vec<i64>,
+    pub case_insensitive: bool,
+}
+
+impl EnumConstraints {
+    /// Map a raw value to its canonical variant name
+    fn resolve(&self, value: &ison_rs::Value) -> Option<&str> {
+        if let Some(code) = value.as_int() {
+            return self
+                .codes
+                .iter()
+                .position(|c| *c == code)
+                .map(|idx| self.variants[idx].as_str());
+        }
+        let s = value.as_str()?;
+        self.variants
+            .iter()
+            .find(|v| {
+                if self.case_insensitive {
+                    v.eq_ignore_ascii_case(s)
+                } else {
+                    v.as_str() == s
+                }
+            })
+            .map(String::as_str)
+    }
+}
+
+/// Formats tried for dates when no explicit format is configured
+const DEFAULT_DATE_FORMATS: &[&str] = &["%Y-%m-%d"];
+
+/// Naive (offset-less) formats tried for datetimes after RFC 3339
+const DEFAULT_NAIVE_DATETIME_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct DateConstraints {
+    pub min: Option<NaiveDate>,
+    pub max: Option<NaiveDate>,
+    /// `chrono` format strings; ISO 8601 (`%Y-%m-%d`) when empty
+    pub formats: Vec<String>,
+}
+
+impl DateConstraints {
+    fn parse(&self, value: &str, field: &str) -> Result<NaiveDate> {
+        let parsed = if self.formats.is_empty() {
+            DEFAULT_DATE_FORMATS
+                .iter()
+                .find_map(|fmt| NaiveDate::parse_from_str(value, fmt).ok())
+        } else {
+            self.formats
+                .iter()
+                .find_map(|fmt| NaiveDate::parse_from_str(value, fmt).ok())
+        };
+        parsed.ok_or_else(|| ValidationError::coded(field, "date.format", []))
+    }
+
+    fn validate(&self, value: NaiveDate, field: &str) -> Result<()> {
+        if let Some(min) = self.min {
+            if value < min {
+                return Err(ValidationError::coded(
+                    field,
+                    "date.too_early",
+                    [("min", min.to_string())],
+                ));
+            }
+        }
+        if let Some(max) = self.max {
+            if value > max {
+                return Err(ValidationError::coded(
+                    field,
+                    "date.too_late",
+                    [("max", max.to_string())],
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DateTimeConstraints {
+    pub min: Option<DateTime<FixedOffset>>,
+    pub max: Option<DateTime<FixedOffset>>,
+    /// `chrono` format strings; RFC 3339 plus common ISO 8601 variants when empty
+    pub formats: Vec<String>,
+    /// Offset applied to values without one (UTC when unset)
+    pub default_offset: Option<FixedOffset>,
+    /// Reject values that carry no explicit offset
+    pub require_offset: bool,
+    /// Convert every value into this offset after parsing
+    pub convert_to: Option<FixedOffset>,
+}
+
+impl DateTimeConstraints {
+    fn parse(&self, value: &str, field: &str) -> Result<DateTime<FixedOffset>> {
+        let parsed = if self.formats.is_empty() {
+            DateTime::parse_from_rfc3339(value)
+                .ok()
+                .map(Ok)
+                .or_else(|| {
+                    DEFAULT_NAIVE_DATETIME_FORMATS
+                        .iter()
+                        .find_map(|fmt| NaiveDateTime::parse_from_str(value, fmt).ok())
+                        .map(|naive| self.attach_offset(naive, field))
+                })
+        } else {
+            self.formats.iter().find_map(|fmt| {
+                DateTime::parse_from_str(value, fmt).ok().map(Ok).or_else(|| {
+                    NaiveDateTime::parse_from_str(value, fmt)
+                        .ok()
+                        .map(|naive| self.attach_offset(naive, field))
+                })
+            })
+        };
+
+        let dt = parsed
+            .ok_or_else(|| ValidationError::coded(field, "datetime.format", []))??;
+
+        Ok(match self.convert_to {
+            Some(offset) => dt.with_timezone(&offset),
+            None => dt,
+        })
+    }
+
+    fn attach_offset(&self, naive: NaiveDateTime, field: &str) -> Result<DateTime<FixedOffset>> {
+        if self.require_offset {
+            return Err(ValidationError::coded(field, "datetime.no_offset", []));
+        }
+        let offset = self.default_offset.unwrap_or_else(|| Utc.fix());
+        naive
+            .and_local_timezone(offset)
+            .single()
+            .ok_or_else(|| ValidationError::coded(field, "datetime.invalid_offset", []))
+    }
+
+    fn validate(&self, value: &DateTime<FixedOffset>, field: &str) -> Result<()> {
+        if let Some(min) = &self.min {
+            if value < min {
+                return Err(ValidationError::coded(
+                    field,
+                    "datetime.too_early",
+                    [("min", min.to_rfc3339())],
+                ));
+            }
+        }
+        if let Some(max) = &self.max {
+            if value > max {
+                return Err(ValidationError::coded(
+                    field,
+                    "datetime.too_late",
+                    [("max", max.to_rfc3339())],
+                ));
+            }
         }
         Ok(())
     }
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct RefConstraints {
+    /// Table whose rows the reference must name
+    pub target: Option<String>,
+    /// Column of `target` holding row ids (`id` when unset)
+    pub target_key: Option<String>,
+    /// Namespace the reference must carry, as `user` in `:user:42`
+    pub ref_type: Option<String>,
+}
+
+impl RefConstraints {
+    fn target_key(&self) -> &str {
+        self.target_key.as_deref().unwrap_or("id")
+    }
+}
+
+/// A row id as a reference writes it: `42` for `:42`, `A-1` for `:A-1`
+pub(crate) fn id_text(value: &ison_rs::Value) -> Option<String> {
+    match value {
+        ison_rs::Value::Int(i) => Some(i.to_string()),
+        ison_rs::Value::String(s) => Some(s.clone()),
+        ison_rs::Value::Reference(r) => Some(r.id.clone()),
+        _ => None,
+    }
+}
+
 // =============================================================================
 // Field Validator Trait
 // =============================================================================
@@ -200,6 +622,12 @@ impl NumberConstraints {
 pub trait FieldValidator: std::fmt::Debug + Send + Sync {
     fn validate(&self, value: &ValidatedValue, field: &str) -> Result<()>;
     fn clone_box(&self) -> Box<dyn FieldValidator>;
+
+    /// The only values accepted, as written in ISON, for validators that
+    /// accept a fixed set; `generate` picks sample values from it
+    fn allowed_values(&self) -> Option<&[String]> {
+        None
+    }
 }
 
 impl Clone for Box<dyn FieldValidator> {
@@ -217,7 +645,9 @@ impl Clone for Box<dyn FieldValidator> {
 pub struct StringFieldBuilder {
     constraints: StringConstraints,
     required: bool,
+    nullable: bool,
     default: Option<String>,
+    validators: Vec<Box<dyn FieldValidator>>,
 }
 
 impl StringFieldBuilder {
@@ -240,11 +670,56 @@ impl StringFieldBuilder {
         self
     }
 
+    /// Strip leading and trailing whitespace before the other checks
+    pub fn trimmed(mut self) -> Self {
+        self.constraints.trim = true;
+        self
+    }
+
+    /// Lowercase before the other checks
+    pub fn lowercase(mut self) -> Self {
+        self.constraints.lowercase = true;
+        self
+    }
+
+    /// Reject characters outside ASCII
+    pub fn ascii_only(mut self) -> Self {
+        self.constraints.ascii_only = true;
+        self
+    }
+
+    pub fn starts_with(mut self, prefix: impl Into<String>) -> Self {
+        self.constraints.starts_with = Some(prefix.into());
+        self
+    }
+
+    pub fn ends_with(mut self, suffix: impl Into<String>) -> Self {
+        self.constraints.ends_with = Some(suffix.into());
+        self
+    }
+
+    /// Limit the UTF-8 encoded length, e.g. to fit a database column
+    pub fn max_bytes(mut self, bytes: usize) -> Self {
+        self.constraints.max_bytes = Some(bytes);
+        self
+    }
+
+    /// Reject the empty string
+    pub fn not_empty(self) -> Self {
+        self.validator(NotEmptyValidator)
+    }
+
     pub fn required(mut self) -> Self {
         self.required = true;
         self
     }
 
+    /// Accept an explicit `null` (distinct from the field being absent)
+    pub fn nullable(mut self) -> Self {
+        self.nullable = true;
+        self
+    }
+
     pub fn default_value(mut self, value: impl Into<String>) -> Self {
         self.default = Some(value.into());
         self
@@ -253,7 +728,9 @@ impl StringFieldBuilder {
     pub fn build(self, name: impl Into<String>) -> FieldSchema {
         let mut schema = FieldSchema::new(name, FieldType::String(self.constraints));
         schema.required = self.required;
+        schema.nullable = self.nullable;
         schema.default = self.default.map(ValidatedValue::String);
+        schema.validators = self.validators;
         schema
     }
 }
@@ -263,7 +740,9 @@ impl StringFieldBuilder {
 pub struct IntFieldBuilder {
     constraints: NumberConstraints,
     required: bool,
+    nullable: bool,
     default: Option<i64>,
+    validators: Vec<Box<dyn FieldValidator>>,
 }
 
 impl IntFieldBuilder {
@@ -281,6 +760,48 @@ impl IntFieldBuilder {
         self
     }
 
+    /// Same as [`min`](Self::min)
+    pub fn ge(self, value: i64) -> Self {
+        self.min(value)
+    }
+
+    /// Same as [`max`](Self::max)
+    pub fn le(self, value: i64) -> Self {
+        self.max(value)
+    }
+
+    /// Require values greater than `value`
+    pub fn gt(mut self, value: i64) -> Self {
+        self.constraints.gt = Some(value as f64);
+        self
+    }
+
+    /// Require values less than `value`
+    pub fn lt(mut self, value: i64) -> Self {
+        self.constraints.lt = Some(value as f64);
+        self
+    }
+
+    /// Bound values by a range, e.g. `range(1..=12)` or `range(0..100)`
+    pub fn range(mut self, range: impl RangeBounds<i64>) -> Self {
+        match range.start_bound() {
+            Bound::Included(v) => self.constraints.min = Some(*v as f64),
+            Bound::Excluded(v) => self.constraints.gt = Some(*v as f64),
+            Bound::Unbounded => {}
+        }
+        match range.end_bound() {
+            Bound::Included(v) => self.constraints.max = Some(*v as f64),
+            Bound::Excluded(v) => self.constraints.lt = Some(*v as f64),
+            Bound::Unbounded => {}
+        }
+        self
+    }
+
+    pub fn multiple_of(mut self, value: i64) -> Self {
+        self.constraints.multiple_of = Some(value as f64);
+        self
+    }
+
     pub fn positive(mut self) -> Self {
         self.constraints.positive = true;
         self
@@ -291,6 +812,12 @@ impl IntFieldBuilder {
         self
     }
 
+    /// Accept an explicit `null` (distinct from the field being absent)
+    pub fn nullable(mut self) -> Self {
+        self.nullable = true;
+        self
+    }
+
     pub fn default_value(mut self, value: i64) -> Self {
         self.default = Some(value);
         self
@@ -299,7 +826,9 @@ impl IntFieldBuilder {
     pub fn build(self, name: impl Into<String>) -> FieldSchema {
         let mut schema = FieldSchema::new(name, FieldType::Int(self.constraints));
         schema.required = self.required;
+        schema.nullable = self.nullable;
         schema.default = self.default.map(ValidatedValue::Int);
+        schema.validators = self.validators;
         schema
     }
 }
@@ -309,7 +838,9 @@ impl IntFieldBuilder {
 pub struct FloatFieldBuilder {
     constraints: NumberConstraints,
     required: bool,
+    nullable: bool,
     default: Option<f64>,
+    validators: Vec<Box<dyn FieldValidator>>,
 }
 
 impl FloatFieldBuilder {
@@ -327,6 +858,56 @@ impl FloatFieldBuilder {
         self
     }
 
+    /// Same as [`min`](Self::min)
+    pub fn ge(self, value: f64) -> Self {
+        self.min(value)
+    }
+
+    /// Same as [`max`](Self::max)
+    pub fn le(self, value: f64) -> Self {
+        self.max(value)
+    }
+
+    /// Require values greater than `value`
+    pub fn gt(mut self, value: f64) -> Self {
+        self.constraints.gt = Some(value);
+        self
+    }
+
+    /// Require values less than `value`
+    pub fn lt(mut self, value: f64) -> Self {
+        self.constraints.lt = Some(value);
+        self
+    }
+
+    /// Bound values by a range, e.g. `range(0.0..1.0)`
+    pub fn range(mut self, range: impl RangeBounds<f64>) -> Self {
+        match range.start_bound() {
+            Bound::Included(v) => self.constraints.min = Some(*v),
+            Bound::Excluded(v) => self.constraints.gt = Some(*v),
+            Bound::Unbounded => {}
+        }
+        match range.end_bound() {
+            Bound::Included(v) => self.constraints.max = Some(*v),
+            Bound::Excluded(v) => self.constraints.lt = Some(*v),
+            Bound::Unbounded => {}
+        }
+        self
+    }
+
+    /// Require a multiple of `value`, to within a relative 1e-9 of the
+    /// quotient so that decimal steps such as `0.01` pass
+    pub fn multiple_of(mut self, value: f64) -> Self {
+        self.constraints.multiple_of = Some(value);
+        self
+    }
+
+    /// Reject NaN and infinities
+    pub fn finite(mut self) -> Self {
+        self.constraints.finite = true;
+        self
+    }
+
     pub fn positive(mut self) -> Self {
         self.constraints.positive = true;
         self
@@ -337,6 +918,12 @@ impl FloatFieldBuilder {
         self
     }
 
+    /// Accept an explicit `null` (distinct from the field being absent)
+    pub fn nullable(mut self) -> Self {
+        self.nullable = true;
+        self
+    }
+
     pub fn default_value(mut self, value: f64) -> Self {
         self.default = Some(value);
         self
@@ -345,7 +932,9 @@ impl FloatFieldBuilder {
     pub fn build(self, name: impl Into<String>) -> FieldSchema {
         let mut schema = FieldSchema::new(name, FieldType::Float(self.constraints));
         schema.required = self.required;
+        schema.nullable = self.nullable;
         schema.default = self.default.map(ValidatedValue::Float);
+        schema.validators = self.validators;
         schema
     }
 }
@@ -354,7 +943,9 @@ impl FloatFieldBuilder {
 #[derive(Debug, Clone, Default)]
 pub struct BoolFieldBuilder {
     required: bool,
+    nullable: bool,
     default: Option<bool>,
+    validators: Vec<Box<dyn FieldValidator>>,
 }
 
 impl BoolFieldBuilder {
@@ -367,6 +958,12 @@ impl BoolFieldBuilder {
         self
     }
 
+    /// Accept an explicit `null` (distinct from the field being absent)
+    pub fn nullable(mut self) -> Self {
+        self.nullable = true;
+        self
+    }
+
     pub fn default_value(mut self, value: bool) -> Self {
         self.default = Some(value);
         self
@@ -375,20 +972,62 @@ impl BoolFieldBuilder {
     pub fn build(self, name: impl Into<String>) -> FieldSchema {
         let mut schema = FieldSchema::new(name, FieldType::Bool);
         schema.required = self.required;
+        schema.nullable = self.nullable;
         schema.default = self.default.map(ValidatedValue::Bool);
+        schema.validators = self.validators;
         schema
     }
 }
 
-/// Reference field builder
+/// Enum field builder
 #[derive(Debug, Clone, Default)]
-pub struct RefFieldBuilder {
+pub struct EnumFieldBuilder {
+    constraints: EnumConstraints,
     required: bool,
+    nullable: bool,
+    default: Option<String>,
+    validators: Vec<Box<dyn FieldValidator>>,
 }
 
-impl RefFieldBuilder {
-    pub fn new() -> Self {
-        Self::default()
+impl EnumFieldBuilder {
+    pub fn new<I, S>(variants: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            constraints: EnumConstraints {
+                variants: variants.into_iter().map(Into::into).collect(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Create an integer-backed enum accepting either the code or the name
+    pub fn with_codes<I, S>(variants: I) -> Self
+    where
+        I: IntoIterator<Item = (i64, S)>,
+        S: Into<String>,
+    {
+        let (codes, variants): (Vec<i64>, Vec<String>) = variants
+            .into_iter()
+            .map(|(code, name)| (code, name.into()))
+            .unzip();
+        Self {
+            constraints: EnumConstraints {
+                variants,
+                codes,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Match variant names case-insensitively (the canonical spelling is kept)
+    pub fn ignore_case(mut self) -> Self {
+        self.constraints.case_insensitive = true;
+        self
     }
 
     pub fn required(mut self) -> Self {
@@ -396,74 +1035,920 @@ impl RefFieldBuilder {
         self
     }
 
+    /// Accept an explicit `null` (distinct from the field being absent)
+    pub fn nullable(mut self) -> Self {
+        self.nullable = true;
+        self
+    }
+
+    pub fn default_value(mut self, value: impl Into<String>) -> Self {
+        self.default = Some(value.into());
+        self
+    }
+
     pub fn build(self, name: impl Into<String>) -> FieldSchema {
-        let mut schema = FieldSchema::new(name, FieldType::Reference);
+        let mut schema = FieldSchema::new(name, FieldType::Enum(self.constraints));
         schema.required = self.required;
+        schema.nullable = self.nullable;
+        schema.default = self.default.map(ValidatedValue::Enum);
+        schema.validators = self.validators;
         schema
     }
 }
 
-// =============================================================================
-// Table Schema
-// =============================================================================
-
-/// Schema for validating ISON tables
-#[derive(Debug, Clone)]
-pub struct TableSchema {
-    pub name: String,
-    pub fields: Vec<FieldSchema>,
+/// Date field builder
+#[derive(Debug, Clone, Default)]
+pub struct DateFieldBuilder {
+    constraints: DateConstraints,
+    required: bool,
+    nullable: bool,
+    default: Option<NaiveDate>,
+    validators: Vec<Box<dyn FieldValidator>>,
 }
 
-impl TableSchema {
-    pub fn new(name: impl Into<String>) -> Self {
-        Self {
-            name: name.into(),
-            fields: Vec::new(),
-        }
+impl DateFieldBuilder {
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    pub fn field(mut self, name: impl Into<String>, builder: impl FieldBuilder) -> Self {
-        self.fields.push(builder.into_field_schema(name));
+    pub fn min(mut self, value: NaiveDate) -> Self {
+        self.constraints.min = Some(value);
         self
     }
 
-    pub fn validate(&self, doc: &ison_rs::Document) -> Result<ValidatedTable> {
-        let block = doc.get(&self.name).ok_or_else(|| {
-            ValidationError::single("", format!("Missing table: {}", self.name))
-        })?;
-
-        let mut table = ValidatedTable::new(&self.name);
-        let mut all_errors = Vec::new();
+    pub fn max(mut self, value: NaiveDate) -> Self {
+        self.constraints.max = Some(value);
+        self
+    }
 
-        for (row_idx, row) in block.rows.iter().enumerate() {
-            let mut validated_row = ValidatedRow::new();
+    /// Accept values in this `chrono` format (may be called repeatedly)
+    pub fn format(mut self, fmt: impl Into<String>) -> Self {
+        self.constraints.formats.push(fmt.into());
+        self
+    }
 
-            for field_schema in &self.fields {
-                let value = row.get(&field_schema.name);
-                match field_schema.validate(value) {
-                    Ok(v) => {
-                        validated_row.fields.insert(field_schema.name.clone(), v);
-                    }
-                    Err(e) => {
-                        for err in e.errors {
-                            all_errors.push(FieldError {
-                                field: format!("[{}].{}", row_idx, err.field),
-                                message: err.message,
-                                value: err.value,
-                            });
-                        }
-                    }
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    /// Accept an explicit `null` (distinct from the field being absent)
+    pub fn nullable(mut self) -> Self {
+        self.nullable = true;
+        self
+    }
+
+    pub fn default_value(mut self, value: NaiveDate) -> Self {
+        self.default = Some(value);
+        self
+    }
+
+    pub fn build(self, name: impl Into<String>) -> FieldSchema {
+        let mut schema = FieldSchema::new(name, FieldType::Date(self.constraints));
+        schema.required = self.required;
+        schema.nullable = self.nullable;
+        schema.default = self.default.map(ValidatedValue::Date);
+        schema.validators = self.validators;
+        schema
+    }
+}
+
+/// Datetime field builder
+#[derive(Debug, Clone, Default)]
+pub struct DateTimeFieldBuilder {
+    constraints: DateTimeConstraints,
+    required: bool,
+    nullable: bool,
+    default: Option<DateTime<FixedOffset>>,
+    validators: Vec<Box<dyn FieldValidator>>,
+}
+
+impl DateTimeFieldBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn min(mut self, value: DateTime<FixedOffset>) -> Self {
+        self.constraints.min = Some(value);
+        self
+    }
+
+    pub fn max(mut self, value: DateTime<FixedOffset>) -> Self {
+        self.constraints.max = Some(value);
+        self
+    }
+
+    /// Accept values in this `chrono` format (may be called repeatedly)
+    pub fn format(mut self, fmt: impl Into<String>) -> Self {
+        self.constraints.formats.push(fmt.into());
+        self
+    }
+
+    /// Offset assumed for values written without one (default: UTC)
+    pub fn default_offset(mut self, offset: FixedOffset) -> Self {
+        self.constraints.default_offset = Some(offset);
+        self
+    }
+
+    /// Reject values that do not carry an explicit offset
+    pub fn require_offset(mut self) -> Self {
+        self.constraints.require_offset = true;
+        self
+    }
+
+    /// Normalize all values into the given offset
+    pub fn convert_to(mut self, offset: FixedOffset) -> Self {
+        self.constraints.convert_to = Some(offset);
+        self
+    }
+
+    /// Normalize all values into UTC
+    pub fn utc(self) -> Self {
+        self.convert_to(Utc.fix())
+    }
+
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    /// Accept an explicit `null` (distinct from the field being absent)
+    pub fn nullable(mut self) -> Self {
+        self.nullable = true;
+        self
+    }
+
+    pub fn default_value(mut self, value: DateTime<FixedOffset>) -> Self {
+        self.default = Some(value);
+        self
+    }
+
+    pub fn build(self, name: impl Into<String>) -> FieldSchema {
+        let mut schema = FieldSchema::new(name, FieldType::DateTime(self.constraints));
+        schema.required = self.required;
+        schema.nullable = self.nullable;
+        schema.default = self.default.map(ValidatedValue::DateTime);
+        schema.validators = self.validators;
+        schema
+    }
+}
+
+/// Reference field builder
+#[derive(Debug, Clone, Default)]
+pub struct RefFieldBuilder {
+    constraints: RefConstraints,
+    required: bool,
+    nullable: bool,
+    validators: Vec<Box<dyn FieldValidator>>,
+}
+
+impl RefFieldBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require the referenced id to exist in the `id` column of `table`,
+    /// checked against the document being validated
+    pub fn points_to(mut self, table: impl Into<String>) -> Self {
+        self.constraints.target = Some(table.into());
+        self
+    }
+
+    /// Match ids against this column of the target table instead of `id`
+    pub fn target_key(mut self, column: impl Into<String>) -> Self {
+        self.constraints.target_key = Some(column.into());
+        self
+    }
+
+    /// Require the reference's namespace, e.g. `user` for `:user:42`
+    pub fn ref_type(mut self, ref_type: impl Into<String>) -> Self {
+        self.constraints.ref_type = Some(ref_type.into());
+        self
+    }
+
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    /// Accept an explicit `null` (distinct from the field being absent)
+    pub fn nullable(mut self) -> Self {
+        self.nullable = true;
+        self
+    }
+
+    pub fn build(self, name: impl Into<String>) -> FieldSchema {
+        let mut schema = FieldSchema::new(name, FieldType::Reference(self.constraints));
+        schema.required = self.required;
+        schema.nullable = self.nullable;
+        schema.validators = self.validators;
+        schema
+    }
+}
+
+/// Custom validator methods shared by every field builder
+macro_rules! impl_validator_methods {
+    ($($builder:ty),*) => {$(
+        impl $builder {
+            /// Run `validator` on every present, non-null value
+            pub fn validator(mut self, validator: impl FieldValidator + 'static) -> Self {
+                self.validators.push(Box::new(validator));
+                self
+            }
+
+            /// Accept only these values, compared with the value as written
+            /// in ISON (e.g. `one_of([1, 2, 3])` on an int field)
+            pub fn one_of<I, V>(self, allowed: I) -> Self
+            where
+                I: IntoIterator<Item = V>,
+                V: ToString,
+            {
+                self.validator(OneOfValidator::new(
+                    allowed.into_iter().map(|v| v.to_string()).collect(),
+                ))
+            }
+        }
+    )*};
+}
+
+impl_validator_methods!(
+    StringFieldBuilder,
+    IntFieldBuilder,
+    FloatFieldBuilder,
+    BoolFieldBuilder,
+    EnumFieldBuilder,
+    DateFieldBuilder,
+    DateTimeFieldBuilder,
+    RefFieldBuilder
+);
+
+// =============================================================================
+// Table Schema
+// =============================================================================
+
+/// Predicate over a whole validated row
+pub type RowPredicate = Arc<dyn Fn(&ValidatedRow) -> bool + Send + Sync>;
+
+/// Predicate over all validated rows of a table
+pub type TablePredicate = Arc<dyn Fn(&[ValidatedRow]) -> bool + Send + Sync>;
+
+/// Whether a failed check rejects the data or is only reported
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Severity {
+    #[default]
+    Error,
+    /// Reported in [`ValidationReport::warnings`] without failing validation
+    Warning,
+}
+
+/// Cross-field invariant checked against each row
+#[derive(Clone)]
+pub struct RowCheck {
+    pub message: String,
+    pub check: RowPredicate,
+    pub severity: Severity,
+}
+
+impl std::fmt::Debug for RowCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RowCheck")
+            .field("message", &self.message)
+            .field("severity", &self.severity)
+            .finish()
+    }
+}
+
+/// Invariant checked against the table as a whole
+#[derive(Clone)]
+pub struct TableCheck {
+    pub message: String,
+    pub check: TablePredicate,
+    pub severity: Severity,
+}
+
+impl std::fmt::Debug for TableCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TableCheck")
+            .field("message", &self.message)
+            .field("severity", &self.severity)
+            .finish()
+    }
+}
+
+/// Controls how much work `TableSchema::validate_with` does on invalid data
+#[derive(Debug, Clone, Default)]
+pub struct ValidationOptions {
+    /// Stop once this many errors have been collected; `Some(0)` is no limit
+    pub max_errors: Option<usize>,
+    /// Stop at the first error (same as `max_errors: Some(1)`)
+    pub fail_fast: bool,
+    /// Attach a copy of the source row to each error
+    pub include_row_snapshot: bool,
+    /// Async validations in flight at once in `validate_async` (16 when `None`)
+    #[cfg(feature = "tokio")]
+    pub max_concurrency: Option<usize>,
+}
+
+impl ValidationOptions {
+    pub(crate) fn error_limit(&self) -> Option<usize> {
+        if self.fail_fast {
+            Some(1)
+        } else {
+            self.max_errors.filter(|max| *max > 0)
+        }
+    }
+}
+
+/// Schema for validating ISON tables
+#[derive(Debug, Clone)]
+pub struct TableSchema {
+    pub name: String,
+    pub fields: Vec<FieldSchema>,
+    /// Convert values of the wrong type (e.g. `"42"` for an int) instead of failing
+    pub coerce: bool,
+    /// Report block columns that the schema does not declare
+    pub deny_unknown_fields: bool,
+    /// Report declared fields that are missing from the block header
+    pub require_declared_columns: bool,
+    /// Fields required or forbidden depending on other fields
+    pub conditionals: Vec<ConditionalRule>,
+    pub row_checks: Vec<RowCheck>,
+    pub table_checks: Vec<TableCheck>,
+    /// Validators awaited by `validate_async`
+    #[cfg(feature = "tokio")]
+    pub async_validators: Vec<crate::async_validation::AsyncValidator>,
+    /// Schema for rows after the `---` separator (ignored when `None`)
+    pub summary: Option<SummarySchema>,
+}
+
+impl TableSchema {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            fields: Vec::new(),
+            coerce: false,
+            deny_unknown_fields: false,
+            require_declared_columns: false,
+            conditionals: Vec::new(),
+            row_checks: Vec::new(),
+            table_checks: Vec::new(),
+            #[cfg(feature = "tokio")]
+            async_validators: Vec::new(),
+            summary: None,
+        }
+    }
+
+    /// A schema built from the type annotations in `block`'s header, as in
+    /// `id:int name:string email:string? boss:ref`, to check a document
+    /// against the types it declares itself.
+    ///
+    /// Fields annotated `int`, `float`, `number`, `string`, `bool`, `date`,
+    /// `datetime` or `ref` are required, or nullable with a trailing `?`.
+    /// Unannotated fields and other annotations such as `computed` are not
+    /// declared, so they accept any value.
+    ///
+    /// ```rust
+    /// use isonantic_rs::prelude::*;
+    ///
+    /// let doc = ison_rs::parse("table.users\nid:int email:string?\n1 ~\nx a@b.c").unwrap();
+    /// let users = &doc["users"];
+    /// let err = TableSchema::from_block_header(users).validate(&doc).unwrap_err();
+    /// assert_eq!(err.errors[0].field, "[1].id");
+    /// ```
+    pub fn from_block_header(block: &ison_rs::Block) -> Self {
+        let mut schema = Self::new(block.name.clone());
+        for info in &block.field_info {
+            let Some(annotation) = info.field_type.as_deref() else {
+                continue;
+            };
+            let (annotation, nullable) = match annotation.strip_suffix('?') {
+                Some(base) => (base, true),
+                None => (annotation, false),
+            };
+            let field_type = match annotation {
+                "int" => FieldType::Int(NumberConstraints::default()),
+                "float" | "number" => FieldType::Float(NumberConstraints::default()),
+                "string" => FieldType::String(StringConstraints::default()),
+                "bool" => FieldType::Bool,
+                "date" => FieldType::Date(DateConstraints::default()),
+                "datetime" => FieldType::DateTime(DateTimeConstraints::default()),
+                "ref" => FieldType::Reference(RefConstraints::default()),
+                _ => continue,
+            };
+            let mut field = FieldSchema::new(info.name.clone(), field_type);
+            field.required = !nullable;
+            field.nullable = nullable;
+            schema.fields.push(field);
+        }
+        schema
+    }
+
+    /// Validate summary rows with their own field set and aggregate rules
+    pub fn summary(mut self, summary: SummarySchema) -> Self {
+        self.summary = Some(summary);
+        self
+    }
+
+    /// Reject columns present in the block header but not declared here
+    pub fn deny_unknown_fields(mut self) -> Self {
+        self.deny_unknown_fields = true;
+        self
+    }
+
+    /// Reject headers that omit any declared field, even optional ones
+    pub fn require_declared_columns(mut self) -> Self {
+        self.require_declared_columns = true;
+        self
+    }
+
+    /// Compare the block header against the declared fields
+    fn check_header(&self, block: &ison_rs::Block) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        let header_error = |field: &str, code: &str| FieldError::coded(field, code, []);
+
+        if self.deny_unknown_fields {
+            for column in &block.fields {
+                if !self.fields.iter().any(|f| &f.name == column) {
+                    errors.push(header_error(column, "column.unknown"));
                 }
             }
+        }
+        if self.require_declared_columns {
+            for field in &self.fields {
+                if !block.fields.contains(&field.name) {
+                    errors.push(header_error(&field.name, "column.missing"));
+                }
+            }
+        }
+        errors
+    }
+
+    /// Ids of the target table of each `points_to` reference field, by
+    /// field index; a missing target table is reported instead
+    fn reference_targets(
+        &self,
+        doc: &ison_rs::Document,
+        errors: &mut Vec<FieldError>,
+    ) -> Vec<Option<(String, HashSet<String>)>> {
+        self.fields
+            .iter()
+            .map(|field| {
+                let FieldType::Reference(constraints) = &field.field_type else {
+                    return None;
+                };
+                let target = constraints.target.as_ref()?;
+                let Some(block) = doc.get(target) else {
+                    errors.push(FieldError::coded(
+                        &field.name,
+                        "ref.missing_table",
+                        [("table", target.clone())],
+                    ));
+                    return None;
+                };
+                let key = constraints.target_key();
+                let ids = block
+                    .rows
+                    .iter()
+                    .filter_map(|row| id_text(row.get(key)?))
+                    .collect();
+                Some((target.clone(), ids))
+            })
+            .collect()
+    }
+
+    /// Add an invariant spanning several fields of a row.
+    ///
+    /// Runs after the row's fields validated successfully; failures are
+    /// reported as `[row_index]` errors.
+    ///
+    /// ```rust
+    /// use isonantic_rs::prelude::*;
+    ///
+    /// let schema = table("bookings")
+    ///     .field("start", int())
+    ///     .field("end", int())
+    ///     .row_check(
+    ///         |row| row.get_int("end") > row.get_int("start"),
+    ///         "end must be after start",
+    ///     );
+    /// ```
+    pub fn row_check<F>(mut self, check: F, message: impl Into<String>) -> Self
+    where
+        F: Fn(&ValidatedRow) -> bool + Send + Sync + 'static,
+    {
+        self.row_checks.push(RowCheck {
+            message: message.into(),
+            check: Arc::new(check),
+            severity: Severity::Error,
+        });
+        self
+    }
 
+    /// Warn about rows for which `condition` holds, without failing them.
+    ///
+    /// Runs like [`row_check`](Self::row_check); the warnings are listed in
+    /// [`validate_report`](Self::validate_report) and ignored by `validate`.
+    ///
+    /// ```rust
+    /// use isonantic_rs::prelude::*;
+    ///
+    /// let schema = table("orders")
+    ///     .field("total", float())
+    ///     .warn_if(
+    ///         |row| row.get("total").and_then(|v| v.as_float()) > Some(1000.0),
+    ///         "large order",
+    ///     );
+    ///
+    /// let doc = ison_rs::parse("table.orders\ntotal\n12.5\n4999").unwrap();
+    /// let report = schema.validate_report(&doc);
+    /// assert!(report.is_valid());
+    /// assert_eq!(report.warnings[0].to_string(), "[1]: large order");
+    /// ```
+    pub fn warn_if<F>(mut self, condition: F, message: impl Into<String>) -> Self
+    where
+        F: Fn(&ValidatedRow) -> bool + Send + Sync + 'static,
+    {
+        self.row_checks.push(RowCheck {
+            message: message.into(),
+            check: Arc::new(move |row| !condition(row)),
+            severity: Severity::Warning,
+        });
+        self
+    }
+
+    /// Add an invariant over all rows (e.g. weights summing to 1.0).
+    ///
+    /// Runs only when every row passed validation; failures are reported
+    /// against the table name.
+    pub fn table_check<F>(mut self, check: F, message: impl Into<String>) -> Self
+    where
+        F: Fn(&[ValidatedRow]) -> bool + Send + Sync + 'static,
+    {
+        self.table_checks.push(TableCheck {
+            message: message.into(),
+            check: Arc::new(check),
+            severity: Severity::Error,
+        });
+        self
+    }
+
+    /// Warn about the table when `condition` holds over its rows, as
+    /// [`warn_if`](Self::warn_if) does for single rows
+    pub fn table_warn_if<F>(mut self, condition: F, message: impl Into<String>) -> Self
+    where
+        F: Fn(&[ValidatedRow]) -> bool + Send + Sync + 'static,
+    {
+        self.table_checks.push(TableCheck {
+            message: message.into(),
+            check: Arc::new(move |rows| !condition(rows)),
+            severity: Severity::Warning,
+        });
+        self
+    }
+
+    /// Declare a field; declaring a name again replaces the earlier field
+    pub fn field(mut self, name: impl Into<String>, builder: impl FieldBuilder) -> Self {
+        self.put_field(builder.into_field_schema(name));
+        self
+    }
+
+    fn put_field(&mut self, field: FieldSchema) {
+        match self.fields.iter_mut().find(|f| f.name == field.name) {
+            Some(existing) => *existing = field,
+            None => self.fields.push(field),
+        }
+    }
+
+    /// Add the fields, conditional rules, checks and summary schema of `base`.
+    ///
+    /// Fields already declared here win over those of `base`, and fields
+    /// declared afterwards replace them, so common columns can be defined
+    /// once and refined per table:
+    ///
+    /// ```rust
+    /// use isonantic_rs::prelude::*;
+    ///
+    /// let audit = table("audit")
+    ///     .field("id", int().required())
+    ///     .field("created_at", datetime());
+    /// let users = table("users")
+    ///     .extend(&audit)
+    ///     .field("id", string().required())
+    ///     .field("email", string().email());
+    ///
+    /// let names: Vec<&str> = users.fields.iter().map(|f| f.name.as_str()).collect();
+    /// assert_eq!(names, ["id", "created_at", "email"]);
+    /// ```
+    pub fn extend(mut self, base: &TableSchema) -> Self {
+        for field in &base.fields {
+            if !self.fields.iter().any(|f| f.name == field.name) {
+                self.fields.push(field.clone());
+            }
+        }
+        self.conditionals.extend(base.conditionals.iter().cloned());
+        self.row_checks.extend(base.row_checks.iter().cloned());
+        self.table_checks.extend(base.table_checks.iter().cloned());
+        #[cfg(feature = "tokio")]
+        self.async_validators
+            .extend(base.async_validators.iter().cloned());
+        if self.summary.is_none() {
+            self.summary = base.summary.clone();
+        }
+        self
+    }
+
+    /// Keep only the named fields, in their declared order.
+    ///
+    /// Names the schema does not declare are ignored. Conditional rules and
+    /// async validators of dropped fields are dropped too; row and table
+    /// checks are kept.
+    pub fn pick<I, S>(self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let names: Vec<S> = names.into_iter().collect();
+        self.retain_fields(|name| names.iter().any(|n| n.as_ref() == name))
+    }
+
+    /// Drop the named fields, as the opposite of [`pick`](Self::pick)
+    pub fn omit<I, S>(self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let names: Vec<S> = names.into_iter().collect();
+        self.retain_fields(|name| !names.iter().any(|n| n.as_ref() == name))
+    }
+
+    fn retain_fields(mut self, keep: impl Fn(&str) -> bool) -> Self {
+        self.fields.retain(|f| keep(&f.name));
+        self.conditionals
+            .retain(|rule| keep(&rule.field) && keep(&rule.then_field));
+        #[cfg(feature = "tokio")]
+        self.async_validators.retain(|v| keep(&v.field));
+        self
+    }
+
+    /// Make every field optional, e.g. for validating partial updates
+    pub fn partial(mut self) -> Self {
+        for field in &mut self.fields {
+            field.required = false;
+        }
+        self
+    }
+
+    /// Enable coercion mode.
+    ///
+    /// Numeric strings pass `int()`/`float()` fields, `1/0/yes/no/on/off`
+    /// pass `boolean()` fields, ints pass `float()` fields, and scalars pass
+    /// `string()` fields. Every coerced value is recorded in
+    /// [`ValidatedTable::coercions`].
+    pub fn coerce(mut self, enabled: bool) -> Self {
+        self.coerce = enabled;
+        self
+    }
+
+    pub fn validate(&self, doc: &ison_rs::Document) -> Result<ValidatedTable> {
+        self.validate_with(doc, &ValidationOptions::default())
+    }
+
+    /// Validate with explicit error-collection options.
+    ///
+    /// By default every row is checked and all errors are returned; use
+    /// `fail_fast` or `max_errors` to bound the work on large tables.
+    pub fn validate_with(
+        &self,
+        doc: &ison_rs::Document,
+        options: &ValidationOptions,
+    ) -> Result<ValidatedTable> {
+        self.validate_report_with(doc, options).into_result()
+    }
+
+    /// Validate, keeping the warnings of [`warn_if`](Self::warn_if) rules
+    /// apart from the errors
+    pub fn validate_report(&self, doc: &ison_rs::Document) -> ValidationReport {
+        self.validate_report_with(doc, &ValidationOptions::default())
+    }
+
+    /// [`validate_report`](Self::validate_report) with explicit
+    /// error-collection options; warnings do not count towards the limit
+    pub fn validate_report_with(
+        &self,
+        doc: &ison_rs::Document,
+        options: &ValidationOptions,
+    ) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        let Some(block) = doc.get(&self.name) else {
+            report.errors.push(FieldError::coded(
+                "",
+                "table.missing",
+                [("table", self.name.clone())],
+            ));
+            return report;
+        };
+
+        let limit = options.error_limit();
+        let mut table = ValidatedTable::new(&self.name);
+        table.fields = self.fields.iter().map(|f| f.name.clone()).collect();
+        table.nullable_fields = self
+            .fields
+            .iter()
+            .filter(|f| f.nullable || matches!(f.field_type, FieldType::Null))
+            .map(|f| f.name.clone())
+            .collect();
+        let mut all_errors = self.check_header(block);
+        let targets = self.reference_targets(doc, &mut all_errors);
+        let mut truncated = false;
+
+        // Stopping here leaves the rows and summary unchecked
+        if let Some(max) = limit.filter(|max| all_errors.len() >= *max) {
+            report.truncated =
+                all_errors.len() > max || !block.rows.is_empty() || self.summary.is_some();
+            all_errors.truncate(max);
+            report.errors = all_errors;
+            return report;
+        }
+
+        for (row_idx, row) in block.rows.iter().enumerate() {
+            let (validated_row, errors, warnings) =
+                self.validate_row(row_idx, row, &targets, options, &mut table.coercions);
+            all_errors.extend(errors);
+            report.warnings.extend(warnings);
+            if let Some(max) = limit.filter(|max| all_errors.len() >= *max) {
+                truncated = all_errors.len() > max
+                    || row_idx + 1 < block.rows.len()
+                    || self.summary.is_some();
+                all_errors.truncate(max);
+                break;
+            }
             table.rows.push(validated_row);
         }
 
-        if !all_errors.is_empty() {
-            return Err(ValidationError::new(all_errors));
+        if let Some(summary) = self.summary.as_ref().filter(|_| !truncated) {
+            let (rows, errors) = summary.validate(&block.summary_rows, &table.rows, self.coerce);
+            table.summary_rows = rows;
+            all_errors.extend(errors);
+            if let Some(max) = limit.filter(|max| all_errors.len() >= *max) {
+                truncated = all_errors.len() > max;
+                all_errors.truncate(max);
+            }
+        }
+
+        if all_errors.is_empty() {
+            for table_check in &self.table_checks {
+                if !(table_check.check)(&table.rows) {
+                    let issue = FieldError::coded(
+                        &self.name,
+                        "table.check",
+                        [("message", table_check.message.clone())],
+                    );
+                    match table_check.severity {
+                        Severity::Error => all_errors.push(issue),
+                        Severity::Warning => report.warnings.push(issue),
+                    }
+                }
+            }
+        }
+
+        report.table = all_errors.is_empty().then_some(table);
+        report.errors = all_errors;
+        report.truncated = truncated;
+        report
+    }
+
+    /// Validate one data row, reporting errors and warnings as
+    /// `[row_idx].field`.
+    ///
+    /// `targets` are the ids of `points_to` references by field index (see
+    /// `reference_targets`); fields past its end are not checked against one.
+    pub(crate) fn validate_row(
+        &self,
+        row_idx: usize,
+        row: &ison_rs::Row,
+        targets: &[Option<(String, HashSet<String>)>],
+        options: &ValidationOptions,
+        coercions: &mut Vec<Coercion>,
+    ) -> (ValidatedRow, Vec<FieldError>, Vec<FieldError>) {
+        let mut validated_row = ValidatedRow::new();
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        let snapshot = || options.include_row_snapshot.then(|| row.clone());
+
+        for (field_idx, field_schema) in self.fields.iter().enumerate() {
+            let value = row.get(field_schema.name.as_str());
+            let target = targets.get(field_idx).and_then(Option::as_ref);
+            let result = field_schema
+                .validate_coercing(value, self.coerce)
+                .and_then(|(v, coerced)| match (&v, target) {
+                    (ValidatedValue::Reference(r), Some((table, ids))) if !ids.contains(&r.id) => {
+                        Err(ValidationError::coded(
+                            &field_schema.name,
+                            "ref.dangling",
+                            [("reference", r.to_ison()), ("table", table.clone())],
+                        ))
+                    }
+                    _ => Ok((v, coerced)),
+                });
+            match result {
+                Ok((v, coerced)) => {
+                    if coerced {
+                        coercions.push(Coercion {
+                            row: row_idx,
+                            field: field_schema.name.clone(),
+                            original: value.cloned().unwrap_or(ison_rs::Value::Null),
+                            coerced: v.clone(),
+                        });
+                    }
+                    validated_row.fields.insert(field_schema.name.clone(), v);
+                }
+                Err(e) => {
+                    errors.extend(e.errors.into_iter().map(|err| FieldError {
+                        field: format!("[{}].{}", row_idx, err.field),
+                        value: err.value.or_else(|| value.map(|v| v.to_string())),
+                        row_snapshot: snapshot(),
+                        ..err
+                    }));
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            for rule in &self.conditionals {
+                let Some(err) = rule.check(&validated_row) else {
+                    continue;
+                };
+                errors.push(FieldError {
+                    field: format!("[{}].{}", row_idx, err.field),
+                    value: row.get(err.field.as_str()).map(|v| v.to_string()),
+                    row_snapshot: snapshot(),
+                    ..err
+                });
+            }
+        }
+
+        if errors.is_empty() {
+            for row_check in &self.row_checks {
+                if !(row_check.check)(&validated_row) {
+                    let issue = FieldError {
+                        row_snapshot: snapshot(),
+                        ..FieldError::coded(
+                            format!("[{}]", row_idx),
+                            "row.check",
+                            [("message", row_check.message.clone())],
+                        )
+                    };
+                    match row_check.severity {
+                        Severity::Error => errors.push(issue),
+                        Severity::Warning => warnings.push(issue),
+                    }
+                }
+            }
         }
 
-        Ok(table)
+        (validated_row, errors, warnings)
+    }
+
+    /// Validate rows one at a time, e.g. while reading a large ISONL file,
+    /// without keeping them.
+    ///
+    /// Yields each row's result in order, with errors reported as
+    /// `[row_index].field`. Only the checks that need a single row run:
+    /// header, `points_to` and table checks, summary rows and the
+    /// coercion record are skipped, and warnings are not reported.
+    ///
+    /// ```rust
+    /// use isonantic_rs::prelude::*;
+    ///
+    /// let isonl = "table.users|id email|1 a@x.com\ntable.users|id email|2 nope";
+    /// let rows = isonl.lines().flat_map(|line| {
+    ///     let mut doc = ison_rs::parse_isonl(line).unwrap();
+    ///     doc.blocks.remove(0).rows
+    /// });
+    ///
+    /// let schema = table("users").field("id", int()).field("email", string().email());
+    /// let results: Vec<_> = schema.validate_stream(rows).collect();
+    /// assert!(results[0].is_ok());
+    /// assert_eq!(results[1].as_ref().unwrap_err().errors[0].field, "[1].email");
+    /// ```
+    pub fn validate_stream<'a, I>(
+        &'a self,
+        rows: I,
+    ) -> impl Iterator<Item = Result<ValidatedRow>> + 'a
+    where
+        I: IntoIterator<Item = ison_rs::Row>,
+        I::IntoIter: 'a,
+    {
+        let options = ValidationOptions::default();
+        rows.into_iter().enumerate().map(move |(row_idx, row)| {
+            let (validated_row, errors, _) =
+                self.validate_row(row_idx, &row, &[], &options, &mut Vec::new());
+            if errors.is_empty() {
+                Ok(validated_row)
+            } else {
+                Err(ValidationError::new(errors))
+            }
+        })
     }
 }
 
@@ -499,6 +1984,24 @@ impl FieldBuilder for BoolFieldBuilder {
     }
 }
 
+impl FieldBuilder for EnumFieldBuilder {
+    fn into_field_schema(self, name: impl Into<String>) -> FieldSchema {
+        self.build(name)
+    }
+}
+
+impl FieldBuilder for DateFieldBuilder {
+    fn into_field_schema(self, name: impl Into<String>) -> FieldSchema {
+        self.build(name)
+    }
+}
+
+impl FieldBuilder for DateTimeFieldBuilder {
+    fn into_field_schema(self, name: impl Into<String>) -> FieldSchema {
+        self.build(name)
+    }
+}
+
 impl FieldBuilder for RefFieldBuilder {
     fn into_field_schema(self, name: impl Into<String>) -> FieldSchema {
         self.build(name)
@@ -534,7 +2037,488 @@ pub fn boolean() -> BoolFieldBuilder {
     BoolFieldBuilder::new()
 }
 
+/// Create an enum field restricted to the given variants
+pub fn enum_of<I, S>(variants: I) -> EnumFieldBuilder
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    EnumFieldBuilder::new(variants)
+}
+
+/// Create an integer-backed enum field from `(code, name)` pairs
+pub fn int_enum<I, S>(variants: I) -> EnumFieldBuilder
+where
+    I: IntoIterator<Item = (i64, S)>,
+    S: Into<String>,
+{
+    EnumFieldBuilder::with_codes(variants)
+}
+
+/// Create a date field (ISO 8601 `YYYY-MM-DD` by default)
+pub fn date() -> DateFieldBuilder {
+    DateFieldBuilder::new()
+}
+
+/// Create a datetime field (RFC 3339 by default)
+pub fn datetime() -> DateTimeFieldBuilder {
+    DateTimeFieldBuilder::new()
+}
+
 /// Create a reference field
 pub fn reference() -> RefFieldBuilder {
     RefFieldBuilder::new()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ison_rs::parse;
+
+    #[test]
+    fn test_coercion_mode() {
+        let text = "table.items\nid price active\n\" 42\" \"9.5 \" yes\n7 3 0";
+        let doc = parse(text).unwrap();
+        let schema = table("items")
+            .field("id", int())
+            .field("price", float())
+            .field("active", boolean());
+
+        assert!(schema.validate(&doc).is_err());
+
+        let items = schema.coerce(true).validate(&doc).unwrap();
+        assert_eq!(items[0].get_int("id"), Some(42));
+        assert_eq!(items[0].get("price").and_then(|v| v.as_float()), Some(9.5));
+        assert_eq!(items[0].get_bool("active"), Some(true));
+        assert_eq!(items[1].get_bool("active"), Some(false));
+
+        // An int is already a valid float, so `3` is not a coercion
+        assert_eq!(items.coercions.len(), 4);
+        assert_eq!(items.coercions[0].row, 0);
+        assert_eq!(items.coercions[0].field, "id");
+        assert_eq!(items.coercions[0].original, ison_rs::Value::String(" 42".into()));
+        assert_eq!(items[1].get("price").and_then(|v| v.as_float()), Some(3.0));
+
+        // Whole floats become ints only within i64 range
+        let ints = table("t").field("n", int()).coerce(true);
+        let doc = parse("table.t\nn\n-9223372036854775808.0").unwrap();
+        assert_eq!(ints.validate(&doc).unwrap()[0].get_int("n"), Some(i64::MIN));
+        assert!(ints.validate(&parse("table.t\nn\n9223372036854775808.0").unwrap()).is_err());
+        assert!(ints.validate(&parse("table.t\nn\n100000000000000000000.0").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_validation_options() {
+        let doc = parse("table.t\nn\nx\ny\nz\n4").unwrap();
+        let schema = table("t").field("n", int());
+
+        let err = schema.validate(&doc).unwrap_err();
+        assert_eq!(err.errors.len(), 3);
+        assert!(!err.truncated);
+        assert_eq!(err.errors[0].value.as_deref(), Some("x"));
+
+        let options = ValidationOptions {
+            fail_fast: true,
+            include_row_snapshot: true,
+            ..Default::default()
+        };
+        let err = schema.validate_with(&doc, &options).unwrap_err();
+        assert_eq!(err.errors.len(), 1);
+        assert!(err.truncated);
+        let snapshot = err.errors[0].row_snapshot.as_ref().unwrap();
+        assert_eq!(snapshot.get("n"), Some(&ison_rs::Value::String("x".into())));
+
+        let options = ValidationOptions {
+            max_errors: Some(2),
+            ..Default::default()
+        };
+        let err = schema.validate_with(&doc, &options).unwrap_err();
+        assert_eq!(err.errors.len(), 2);
+        assert!(err.errors[1].row_snapshot.is_none());
+
+        // Zero is no limit
+        let options = ValidationOptions {
+            max_errors: Some(0),
+            ..Default::default()
+        };
+        let err = schema.validate_with(&doc, &options).unwrap_err();
+        assert_eq!(err.errors.len(), 3);
+        let clean = parse("table.t\nn\n1\n2").unwrap();
+        let report = schema.validate_report_with(&clean, &options);
+        assert!(report.table.is_some());
+        assert!(!report.truncated);
+
+        // Reaching the limit on the last row discards nothing
+        let options = ValidationOptions {
+            max_errors: Some(1),
+            ..Default::default()
+        };
+        let err = schema
+            .validate_with(&parse("table.t\nn\n1\nx").unwrap(), &options)
+            .unwrap_err();
+        assert_eq!(err.errors.len(), 1);
+        assert!(!err.truncated);
+        let err = schema
+            .validate_with(&parse("table.t\nn\nx\n1").unwrap(), &options)
+            .unwrap_err();
+        assert!(err.truncated);
+    }
+
+    #[test]
+    fn test_row_and_table_checks() {
+        let doc = parse("table.w\nstart end weight\n1 5 0.5\n6 2 0.25").unwrap();
+        let schema = table("w")
+            .field("start", int())
+            .field("end", int())
+            .field("weight", float())
+            .row_check(
+                |row| row.get_int("end") > row.get_int("start"),
+                "end must be after start",
+            )
+            .table_check(
+                |rows| {
+                    let total: f64 = rows
+                        .iter()
+                        .filter_map(|r| r.get("weight").and_then(|v| v.as_float()))
+                        .sum();
+                    (total - 1.0).abs() < 1e-9
+                },
+                "weights must sum to 1.0",
+            );
+
+        let err = schema.validate(&doc).unwrap_err();
+        assert_eq!(err.errors.len(), 1);
+        assert_eq!(err.errors[0].field, "[1]");
+        assert_eq!(err.errors[0].message, "end must be after start");
+
+        let doc = parse("table.w\nstart end weight\n1 5 0.5\n2 6 0.25").unwrap();
+        let err = schema.validate(&doc).unwrap_err();
+        assert_eq!(err.errors[0].field, "w");
+        assert_eq!(err.errors[0].message, "weights must sum to 1.0");
+    }
+
+    #[test]
+    fn test_number_constraints() {
+        let schema = table("t")
+            .field("month", int().range(1..=12))
+            .field("pct", float().range(0.0..100.0).multiple_of(0.01))
+            .field("step", int().gt(0).multiple_of(5))
+            .field("big", int().multiple_of(3));
+        let doc = parse(
+            "table.t\nmonth pct step big\n\
+             12 99.99 5 9007199254740993\n\
+             13 100 4 9007199254740992\n\
+             0 12.345 0 3",
+        )
+        .unwrap();
+        let err = schema.validate(&doc).unwrap_err();
+        let errors: Vec<String> = err.errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            errors,
+            [
+                "[1].month: Value must be <= 12",
+                "[1].pct: Value must be < 100",
+                "[1].step: Value must be a multiple of 5",
+                "[1].big: Value must be a multiple of 3",
+                "[2].month: Value must be >= 1",
+                "[2].pct: Value must be a multiple of 0.01",
+                "[2].step: Value must be > 0",
+            ]
+        );
+
+        let min = table("t").field("n", int().multiple_of(-1));
+        let doc = parse("table.t\nn\n-9223372036854775808").unwrap();
+        assert!(min.validate(&doc).is_ok());
+        let large = float().multiple_of(0.01).build("x");
+        assert!(large
+            .validate(Some(&ison_rs::Value::Float(1e12 + 0.01)))
+            .is_ok());
+
+        let field = float().finite().build("x");
+        let err = field
+            .validate(Some(&ison_rs::Value::Float(f64::NAN)))
+            .unwrap_err();
+        assert_eq!(err.errors[0].code, "number.not_finite");
+        let unchecked = float().build("x");
+        assert!(unchecked
+            .validate(Some(&ison_rs::Value::Float(f64::INFINITY)))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_string_normalization() {
+        let schema = table("t")
+            .field(
+                "tag",
+                string().trimmed().lowercase().min(2).starts_with("#"),
+            )
+            .field("code", string().nullable().ascii_only().ends_with("-x"))
+            .field("name", string().nullable().max_bytes(4));
+        let doc = parse(
+            "table.t\ntag code name\n\
+             \"  #Rust \" ab-x ~\n\
+             \" #\" ~ Zoë\n\
+             Go é-x Zoë!",
+        )
+        .unwrap();
+        let err = schema.validate(&doc).unwrap_err();
+        let errors: Vec<String> = err.errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            errors,
+            [
+                "[1].tag: String must be at least 2 characters",
+                "[2].tag: String must start with #",
+                "[2].code: String must contain only ASCII characters",
+                "[2].name: String must be at most 4 bytes",
+            ]
+        );
+
+        let doc = parse("table.t\ntag code\n\" #Rust\t\" ab-x").unwrap();
+        let validated = schema.validate(&doc).unwrap();
+        assert_eq!(validated.rows[0].get_string("tag"), Some("#rust"));
+    }
+
+    #[test]
+    fn test_from_block_header() {
+        let doc = parse(
+            "table.events\nid:int score:float name:string? at:date when:datetime? by:ref ok:bool total:computed note\n\
+             1 2 Ada 2024-01-01 ~ :u1 true x anything\n\
+             2 2.5 ~ 2024-02-30 2024-01-01T10:00:00Z 7 yes 3\n\
+             3.5 1",
+        )
+        .unwrap();
+        let schema = TableSchema::from_block_header(&doc["events"]);
+        let declared: Vec<&str> = schema.fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(declared, ["id", "score", "name", "at", "when", "by", "ok"]);
+
+        let err = schema.validate(&doc).unwrap_err();
+        let errors: Vec<String> = err.errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            errors,
+            [
+                "[1].at: Invalid date format",
+                "[1].by: Expected reference",
+                "[1].ok: Expected boolean",
+                "[2].id: Expected integer",
+                "[2].at: Field is required",
+                "[2].by: Field is required",
+                "[2].ok: Field is required",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_warnings() {
+        let schema = table("w")
+            .field("n", int())
+            .warn_if(|row| row.get_int("n") > Some(100), "unusually large")
+            .row_check(|row| row.get_int("n") != Some(0), "zero is not allowed")
+            .table_warn_if(|rows| rows.len() < 3, "few rows");
+
+        let doc = parse("table.w\nn\n5\n500").unwrap();
+        assert_eq!(schema.validate(&doc).unwrap().len(), 2);
+        let report = schema.validate_report(&doc);
+        assert!(report.is_valid());
+        assert_eq!(report.table.as_ref().map(|t| t.len()), Some(2));
+        let warnings: Vec<String> = report.warnings.iter().map(|w| w.to_string()).collect();
+        assert_eq!(warnings, ["[1]: unusually large", "w: few rows"]);
+
+        // Warnings do not count towards the error limit
+        let doc = parse("table.w\nn\n500\n0\n0\n7").unwrap();
+        let options = ValidationOptions {
+            max_errors: Some(1),
+            ..Default::default()
+        };
+        let report = schema.validate_report_with(&doc, &options);
+        assert!(!report.is_valid());
+        assert!(report.table.is_none());
+        assert_eq!(report.warnings.len(), 1);
+        assert_eq!(report.errors[0].to_string(), "[1]: zero is not allowed");
+        assert!(report.truncated);
+        assert!(report.into_result().unwrap_err().truncated);
+    }
+
+    #[test]
+    fn test_validate_stream() {
+        let schema = table("w")
+            .field("start", int())
+            .field("end", int())
+            .field("owner", reference().points_to("users"))
+            .coerce(true)
+            .row_check(
+                |row| row.get_int("end") > row.get_int("start"),
+                "end must be after start",
+            )
+            .table_check(|rows| rows.len() > 10, "too few rows");
+        let doc = parse("table.w\nstart end owner\n1 \"5\" :u1\n6 2\nx 3").unwrap();
+
+        let results: Vec<_> = schema.validate_stream(doc["w"].rows.clone()).collect();
+        assert_eq!(results.len(), 3);
+        let first = results[0].as_ref().unwrap();
+        assert_eq!(first.get_int("end"), Some(5));
+        let errors: Vec<String> = results[1..]
+            .iter()
+            .map(|r| r.as_ref().unwrap_err().errors[0].to_string())
+            .collect();
+        assert_eq!(errors, ["[1]: end must be after start", "[2].start: Expected integer"]);
+    }
+
+    #[test]
+    fn test_schema_composition() {
+        let audit = table("audit")
+            .field("id", int().required())
+            .field("created_by", string().required())
+            .row_check(|row| row.get_int("id") != Some(0), "id 0 is reserved");
+        let users = table("users")
+            .field("name", string().required())
+            .extend(&audit)
+            .field("id", string().required())
+            .deny_unknown_fields();
+        let names =
+            |s: &TableSchema| -> Vec<String> { s.fields.iter().map(|f| f.name.clone()).collect() };
+        assert_eq!(names(&users), ["name", "id", "created_by"]);
+
+        let doc = parse("table.users\nname id created_by\nAlice u1 root").unwrap();
+        assert!(users.validate(&doc).is_ok());
+        assert_eq!(users.row_checks.len(), 1);
+        let err = audit
+            .clone()
+            .omit(["created_by"])
+            .validate(&parse("table.audit\nid\n0").unwrap());
+        assert_eq!(err.unwrap_err().errors[0].message, "id 0 is reserved");
+
+        let public = users.clone().pick(["id", "name", "missing"]);
+        assert_eq!(names(&public), ["name", "id"]);
+        let doc = parse("table.users\nid name created_by\nu1 Alice root").unwrap();
+        let err = public.validate(&doc).unwrap_err();
+        assert_eq!(err.errors[0].field, "created_by");
+
+        let update = users.omit(["created_by"]).partial();
+        assert_eq!(names(&update), ["name", "id"]);
+        let doc = parse("table.users\nname\nBob").unwrap();
+        assert!(update.validate(&doc).is_ok());
+    }
+
+    #[test]
+    fn test_nullable_vs_missing() {
+        let doc = parse("table.t\na b\n1 null\n2").unwrap();
+
+        let err = table("t").field("b", string()).validate(&doc).unwrap_err();
+        assert_eq!(err.errors.len(), 1);
+        assert_eq!(err.errors[0].field, "[0].b");
+        assert_eq!(err.errors[0].message, "Field is present but null is not allowed");
+
+        let rows = table("t").field("b", string().nullable()).validate(&doc).unwrap();
+        assert!(rows[0].get("b").unwrap().is_null());
+
+        let err = table("t")
+            .field("b", string().nullable().required())
+            .validate(&doc)
+            .unwrap_err();
+        assert_eq!(err.errors[0].field, "[1].b");
+        assert_eq!(err.errors[0].message, "Field is required");
+
+        // Defaults fill in absent fields, never an explicit null
+        let rows = table("t")
+            .field("b", string().nullable().default_value("x"))
+            .validate(&doc)
+            .unwrap();
+        assert!(rows[0].get("b").unwrap().is_null());
+        assert_eq!(rows[1].get_string("b"), Some("x"));
+        let err = table("t")
+            .field("b", string().default_value("x"))
+            .validate(&doc)
+            .unwrap_err();
+        assert_eq!(err.errors[0].field, "[0].b");
+    }
+
+    #[test]
+    fn test_strict_columns() {
+        let doc = parse("table.t\nid extra\n1 x").unwrap();
+        let schema = table("t").field("id", int()).field("name", string());
+
+        assert!(schema.validate(&doc).is_ok());
+
+        let err = schema.clone().deny_unknown_fields().validate(&doc).unwrap_err();
+        assert_eq!(err.errors.len(), 1);
+        assert_eq!(err.errors[0].field, "extra");
+
+        let err = schema
+            .deny_unknown_fields()
+            .require_declared_columns()
+            .validate(&doc)
+            .unwrap_err();
+        let fields: Vec<&str> = err.errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["extra", "name"]);
+    }
+
+    #[test]
+    fn test_enum_field() {
+        let doc = parse("table.users\nid status\n1 active\n2 Suspended\n3 gone").unwrap();
+
+        let err = table("users")
+            .field("status", enum_of(["active", "suspended", "deleted"]))
+            .validate(&doc)
+            .unwrap_err();
+        assert_eq!(err.errors.len(), 2);
+
+        let doc = parse("table.users\nid status\n1 active\n2 Suspended").unwrap();
+        let users = table("users")
+            .field("status", enum_of(["active", "suspended"]).ignore_case())
+            .validate(&doc)
+            .unwrap();
+        assert_eq!(users[1].get("status"), Some(&ValidatedValue::Enum("suspended".into())));
+
+        let doc = parse("table.users\nid status\n1 2\n2 active").unwrap();
+        let users = table("users")
+            .field("status", int_enum([(1, "active"), (2, "suspended")]))
+            .validate(&doc)
+            .unwrap();
+        assert_eq!(users[0].get_string("status"), Some("suspended"));
+        assert_eq!(users[1].get_string("status"), Some("active"));
+    }
+
+    #[test]
+    fn test_date_field() {
+        let doc = parse("table.events\nid day\n1 2024-03-01\n2 2024-13-01").unwrap();
+        let schema = table("events").field(
+            "day",
+            date().min(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()),
+        );
+
+        let err = schema.validate(&doc).unwrap_err();
+        assert_eq!(err.errors.len(), 1);
+        assert_eq!(err.errors[0].field, "[1].day");
+
+        let doc = parse("table.events\nid day\n1 2023-12-31").unwrap();
+        assert!(schema.validate(&doc).is_err());
+
+        let doc = parse("table.events\nid day\n1 01/03/2024").unwrap();
+        let schema = table("events").field("day", date().format("%d/%m/%Y"));
+        let events = schema.validate(&doc).unwrap();
+        assert_eq!(
+            events[0].get_date("day"),
+            NaiveDate::from_ymd_opt(2024, 3, 1)
+        );
+    }
+
+    #[test]
+    fn test_datetime_offsets() {
+        let text = "table.events\nid at\n1 2024-03-01T10:00:00+02:00\n2 \"2024-03-01 10:00:00\"";
+        let doc = parse(text).unwrap();
+
+        let events = table("events")
+            .field("at", datetime().utc())
+            .validate(&doc)
+            .unwrap();
+        assert_eq!(events[0].get_datetime("at").unwrap().to_rfc3339(), "2024-03-01T08:00:00+00:00");
+        assert_eq!(events[1].get_datetime("at").unwrap().to_rfc3339(), "2024-03-01T10:00:00+00:00");
+
+        let err = table("events")
+            .field("at", datetime().require_offset())
+            .validate(&doc)
+            .unwrap_err();
+        assert_eq!(err.errors.len(), 1);
+        assert_eq!(err.errors[0].field, "[1].at");
+    }
+}
@@ -0,0 +1,245 @@
+//! Summary row validation
+//!
+//! ISON tables may end with summary rows after a `---` separator. A
+//! [`SummarySchema`] declares the fields expected in those rows and how they
+//! relate to the data above them, e.g. that `total` equals the sum of the
+//! `amount` column.
+
+use crate::schema::{FieldBuilder, FieldSchema};
+use crate::{FieldError, ValidatedRow};
+
+/// Aggregate function computed over a data column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    Sum,
+    Count,
+    Min,
+    Max,
+    Mean,
+}
+
+impl Aggregate {
+    fn name(self) -> &'static str {
+        match self {
+            Aggregate::Sum => "sum",
+            Aggregate::Count => "count",
+            Aggregate::Min => "min",
+            Aggregate::Max => "max",
+            Aggregate::Mean => "mean",
+        }
+    }
+
+    /// Apply to the numeric values of a column (`None` for min/max/mean of nothing)
+    pub fn apply(self, values: &[f64]) -> Option<f64> {
+        match self {
+            Aggregate::Sum => Some(values.iter().sum()),
+            Aggregate::Count => Some(values.len() as f64),
+            Aggregate::Min => values.iter().copied().reduce(f64::min),
+            Aggregate::Max => values.iter().copied().reduce(f64::max),
+            Aggregate::Mean if values.is_empty() => None,
+            Aggregate::Mean => Some(values.iter().sum::<f64>() / values.len() as f64),
+        }
+    }
+}
+
+/// Expectation that a summary field equals an aggregate of a data column
+#[derive(Debug, Clone)]
+pub struct AggregateRule {
+    pub field: String,
+    pub aggregate: Aggregate,
+    /// Data column aggregated (ignored for `Count`)
+    pub column: String,
+}
+
+/// Schema for the summary rows of a table
+#[derive(Debug, Clone)]
+pub struct SummarySchema {
+    pub fields: Vec<FieldSchema>,
+    pub rules: Vec<AggregateRule>,
+    /// Allowed absolute difference when comparing aggregates
+    pub tolerance: f64,
+}
+
+impl Default for SummarySchema {
+    fn default() -> Self {
+        Self {
+            fields: Vec::new(),
+            rules: Vec::new(),
+            tolerance: 1e-9,
+        }
+    }
+}
+
+impl SummarySchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn field(mut self, name: impl Into<String>, builder: impl FieldBuilder) -> Self {
+        self.fields.push(builder.into_field_schema(name));
+        self
+    }
+
+    /// Require `field` to equal `aggregate` over the data `column`
+    pub fn expect(
+        mut self,
+        field: impl Into<String>,
+        aggregate: Aggregate,
+        column: impl Into<String>,
+    ) -> Self {
+        self.rules.push(AggregateRule {
+            field: field.into(),
+            aggregate,
+            column: column.into(),
+        });
+        self
+    }
+
+    /// Require `field` to equal the sum of `column`
+    pub fn sum(self, field: impl Into<String>, column: impl Into<String>) -> Self {
+        self.expect(field, Aggregate::Sum, column)
+    }
+
+    /// Require `field` to equal the mean of `column`
+    pub fn mean(self, field: impl Into<String>, column: impl Into<String>) -> Self {
+        self.expect(field, Aggregate::Mean, column)
+    }
+
+    /// Require `field` to equal the minimum of `column`
+    pub fn min(self, field: impl Into<String>, column: impl Into<String>) -> Self {
+        self.expect(field, Aggregate::Min, column)
+    }
+
+    /// Require `field` to equal the maximum of `column`
+    pub fn max(self, field: impl Into<String>, column: impl Into<String>) -> Self {
+        self.expect(field, Aggregate::Max, column)
+    }
+
+    /// Require `field` to equal the number of data rows
+    pub fn count(self, field: impl Into<String>) -> Self {
+        self.expect(field, Aggregate::Count, String::new())
+    }
+
+    pub fn tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Validate summary rows against their fields and the validated data rows.
+    ///
+    /// Errors are reported as `summary[i].field`.
+    pub(crate) fn validate(
+        &self,
+        summary_rows: &[ison_rs::Row],
+        data_rows: &[ValidatedRow],
+        coerce: bool,
+    ) -> (Vec<ValidatedRow>, Vec<FieldError>) {
+        let mut validated = Vec::new();
+        let mut errors = Vec::new();
+        let error = |idx: usize, field: &str, err: FieldError, row: &ison_rs::Row| FieldError {
+            field: format!("summary[{}].{}", idx, field),
+            value: row.get(field).map(|v| v.to_string()),
+            ..err
+        };
+
+        for (idx, row) in summary_rows.iter().enumerate() {
+            let mut validated_row = ValidatedRow::new();
+            for field in &self.fields {
+                match field.validate_coercing(row.get(field.name.as_str()), coerce) {
+                    Ok((v, _)) => {
+                        validated_row.fields.insert(field.name.clone(), v);
+                    }
+                    Err(e) => {
+                        for err in e.errors {
+                            errors.push(error(idx, &field.name, err, row));
+                        }
+                    }
+                }
+            }
+
+            for rule in &self.rules {
+                let column: Vec<f64> = if rule.aggregate == Aggregate::Count {
+                    vec![0.0; data_rows.len()]
+                } else {
+                    data_rows
+                        .iter()
+                        .filter_map(|r| r.get(&rule.column).and_then(|v| v.as_float()))
+                        .collect()
+                };
+                let expected = rule.aggregate.apply(&column);
+                let actual = validated_row
+                    .get(&rule.field)
+                    .and_then(|v| v.as_float())
+                    .or_else(|| row.get(rule.field.as_str()).and_then(|v| v.as_float()));
+
+                let matches = match (expected, actual) {
+                    (Some(e), Some(a)) => (e - a).abs() <= self.tolerance,
+                    (None, None) => true,
+                    _ => false,
+                };
+                if !matches {
+                    let expected_str = expected.map_or("none".to_string(), |e| e.to_string());
+                    let err = if rule.aggregate == Aggregate::Count {
+                        FieldError::coded("", "summary.count", [("expected", expected_str)])
+                    } else {
+                        FieldError::coded(
+                            "",
+                            "summary.aggregate",
+                            [
+                                ("aggregate", rule.aggregate.name().to_string()),
+                                ("column", rule.column.clone()),
+                                ("expected", expected_str),
+                            ],
+                        )
+                    };
+                    errors.push(error(idx, &rule.field, err, row));
+                }
+            }
+
+            validated.push(validated_row);
+        }
+
+        (validated, errors)
+    }
+}
+
+/// Create a summary row schema
+pub fn summary() -> SummarySchema {
+    SummarySchema::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use ison_rs::parse;
+
+    fn schema() -> TableSchema {
+        table("orders").field("amount", float()).summary(
+            summary()
+                .field("amount", float().required())
+                .sum("amount", "amount")
+                .field("n", int())
+                .count("n"),
+        )
+    }
+
+    #[test]
+    fn test_summary_aggregates() {
+        let doc = parse("table.orders\namount n\n10 ~\n20.5 ~\n---\n30.5 2").unwrap();
+        let orders = schema().validate(&doc).unwrap();
+        assert_eq!(orders.summary_rows.len(), 1);
+        assert_eq!(orders.summary_rows[0].get_int("n"), Some(2));
+    }
+
+    #[test]
+    fn test_summary_errors_are_distinguished() {
+        let doc = parse("table.orders\namount n\n10 ~\nbad ~\n---\n25 3").unwrap();
+        let err = schema().validate(&doc).unwrap_err();
+        let fields: Vec<&str> = err.errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec!["[1].amount", "summary[0].amount", "summary[0].n"]
+        );
+        assert_eq!(err.errors[1].message, "Expected sum of amount = 10");
+    }
+}
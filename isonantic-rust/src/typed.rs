@@ -0,0 +1,152 @@
+//! Typed access to validated rows
+//!
+//! The [`IsonSchema`] trait ties a Rust struct to a [`TableSchema`] so a
+//! table can be validated straight into `Vec<T>`. It is normally implemented
+//! with `#[derive(IsonSchema)]` (requires the `derive` feature):
+//!
+//! ```rust,ignore
+//! use isonantic_rs::prelude::*;
+//!
+//! #[derive(IsonSchema)]
+//! #[ison(table = "users")]
+//! struct User {
+//!     #[ison(min = 1)]
+//!     id: i64,
+//!     #[ison(email)]
+//!     email: String,
+//!     nickname: Option<String>,
+//! }
+//!
+//! let users: Vec<User> = validate_into(&doc)?;
+//! ```
+
+use chrono::{DateTime, FixedOffset, NaiveDate};
+
+use crate::{ISONReference, Result, TableSchema, ValidatedRow, ValidatedValue, ValidationError};
+
+/// A struct with an ISON table schema
+pub trait IsonSchema: Sized {
+    /// Schema describing the table this type is read from
+    fn schema() -> TableSchema;
+
+    /// Build an instance from a row that already passed `schema()`
+    fn from_validated_row(row: &ValidatedRow) -> Result<Self>;
+
+    /// Validate the table in `doc` and convert every row
+    fn validate_into(doc: &ison_rs::Document) -> Result<Vec<Self>> {
+        let table = Self::schema().validate(doc)?;
+        table.iter().map(Self::from_validated_row).collect()
+    }
+}
+
+/// Validate the table for `T` in `doc` and return typed rows
+pub fn validate_into<T: IsonSchema>(doc: &ison_rs::Document) -> Result<Vec<T>> {
+    T::validate_into(doc)
+}
+
+/// Conversion from a validated cell into a Rust value
+pub trait FromValidatedValue: Sized {
+    fn from_validated(value: Option<&ValidatedValue>, field: &str) -> Result<Self>;
+}
+
+fn present<'a>(value: Option<&'a ValidatedValue>, field: &str) -> Result<&'a ValidatedValue> {
+    match value {
+        None | Some(ValidatedValue::Null) => {
+            Err(ValidationError::coded(field, "field.required", []))
+        }
+        Some(v) => Ok(v),
+    }
+}
+
+fn mismatch(field: &str, expected: &str) -> ValidationError {
+    ValidationError::coded(field, "convert.type", [("expected", expected.to_string())])
+}
+
+macro_rules! impl_from_validated_int {
+    ($($t:ty),*) => {$(
+        impl FromValidatedValue for $t {
+            fn from_validated(value: Option<&ValidatedValue>, field: &str) -> Result<Self> {
+                let i = present(value, field)?
+                    .as_int()
+                    .ok_or_else(|| mismatch(field, stringify!($t)))?;
+                <$t>::try_from(i).map_err(|_| {
+                    ValidationError::coded(field, "convert.range", [("type", stringify!($t).to_string())])
+                })
+            }
+        }
+    )*};
+}
+
+impl_from_validated_int!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl FromValidatedValue for f64 {
+    fn from_validated(value: Option<&ValidatedValue>, field: &str) -> Result<Self> {
+        present(value, field)?
+            .as_float()
+            .ok_or_else(|| mismatch(field, "f64"))
+    }
+}
+
+impl FromValidatedValue for f32 {
+    fn from_validated(value: Option<&ValidatedValue>, field: &str) -> Result<Self> {
+        f64::from_validated(value, field).map(|f| f as f32)
+    }
+}
+
+impl FromValidatedValue for bool {
+    fn from_validated(value: Option<&ValidatedValue>, field: &str) -> Result<Self> {
+        present(value, field)?
+            .as_bool()
+            .ok_or_else(|| mismatch(field, "bool"))
+    }
+}
+
+impl FromValidatedValue for String {
+    fn from_validated(value: Option<&ValidatedValue>, field: &str) -> Result<Self> {
+        present(value, field)?
+            .as_str()
+            .map(String::from)
+            .ok_or_else(|| mismatch(field, "String"))
+    }
+}
+
+impl FromValidatedValue for NaiveDate {
+    fn from_validated(value: Option<&ValidatedValue>, field: &str) -> Result<Self> {
+        present(value, field)?
+            .as_date()
+            .ok_or_else(|| mismatch(field, "NaiveDate"))
+    }
+}
+
+impl FromValidatedValue for DateTime<FixedOffset> {
+    fn from_validated(value: Option<&ValidatedValue>, field: &str) -> Result<Self> {
+        present(value, field)?
+            .as_datetime()
+            .copied()
+            .ok_or_else(|| mismatch(field, "DateTime"))
+    }
+}
+
+impl FromValidatedValue for ISONReference {
+    fn from_validated(value: Option<&ValidatedValue>, field: &str) -> Result<Self> {
+        present(value, field)?
+            .as_reference()
+            .cloned()
+            .ok_or_else(|| mismatch(field, "ISONReference"))
+    }
+}
+
+impl FromValidatedValue for ValidatedValue {
+    fn from_validated(value: Option<&ValidatedValue>, _field: &str) -> Result<Self> {
+        Ok(value.cloned().unwrap_or(ValidatedValue::Null))
+    }
+}
+
+impl<T: FromValidatedValue> FromValidatedValue for Option<T> {
+    fn from_validated(value: Option<&ValidatedValue>, field: &str) -> Result<Self> {
+        match value {
+            None | Some(ValidatedValue::Null) => Ok(None),
+            Some(_) => T::from_validated(value, field).map(Some),
+        }
+    }
+}
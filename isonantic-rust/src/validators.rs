@@ -11,7 +11,7 @@ impl FieldValidator for NotEmptyValidator {
     fn validate(&self, value: &ValidatedValue, field: &str) -> Result<()> {
         if let ValidatedValue::String(s) = value {
             if s.is_empty() {
-                return Err(ValidationError::single(field, "String cannot be empty"));
+                return Err(ValidationError::coded(field, "string.empty", []));
             }
         }
         Ok(())
@@ -22,7 +22,8 @@ impl FieldValidator for NotEmptyValidator {
     }
 }
 
-/// Validates that a value is in a set of allowed values
+/// Validates that a value is in a set of allowed values, compared as
+/// written in ISON for non-string values
 #[derive(Debug, Clone)]
 pub struct OneOfValidator {
     pub allowed: Vec<String>,
@@ -36,13 +37,17 @@ impl OneOfValidator {
 
 impl FieldValidator for OneOfValidator {
     fn validate(&self, value: &ValidatedValue, field: &str) -> Result<()> {
-        if let ValidatedValue::String(s) = value {
-            if !self.allowed.contains(s) {
-                return Err(ValidationError::single(
-                    field,
-                    format!("Value must be one of: {:?}", self.allowed),
-                ));
-            }
+        let text = match value {
+            ValidatedValue::Null => return Ok(()),
+            ValidatedValue::String(s) | ValidatedValue::Enum(s) => s.clone(),
+            other => other.to_ison_value().to_string(),
+        };
+        if !self.allowed.contains(&text) {
+            return Err(ValidationError::coded(
+                field,
+                "value.not_allowed",
+                [("allowed", format!("{:?}", self.allowed))],
+            ));
         }
         Ok(())
     }
@@ -50,6 +55,10 @@ impl FieldValidator for OneOfValidator {
     fn clone_box(&self) -> Box<dyn FieldValidator> {
         Box::new(self.clone())
     }
+
+    fn allowed_values(&self) -> Option<&[String]> {
+        Some(&self.allowed)
+    }
 }
 
 /// Custom validation function
@@ -120,3 +129,38 @@ pub fn not_empty() -> NotEmptyValidator {
 pub fn one_of(allowed: Vec<&str>) -> OneOfValidator {
     OneOfValidator::new(allowed.into_iter().map(String::from).collect())
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use ison_rs::parse;
+
+    #[test]
+    fn test_builder_validators() {
+        let schema = table("items")
+            .field(
+                "sku",
+                string().not_empty().validator(custom(
+                    |v| v.as_str().is_some_and(|s| s.starts_with("PRO-")),
+                    "Must start with PRO-",
+                )),
+            )
+            .field("size", int().one_of([1, 2, 4]))
+            .field("color", string().nullable().one_of(["red", "blue"]));
+
+        let doc = parse("table.items\nsku size color\nPRO-1 2 red\nPRO-2 4 ~").unwrap();
+        assert!(schema.validate(&doc).is_ok());
+
+        let doc = parse("table.items\nsku size color\n\"\" 3 green").unwrap();
+        let err = schema.validate(&doc).unwrap_err();
+        let errors: Vec<String> = err.errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            errors,
+            [
+                "[0].sku: String cannot be empty",
+                "[0].size: Value must be one of: [\"1\", \"2\", \"4\"]",
+                "[0].color: Value must be one of: [\"red\", \"blue\"]",
+            ]
+        );
+    }
+}